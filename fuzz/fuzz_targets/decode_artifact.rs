@@ -0,0 +1,51 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Feeds arbitrary bytes into the artifact container parser and the decoders
+//! of the artifact payloads.
+
+#![no_main]
+
+use amplify::confinement::{Confined, U24};
+use libfuzzer_sys::fuzz_target;
+use rgbcore::file::{decode_artifact, ArtifactType};
+use rgbcore::Schema;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((ty, payload)) = decode_artifact(data, None) else {
+        return;
+    };
+    // Consignment and disclosure payloads contain witness transactions, which
+    // are covered by the bitcoin decoders upstream.
+    if ty != ArtifactType::Schema {
+        return;
+    }
+    let Ok(payload) = Confined::<Vec<u8>, 0, U24>::try_from(payload.to_vec()) else {
+        return;
+    };
+    let Ok(schema) = Schema::from_strict_serialized::<U24>(payload) else {
+        return;
+    };
+    let data = schema.to_strict_serialized::<U24>().expect("decoded from smaller data");
+    assert_eq!(Schema::from_strict_serialized::<U24>(data).ok(), Some(schema));
+});
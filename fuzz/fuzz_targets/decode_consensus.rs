@@ -0,0 +1,62 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Feeds arbitrary bytes into the strict decoders of the consensus data
+//! structures. The first byte selects the decoded type; any data which
+//! decodes must survive encoding round-trip.
+
+#![no_main]
+
+use std::fmt::Debug;
+
+use amplify::confinement::{Confined, U24};
+use libfuzzer_sys::fuzz_target;
+use rgbcore::{BundlePool, Extension, Genesis, Schema, Transition};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+fn round_trip<T>(data: Confined<Vec<u8>, 0, U24>)
+where T: StrictSerialize + StrictDeserialize + Eq + Debug {
+    let Ok(val) = T::from_strict_serialized::<U24>(data) else {
+        return;
+    };
+    let data = val.to_strict_serialized::<U24>().expect("decoded from smaller data");
+    assert_eq!(T::from_strict_serialized::<U24>(data).ok(), Some(val));
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((ty, data)) = data.split_first() else {
+        return;
+    };
+    let Ok(data) = Confined::try_from(data.to_vec()) else {
+        return;
+    };
+    match ty % 6 {
+        0 => round_trip::<Schema>(data),
+        1 => round_trip::<Genesis>(data),
+        2 => round_trip::<Transition>(data),
+        3 => round_trip::<Extension>(data),
+        4 => round_trip::<BundlePool>(data),
+        _ => {
+            let _ = BundlePool::from_legacy_serialized::<U24>(data);
+        }
+    }
+});
@@ -0,0 +1,118 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Feeds structurally valid, but mutated, contract data into the validator.
+//! The mutations replace parts of the frozen `transfers` test vector with
+//! data decoded from the fuzzer input; the validator must report all the
+//! inconsistencies as failures in the validation status.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use amplify::confinement::{Confined, U24, U32};
+use libfuzzer_sys::fuzz_target;
+use rgbcore::file::{decode_artifact, ArtifactType};
+use rgbcore::testing::TestVector;
+use rgbcore::validation::{Terminal, ValidationPolicy};
+use rgbcore::{BundlePool, Genesis, Schema, Transition, TransitionBundle};
+use strict_encoding::{StrictDecode, StrictDeserialize, StrictReader};
+
+static TRANSFERS: &[u8] = include_bytes!("../../tests/data/transfers.rgb");
+
+fn vector() -> &'static TestVector {
+    static VECTOR: OnceLock<TestVector> = OnceLock::new();
+    VECTOR.get_or_init(|| {
+        let (_, payload) = decode_artifact(TRANSFERS, Some(ArtifactType::Consignment))
+            .expect("frozen test vector");
+        let payload = Confined::try_from(payload.to_vec()).expect("frozen test vector");
+        TestVector::from_strict_serialized::<U32>(payload).expect("frozen test vector")
+    })
+}
+
+/// Rebuilds the bundle pool, modifying the bundle with the given index.
+fn mutate_bundle(
+    pool: &BundlePool,
+    index: usize,
+    f: impl FnOnce(TransitionBundle) -> TransitionBundle,
+) -> Option<BundlePool> {
+    let mut f = Some(f);
+    let mut mutated = BundlePool::new();
+    for (no, bundle_id) in pool.bundle_ids().enumerate() {
+        let bundle = pool.bundle(bundle_id)?.clone();
+        let (witness_id, anchor) = pool.anchor(bundle_id)?;
+        let bundle = match f.take() {
+            Some(f) if no == index % pool.len() => f(bundle),
+            other => {
+                f = other;
+                bundle
+            }
+        };
+        mutated.add(witness_id, anchor.clone(), bundle).ok()?;
+    }
+    Some(mutated)
+}
+
+/// Decodes the data ignoring its unconsumed part.
+fn decode<T: StrictDecode>(data: &[u8]) -> Option<T> {
+    T::strict_decode(&mut StrictReader::in_memory::<U24>(data)).ok()
+}
+
+fn mutate(vector: &mut TestVector, data: &[u8]) -> Option<()> {
+    if data.len() < 2 {
+        return None;
+    }
+    let (part, index, data) = (data[0], data[1] as usize, &data[2..]);
+    match part % 5 {
+        0 => vector.schema = decode::<Schema>(data)?,
+        1 => vector.genesis = decode::<Genesis>(data)?,
+        2 => {
+            let bundle = decode::<TransitionBundle>(data)?;
+            vector.bundles = mutate_bundle(&vector.bundles, index, |_| bundle)?;
+        }
+        3 => {
+            // Replaces a transition while keeping the id under which it is
+            // known to the bundle.
+            let transition = decode::<Transition>(data)?;
+            vector.bundles = mutate_bundle(&vector.bundles, index, |mut bundle| {
+                if let Some((_, known)) = bundle.known_transitions.keyed_values_mut().next() {
+                    *known = transition;
+                }
+                bundle
+            })?;
+        }
+        _ => {
+            let terminal = decode::<Terminal>(data)?;
+            vector.terminals = Confined::try_from(vec![terminal]).ok()?;
+        }
+    }
+    Some(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut vector = vector().clone();
+    if mutate(&mut vector, data).is_none() {
+        return;
+    }
+    let _ = vector.validate(ValidationPolicy::default());
+    let _ = vector.validate(ValidationPolicy::strict());
+});
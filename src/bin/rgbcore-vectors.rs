@@ -0,0 +1,49 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates test vectors used by the backward-compatibility tests. Existing
+//! vectors are never overwritten, since they must stay frozen once released.
+
+use std::env;
+use std::path::PathBuf;
+
+use rgbcore::file::{ArtifactType, save_file};
+use rgbcore::testing::vector_scenarios;
+
+fn main() {
+    let dir = env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("tests/data"));
+    std::fs::create_dir_all(&dir).expect("unable to create output directory");
+
+    for (name, scenario) in vector_scenarios() {
+        let path = dir.join(format!("{name}.rgb"));
+        if path.exists() {
+            println!("{} already exists, skipping", path.display());
+            continue;
+        }
+        save_file(&path, ArtifactType::Consignment, &scenario.to_vector())
+            .expect("unable to write test vector");
+        println!("{} written", path.display());
+    }
+}
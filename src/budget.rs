@@ -0,0 +1,262 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Accounting of resources required for decoding untrusted data.
+//!
+//! Strict encoding puts confinement bounds on every collection, so for each
+//! type it is possible to compute the worst-case size of its serialized data.
+//! Together with the payload length declared in an artifact header (see
+//! [`crate::file::ArtifactHeader`]) this allows rejecting oversized payloads
+//! before reading and decoding them. Decoded data are allocated proportionally
+//! to the number of consumed bytes, so limiting the latter bounds memory use.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use strict_encoding::Sizing;
+use strict_types::{SemId, Ty, TypeSystem};
+
+/// Default decode budget of 256 MiB.
+pub const DEFAULT_DECODE_BUDGET: u64 = 0x1000_0000;
+
+/// Upper bound on the size of a serialized data.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum SizeBound {
+    /// Data can't exceed the given number of bytes.
+    Bounded(u64),
+    /// Data size is not bounded, which happens for recursive types.
+    Unbounded,
+}
+
+impl Display for SizeBound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeBound::Bounded(size) => write!(f, "{size} bytes"),
+            SizeBound::Unbounded => f.write_str("unbounded"),
+        }
+    }
+}
+
+impl SizeBound {
+    fn add(self, other: SizeBound) -> SizeBound {
+        match (self, other) {
+            (SizeBound::Bounded(a), SizeBound::Bounded(b)) => a
+                .checked_add(b)
+                .map(SizeBound::Bounded)
+                .unwrap_or(SizeBound::Unbounded),
+            _ => SizeBound::Unbounded,
+        }
+    }
+
+    fn mul(self, count: u64) -> SizeBound {
+        match self {
+            SizeBound::Bounded(size) => size
+                .checked_mul(count)
+                .map(SizeBound::Bounded)
+                .unwrap_or(SizeBound::Unbounded),
+            SizeBound::Unbounded if count == 0 => SizeBound::Bounded(0),
+            SizeBound::Unbounded => SizeBound::Unbounded,
+        }
+    }
+
+    /// Returns the bound limited by the actual data length, if known.
+    pub fn limit(self, len: u64) -> u64 {
+        match self {
+            SizeBound::Bounded(size) => size.min(len),
+            SizeBound::Unbounded => len,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum BudgetError {
+    /// decoding requires up to {required} bytes, exceeding the budget of
+    /// {budget} bytes.
+    Exceeded { required: u64, budget: u64 },
+
+    /// type {0} is absent from the type system.
+    UnknownType(SemId),
+}
+
+/// Computes the worst-case size of serialized data of a given type, based on
+/// the confinement bounds of all the collections it contains.
+pub fn max_serialized_size(types: &TypeSystem, sem_id: SemId) -> Result<SizeBound, BudgetError> {
+    SizeAudit {
+        types,
+        cache: empty!(),
+    }
+    .size(sem_id)
+}
+
+struct SizeAudit<'types> {
+    types: &'types TypeSystem,
+    // `None` marks types which are being audited, detecting recursion
+    cache: BTreeMap<SemId, Option<SizeBound>>,
+}
+
+impl SizeAudit<'_> {
+    fn size(&mut self, sem_id: SemId) -> Result<SizeBound, BudgetError> {
+        match self.cache.get(&sem_id) {
+            Some(Some(size)) => return Ok(*size),
+            Some(None) => return Ok(SizeBound::Unbounded),
+            None => {}
+        }
+        let ty = self
+            .types
+            .get(sem_id)
+            .ok_or(BudgetError::UnknownType(sem_id))?;
+        self.cache.insert(sem_id, None);
+        let size = self.ty_size(ty)?;
+        self.cache.insert(sem_id, Some(size));
+        Ok(size)
+    }
+
+    fn ty_size(&mut self, ty: &Ty<SemId>) -> Result<SizeBound, BudgetError> {
+        Ok(match ty {
+            Ty::Primitive(primitive) => SizeBound::Bounded(primitive.byte_size() as u64),
+            Ty::UnicodeChar => SizeBound::Bounded(4),
+            Ty::Enum(_) => SizeBound::Bounded(1),
+            Ty::Union(variants) => {
+                let mut max = SizeBound::Bounded(0);
+                for (_, ty) in variants.iter() {
+                    max = max.max(self.size(*ty)?);
+                }
+                SizeBound::Bounded(1).add(max)
+            }
+            Ty::Tuple(fields) => {
+                let mut sum = SizeBound::Bounded(0);
+                for ty in fields.iter() {
+                    sum = sum.add(self.size(*ty)?);
+                }
+                sum
+            }
+            Ty::Struct(fields) => {
+                let mut sum = SizeBound::Bounded(0);
+                for field in fields.iter() {
+                    sum = sum.add(self.size(field.ty)?);
+                }
+                sum
+            }
+            Ty::Array(ty, len) => self.size(*ty)?.mul(*len as u64),
+            Ty::List(ty, sizing) | Ty::Set(ty, sizing) => {
+                len_prefix(*sizing).add(self.size(*ty)?.mul(sizing.max))
+            }
+            Ty::Map(key, value, sizing) => {
+                let item = self.size(*key)?.add(self.size(*value)?);
+                len_prefix(*sizing).add(item.mul(sizing.max))
+            }
+        })
+    }
+}
+
+fn len_prefix(sizing: Sizing) -> SizeBound {
+    SizeBound::Bounded(match sizing.max {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x1_0000..=0xFF_FFFF => 3,
+        0x100_0000..=0xFFFF_FFFF => 4,
+        _ => 8,
+    })
+}
+
+/// Overall budget for decoding untrusted data.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DecodeBudget {
+    pub max_bytes: u64,
+}
+
+impl Default for DecodeBudget {
+    fn default() -> Self {
+        DecodeBudget {
+            max_bytes: DEFAULT_DECODE_BUDGET,
+        }
+    }
+}
+
+impl DecodeBudget {
+    pub fn with(max_bytes: u64) -> Self { DecodeBudget { max_bytes } }
+
+    /// Checks that decoding of the given number of bytes fits the budget.
+    pub fn check(&self, required: u64) -> Result<(), BudgetError> {
+        if required > self.max_bytes {
+            return Err(BudgetError::Exceeded {
+                required,
+                budget: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that decoding of up to `len` bytes of data of a given type fits
+    /// the budget, taking into account that the data can't be larger than the
+    /// worst-case size of the type.
+    pub fn check_type(
+        &self,
+        len: u64,
+        types: &TypeSystem,
+        sem_id: SemId,
+    ) -> Result<u64, BudgetError> {
+        let required = max_serialized_size(types, sem_id)?.limit(len);
+        self.check(required)?;
+        Ok(required)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_types::SystemBuilder;
+    use strict_types::typelib::LibBuilder;
+
+    use super::*;
+    use crate::{DataState, LIB_NAME_RGB};
+
+    #[test]
+    fn worst_case_size() {
+        let lib = LibBuilder::new(libname!(LIB_NAME_RGB), None)
+            .transpile::<DataState>()
+            .compile()
+            .unwrap();
+        let sys = SystemBuilder::new()
+            .import(lib)
+            .unwrap()
+            .finalize()
+            .unwrap();
+        let data = *sys.resolve("RGB.DataState").unwrap();
+        let types = sys.into_type_system();
+
+        // Two-byte length prefix and up to 2^16-1 bytes
+        let max = 2 + 0xFFFF;
+        assert_eq!(max_serialized_size(&types, data), Ok(SizeBound::Bounded(max)));
+
+        let budget = DecodeBudget::with(1000);
+        assert_eq!(budget.check_type(500, &types, data), Ok(500));
+        assert_eq!(
+            budget.check_type(u64::MAX, &types, data),
+            Err(BudgetError::Exceeded {
+                required: max,
+                budget: 1000
+            })
+        );
+        assert_eq!(DecodeBudget::with(max).check_type(u64::MAX, &types, data), Ok(max));
+    }
+}
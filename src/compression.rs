@@ -0,0 +1,201 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Self-describing compression framing for serialized consignments and other
+//! large RGB data.
+//!
+//! Compressed frame starts with a header consisting of [`COMPRESSION_MAGIC`]
+//! bytes, one byte identifying compression algorithm and 4 bytes of the
+//! uncompressed data length in little-endian encoding. The length is checked
+//! against the limit provided by the caller before any decompression happens,
+//! protecting from the decompression bombs.
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+use std::io::Read;
+
+/// Magic bytes starting a compressed frame.
+pub const COMPRESSION_MAGIC: [u8; 4] = *b"RGBz";
+
+/// Length of the compressed frame header.
+pub const COMPRESSION_HEADER_LEN: usize = 9;
+
+/// Compression algorithm used by a frame.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Display)]
+#[display(lowercase)]
+#[repr(u8)]
+pub enum Compression {
+    #[default]
+    None = 0,
+    Deflate = 1,
+    Zstd = 2,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = CompressionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Deflate),
+            2 => Ok(Compression::Zstd),
+            unknown => Err(CompressionError::UnknownAlgorithm(unknown)),
+        }
+    }
+}
+
+impl Compression {
+    /// Detects whether the data start with a compressed frame header.
+    pub fn is_framed(data: &[u8]) -> bool { data.starts_with(&COMPRESSION_MAGIC) }
+}
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum CompressionError {
+    /// data doesn't start with a valid compression frame header.
+    InvalidHeader,
+
+    /// unknown compression algorithm with id {0}.
+    UnknownAlgorithm(u8),
+
+    /// compression algorithm {0} is not supported by this build.
+    Unsupported(Compression),
+
+    /// data of {0} bytes exceed the maximum allowed size of {1} bytes.
+    TooLarge(usize, usize),
+
+    /// decompressed data length doesn't match the length of {0} bytes
+    /// declared in the frame header.
+    LengthMismatch(usize),
+
+    /// I/O error during compression. Details: {0}
+    #[from]
+    Io(std::io::Error),
+}
+
+/// Compresses data, prepending them with a frame header.
+pub fn compress(data: &[u8], compression: Compression) -> Result<Vec<u8>, CompressionError> {
+    let len = u32::try_from(data.len())
+        .map_err(|_| CompressionError::TooLarge(data.len(), u32::MAX as usize))?;
+    let mut frame = Vec::with_capacity(COMPRESSION_HEADER_LEN + data.len());
+    frame.extend_from_slice(&COMPRESSION_MAGIC);
+    frame.push(compression as u8);
+    frame.extend_from_slice(&len.to_le_bytes());
+    match compression {
+        Compression::None => frame.extend_from_slice(data),
+        #[cfg(feature = "deflate")]
+        Compression::Deflate => {
+            use std::io::Write;
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(frame, flate2::Compression::default());
+            encoder.write_all(data)?;
+            frame = encoder.finish()?;
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            let compressed = zstd::stream::encode_all(data, 0)?;
+            frame.extend(compressed);
+        }
+        #[allow(unreachable_patterns)]
+        unsupported => return Err(CompressionError::Unsupported(unsupported)),
+    }
+    Ok(frame)
+}
+
+/// Decompresses a frame, failing if the uncompressed data exceed `max_len`
+/// bytes. The check is performed before the decompression starts.
+pub fn decompress(frame: &[u8], max_len: usize) -> Result<Vec<u8>, CompressionError> {
+    if frame.len() < COMPRESSION_HEADER_LEN || !Compression::is_framed(frame) {
+        return Err(CompressionError::InvalidHeader);
+    }
+    let compression = Compression::try_from(frame[4])?;
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&frame[5..COMPRESSION_HEADER_LEN]);
+    let len = u32::from_le_bytes(len) as usize;
+    if len > max_len {
+        return Err(CompressionError::TooLarge(len, max_len));
+    }
+    let payload = &frame[COMPRESSION_HEADER_LEN..];
+
+    let data = match compression {
+        Compression::None => payload.to_vec(),
+        #[cfg(feature = "deflate")]
+        Compression::Deflate => read_limited(flate2::read::DeflateDecoder::new(payload), len)?,
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => read_limited(zstd::stream::read::Decoder::new(payload)?, len)?,
+        #[allow(unreachable_patterns)]
+        unsupported => return Err(CompressionError::Unsupported(unsupported)),
+    };
+    if data.len() != len {
+        return Err(CompressionError::LengthMismatch(len));
+    }
+    Ok(data)
+}
+
+/// Reads at most one byte more than the declared length, so the excessive
+/// data are detected without decompressing them.
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+fn read_limited(reader: impl Read, len: usize) -> Result<Vec<u8>, CompressionError> {
+    let mut data = Vec::with_capacity(len);
+    reader.take(len as u64 + 1).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn uncompressed() {
+        let data = b"some consignment data".to_vec();
+        let frame = compress(&data, Compression::None).unwrap();
+        assert!(Compression::is_framed(&frame));
+        assert_eq!(decompress(&frame, data.len()).unwrap(), data);
+        assert!(matches!(
+            decompress(&frame, data.len() - 1),
+            Err(CompressionError::TooLarge(21, 20))
+        ));
+        assert!(matches!(decompress(&data, 1000), Err(CompressionError::InvalidHeader)));
+    }
+
+    #[test]
+    #[cfg(feature = "deflate")]
+    fn deflate() {
+        let data = vec![0xAAu8; 100_000];
+        let frame = compress(&data, Compression::Deflate).unwrap();
+        assert!(frame.len() < data.len());
+        assert_eq!(decompress(&frame, data.len()).unwrap(), data);
+
+        // Header lying about the uncompressed size must not allow bombs
+        let mut bomb = frame.clone();
+        bomb[5..9].copy_from_slice(&10u32.to_le_bytes());
+        assert!(matches!(decompress(&bomb, 1000), Err(CompressionError::LengthMismatch(10))));
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn zstd() {
+        let data = vec![0xAAu8; 100_000];
+        let frame = compress(&data, Compression::Zstd).unwrap();
+        assert!(frame.len() < data.len());
+        assert_eq!(decompress(&frame, data.len()).unwrap(), data);
+    }
+}
@@ -0,0 +1,351 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Construction of state transitions paying fungible state to many recipients
+//! at once, like in airdrops.
+//!
+//! [`Airdrop`] collects invoices, orders them deterministically by their
+//! blinded seals and splits them into chunks, each of which fits into a single
+//! state transition. Transitions are created from templates provided by the
+//! wallet for each of the chunks, which must contain inputs covering the chunk
+//! amount. Recipient assignments are appended after the assignments already
+//! present in the template, so the wallet may add change assignments to the
+//! end of the list without affecting the reported indexes.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::SmallVec;
+use bp::secp256k1::rand::{Rng, RngCore, thread_rng};
+
+use crate::{
+    AssetTag, Assign, AssignmentType, ContractId, Invoice, InvoiceState, Operation, Opout,
+    RevealedValue, SecretSeal, Transition, TypedAssigns, XChain,
+};
+
+/// Default maximal number of recipients paid by a single transition.
+pub const AIRDROP_CHUNK_SIZE: u16 = 0x400;
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AirdropError {
+    /// invoice is issued for a different contract {0}.
+    ContractMismatch(ContractId),
+
+    /// invoice requests state of type {0} not matching the airdropped state.
+    AssignmentTypeMismatch(AssignmentType),
+
+    /// invoice for the seal {0} doesn't request a fungible amount.
+    NoAmount(XChain<SecretSeal>),
+
+    /// seal {0} is already paid by the airdrop.
+    DuplicateSeal(XChain<SecretSeal>),
+
+    /// total airdrop amount exceeds 64-bit integer.
+    AmountOverflow,
+
+    /// transition template for chunk {0} assigns non-fungible state to the
+    /// airdropped state type.
+    TemplateStateMismatch(usize),
+
+    /// transition template for chunk {0} belongs to a different contract.
+    TemplateContractMismatch(usize),
+
+    /// chunk {0} doesn't fit into a single transition due to the number of
+    /// assignments.
+    TooManyAssignments(usize),
+}
+
+/// Payment of an invoice by an airdrop transition.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AirdropPayment {
+    pub invoice: Invoice,
+    /// Index of the assignment paying the invoice.
+    pub index: u16,
+}
+
+/// State transition produced for an airdrop chunk.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AirdropTransition {
+    pub transition: Transition,
+    pub assignment_type: AssignmentType,
+    pub payments: Vec<AirdropPayment>,
+}
+
+impl AirdropTransition {
+    /// Lists transition outputs paying each of the invoices.
+    ///
+    /// Must be called on a finalized transition, since the outputs depend on
+    /// the operation id.
+    pub fn opouts(&self) -> impl Iterator<Item = (&Invoice, Opout)> + '_ {
+        let opid = self.transition.id();
+        self.payments.iter().map(move |payment| {
+            (&payment.invoice, Opout::new(opid, self.assignment_type, payment.index))
+        })
+    }
+}
+
+/// Builder of transitions paying fungible state to many recipients.
+#[derive(Clone, Debug)]
+pub struct Airdrop {
+    contract_id: ContractId,
+    assignment_type: AssignmentType,
+    asset_tag: AssetTag,
+    chunk_size: u16,
+    total: u64,
+    // Ordering by the seals makes the produced transitions deterministic.
+    recipients: BTreeMap<XChain<SecretSeal>, (Invoice, u64)>,
+}
+
+impl Airdrop {
+    pub fn new(
+        contract_id: ContractId,
+        assignment_type: AssignmentType,
+        asset_tag: AssetTag,
+    ) -> Self {
+        Airdrop {
+            contract_id,
+            assignment_type,
+            asset_tag,
+            chunk_size: AIRDROP_CHUNK_SIZE,
+            total: 0,
+            recipients: empty!(),
+        }
+    }
+
+    /// Sets maximal number of recipients paid by a single transition.
+    ///
+    /// # Panics
+    ///
+    /// If the chunk size is zero.
+    pub fn with_chunk_size(mut self, chunk_size: u16) -> Self {
+        assert_ne!(chunk_size, 0, "airdrop chunk size must be non-zero");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Adds recipient requesting the airdropped state with an invoice.
+    pub fn add_invoice(&mut self, invoice: Invoice) -> Result<(), AirdropError> {
+        if invoice.contract_id != self.contract_id {
+            return Err(AirdropError::ContractMismatch(invoice.contract_id));
+        }
+        if invoice.assignment_type != self.assignment_type {
+            return Err(AirdropError::AssignmentTypeMismatch(invoice.assignment_type));
+        }
+        let InvoiceState::Amount(amount) = invoice.state else {
+            return Err(AirdropError::NoAmount(invoice.seal));
+        };
+        if self.recipients.contains_key(&invoice.seal) {
+            return Err(AirdropError::DuplicateSeal(invoice.seal));
+        }
+        let amount = amount.as_u64();
+        self.total = self
+            .total
+            .checked_add(amount)
+            .ok_or(AirdropError::AmountOverflow)?;
+        self.recipients.insert(invoice.seal, (invoice, amount));
+        Ok(())
+    }
+
+    /// Number of the recipients.
+    pub fn len(&self) -> usize { self.recipients.len() }
+
+    pub fn is_empty(&self) -> bool { self.recipients.is_empty() }
+
+    /// Total amount paid to all the recipients.
+    pub fn total(&self) -> u64 { self.total }
+
+    /// Number of transitions required to pay all the recipients.
+    pub fn chunk_count(&self) -> usize { self.len().div_ceil(self.chunk_size as usize) }
+
+    /// Amounts paid by each of the chunks.
+    pub fn chunk_amounts(&self) -> Vec<u64> {
+        self.chunks()
+            .into_iter()
+            .map(|chunk| chunk.iter().map(|(_, amount)| amount).sum())
+            .collect()
+    }
+
+    fn chunks(&self) -> Vec<Vec<&(Invoice, u64)>> {
+        let recipients = self.recipients.values().collect::<Vec<_>>();
+        recipients
+            .chunks(self.chunk_size as usize)
+            .map(<[_]>::to_vec)
+            .collect()
+    }
+
+    /// Constructs transitions paying all the recipients.
+    ///
+    /// The `template` callback is called for each chunk with its number and
+    /// the amount it pays, and must return transition spending inputs
+    /// sufficient to cover the amount.
+    pub fn transitions(
+        &self,
+        template: impl FnMut(usize, u64) -> Transition,
+    ) -> Result<Vec<AirdropTransition>, AirdropError> {
+        self.transitions_with_rng(template, &mut thread_rng())
+    }
+
+    /// Constructs transitions like [`Airdrop::transitions`], using the
+    /// provided random generator for creating blinding factors.
+    pub fn transitions_with_rng<R: Rng + RngCore>(
+        &self,
+        mut template: impl FnMut(usize, u64) -> Transition,
+        rng: &mut R,
+    ) -> Result<Vec<AirdropTransition>, AirdropError> {
+        let mut transitions = Vec::with_capacity(self.chunk_count());
+        for (no, chunk) in self.chunks().into_iter().enumerate() {
+            let amount = chunk.iter().map(|(_, amount)| amount).sum();
+            let mut transition = template(no, amount);
+            if transition.contract_id != self.contract_id {
+                return Err(AirdropError::TemplateContractMismatch(no));
+            }
+
+            let mut assigns = match transition.assignments.get(&self.assignment_type) {
+                None => SmallVec::new(),
+                Some(TypedAssigns::Fungible(assigns)) => assigns.clone(),
+                Some(_) => return Err(AirdropError::TemplateStateMismatch(no)),
+            };
+            let mut payments = Vec::with_capacity(chunk.len());
+            for (invoice, amount) in chunk {
+                let index = assigns.len() as u16;
+                let state = RevealedValue::with_rng(*amount, rng, self.asset_tag);
+                assigns
+                    .push(Assign::ConfidentialSeal {
+                        seal: invoice.seal,
+                        state,
+                        lock: default!(),
+                    })
+                    .map_err(|_| AirdropError::TooManyAssignments(no))?;
+                payments.push(AirdropPayment {
+                    invoice: invoice.clone(),
+                    index,
+                });
+            }
+            transition
+                .assignments
+                .insert(self.assignment_type, TypedAssigns::Fungible(assigns))
+                .map_err(|_| AirdropError::TooManyAssignments(no))?;
+            transition.invalidate_id();
+
+            transitions.push(AirdropTransition {
+                transition,
+                assignment_type: self.assignment_type,
+                payments,
+            });
+        }
+        Ok(transitions)
+    }
+
+    /// Produces report mapping each of the paid invoices to the transition
+    /// output paying it.
+    pub fn report<'a>(
+        transitions: impl IntoIterator<Item = &'a AirdropTransition>,
+    ) -> Vec<(Invoice, Opout)> {
+        transitions
+            .into_iter()
+            .flat_map(AirdropTransition::opouts)
+            .map(|(invoice, opout)| (invoice.clone(), opout))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use bp::dbc::Method;
+    use commit_verify::Conceal;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{Assignments, FungibleState, GraphSeal};
+
+    #[test]
+    fn chunking() {
+        let contract_id = ContractId::from_byte_array([0x6c; 32]);
+        let ty = AssignmentType::with(1);
+        let tag = AssetTag::from([0xAA; 32]);
+        let invoice = |vout: u32, amount: u64| {
+            let seal: XChain<GraphSeal> =
+                XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, vout, 0));
+            Invoice::new(
+                contract_id,
+                ty,
+                InvoiceState::Amount(FungibleState::Bits64(amount)),
+                seal.conceal(),
+            )
+        };
+
+        let mut airdrop = Airdrop::new(contract_id, ty, tag).with_chunk_size(2);
+        for vout in 0..5 {
+            airdrop
+                .add_invoice(invoice(vout, 10 + vout as u64))
+                .unwrap();
+        }
+        assert_eq!(
+            airdrop.add_invoice(invoice(0, 1)),
+            Err(AirdropError::DuplicateSeal(invoice(0, 1).seal))
+        );
+        let mut foreign = invoice(6, 1);
+        foreign.contract_id = ContractId::from_byte_array([0x01; 32]);
+        assert!(matches!(airdrop.add_invoice(foreign), Err(AirdropError::ContractMismatch(_))));
+        assert_eq!(airdrop.len(), 5);
+        assert_eq!(airdrop.total(), 60);
+        assert_eq!(airdrop.chunk_count(), 3);
+        assert_eq!(airdrop.chunk_amounts().iter().sum::<u64>(), 60);
+
+        let change = Assign::ConfidentialSeal {
+            seal: invoice(100, 0).seal,
+            state: RevealedValue::new_random_blinding(5u64, tag),
+            lock: default!(),
+        };
+        let transitions = airdrop
+            .transitions(|_, _| {
+                let mut transition = Transition::strict_dumb();
+                transition.contract_id = contract_id;
+                transition.assignments = Assignments::from(tiny_bmap! {
+                    ty => TypedAssigns::Fungible(small_vec![change.clone()])
+                });
+                transition
+            })
+            .unwrap();
+        assert_eq!(transitions.len(), 3);
+        assert_eq!(transitions[2].payments.len(), 1);
+
+        let report = Airdrop::report(&transitions);
+        assert_eq!(report.len(), 5);
+        let mut seals = vec![];
+        for (invoice, opout) in report {
+            let transition = transitions
+                .iter()
+                .map(|t| &t.transition)
+                .find(|t| t.id() == opout.op)
+                .unwrap();
+            assert_ne!(opout.no, 0);
+            assert_eq!(
+                transition.assignments[&ty].to_confidential_seal_at(opout.no),
+                Ok(invoice.seal)
+            );
+            seals.push(invoice.seal);
+        }
+        assert!(seals.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -23,14 +23,14 @@
 use std::cmp::Ordering;
 
 use bp::dbc::opret::{OpretError, OpretProof};
-use bp::dbc::tapret::TapretProof;
-use bp::dbc::Method;
-use bp::{dbc, Tx};
+use bp::dbc::tapret::{TapretKeyError, TapretProof};
+use bp::dbc::{Method, Proof};
+use bp::{Tx, dbc};
 use commit_verify::mpc::Commitment;
-use commit_verify::{mpc, ConvolveVerifyError, EmbedVerifyError};
+use commit_verify::{ConvolveCommit, ConvolveVerifyError, EmbedVerifyError, mpc};
 use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
 
-use crate::{WitnessOrd, XWitnessId, LIB_NAME_RGB};
+use crate::{BundleId, LIB_NAME_RGB, TransitionBundle, Vin, WitnessOrd, XWitnessId};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
 #[cfg_attr(
@@ -115,6 +115,94 @@ impl dbc::Proof for DbcProof {
 /// Anchor which DBC proof is either Tapret or Opret.
 pub type EAnchor<P = mpc::MerkleProof> = dbc::Anchor<P, DbcProof>;
 
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum UnsignedAnchorError {
+    /// bundle {0} uses seal closing method different from the one of the
+    /// anchor.
+    MethodMismatch(BundleId),
+
+    /// multi-protocol commitment proof of the anchor doesn't match the
+    /// contract of the bundle {0}.
+    NotCommitted(BundleId),
+
+    /// bundle {0} commits to the input #{1} absent in the witness
+    /// transaction.
+    NoInput(BundleId, Vin),
+
+    /// invalid commitment in the witness transaction: {0}
+    #[from]
+    Dbc(DbcError),
+
+    /// tapret commitment can't be put into the witness transaction output:
+    /// {0}
+    #[from]
+    TapretKey(TapretKeyError),
+}
+
+/// Dry-run verification of anchors against witness transactions which are
+/// not yet signed.
+pub trait VerifyUnsigned {
+    /// Verifies that the witness transaction commits to the bundle in the way
+    /// the receiver validation expects, and that it spends all the inputs
+    /// committed by the bundle input map. Since signatures and txid are not
+    /// covered by the commitments, the transaction doesn't have to be signed.
+    ///
+    /// Tapret output of the transaction may still use the original,
+    /// untweaked, key; in this case the method checks that the tweak can be
+    /// derived from the proof.
+    ///
+    /// Returns the multi-protocol commitment of the transaction.
+    fn verify_unsigned(
+        &self,
+        bundle: &TransitionBundle,
+        tx: &Tx,
+    ) -> Result<Commitment, UnsignedAnchorError>;
+}
+
+impl VerifyUnsigned for EAnchor {
+    fn verify_unsigned(
+        &self,
+        bundle: &TransitionBundle,
+        tx: &Tx,
+    ) -> Result<Commitment, UnsignedAnchorError> {
+        let bundle_id = bundle.bundle_id();
+        match (bundle.close_method, &self.dbc_proof) {
+            (Method::OpretFirst, DbcProof::Opret(_)) |
+            (Method::TapretFirst, DbcProof::Tapret(_)) => {}
+            _ => return Err(UnsignedAnchorError::MethodMismatch(bundle_id)),
+        }
+        let contract_id = bundle
+            .known_transitions
+            .values()
+            .next()
+            .expect("bundle always has transitions")
+            .contract_id;
+        let commitment = self
+            .convolve(contract_id, bundle_id)
+            .map_err(|_| UnsignedAnchorError::NotCommitted(bundle_id))?;
+        if let Some(vin) = bundle
+            .input_map
+            .keys()
+            .find(|vin| tx.inputs.get(vin.to_usize()).is_none())
+        {
+            return Err(UnsignedAnchorError::NoInput(bundle_id, *vin));
+        }
+        if let DbcProof::Tapret(tapret) = &self.dbc_proof {
+            let original = tapret.original_pubkey_script();
+            let output = tx.outputs.iter().find(|out| out.script_pubkey.is_p2tr());
+            if output.is_some_and(|out| out.script_pubkey == original) {
+                tapret
+                    .internal_pk
+                    .convolve_commit(&tapret.path_proof, &commitment)?;
+                return Ok(commitment);
+            }
+        }
+        self.dbc_proof.verify(&commitment, tx)?;
+        Ok(commitment)
+    }
+}
+
 /// Txid and height information ordered according to the RGB consensus rules.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
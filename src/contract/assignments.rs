@@ -29,11 +29,11 @@ use amplify::confinement::{Confined, SmallVec, TinyOrdMap};
 use commit_verify::{Conceal, ReservedBytes};
 use strict_encoding::{StrictDumb, StrictEncode};
 
-use super::ExposedState;
+use super::{ConfidentialState, ExposedState};
 use crate::contract::seal::GenesisSeal;
 use crate::{
-    AssignmentType, ExposedSeal, GraphSeal, RevealedAttach, RevealedData, RevealedValue,
-    SecretSeal, StateType, VoidState, XChain, LIB_NAME_RGB,
+    AssignmentType, ExposedSeal, GraphSeal, LIB_NAME_RGB, MapView, RevealedAttach, RevealedData,
+    RevealedValue, SchemaFlags, SecretSeal, StateAtom, StateType, VoidState, XChain,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, Error)]
@@ -41,6 +41,21 @@ use crate::{
 /// the requested data are not present.
 pub struct UnknownDataError;
 
+/// Errors accessing revealed fungible state.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum RevealedStateError {
+    /// the assignments don't hold fungible state.
+    NotFungible,
+
+    /// fungible state #{0} is concealed while the schema requires all amounts
+    /// to be revealed, thus the data are invalid.
+    ConfidentialForbidden(u16),
+
+    /// amount of fungible state #{0} is withheld.
+    Withheld(u16),
+}
+
 pub type AssignRights<Seal> = Assign<VoidState, Seal>;
 pub type AssignFungible<Seal> = Assign<RevealedValue, Seal>;
 pub type AssignData<Seal> = Assign<RevealedData, Seal>;
@@ -227,6 +242,68 @@ impl<State: ExposedState, Seal: ExposedSeal> Assign<State, Seal> {
             _ => None,
         }
     }
+
+    /// Checks that the concealed form of the assignment matches the
+    /// confidential seal and state derived from it individually.
+    pub fn is_conceal_consistent(&self) -> bool {
+        let Assign::Confidential { seal, state, .. } = self.conceal() else {
+            return false;
+        };
+        seal == self.to_confidential_seal() && state == self.to_confidential_state()
+    }
+
+    /// Conceals the seal of the assignment, keeping the state in the form
+    /// it is already present.
+    ///
+    /// Together with [`Assign::conceal_state`] and [`Conceal::conceal`] this
+    /// allows only conversions towards more concealed forms; revealing data
+    /// requires merging with a revealed version of the same assignment (see
+    /// [`crate::MergeReveal`]).
+    pub fn conceal_seal(&self) -> Self {
+        match self {
+            Assign::Revealed { seal, state, lock } => Assign::ConfidentialSeal {
+                seal: seal.conceal(),
+                state: state.clone(),
+                lock: *lock,
+            },
+            Assign::ConfidentialState { seal, state, lock } => Assign::Confidential {
+                seal: seal.conceal(),
+                state: *state,
+                lock: *lock,
+            },
+            Assign::Confidential { .. } | Assign::ConfidentialSeal { .. } => self.clone(),
+        }
+    }
+
+    /// Conceals the state of the assignment, keeping the seal in the form it
+    /// is already present.
+    pub fn conceal_state(&self) -> Self {
+        match self {
+            Assign::Revealed { seal, state, lock } => Assign::ConfidentialState {
+                seal: *seal,
+                state: state.conceal(),
+                lock: *lock,
+            },
+            Assign::ConfidentialSeal { seal, state, lock } => Assign::Confidential {
+                seal: *seal,
+                state: state.conceal(),
+                lock: *lock,
+            },
+            Assign::Confidential { .. } | Assign::ConfidentialState { .. } => self.clone(),
+        }
+    }
+
+    /// Returns the assigned state with its specific type erased.
+    pub fn to_state_atom(&self) -> StateAtom {
+        match self {
+            Assign::Revealed { state, .. } | Assign::ConfidentialSeal { state, .. } => {
+                StateAtom::Revealed(state.state_data())
+            }
+            Assign::Confidential { state, .. } | Assign::ConfidentialState { state, .. } => {
+                StateAtom::Concealed(state.state_commitment())
+            }
+        }
+    }
 }
 
 impl<State: ExposedState, Seal: ExposedSeal> Conceal for Assign<State, Seal>
@@ -283,6 +360,61 @@ impl<State: ExposedState> Assign<State, GenesisSeal> {
     }
 }
 
+impl<State: ExposedState> From<Assign<State, GenesisSeal>> for Assign<State, GraphSeal> {
+    fn from(assign: Assign<State, GenesisSeal>) -> Self { assign.transmutate_seals() }
+}
+
+/// Reference to a single assignment, with the kind of its state erased.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, From)]
+pub enum AssignRef<'assign, Seal: ExposedSeal> {
+    #[from]
+    Declarative(&'assign AssignRights<Seal>),
+    #[from]
+    Fungible(&'assign AssignFungible<Seal>),
+    #[from]
+    Structured(&'assign AssignData<Seal>),
+    #[from]
+    Attachment(&'assign AssignAttach<Seal>),
+}
+
+impl<Seal: ExposedSeal> AssignRef<'_, Seal> {
+    pub fn state_type(&self) -> StateType {
+        match self {
+            AssignRef::Declarative(_) => StateType::Void,
+            AssignRef::Fungible(_) => StateType::Fungible,
+            AssignRef::Structured(_) => StateType::Structured,
+            AssignRef::Attachment(_) => StateType::Attachment,
+        }
+    }
+
+    pub fn revealed_seal(&self) -> Option<XChain<Seal>> {
+        match self {
+            AssignRef::Declarative(a) => a.revealed_seal(),
+            AssignRef::Fungible(a) => a.revealed_seal(),
+            AssignRef::Structured(a) => a.revealed_seal(),
+            AssignRef::Attachment(a) => a.revealed_seal(),
+        }
+    }
+
+    pub fn to_confidential_seal(&self) -> XChain<SecretSeal> {
+        match self {
+            AssignRef::Declarative(a) => a.to_confidential_seal(),
+            AssignRef::Fungible(a) => a.to_confidential_seal(),
+            AssignRef::Structured(a) => a.to_confidential_seal(),
+            AssignRef::Attachment(a) => a.to_confidential_seal(),
+        }
+    }
+
+    pub fn to_state_atom(&self) -> StateAtom {
+        match self {
+            AssignRef::Declarative(a) => a.to_state_atom(),
+            AssignRef::Fungible(a) => a.to_state_atom(),
+            AssignRef::Structured(a) => a.to_state_atom(),
+            AssignRef::Attachment(a) => a.to_state_atom(),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Declarative(strict_dumb!()))]
@@ -442,6 +574,25 @@ impl<Seal: ExposedSeal> TypedAssigns<Seal> {
         }
     }
 
+    /// If assignment does not exist, returns [`UnknownDataError`]; otherwise
+    /// returns reference to it.
+    pub fn assign_at(&self, index: u16) -> Result<AssignRef<'_, Seal>, UnknownDataError> {
+        let index = index as usize;
+        match self {
+            TypedAssigns::Declarative(vec) => vec.get(index).map(AssignRef::from),
+            TypedAssigns::Fungible(vec) => vec.get(index).map(AssignRef::from),
+            TypedAssigns::Structured(vec) => vec.get(index).map(AssignRef::from),
+            TypedAssigns::Attachment(vec) => vec.get(index).map(AssignRef::from),
+        }
+        .ok_or(UnknownDataError)
+    }
+
+    /// Iterates over the assignments, without allocating intermediate
+    /// collections.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = AssignRef<'_, Seal>> + '_ {
+        (0..self.len_u16()).map(|no| self.assign_at(no).expect("index within the length"))
+    }
+
     /// If seal definition does not exist, returns [`UnknownDataError`]. If the
     /// seal is confidential, returns `Ok(None)`; otherwise returns revealed
     /// seal data packed as `Ok(Some(`[`Seal`]`))`
@@ -466,6 +617,90 @@ impl<Seal: ExposedSeal> TypedAssigns<Seal> {
         })
     }
 
+    /// If seal definition does not exist, returns [`UnknownDataError`];
+    /// otherwise returns the seal in its concealed form.
+    pub fn to_confidential_seal_at(
+        &self,
+        index: u16,
+    ) -> Result<XChain<SecretSeal>, UnknownDataError> {
+        Ok(match self {
+            TypedAssigns::Declarative(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_confidential_seal(),
+            TypedAssigns::Fungible(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_confidential_seal(),
+            TypedAssigns::Structured(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_confidential_seal(),
+            TypedAssigns::Attachment(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_confidential_seal(),
+        })
+    }
+
+    /// If state definition does not exist, returns [`UnknownDataError`];
+    /// otherwise returns the state with its specific type erased, which
+    /// allows to inspect it without matching on the assignment type.
+    pub fn state_at(&self, index: u16) -> Result<StateAtom, UnknownDataError> {
+        Ok(match self {
+            TypedAssigns::Declarative(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_state_atom(),
+            TypedAssigns::Fungible(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_state_atom(),
+            TypedAssigns::Structured(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_state_atom(),
+            TypedAssigns::Attachment(vec) => vec
+                .get(index as usize)
+                .ok_or(UnknownDataError)?
+                .to_state_atom(),
+        })
+    }
+
+    /// Iterates over all assignments, yielding concealed seals together with
+    /// the type-erased state.
+    pub fn state_atoms(&self) -> impl Iterator<Item = (XChain<SecretSeal>, StateAtom)> + '_ {
+        (0..self.len_u16()).map(|index| {
+            let seal = self
+                .to_confidential_seal_at(index)
+                .expect("index within range");
+            let state = self.state_at(index).expect("index within range");
+            (seal, state)
+        })
+    }
+
+    /// Iterates over assignments with revealed seals, yielding the seals
+    /// together with the type-erased state.
+    pub fn revealed_state_atoms(&self) -> impl Iterator<Item = (XChain<Seal>, StateAtom)> + '_ {
+        (0..self.len_u16()).filter_map(|index| {
+            let seal = self.revealed_seal_at(index).expect("index within range")?;
+            let state = self.state_at(index).expect("index within range");
+            Some((seal, state))
+        })
+    }
+
+    /// Returns index of the first assignment which concealed form is not
+    /// consistent with its revealed data, if any.
+    pub fn find_conceal_inconsistency(&self) -> Option<u16> {
+        let pos = match self {
+            TypedAssigns::Declarative(vec) => vec.iter().position(|a| !a.is_conceal_consistent()),
+            TypedAssigns::Fungible(vec) => vec.iter().position(|a| !a.is_conceal_consistent()),
+            TypedAssigns::Structured(vec) => vec.iter().position(|a| !a.is_conceal_consistent()),
+            TypedAssigns::Attachment(vec) => vec.iter().position(|a| !a.is_conceal_consistent()),
+        };
+        pos.map(|pos| pos as u16)
+    }
+
     pub fn to_confidential_seals(&self) -> Vec<XChain<SecretSeal>> {
         match self {
             TypedAssigns::Declarative(s) => s
@@ -513,6 +748,31 @@ impl<Seal: ExposedSeal> TypedAssigns<Seal> {
         }
     }
 
+    /// Returns revealed state of all fungible assignments. If some of the
+    /// amounts are concealed, the error distinguishes whether the schema
+    /// forbids confidential amounts (see [`SchemaFlags::REVEALED_AMOUNTS`]),
+    /// making the data invalid, or the amount was withheld.
+    pub fn as_revealed_state_values(
+        &self,
+        flags: SchemaFlags,
+    ) -> Result<Vec<&RevealedValue>, RevealedStateError> {
+        let TypedAssigns::Fungible(vec) = self else {
+            return Err(RevealedStateError::NotFungible);
+        };
+        vec.iter()
+            .enumerate()
+            .map(|(no, assign)| {
+                assign.as_revealed_state().ok_or_else(|| {
+                    if flags.contains(SchemaFlags::REVEALED_AMOUNTS) {
+                        RevealedStateError::ConfidentialForbidden(no as u16)
+                    } else {
+                        RevealedStateError::Withheld(no as u16)
+                    }
+                })
+            })
+            .collect()
+    }
+
     pub fn into_structured_state_at(
         self,
         index: u16,
@@ -550,6 +810,31 @@ impl<Seal: ExposedSeal> TypedAssigns<Seal> {
     }
 }
 
+/// Operation over a homogeneous list of assignments which is generic over the
+/// kind of their state.
+///
+/// Allows to process [`TypedAssigns`] with [`TypedAssigns::visit`] without
+/// matching each of its variants, such that adding a new state kind doesn't
+/// require updating the processing code.
+pub trait AssignsVisitor<Seal: ExposedSeal> {
+    type Output;
+
+    fn visit<State: ExposedState>(self, assigns: &[Assign<State, Seal>]) -> Self::Output;
+}
+
+impl<Seal: ExposedSeal> TypedAssigns<Seal> {
+    /// Applies the visitor to the list of assignments, whichever kind of
+    /// state it has.
+    pub fn visit<V: AssignsVisitor<Seal>>(&self, visitor: V) -> V::Output {
+        match self {
+            TypedAssigns::Declarative(a) => visitor.visit(a),
+            TypedAssigns::Fungible(a) => visitor.visit(a),
+            TypedAssigns::Structured(a) => visitor.visit(a),
+            TypedAssigns::Attachment(a) => visitor.visit(a),
+        }
+    }
+}
+
 impl TypedAssigns<GenesisSeal> {
     pub fn transmutate_seals(&self) -> TypedAssigns<GraphSeal> {
         match self {
@@ -573,6 +858,10 @@ impl TypedAssigns<GenesisSeal> {
     }
 }
 
+impl From<TypedAssigns<GenesisSeal>> for TypedAssigns<GraphSeal> {
+    fn from(assigns: TypedAssigns<GenesisSeal>) -> Self { assigns.transmutate_seals() }
+}
+
 #[derive(Wrapper, WrapperMut, Clone, PartialEq, Eq, Debug, From)]
 #[wrapper(Deref)]
 #[wrapper_mut(DerefMut)]
@@ -594,6 +883,43 @@ impl<Seal: ExposedSeal> Default for Assignments<Seal> {
     fn default() -> Self { Self(empty!()) }
 }
 
+impl<Seal: ExposedSeal> Assignments<Seal> {
+    /// Read-only view of the assignments by their type.
+    pub fn view(&self) -> MapView<'_, AssignmentType, TypedAssigns<Seal>> { MapView::new(&self.0) }
+
+    /// Iterates over all assignments of all types, yielding assignment type,
+    /// index of the assignment within the type and the reference to it.
+    pub fn iter_flat(
+        &self,
+    ) -> impl Iterator<Item = (AssignmentType, u16, AssignRef<'_, Seal>)> + '_ {
+        self.iter().flat_map(|(ty, assigns)| {
+            assigns
+                .iter()
+                .enumerate()
+                .map(move |(no, assign)| (*ty, no as u16, assign))
+        })
+    }
+
+    /// Checks that none of the assignment types has an empty list of
+    /// assignments (see [`Self::canonicalize`]).
+    pub fn is_canonical(&self) -> bool { self.values().all(|assigns| !assigns.is_empty()) }
+
+    /// Removes assignment types with an empty list of assignments. Such
+    /// entries have the same meaning and produce the same operation id as the
+    /// absence of the type, but change the operation encoding and its
+    /// disclosure hash.
+    pub fn canonicalize(&mut self) {
+        if self.is_canonical() {
+            return;
+        }
+        let assignments = self
+            .iter()
+            .filter(|(_, assigns)| !assigns.is_empty())
+            .map(|(ty, assigns)| (*ty, assigns.clone()));
+        self.0 = Confined::try_from_iter(assignments).expect("same size");
+    }
+}
+
 impl Assignments<GenesisSeal> {
     pub fn transmutate_seals(&self) -> Assignments<GraphSeal> {
         Assignments(
@@ -603,6 +929,10 @@ impl Assignments<GenesisSeal> {
     }
 }
 
+impl From<Assignments<GenesisSeal>> for Assignments<GraphSeal> {
+    fn from(assignments: Assignments<GenesisSeal>) -> Self { assignments.transmutate_seals() }
+}
+
 impl<Seal: ExposedSeal> IntoIterator for Assignments<Seal> {
     type Item = (AssignmentType, TypedAssigns<Seal>);
     type IntoIter = btree_map::IntoIter<AssignmentType, TypedAssigns<Seal>>;
@@ -629,6 +959,13 @@ impl AssignmentsRef<'_> {
 
     pub fn is_empty(&self) -> bool { self.len() == 0 }
 
+    pub fn is_canonical(&self) -> bool {
+        match self {
+            AssignmentsRef::Genesis(a) => a.is_canonical(),
+            AssignmentsRef::Graph(a) => a.is_canonical(),
+        }
+    }
+
     pub fn flat(&self) -> Assignments<GraphSeal> {
         match *self {
             AssignmentsRef::Genesis(a) => a.transmutate_seals(),
@@ -636,6 +973,34 @@ impl AssignmentsRef<'_> {
         }
     }
 
+    /// Iterates over concealed seals of all assignments, together with the
+    /// assignment type and the kind of its state. Unlike [`Self::flat`],
+    /// doesn't clone the assignments, but produces the same seals as it does:
+    /// revealed genesis seals are concealed after converting them into graph
+    /// seals.
+    pub fn confidential_seals(
+        &self,
+    ) -> impl Iterator<Item = (AssignmentType, StateType, XChain<SecretSeal>)> + '_ {
+        let (genesis, graph) = match *self {
+            AssignmentsRef::Genesis(a) => (Some(a), None),
+            AssignmentsRef::Graph(a) => (None, Some(a)),
+        };
+        let genesis = genesis.into_iter().flat_map(|a| {
+            a.iter_flat().map(|(ty, _, a)| {
+                let seal = match a.revealed_seal() {
+                    Some(seal) => seal.transmutate().conceal(),
+                    None => a.to_confidential_seal(),
+                };
+                (ty, a.state_type(), seal)
+            })
+        });
+        let graph = graph.into_iter().flat_map(|a| {
+            a.iter_flat()
+                .map(|(ty, _, a)| (ty, a.state_type(), a.to_confidential_seal()))
+        });
+        genesis.chain(graph)
+    }
+
     pub fn types(&self) -> BTreeSet<AssignmentType> {
         match self {
             AssignmentsRef::Genesis(a) => a.keys().copied().collect(),
@@ -656,4 +1021,145 @@ impl AssignmentsRef<'_> {
             AssignmentsRef::Graph(a) => a.get(&t).cloned(),
         }
     }
+
+    /// Returns number of assignments of the given type, whether revealed or
+    /// concealed.
+    pub fn count(&self, t: AssignmentType) -> u16 {
+        match self {
+            AssignmentsRef::Genesis(a) => a.get(&t).map(TypedAssigns::len_u16),
+            AssignmentsRef::Graph(a) => a.get(&t).map(TypedAssigns::len_u16),
+        }
+        .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bp::dbc::Method;
+
+    use super::*;
+    use crate::{AssetTag, BlindingFactor, RevealedState};
+
+    #[test]
+    fn state_atoms() {
+        let tag = AssetTag::from([0x6c; 32]);
+        let value = RevealedValue::with_blinding(100u64, BlindingFactor::EMPTY, tag);
+        let seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 0));
+        let assign = Assign::revealed(seal, value.clone());
+        let concealed = assign.conceal_seal();
+        let hidden = assign.conceal();
+        let assigns = TypedAssigns::Fungible(small_vec![assign, concealed, hidden]);
+
+        let revealed = StateAtom::Revealed(RevealedState::Fungible(value));
+        assert_eq!(assigns.state_at(0), Ok(revealed.clone()));
+        assert_eq!(assigns.state_at(1), Ok(revealed.clone()));
+        assert_eq!(assigns.state_at(3), Err(UnknownDataError));
+        let atom = assigns.state_at(2).unwrap();
+        assert!(!atom.is_revealed());
+        assert_eq!(atom.state_type(), StateType::Fungible);
+        assert_eq!(atom.commitment(), revealed.commitment());
+
+        assert_eq!(assigns.state_atoms().count(), 3);
+        assert!(
+            assigns
+                .state_atoms()
+                .all(|(secret, _)| secret == seal.map_ref(|seal| seal.conceal()))
+        );
+        assert_eq!(assigns.revealed_state_atoms().collect::<Vec<_>>(), vec![(seal, revealed)]);
+    }
+
+    #[test]
+    fn visitor() {
+        struct Revealed;
+        impl AssignsVisitor<GraphSeal> for Revealed {
+            type Output = usize;
+            fn visit<State: ExposedState>(self, assigns: &[Assign<State, GraphSeal>]) -> usize {
+                assigns.iter().filter(|a| a.as_revealed().is_some()).count()
+            }
+        }
+
+        let seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 0));
+        let right = Assign::revealed(seal, VoidState::default());
+        let rights = TypedAssigns::Declarative(small_vec![right.clone(), right.conceal()]);
+        assert_eq!(rights.visit(Revealed), 1);
+        let value = RevealedValue::with_blinding(1u64, BlindingFactor::EMPTY, strict_dumb!());
+        let values = TypedAssigns::Fungible(small_vec![Assign::revealed(seal, value)]);
+        assert_eq!(values.visit(Revealed), 1);
+    }
+
+    #[test]
+    fn flat_iteration() {
+        let seal =
+            |vout| XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, vout, 0));
+        let right = Assign::revealed(seal(0), VoidState::default());
+        let value = RevealedValue::with_blinding(1u64, BlindingFactor::EMPTY, strict_dumb!());
+        let amount = Assign::revealed(seal(1), value);
+        let assignments = Assignments::from(tiny_bmap! {
+            AssignmentType::with(2) => TypedAssigns::Fungible(small_vec![amount.clone()]),
+            AssignmentType::with(1) => TypedAssigns::Declarative(small_vec![
+                right.clone(),
+                right.conceal(),
+            ]),
+        });
+
+        let flat = assignments.iter_flat().collect::<Vec<_>>();
+        assert_eq!(flat, vec![
+            (AssignmentType::with(1), 0, AssignRef::Declarative(&right)),
+            (AssignmentType::with(1), 1, AssignRef::Declarative(&right.conceal())),
+            (AssignmentType::with(2), 0, AssignRef::Fungible(&amount)),
+        ]);
+        assert_eq!(flat[1].2.revealed_seal(), None);
+        assert_eq!(flat[2].2.state_type(), StateType::Fungible);
+        assert_eq!(flat[2].2.to_confidential_seal(), seal(1).conceal());
+        assert_eq!(assignments[&AssignmentType::with(1)].iter().len(), 2);
+    }
+
+    #[test]
+    fn ref_confidential_seals() {
+        let seal = GenesisSeal::with_blinding(Method::OpretFirst, bp::Txid::strict_dumb(), 0, 0);
+        let right = Assign::revealed(XChain::Bitcoin(seal), VoidState::default());
+        let assignments = Assignments::from(tiny_bmap! {
+            AssignmentType::with(1) => TypedAssigns::Declarative(small_vec![
+                right.clone(),
+                right.conceal(),
+            ]),
+        });
+        let expected = assignments
+            .transmutate_seals()
+            .iter()
+            .flat_map(|(ty, a)| a.to_confidential_seals().into_iter().map(|s| (*ty, s)))
+            .collect::<Vec<_>>();
+        let seals = AssignmentsRef::Genesis(&assignments)
+            .confidential_seals()
+            .map(|(ty, state_type, seal)| {
+                assert_eq!(state_type, StateType::Void);
+                (ty, seal)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(seals, expected);
+    }
+
+    #[test]
+    fn concealment_forms() {
+        let tag = AssetTag::from([0x6c; 32]);
+        let value = RevealedValue::with_blinding(100u64, BlindingFactor::EMPTY, tag);
+        let seal = GenesisSeal::with_blinding(Method::OpretFirst, bp::Txid::strict_dumb(), 0, 0);
+        let assign = Assign::revealed(XChain::Bitcoin(seal), value);
+
+        let no_seal = assign.conceal_seal();
+        let no_state = assign.conceal_state();
+        assert!(matches!(no_seal, Assign::ConfidentialSeal { .. }));
+        assert!(matches!(no_state, Assign::ConfidentialState { .. }));
+        for form in [&no_seal, &no_state] {
+            assert_eq!(form, &assign);
+            assert!(matches!(form.conceal_seal().conceal_state(), Assign::Confidential { .. }));
+            assert!(matches!(form.conceal_state().conceal_seal(), Assign::Confidential { .. }));
+        }
+        assert!(matches!(no_seal.conceal_seal(), Assign::ConfidentialSeal { .. }));
+        assert!(matches!(no_state.conceal_state(), Assign::ConfidentialState { .. }));
+
+        let graph = Assign::<_, GraphSeal>::from(no_state.clone());
+        assert_eq!(graph.revealed_seal(), Some(XChain::Bitcoin(seal.transmutate())));
+        assert_eq!(graph.to_confidential_state(), no_state.to_confidential_state());
+    }
 }
@@ -20,19 +20,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{btree_map, BTreeMap};
+use std::collections::{BTreeMap, BTreeSet, btree_map};
+use std::fmt::{self, Display, Formatter};
+use std::num::NonZeroUsize;
+use std::thread;
 
 use amplify::confinement::{Confined, U16 as U16MAX};
 use amplify::{Bytes32, Wrapper};
-use bp::seals::txout::CloseMethod;
 use bp::Vout;
-use commit_verify::{mpc, CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, Sha256};
+use bp::seals::txout::CloseMethod;
+use commit_verify::{CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, Sha256, mpc};
 use strict_encoding::{StrictDumb, StrictEncode};
 
-use crate::{OpId, Transition, LIB_NAME_RGB};
+use crate::summary::fmt_list;
+use crate::{ContractId, LIB_NAME_RGB, MapView, OpId, Operation, Opout, Transition};
 
 pub type Vin = Vout;
 
+/// Minimal number of known transitions in a bundle for which
+/// [`TransitionBundle::precompute_ids`] spreads hashing across threads.
+pub const PARALLEL_HASHING_THRESHOLD: usize = 64;
+
 /// Unique state transition bundle identifier equivalent to the bundle
 /// commitment hash
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
@@ -84,6 +92,10 @@ impl StrictDumb for InputMap {
 
 impl InputMap {
     pub fn with(input: Vin, id: OpId) -> Self { InputMap(Confined::with((input, id))) }
+
+    /// Read-only view of the state transitions by the witness input spending
+    /// their seals.
+    pub fn view(&self) -> MapView<'_, Vin, OpId> { MapView::new(&self.0) }
 }
 
 impl IntoIterator for InputMap {
@@ -100,6 +112,30 @@ impl<'a> IntoIterator for &'a InputMap {
     fn into_iter(self) -> Self::IntoIter { self.0.iter() }
 }
 
+/// Errors constructing transition bundle from multiple state transitions.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum BundleError {
+    /// no state transitions are provided for the bundle.
+    Empty,
+
+    /// bundle contains more than 65535 state transitions or witness inputs.
+    TooLarge,
+
+    /// state transition {0} belongs to a contract {1} other than the rest of
+    /// the bundle.
+    ContractMismatch(OpId, ContractId),
+
+    /// state transition {0} is not assigned any of the witness inputs.
+    NoInputs(OpId),
+
+    /// witness input {0} is assigned to several state transitions.
+    InputConflict(Vin),
+
+    /// output {0} is spent by several state transitions.
+    DoubleSpend(Opout),
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, From)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
@@ -133,6 +169,307 @@ impl StrictDumb for TransitionBundle {
     }
 }
 
+/// Displays a summary of the bundle; the alternate format (`{:#}`) lists its
+/// inputs and known transitions, limiting their number.
+impl Display for TransitionBundle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bundle {} closing {} seals: {} inputs, {} known transitions",
+            self.bundle_id(),
+            self.close_method,
+            self.input_map.len(),
+            self.known_transitions.len()
+        )?;
+        if f.alternate() {
+            fmt_list(
+                f,
+                self.input_map
+                    .iter()
+                    .map(|(vin, opid)| format!("input {vin} spent by {opid}")),
+            )?;
+            fmt_list(f, self.known_transitions.values())?;
+        }
+        Ok(())
+    }
+}
+
+/// Compact summary of the state transitions committed by a signing digest,
+/// short enough to be displayed and confirmed on a hardware wallet screen.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SigningSummary {
+    /// Digest committed on-chain (see [`TransitionBundle::signing_digest`] and
+    /// [`Transition::signing_digest`]).
+    pub digest: Bytes32,
+    /// Contract (asset) which state is transferred.
+    pub contract_id: ContractId,
+    /// Number of the summarized state transitions.
+    pub transitions: u16,
+    /// Sum of the revealed fungible amounts assigned by the transitions.
+    pub amount: u64,
+    /// Number of the fungible assignments which amounts are concealed and
+    /// not included into [`SigningSummary::amount`].
+    pub concealed_amounts: u32,
+    /// Total number of assignments (destinations) of the transitions.
+    pub destinations: u32,
+}
+
+impl Display for SigningSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} to {} destinations", self.amount, self.contract_id, self.destinations)?;
+        if self.concealed_amounts > 0 {
+            write!(f, " (+{} concealed amounts)", self.concealed_amounts)?;
+        }
+        write!(f, " in {} transitions, digest {}", self.transitions, self.digest)
+    }
+}
+
+impl SigningSummary {
+    pub(crate) fn with<'op>(
+        digest: Bytes32,
+        transitions: impl IntoIterator<Item = &'op Transition>,
+    ) -> Self {
+        let mut summary = SigningSummary {
+            digest,
+            contract_id: strict_dumb!(),
+            transitions: 0,
+            amount: 0,
+            concealed_amounts: 0,
+            destinations: 0,
+        };
+        for transition in transitions {
+            summary.contract_id = transition.contract_id;
+            summary.transitions += 1;
+            for assigns in transition.assignments.values() {
+                summary.destinations += assigns.len_u16() as u32;
+                for assign in assigns.as_fungible() {
+                    match assign.as_revealed_state() {
+                        Some(state) => {
+                            summary.amount = summary.amount.saturating_add(state.value.as_u64())
+                        }
+                        None => summary.concealed_amounts += 1,
+                    }
+                }
+            }
+        }
+        summary
+    }
+}
+
 impl TransitionBundle {
+    /// Constructs bundle of several state transitions of the same contract
+    /// closing seals in a single witness transaction, for instance batching
+    /// multiple withdrawals. Each transition is provided together with the
+    /// witness inputs closing its seals; the inputs and the spent outputs must
+    /// not be shared between the transitions.
+    pub fn with_transitions(
+        close_method: CloseMethod,
+        transitions: impl IntoIterator<Item = (Transition, BTreeSet<Vin>)>,
+    ) -> Result<Self, BundleError> {
+        let mut contract_id = None;
+        let mut input_map = BTreeMap::new();
+        let mut known_transitions = BTreeMap::new();
+        let mut spent = BTreeSet::new();
+        for (transition, vins) in transitions {
+            let opid = transition.id();
+            if *contract_id.get_or_insert(transition.contract_id) != transition.contract_id {
+                return Err(BundleError::ContractMismatch(opid, transition.contract_id));
+            }
+            if vins.is_empty() {
+                return Err(BundleError::NoInputs(opid));
+            }
+            for vin in vins {
+                if input_map.insert(vin, opid).is_some() {
+                    return Err(BundleError::InputConflict(vin));
+                }
+            }
+            for input in &transition.inputs {
+                if !spent.insert(input.prev_out) {
+                    return Err(BundleError::DoubleSpend(input.prev_out));
+                }
+            }
+            known_transitions.insert(opid, transition);
+        }
+        if known_transitions.is_empty() {
+            return Err(BundleError::Empty);
+        }
+        Ok(TransitionBundle {
+            close_method,
+            input_map: InputMap(Confined::try_from(input_map).map_err(|_| BundleError::TooLarge)?),
+            known_transitions: Confined::try_from(known_transitions)
+                .map_err(|_| BundleError::TooLarge)?,
+        })
+    }
+
     pub fn bundle_id(&self) -> BundleId { self.commit_id() }
+
+    /// Digest committed on-chain by the witness transaction as the
+    /// multi-protocol commitment message of the contract. Equals to the
+    /// bundle id.
+    pub fn signing_digest(&self) -> Bytes32 { self.bundle_id().into_inner() }
+
+    /// Summary of the bundle transitions committed by
+    /// [`TransitionBundle::signing_digest`].
+    pub fn signing_summary(&self) -> SigningSummary {
+        SigningSummary::with(self.signing_digest(), self.known_transitions.values())
+    }
+
+    /// Computes ids of all known transitions in a batch, caching them inside
+    /// the transitions (see [`Operation::cached_id`]).
+    ///
+    /// Airdrop-style bundles may contain hundreds of transitions, making their
+    /// validation bound by merklization of the transition data. Starting from
+    /// [`PARALLEL_HASHING_THRESHOLD`] transitions the hashing is split between
+    /// the available CPU cores.
+    pub fn precompute_ids(&self) {
+        let transitions = self.known_transitions.values().collect::<Vec<_>>();
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        if threads < 2 || transitions.len() < PARALLEL_HASHING_THRESHOLD {
+            transitions.iter().for_each(|transition| {
+                transition.cached_id();
+            });
+            return;
+        }
+        let chunk_size = transitions.len().div_ceil(threads);
+        thread::scope(|scope| {
+            for chunk in transitions.chunks(chunk_size) {
+                scope.spawn(move || {
+                    chunk.iter().for_each(|transition| {
+                        transition.cached_id();
+                    })
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use commit_verify::Conceal;
+
+    use super::*;
+    use crate::{
+        AssetTag, Assign, AssignmentType, Assignments, BlindingFactor, GraphSeal, Input, Inputs,
+        RevealedValue, TransitionType, TypedAssigns, VoidState, XChain,
+    };
+
+    #[test]
+    fn precompute_ids() {
+        let transitions = (0..PARALLEL_HASHING_THRESHOLD as u16 * 2)
+            .map(|no| {
+                let mut transition = Transition::strict_dumb();
+                transition.transition_type = TransitionType::with(no);
+                (transition.id(), transition)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let bundle = TransitionBundle {
+            close_method: strict_dumb!(),
+            input_map: strict_dumb!(),
+            known_transitions: Confined::try_from(transitions).unwrap(),
+        };
+        bundle.precompute_ids();
+        for (opid, transition) in &bundle.known_transitions {
+            assert_eq!(transition.cached_id(), *opid);
+        }
+    }
+
+    #[test]
+    fn batched_transitions() {
+        let prev_id = OpId::from([0x6c; 32]);
+        let transition = |no: u16| {
+            let mut transition = Transition::strict_dumb();
+            transition.inputs = Inputs::from(small_bset![Input::with(Opout::new(
+                prev_id,
+                AssignmentType::with(0),
+                no
+            ))]);
+            transition
+        };
+
+        let bundle = TransitionBundle::with_transitions(CloseMethod::OpretFirst, [
+            (transition(0), bset![Vin::from_u32(0)]),
+            (transition(1), bset![Vin::from_u32(1), Vin::from_u32(2)]),
+        ])
+        .unwrap();
+        assert_eq!(bundle.known_transitions.len(), 2);
+        assert_eq!(bundle.input_map.len(), 3);
+        assert_eq!(bundle.input_map[&Vin::from_u32(2)], transition(1).id());
+
+        assert_eq!(
+            TransitionBundle::with_transitions(CloseMethod::OpretFirst, [
+                (transition(0), bset![Vin::from_u32(0)]),
+                (transition(1), bset![Vin::from_u32(0)]),
+            ]),
+            Err(BundleError::InputConflict(Vin::from_u32(0)))
+        );
+        let mut double_spend = transition(0);
+        double_spend.transition_type = TransitionType::with(1);
+        assert_eq!(
+            TransitionBundle::with_transitions(CloseMethod::OpretFirst, [
+                (transition(0), bset![Vin::from_u32(0)]),
+                (double_spend, bset![Vin::from_u32(1)]),
+            ]),
+            Err(BundleError::DoubleSpend(Opout::new(prev_id, AssignmentType::with(0), 0)))
+        );
+        let mut foreign = transition(1);
+        foreign.contract_id = ContractId::from([0x01; 32]);
+        assert!(matches!(
+            TransitionBundle::with_transitions(CloseMethod::OpretFirst, [
+                (transition(0), bset![Vin::from_u32(0)]),
+                (foreign, bset![Vin::from_u32(1)]),
+            ]),
+            Err(BundleError::ContractMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn signing_summary() {
+        let tag = AssetTag::from([0x6c; 32]);
+        let seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(CloseMethod::OpretFirst, 0, 0));
+        let assign = |amount: u64| {
+            Assign::revealed(seal, RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, tag))
+        };
+        let mut transfer = Transition::strict_dumb();
+        transfer.contract_id = ContractId::from([0x01; 32]);
+        transfer.assignments = Assignments::from_inner(confined_bmap! {
+            AssignmentType::with(1) => TypedAssigns::Fungible(small_vec![
+                assign(100), assign(20).conceal(), assign(5).conceal_seal()
+            ]),
+            AssignmentType::with(2) => TypedAssigns::Declarative(small_vec![
+                Assign::revealed(seal, VoidState::default())
+            ])
+        });
+        let summary = transfer.signing_summary();
+        assert_eq!(summary.digest, transfer.id().into_inner());
+        assert_eq!(summary.digest, transfer.signing_digest());
+        assert_eq!(summary.contract_id, transfer.contract_id);
+        assert_eq!(summary.amount, 105);
+        assert_eq!(summary.concealed_amounts, 1);
+        assert_eq!(summary.destinations, 4);
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "105 of {} to 4 destinations (+1 concealed amounts) in 1 transitions, digest {}",
+                transfer.contract_id, summary.digest
+            )
+        );
+
+        let mut change = transfer.clone();
+        change.transition_type = TransitionType::with(1);
+        let bundle = TransitionBundle {
+            close_method: CloseMethod::OpretFirst,
+            input_map: strict_dumb!(),
+            known_transitions: confined_bmap! {
+                transfer.id() => transfer.clone(),
+                change.id() => change
+            },
+        };
+        let summary = bundle.signing_summary();
+        assert_eq!(bundle.signing_digest(), bundle.bundle_id().into_inner());
+        assert_eq!(summary.digest, mpc::Message::from(bundle.bundle_id()).into_inner());
+        assert_eq!(summary.transitions, 2);
+        assert_eq!(summary.amount, 210);
+        assert_eq!(summary.concealed_amounts, 2);
+        assert_eq!(summary.destinations, 8);
+    }
 }
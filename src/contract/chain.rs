@@ -0,0 +1,232 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Chain networks which RGB contracts can be issued on and genesis block
+//! trust anchors used to make sure that witness resolvers operate on the same
+//! chain as the contract.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use bp::BlockHash;
+
+use crate::{LIB_NAME_RGB, Layer1};
+
+pub const BITCOIN_MAINNET_GENESIS: &str =
+    "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+pub const BITCOIN_TESTNET_GENESIS: &str =
+    "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943";
+pub const BITCOIN_SIGNET_GENESIS: &str =
+    "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6";
+pub const BITCOIN_REGTEST_GENESIS: &str =
+    "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
+
+/// Chain network the contract is issued on.
+///
+/// The network is the same for all layer 1 used by a contract (see
+/// [`Layer1`] and [`crate::AltLayer1`]), i.e. a contract issued on testnet
+/// may use bitcoin and liquid testnets for its single-use seals.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(lowercase)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[repr(u8)]
+pub enum ChainNet {
+    #[strict_type(dumb)]
+    Mainnet = 0,
+    Testnet = 1,
+    Signet = 2,
+    Regtest = 3,
+    /// Private chain which genesis block hash must be registered with
+    /// [`ChainTrustAnchors::register`] before it can be used.
+    Custom = 0xFF,
+}
+
+impl ChainNet {
+    pub fn is_mainnet(self) -> bool { self == ChainNet::Mainnet }
+
+    pub fn is_testnet(self) -> bool { !self.is_mainnet() }
+
+    /// Returns well-known genesis block hash for a given layer 1, if any.
+    pub fn genesis_hash(self, layer1: Layer1) -> Option<BlockHash> {
+        let hash = match (layer1, self) {
+            (Layer1::Bitcoin, ChainNet::Mainnet) => BITCOIN_MAINNET_GENESIS,
+            (Layer1::Bitcoin, ChainNet::Testnet) => BITCOIN_TESTNET_GENESIS,
+            (Layer1::Bitcoin, ChainNet::Signet) => BITCOIN_SIGNET_GENESIS,
+            (Layer1::Bitcoin, ChainNet::Regtest) => BITCOIN_REGTEST_GENESIS,
+            _ => return None,
+        };
+        Some(BlockHash::from_str(hash).expect("hardcoded genesis hash"))
+    }
+}
+
+/// Errors checking chain genesis block against the set of trust anchors.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+pub enum ChainError {
+    /// {0} {1} has no registered genesis trust anchors.
+    NoAnchors(Layer1, ChainNet),
+
+    /// genesis block {2} is not a trust anchor for {0} {1}.
+    GenesisMismatch(Layer1, ChainNet, BlockHash),
+}
+
+/// Set of chain genesis block hashes trusted for each pair of layer 1 and
+/// chain network.
+///
+/// Default value contains well-known genesis blocks for bitcoin networks.
+/// Integration tests and CI environments running private chains may register
+/// their own genesis hashes for [`ChainNet::Regtest`] or [`ChainNet::Custom`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChainTrustAnchors(BTreeMap<(Layer1, ChainNet), BTreeSet<BlockHash>>);
+
+impl Default for ChainTrustAnchors {
+    fn default() -> Self {
+        let mut anchors = ChainTrustAnchors(empty!());
+        for chain_net in [ChainNet::Mainnet, ChainNet::Testnet, ChainNet::Signet, ChainNet::Regtest]
+        {
+            let hash = chain_net
+                .genesis_hash(Layer1::Bitcoin)
+                .expect("bitcoin networks have known genesis");
+            anchors.register(Layer1::Bitcoin, chain_net, hash);
+        }
+        anchors
+    }
+}
+
+impl ChainTrustAnchors {
+    /// Constructs trust anchors which do not contain any genesis hashes.
+    pub fn empty() -> Self { ChainTrustAnchors(empty!()) }
+
+    /// Registers genesis block hash as a trust anchor for a chain. Returns
+    /// `false` if the hash was already registered.
+    pub fn register(&mut self, layer1: Layer1, chain_net: ChainNet, genesis: BlockHash) -> bool {
+        self.0
+            .entry((layer1, chain_net))
+            .or_default()
+            .insert(genesis)
+    }
+
+    /// Registers genesis block hash of a custom regtest chain.
+    pub fn register_regtest(&mut self, layer1: Layer1, genesis: BlockHash) -> bool {
+        self.register(layer1, ChainNet::Regtest, genesis)
+    }
+
+    /// Returns iterator over trusted genesis hashes for a chain.
+    pub fn genesis_hashes(
+        &self,
+        layer1: Layer1,
+        chain_net: ChainNet,
+    ) -> impl Iterator<Item = BlockHash> + '_ {
+        self.0
+            .get(&(layer1, chain_net))
+            .into_iter()
+            .flat_map(|set| set.iter().copied())
+    }
+
+    /// Detects chain network by its genesis block hash.
+    pub fn detect(&self, layer1: Layer1, genesis: BlockHash) -> Option<ChainNet> {
+        self.0
+            .iter()
+            .find(|((l1, _), set)| *l1 == layer1 && set.contains(&genesis))
+            .map(|((_, chain_net), _)| *chain_net)
+    }
+
+    /// Checks that the genesis block hash is a trust anchor for the chain.
+    pub fn verify(
+        &self,
+        layer1: Layer1,
+        chain_net: ChainNet,
+        genesis: BlockHash,
+    ) -> Result<(), ChainError> {
+        let set = self
+            .0
+            .get(&(layer1, chain_net))
+            .ok_or(ChainError::NoAnchors(layer1, chain_net))?;
+        if !set.contains(&genesis) {
+            return Err(ChainError::GenesisMismatch(layer1, chain_net, genesis));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_anchors() {
+        let anchors = ChainTrustAnchors::default();
+        let mainnet = BlockHash::from_str(BITCOIN_MAINNET_GENESIS).unwrap();
+        let regtest = BlockHash::from_str(BITCOIN_REGTEST_GENESIS).unwrap();
+        assert_eq!(anchors.detect(Layer1::Bitcoin, mainnet), Some(ChainNet::Mainnet));
+        assert_eq!(anchors.detect(Layer1::Bitcoin, regtest), Some(ChainNet::Regtest));
+        assert_eq!(anchors.detect(Layer1::Liquid, mainnet), None);
+        assert!(
+            anchors
+                .verify(Layer1::Bitcoin, ChainNet::Mainnet, mainnet)
+                .is_ok()
+        );
+        assert_eq!(
+            anchors.verify(Layer1::Bitcoin, ChainNet::Testnet, mainnet),
+            Err(ChainError::GenesisMismatch(Layer1::Bitcoin, ChainNet::Testnet, mainnet))
+        );
+        assert_eq!(
+            anchors.verify(Layer1::Bitcoin, ChainNet::Custom, mainnet),
+            Err(ChainError::NoAnchors(Layer1::Bitcoin, ChainNet::Custom))
+        );
+    }
+
+    #[test]
+    fn custom_regtest() {
+        let mut anchors = ChainTrustAnchors::default();
+        let custom = BlockHash::from([0xAA; 32]);
+        assert!(
+            anchors
+                .verify(Layer1::Bitcoin, ChainNet::Regtest, custom)
+                .is_err()
+        );
+        assert!(anchors.register_regtest(Layer1::Bitcoin, custom));
+        assert!(!anchors.register_regtest(Layer1::Bitcoin, custom));
+        assert!(
+            anchors
+                .verify(Layer1::Bitcoin, ChainNet::Regtest, custom)
+                .is_ok()
+        );
+        assert_eq!(
+            anchors
+                .genesis_hashes(Layer1::Bitcoin, ChainNet::Regtest)
+                .count(),
+            2
+        );
+    }
+}
@@ -37,11 +37,11 @@ use commit_verify::{
 use strict_encoding::StrictDumb;
 
 use crate::{
-    impl_serde_baid64, Assign, AssignmentType, Assignments, BundleId, ConcealedAttach,
+    Assign, AssignmentType, Assignments, AssignsVisitor, BundleId, ChainNet, ConcealedAttach,
     ConcealedData, ConcealedState, ConfidentialState, DataState, ExposedSeal, ExposedState,
-    Extension, ExtensionType, Ffv, Genesis, GlobalState, GlobalStateType, Operation,
-    PedersenCommitment, Redeemed, SchemaId, SecretSeal, Transition, TransitionBundle,
-    TransitionType, TypedAssigns, XChain, LIB_NAME_RGB,
+    Extension, ExtensionType, Ffv, Genesis, GlobalState, GlobalStateType, LIB_NAME_RGB, MetaType,
+    MetaValue, Metadata, Operation, PedersenCommitment, Redeemed, SchemaId, SecretSeal, Transition,
+    TransitionBundle, TransitionType, XChain, impl_serde_baid64,
 };
 
 /// Unique contract identifier equivalent to the contract genesis commitment
@@ -68,12 +68,15 @@ impl ContractId {
     }
 }
 
+/// Contract ids are displayed with a mnemonic checksum suffix, which can be
+/// omitted with the alternate formatting flag (`{:#}`). When parsing, the
+/// mnemonic is optional, but if present it must match the id.
 impl DisplayBaid64 for ContractId {
     const HRI: &'static str = "rgb";
     const CHUNKING: bool = true;
     const PREFIX: bool = true;
     const EMBED_CHECKSUM: bool = false;
-    const MNEMONIC: bool = false;
+    const MNEMONIC: bool = true;
     fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
 }
 impl FromBaid64Str for ContractId {}
@@ -95,11 +98,38 @@ impl From<ContractId> for mpc::ProtocolId {
 
 impl_serde_baid64!(ContractId);
 
+/// Contract id prefixed with the chain network the contract is issued on,
+/// which is the canonical form for presenting contracts to users.
+///
+/// Since genesis commits to the chain network, the same genesis content
+/// issued on different networks produces different contract ids; the prefix
+/// makes this distinction visible.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{chain_net}:{contract_id}")]
+pub struct ChainContractId {
+    pub chain_net: ChainNet,
+    pub contract_id: ContractId,
+}
+
+impl Genesis {
+    /// Returns contract id together with the chain network the contract is
+    /// issued on.
+    pub fn chain_contract_id(&self) -> ChainContractId {
+        ChainContractId {
+            chain_net: self.chain_net,
+            contract_id: self.contract_id(),
+        }
+    }
+}
+
 /// Unique operation (genesis, extensions & state transition) identifier
-/// equivalent to the commitment hash
-#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+/// equivalent to the commitment hash.
+///
+/// Operation ids are displayed in hex, since they are a part of other
+/// identifiers like [`crate::Opout`]. The alternate formatting flag (`{:#}`)
+/// adds a mnemonic checksum suffix, which is verified when parsing.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
-#[display(Self::to_hex)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
 #[cfg_attr(
@@ -121,9 +151,47 @@ impl CommitmentId for OpId {
     const TAG: &'static str = "urn:lnp-bp:rgb:operation#2024-02-03";
 }
 
+/// Errors parsing operation id.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum OpIdParseError {
+    /// invalid operation id hex representation - {0}
+    #[from]
+    Hex(hex::Error),
+
+    /// mnemonic '{1}' doesn't match operation id {0}.
+    InvalidMnemonic(OpId, String),
+}
+
+impl DisplayBaid64 for OpId {
+    const HRI: &'static str = "rgb:op";
+    const CHUNKING: bool = false;
+    const PREFIX: bool = false;
+    const EMBED_CHECKSUM: bool = false;
+    const MNEMONIC: bool = false;
+    fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
+}
+
 impl FromStr for OpId {
-    type Err = hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+    type Err = OpIdParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hex, mnemonic) = s.split_once('#').unwrap_or((s, ""));
+        let id = Self::from_hex(hex)?;
+        if !mnemonic.is_empty() && mnemonic != id.to_baid64_mnemonic() {
+            return Err(OpIdParseError::InvalidMnemonic(id, mnemonic.to_owned()));
+        }
+        Ok(id)
+    }
+}
+
+impl Display for OpId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())?;
+        if f.alternate() {
+            write!(f, "#{}", self.to_baid64_mnemonic())?;
+        }
+        Ok(())
+    }
 }
 
 impl OpId {
@@ -236,7 +304,7 @@ pub struct BaseCommitment {
     pub schema_id: SchemaId,
     pub timestamp: i64,
     pub issuer: StrictHash,
-    pub testnet: bool,
+    pub chain_net: ChainNet,
     pub alt_layers1: StrictHash,
     pub asset_tags: StrictHash,
 }
@@ -263,7 +331,9 @@ pub enum TypeCommitment {
 pub struct OpCommitment {
     pub ffv: Ffv,
     pub op_type: TypeCommitment,
-    pub metadata: StrictHash,
+    /// Commitment to the operation metadata, which depends on the operation
+    /// version (see [`Metadata::commitment`]).
+    pub metadata: MerkleHash,
     pub globals: MerkleHash,
     pub inputs: MerkleHash,
     pub assignments: MerkleHash,
@@ -279,7 +349,7 @@ impl Genesis {
             flags: self.flags,
             schema_id: self.schema_id,
             timestamp: self.timestamp,
-            testnet: self.testnet,
+            chain_net: self.chain_net,
             alt_layers1: self.alt_layers1.commit_id(),
             issuer: self.issuer.commit_id(),
             asset_tags: self.asset_tags.commit_id(),
@@ -287,7 +357,7 @@ impl Genesis {
         OpCommitment {
             ffv: self.ffv,
             op_type: TypeCommitment::Genesis(base),
-            metadata: self.metadata.commit_id(),
+            metadata: metadata_commitment(self.ffv, &self.metadata),
             globals: MerkleHash::merklize(&self.globals),
             inputs: MerkleHash::void(0, u256::ZERO),
             assignments: MerkleHash::merklize(&self.assignments),
@@ -306,7 +376,7 @@ impl Transition {
         OpCommitment {
             ffv: self.ffv,
             op_type: TypeCommitment::Transition(self.contract_id, self.transition_type),
-            metadata: self.metadata.commit_id(),
+            metadata: metadata_commitment(self.ffv, &self.metadata),
             globals: MerkleHash::merklize(&self.globals),
             inputs: MerkleHash::merklize(&self.inputs),
             assignments: MerkleHash::merklize(&self.assignments),
@@ -323,7 +393,7 @@ impl Extension {
         OpCommitment {
             ffv: self.ffv,
             op_type: TypeCommitment::Extension(self.contract_id, self.extension_type),
-            metadata: self.metadata.commit_id(),
+            metadata: metadata_commitment(self.ffv, &self.metadata),
             globals: MerkleHash::merklize(&self.globals),
             inputs: MerkleHash::void(0, u256::ZERO),
             assignments: MerkleHash::merklize(&self.assignments),
@@ -386,28 +456,22 @@ impl<Seal: ExposedSeal> MerkleLeaves for Assignments<Seal> {
 
     fn merkle_leaves(&self) -> Self::LeafIter<'_> {
         self.iter()
-            .flat_map(|(ty, a)| {
-                match a {
-                    TypedAssigns::Declarative(list) => {
-                        list.iter().map(|a| a.commitment(*ty)).collect::<Vec<_>>()
-                    }
-                    TypedAssigns::Fungible(list) => {
-                        list.iter().map(|a| a.commitment(*ty)).collect()
-                    }
-                    TypedAssigns::Structured(list) => {
-                        list.iter().map(|a| a.commitment(*ty)).collect()
-                    }
-                    TypedAssigns::Attachment(list) => {
-                        list.iter().map(|a| a.commitment(*ty)).collect()
-                    }
-                }
-                .into_iter()
-            })
+            .flat_map(|(ty, a)| a.visit(AssignmentLeaves(*ty)))
             .collect::<Vec<_>>()
             .into_iter()
     }
 }
 
+struct AssignmentLeaves(AssignmentType);
+
+impl<Seal: ExposedSeal> AssignsVisitor<Seal> for AssignmentLeaves {
+    type Output = Vec<AssignmentCommitment>;
+
+    fn visit<State: ExposedState>(self, assigns: &[Assign<State, Seal>]) -> Self::Output {
+        assigns.iter().map(|a| a.commitment(self.0)).collect()
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub struct GlobalCommitment {
     pub ty: GlobalStateType,
@@ -440,3 +504,47 @@ impl MerkleLeaves for GlobalState {
             .into_iter()
     }
 }
+
+/// Commits to the operation metadata according to the operation version:
+/// [`Ffv::V0`] operations commit to the strict hash of the metadata, keeping
+/// ids of the operations created by earlier releases; since [`Ffv::V1`] the
+/// metadata are merklized, such that individual fields can be proven with
+/// [`Metadata::prove_field`].
+pub fn metadata_commitment(ffv: Ffv, metadata: &Metadata) -> MerkleHash {
+    if ffv < Ffv::V1 {
+        MerkleHash::from_byte_array(metadata.commit_id().to_byte_array())
+    } else {
+        metadata.commitment()
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MetaCommitment {
+    pub ty: MetaType,
+    pub value: MetaValue,
+}
+
+impl CommitEncode for MetaCommitment {
+    type CommitmentId = MerkleHash;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        e.commit_to_serialized(&self.ty);
+        e.commit_to_serialized(&self.value);
+        e.set_finished();
+    }
+}
+
+impl MerkleLeaves for Metadata {
+    type Leaf = MetaCommitment;
+    type LeafIter<'tmp> = vec::IntoIter<MetaCommitment>;
+
+    fn merkle_leaves(&self) -> Self::LeafIter<'_> {
+        self.iter()
+            .map(|(ty, value)| MetaCommitment {
+                ty: *ty,
+                value: value.clone(),
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
@@ -23,21 +23,28 @@
 //! Extraction of contract state.
 
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::num::ParseIntError;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
+use std::sync::Arc;
 
-use amplify::confinement::{LargeOrdMap, LargeOrdSet, SmallVec, TinyOrdMap};
-use amplify::hex;
-use strict_encoding::{StrictDecode, StrictDumb, StrictEncode};
+use amplify::Wrapper;
+use amplify::confinement::{LargeOrdMap, LargeOrdSet, SmallVec, TinyOrdMap, U16};
+use bp::Outpoint;
+use strict_encoding::{StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize};
 
+#[cfg(feature = "std-helpers")]
+use crate::Reachability;
+use crate::validation::{ResolveWitness, WitnessResolverError};
 use crate::{
     Assign, AssignmentType, Assignments, AssignmentsRef, ContractId, DataState, ExposedSeal,
-    ExposedState, Extension, Genesis, GlobalStateType, OpId, Operation, RevealedAttach,
-    RevealedData, RevealedValue, Schema, SchemaId, Transition, TypedAssigns, VoidState,
-    WitnessAnchor, XChain, XOutputSeal, XWitnessId, LIB_NAME_RGB,
+    ExposedState, Extension, Genesis, GlobalStateType, Inputs, LIB_NAME_RGB, LangTag,
+    LocalizedText, OpId, OpIdParseError, Operation, RevealedAttach, RevealedData, RevealedValue,
+    RicardianHash, Schema, SchemaFlags, SchemaId, SealResolutionError, Transition, TypedAssigns,
+    VoidState, WitnessAnchor, WitnessOrd, XChain, XOutpoint, XOutputSeal, XWitnessId,
 };
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
@@ -65,7 +72,7 @@ impl Opout {
 #[display(inner)]
 pub enum OpoutParseError {
     #[from]
-    InvalidNodeId(hex::Error),
+    InvalidNodeId(OpIdParseError),
 
     InvalidType(ParseIntError),
 
@@ -178,6 +185,24 @@ impl<State: KnownState> Ord for OutputAssignment<State> {
 }
 
 impl<State: KnownState> OutputAssignment<State> {
+    /// Constructs assignment with the seal resolved against the witness
+    /// transaction of the operation defining it (see [`XChain::resolve`]).
+    pub fn resolve<Seal: ExposedSeal>(
+        seal: XChain<Seal>,
+        witness_id: Option<XWitnessId>,
+        state: State,
+        opid: OpId,
+        ty: AssignmentType,
+        no: u16,
+    ) -> Result<Self, SealResolutionError> {
+        Ok(OutputAssignment {
+            opout: Opout::new(opid, ty, no),
+            seal: seal.resolve(witness_id)?,
+            state,
+            witness: witness_id.into(),
+        })
+    }
+
     /// # Panics
     ///
     /// If the processing is done on invalid stash data, the seal is
@@ -190,15 +215,10 @@ impl<State: KnownState> OutputAssignment<State> {
         ty: AssignmentType,
         no: u16,
     ) -> Self {
-        OutputAssignment {
-            opout: Opout::new(opid, ty, no),
-            seal: seal.try_to_output_seal(witness_id).expect(
-                "processing contract from unverified/invalid stash: witness seal chain doesn't \
-                 match anchor's chain",
-            ),
-            state,
-            witness: witness_id.into(),
-        }
+        Self::resolve(seal, Some(witness_id), state, opid, ty, no).expect(
+            "processing contract from unverified/invalid stash: witness seal chain doesn't match \
+             anchor's chain",
+        )
     }
 
     /// # Panics
@@ -212,19 +232,14 @@ impl<State: KnownState> OutputAssignment<State> {
         ty: AssignmentType,
         no: u16,
     ) -> Self {
-        OutputAssignment {
-            opout: Opout::new(opid, ty, no),
-            seal: seal.to_output_seal().expect(
-                "processing contract from unverified/invalid stash: seal must have txid \
-                 information since it comes from genesis or extension",
-            ),
-            state,
-            witness: AssignmentWitness::Absent,
-        }
+        Self::resolve(seal, None, state, opid, ty, no).expect(
+            "processing contract from unverified/invalid stash: seal must have txid information \
+             since it comes from genesis or extension",
+        )
     }
 
-    pub fn transmute<S: KnownState>(self) -> OutputAssignment<S>
-    where S: From<State> {
+    pub fn transmute<S>(self) -> OutputAssignment<S>
+    where S: KnownState + From<State> {
         OutputAssignment {
             opout: self.opout,
             seal: self.seal,
@@ -244,6 +259,9 @@ impl<State: KnownState> OutputAssignment<State> {
 )]
 pub struct GlobalOrd {
     pub witness_anchor: Option<WitnessAnchor>,
+    /// Operation defining the state, which distinguishes state of the
+    /// operations sharing the same witness.
+    pub opid: OpId,
     pub idx: u16,
 }
 
@@ -257,25 +275,29 @@ impl Ord for GlobalOrd {
             return Ordering::Equal;
         }
         match (self.witness_anchor, &other.witness_anchor) {
-            (None, None) => self.idx.cmp(&other.idx),
+            (None, None) => (self.opid, self.idx).cmp(&(other.opid, other.idx)),
             (None, Some(_)) => Ordering::Less,
             (Some(_), None) => Ordering::Greater,
-            (Some(ord1), Some(ord2)) if ord1 == *ord2 => self.idx.cmp(&other.idx),
+            (Some(ord1), Some(ord2)) if ord1 == *ord2 => {
+                (self.opid, self.idx).cmp(&(other.opid, other.idx))
+            }
             (Some(ord1), Some(ord2)) => ord1.cmp(ord2),
         }
     }
 }
 
 impl GlobalOrd {
-    pub fn with_anchor(ord_txid: WitnessAnchor, idx: u16) -> Self {
+    pub fn with_anchor(ord_txid: WitnessAnchor, opid: OpId, idx: u16) -> Self {
         GlobalOrd {
             witness_anchor: Some(ord_txid),
+            opid,
             idx,
         }
     }
-    pub fn genesis(idx: u16) -> Self {
+    pub fn genesis(opid: OpId, idx: u16) -> Self {
         GlobalOrd {
             witness_anchor: None,
+            opid,
             idx,
         }
     }
@@ -306,8 +328,24 @@ pub struct ContractHistory {
     fungibles: LargeOrdSet<OutputAssignment<RevealedValue>>,
     data: LargeOrdSet<OutputAssignment<RevealedData>>,
     attach: LargeOrdSet<OutputAssignment<RevealedAttach>>,
+    /// Inputs of the state transitions added to the history.
+    inputs: LargeOrdMap<OpId, Inputs>,
+    /// Amounts of all fungible assignments with revealed state made by the
+    /// operations added to the history, including the ones with concealed
+    /// seals.
+    amounts: LargeOrdMap<Opout, u64>,
+    /// Fungible assignments with concealed state made by the operations added
+    /// to the history.
+    concealed: LargeOrdSet<Opout>,
+    /// Witness anchors of the operations added to the history, with the
+    /// witness transaction statuses as they were known at the moment of
+    /// adding.
+    witnesses: LargeOrdMap<OpId, WitnessAnchor>,
 }
 
+impl StrictSerialize for ContractHistory {}
+impl StrictDeserialize for ContractHistory {}
+
 impl ContractHistory {
     /// # Panics
     ///
@@ -322,6 +360,10 @@ impl ContractHistory {
             fungibles: empty!(),
             data: empty!(),
             attach: empty!(),
+            inputs: empty!(),
+            amounts: empty!(),
+            concealed: empty!(),
+            witnesses: empty!(),
         };
         state.update_genesis(genesis);
         state
@@ -349,6 +391,20 @@ impl ContractHistory {
         self.add_operation(extension, Some(witness_anchor));
     }
 
+    /// Builds index of ancestor-descendant relations between the state
+    /// transitions of the history and the operations they spend.
+    #[cfg(feature = "std-helpers")]
+    pub fn reachability(&self) -> Reachability {
+        Reachability::with(self.inputs.iter().map(|(opid, inputs)| (*opid, inputs)))
+    }
+
+    #[cfg(feature = "std-helpers")]
+    pub(super) fn global_state(
+        &self,
+    ) -> &TinyOrdMap<GlobalStateType, LargeOrdMap<GlobalOrd, DataState>> {
+        &self.global
+    }
+
     fn add_operation(&mut self, op: &impl Operation, witness_anchor: Option<WitnessAnchor>) {
         let opid = op.id();
 
@@ -368,6 +424,7 @@ impl ContractHistory {
                 let idx = idx as u16;
                 let glob_idx = GlobalOrd {
                     witness_anchor,
+                    opid,
                     idx,
                 };
                 map.insert(glob_idx, s.clone())
@@ -408,6 +465,19 @@ impl ContractHistory {
         }
          */
 
+        let inputs = op.inputs();
+        if !inputs.is_empty() {
+            self.inputs
+                .insert(opid, inputs)
+                .expect("contract history exceeded 2^32 operations, which is unrealistic");
+        }
+
+        if let Some(anchor) = witness_anchor {
+            self.witnesses
+                .insert(opid, anchor)
+                .expect("contract history exceeded 2^32 operations, which is unrealistic");
+        }
+
         let witness_id = witness_anchor.map(|wa| wa.witness_id);
         match op.assignments() {
             AssignmentsRef::Genesis(assignments) => {
@@ -437,12 +507,11 @@ impl ContractHistory {
                 .enumerate()
                 .filter_map(|(n, a)| a.to_revealed().map(|(seal, state)| (n, seal, state)))
             {
-                let assigned_state = match witness_id {
-                    Some(witness_id) => {
-                        OutputAssignment::with_witness(seal, witness_id, state, opid, ty, no as u16)
-                    }
-                    None => OutputAssignment::with_no_witness(seal, state, opid, ty, no as u16),
-                };
+                let assigned_state =
+                    OutputAssignment::resolve(seal, witness_id, state, opid, ty, no as u16)
+                        .unwrap_or_else(|err| {
+                            panic!("processing contract from unverified/invalid stash: {err}")
+                        });
                 contract_state
                     .push(assigned_state)
                     .expect("contract state exceeded 2^32 items, which is unrealistic");
@@ -450,6 +519,14 @@ impl ContractHistory {
         }
 
         for (ty, assignments) in assignments.iter() {
+            for (no, assign) in assignments.as_fungible().iter().enumerate() {
+                let opout = Opout::new(opid, *ty, no as u16);
+                match assign.as_revealed_state() {
+                    Some(state) => self.amounts.insert(opout, state.value.as_u64()).map(|_| ()),
+                    None => self.concealed.push(opout),
+                }
+                .expect("contract state exceeded 2^32 items, which is unrealistic");
+            }
             match assignments {
                 TypedAssigns::Declarative(assignments) => {
                     process(&mut self.rights, assignments, opid, *ty, witness_id)
@@ -468,6 +545,48 @@ impl ContractHistory {
     }
 }
 
+/// Unconfirmed transaction spending a seal which backs the current owned state
+/// of a contract, reported by [`ContractState::watch_conflicts`].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display("{opout} spent by {witness_id}")]
+pub struct SealConflict {
+    /// Output of the operation which assigned the state.
+    pub opout: Opout,
+    /// Seal defined by the assignment.
+    pub seal: XOutputSeal,
+    /// Id of the mempool transaction spending the seal.
+    pub witness_id: XWitnessId,
+}
+
+/// Owned state of a contract available for spending, reported by
+/// [`ContractState::spendable_outputs`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{opout} at {seal}")]
+pub struct SpendableOutput {
+    /// Output of the operation which assigned the state.
+    pub opout: Opout,
+    /// Seal defined by the assignment.
+    pub seal: XOutputSeal,
+    /// Status of the witness transaction of the operation which assigned the
+    /// state, as it was known at the moment of adding the operation to the
+    /// history. `None` for the state assigned by the genesis and extensions.
+    pub witness_ord: Option<WitnessOrd>,
+    /// Amount of fungible state; `None` for other state types.
+    pub value: Option<u64>,
+}
+
+impl SpendableOutput {
+    /// Returns number of confirmations of the witness transaction given the
+    /// height of the chain tip, or `None` if the state was assigned without a
+    /// witness transaction.
+    pub fn confirmations(&self, tip_height: u32) -> Option<u32> {
+        Some(match self.witness_ord? {
+            WitnessOrd::OnChain(pos) => (tip_height + 1).saturating_sub(pos.height().get()),
+            WitnessOrd::OffChain => 0,
+        })
+    }
+}
+
 /// Contract state provides API to read consensus-valid data from the
 /// [`ContractHistory`].
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -508,4 +627,570 @@ impl ContractState {
         let iter = state.values().take(schema.max_items as usize);
         SmallVec::try_from_iter(iter).expect("same size as previous confined collection")
     }
+
+    /// Returns the most recent localized text of a given global state type, if
+    /// the schema declares the type as holding localized texts.
+    pub fn localized(&self, state_type: GlobalStateType) -> Option<LocalizedText> {
+        if !self.schema.global_types.get(&state_type)?.is_localized() {
+            return None;
+        }
+        let data = self.global.get(&state_type)?.values().next_back()?;
+        LocalizedText::from_strict_serialized::<U16>(data.to_inner()).ok()
+    }
+
+    /// Returns translation of the most recent localized text of a given global
+    /// state type, selected by the language tag (see
+    /// [`LocalizedText::select`]).
+    pub fn localized_text(&self, state_type: GlobalStateType, tag: &LangTag) -> Option<String> {
+        self.localized(state_type)?.select(tag).map(str::to_owned)
+    }
+
+    /// Drops records of the owned state allocations spent by operations which
+    /// witness transactions were mined below `spent_before_height`, and
+    /// witness anchors of the operations which no longer own or spend any of
+    /// the allocations.
+    ///
+    /// The height defines retention: allocations spent by recent operations,
+    /// as well as by operations which witnesses were unmined or off-chain at
+    /// the moment of adding them to the history, are kept to survive
+    /// re-orgs. Operation inputs, amounts and global state are always kept,
+    /// since they are required to validate incoming consignments and to
+    /// account the supply.
+    ///
+    /// Returns number of the removed allocation records.
+    pub fn gc(&mut self, spent_before_height: u32) -> usize {
+        let spenders = self
+            .witnesses
+            .iter()
+            .filter(|(_, anchor)| match anchor.witness_ord {
+                WitnessOrd::OnChain(pos) => pos.height().get() < spent_before_height,
+                WitnessOrd::OffChain => false,
+            })
+            .map(|(opid, _)| *opid)
+            .filter(|opid| self.history.inputs.contains_key(opid))
+            .collect::<BTreeSet<_>>();
+        let spent = spenders
+            .iter()
+            .flat_map(|opid| &self.history.inputs[opid])
+            .map(|input| input.prev_out)
+            .collect::<BTreeSet<_>>();
+
+        fn prune<State: KnownState>(
+            set: &mut LargeOrdSet<OutputAssignment<State>>,
+            spent: &BTreeSet<Opout>,
+        ) -> usize {
+            let pruned = set
+                .iter()
+                .filter(|a| spent.contains(&a.opout))
+                .cloned()
+                .collect::<Vec<_>>();
+            for a in &pruned {
+                set.remove(a).expect("collection allows zero elements");
+            }
+            pruned.len()
+        }
+
+        let history = &mut self.history;
+        let count = prune(&mut history.rights, &spent) +
+            prune(&mut history.fungibles, &spent) +
+            prune(&mut history.data, &spent) +
+            prune(&mut history.attach, &spent);
+
+        let owners = history
+            .rights
+            .iter()
+            .map(|a| a.opout.op)
+            .chain(history.fungibles.iter().map(|a| a.opout.op))
+            .chain(history.data.iter().map(|a| a.opout.op))
+            .chain(history.attach.iter().map(|a| a.opout.op))
+            .collect::<BTreeSet<_>>();
+        for opid in spenders.difference(&owners) {
+            history
+                .witnesses
+                .remove(opid)
+                .expect("collection allows zero elements");
+        }
+
+        count
+    }
+
+    /// Returns hash of the Ricardian contract text the contract currently
+    /// commits to, if the schema declares the Ricardian contract global state.
+    /// If the contract text was updated by a later operation, returns the most
+    /// recent hash.
+    pub fn ricardian(&self) -> Option<RicardianHash> {
+        let ricardian = self.schema.ricardian()?;
+        self.global
+            .get(&ricardian.global_type)?
+            .values()
+            .next_back()
+            .and_then(|state| RicardianHash::try_from(state).ok())
+    }
+
+    /// Returns value of a numeric accumulator (see
+    /// [`crate::GlobalStateKind::Accumulator`]): the initial value set by
+    /// genesis together with the deltas of all operations added to the
+    /// history.
+    ///
+    /// Returns `None` if the schema doesn't declare the state type as an
+    /// accumulator, or if the value doesn't fit into 64-bit unsigned integer.
+    pub fn accumulator(&self, state_type: GlobalStateType) -> Option<u64> {
+        if !self.schema.global_types.get(&state_type)?.is_accumulator() {
+            return None;
+        }
+        let Some(state) = self.global.get(&state_type) else {
+            return Some(0);
+        };
+        let mut value = 0i128;
+        for (ord, data) in state {
+            let bytes = <[u8; 8]>::try_from(data.as_slice()).ok()?;
+            value += match ord.witness_anchor {
+                None => u64::from_le_bytes(bytes) as i128,
+                Some(_) => i64::from_le_bytes(bytes) as i128,
+            };
+        }
+        u64::try_from(value).ok()
+    }
+
+    /// Returns the current issuer authority of a contract with
+    /// [`crate::SchemaFlags::ISSUER_AUTHORITY`] flag set: the authority
+    /// assignment which is not spent by any of the state transitions added to
+    /// the history.
+    ///
+    /// Returns `None` if the schema has no issuer authority, no authority was
+    /// assigned, or the history is missing some of the rotations, so there are
+    /// several candidates.
+    pub fn authority(&self) -> Option<&OutputAssignment<RevealedData>> {
+        if !self.schema.flags.contains(SchemaFlags::ISSUER_AUTHORITY) {
+            return None;
+        }
+        let spent = self.spent();
+        let mut current = self
+            .history
+            .data
+            .iter()
+            .filter(|a| a.opout.ty.is_issuer_authority() && !spent.contains(&a.opout));
+        let authority = current.next()?;
+        current.next().is_none().then_some(authority)
+    }
+
+    /// Checks whether any of the provided mempool transactions spends a seal
+    /// backing the current (i.e. not spent by any of the known state
+    /// transitions) owned state of the contract, signalling that someone is
+    /// moving the assets before the expected transfer gets confirmed.
+    ///
+    /// Transaction ids may be obtained with
+    /// [`ResolveWitness::resolve_mempool`]; the transactions themselves are
+    /// retrieved from the `resolver`. Transactions unknown to the resolver
+    /// (for instance, evicted from the mempool in the meantime) are
+    /// ignored.
+    pub fn watch_conflicts(
+        &self,
+        mempool_txids: impl IntoIterator<Item = XWitnessId>,
+        resolver: &impl ResolveWitness,
+    ) -> Result<BTreeSet<SealConflict>, WitnessResolverError> {
+        let spent = self.spent();
+        let mut owned = BTreeMap::<XOutpoint, Vec<(Opout, XOutputSeal)>>::new();
+        let rights = self.rights.iter().map(|a| (a.opout, a.seal));
+        let fungibles = self.fungibles.iter().map(|a| (a.opout, a.seal));
+        let data = self.data.iter().map(|a| (a.opout, a.seal));
+        let attach = self.attach.iter().map(|a| (a.opout, a.seal));
+        for (opout, seal) in rights.chain(fungibles).chain(data).chain(attach) {
+            if !spent.contains(&opout) {
+                owned
+                    .entry(seal.to_outpoint())
+                    .or_default()
+                    .push((opout, seal));
+            }
+        }
+
+        let mut conflicts = bset![];
+        for witness_id in mempool_txids {
+            let tx = match resolver.resolve_pub_witness(witness_id) {
+                Ok(tx) => tx,
+                Err(WitnessResolverError::Unknown(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            let layer1 = tx.layer1();
+            for input in &tx.as_reduced_unsafe().inputs {
+                let outpoint = XChain::<Outpoint>::with(layer1, input.prev_output);
+                let outpoint = XOutpoint::from(outpoint);
+                for (opout, seal) in owned.get(&outpoint).into_iter().flatten() {
+                    conflicts.insert(SealConflict {
+                        opout: *opout,
+                        seal: *seal,
+                        witness_id,
+                    });
+                }
+            }
+        }
+        Ok(conflicts)
+    }
+
+    /// Lists the owned state of the given type which is not spent by any of
+    /// the state transitions added to the history, ordered by the operation
+    /// outputs. This is the single source of the state for constructing new
+    /// state transitions, including coin selection.
+    pub fn spendable_outputs(&self, ty: AssignmentType) -> Vec<SpendableOutput> {
+        let spent = self.spent();
+        let output = |opout: Opout, seal: XOutputSeal, value: Option<u64>| SpendableOutput {
+            opout,
+            seal,
+            witness_ord: self
+                .witnesses
+                .get(&opout.op)
+                .map(|anchor| anchor.witness_ord),
+            value,
+        };
+        let rights = self.rights.iter().map(|a| output(a.opout, a.seal, None));
+        let fungibles = self
+            .fungibles
+            .iter()
+            .map(|a| output(a.opout, a.seal, Some(a.state.value.as_u64())));
+        let data = self.data.iter().map(|a| output(a.opout, a.seal, None));
+        let attach = self.attach.iter().map(|a| output(a.opout, a.seal, None));
+        let mut outputs = rights
+            .chain(fungibles)
+            .chain(data)
+            .chain(attach)
+            .filter(|output| output.opout.ty == ty && !spent.contains(&output.opout))
+            .collect::<Vec<_>>();
+        outputs.sort_by_key(|output| output.opout);
+        outputs
+    }
+
+    fn spent(&self) -> BTreeSet<Opout> {
+        self.history
+            .inputs
+            .values()
+            .flatten()
+            .map(|input| input.prev_out)
+            .collect()
+    }
+}
+
+/// Contract state which can be shared across threads and tasks.
+///
+/// Readers take cheap [`Arc`] snapshots of the state, which are never
+/// modified. Updates are copy-on-write: the state is cloned only if some
+/// snapshots are still alive, so they keep seeing the state as of the moment
+/// they were taken.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SharedContractState(Arc<ContractState>);
+
+impl From<ContractState> for SharedContractState {
+    fn from(state: ContractState) -> Self { SharedContractState(Arc::new(state)) }
+}
+
+impl Deref for SharedContractState {
+    type Target = ContractState;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl SharedContractState {
+    /// Returns snapshot of the current state.
+    pub fn snapshot(&self) -> Arc<ContractState> { self.0.clone() }
+
+    /// Updates the state, cloning it first if there are snapshots of it.
+    pub fn update<T>(&mut self, f: impl FnOnce(&mut ContractState) -> T) -> T {
+        f(Arc::make_mut(&mut self.0))
+    }
+
+    /// Returns the state, cloning it if there are snapshots of it.
+    pub fn into_inner(self) -> ContractState {
+        Arc::try_unwrap(self.0).unwrap_or_else(|state| (*state).clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, SmallBlob, SmallOrdSet};
+    use bp::dbc::Method;
+    use bp::{LockTime, SeqNo, Tx, TxIn, TxVer, Txid};
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::testing::MockResolver;
+    use crate::{
+        AssetTag, BlindingFactor, GenesisSeal, GlobalState, GlobalStateSchema, GlobalValues,
+        GraphSeal, Input, Layer1,
+    };
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+
+    fn amounts<Seal: ExposedSeal>(values: &[u64], seal: impl Fn(u32) -> Seal) -> Assignments<Seal> {
+        let tag = AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0);
+        let blinding = BlindingFactor::from_str(
+            "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53",
+        )
+        .unwrap();
+        let assigns = values
+            .iter()
+            .enumerate()
+            .map(|(no, value)| {
+                let state = RevealedValue::with_blinding(*value, blinding.clone(), tag);
+                Assign::revealed(XChain::Bitcoin(seal(no as u32)), state)
+            })
+            .collect::<Vec<_>>();
+        Assignments::from(tiny_bmap! {
+            ASSET => TypedAssigns::Fungible(SmallVec::try_from(assigns).unwrap()),
+        })
+    }
+
+    fn transfer(prev_out: Opout, value: u64) -> Transition {
+        let mut transition = Transition::strict_dumb();
+        let inputs = SmallOrdSet::try_from_iter([Input::with(prev_out)]).unwrap();
+        transition.inputs = Inputs::from(inputs);
+        transition.assignments = amounts(&[value], |vout| {
+            GraphSeal::with_blinded_vout(Method::OpretFirst, vout, vout as u64)
+        });
+        transition
+    }
+
+    #[test]
+    fn gc_spent_state() {
+        let mut genesis = Genesis::strict_dumb();
+        genesis.assignments = amounts(&[60, 40], |vout| {
+            GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, 0)
+        });
+        let schema = Schema::strict_dumb();
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = ContractState { schema, history };
+
+        let witness_id = XWitnessId::Bitcoin(Txid::strict_dumb());
+        let mined = WitnessAnchor {
+            witness_ord: WitnessOrd::with_mempool_or_height(100, 1231006505),
+            witness_id,
+        };
+        let first = transfer(Opout::new(genesis.id(), ASSET, 0), 60);
+        let second = transfer(Opout::new(first.id(), ASSET, 0), 60);
+        state.add_transition(&first, mined);
+        state.add_transition(&second, WitnessAnchor::from_mempool(witness_id));
+        assert_eq!(state.fungibles().len(), 4);
+
+        // Spent state is retained for the recent operations
+        assert_eq!(state.gc(100), 0);
+        assert_eq!(state.fungibles().len(), 4);
+
+        // Allocation spent by an unmined operation is always retained
+        assert_eq!(state.gc(u32::MAX), 1);
+        assert_eq!(state.gc(u32::MAX), 0);
+        let left = state
+            .fungibles()
+            .iter()
+            .map(|a| a.opout)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(left, bset! {
+            Opout::new(genesis.id(), ASSET, 1),
+            Opout::new(first.id(), ASSET, 0),
+            Opout::new(second.id(), ASSET, 0),
+        });
+        assert_eq!(state.witnesses().len(), 2);
+        assert_eq!(state.inputs().len(), 2);
+    }
+
+    #[test]
+    fn spendable_outputs() {
+        let mut genesis = Genesis::strict_dumb();
+        genesis.assignments = amounts(&[60, 40], |vout| {
+            GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, 0)
+        });
+        let schema = Schema::strict_dumb();
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = ContractState { schema, history };
+        let spendable = |state: &ContractState| {
+            state
+                .spendable_outputs(ASSET)
+                .into_iter()
+                .map(|output| (output.opout, output.value, output.confirmations(105)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(spendable(&state), vec![
+            (Opout::new(genesis.id(), ASSET, 0), Some(60), None),
+            (Opout::new(genesis.id(), ASSET, 1), Some(40), None),
+        ]);
+        assert_eq!(state.spendable_outputs(AssignmentType::with(2)), vec![]);
+
+        let witness_id = XWitnessId::Bitcoin(Txid::strict_dumb());
+        let mined = WitnessAnchor {
+            witness_ord: WitnessOrd::with_mempool_or_height(100, 1231006505),
+            witness_id,
+        };
+        let first = transfer(Opout::new(genesis.id(), ASSET, 0), 60);
+        state.add_transition(&first, mined);
+        let mut outputs = vec![
+            (Opout::new(genesis.id(), ASSET, 1), Some(40), None),
+            (Opout::new(first.id(), ASSET, 0), Some(60), Some(6)),
+        ];
+        outputs.sort();
+        assert_eq!(spendable(&state), outputs);
+
+        let second = transfer(Opout::new(first.id(), ASSET, 0), 60);
+        state.add_transition(&second, WitnessAnchor::from_mempool(witness_id));
+        let mut outputs = vec![
+            (Opout::new(genesis.id(), ASSET, 1), Some(40), None),
+            (Opout::new(second.id(), ASSET, 0), Some(60), Some(0)),
+        ];
+        outputs.sort();
+        assert_eq!(spendable(&state), outputs);
+    }
+
+    #[test]
+    fn shared_state() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ContractState>();
+        assert_send_sync::<SharedContractState>();
+
+        let genesis = Genesis::strict_dumb();
+        let schema = Schema::strict_dumb();
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = SharedContractState::from(ContractState { schema, history });
+
+        let snapshot = state.snapshot();
+        let witness_id = XWitnessId::Bitcoin(Txid::strict_dumb());
+        let transition = transfer(Opout::new(genesis.id(), ASSET, 0), 60);
+        state.update(|state| {
+            state.add_transition(&transition, WitnessAnchor::from_mempool(witness_id))
+        });
+        assert_eq!(state.inputs().len(), 1);
+        assert_eq!(snapshot.inputs().len(), 0);
+
+        let reader = std::thread::spawn(move || snapshot.fungibles().len());
+        assert_eq!(reader.join().unwrap(), 0);
+        assert_eq!(state.into_inner().fungibles().len(), 1);
+    }
+
+    #[test]
+    fn accumulator() {
+        const STAKED: GlobalStateType = GlobalStateType::with(1);
+        let value = |bytes: [u8; 8]| {
+            GlobalValues::with(DataState::from(SmallBlob::try_from(bytes.to_vec()).unwrap()))
+        };
+
+        let mut schema = Schema::strict_dumb();
+        schema.global_types =
+            tiny_bmap! { STAKED => GlobalStateSchema::accumulator(strict_dumb!()) };
+        let mut genesis = Genesis::strict_dumb();
+        genesis.globals =
+            GlobalState::from_inner(tiny_bmap! { STAKED => value(100u64.to_le_bytes()) });
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = ContractState { schema, history };
+        assert_eq!(state.accumulator(STAKED), Some(100));
+        assert_eq!(state.accumulator(GlobalStateType::with(2)), None);
+
+        // Updates sharing the same witness
+        let witness = WitnessAnchor::from_mempool(XWitnessId::Bitcoin(Txid::strict_dumb()));
+        let update = |delta: i64| {
+            let mut transition = transfer(Opout::new(genesis.id(), ASSET, 0), 100);
+            transition.globals =
+                GlobalState::from_inner(tiny_bmap! { STAKED => value(delta.to_le_bytes()) });
+            transition
+        };
+        state.add_transition(&update(5), witness);
+        state.add_transition(&update(-20), witness);
+        assert_eq!(state.accumulator(STAKED), Some(85));
+
+        state.add_transition(&update(-100), witness);
+        assert_eq!(state.accumulator(STAKED), None);
+    }
+
+    #[test]
+    fn authority() {
+        fn key<Seal: ExposedSeal>(key: u8, seal: Seal) -> Assignments<Seal> {
+            let assign = Assign::revealed(
+                XChain::Bitcoin(seal),
+                RevealedData::with_salt(small_vec![key], key as u128),
+            );
+            Assignments::from(tiny_bmap! {
+                AssignmentType::ISSUER_AUTHORITY =>
+                    TypedAssigns::Structured(SmallVec::try_from_iter([assign]).unwrap()),
+            })
+        }
+        let value = |state: &ContractState| {
+            state
+                .authority()
+                .map(|authority| authority.state.value.to_vec())
+        };
+
+        let mut genesis = Genesis::strict_dumb();
+        genesis.assignments =
+            key(1, GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), 0, 0));
+        let mut schema = Schema::strict_dumb();
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let state = ContractState {
+            schema: schema.clone(),
+            history: history.clone(),
+        };
+        assert_eq!(state.authority(), None);
+
+        schema.flags = SchemaFlags::ISSUER_AUTHORITY;
+        let mut state = ContractState { schema, history };
+        assert_eq!(value(&state), Some(vec![1]));
+
+        let mut rotation = Transition::strict_dumb();
+        let prev_out = Opout::new(genesis.id(), AssignmentType::ISSUER_AUTHORITY, 0);
+        let inputs = SmallOrdSet::try_from_iter([Input::with(prev_out)]).unwrap();
+        rotation.inputs = Inputs::from(inputs);
+        rotation.assignments = key(2, GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 0));
+        let witness = WitnessAnchor::from_mempool(XWitnessId::Bitcoin(Txid::strict_dumb()));
+        state.add_transition(&rotation, witness);
+        assert_eq!(value(&state), Some(vec![2]));
+    }
+
+    #[test]
+    fn watch_conflicts() {
+        let spend = |outpoints: &[Outpoint]| {
+            let inputs = outpoints.iter().map(|outpoint| TxIn {
+                prev_output: *outpoint,
+                sig_script: none!(),
+                sequence: SeqNo::from_consensus_u32(0xFFFF_FFFF),
+                witness: none!(),
+            });
+            XChain::Bitcoin(Tx {
+                version: TxVer::V2,
+                inputs: Confined::try_from_iter(inputs).unwrap(),
+                outputs: none!(),
+                lock_time: LockTime::ZERO,
+            })
+        };
+
+        let mut genesis = Genesis::strict_dumb();
+        genesis.assignments = amounts(&[60, 40], |vout| {
+            GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, 0)
+        });
+        let schema = Schema::strict_dumb();
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = ContractState { schema, history };
+        let txid = Txid::from([0x11; 32]);
+        let first = transfer(Opout::new(genesis.id(), ASSET, 0), 60);
+        state.add_transition(&first, WitnessAnchor::from_mempool(XWitnessId::Bitcoin(txid)));
+
+        let mut resolver = MockResolver::new();
+        let unrelated = resolver.broadcast(spend(&[Outpoint::new(Txid::from([0x22; 32]), 0)]));
+        let no_conflicts = state
+            .watch_conflicts(resolver.resolve_mempool(Layer1::Bitcoin).unwrap(), &resolver)
+            .unwrap();
+        assert!(no_conflicts.is_empty());
+
+        // Spending of the already spent genesis output is not a conflict
+        let moving_genesis = resolver.broadcast(spend(&[
+            Outpoint::new(Txid::strict_dumb(), 0),
+            Outpoint::new(Txid::strict_dumb(), 1),
+        ]));
+        let moving_transfer = resolver.broadcast(spend(&[Outpoint::new(txid, 0)]));
+        let unknown = XWitnessId::Bitcoin(Txid::from([0x33; 32]));
+        let mempool = resolver.resolve_mempool(Layer1::Bitcoin).unwrap();
+        assert_eq!(mempool, bset! { unrelated, moving_genesis, moving_transfer });
+        let conflicts = state
+            .watch_conflicts(mempool.into_iter().chain([unknown]), &resolver)
+            .unwrap()
+            .into_iter()
+            .map(|conflict| (conflict.opout, conflict.witness_id))
+            .collect::<BTreeSet<_>>();
+        assert_eq!(conflicts, bset! {
+            (Opout::new(genesis.id(), ASSET, 1), moving_genesis),
+            (Opout::new(first.id(), ASSET, 0), moving_transfer),
+        });
+        assert!(resolver.resolve_mempool(Layer1::Liquid).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,128 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Expiring allocations with claim-or-return semantics.
+//!
+//! Under a schema with [`SchemaFlags::EXPIRING_ALLOCATIONS`] flag, a state
+//! transition may commit to [`AllocationExpiries`], making some of its
+//! assignments expiring. The receiver must claim an expiring allocation, i.e.
+//! close its seal, in a witness transaction mined at or below the expiry
+//! height. Witnesses mined after the expiry height must close the fallback
+//! seal provided by the sender instead, so the allocation returns to the
+//! sender.
+//!
+//! [`SchemaFlags::EXPIRING_ALLOCATIONS`]: crate::SchemaFlags::EXPIRING_ALLOCATIONS
+
+use amplify::Wrapper;
+use amplify::confinement::TinyOrdMap;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::schema::MetaType;
+use crate::{
+    AssignmentType, GraphSeal, LIB_NAME_RGB, MetaValue, MetadataError, Opout, Transition,
+    WitnessOrd, XChain,
+};
+
+/// Metadata type reserved for the [`AllocationExpiries`] of a state
+/// transition. Valid in state transitions of schemata with
+/// [`crate::SchemaFlags::EXPIRING_ALLOCATIONS`] flag which don't declare
+/// metadata of this type.
+pub const ALLOCATION_EXPIRY_TYPE: MetaType = MetaType::with(0xFFFC);
+
+/// Expiry of a single allocation.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AllocationExpiry {
+    /// Last block height at which the allocation can be claimed.
+    pub height: u32,
+    /// Seal controlling the allocation after the expiry height. Seals without
+    /// txid refer to the witness of the transition defining the allocation.
+    pub fallback: XChain<GraphSeal>,
+}
+
+impl AllocationExpiry {
+    /// Checks whether a witness with a given mining status closes the seal
+    /// after the expiry. Witnesses which are not mined can't be checked, so
+    /// they are not considered expired.
+    pub fn is_expired_at(&self, witness_ord: WitnessOrd) -> bool {
+        match witness_ord {
+            WitnessOrd::OnChain(pos) => pos.height().get() > self.height,
+            _ => false,
+        }
+    }
+}
+
+/// Expiries of the allocations of a given type assigned by a state
+/// transition, indexed by the assignment number.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AllocationExpiries {
+    pub assignment_type: AssignmentType,
+    pub expiries: TinyOrdMap<u16, AllocationExpiry>,
+}
+
+impl StrictSerialize for AllocationExpiries {}
+impl StrictDeserialize for AllocationExpiries {}
+
+impl AllocationExpiries {
+    /// Returns expiry of the allocation defined by a given output.
+    pub fn get(&self, opout: Opout) -> Option<&AllocationExpiry> {
+        if opout.ty != self.assignment_type {
+            return None;
+        }
+        self.expiries.get(&opout.no)
+    }
+}
+
+impl Transition {
+    /// Returns allocation expiries of the transition, if present and valid.
+    pub fn allocation_expiries(&self) -> Option<AllocationExpiries> {
+        let value = self.metadata.get(&ALLOCATION_EXPIRY_TYPE)?;
+        AllocationExpiries::from_strict_serialized::<{ u16::MAX as usize }>(value.to_inner()).ok()
+    }
+
+    /// Commits the transition to allocation expiries, changing its id.
+    pub fn add_allocation_expiries(
+        &mut self,
+        expiries: &AllocationExpiries,
+    ) -> Result<(), MetadataError> {
+        let value = expiries
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .expect("at most 255 expiries fit metadata");
+        self.metadata
+            .add_value(ALLOCATION_EXPIRY_TYPE, MetaValue::from(value))?;
+        self.invalidate_id();
+        Ok(())
+    }
+}
@@ -31,32 +31,34 @@
 //! using elliptic curve homomorphic cryptography such as Pedesen commitments.
 
 use core::cmp::Ordering;
-use core::fmt::Debug;
+use core::fmt::{self, Debug, Formatter, LowerHex};
 use core::num::ParseIntError;
 use core::ops::Deref;
 use core::str::FromStr;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 use std::io;
 
 use amplify::confinement::U8;
 use amplify::hex::ToHex;
 // We do not import particular modules to keep aware with namespace prefixes
-// that we do not use the standard secp256k1zkp library
-use amplify::{hex, Array, Bytes32, Wrapper};
+// that we do not use the standard secp256k1 library
+use amplify::{Array, Bytes, Bytes32, Wrapper, hex};
+use bp::secp256k1;
 use bp::secp256k1::rand::thread_rng;
 use chrono::{DateTime, Utc};
 use commit_verify::{
     CommitVerify, CommitmentProtocol, Conceal, DigestExt, Sha256, UntaggedProtocol,
 };
-use secp256k1_zkp::rand::{Rng, RngCore};
-use secp256k1_zkp::SECP256K1;
+use secp256k1::rand::{Rng, RngCore};
 use strict_encoding::{
     DecodeError, ReadTuple, StrictDecode, StrictDumb, StrictEncode, TypedRead, TypedWrite,
     WriteTuple,
 };
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
 
-use super::{ConfidentialState, ExposedState};
-use crate::{schema, AssignmentType, ConcealedState, RevealedState, StateType, LIB_NAME_RGB};
+use super::{ConfidentialState, ExposedState, pedersen_engine};
+use crate::{AssignmentType, ConcealedState, LIB_NAME_RGB, RevealedState, StateType, schema};
 
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
@@ -148,11 +150,16 @@ impl FungibleState {
     pub fn as_u64(&self) -> u64 { (*self).into() }
 }
 
+/// invalid pedersen commitment data.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct InvalidCommitment;
+
 /// value provided for a blinding factor overflows prime field order for
 /// Secp256k1 curve.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
 #[display(doc_comments)]
-#[from(secp256k1_zkp::UpstreamError)]
+#[from(secp256k1::Error)]
 pub struct InvalidFieldElement;
 
 /// Errors parsing string representation of a blinding factor.
@@ -173,14 +180,23 @@ pub enum BlindingParseError {
 ///
 /// Knowledge of the blinding factor is important to reproduce the commitment
 /// process if the original value is kept.
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
-#[display(Self::to_hex)]
+///
+/// Since the blinding factor is a secret, it is zeroized on drop and is never
+/// printed by [`Debug`]; the hex value must be requested explicitly with
+/// [`ToHex::to_hex`]. Only the equality comparison ([`PartialEq`] and
+/// [`ConstantTimeEq`]) runs in constant time; [`Ord`] and [`Hash`]
+/// implementations, required for using the factor inside ordered and hashed
+/// collections, are not constant time and must not be exposed to an attacker
+/// able to measure their timing. Copies of the secret created by the hex and
+/// strict encodings, as well as the secp256k1 keys created from the factor,
+/// are not zeroized by this type.
+#[derive(Clone)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
-    serde(crate = "serde_crate", try_from = "secp256k1_zkp::SecretKey")
+    serde(crate = "serde_crate", try_from = "secp256k1::SecretKey")
 )]
 pub struct BlindingFactor(Bytes32);
 
@@ -188,9 +204,36 @@ impl BlindingFactor {
     pub const EMPTY: Self = BlindingFactor(Bytes32::from_array([0x7E; 32]));
 }
 
-impl Deref for BlindingFactor {
-    type Target = [u8; 32];
-    fn deref(&self) -> &Self::Target { self.0.as_inner() }
+impl Drop for BlindingFactor {
+    fn drop(&mut self) { self.0.as_mut().zeroize() }
+}
+
+impl Debug for BlindingFactor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BlindingFactor").field(&"..").finish()
+    }
+}
+
+impl ConstantTimeEq for BlindingFactor {
+    fn ct_eq(&self, other: &Self) -> Choice { self.0.as_slice().ct_eq(other.0.as_slice()) }
+}
+
+impl PartialEq for BlindingFactor {
+    fn eq(&self, other: &Self) -> bool { self.ct_eq(other).into() }
+}
+
+impl Eq for BlindingFactor {}
+
+impl PartialOrd for BlindingFactor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for BlindingFactor {
+    fn cmp(&self, other: &Self) -> Ordering { self.0.cmp(&other.0) }
+}
+
+impl Hash for BlindingFactor {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state) }
 }
 
 impl ToHex for BlindingFactor {
@@ -205,11 +248,11 @@ impl FromStr for BlindingFactor {
     }
 }
 
-impl From<secp256k1_zkp::SecretKey> for BlindingFactor {
-    fn from(key: secp256k1_zkp::SecretKey) -> Self { Self(Bytes32::from_inner(*key.as_ref())) }
+impl From<secp256k1::SecretKey> for BlindingFactor {
+    fn from(key: secp256k1::SecretKey) -> Self { Self(Bytes32::from_inner(*key.as_ref())) }
 }
 
-impl From<BlindingFactor> for secp256k1_zkp::SecretKey {
+impl From<BlindingFactor> for secp256k1::SecretKey {
     fn from(bf: BlindingFactor) -> Self { bf.to_secret_key() }
 }
 
@@ -221,7 +264,7 @@ impl BlindingFactor {
     /// Generates a random blinding factor using custom random number generator.
     #[inline]
     pub fn random_custom<R: Rng + RngCore>(rng: &mut R) -> Self {
-        secp256k1_zkp::SecretKey::new(rng).into()
+        secp256k1::SecretKey::new(rng).into()
     }
 
     /// Generates new blinding factor which balances a given set of negatives
@@ -238,22 +281,33 @@ impl BlindingFactor {
         negative: impl IntoIterator<Item = BlindingFactor>,
         positive: impl IntoIterator<Item = BlindingFactor>,
     ) -> Result<Self, InvalidFieldElement> {
-        let mut blinding_neg_sum = secp256k1_zkp::Scalar::ZERO;
-        let mut blinding_pos_sum = secp256k1_zkp::Scalar::ZERO;
+        let mut blinding_neg_sum = secp256k1::Scalar::ZERO;
+        let mut blinding_pos_sum = secp256k1::Scalar::ZERO;
         for neg in negative {
-            blinding_neg_sum = neg.to_secret_key().add_tweak(&blinding_neg_sum)?.into();
+            let mut key = neg.to_secret_key();
+            blinding_neg_sum = key.add_tweak(&blinding_neg_sum)?.into();
+            key.non_secure_erase();
         }
-        let blinding_neg_sum =
-            secp256k1_zkp::SecretKey::from_slice(&blinding_neg_sum.to_be_bytes())?.negate();
+        let mut blinding_neg_sum =
+            secp256k1::SecretKey::from_slice(&blinding_neg_sum.to_be_bytes())?.negate();
         for pos in positive {
-            blinding_pos_sum = pos.to_secret_key().add_tweak(&blinding_pos_sum)?.into();
+            let mut key = pos.to_secret_key();
+            blinding_pos_sum = key.add_tweak(&blinding_pos_sum)?.into();
+            key.non_secure_erase();
         }
-        let blinding_correction = blinding_neg_sum.add_tweak(&blinding_pos_sum)?.negate();
-        Ok(blinding_correction.into())
+        let mut blinding_correction = blinding_neg_sum.add_tweak(&blinding_pos_sum)?.negate();
+        blinding_neg_sum.non_secure_erase();
+        let bf = BlindingFactor::from(blinding_correction);
+        blinding_correction.non_secure_erase();
+        Ok(bf)
     }
 
-    fn to_secret_key(self) -> secp256k1_zkp::SecretKey {
-        secp256k1_zkp::SecretKey::from_slice(self.0.as_slice())
+    /// Returns the secret value, for passing it to cryptographic libraries.
+    #[cfg(feature = "pedersen")]
+    pub(crate) fn as_secret(&self) -> &[u8; 32] { self.0.as_inner() }
+
+    fn to_secret_key(&self) -> secp256k1::SecretKey {
+        secp256k1::SecretKey::from_slice(self.0.as_slice())
             .expect("blinding factor is an invalid secret key")
     }
 }
@@ -262,7 +316,7 @@ impl TryFrom<[u8; 32]> for BlindingFactor {
     type Error = InvalidFieldElement;
 
     fn try_from(array: [u8; 32]) -> Result<Self, Self::Error> {
-        secp256k1_zkp::SecretKey::from_slice(&array)
+        secp256k1::SecretKey::from_slice(&array)
             .map_err(|_| InvalidFieldElement)
             .map(Self::from)
     }
@@ -279,7 +333,7 @@ impl TryFrom<Bytes32> for BlindingFactor {
 /// State item for a homomorphically-encryptable state.
 ///
 /// Consists of the 64-bit value and
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB, rename = "RevealedFungible")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
@@ -328,7 +382,7 @@ impl RevealedValue {
 impl ExposedState for RevealedValue {
     type Confidential = ConcealedValue;
     fn state_type(&self) -> StateType { StateType::Fungible }
-    fn state_data(&self) -> RevealedState { RevealedState::Fungible(*self) }
+    fn state_data(&self) -> RevealedState { RevealedState::Fungible(self.clone()) }
 }
 
 impl Conceal for RevealedValue {
@@ -351,28 +405,60 @@ impl Ord for RevealedValue {
 }
 
 /// Opaque type holding pedersen commitment for an [`FungibleState`].
-#[derive(Wrapper, Copy, Clone, Eq, PartialEq, Hash, Debug, From)]
-#[wrapper(Deref, FromStr, Display, LowerHex)]
+///
+/// Elliptic curve operations over the commitment are performed by the
+/// [`crate::PedersenEngine`] returned from [`pedersen_engine`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(LowerHex)]
 #[derive(StrictType)]
 #[strict_type(lib = LIB_NAME_RGB)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
-    serde(crate = "serde_crate", transparent)
+    serde(crate = "serde_crate", try_from = "Bytes<33>", into = "Bytes<33>")
 )]
-pub struct PedersenCommitment(secp256k1_zkp::PedersenCommitment);
+pub struct PedersenCommitment(Bytes<33>);
+
+impl Deref for PedersenCommitment {
+    type Target = [u8; 33];
+    fn deref(&self) -> &Self::Target { self.0.as_inner() }
+}
+
+impl LowerHex for PedersenCommitment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { LowerHex::fmt(&self.0, f) }
+}
 
 impl StrictDumb for PedersenCommitment {
-    fn strict_dumb() -> Self {
-        secp256k1_zkp::PedersenCommitment::from_slice(&[0x08; 33])
-            .expect("hardcoded pedersen commitment value")
-            .into()
+    fn strict_dumb() -> Self { Self(Bytes::from_array([0x08; 33])) }
+}
+
+impl TryFrom<Bytes<33>> for PedersenCommitment {
+    type Error = InvalidCommitment;
+
+    fn try_from(bytes: Bytes<33>) -> Result<Self, Self::Error> {
+        if !pedersen_engine().is_valid(bytes.as_inner()) {
+            return Err(InvalidCommitment);
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl From<PedersenCommitment> for Bytes<33> {
+    fn from(commitment: PedersenCommitment) -> Self { commitment.0 }
+}
+
+impl FromStr for PedersenCommitment {
+    type Err = InvalidCommitment;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Bytes::<33>::from_str(s)
+            .map_err(|_| InvalidCommitment)
+            .and_then(Self::try_from)
     }
 }
 
 impl StrictEncode for PedersenCommitment {
     fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
-        writer.write_tuple::<Self>(|w| Ok(w.write_field(&self.0.serialize())?.complete()))
+        writer.write_tuple::<Self>(|w| Ok(w.write_field(self.0.as_inner())?.complete()))
     }
 }
 
@@ -380,27 +466,23 @@ impl StrictDecode for PedersenCommitment {
     fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
         reader.read_tuple(|r| {
             let commitment = r.read_field::<[u8; 33]>()?;
-            secp256k1_zkp::PedersenCommitment::from_slice(&commitment)
-                .map_err(|_| {
-                    DecodeError::DataIntegrityError(s!("invalid pedersen commitment data"))
-                })
-                .map(PedersenCommitment::from_inner)
+            PedersenCommitment::try_from(Bytes::from_array(commitment)).map_err(|_| {
+                DecodeError::DataIntegrityError(s!("invalid pedersen commitment data"))
+            })
         })
     }
 }
 
 impl CommitVerify<RevealedValue, UntaggedProtocol> for PedersenCommitment {
+    /// Commits to the revealed value using [`pedersen_engine`].
+    ///
+    /// Engines unable to create commitments produce a dumb commitment, which
+    /// never matches commitments from other engines, such that operations
+    /// containing the value fail validation.
     fn commit(revealed: &RevealedValue) -> Self {
-        use secp256k1_zkp::{Generator, Tag, Tweak};
-
-        let blinding = Tweak::from_inner(revealed.blinding.0.into_inner())
-            .expect("type guarantees of BlindingFactor are broken");
-        let FungibleState::Bits64(value) = revealed.value;
-
-        let tag = Tag::from(revealed.tag.to_byte_array());
-        let generator = Generator::new_unblinded(SECP256K1, tag);
-
-        secp256k1_zkp::PedersenCommitment::new(SECP256K1, value, blinding, generator).into()
+        pedersen_engine()
+            .commit(revealed)
+            .unwrap_or_else(|_| Self::strict_dumb())
     }
 }
 
@@ -492,23 +574,15 @@ impl ConfidentialState for ConcealedValue {
 }
 
 impl CommitVerify<RevealedValue, PedersenProtocol> for ConcealedValue {
-    fn commit(revealed: &RevealedValue) -> Self {
-        let commitment = PedersenCommitment::commit(revealed);
-        // TODO: Do actual conceal upon integration of bulletproofs library
-        let range_proof = RangeProof::default();
-        ConcealedValue {
-            commitment,
-            range_proof,
-        }
-    }
+    fn commit(revealed: &RevealedValue) -> Self { Self::conceal_with(revealed, &Bulletproofs) }
 }
 
 /// Errors verifying range proofs.
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
 #[display(doc_comments)]
 pub enum RangeProofError {
-    /// invalid blinding factor {0}.
-    InvalidBlinding(BlindingFactor),
+    /// invalid blinding factor.
+    InvalidBlinding,
 
     /// bulletproofs verification is not implemented in RGB Core v0.10. Please
     /// update your software and try again, or ask your software producer to use
@@ -516,14 +590,73 @@ pub enum RangeProofError {
     BulletproofsAbsent,
 }
 
-impl ConcealedValue {
-    /// Verifies validity of the range proof.
-    pub fn verify_range_proof(&self) -> Result<bool, RangeProofError> {
+/// Range proof system proving that the amounts hidden by the pedersen
+/// commitments don't exceed the boundaries of their type.
+///
+/// Commitments are independent of the range proof system, thus contracts can
+/// switch to another system selected by a schema flag without changing the
+/// pedersen sums validation.
+pub trait RangeProofScheme {
+    /// Name of the range proof system.
+    fn name(&self) -> &'static str;
+
+    /// Creates range proof for the revealed value.
+    fn prove(&self, revealed: &RevealedValue) -> RangeProof;
+
+    /// Verifies range proof for the commitment.
+    fn verify(
+        &self,
+        commitment: &PedersenCommitment,
+        proof: &RangeProof,
+    ) -> Result<(), RangeProofError>;
+}
+
+/// Bulletproofs over secp256k1 curve, the default range proof system.
+///
+/// Until the bulletproofs library is integrated, produces placeholder proofs
+/// and always fails verification.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Bulletproofs;
+
+impl RangeProofScheme for Bulletproofs {
+    fn name(&self) -> &'static str { "bulletproofs" }
+
+    fn prove(&self, _revealed: &RevealedValue) -> RangeProof {
+        // TODO: Do actual proving upon integration of bulletproofs library
+        RangeProof::default()
+    }
+
+    fn verify(&self, _: &PedersenCommitment, _: &RangeProof) -> Result<(), RangeProofError> {
         // We always fail here
         Err(RangeProofError::BulletproofsAbsent)
     }
 }
 
+impl ConcealedValue {
+    /// Conceals the value, proving its range with the given proof system.
+    pub fn conceal_with(revealed: &RevealedValue, scheme: &impl RangeProofScheme) -> Self {
+        ConcealedValue {
+            commitment: PedersenCommitment::commit(revealed),
+            range_proof: scheme.prove(revealed),
+        }
+    }
+
+    /// Verifies validity of the range proof using the default proof system.
+    pub fn verify_range_proof(&self) -> Result<bool, RangeProofError> {
+        self.verify_range_proof_with(&Bulletproofs)
+    }
+
+    /// Verifies validity of the range proof using the given proof system.
+    pub fn verify_range_proof_with(
+        &self,
+        scheme: &(impl RangeProofScheme + ?Sized),
+    ) -> Result<bool, RangeProofError> {
+        scheme
+            .verify(&self.commitment, &self.range_proof)
+            .map(|_| true)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use amplify::ByteArray;
@@ -535,82 +668,73 @@ mod test {
         let mut r = thread_rng();
         let tag = AssetTag::from_byte_array([1u8; 32]);
 
-        let a = PedersenCommitment::commit(&RevealedValue::with_rng(15, &mut r, tag)).into_inner();
-        let b = PedersenCommitment::commit(&RevealedValue::with_rng(7, &mut r, tag)).into_inner();
+        let a = PedersenCommitment::commit(&RevealedValue::with_rng(15, &mut r, tag));
+        let b = PedersenCommitment::commit(&RevealedValue::with_rng(7, &mut r, tag));
 
-        let c = PedersenCommitment::commit(&RevealedValue::with_rng(13, &mut r, tag)).into_inner();
-        let d = PedersenCommitment::commit(&RevealedValue::with_rng(9, &mut r, tag)).into_inner();
+        let c = PedersenCommitment::commit(&RevealedValue::with_rng(13, &mut r, tag));
+        let d = PedersenCommitment::commit(&RevealedValue::with_rng(9, &mut r, tag));
 
-        assert!(!secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b], &[c, d]))
+        assert!(!pedersen_engine().verify_sum(&[a, b], &[c, d]).unwrap())
     }
 
     #[test]
     fn pedersen_blinding_same() {
-        let blinding =
-            BlindingFactor::from(secp256k1_zkp::SecretKey::from_slice(&[1u8; 32]).unwrap());
+        let blinding = BlindingFactor::from(secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap());
         let tag = AssetTag::from_byte_array([1u8; 32]);
 
-        let a = PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding, tag))
-            .into_inner();
-        let b = PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding, tag))
-            .into_inner();
+        let a =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding.clone(), tag));
+        let b = PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding.clone(), tag));
 
-        let c = PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding, tag))
-            .into_inner();
-        let d = PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding, tag))
-            .into_inner();
+        let c =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding.clone(), tag));
+        let d = PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding.clone(), tag));
 
-        assert!(secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b], &[c, d]))
+        assert!(pedersen_engine().verify_sum(&[a, b], &[c, d]).unwrap())
     }
 
     #[test]
     fn pedersen_blinding_same_tag_differ() {
-        let blinding =
-            BlindingFactor::from(secp256k1_zkp::SecretKey::from_slice(&[1u8; 32]).unwrap());
+        let blinding = BlindingFactor::from(secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap());
         let tag = AssetTag::from_byte_array([1u8; 32]);
         let tag2 = AssetTag::from_byte_array([2u8; 32]);
 
-        let a = PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding, tag2))
-            .into_inner();
-        let b = PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding, tag))
-            .into_inner();
+        let a =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding.clone(), tag2));
+        let b = PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding.clone(), tag));
 
-        let c = PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding, tag2))
-            .into_inner();
-        let d = PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding, tag))
-            .into_inner();
+        let c =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding.clone(), tag2));
+        let d = PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding.clone(), tag));
 
-        assert!(!secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b], &[c, d]))
+        assert!(!pedersen_engine().verify_sum(&[a, b], &[c, d]).unwrap())
     }
 
     #[test]
     fn pedersen_two_tags() {
-        let blinding =
-            BlindingFactor::from(secp256k1_zkp::SecretKey::from_slice(&[1u8; 32]).unwrap());
+        let blinding = BlindingFactor::from(secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap());
         let tag = AssetTag::from_byte_array([1u8; 32]);
         let tag2 = AssetTag::from_byte_array([2u8; 32]);
 
-        let a = PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding, tag2))
-            .into_inner();
-        let b = PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding, tag2))
-            .into_inner();
-        let c = PedersenCommitment::commit(&RevealedValue::with_blinding(2, blinding, tag))
-            .into_inner();
-        let d = PedersenCommitment::commit(&RevealedValue::with_blinding(4, blinding, tag))
-            .into_inner();
-
-        let e = PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding, tag2))
-            .into_inner();
-        let f = PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding, tag2))
-            .into_inner();
-        let g = PedersenCommitment::commit(&RevealedValue::with_blinding(1, blinding, tag))
-            .into_inner();
-        let h = PedersenCommitment::commit(&RevealedValue::with_blinding(5, blinding, tag))
-            .into_inner();
-
-        assert!(secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b, c, d], &[
-            e, f, g, h
-        ]))
+        let a =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding.clone(), tag2));
+        let b =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding.clone(), tag2));
+        let c = PedersenCommitment::commit(&RevealedValue::with_blinding(2, blinding.clone(), tag));
+        let d = PedersenCommitment::commit(&RevealedValue::with_blinding(4, blinding.clone(), tag));
+
+        let e =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding.clone(), tag2));
+        let f =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding.clone(), tag2));
+        let g = PedersenCommitment::commit(&RevealedValue::with_blinding(1, blinding.clone(), tag));
+        let h = PedersenCommitment::commit(&RevealedValue::with_blinding(5, blinding.clone(), tag));
+
+        assert!(
+            pedersen_engine()
+                .verify_sum(&[a, b, c, d], &[e, f, g, h])
+                .unwrap()
+        )
     }
 
     #[test]
@@ -618,19 +742,98 @@ mod test {
         let blinding1 = BlindingFactor::random();
         let blinding2 = BlindingFactor::random();
         let blinding3 = BlindingFactor::random();
-        let blinding4 = BlindingFactor::zero_balanced([blinding1, blinding2], [blinding3]).unwrap();
+        let blinding4 = BlindingFactor::zero_balanced([blinding1.clone(), blinding2.clone()], [
+            blinding3.clone(),
+        ])
+        .unwrap();
         let tag = AssetTag::from_byte_array([1u8; 32]);
 
-        let a = PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding1, tag))
-            .into_inner();
-        let b = PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding2, tag))
-            .into_inner();
+        let a =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(15, blinding1.clone(), tag));
+        let b =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(7, blinding2.clone(), tag));
+
+        let c =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding3.clone(), tag));
+        let d =
+            PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding4.clone(), tag));
+
+        assert!(pedersen_engine().verify_sum(&[a, b], &[c, d]).unwrap())
+    }
+
+    /// Commitments are independent of the range proof system, thus these
+    /// vectors must hold for any [`RangeProofScheme`] implementation.
+    const COMMITMENT_VECTORS: [(u64, &str, u8, &str); 3] = [
+        (
+            0,
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            1,
+            "0879be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        ),
+        (
+            100_000,
+            "a6d7a42c5e9ddcbad4b5dea0af8bde5fa69d7eef6b0e8b3f9c64d8b77a6a58b1",
+            2,
+            "09f36596d7add96eebd5d2c04798963076447e60a92448b4fc6e898236113b1c99",
+        ),
+        (
+            u64::MAX,
+            "0b1e1c0ffee0b1e1c0ffee0b1e1c0ffee0b1e1c0ffee0b1e1c0ffee0b1e1c0ff",
+            0xff,
+            "08ab91f772222a472e3b2c1c36dcfb453837807d043c76514a01bb6b457f0329af",
+        ),
+    ];
+
+    fn check_range_proof_scheme(scheme: &dyn RangeProofScheme) {
+        for (value, blinding, tag, commitment) in COMMITMENT_VECTORS {
+            let blinding = BlindingFactor::from_str(blinding).unwrap();
+            let tag = AssetTag::from_byte_array([tag; 32]);
+            let revealed = RevealedValue::with_blinding(value, blinding, tag);
+            let concealed = ConcealedValue {
+                commitment: PedersenCommitment::commit(&revealed),
+                range_proof: scheme.prove(&revealed),
+            };
+            assert_eq!(
+                concealed.commitment.to_string(),
+                commitment,
+                "{} commitment to {value}",
+                scheme.name()
+            );
+            assert_eq!(
+                concealed.verify_range_proof_with(scheme),
+                scheme
+                    .verify(&concealed.commitment, &concealed.range_proof)
+                    .map(|_| true)
+            );
+        }
+    }
+
+    #[test]
+    fn range_proof_schemes() {
+        check_range_proof_scheme(&Bulletproofs);
+
+        let revealed = RevealedValue::with_blinding(
+            7,
+            BlindingFactor::from_str(COMMITMENT_VECTORS[0].1).unwrap(),
+            AssetTag::from_byte_array([1u8; 32]),
+        );
+        let concealed = revealed.conceal();
+        assert_eq!(concealed, ConcealedValue::conceal_with(&revealed, &Bulletproofs));
+        assert_eq!(concealed.verify_range_proof(), Err(RangeProofError::BulletproofsAbsent));
+    }
 
-        let c = PedersenCommitment::commit(&RevealedValue::with_blinding(13, blinding3, tag))
-            .into_inner();
-        let d = PedersenCommitment::commit(&RevealedValue::with_blinding(9, blinding4, tag))
-            .into_inner();
+    #[test]
+    fn blinding_secrecy() {
+        let hex = "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53";
+        let blinding = BlindingFactor::from_str(hex).unwrap();
+        assert_eq!(blinding.to_hex(), hex);
+        assert_eq!(format!("{blinding:?}"), "BlindingFactor(\"..\")");
+
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        let revealed = RevealedValue::with_blinding(5, blinding.clone(), tag);
+        assert!(!format!("{revealed:?}").contains(hex));
 
-        assert!(secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b], &[c, d]))
+        assert_eq!(blinding, BlindingFactor::from_str(hex).unwrap());
+        assert_ne!(blinding, BlindingFactor::EMPTY);
     }
 }
@@ -24,10 +24,10 @@ use std::collections::btree_map;
 use std::vec;
 
 use amplify::confinement::{Confined, TinyOrdMap, U16};
-use amplify::{confinement, Wrapper};
+use amplify::{Wrapper, confinement};
 use strict_encoding::StrictDumb;
 
-use crate::{schema, DataState, LIB_NAME_RGB};
+use crate::{DataState, LIB_NAME_RGB, MapView, schema};
 
 #[derive(Wrapper, WrapperMut, Clone, PartialEq, Eq, Hash, Debug, From)]
 #[wrapper(Deref)]
@@ -47,6 +47,9 @@ impl StrictDumb for GlobalValues {
 
 impl GlobalValues {
     pub fn with(state: DataState) -> Self { GlobalValues(Confined::with(state)) }
+
+    /// Read-only view of the values, in the order they were added.
+    pub fn as_slice(&self) -> &[DataState] { self.0.as_slice() }
 }
 
 impl IntoIterator for GlobalValues {
@@ -69,6 +72,11 @@ impl IntoIterator for GlobalValues {
 pub struct GlobalState(TinyOrdMap<schema::GlobalStateType, GlobalValues>);
 
 impl GlobalState {
+    /// Read-only view of the global state values by their type.
+    pub fn view(&self) -> MapView<'_, schema::GlobalStateType, GlobalValues> {
+        MapView::new(&self.0)
+    }
+
     pub fn add_state(
         &mut self,
         ty: schema::GlobalStateType,
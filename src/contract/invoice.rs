@@ -0,0 +1,430 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Data model for invoices requesting state to be assigned to a receiver seal.
+
+use std::fmt::{self, Display, Formatter};
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use amplify::confinement::TinyVec;
+use amplify::hex::{self, FromHex};
+use amplify::{ByteArray, Bytes32, Wrapper};
+use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+use commit_verify::{CommitId, CommitmentId, DigestExt, Sha256};
+use strict_encoding::stl::AsciiPrintable;
+use strict_encoding::{InvalidRString, RString, StrictDeserialize, StrictSerialize};
+
+use crate::envelope::EnvelopeKey;
+use crate::{
+    AssignmentType, ContractId, DataState, FungibleState, LIB_NAME_RGB, SecretSeal, XChain,
+    XChainParseError, impl_serde_baid64,
+};
+
+pub const INVOICE_QUERY_EXPIRY: &str = "expiry";
+pub const INVOICE_QUERY_TRANSPORT: &str = "transport";
+pub const INVOICE_QUERY_ENVELOPE: &str = "envelope";
+
+/// Characters of transport hints which are percent-encoded in the invoice
+/// query, since they separate the query parameters or the URI fragment.
+const TRANSPORT_ESCAPED: [char; 4] = ['%', '&', '=', '#'];
+
+/// Invoice identifier, committing to all the invoice data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct InvoiceId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for InvoiceId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for InvoiceId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:invoice#2024-07-01";
+}
+
+impl DisplayBaid64 for InvoiceId {
+    const HRI: &'static str = "rgb:inv";
+    const CHUNKING: bool = false;
+    const PREFIX: bool = true;
+    const EMBED_CHECKSUM: bool = false;
+    const MNEMONIC: bool = false;
+    fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
+}
+impl FromBaid64Str for InvoiceId {}
+impl FromStr for InvoiceId {
+    type Err = Baid64ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+}
+impl Display for InvoiceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
+}
+
+impl_serde_baid64!(InvoiceId);
+
+/// Hint on a transport which can be used for sending consignment to the
+/// invoice issuer, like a proxy server URL.
+#[derive(Wrapper, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, From, Display)]
+#[wrapper(Deref, FromStr)]
+#[display(inner)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct TransportHint(RString<AsciiPrintable, AsciiPrintable, 1, 256>);
+
+impl TransportHint {
+    /// Formats the hint for the invoice query, percent-encoding characters
+    /// which separate query parameters.
+    fn to_query(&self) -> String {
+        let mut s = String::with_capacity(self.len());
+        for c in self.as_str().chars() {
+            if TRANSPORT_ESCAPED.contains(&c) {
+                s.push_str(&format!("%{:02X}", c as u8));
+            } else {
+                s.push(c);
+            }
+        }
+        s
+    }
+
+    /// Parses the hint from the invoice query, decoding percent-encoded
+    /// characters.
+    fn from_query(s: &str) -> Result<Self, InvoiceParseError> {
+        let mut hint = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                hint.push(c);
+                continue;
+            }
+            let code = chars.by_ref().take(2).collect::<String>();
+            // `from_str_radix` accepts a leading sign, thus digits are checked first
+            if code.len() != 2 || !code.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(InvoiceParseError::TransportEscape(s.to_owned()));
+            }
+            match u8::from_str_radix(&code, 16) {
+                Ok(byte) if byte.is_ascii() => hint.push(byte as char),
+                _ => return Err(InvoiceParseError::TransportEscape(s.to_owned())),
+            }
+        }
+        Ok(hint.parse()?)
+    }
+}
+
+/// State requested by an invoice.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = order)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum InvoiceState {
+    /// Declarative state, or any state which the payer decides to assign.
+    #[strict_type(dumb)]
+    Void,
+
+    /// Amount of fungible state.
+    Amount(FungibleState),
+
+    /// Structured data.
+    Data(DataState),
+}
+
+impl Display for InvoiceState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InvoiceState::Void => f.write_str("void"),
+            InvoiceState::Amount(amount) => Display::fmt(amount, f),
+            InvoiceState::Data(data) => write!(f, "data:{data}"),
+        }
+    }
+}
+
+impl FromStr for InvoiceState {
+    type Err = InvoiceParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "void" {
+            return Ok(InvoiceState::Void);
+        }
+        if let Some(data) = s.strip_prefix("data:") {
+            return DataState::from_hex(data)
+                .map(InvoiceState::Data)
+                .map_err(InvoiceParseError::from);
+        }
+        FungibleState::from_str(s)
+            .map(InvoiceState::Amount)
+            .map_err(InvoiceParseError::from)
+    }
+}
+
+/// Invoice requesting assignment of the state of a given type under some
+/// contract to the receiver blinded seal.
+///
+/// String representation of the invoice has form of
+/// `<contract_id>/<assignment_type>/<state>/<seal>` optionally followed by
+/// query parameters `expiry=<height>`, `envelope=<key>` and `transport=<hint>`
+/// (which may repeat).
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = InvoiceId)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Invoice {
+    pub contract_id: ContractId,
+    pub assignment_type: AssignmentType,
+    pub state: InvoiceState,
+    pub seal: XChain<SecretSeal>,
+    /// Block height after which the invoice must not be paid.
+    pub expiry: Option<u32>,
+    pub transports: TinyVec<TransportHint>,
+    /// Key which must be used to encrypt consignment sent to the receiver.
+    pub envelope_key: Option<EnvelopeKey>,
+}
+
+impl StrictSerialize for Invoice {}
+impl StrictDeserialize for Invoice {}
+
+impl Invoice {
+    pub fn new(
+        contract_id: ContractId,
+        assignment_type: AssignmentType,
+        state: InvoiceState,
+        seal: XChain<SecretSeal>,
+    ) -> Self {
+        Invoice {
+            contract_id,
+            assignment_type,
+            state,
+            seal,
+            expiry: None,
+            transports: empty!(),
+            envelope_key: None,
+        }
+    }
+
+    pub fn invoice_id(&self) -> InvoiceId { self.commit_id() }
+
+    /// Checks whether the invoice has expired at a given block height.
+    pub fn is_expired(&self, height: u32) -> bool {
+        self.expiry
+            .map(|expiry| height > expiry)
+            .unwrap_or_default()
+    }
+
+    /// Checks whether a seal receiving state under a contract pays this
+    /// invoice.
+    pub fn is_paid_by(&self, contract_id: ContractId, seal: XChain<SecretSeal>) -> bool {
+        self.contract_id == contract_id && self.seal == seal
+    }
+}
+
+impl Display for Invoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Mnemonic suffix of the contract id is omitted, since `#` starts URI fragment
+        write!(
+            f,
+            "{:#}/{}/{}/{}",
+            self.contract_id,
+            self.assignment_type.to_inner(),
+            self.state,
+            self.seal
+        )?;
+        let mut sep = '?';
+        if let Some(expiry) = self.expiry {
+            write!(f, "{sep}{INVOICE_QUERY_EXPIRY}={expiry}")?;
+            sep = '&';
+        }
+        if let Some(key) = self.envelope_key {
+            write!(f, "{sep}{INVOICE_QUERY_ENVELOPE}={key}")?;
+            sep = '&';
+        }
+        for transport in &self.transports {
+            write!(f, "{sep}{INVOICE_QUERY_TRANSPORT}={}", transport.to_query())?;
+            sep = '&';
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Invoice {
+    type Err = InvoiceParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, query) = s.split_once('?').unwrap_or((s, ""));
+        let mut parts = path.split('/');
+        let (Some(contract_id), Some(assignment_type), Some(state), Some(seal), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(InvoiceParseError::InvalidPath(path.to_owned()));
+        };
+
+        let mut invoice = Invoice::new(
+            contract_id.parse()?,
+            AssignmentType::with(assignment_type.parse()?),
+            state.parse()?,
+            seal.parse()?,
+        );
+
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            match param.split_once('=') {
+                Some((INVOICE_QUERY_EXPIRY, expiry)) => invoice.expiry = Some(expiry.parse()?),
+                Some((INVOICE_QUERY_ENVELOPE, key)) => {
+                    invoice.envelope_key =
+                        Some(key.parse().map_err(InvoiceParseError::EnvelopeKey)?)
+                }
+                Some((INVOICE_QUERY_TRANSPORT, transport)) => invoice
+                    .transports
+                    .push(TransportHint::from_query(transport)?)
+                    .map_err(|_| InvoiceParseError::TooManyTransports)?,
+                _ => return Err(InvoiceParseError::UnknownParam(param.to_owned())),
+            }
+        }
+
+        Ok(invoice)
+    }
+}
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum InvoiceParseError {
+    /// invalid invoice path '{0}'.
+    InvalidPath(String),
+
+    /// unknown invoice parameter '{0}'.
+    UnknownParam(String),
+
+    /// invoice contains too many transport hints.
+    TooManyTransports,
+
+    /// invalid contract id. Details: {0}
+    #[from]
+    ContractId(Baid64ParseError),
+
+    /// invalid integer value. Details: {0}
+    #[from]
+    Int(ParseIntError),
+
+    /// invalid data state. Details: {0}
+    #[from]
+    Data(hex::Error),
+
+    /// invalid envelope key. Details: {0}
+    EnvelopeKey(hex::Error),
+
+    /// invalid seal. Details: {0}
+    #[from]
+    Seal(XChainParseError<Baid64ParseError>),
+
+    /// invalid transport hint. Details: {0}
+    #[from]
+    Transport(InvalidRString),
+
+    /// invalid percent-encoding of transport hint '{0}'.
+    TransportEscape(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_from_str() {
+        let mut invoice = Invoice::new(
+            ContractId::from([0xAC; 32]),
+            AssignmentType::with(4000),
+            InvoiceState::Amount(FungibleState::Bits64(100)),
+            XChain::Bitcoin(SecretSeal::from([0x11; 32])),
+        );
+        assert_eq!(invoice.to_string().parse::<Invoice>().unwrap(), invoice);
+
+        invoice.state = InvoiceState::Data(DataState::from_hex("deadbeef").unwrap());
+        invoice.expiry = Some(840000);
+        invoice.envelope_key = Some(EnvelopeKey::from([0x42; 32]));
+        invoice
+            .transports
+            .push(TransportHint::from_str("rpcs://proxy.example.com/json-rpc").unwrap())
+            .unwrap();
+        let s = invoice.to_string();
+        assert!(s.contains("?expiry=840000&envelope=4242"));
+        assert!(s.ends_with("&transport=rpcs://proxy.example.com/json-rpc"));
+        assert_eq!(s.parse::<Invoice>().unwrap(), invoice);
+
+        let hint = "https://proxy.example.com/x?a=1&b=100%#top";
+        invoice
+            .transports
+            .push(TransportHint::from_str(hint).unwrap())
+            .unwrap();
+        let s = invoice.to_string();
+        assert!(s.ends_with("&transport=https://proxy.example.com/x?a%3D1%26b%3D100%25%23top"));
+        assert_eq!(s.parse::<Invoice>().unwrap(), invoice);
+        assert!(matches!(
+            format!("{s}%2").parse::<Invoice>(),
+            Err(InvoiceParseError::TransportEscape(_))
+        ));
+        assert!(matches!(
+            format!("{s}%+7").parse::<Invoice>(),
+            Err(InvoiceParseError::TransportEscape(_))
+        ));
+
+        assert!(invoice.is_expired(840001));
+        assert!(!invoice.is_expired(840000));
+
+        let invoice_id = invoice.invoice_id();
+        assert_eq!(invoice_id.to_string().parse::<InvoiceId>().unwrap(), invoice_id);
+        invoice.expiry = None;
+        assert_ne!(invoice.invoice_id(), invoice_id);
+    }
+
+    #[test]
+    fn strict_roundtrip() {
+        let invoice = Invoice::new(
+            ContractId::from([0xAC; 32]),
+            AssignmentType::with(4000),
+            InvoiceState::Void,
+            XChain::Liquid(SecretSeal::from([0x11; 32])),
+        );
+        let data = invoice
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .unwrap();
+        assert_eq!(
+            Invoice::from_strict_serialized::<{ u16::MAX as usize }>(data).unwrap(),
+            invoice
+        );
+    }
+}
@@ -0,0 +1,222 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protection against re-issuance of a known genesis.
+//!
+//! A genesis of a known asset may be re-published under a different chain or
+//! schema, getting a new contract id while keeping all the issuance data, and
+//! presented to users with altered out-of-band information. Issuers are
+//! advised to put a random uniqueness salt into the genesis, committed to the
+//! contract id, while wallets may detect re-published geneses with
+//! [`GenesisIndex`].
+//!
+//! Issuance may also be shared by a consortium of issuers: the genesis then
+//! commits to the identities of the issuers with [`IssuanceSplit`] and splits
+//! the inflation rights among a separate seal for each of them.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::{SmallBlob, TinyOrdSet};
+use amplify::{Bytes32, Wrapper};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::schema::MetaType;
+use crate::{
+    AssignmentType, ContractId, Genesis, LIB_NAME_RGB, MetaValue, MetadataError, Operation,
+};
+
+/// Metadata type reserved for the genesis uniqueness salt. The salt is a
+/// little-endian 64-bit integer and is valid in genesis of any schema not
+/// declaring metadata of this type.
+pub const GENESIS_SALT_TYPE: MetaType = MetaType::with(0xFFFF);
+
+/// Metadata type reserved for the [`IssuanceSplit`] of a genesis shared by
+/// multiple issuers. Valid in genesis of any schema not declaring metadata of
+/// this type.
+pub const ISSUANCE_SPLIT_TYPE: MetaType = MetaType::with(0xFFFD);
+
+/// Split of the issuance among multiple issuers.
+///
+/// Genesis committing to the split must assign exactly one right of the given
+/// type to a distinct seal per each issuer, so none of the issuers can
+/// exercise the other's share of the inflation rights.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct IssuanceSplit {
+    /// Owned state type of the inflation rights.
+    pub assignment_type: AssignmentType,
+    /// Commitments to the identities of the issuers, like hashes of their
+    /// public keys.
+    pub issuers: TinyOrdSet<Bytes32>,
+}
+
+impl StrictSerialize for IssuanceSplit {}
+impl StrictDeserialize for IssuanceSplit {}
+
+impl Genesis {
+    /// Returns uniqueness salt of the genesis, if present and valid.
+    pub fn salt(&self) -> Option<u64> {
+        let value = self.metadata.get(&GENESIS_SALT_TYPE)?;
+        let bytes = <[u8; 8]>::try_from(value.as_slice()).ok()?;
+        Some(u64::from_le_bytes(bytes))
+    }
+
+    /// Adds uniqueness salt to the genesis, changing its contract id.
+    pub fn add_salt(&mut self, salt: u64) -> Result<(), MetadataError> {
+        let value = SmallBlob::try_from(salt.to_le_bytes().to_vec()).expect("8 bytes");
+        let value = MetaValue::from(value);
+        self.metadata.add_value(GENESIS_SALT_TYPE, value)?;
+        self.invalidate_id();
+        Ok(())
+    }
+
+    /// Returns issuance split of the genesis, if present and valid.
+    pub fn issuance_split(&self) -> Option<IssuanceSplit> {
+        let value = self.metadata.get(&ISSUANCE_SPLIT_TYPE)?;
+        let split =
+            IssuanceSplit::from_strict_serialized::<{ u16::MAX as usize }>(value.to_inner())
+                .ok()?;
+        (split.issuers.len() > 1).then_some(split)
+    }
+
+    /// Commits the genesis to the issuance split, changing its contract id.
+    pub fn add_issuance_split(&mut self, split: &IssuanceSplit) -> Result<(), MetadataError> {
+        let value = split
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .expect("at most 255 issuers fit metadata");
+        self.metadata
+            .add_value(ISSUANCE_SPLIT_TYPE, MetaValue::from(value))?;
+        self.invalidate_id();
+        Ok(())
+    }
+
+    /// Computes fingerprint of the genesis issuance data, which doesn't depend
+    /// on the chain and schema claimed by the genesis.
+    pub fn fingerprint(&self) -> GenesisFingerprint {
+        let mut genesis = self.clone();
+        genesis.schema_id = strict_dumb!();
+        genesis.chain_net = strict_dumb!();
+        genesis.alt_layers1 = none!();
+        genesis.invalidate_id();
+        GenesisFingerprint(genesis.id().into_inner())
+    }
+}
+
+/// Fingerprint of the genesis issuance data (see [`Genesis::fingerprint`]).
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, Hex, Index, RangeOps)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct GenesisFingerprint(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+/// Genesis repeating the issuance data of an already known contract.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(
+    "genesis of contract {duplicate} repeats the genesis of contract {original} under a different \
+     chain or schema."
+)]
+pub struct DuplicateGenesis {
+    pub original: ContractId,
+    pub duplicate: ContractId,
+}
+
+/// Index of known geneses, detecting re-issuance of contracts.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct GenesisIndex(BTreeMap<GenesisFingerprint, ContractId>);
+
+impl GenesisIndex {
+    pub fn new() -> Self { default!() }
+
+    /// Returns id of a known contract sharing the issuance data with the
+    /// genesis.
+    pub fn find(&self, genesis: &Genesis) -> Option<ContractId> {
+        self.0.get(&genesis.fingerprint()).copied()
+    }
+
+    /// Registers genesis in the index, failing if it repeats a genesis of
+    /// another known contract. Registering the same genesis twice succeeds.
+    pub fn register(&mut self, genesis: &Genesis) -> Result<(), DuplicateGenesis> {
+        let contract_id = genesis.contract_id();
+        match self.0.insert(genesis.fingerprint(), contract_id) {
+            Some(original) if original != contract_id => {
+                self.0.insert(genesis.fingerprint(), original);
+                Err(DuplicateGenesis {
+                    original,
+                    duplicate: contract_id,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::ChainNet;
+
+    #[test]
+    fn reissue_detection() {
+        let genesis = Genesis::strict_dumb();
+        let mut salted = genesis.clone();
+        salted.add_salt(0x1234).unwrap();
+        assert_eq!(genesis.salt(), None);
+        assert_eq!(salted.salt(), Some(0x1234));
+        assert_ne!(salted.contract_id(), genesis.contract_id());
+        assert!(salted.add_salt(1).is_err());
+
+        let mut reissued = salted.clone();
+        reissued.chain_net = ChainNet::Testnet;
+        reissued.invalidate_id();
+        assert_ne!(reissued.contract_id(), salted.contract_id());
+        assert_eq!(reissued.fingerprint(), salted.fingerprint());
+
+        let mut index = GenesisIndex::new();
+        index.register(&genesis).unwrap();
+        index.register(&salted).unwrap();
+        index.register(&salted).unwrap();
+        assert_eq!(index.find(&reissued), Some(salted.contract_id()));
+        assert_eq!(
+            index.register(&reissued),
+            Err(DuplicateGenesis {
+                original: salted.contract_id(),
+                duplicate: reissued.contract_id()
+            })
+        );
+        assert_eq!(index.find(&reissued), Some(salted.contract_id()));
+    }
+}
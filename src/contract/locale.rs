@@ -0,0 +1,164 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Language-tagged variants of human-readable contract data, like a contract
+//! name or description.
+//!
+//! Global state types of [`crate::GlobalStateKind::Localized`] kind hold a
+//! strict-serialized [`LocalizedText`] value. Since the value contains
+//! the full set of the translations, ordered by the language tag, contract
+//! commits to all of them at once.
+
+use std::str::FromStr;
+
+use amplify::confinement::{SmallString, TinyOrdMap};
+use strict_encoding::stl::{Alpha, AlphaNumDash};
+use strict_encoding::{InvalidRString, RString, StrictDeserialize, StrictSerialize};
+
+use crate::{LIB_NAME_RGB, MapView};
+
+/// BCP-47 language tag, normalized to lowercase (for instance, `en-us`).
+#[derive(Wrapper, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, From, Display)]
+#[wrapper(Deref)]
+#[display(inner)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct LangTag(RString<Alpha, AlphaNumDash, 2, 35>);
+
+impl LangTag {
+    /// Returns primary language subtag (`en` for `en-us`).
+    pub fn language(&self) -> &str {
+        let s = self.0.as_str();
+        s.split('-').next().unwrap_or(s)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum LangTagError {
+    /// invalid characters in language tag. Details: {0}
+    #[from]
+    Chars(InvalidRString),
+
+    /// language tag '{0}' has empty or too long subtag.
+    Subtag(String),
+}
+
+impl FromStr for LangTag {
+    type Err = LangTagError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.split('-').any(|sub| sub.is_empty() || sub.len() > 8) {
+            return Err(LangTagError::Subtag(s.to_owned()));
+        }
+        let primary = s.split('-').next().unwrap_or_default();
+        if primary.len() < 2 {
+            return Err(LangTagError::Subtag(s.to_owned()));
+        }
+        Ok(Self(RString::from_str(&s.to_ascii_lowercase())?))
+    }
+}
+
+/// Set of translations of a text, indexed by the language tag.
+#[derive(Wrapper, Clone, PartialEq, Eq, Debug, Default, From)]
+#[wrapper(Deref)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct LocalizedText(TinyOrdMap<LangTag, SmallString>);
+
+impl StrictSerialize for LocalizedText {}
+impl StrictDeserialize for LocalizedText {}
+
+impl LocalizedText {
+    pub fn new() -> Self { default!() }
+
+    /// Read-only view of the translations by their language tag.
+    pub fn view(&self) -> MapView<'_, LangTag, SmallString> { MapView::new(&self.0) }
+
+    /// Adds translation, replacing previous translation for the same language
+    /// tag. Errors if the set already contains the maximal number of
+    /// translations.
+    pub fn add(&mut self, tag: LangTag, text: SmallString) -> Result<(), LangTag> {
+        if !self.0.contains_key(&tag) && self.0.len() >= u8::MAX as usize {
+            return Err(tag);
+        }
+        self.0.insert(tag, text).expect("checked above");
+        Ok(())
+    }
+
+    /// Selects translation matching the language tag exactly. If there is no
+    /// such translation, selects the first translation having the same
+    /// primary language.
+    pub fn select(&self, tag: &LangTag) -> Option<&str> {
+        self.0
+            .get(tag)
+            .or_else(|| {
+                self.0
+                    .iter()
+                    .find(|(t, _)| t.language() == tag.language())
+                    .map(|(_, text)| text)
+            })
+            .map(|text| text.as_str())
+    }
+
+    /// Selects translation like [`LocalizedText::select`], falling back to
+    /// the first available translation.
+    pub fn select_or_any(&self, tag: &LangTag) -> Option<&str> {
+        self.select(tag)
+            .or_else(|| self.0.values().next().map(|text| text.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn select() {
+        let mut text = LocalizedText::new();
+        text.add("en".parse().unwrap(), SmallString::try_from(s!("Token")).unwrap())
+            .unwrap();
+        text.add("pt-BR".parse().unwrap(), SmallString::try_from(s!("Ficha")).unwrap())
+            .unwrap();
+
+        assert_eq!(text.select(&"pt-br".parse().unwrap()), Some("Ficha"));
+        assert_eq!(text.select(&"pt-PT".parse().unwrap()), Some("Ficha"));
+        assert_eq!(text.select(&"en-GB".parse().unwrap()), Some("Token"));
+        assert_eq!(text.select(&"de".parse().unwrap()), None);
+        assert_eq!(text.select_or_any(&"de".parse().unwrap()), Some("Token"));
+
+        assert!(LangTag::from_str("e").is_err());
+        assert!(LangTag::from_str("en--us").is_err());
+        assert!(LangTag::from_str("en_US").is_err());
+        assert!(LangTag::from_str("en-abcdefghi").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merging of different versions of the same contract data, which may have
+//! different parts of the state and seals revealed. Used by peers to combine
+//! overlapping contract histories received from different sources.
+
+use amplify::confinement::SmallVec;
+
+use crate::{
+    Assign, AssignmentType, Assignments, BundleId, EAnchor, ExposedSeal, ExposedState, Extension,
+    Genesis, OpId, Operation, Transition, TransitionBundle, TypedAssigns,
+};
+
+/// Errors merging two versions of contract data.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum MergeRevealError {
+    /// operations {0} and {1} can't be merged since they have different ids.
+    OperationMismatch(OpId, OpId),
+
+    /// bundles {0} and {1} can't be merged since they have different ids.
+    BundleMismatch(BundleId, BundleId),
+
+    /// bundle {0} has too many transitions after the merge.
+    BundleOverflow(BundleId),
+
+    /// assignments of type {0} have different state types or number of
+    /// assigned seals.
+    AssignmentsMismatch(AssignmentType),
+
+    /// assignments commit to different seals or state.
+    AssignMismatch,
+
+    /// anchors for the same witness contain different proofs.
+    AnchorMismatch,
+}
+
+/// Merges data revealed in a different version of the same object, failing
+/// if the objects commit to a different data.
+pub trait MergeReveal: Sized {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError>;
+}
+
+impl<State: ExposedState, Seal: ExposedSeal> MergeReveal for Assign<State, Seal> {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError> {
+        if *self != other {
+            return Err(MergeRevealError::AssignMismatch);
+        }
+        let lock = match self {
+            Assign::Confidential { lock, .. } |
+            Assign::Revealed { lock, .. } |
+            Assign::ConfidentialSeal { lock, .. } |
+            Assign::ConfidentialState { lock, .. } => *lock,
+        };
+        let seal = self.revealed_seal().or_else(|| other.revealed_seal());
+        let state = self
+            .as_revealed_state()
+            .cloned()
+            .or_else(|| other.into_revealed_state());
+        *self = match (seal, state) {
+            (Some(seal), Some(state)) => Assign::Revealed { seal, state, lock },
+            (Some(seal), None) => Assign::ConfidentialState {
+                seal,
+                state: self.to_confidential_state(),
+                lock,
+            },
+            (None, Some(state)) => Assign::ConfidentialSeal {
+                seal: self.to_confidential_seal(),
+                state,
+                lock,
+            },
+            (None, None) => return Ok(()),
+        };
+        Ok(())
+    }
+}
+
+fn merge_vec<T: MergeReveal>(
+    ty: AssignmentType,
+    vec: &mut SmallVec<T>,
+    other: SmallVec<T>,
+) -> Result<(), MergeRevealError> {
+    if vec.len() != other.len() {
+        return Err(MergeRevealError::AssignmentsMismatch(ty));
+    }
+    for (item, other) in vec.iter_mut().zip(other) {
+        item.merge_reveal(other)?;
+    }
+    Ok(())
+}
+
+fn merge_typed<Seal: ExposedSeal>(
+    ty: AssignmentType,
+    assigns: &mut TypedAssigns<Seal>,
+    other: TypedAssigns<Seal>,
+) -> Result<(), MergeRevealError> {
+    match (assigns, other) {
+        (TypedAssigns::Declarative(a), TypedAssigns::Declarative(b)) => merge_vec(ty, a, b),
+        (TypedAssigns::Fungible(a), TypedAssigns::Fungible(b)) => merge_vec(ty, a, b),
+        (TypedAssigns::Structured(a), TypedAssigns::Structured(b)) => merge_vec(ty, a, b),
+        (TypedAssigns::Attachment(a), TypedAssigns::Attachment(b)) => merge_vec(ty, a, b),
+        _ => Err(MergeRevealError::AssignmentsMismatch(ty)),
+    }
+}
+
+impl<Seal: ExposedSeal> MergeReveal for Assignments<Seal> {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError> {
+        if let Some(ty) = self.keys().find(|ty| !other.contains_key(*ty)) {
+            return Err(MergeRevealError::AssignmentsMismatch(*ty));
+        }
+        for (ty, assigns) in other {
+            let Some(existing) = self.get_mut(&ty) else {
+                return Err(MergeRevealError::AssignmentsMismatch(ty));
+            };
+            merge_typed(ty, existing, assigns)?;
+        }
+        Ok(())
+    }
+}
+
+impl MergeReveal for Genesis {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError> {
+        if self.id() != other.id() {
+            return Err(MergeRevealError::OperationMismatch(self.id(), other.id()));
+        }
+        self.invalidate_id();
+        self.assignments.merge_reveal(other.assignments)
+    }
+}
+
+impl MergeReveal for Transition {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError> {
+        if self.id() != other.id() {
+            return Err(MergeRevealError::OperationMismatch(self.id(), other.id()));
+        }
+        self.invalidate_id();
+        self.assignments.merge_reveal(other.assignments)
+    }
+}
+
+impl MergeReveal for Extension {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError> {
+        if self.id() != other.id() {
+            return Err(MergeRevealError::OperationMismatch(self.id(), other.id()));
+        }
+        self.invalidate_id();
+        self.assignments.merge_reveal(other.assignments)
+    }
+}
+
+impl MergeReveal for TransitionBundle {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError> {
+        let bundle_id = self.bundle_id();
+        if bundle_id != other.bundle_id() {
+            return Err(MergeRevealError::BundleMismatch(bundle_id, other.bundle_id()));
+        }
+        for (opid, transition) in other.known_transitions {
+            match self.known_transitions.get_mut(&opid) {
+                Some(existing) => existing.merge_reveal(transition)?,
+                None => {
+                    self.known_transitions
+                        .insert(opid, transition)
+                        .map_err(|_| MergeRevealError::BundleOverflow(bundle_id))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MergeReveal for EAnchor {
+    fn merge_reveal(&mut self, other: Self) -> Result<(), MergeRevealError> {
+        if *self != other {
+            return Err(MergeRevealError::AnchorMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::FromHex;
+    use bp::dbc::Method;
+    use commit_verify::Conceal;
+
+    use super::*;
+    use crate::{AssignData, DataState, GraphSeal, RevealedData, XChain};
+
+    #[test]
+    fn merge_assign() {
+        let seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 13));
+        let state = RevealedData::with_salt(DataState::from_hex("010203").unwrap(), 42);
+        let revealed = AssignData::revealed(seal, state.clone());
+
+        let mut conf_seal = AssignData::ConfidentialSeal {
+            seal: seal.conceal(),
+            state: state.clone(),
+            lock: default!(),
+        };
+        let conf_state = AssignData::ConfidentialState {
+            seal,
+            state: state.conceal(),
+            lock: default!(),
+        };
+        conf_seal.merge_reveal(conf_state).unwrap();
+        assert_eq!(conf_seal.to_revealed(), revealed.to_revealed());
+
+        let mut confidential = revealed.conceal();
+        confidential.merge_reveal(revealed.clone()).unwrap();
+        assert_eq!(confidential.to_revealed(), revealed.to_revealed());
+
+        let other_seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 1, 13));
+        let mut other = AssignData::revealed(other_seal, state);
+        assert_eq!(other.merge_reveal(revealed), Err(MergeRevealError::AssignMismatch));
+    }
+}
@@ -22,11 +22,11 @@
 
 use std::collections::btree_map;
 
-use amplify::confinement::{SmallBlob, TinyOrdMap};
-use amplify::{confinement, Wrapper};
-use commit_verify::StrictHash;
+use amplify::confinement::{SmallBlob, TinyOrdMap, TinyVec};
+use amplify::{Wrapper, confinement};
+use commit_verify::{CommitId, MerkleHash, MerkleLeaves, StrictHash};
 
-use crate::{schema, LIB_NAME_RGB};
+use crate::{LIB_NAME_RGB, MapView, MetaCommitment, schema};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
 #[display(doc_comments)]
@@ -88,6 +88,9 @@ mod _serde {
 pub struct Metadata(TinyOrdMap<schema::MetaType, MetaValue>);
 
 impl Metadata {
+    /// Read-only view of the metadata values by their type.
+    pub fn view(&self) -> MapView<'_, schema::MetaType, MetaValue> { MapView::new(&self.0) }
+
     pub fn add_value(
         &mut self,
         ty: schema::MetaType,
@@ -99,6 +102,131 @@ impl Metadata {
         self.0.insert(ty, meta)?;
         Ok(())
     }
+
+    /// Returns Merkle root of the metadata, which is committed into the id of
+    /// operations of [`crate::Ffv::V1`] and later versions.
+    pub fn commitment(&self) -> MerkleHash { MerkleHash::merklize(self) }
+
+    /// Constructs proof of inclusion of a single metadata field into the
+    /// metadata commitment, which can be presented to a third party without
+    /// revealing the rest of the metadata.
+    ///
+    /// Operations of [`crate::Ffv::V0`] version commit to the strict hash of
+    /// the metadata, thus the proof can't be verified against their ids.
+    pub fn prove_field(&self, ty: schema::MetaType) -> Option<MetaProof> {
+        let index = self.0.keys().position(|t| *t == ty)?;
+        let nodes = self
+            .merkle_leaves()
+            .map(|leaf| leaf.commit_id())
+            .collect::<Vec<_>>();
+        let width = nodes.len() as u32;
+        let mut path = vec![];
+        if nodes.len() > 1 {
+            prove(&nodes, index, 0, width, &mut path);
+        }
+        Some(MetaProof {
+            width,
+            path: TinyVec::try_from(path).expect("tiny map has depth below 8"),
+        })
+    }
+
+    /// Verifies that a metadata field with a given value is a part of the
+    /// metadata committed to with the `root`.
+    pub fn verify_field_proof(
+        root: MerkleHash,
+        ty: schema::MetaType,
+        value: MetaValue,
+        proof: &MetaProof,
+    ) -> bool {
+        let mut node = MetaCommitment { ty, value }.commit_id();
+        for (depth, step) in proof.path.iter().enumerate().rev() {
+            let depth = depth as u8;
+            node = match step {
+                MetaProofStep::Single => MerkleHash::single(depth, proof.width, node),
+                MetaProofStep::Left(sibling) => {
+                    MerkleHash::branches(depth, proof.width, node, *sibling)
+                }
+                MetaProofStep::Right(sibling) => {
+                    MerkleHash::branches(depth, proof.width, *sibling, node)
+                }
+            };
+        }
+        node == root
+    }
+}
+
+/// Step of a Merkle path in [`MetaProof`], going from the root to the leaf.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = order)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum MetaProofStep {
+    /// Node has a single child.
+    #[strict_type(dumb)]
+    Single,
+    /// Proven leaf is in the left branch; contains hash of the right branch.
+    Left(MerkleHash),
+    /// Proven leaf is in the right branch; contains hash of the left branch.
+    Right(MerkleHash),
+}
+
+/// Proof of inclusion of a single field into [`Metadata`] commitment.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct MetaProof {
+    /// Number of fields in the metadata.
+    pub width: u32,
+    pub path: TinyVec<MetaProofStep>,
+}
+
+// Mirrors merklization procedure from `commit_verify`, which splits each
+// branch into the halves with the left one being larger for odd widths.
+fn merklize(nodes: &[MerkleHash], depth: u8, width: u32) -> MerkleHash {
+    match nodes {
+        [] => MerkleHash::void(depth, width),
+        [node] => MerkleHash::single(depth, width, *node),
+        [node1, node2] => MerkleHash::branches(depth, width, *node1, *node2),
+        _ => {
+            let div = nodes.len() / 2 + nodes.len() % 2;
+            MerkleHash::branches(
+                depth,
+                width,
+                merklize(&nodes[..div], depth + 1, width),
+                merklize(&nodes[div..], depth + 1, width),
+            )
+        }
+    }
+}
+
+fn prove(nodes: &[MerkleHash], index: usize, depth: u8, width: u32, path: &mut Vec<MetaProofStep>) {
+    match nodes {
+        [_] => path.push(MetaProofStep::Single),
+        [node1, node2] => path.push(if index == 0 {
+            MetaProofStep::Left(*node2)
+        } else {
+            MetaProofStep::Right(*node1)
+        }),
+        _ => {
+            let div = nodes.len() / 2 + nodes.len() % 2;
+            if index < div {
+                path.push(MetaProofStep::Left(merklize(&nodes[div..], depth + 1, width)));
+                prove(&nodes[..div], index, depth + 1, width, path);
+            } else {
+                path.push(MetaProofStep::Right(merklize(&nodes[..div], depth + 1, width)));
+                prove(&nodes[div..], index - div, depth + 1, width, path);
+            }
+        }
+    }
 }
 
 impl<'a> IntoIterator for &'a Metadata {
@@ -107,3 +235,52 @@ impl<'a> IntoIterator for &'a Metadata {
 
     fn into_iter(self) -> Self::IntoIter { self.0.iter() }
 }
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::schema::MetaType;
+    use crate::{Ffv, Genesis, Operation};
+
+    #[test]
+    fn field_proofs() {
+        for width in 1..=9u16 {
+            let mut metadata = Metadata::default();
+            for ty in 0..width {
+                metadata
+                    .add_value(MetaType::with(ty), MetaValue::from(small_vec!(ty as u8)))
+                    .unwrap();
+            }
+            let root = metadata.commitment();
+            for ty in 0..width {
+                let ty = MetaType::with(ty);
+                let value = metadata.get(&ty).unwrap().clone();
+                let proof = metadata.prove_field(ty).unwrap();
+                assert!(Metadata::verify_field_proof(root, ty, value.clone(), &proof));
+                let wrong = MetaValue::from(small_vec!(0xFF));
+                assert!(!Metadata::verify_field_proof(root, ty, wrong, &proof));
+            }
+            assert_eq!(metadata.prove_field(MetaType::with(width)), None);
+        }
+    }
+
+    #[test]
+    fn versioned_commitment() {
+        let mut genesis = Genesis::strict_dumb();
+        genesis
+            .metadata
+            .add_value(MetaType::with(1), MetaValue::from(small_vec!(1)))
+            .unwrap();
+        let root = genesis.metadata.commitment();
+
+        assert_eq!(genesis.ffv, Ffv::V0);
+        assert_ne!(genesis.commit().metadata, root);
+        let v0 = genesis.id();
+
+        genesis.ffv = Ffv::V1;
+        assert_eq!(genesis.commit().metadata, root);
+        assert_ne!(genesis.id(), v0);
+    }
+}
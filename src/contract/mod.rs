@@ -24,50 +24,132 @@ mod meta;
 mod global;
 mod data;
 mod fungible;
+mod pedersen;
 mod attachment;
 mod state;
 mod anchor;
+mod chain;
+mod invoice;
+mod issuance;
+mod expiry;
+mod locale;
+mod merge;
+mod pool;
+#[cfg(feature = "std-helpers")]
+mod padding;
+#[cfg(feature = "std-helpers")]
+mod reachability;
+#[cfg(feature = "std-helpers")]
+mod render;
+mod ricardian;
+#[cfg(feature = "std-helpers")]
+mod snapshot;
+#[cfg(feature = "std-helpers")]
+mod stats;
+mod store;
+#[cfg(feature = "std-helpers")]
+mod supply;
+#[cfg(feature = "std-helpers")]
+mod airdrop;
+#[cfg(feature = "std-helpers")]
+mod refund;
+#[cfg(feature = "std-helpers")]
+mod template;
+#[cfg(feature = "std-helpers")]
+mod witness;
 pub mod seal;
 pub mod assignments;
 mod operations;
 mod bundle;
 #[allow(clippy::module_inception)]
 mod contract;
+mod view;
 mod xchain;
 mod commit;
 
-pub use anchor::{DbcError, DbcProof, EAnchor, Layer1, WitnessAnchor};
+#[cfg(feature = "std-helpers")]
+pub use airdrop::{AIRDROP_CHUNK_SIZE, Airdrop, AirdropError, AirdropPayment, AirdropTransition};
+pub use anchor::{
+    DbcError, DbcProof, EAnchor, Layer1, UnsignedAnchorError, VerifyUnsigned, WitnessAnchor,
+};
 pub use assignments::{
-    Assign, AssignAttach, AssignData, AssignFungible, AssignRights, Assignments, AssignmentsRef,
-    TypedAssigns,
+    Assign, AssignAttach, AssignData, AssignFungible, AssignRef, AssignRights, Assignments,
+    AssignmentsRef, AssignsVisitor, TypedAssigns,
 };
 pub use attachment::{AttachId, ConcealedAttach, RevealedAttach};
-pub use bundle::{BundleId, InputMap, TransitionBundle, Vin};
+pub use bundle::{
+    BundleError, BundleId, InputMap, PARALLEL_HASHING_THRESHOLD, SigningSummary, TransitionBundle,
+    Vin,
+};
+pub use chain::{
+    BITCOIN_MAINNET_GENESIS, BITCOIN_REGTEST_GENESIS, BITCOIN_SIGNET_GENESIS,
+    BITCOIN_TESTNET_GENESIS, ChainError, ChainNet, ChainTrustAnchors,
+};
 pub use commit::{
-    AssignmentCommitment, AssignmentIndex, BaseCommitment, BundleDisclosure, ContractId,
-    DiscloseHash, GlobalCommitment, OpCommitment, OpDisclose, OpId, TypeCommitment,
+    AssignmentCommitment, AssignmentIndex, BaseCommitment, BundleDisclosure, ChainContractId,
+    ContractId, DiscloseHash, GlobalCommitment, MetaCommitment, OpCommitment, OpDisclose, OpId,
+    OpIdParseError, TypeCommitment,
 };
 pub use contract::{
     AssignmentWitness, ContractHistory, ContractState, GlobalOrd, KnownState, Opout,
-    OpoutParseError, OutputAssignment,
+    OpoutParseError, OutputAssignment, SealConflict, SharedContractState, SpendableOutput,
 };
 pub use data::{ConcealedData, DataState, RevealedData, VoidState};
+pub use expiry::{ALLOCATION_EXPIRY_TYPE, AllocationExpiries, AllocationExpiry};
 pub use fungible::{
-    AssetTag, BlindingFactor, BlindingParseError, ConcealedValue, FungibleState,
-    InvalidFieldElement, NoiseDumb, PedersenCommitment, RangeProof, RangeProofError, RevealedValue,
+    AssetTag, BlindingFactor, BlindingParseError, Bulletproofs, ConcealedValue, FungibleState,
+    InvalidCommitment, InvalidFieldElement, NoiseDumb, PedersenCommitment, RangeProof,
+    RangeProofError, RangeProofScheme, RevealedValue,
 };
 pub use global::{GlobalState, GlobalValues};
-pub use meta::{MetaValue, Metadata, MetadataError};
+pub use invoice::{
+    INVOICE_QUERY_ENVELOPE, INVOICE_QUERY_EXPIRY, INVOICE_QUERY_TRANSPORT, Invoice, InvoiceId,
+    InvoiceParseError, InvoiceState, TransportHint,
+};
+pub use issuance::{
+    DuplicateGenesis, GENESIS_SALT_TYPE, GenesisFingerprint, GenesisIndex, ISSUANCE_SPLIT_TYPE,
+    IssuanceSplit,
+};
+pub use locale::{LangTag, LangTagError, LocalizedText};
+pub use merge::{MergeReveal, MergeRevealError};
+pub use meta::{MetaProof, MetaProofStep, MetaValue, Metadata, MetadataError};
 pub use operations::{
-    AssetTags, Extension, Genesis, Identity, Input, Inputs, OpRef, Operation, Redeemed, Transition,
-    Valencies,
+    AssetTags, ConcealInconsistency, Extension, Genesis, Identity, Input, Inputs, OpRef, Operation,
+    Redeemed, Transition, Valencies,
 };
+#[cfg(feature = "std-helpers")]
+pub use padding::{ConstantShape, PaddingError};
+#[cfg(feature = "pedersen")]
+pub use pedersen::Secp256k1Zkp;
+pub use pedersen::{PedersenEngine, PedersenError, VerifyOnly, pedersen_engine};
+pub use pool::{AnchoredBundle, BundleAnchor, BundlePool, LegacyBundles, PoolError};
+#[cfg(feature = "std-helpers")]
+pub use reachability::Reachability;
+#[cfg(feature = "std-helpers")]
+pub use refund::{Refund, RefundError};
+#[cfg(feature = "std-helpers")]
+pub use render::{RenderedAllocation, StateRenderer};
+pub use ricardian::RicardianHash;
 pub use seal::{
-    ExposedSeal, GenesisSeal, GraphSeal, OutputSeal, SecretSeal, TxoSeal, WitnessOrd, WitnessPos,
-    XGenesisSeal, XGraphSeal, XOutputSeal, XWitnessId, XWitnessTx,
+    ExposedSeal, GenesisSeal, GraphSeal, OutputSeal, SealResolutionError, SecretSeal, TxoSeal,
+    WitnessOrd, WitnessPos, XGenesisSeal, XGraphSeal, XOutputSeal, XWitnessId, XWitnessTx,
+};
+#[cfg(feature = "std-helpers")]
+pub use snapshot::{ContractStateSnapshot, SnapshotError, SnapshotHash, SnapshotVersion};
+pub use state::{
+    ConcealedState, ConfidentialState, ExposedState, RevealedState, StateAtom, StateType,
 };
-pub use state::{ConcealedState, ConfidentialState, ExposedState, RevealedState, StateType};
+#[cfg(feature = "std-helpers")]
+pub use stats::ContractStats;
+pub use store::NodeStore;
+#[cfg(feature = "std-helpers")]
+pub use supply::{AssetSupply, SupplyReport};
+#[cfg(feature = "std-helpers")]
+pub use template::{TemplateError, TransitionBuilder, TransitionTemplate};
+pub use view::{MapView, SetView};
+#[cfg(feature = "std-helpers")]
+pub use witness::{WitnessTemplate, WitnessTemplateError};
 pub use xchain::{
-    AltLayer1, AltLayer1Set, XChain, XChainParseError, XOutpoint, XCHAIN_BITCOIN_PREFIX,
-    XCHAIN_LIQUID_PREFIX,
+    AltLayer1, AltLayer1Set, XCHAIN_BITCOIN_PREFIX, XCHAIN_LIQUID_PREFIX, XChain, XChainParseError,
+    XOutpoint,
 };
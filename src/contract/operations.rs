@@ -21,24 +21,28 @@
 // limitations under the License.
 
 use std::cmp::Ordering;
-use std::collections::{btree_map, btree_set, BTreeMap};
+use std::collections::{BTreeMap, BTreeSet, btree_map, btree_set};
+use std::fmt::{self, Debug, Display, Formatter};
 use std::iter;
+use std::sync::OnceLock;
 
 use amplify::confinement::{Confined, SmallOrdSet, TinyOrdMap, TinyOrdSet};
-use amplify::Wrapper;
+use amplify::{Bytes32, Wrapper};
 use commit_verify::{
     CommitEncode, CommitEngine, CommitId, Conceal, MerkleHash, MerkleLeaves, ReservedBytes,
     StrictHash,
 };
 use strict_encoding::stl::AsciiPrintable;
-use strict_encoding::{RString, StrictDeserialize, StrictEncode, StrictSerialize};
+use strict_encoding::{RString, StrictDeserialize, StrictEncode, StrictSerialize, StrictType};
 
 use crate::schema::{self, ExtensionType, OpFullType, OpType, SchemaId, TransitionType};
+use crate::summary::{TruncatedHex, fmt_list};
 use crate::{
     AltLayer1Set, AssetTag, Assign, AssignmentIndex, AssignmentType, Assignments, AssignmentsRef,
-    ConcealedAttach, ConcealedData, ConcealedValue, ContractId, DiscloseHash, ExposedState, Ffv,
-    GenesisSeal, GlobalState, GraphSeal, Metadata, OpDisclose, OpId, Opout, SecretSeal,
-    TypedAssigns, VoidState, XChain, LIB_NAME_RGB,
+    ChainNet, ConcealedAttach, ConcealedData, ConcealedValue, ContractId, DiscloseHash,
+    ExposedSeal, ExposedState, Ffv, GenesisSeal, GlobalState, GraphSeal, LIB_NAME_RGB, MapView,
+    Metadata, OpDisclose, OpId, Opout, SecretSeal, SetView, SigningSummary, StateAtom,
+    TypedAssigns, VoidState, XChain,
 };
 
 #[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, From)]
@@ -55,6 +59,11 @@ use crate::{
 )]
 pub struct AssetTags(TinyOrdMap<AssignmentType, AssetTag>);
 
+impl AssetTags {
+    /// Read-only view of the asset tags by the fungible assignment type.
+    pub fn view(&self) -> MapView<'_, AssignmentType, AssetTag> { MapView::new(&self.0) }
+}
+
 #[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, From)]
 #[wrapper(Deref)]
 #[wrapper_mut(DerefMut)]
@@ -69,6 +78,11 @@ pub struct AssetTags(TinyOrdMap<AssignmentType, AssetTag>);
 )]
 pub struct Valencies(TinyOrdSet<schema::ValencyType>);
 
+impl Valencies {
+    /// Read-only view of the valency types.
+    pub fn view(&self) -> SetView<'_, schema::ValencyType> { SetView::new(&self.0) }
+}
+
 impl<'a> IntoIterator for &'a Valencies {
     type Item = schema::ValencyType;
     type IntoIter = iter::Copied<btree_set::Iter<'a, schema::ValencyType>>;
@@ -90,6 +104,11 @@ impl<'a> IntoIterator for &'a Valencies {
 )]
 pub struct Redeemed(TinyOrdMap<schema::ValencyType, OpId>);
 
+impl Redeemed {
+    /// Read-only view of the operations redeemed by each valency type.
+    pub fn view(&self) -> MapView<'_, schema::ValencyType, OpId> { MapView::new(&self.0) }
+}
+
 impl<'a> IntoIterator for &'a Redeemed {
     type Item = (&'a schema::ValencyType, &'a OpId);
     type IntoIter = btree_map::Iter<'a, schema::ValencyType, OpId>;
@@ -107,8 +126,31 @@ impl<'a> IntoIterator for &'a Redeemed {
     derive(Serialize, Deserialize),
     serde(crate = "serde_crate", transparent)
 )]
+/// Ordered set of the outputs spent by a state transition.
+///
+/// Each input points to an exact parent output by its operation id,
+/// assignment type and position index within the assignments of that type, so
+/// a transition spending one of several same-typed assignments of a parent is
+/// unambiguous. Inputs are committed to as merkle leaves of the operation id.
 pub struct Inputs(SmallOrdSet<Input>);
 
+impl Inputs {
+    /// Read-only view of the inputs.
+    pub fn view(&self) -> SetView<'_, Input> { SetView::new(&self.0) }
+
+    /// Detects whether the given parent output is spent by these inputs.
+    pub fn spends(&self, opout: Opout) -> bool { self.0.contains(&Input::with(opout)) }
+
+    /// Returns outputs of the given parent operation spent by these inputs,
+    /// ordered by assignment type and position index.
+    pub fn from_parent(&self, op: OpId) -> impl Iterator<Item = Opout> + '_ {
+        self.0
+            .iter()
+            .map(|input| input.prev_out)
+            .filter(move |prev_out| prev_out.op == op)
+    }
+}
+
 impl<'a> IntoIterator for &'a Inputs {
     type Item = Input;
     type IntoIter = iter::Copied<btree_set::Iter<'a, Input>>;
@@ -134,6 +176,7 @@ impl MerkleLeaves for Inputs {
     serde(crate = "serde_crate", rename_all = "camelCase")
 )]
 #[display("{prev_out}")]
+/// Transition input spending a specific parent output.
 pub struct Input {
     pub prev_out: Opout,
     #[cfg_attr(feature = "serde", serde(skip))]
@@ -149,6 +192,22 @@ impl Input {
     }
 }
 
+/// Errors detected by [`Operation::verify_conceal_consistency`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ConcealInconsistency {
+    /// concealing operation {0} changes its id to {1}.
+    OpId(OpId, OpId),
+
+    /// concealed form of assignment #{no} of type {ty} in operation {opid}
+    /// doesn't match its revealed seal or state.
+    Assignment {
+        opid: OpId,
+        ty: AssignmentType,
+        no: u16,
+    },
+}
+
 /// RGB contract operation API, defined as trait
 ///
 /// Implemented by all contract operation types (see [`OpType`]):
@@ -169,6 +228,13 @@ pub trait Operation {
     /// serialization
     fn id(&self) -> OpId;
 
+    /// Returns [`OpId`] computed on the first call and cached by the
+    /// operation for the subsequent calls.
+    ///
+    /// If the operation data are modified after the id was cached, the cache
+    /// must be cleared with `invalidate_id` method of the operation.
+    fn cached_id(&self) -> OpId;
+
     /// Returns [`ContractId`] this operation belongs to.
     fn contract_id(&self) -> ContractId;
 
@@ -188,7 +254,7 @@ pub trait Operation {
     fn globals(&self) -> &GlobalState;
     fn valencies(&self) -> &Valencies;
 
-    fn assignments(&self) -> AssignmentsRef;
+    fn assignments(&self) -> AssignmentsRef<'_>;
 
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>>;
 
@@ -197,6 +263,53 @@ pub trait Operation {
     /// indexed rights.
     fn inputs(&self) -> Inputs;
 
+    /// Returns pointers to all outputs of the operation. Output number is the
+    /// position of the assignment among the assignments of the same type; it
+    /// doesn't depend on whether the assignment seal or state are concealed,
+    /// so the outputs are the same for all disclosure forms of the operation.
+    fn outputs(&self) -> BTreeSet<Opout> {
+        let opid = self.id();
+        let assignments = self.assignments();
+        assignments
+            .types()
+            .into_iter()
+            .flat_map(|ty| (0..assignments.count(ty)).map(move |no| Opout::new(opid, ty, no)))
+            .collect()
+    }
+
+    /// Detects whether the operation has the output the pointer refers to.
+    fn has_output(&self, opout: Opout) -> bool {
+        opout.op == self.id() && opout.no < self.assignments().count(opout.ty)
+    }
+
+    /// Returns ids of the operations this operation directly depends on: the
+    /// ones whose outputs are spent by a state transition, or whose valencies
+    /// are redeemed by a state extension. Always empty for genesis.
+    fn parent_ids(&self) -> BTreeSet<OpId>;
+
+    /// Checks that each assignment re-derives its concealed form and that
+    /// concealing the operation doesn't change its id. Failing this check
+    /// indicates a bug in the software which has constructed the operation.
+    fn verify_conceal_consistency(&self) -> Result<(), ConcealInconsistency>;
+
+    /// Checks that the operation is in the canonical form. Maps and sets
+    /// within the operation are always canonical: their strict encoding puts
+    /// the items in the consensus order, and decoding rejects any other order.
+    /// The order of list items is significant and is never changed. Thus, the
+    /// only non-canonical form is an assignment type with an empty list of
+    /// assignments, which is removed by the `canonicalize` method of the
+    /// operation.
+    fn is_canonical(&self) -> bool { self.assignments().is_canonical() }
+
+    /// Downcasts the operation to [`Genesis`], if it is one.
+    fn as_genesis(&self) -> Option<&Genesis> { None }
+
+    /// Downcasts the operation to [`Transition`], if it is one.
+    fn as_transition(&self) -> Option<&Transition> { None }
+
+    /// Downcasts the operation to [`Extension`], if it is one.
+    fn as_extension(&self) -> Option<&Extension> { None }
+
     /// Provides summary about parts of the operation which are revealed.
     fn disclose(&self) -> OpDisclose {
         fn proc_seals<State: ExposedState>(
@@ -286,6 +399,35 @@ impl Identity {
     pub fn is_anonymous(&self) -> bool { self == &default!() }
 }
 
+/// Lazily computed [`OpId`] of an operation.
+///
+/// The cache is not a part of the operation data: it is skipped by the
+/// encodings and ignored by the comparisons.
+#[derive(Clone, Default)]
+pub struct OpIdCache(OnceLock<OpId>);
+
+impl PartialEq for OpIdCache {
+    fn eq(&self, _: &Self) -> bool { true }
+}
+
+impl Eq for OpIdCache {}
+
+// Required for deriving `StrictDumb` on operations; the cache itself is never
+// encoded.
+impl StrictType for OpIdCache {
+    const STRICT_LIB_NAME: &'static str = LIB_NAME_RGB;
+}
+
+impl Debug for OpIdCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OpIdCache").field(&self.0.get()).finish()
+    }
+}
+
+impl OpIdCache {
+    fn get_or_init(&self, f: impl FnOnce() -> OpId) -> OpId { *self.0.get_or_init(f) }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
@@ -300,7 +442,7 @@ pub struct Genesis {
     pub flags: ReservedBytes<1, 0>,
     pub timestamp: i64,
     pub issuer: Identity,
-    pub testnet: bool,
+    pub chain_net: ChainNet,
     pub alt_layers1: AltLayer1Set,
     pub asset_tags: AssetTags,
     pub metadata: Metadata,
@@ -308,6 +450,9 @@ pub struct Genesis {
     pub assignments: Assignments<GenesisSeal>,
     pub valencies: Valencies,
     pub validator: ReservedBytes<1, 0>,
+    #[strict_type(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id_cache: OpIdCache,
 }
 
 impl StrictSerialize for Genesis {}
@@ -332,6 +477,9 @@ pub struct Extension {
     pub valencies: Valencies,
     pub validator: ReservedBytes<1, 0>,
     pub witness: ReservedBytes<2, 0>,
+    #[strict_type(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id_cache: OpIdCache,
 }
 
 impl StrictSerialize for Extension {}
@@ -364,6 +512,9 @@ pub struct Transition {
     pub valencies: Valencies,
     pub validator: ReservedBytes<1, 0>,
     pub witness: ReservedBytes<2, 0>,
+    #[strict_type(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id_cache: OpIdCache,
 }
 
 impl StrictSerialize for Transition {}
@@ -377,6 +528,127 @@ impl PartialOrd for Transition {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
 }
 
+/// Writes numbers of the operation items, which are printed by
+/// [`fmt_details`].
+fn fmt_counts(op: &impl Operation, f: &mut Formatter<'_>) -> fmt::Result {
+    let assignments = op.assignments();
+    let assignments = assignments
+        .types()
+        .into_iter()
+        .map(|ty| assignments.count(ty) as usize)
+        .sum::<usize>();
+    let globals = op
+        .globals()
+        .values()
+        .map(|values| values.len())
+        .sum::<usize>();
+    write!(
+        f,
+        "{} inputs, {} metadata, {} global, {} assignments, {} valencies",
+        op.inputs().len(),
+        op.metadata().len(),
+        globals,
+        assignments,
+        op.valencies().len()
+    )
+}
+
+/// Writes size-bounded details of the operation, one item per line.
+fn fmt_details(op: &impl Operation, f: &mut Formatter<'_>) -> fmt::Result {
+    fmt_list(
+        f,
+        op.inputs()
+            .iter()
+            .map(|input| format!("input {}", input.prev_out)),
+    )?;
+    fmt_list(
+        f,
+        op.metadata()
+            .iter()
+            .map(|(ty, value)| format!("metadata {ty}: {}", TruncatedHex(value.as_slice()))),
+    )?;
+    fmt_list(
+        f,
+        op.globals().iter().flat_map(|(ty, values)| {
+            values.iter().enumerate().map(move |(no, value)| {
+                format!("global {ty}/{no}: {}", TruncatedHex(value.as_slice()))
+            })
+        }),
+    )?;
+    let assignments = op.assignments().flat();
+    fmt_list(
+        f,
+        assignments.iter_flat().map(|(ty, no, assign)| {
+            let seal = if assign.revealed_seal().is_some() {
+                "revealed"
+            } else {
+                "concealed"
+            };
+            let state = match assign.to_state_atom() {
+                StateAtom::Revealed(_) => "revealed",
+                StateAtom::Concealed(_) => "concealed",
+            };
+            format!(
+                "assignment {ty}/{no}: {} state, {seal} seal, {state} state",
+                assign.state_type()
+            )
+        }),
+    )?;
+    fmt_list(
+        f,
+        op.valencies()
+            .iter()
+            .map(|valency| format!("valency {valency}")),
+    )
+}
+
+/// Displays a summary of the genesis; the alternate format (`{:#}`) adds
+/// size-bounded details of its data.
+impl Display for Genesis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "genesis {} of schema {}: ", self.id(), self.schema_id)?;
+        fmt_counts(self, f)?;
+        if f.alternate() {
+            fmt_details(self, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a summary of the state extension; the alternate format (`{:#}`)
+/// adds size-bounded details of its data.
+impl Display for Extension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let redeemed = self.redeemed.len();
+        let (id, ty) = (self.id(), self.extension_type);
+        write!(f, "extension {id} of type {ty}: {redeemed} redeemed, ")?;
+        fmt_counts(self, f)?;
+        if f.alternate() {
+            fmt_list(
+                f,
+                self.redeemed
+                    .iter()
+                    .map(|(valency, opid)| format!("redeem {valency} of {opid}")),
+            )?;
+            fmt_details(self, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a summary of the state transition; the alternate format (`{:#}`)
+/// adds size-bounded details of its data.
+impl Display for Transition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "transition {} of type {}: ", self.id(), self.transition_type)?;
+        fmt_counts(self, f)?;
+        if f.alternate() {
+            fmt_details(self, f)?;
+        }
+        Ok(())
+    }
+}
+
 impl Conceal for Genesis {
     type Concealed = Self;
     fn conceal(&self) -> Self::Concealed {
@@ -428,14 +700,47 @@ impl CommitEncode for Extension {
     fn commit_encode(&self, e: &mut CommitEngine) { e.commit_to_serialized(&self.commit()) }
 }
 
+impl Genesis {
+    /// Clears the cached operation id (see [`Operation::cached_id`]).
+    pub fn invalidate_id(&mut self) { self.id_cache = default!(); }
+
+    /// Brings the operation into the canonical form (see
+    /// [`Operation::is_canonical`]). Doesn't change the operation id.
+    pub fn canonicalize(&mut self) { self.assignments.canonicalize(); }
+}
+
 impl Transition {
+    /// Clears the cached operation id (see [`Operation::cached_id`]).
+    pub fn invalidate_id(&mut self) { self.id_cache = default!(); }
+
+    /// Brings the operation into the canonical form (see
+    /// [`Operation::is_canonical`]). Doesn't change the operation id.
+    pub fn canonicalize(&mut self) { self.assignments.canonicalize(); }
+
     /// Returns reference to information about the owned rights in form of
     /// [`Inputs`] wrapper structure which this operation updates with
     /// state transition ("parent owned rights").
     pub fn prev_state(&self) -> &Inputs { &self.inputs }
+
+    /// Digest committed by the bundle input map and, through it, on-chain.
+    /// Equals to the operation id.
+    pub fn signing_digest(&self) -> Bytes32 { self.id().into_inner() }
+
+    /// Summary of the transition committed by
+    /// [`Transition::signing_digest`].
+    pub fn signing_summary(&self) -> SigningSummary {
+        SigningSummary::with(self.signing_digest(), [self])
+    }
 }
 
 impl Extension {
+    /// Clears the cached operation id (see [`Operation::cached_id`]).
+    pub fn invalidate_id(&mut self) { self.id_cache = default!(); }
+
+    /// Brings the operation into the canonical form (see
+    /// [`Operation::is_canonical`]). Doesn't change the operation id.
+    pub fn canonicalize(&mut self) { self.assignments.canonicalize(); }
+
     /// Returns reference to information about the public rights (in form of
     /// [`Redeemed`] wrapper structure), defined with "parent" state
     /// extensions (i.e. those finalized with the current state transition) or
@@ -454,6 +759,9 @@ impl Operation for Genesis {
     #[inline]
     fn id(&self) -> OpId { self.commit_id() }
 
+    #[inline]
+    fn cached_id(&self) -> OpId { self.id_cache.get_or_init(|| self.id()) }
+
     #[inline]
     fn contract_id(&self) -> ContractId { ContractId::from_inner(self.id().into_inner()) }
 
@@ -473,7 +781,7 @@ impl Operation for Genesis {
     fn valencies(&self) -> &Valencies { &self.valencies }
 
     #[inline]
-    fn assignments(&self) -> AssignmentsRef { (&self.assignments).into() }
+    fn assignments(&self) -> AssignmentsRef<'_> { (&self.assignments).into() }
 
     #[inline]
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>> {
@@ -484,6 +792,14 @@ impl Operation for Genesis {
 
     #[inline]
     fn inputs(&self) -> Inputs { empty!() }
+
+    fn parent_ids(&self) -> BTreeSet<OpId> { empty!() }
+
+    fn verify_conceal_consistency(&self) -> Result<(), ConcealInconsistency> {
+        verify_conceal(self, &self.assignments)
+    }
+
+    fn as_genesis(&self) -> Option<&Genesis> { Some(self) }
 }
 
 impl Operation for Extension {
@@ -496,6 +812,9 @@ impl Operation for Extension {
     #[inline]
     fn id(&self) -> OpId { self.commit_id() }
 
+    #[inline]
+    fn cached_id(&self) -> OpId { self.id_cache.get_or_init(|| self.id()) }
+
     #[inline]
     fn contract_id(&self) -> ContractId { self.contract_id }
 
@@ -515,7 +834,7 @@ impl Operation for Extension {
     fn valencies(&self) -> &Valencies { &self.valencies }
 
     #[inline]
-    fn assignments(&self) -> AssignmentsRef { (&self.assignments).into() }
+    fn assignments(&self) -> AssignmentsRef<'_> { (&self.assignments).into() }
 
     #[inline]
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>> {
@@ -526,6 +845,14 @@ impl Operation for Extension {
 
     #[inline]
     fn inputs(&self) -> Inputs { empty!() }
+
+    fn parent_ids(&self) -> BTreeSet<OpId> { self.redeemed.values().copied().collect() }
+
+    fn verify_conceal_consistency(&self) -> Result<(), ConcealInconsistency> {
+        verify_conceal(self, &self.assignments)
+    }
+
+    fn as_extension(&self) -> Option<&Extension> { Some(self) }
 }
 
 impl Operation for Transition {
@@ -538,6 +865,9 @@ impl Operation for Transition {
     #[inline]
     fn id(&self) -> OpId { self.commit_id() }
 
+    #[inline]
+    fn cached_id(&self) -> OpId { self.id_cache.get_or_init(|| self.id()) }
+
     #[inline]
     fn contract_id(&self) -> ContractId { self.contract_id }
 
@@ -557,7 +887,7 @@ impl Operation for Transition {
     fn valencies(&self) -> &Valencies { &self.valencies }
 
     #[inline]
-    fn assignments(&self) -> AssignmentsRef { (&self.assignments).into() }
+    fn assignments(&self) -> AssignmentsRef<'_> { (&self.assignments).into() }
 
     #[inline]
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>> {
@@ -565,6 +895,37 @@ impl Operation for Transition {
     }
 
     fn inputs(&self) -> Inputs { self.inputs.clone() }
+
+    fn parent_ids(&self) -> BTreeSet<OpId> {
+        self.inputs.iter().map(|input| input.prev_out.op).collect()
+    }
+
+    fn verify_conceal_consistency(&self) -> Result<(), ConcealInconsistency> {
+        verify_conceal(self, &self.assignments)
+    }
+
+    fn as_transition(&self) -> Option<&Transition> { Some(self) }
+}
+
+fn verify_conceal<Op, Seal>(
+    op: &Op,
+    assignments: &Assignments<Seal>,
+) -> Result<(), ConcealInconsistency>
+where
+    Op: Operation + Conceal<Concealed = Op>,
+    Seal: ExposedSeal,
+{
+    let opid = op.id();
+    for (ty, assigns) in assignments.iter() {
+        if let Some(no) = assigns.find_conceal_inconsistency() {
+            return Err(ConcealInconsistency::Assignment { opid, ty: *ty, no });
+        }
+    }
+    let concealed_id = op.conceal().id();
+    if concealed_id != opid {
+        return Err(ConcealInconsistency::OpId(opid, concealed_id));
+    }
+    Ok(())
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, From)]
@@ -577,6 +938,16 @@ pub enum OpRef<'op> {
     Extension(&'op Extension),
 }
 
+impl Display for OpRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OpRef::Genesis(op) => Display::fmt(op, f),
+            OpRef::Transition(op) => Display::fmt(op, f),
+            OpRef::Extension(op) => Display::fmt(op, f),
+        }
+    }
+}
+
 impl<'op> Operation for OpRef<'op> {
     fn op_type(&self) -> OpType {
         match self {
@@ -602,6 +973,14 @@ impl<'op> Operation for OpRef<'op> {
         }
     }
 
+    fn cached_id(&self) -> OpId {
+        match self {
+            OpRef::Genesis(op) => op.cached_id(),
+            OpRef::Transition(op) => op.cached_id(),
+            OpRef::Extension(op) => op.cached_id(),
+        }
+    }
+
     fn contract_id(&self) -> ContractId {
         match self {
             OpRef::Genesis(op) => op.contract_id(),
@@ -673,6 +1052,43 @@ impl<'op> Operation for OpRef<'op> {
             OpRef::Extension(op) => op.inputs(),
         }
     }
+
+    fn parent_ids(&self) -> BTreeSet<OpId> {
+        match self {
+            OpRef::Genesis(op) => op.parent_ids(),
+            OpRef::Transition(op) => op.parent_ids(),
+            OpRef::Extension(op) => op.parent_ids(),
+        }
+    }
+
+    fn verify_conceal_consistency(&self) -> Result<(), ConcealInconsistency> {
+        match self {
+            OpRef::Genesis(op) => op.verify_conceal_consistency(),
+            OpRef::Transition(op) => op.verify_conceal_consistency(),
+            OpRef::Extension(op) => op.verify_conceal_consistency(),
+        }
+    }
+
+    fn as_genesis(&self) -> Option<&Genesis> {
+        match self {
+            OpRef::Genesis(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn as_transition(&self) -> Option<&Transition> {
+        match self {
+            OpRef::Transition(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn as_extension(&self) -> Option<&Extension> {
+        match self {
+            OpRef::Extension(op) => Some(op),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -680,17 +1096,48 @@ mod test {
     use std::str::FromStr;
 
     use amplify::ByteArray;
+    use amplify::hex::ToHex;
     use baid64::DisplayBaid64;
+    use bp::dbc::Method;
+    use strict_encoding::StrictDumb;
 
     use super::*;
+    use crate::{MergeReveal, OpIdParseError};
 
     #[test]
     fn contract_id_display() {
         const ID: &str = "rgb:bGxsbGxs-bGxsbGx-sbGxsbG-xsbGxsb-GxsbGxs-bGxsbGw";
+        const MNEMONIC: &str = "drama-except-gondola";
         let id = ContractId::from_byte_array([0x6c; 32]);
         assert_eq!(ID.len(), 52);
-        assert_eq!(ID, id.to_string());
-        assert_eq!(ID, id.to_baid64_string());
+        assert_eq!(format!("{ID}#{MNEMONIC}"), id.to_string());
+        assert_eq!(format!("{ID}#{MNEMONIC}"), id.to_baid64_string());
+        assert_eq!(ID, format!("{id:#}"));
+        assert_eq!(MNEMONIC, id.to_baid64_mnemonic());
+    }
+
+    #[test]
+    fn id_mnemonic() {
+        let id = ContractId::from_byte_array([0x6c; 32]);
+        assert_eq!(ContractId::from_str(&id.to_string()).unwrap(), id);
+        assert_eq!(ContractId::from_str(&format!("{id:#}")).unwrap(), id);
+        let other = ContractId::from_byte_array([0x6d; 32]);
+        let wrong = format!("{id:#}#{}", other.to_baid64_mnemonic());
+        assert!(ContractId::from_str(&wrong).is_err());
+
+        let opid = OpId::from_byte_array([0x6c; 32]);
+        assert_eq!(opid.to_string(), opid.to_hex());
+        assert_eq!(format!("{opid:#}"), format!("{opid}#{}", opid.to_baid64_mnemonic()));
+        assert_eq!(OpId::from_str(&opid.to_string()).unwrap(), opid);
+        assert_eq!(OpId::from_str(&format!("{opid:#}")).unwrap(), opid);
+        let other = OpId::from_byte_array([0x6d; 32]);
+        let wrong = format!("{opid}#{}", other.to_baid64_mnemonic());
+        assert_eq!(
+            OpId::from_str(&wrong),
+            Err(OpIdParseError::InvalidMnemonic(opid, other.to_baid64_mnemonic()))
+        );
+        let opout = Opout::new(opid, AssignmentType::with(1), 0);
+        assert_eq!(Opout::from_str(&format!("{opid:#}/1/0")).unwrap(), opout);
     }
 
     #[test]
@@ -722,4 +1169,176 @@ mod test {
             ContractId::from_str("rgb:bGxs-bGxs-bGxsbGx-sbGxsbG-xsbGxsb-GxsbGxs-bGxsbGw").is_ok()
         );
     }
+
+    #[test]
+    fn downcast() {
+        let genesis = Genesis::strict_dumb();
+        let mut transition = Transition::strict_dumb();
+        let prev_id = OpId::from_byte_array([0x6c; 32]);
+        transition.inputs = Inputs::from(small_bset![
+            Input::with(Opout::new(prev_id, AssignmentType::with(0), 0)),
+            Input::with(Opout::new(prev_id, AssignmentType::with(0), 1)),
+        ]);
+
+        let op = OpRef::from(&genesis);
+        assert_eq!(op.as_genesis(), Some(&genesis));
+        assert_eq!(op.as_transition(), None);
+        assert_eq!(op.as_extension(), None);
+        assert!(op.parent_ids().is_empty());
+
+        let op = OpRef::from(&transition);
+        assert_eq!(op.as_genesis(), None);
+        assert_eq!(op.as_transition(), Some(&transition));
+        assert_eq!(op.parent_ids(), bset![prev_id]);
+    }
+
+    #[test]
+    fn conceal_consistency() {
+        let genesis = Genesis::strict_dumb();
+        assert_eq!(genesis.verify_conceal_consistency(), Ok(()));
+        assert_eq!(genesis.conceal().verify_conceal_consistency(), Ok(()));
+
+        let transition = Transition::strict_dumb();
+        assert_eq!(OpRef::from(&transition).verify_conceal_consistency(), Ok(()));
+    }
+
+    #[test]
+    fn chain_contract_id() {
+        let mut genesis = Genesis::strict_dumb();
+        let mainnet = genesis.chain_contract_id();
+        genesis.chain_net = ChainNet::Testnet;
+        let testnet = genesis.chain_contract_id();
+        assert_ne!(mainnet.contract_id, testnet.contract_id);
+        assert_eq!(testnet.to_string(), format!("testnet:{}", testnet.contract_id));
+        assert!(mainnet.to_string().starts_with("mainnet:rgb:"));
+    }
+
+    #[test]
+    fn disclose_shape() {
+        let ty = AssignmentType::with(0);
+        let seal =
+            |vout| XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, vout));
+        let mut transition = Transition::strict_dumb();
+        let revealed = small_vec![
+            Assign::revealed(seal(0), VoidState::default()),
+            Assign::revealed(seal(1), VoidState::default()),
+        ];
+        transition.assignments =
+            Assignments::from(tiny_bmap! { ty => TypedAssigns::Declarative(revealed.clone()) });
+        assert_eq!(transition.disclose().seals.len(), 2);
+
+        // Stripping revealed data keeps the id, but changes the disclose hash
+        let mut concealed = transition.clone();
+        let mut assigns = revealed.clone();
+        assigns[1] = assigns[1].conceal();
+        concealed.assignments =
+            Assignments::from(tiny_bmap! { ty => TypedAssigns::Declarative(assigns) });
+        assert_eq!(concealed.id(), transition.id());
+        assert_ne!(concealed.disclose_hash(), transition.disclose_hash());
+        assert_eq!(concealed.disclose().seals.len(), 1);
+
+        // Any change to the number or positions of assignments changes the id
+        let mut dropped = transition.clone();
+        dropped.assignments = Assignments::from(
+            tiny_bmap! { ty => TypedAssigns::Declarative(small_vec![revealed[0].clone()]) },
+        );
+        assert_ne!(dropped.id(), transition.id());
+        let mut swapped = transition.clone();
+        swapped.assignments = Assignments::from(tiny_bmap! {
+            ty => TypedAssigns::Declarative(small_vec![revealed[1].clone(), revealed[0].clone()])
+        });
+        assert_ne!(swapped.id(), transition.id());
+    }
+
+    #[test]
+    fn cached_id() {
+        let mut transition = Transition::strict_dumb();
+        let fresh = transition.clone();
+        assert_eq!(transition.cached_id(), transition.id());
+        assert_eq!(transition, fresh);
+        assert_eq!(
+            transition.to_strict_serialized::<{ usize::MAX }>().unwrap(),
+            fresh.to_strict_serialized::<{ usize::MAX }>().unwrap()
+        );
+
+        let prev_id = transition.cached_id();
+        transition.transition_type = TransitionType::with(1);
+        assert_eq!(transition.cached_id(), prev_id);
+        transition.invalidate_id();
+        assert_ne!(transition.cached_id(), prev_id);
+        assert_eq!(transition.cached_id(), transition.id());
+
+        let mut genesis = Genesis::strict_dumb();
+        let other = genesis.clone();
+        let contract_id = genesis.contract_id();
+        genesis.merge_reveal(other).unwrap();
+        assert_eq!(genesis.id_cache.0.get(), None);
+        assert_eq!(genesis.contract_id(), contract_id);
+    }
+
+    #[test]
+    fn canonicalize() {
+        let mut transition = Transition::strict_dumb();
+        let id = transition.id();
+        assert!(transition.is_canonical());
+
+        transition.assignments = Assignments::from(tiny_bmap! {
+            AssignmentType::with(1) => TypedAssigns::Declarative(none!()),
+        });
+        transition.invalidate_id();
+        assert!(!transition.is_canonical());
+        assert_eq!(transition.id(), id);
+        assert_eq!(transition.disclose(), Transition::strict_dumb().disclose());
+
+        transition.canonicalize();
+        assert!(transition.is_canonical());
+        assert_eq!(transition.id(), id);
+        assert_eq!(transition.disclose(), Transition::strict_dumb().disclose());
+    }
+
+    #[test]
+    fn input_positions() {
+        let parent = OpId::from_byte_array([0x6c; 32]);
+        let other = OpId::from_byte_array([0x6d; 32]);
+        let ty = AssignmentType::with(1);
+        let inputs = Inputs::from(small_bset![
+            Input::with(Opout::new(parent, ty, 2)),
+            Input::with(Opout::new(parent, ty, 0)),
+            Input::with(Opout::new(other, ty, 1)),
+        ]);
+
+        assert!(inputs.spends(Opout::new(parent, ty, 0)));
+        assert!(inputs.spends(Opout::new(parent, ty, 2)));
+        assert!(!inputs.spends(Opout::new(parent, ty, 1)));
+        assert!(!inputs.spends(Opout::new(other, ty, 0)));
+        assert_eq!(inputs.from_parent(parent).collect::<Vec<_>>(), vec![
+            Opout::new(parent, ty, 0),
+            Opout::new(parent, ty, 2)
+        ]);
+    }
+
+    #[test]
+    fn output_numbering() {
+        let seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 0));
+        let right = Assign::revealed(seal, VoidState::default());
+        let ty = AssignmentType::with(1);
+        let mut transition = Transition::strict_dumb();
+        transition.assignments = Assignments::from(tiny_bmap! {
+            ty => TypedAssigns::Declarative(small_vec![right.clone(), right.clone()]),
+        });
+        transition.invalidate_id();
+        let opid = transition.id();
+        let outputs = transition.outputs();
+        assert_eq!(outputs, bset![Opout::new(opid, ty, 0), Opout::new(opid, ty, 1)]);
+        assert!(transition.has_output(Opout::new(opid, ty, 1)));
+        assert!(!transition.has_output(Opout::new(opid, ty, 2)));
+        assert!(!transition.has_output(Opout::new(opid, AssignmentType::with(2), 0)));
+
+        transition.assignments = Assignments::from(tiny_bmap! {
+            ty => TypedAssigns::Declarative(small_vec![right.conceal(), right]),
+        });
+        transition.invalidate_id();
+        assert_eq!(transition.id(), opid);
+        assert_eq!(transition.outputs(), outputs);
+    }
 }
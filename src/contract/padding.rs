@@ -0,0 +1,296 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Constant-size confidential transfers.
+//!
+//! [`ConstantShape`] pads state transitions with zero-value confidential
+//! allocations up to a fixed number of outputs and fills in the metadata up to
+//! a fixed layout, such that neither the chain-side nor the consignment-side
+//! observers can distinguish payment patterns by the transition shape. Padding
+//! allocations are assigned to random blinded seals, which nobody controls;
+//! their blinding factors are compensated by one of the revealed outputs, such
+//! that the transition still conserves the sum of the Pedersen commitments.
+
+use amplify::confinement::Confined;
+use bp::secp256k1::rand::seq::SliceRandom;
+use bp::secp256k1::rand::{Rng, RngCore, thread_rng};
+
+use crate::schema::MetaType;
+use crate::{
+    Assign, AssignmentType, BlindingFactor, Bulletproofs, ConcealedValue, Metadata, PedersenError,
+    RangeProofScheme, RevealedValue, SecretSeal, Transition, TypedAssigns, XChain, pedersen_engine,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PaddingError {
+    /// transition doesn't assign fungible state of type {0}.
+    NoAssignments(AssignmentType),
+
+    /// transition assigns {1} outputs of type {0}, exceeding the constant
+    /// size of {2} outputs.
+    TooManyOutputs(AssignmentType, u16, u16),
+
+    /// transition assigns {1} outputs of type {0} instead of {2}.
+    OutputCountMismatch(AssignmentType, u16, u16),
+
+    /// transition metadata of type {0} is not a part of the constant layout.
+    UnexpectedMetadata(MetaType),
+
+    /// transition lacks metadata of type {0} required by the constant layout.
+    MetadataAbsent(MetaType),
+
+    /// none of the outputs of type {0} reveals its blinding factor, which is
+    /// required to compensate the padding.
+    NoBalancingOutput(AssignmentType),
+
+    /// blinding factors of the padding can't be balanced; please retry.
+    InvalidBlinding,
+
+    #[from]
+    #[display(inner)]
+    Pedersen(PedersenError),
+}
+
+/// Shape of the state transitions in the constant-size confidential transfer
+/// mode.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConstantShape {
+    /// Padded fungible state type.
+    pub assignment_type: AssignmentType,
+    /// Number of outputs of the padded type in each transition.
+    pub outputs: u16,
+    /// Metadata layout. Each transition must carry metadata of exactly these
+    /// types; the values are used for the types missed by a transition.
+    pub metadata: Metadata,
+}
+
+impl ConstantShape {
+    /// Constructs shape with a given number of outputs of a fungible state
+    /// type and without metadata.
+    pub fn new(assignment_type: AssignmentType, outputs: u16) -> Self {
+        ConstantShape {
+            assignment_type,
+            outputs,
+            metadata: none!(),
+        }
+    }
+
+    /// Sets the metadata layout.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Pads the transition to the constant shape.
+    pub fn pad(&self, transition: Transition) -> Result<Transition, PaddingError> {
+        self.pad_with_rng(transition, &mut thread_rng())
+    }
+
+    /// Pads the transition like [`ConstantShape::pad`], using the provided
+    /// random generator for creating padding seals and blinding factors.
+    ///
+    /// Outputs of the padded type get shuffled, such that the padding
+    /// allocations can't be recognized by their position; the wallet must
+    /// look up its outputs in the returned transition.
+    pub fn pad_with_rng<R: Rng + RngCore>(
+        &self,
+        mut transition: Transition,
+        rng: &mut R,
+    ) -> Result<Transition, PaddingError> {
+        let ty = self.assignment_type;
+        for meta_type in transition.metadata.keys() {
+            if !self.metadata.contains_key(meta_type) {
+                return Err(PaddingError::UnexpectedMetadata(*meta_type));
+            }
+        }
+        for (meta_type, value) in self.metadata.iter() {
+            if !transition.metadata.contains_key(meta_type) {
+                transition
+                    .metadata
+                    .add_value(*meta_type, value.clone())
+                    .expect("metadata layout fits the transition");
+            }
+        }
+
+        let mut assigns = match transition.assignments.get(&ty) {
+            Some(TypedAssigns::Fungible(assigns)) => assigns.to_vec(),
+            _ => return Err(PaddingError::NoAssignments(ty)),
+        };
+        let count = assigns.len() as u16;
+        if count > self.outputs {
+            return Err(PaddingError::TooManyOutputs(ty, count, self.outputs));
+        }
+        if count < self.outputs {
+            let balancing = assigns
+                .iter_mut()
+                .rev()
+                .find_map(|assign| {
+                    let layer1 = assign.to_confidential_seal().layer1();
+                    assign.as_revealed_state_mut().map(|state| (layer1, state))
+                })
+                .ok_or(PaddingError::NoBalancingOutput(ty))?;
+            let (layer1, state) = balancing;
+            let tag = state.tag;
+            let mut blindings = vec![];
+            let mut padding = vec![];
+            for _ in count..self.outputs {
+                let blinding = BlindingFactor::random_custom(rng);
+                let zero = RevealedValue::with_blinding(0u64, blinding.clone(), tag);
+                let commitment = pedersen_engine().commit_zero(tag, blinding.clone())?;
+                let seal = XChain::with(layer1, SecretSeal::from(rng.gen::<[u8; 32]>()));
+                padding.push(Assign::Confidential {
+                    seal,
+                    state: ConcealedValue {
+                        commitment,
+                        range_proof: Bulletproofs.prove(&zero),
+                    },
+                    lock: default!(),
+                });
+                blindings.push(blinding);
+            }
+            state.blinding = BlindingFactor::zero_balanced([state.blinding.clone()], blindings)
+                .map_err(|_| PaddingError::InvalidBlinding)?;
+            assigns.extend(padding);
+        }
+        assigns.shuffle(rng);
+        let assigns = Confined::try_from(assigns).expect("number of outputs fits u16");
+        transition
+            .assignments
+            .insert(ty, TypedAssigns::Fungible(assigns))
+            .expect("type is already present");
+        transition.invalidate_id();
+        Ok(transition)
+    }
+
+    /// Checks that the transition has the constant shape.
+    pub fn verify(&self, transition: &Transition) -> Result<(), PaddingError> {
+        let ty = self.assignment_type;
+        for meta_type in transition.metadata.keys() {
+            if !self.metadata.contains_key(meta_type) {
+                return Err(PaddingError::UnexpectedMetadata(*meta_type));
+            }
+        }
+        if let Some(meta_type) = self
+            .metadata
+            .keys()
+            .find(|meta_type| !transition.metadata.contains_key(*meta_type))
+        {
+            return Err(PaddingError::MetadataAbsent(*meta_type));
+        }
+        let count = match transition.assignments.get(&ty) {
+            Some(assigns @ TypedAssigns::Fungible(_)) => assigns.len_u16(),
+            _ => return Err(PaddingError::NoAssignments(ty)),
+        };
+        if count != self.outputs {
+            return Err(PaddingError::OutputCountMismatch(ty, count, self.outputs));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::SmallBlob;
+    use bp::dbc::Method;
+    use commit_verify::Conceal;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AssetTag, Assignments, GraphSeal, MetaValue};
+
+    #[test]
+    fn constant_shape() {
+        let ty = AssignmentType::with(1);
+        let tag = AssetTag::from([0x6c; 32]);
+        let memo = MetaType::with(1);
+        let value = |byte| MetaValue::from(SmallBlob::try_from(vec![byte]).unwrap());
+        let seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 0));
+        let mut rng = thread_rng();
+
+        let input = RevealedValue::with_rng(100u64, &mut rng, tag);
+        let payment = RevealedValue::with_rng(70u64, &mut rng, tag);
+        let change =
+            BlindingFactor::zero_balanced([input.blinding.clone()], [payment.blinding.clone()])
+                .unwrap();
+        let change = RevealedValue::with_blinding(30u64, change, tag);
+        let mut transition = Transition::strict_dumb();
+        transition.assignments = Assignments::from(tiny_bmap! {
+            ty => TypedAssigns::Fungible(small_vec![
+                Assign::revealed(seal, payment).conceal_seal(),
+                Assign::revealed(seal, change)
+            ])
+        });
+
+        let shape = ConstantShape::new(ty, 4).with_metadata(Metadata::from(tiny_bmap! {
+            memo => value(0)
+        }));
+        assert_eq!(shape.verify(&transition), Err(PaddingError::MetadataAbsent(memo)));
+        let padded = shape.pad_with_rng(transition.clone(), &mut rng).unwrap();
+        assert_eq!(shape.verify(&padded), Ok(()));
+        assert_eq!(padded.metadata, shape.metadata);
+        let assigns = padded.assignments[&ty].as_fungible();
+        assert_eq!(assigns.len(), 4);
+        assert_eq!(
+            assigns
+                .iter()
+                .filter(|a| a.as_revealed_state().is_none())
+                .count(),
+            2
+        );
+        let outputs = assigns
+            .iter()
+            .map(|a| a.to_confidential_state().commitment)
+            .collect::<Vec<_>>();
+        let input = pedersen_engine().commit(&input).unwrap();
+        assert_eq!(pedersen_engine().verify_sum(&[input], &outputs), Ok(true));
+
+        // Transitions already having the constant shape are only shuffled
+        let repadded = shape.pad_with_rng(padded.clone(), &mut rng).unwrap();
+        assert_eq!(repadded.assignments[&ty].len_u16(), 4);
+
+        let mut foreign = transition.clone();
+        foreign.metadata = Metadata::from(tiny_bmap! { MetaType::with(2) => value(1) });
+        assert_eq!(
+            shape.pad_with_rng(foreign, &mut rng),
+            Err(PaddingError::UnexpectedMetadata(MetaType::with(2)))
+        );
+        assert_eq!(
+            ConstantShape::new(ty, 1).pad_with_rng(transition.clone(), &mut rng),
+            Err(PaddingError::TooManyOutputs(ty, 2, 1))
+        );
+        let other = AssignmentType::with(2);
+        assert_eq!(
+            ConstantShape::new(other, 4).pad_with_rng(transition.clone(), &mut rng),
+            Err(PaddingError::NoAssignments(other))
+        );
+        let mut hidden = transition.clone();
+        hidden.assignments = Assignments::from(tiny_bmap! {
+            ty => TypedAssigns::Fungible(small_vec![
+                Assign::revealed(seal, RevealedValue::with_rng(100u64, &mut rng, tag)).conceal()
+            ])
+        });
+        assert_eq!(
+            ConstantShape::new(ty, 4).pad_with_rng(hidden, &mut rng),
+            Err(PaddingError::NoBalancingOutput(ty))
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Engines performing elliptic curve operations over pedersen commitments.
+//!
+//! Creating and verifying pedersen commitments requires the secp256k1-zkp C
+//! library, which is available only with `pedersen` feature. Builds without
+//! it use [`VerifyOnly`] engine, which is able to validate contracts without
+//! confidential amounts.
+
+#[cfg(feature = "pedersen")]
+use amplify::Bytes;
+
+use super::{AssetTag, BlindingFactor, PedersenCommitment, RevealedValue};
+
+/// Errors performed by pedersen engines.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum PedersenError {
+    /// operation is not supported by {0} pedersen engine.
+    Unsupported(&'static str),
+
+    /// commitment to zero value with an empty blinding factor can be
+    /// recognized by anybody.
+    TransparentZero,
+}
+
+/// Implementation of the elliptic curve operations over pedersen commitments.
+pub trait PedersenEngine {
+    /// Name of the engine.
+    fn name(&self) -> &'static str;
+
+    /// Checks that the serialized commitment is a valid curve point.
+    fn is_valid(&self, commitment: &[u8; 33]) -> bool;
+
+    /// Creates pedersen commitment to the revealed value.
+    fn commit(&self, revealed: &RevealedValue) -> Result<PedersenCommitment, PedersenError>;
+
+    /// Creates pedersen commitment to zero value, used for padding operations
+    /// with dummy outputs. The blinding factor must be random, since zero
+    /// commitments with [`BlindingFactor::EMPTY`] are recognizable.
+    fn commit_zero(
+        &self,
+        tag: AssetTag,
+        blinding: BlindingFactor,
+    ) -> Result<PedersenCommitment, PedersenError> {
+        if blinding == BlindingFactor::EMPTY {
+            return Err(PedersenError::TransparentZero);
+        }
+        self.commit(&RevealedValue::with_blinding(0u64, blinding, tag))
+    }
+
+    /// Verifies that sums of the positive and negative commitments are equal.
+    fn verify_sum(
+        &self,
+        positive: &[PedersenCommitment],
+        negative: &[PedersenCommitment],
+    ) -> Result<bool, PedersenError>;
+}
+
+/// Returns pedersen engine used by the library.
+pub fn pedersen_engine() -> &'static dyn PedersenEngine {
+    #[cfg(feature = "pedersen")]
+    return &Secp256k1Zkp;
+    #[cfg(not(feature = "pedersen"))]
+    return &VerifyOnly;
+}
+
+/// Pedersen engine using secp256k1-zkp library.
+#[cfg(feature = "pedersen")]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Secp256k1Zkp;
+
+#[cfg(feature = "pedersen")]
+impl Secp256k1Zkp {
+    fn parse(commitments: &[PedersenCommitment]) -> Option<Vec<secp256k1_zkp::PedersenCommitment>> {
+        commitments
+            .iter()
+            .map(|c| secp256k1_zkp::PedersenCommitment::from_slice(c.as_slice()).ok())
+            .collect()
+    }
+}
+
+#[cfg(feature = "pedersen")]
+impl PedersenEngine for Secp256k1Zkp {
+    fn name(&self) -> &'static str { "secp256k1-zkp" }
+
+    fn is_valid(&self, commitment: &[u8; 33]) -> bool {
+        secp256k1_zkp::PedersenCommitment::from_slice(commitment).is_ok()
+    }
+
+    fn commit(&self, revealed: &RevealedValue) -> Result<PedersenCommitment, PedersenError> {
+        use secp256k1_zkp::{Generator, SECP256K1, Tag, Tweak};
+
+        let blinding = Tweak::from_inner(*revealed.blinding.as_secret())
+            .expect("type guarantees of BlindingFactor are broken");
+        let value = revealed.value.as_u64();
+
+        let tag = Tag::from(revealed.tag.to_byte_array());
+        let generator = Generator::new_unblinded(SECP256K1, tag);
+
+        let commitment =
+            secp256k1_zkp::PedersenCommitment::new(SECP256K1, value, blinding, generator);
+        Ok(PedersenCommitment::try_from(Bytes::from_array(commitment.serialize()))
+            .expect("secp256k1-zkp produces valid commitments"))
+    }
+
+    fn verify_sum(
+        &self,
+        positive: &[PedersenCommitment],
+        negative: &[PedersenCommitment],
+    ) -> Result<bool, PedersenError> {
+        let (Some(positive), Some(negative)) = (Self::parse(positive), Self::parse(negative))
+        else {
+            return Ok(false);
+        };
+        Ok(secp256k1_zkp::verify_commitments_sum_to_equal(
+            secp256k1_zkp::SECP256K1,
+            &positive,
+            &negative,
+        ))
+    }
+}
+
+/// Pure-rust pedersen engine for the builds without secp256k1-zkp library.
+///
+/// The engine can't create commitments and verifies only sums consisting of
+/// the same commitments on both sides, like in the case of plain transfers of
+/// the whole state. Other verifications, as well as processing of operations
+/// with revealed fungible state, fail.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct VerifyOnly;
+
+impl PedersenEngine for VerifyOnly {
+    fn name(&self) -> &'static str { "verify-only" }
+
+    fn is_valid(&self, commitment: &[u8; 33]) -> bool { matches!(commitment[0], 0x08 | 0x09) }
+
+    fn commit(&self, _: &RevealedValue) -> Result<PedersenCommitment, PedersenError> {
+        Err(PedersenError::Unsupported(self.name()))
+    }
+
+    fn verify_sum(
+        &self,
+        positive: &[PedersenCommitment],
+        negative: &[PedersenCommitment],
+    ) -> Result<bool, PedersenError> {
+        let mut positive = positive.iter().map(|c| **c).collect::<Vec<_>>();
+        let mut negative = negative.iter().map(|c| **c).collect::<Vec<_>>();
+        positive.sort_unstable();
+        negative.sort_unstable();
+        if positive != negative {
+            return Err(PedersenError::Unsupported(self.name()));
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use commit_verify::CommitVerify;
+
+    use super::*;
+    use crate::{AssetTag, BlindingFactor};
+
+    #[test]
+    fn verify_only() {
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        let a = RevealedValue::with_blinding(5, BlindingFactor::EMPTY, tag);
+        let b = RevealedValue::with_blinding(7, BlindingFactor::EMPTY, tag);
+        assert_eq!(VerifyOnly.commit(&a), Err(PedersenError::Unsupported("verify-only")));
+
+        let a = PedersenCommitment::commit(&a);
+        let b = PedersenCommitment::commit(&b);
+        assert!(VerifyOnly.is_valid(&a));
+        assert_eq!(VerifyOnly.verify_sum(&[a, b], &[b, a]), Ok(true));
+        assert_eq!(
+            VerifyOnly.verify_sum(&[a, b], &[a]),
+            Err(PedersenError::Unsupported("verify-only"))
+        );
+        assert_eq!(pedersen_engine().verify_sum(&[a, b], &[b, a]), Ok(true));
+    }
+
+    #[test]
+    #[cfg(feature = "pedersen")]
+    fn zero_commitment() {
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        assert_eq!(
+            Secp256k1Zkp.commit_zero(tag, BlindingFactor::EMPTY),
+            Err(PedersenError::TransparentZero)
+        );
+        let blinding = BlindingFactor::try_from([2u8; 32]).unwrap();
+        let zero = Secp256k1Zkp.commit_zero(tag, blinding.clone()).unwrap();
+        assert!(Secp256k1Zkp.is_valid(&zero));
+
+        // Zero commitment changes the sum by its blinding factor only
+        let a = RevealedValue::with_blinding(5, BlindingFactor::try_from([3u8; 32]).unwrap(), tag);
+        let balanced = BlindingFactor::zero_balanced([a.blinding.clone()], [blinding]).unwrap();
+        let b = RevealedValue::with_blinding(5, balanced, tag);
+        let a = Secp256k1Zkp.commit(&a).unwrap();
+        let b = Secp256k1Zkp.commit(&b).unwrap();
+        assert_eq!(Secp256k1Zkp.verify_sum(&[a], &[b, zero]), Ok(true));
+        assert_eq!(Secp256k1Zkp.verify_sum(&[a], &[b]), Ok(false));
+    }
+}
@@ -0,0 +1,372 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content-addressed storage of bundles used by consignments.
+//!
+//! Each bundle is stored in the pool only once under its id, together with
+//! its anchor, and is referenced by the id even if the contract history
+//! contains the bundle on multiple paths of the operation graph (see
+//! [`crate::validation::Consignment`]).
+
+use amplify::confinement::{Confined, LargeOrdMap, LargeVec};
+use strict_encoding::{
+    DecodeError, DeserializeError, ReadStruct, StrictDecode, StrictDeserialize, StrictSerialize,
+    TypedRead,
+};
+
+use crate::{
+    BundleId, EAnchor, LIB_NAME_RGB, MergeReveal, MergeRevealError, TransitionBundle, XWitnessId,
+};
+
+/// Bundle stored together with its anchor, as used by the legacy consignment
+/// layout.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AnchoredBundle {
+    pub witness_id: XWitnessId,
+    pub anchor: EAnchor,
+    pub bundle: TransitionBundle,
+}
+
+/// Anchor of a bundle stored in a [`BundlePool`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BundleAnchor {
+    pub witness_id: XWitnessId,
+    pub anchor: EAnchor,
+}
+
+/// Legacy consignment layout, where each anchored bundle is stored once per
+/// each path of the operation graph it belongs to.
+#[derive(Wrapper, Clone, PartialEq, Eq, Debug, Default, From)]
+#[wrapper(Deref)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LegacyBundles(LargeVec<AnchoredBundle>);
+
+impl StrictSerialize for LegacyBundles {}
+impl StrictDeserialize for LegacyBundles {}
+
+/// Errors constructing bundle pool.
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PoolError {
+    /// bundle {0} is anchored to different witnesses {1} and {2}.
+    WitnessMismatch(BundleId, XWitnessId, XWitnessId),
+
+    /// bundle pool has exceeded the maximal number of bundles.
+    Overflow,
+
+    /// bundle {1} is stored in the pool under a different id {0}.
+    BundleIdMismatch(BundleId, BundleId),
+
+    /// bundle {0} has no anchor in the pool.
+    AnchorAbsent(BundleId),
+
+    /// anchor of bundle {0} is present in the pool without the bundle.
+    BundleAbsent(BundleId),
+
+    /// conflicting data for the same bundle. Details: {0}
+    #[from]
+    Merge(MergeRevealError),
+
+    /// unable to decode bundles in the legacy layout. Details: {0}
+    #[from]
+    Legacy(DeserializeError),
+}
+
+/// Pool of anchored bundles, where each bundle is stored under its id and
+/// has an anchor. Decoding checks both of these invariants.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictEncode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase", try_from = "PoolData")
+)]
+pub struct BundlePool {
+    bundles: LargeOrdMap<BundleId, TransitionBundle>,
+    anchors: LargeOrdMap<BundleId, BundleAnchor>,
+}
+
+impl StrictSerialize for BundlePool {}
+impl StrictDeserialize for BundlePool {}
+
+impl StrictDecode for BundlePool {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let bundles = r.read_field(fname!("bundles"))?;
+            let anchors = r.read_field(fname!("anchors"))?;
+            BundlePool::try_from(PoolData { bundles, anchors })
+                .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
+        })
+    }
+}
+
+/// Pool data which are not yet checked for the pool invariants.
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+struct PoolData {
+    bundles: LargeOrdMap<BundleId, TransitionBundle>,
+    anchors: LargeOrdMap<BundleId, BundleAnchor>,
+}
+
+impl TryFrom<PoolData> for BundlePool {
+    type Error = PoolError;
+
+    fn try_from(data: PoolData) -> Result<Self, Self::Error> {
+        let PoolData { bundles, anchors } = data;
+        for (bundle_id, bundle) in &bundles {
+            if bundle.bundle_id() != *bundle_id {
+                return Err(PoolError::BundleIdMismatch(*bundle_id, bundle.bundle_id()));
+            }
+            if !anchors.contains_key(bundle_id) {
+                return Err(PoolError::AnchorAbsent(*bundle_id));
+            }
+        }
+        if let Some(bundle_id) = anchors.keys().find(|id| !bundles.contains_key(*id)) {
+            return Err(PoolError::BundleAbsent(*bundle_id));
+        }
+        Ok(BundlePool { bundles, anchors })
+    }
+}
+
+impl BundlePool {
+    pub fn new() -> Self { default!() }
+
+    /// Adds anchored bundle to the pool. If the bundle is already present,
+    /// merges revealed data from both versions of the bundle.
+    pub fn add(
+        &mut self,
+        witness_id: XWitnessId,
+        anchor: EAnchor,
+        bundle: TransitionBundle,
+    ) -> Result<(), PoolError> {
+        let bundle_id = bundle.bundle_id();
+        match self.anchors.get_mut(&bundle_id) {
+            Some(existing) if existing.witness_id != witness_id => {
+                return Err(PoolError::WitnessMismatch(bundle_id, existing.witness_id, witness_id));
+            }
+            Some(existing) => existing.anchor.merge_reveal(anchor)?,
+            None => {
+                self.anchors
+                    .insert(bundle_id, BundleAnchor { witness_id, anchor })
+                    .map_err(|_| PoolError::Overflow)?;
+            }
+        }
+        match self.bundles.get_mut(&bundle_id) {
+            Some(existing) => existing.merge_reveal(bundle)?,
+            None => {
+                self.bundles
+                    .insert(bundle_id, bundle)
+                    .map_err(|_| PoolError::Overflow)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges bundles and anchors of another pool into this one, revealing
+    /// data known to either of the pools. Fails if both pools contain the same
+    /// bundle with different witnesses, anchors or conflicting data, leaving
+    /// this pool unchanged.
+    pub fn merge(&mut self, other: BundlePool) -> Result<(), PoolError> {
+        let mut merged = self.clone();
+        let BundlePool {
+            bundles,
+            mut anchors,
+        } = other;
+        for (bundle_id, bundle) in bundles {
+            let BundleAnchor { witness_id, anchor } = anchors
+                .remove(&bundle_id)
+                .ok()
+                .flatten()
+                .ok_or(PoolError::AnchorAbsent(bundle_id))?;
+            merged.add(witness_id, anchor, bundle)?;
+        }
+        *self = merged;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize { self.bundles.len() }
+
+    pub fn is_empty(&self) -> bool { self.bundles.is_empty() }
+
+    pub fn bundle_ids(&self) -> impl Iterator<Item = BundleId> + '_ { self.bundles.keys().copied() }
+
+    pub fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+        self.bundles.get(&bundle_id)
+    }
+
+    pub fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)> {
+        self.anchors
+            .get(&bundle_id)
+            .map(|ba| (ba.witness_id, &ba.anchor))
+    }
+
+    /// Converts the pool into the legacy layout.
+    pub fn to_legacy(&self) -> Result<LegacyBundles, PoolError> {
+        let mut legacy = LargeVec::new();
+        for (bundle_id, bundle) in &self.bundles {
+            let ba = self
+                .anchors
+                .get(bundle_id)
+                .ok_or(PoolError::AnchorAbsent(*bundle_id))?;
+            legacy
+                .push(AnchoredBundle {
+                    witness_id: ba.witness_id,
+                    anchor: ba.anchor.clone(),
+                    bundle: bundle.clone(),
+                })
+                .map_err(|_| PoolError::Overflow)?;
+        }
+        Ok(LegacyBundles(legacy))
+    }
+
+    /// Decodes bundles serialized using the legacy layout, de-duplicating
+    /// them into the pool.
+    pub fn from_legacy_serialized<const MAX: usize>(
+        data: Confined<Vec<u8>, 0, MAX>,
+    ) -> Result<Self, PoolError> {
+        LegacyBundles::from_strict_serialized::<MAX>(data)?.try_into()
+    }
+}
+
+impl TryFrom<LegacyBundles> for BundlePool {
+    type Error = PoolError;
+
+    fn try_from(legacy: LegacyBundles) -> Result<Self, Self::Error> {
+        let mut pool = BundlePool::new();
+        for ab in legacy.0 {
+            pool.add(ab.witness_id, ab.anchor, ab.bundle)?;
+        }
+        Ok(pool)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::testing::Scenario;
+    use crate::{ChainNet, DbcProof};
+
+    fn pool() -> BundlePool {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        scenario.transfer();
+        scenario.consignment().bundles().clone()
+    }
+
+    fn decode(pool: &BundlePool) -> Result<BundlePool, DeserializeError> {
+        BundlePool::from_strict_serialized::<U32>(pool.to_strict_serialized::<U32>().unwrap())
+    }
+
+    #[test]
+    fn legacy_roundtrip() {
+        let pool = pool();
+        let legacy = pool.to_legacy().unwrap();
+        assert_eq!(legacy.len(), 2);
+        let data = legacy.to_strict_serialized::<U32>().unwrap();
+        assert_eq!(BundlePool::from_legacy_serialized::<U32>(data).unwrap(), pool);
+        assert_eq!(decode(&pool).unwrap(), pool);
+    }
+
+    #[test]
+    fn merge() {
+        let pool = pool();
+        let ids = pool.bundle_ids().collect::<Vec<_>>();
+        let mut partial = pool.clone();
+        partial.bundles.remove(&ids[0]).unwrap();
+        partial.anchors.remove(&ids[0]).unwrap();
+        let mut merged = partial.clone();
+        merged.merge(pool.clone()).unwrap();
+        assert_eq!(merged, pool);
+        merged.merge(partial).unwrap();
+        assert_eq!(merged, pool);
+
+        let mut other = pool.clone();
+        other.anchors.get_mut(&ids[0]).unwrap().anchor.dbc_proof = DbcProof::strict_dumb();
+        let mut merged = pool.clone();
+        assert_eq!(
+            merged.merge(other).unwrap_err().to_string(),
+            PoolError::Merge(MergeRevealError::AnchorMismatch).to_string()
+        );
+        assert_eq!(merged, pool);
+
+        let mut other = pool.clone();
+        let witness_id = pool.anchors.get(&ids[1]).unwrap().witness_id;
+        other.anchors.get_mut(&ids[0]).unwrap().witness_id = witness_id;
+        let mut merged = pool.clone();
+        assert!(matches!(
+            merged.merge(other),
+            Err(PoolError::WitnessMismatch(id, _, other)) if id == ids[0] && other == witness_id
+        ));
+        assert_eq!(merged, pool);
+    }
+
+    #[test]
+    fn mismatched_key() {
+        let mut pool = pool();
+        let ids = pool.bundle_ids().collect::<Vec<_>>();
+        let bundle = pool.bundles.remove(&ids[0]).unwrap().unwrap();
+        *pool.bundles.get_mut(&ids[1]).unwrap() = bundle;
+        assert!(decode(&pool).is_err());
+        assert!(matches!(
+            BundlePool::try_from(PoolData {
+                bundles: pool.bundles,
+                anchors: pool.anchors,
+            }),
+            Err(PoolError::BundleIdMismatch(id, actual)) if id == ids[1] && actual == ids[0]
+        ));
+    }
+
+    #[test]
+    fn missing_anchor() {
+        let mut pool = pool();
+        let bundle_id = pool.bundle_ids().next().unwrap();
+        let anchor = pool.anchors.remove(&bundle_id).unwrap().unwrap();
+        assert!(decode(&pool).is_err());
+        assert!(matches!(pool.to_legacy(), Err(PoolError::AnchorAbsent(id)) if id == bundle_id));
+
+        pool.anchors.insert(bundle_id, anchor).unwrap();
+        pool.bundles.remove(&bundle_id).unwrap();
+        assert!(decode(&pool).is_err());
+    }
+}
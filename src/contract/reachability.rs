@@ -0,0 +1,258 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reachability index over the operations of a contract history.
+//!
+//! The index labels each operation with its position in the depth-first
+//! post-order of the spending graph, and with a compressed set of position
+//! intervals covering all of its descendants. Operations lying on a single
+//! branch of a spanning tree occupy a contiguous range of positions, so
+//! chains and airdrop fan-outs need a single interval per operation, and
+//! ancestry queries are answered with a binary search instead of a walk over
+//! the graph.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Inputs, OpId};
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Label {
+    pos: u32,
+    /// Sorted non-overlapping inclusive ranges of positions of the operation
+    /// itself and all of its descendants.
+    intervals: Vec<(u32, u32)>,
+}
+
+/// Ancestor-descendant relation index over the operations connected by
+/// spending of their outputs.
+///
+/// The index is a snapshot: it has to be rebuilt after new operations are
+/// added to the history. Operations which are not known to the history but
+/// are referenced by inputs of the known ones are included into the index.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Reachability {
+    labels: BTreeMap<OpId, Label>,
+    order: Vec<OpId>,
+}
+
+impl Reachability {
+    /// Builds the index from the inputs of the state transitions.
+    pub fn with<'inputs>(inputs: impl IntoIterator<Item = (OpId, &'inputs Inputs)>) -> Self {
+        let mut children = BTreeMap::<OpId, BTreeSet<OpId>>::new();
+        let mut has_parents = BTreeSet::new();
+        for (opid, inputs) in inputs {
+            children.entry(opid).or_default();
+            for input in inputs {
+                if input.prev_out.op == opid {
+                    continue;
+                }
+                children.entry(input.prev_out.op).or_default().insert(opid);
+                has_parents.insert(opid);
+            }
+        }
+
+        // Iterative depth-first traversal from the roots, assigning each
+        // operation the first and the last post-order positions of its subtree
+        // in the spanning forest.
+        let mut spans = BTreeMap::<OpId, (u32, u32)>::new();
+        let mut order = Vec::with_capacity(children.len());
+        let roots = children.keys().filter(|opid| !has_parents.contains(*opid));
+        for root in roots {
+            let mut stack = vec![(*root, order.len() as u32, children[root].iter())];
+            spans.insert(*root, (0, 0));
+            while let Some((opid, low, iter)) = stack.last_mut() {
+                match iter.find(|child| !spans.contains_key(*child)) {
+                    Some(child) => {
+                        let child = *child;
+                        spans.insert(child, (0, 0));
+                        stack.push((child, order.len() as u32, children[&child].iter()));
+                    }
+                    None => {
+                        spans.insert(*opid, (*low, order.len() as u32));
+                        order.push(*opid);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        // Each descendant has a smaller post-order position than its ancestors,
+        // so the descendant intervals are known when their ancestors are
+        // processed.
+        let mut labels = BTreeMap::<OpId, Label>::new();
+        for opid in &order {
+            let (low, pos) = spans[opid];
+            let mut intervals = vec![(low, pos)];
+            for child in &children[opid] {
+                intervals.extend(&labels[child].intervals);
+            }
+            labels.insert(*opid, Label {
+                pos,
+                intervals: merge(intervals),
+            });
+        }
+
+        Reachability { labels, order }
+    }
+
+    /// Returns number of operations in the index.
+    pub fn len(&self) -> usize { self.order.len() }
+
+    /// Detects whether the index is empty.
+    pub fn is_empty(&self) -> bool { self.order.is_empty() }
+
+    /// Detects whether the operation is known to the index.
+    pub fn contains(&self, opid: OpId) -> bool { self.labels.contains_key(&opid) }
+
+    /// Detects whether the `descendant` operation directly or indirectly
+    /// spends outputs of the `ancestor` operation. An operation is not an
+    /// ancestor of itself. Returns `false` if any of the operations is
+    /// unknown to the index.
+    pub fn is_ancestor(&self, ancestor: OpId, descendant: OpId) -> bool {
+        if ancestor == descendant {
+            return false;
+        }
+        let (Some(ancestor), Some(descendant)) =
+            (self.labels.get(&ancestor), self.labels.get(&descendant))
+        else {
+            return false;
+        };
+        covers(&ancestor.intervals, descendant.pos)
+    }
+
+    /// Returns all operations which directly or indirectly spend outputs of
+    /// the given operation.
+    pub fn descendants(&self, opid: OpId) -> BTreeSet<OpId> {
+        let Some(label) = self.labels.get(&opid) else {
+            return bset![];
+        };
+        label
+            .intervals
+            .iter()
+            .flat_map(|(start, end)| &self.order[*start as usize..=*end as usize])
+            .copied()
+            .filter(|descendant| *descendant != opid)
+            .collect()
+    }
+
+    /// Returns all operations whose outputs are directly or indirectly spent
+    /// by the given operation. Unlike other queries, this one scans labels of
+    /// all operations in the index.
+    pub fn ancestors(&self, opid: OpId) -> BTreeSet<OpId> {
+        let Some(label) = self.labels.get(&opid) else {
+            return bset![];
+        };
+        self.labels
+            .iter()
+            .filter(|(ancestor, _)| **ancestor != opid)
+            .filter(|(_, ancestor)| covers(&ancestor.intervals, label.pos))
+            .map(|(ancestor, _)| *ancestor)
+            .collect()
+    }
+}
+
+fn merge(mut intervals: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    intervals.sort_unstable();
+    let mut merged = Vec::<(u32, u32)>::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some((_, last)) if start <= last.saturating_add(1) => *last = (*last).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn covers(intervals: &[(u32, u32)], pos: u32) -> bool {
+    let idx = intervals.partition_point(|(start, _)| *start <= pos);
+    idx > 0 && intervals[idx - 1].1 >= pos
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use amplify::confinement::SmallOrdSet;
+
+    use super::*;
+    use crate::{AssignmentType, Input, Opout};
+
+    fn opid(no: u8) -> OpId { OpId::from_byte_array([no; 32]) }
+
+    fn spend(prev_outs: &[(u8, u16)]) -> Inputs {
+        let inputs = prev_outs
+            .iter()
+            .map(|(op, no)| Input::with(Opout::new(opid(*op), AssignmentType::with(1), *no)));
+        Inputs::from(SmallOrdSet::try_from_iter(inputs).unwrap())
+    }
+
+    #[test]
+    fn merge_intervals() {
+        assert_eq!(merge(vec![(4, 5), (0, 1), (2, 3), (7, 9), (8, 8)]), vec![(0, 5), (7, 9)]);
+        assert!(covers(&[(0, 5), (7, 9)], 9));
+        assert!(!covers(&[(0, 5), (7, 9)], 6));
+        assert!(!covers(&[(1, 5)], 0));
+    }
+
+    #[test]
+    fn diamond() {
+        // 1 -> 2 -> 4 -> 5
+        //   \-> 3 -/
+        // 6 -> 7
+        let inputs = [
+            (opid(2), spend(&[(1, 0)])),
+            (opid(3), spend(&[(1, 1)])),
+            (opid(4), spend(&[(2, 0), (3, 0)])),
+            (opid(5), spend(&[(4, 0)])),
+            (opid(7), spend(&[(6, 0)])),
+        ];
+        let index = Reachability::with(inputs.iter().map(|(opid, inputs)| (*opid, inputs)));
+        assert_eq!(index.len(), 7);
+        assert!(index.contains(opid(1)));
+        assert!(!index.contains(opid(8)));
+
+        assert!(index.is_ancestor(opid(1), opid(5)));
+        assert!(index.is_ancestor(opid(2), opid(4)));
+        assert!(index.is_ancestor(opid(3), opid(5)));
+        assert!(!index.is_ancestor(opid(2), opid(3)));
+        assert!(!index.is_ancestor(opid(3), opid(2)));
+        assert!(!index.is_ancestor(opid(5), opid(1)));
+        assert!(!index.is_ancestor(opid(1), opid(1)));
+        assert!(!index.is_ancestor(opid(1), opid(7)));
+        assert!(!index.is_ancestor(opid(6), opid(8)));
+
+        assert_eq!(index.descendants(opid(3)), bset![opid(4), opid(5)]);
+        assert_eq!(index.descendants(opid(1)), bset![opid(2), opid(3), opid(4), opid(5)]);
+        assert_eq!(index.ancestors(opid(4)), bset![opid(1), opid(2), opid(3)]);
+        assert_eq!(index.ancestors(opid(7)), bset![opid(6)]);
+        assert!(index.descendants(opid(5)).is_empty());
+    }
+
+    #[test]
+    fn airdrop() {
+        let inputs = (2..=200u8).map(|no| (opid(no), spend(&[(1, no as u16)])));
+        let inputs = inputs.collect::<Vec<_>>();
+        let index = Reachability::with(inputs.iter().map(|(opid, inputs)| (*opid, inputs)));
+        assert_eq!(index.labels[&opid(1)].intervals.len(), 1);
+        assert!((2..=200u8).all(|no| index.is_ancestor(opid(1), opid(no))));
+        assert!(!index.is_ancestor(opid(2), opid(3)));
+    }
+}
@@ -0,0 +1,269 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Construction of state transitions returning received fungible state back
+//! to its sender.
+//!
+//! [`Refund`] spends an accepted incoming allocation, paying the refunded
+//! amount to the blinded seal provided by the sender with a refund invoice and
+//! returning the rest of the allocation to a change seal of the refunding
+//! party. The refund assignment always goes first, followed by the change
+//! assignment, if any; blinding factors are selected such that the transition
+//! conserves the sum of the Pedersen commitments.
+
+use amplify::confinement::SmallVec;
+use bp::secp256k1::rand::{Rng, RngCore, thread_rng};
+
+use crate::{
+    Assign, AssignmentType, BlindingFactor, ContractId, GraphSeal, Input, Inputs, Invoice,
+    InvoiceState, Opout, OutputAssignment, RevealedValue, SecretSeal, Transition, TypedAssigns,
+    XChain,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum RefundError {
+    /// refund invoice requests state of type {0} while the allocation has type
+    /// {1}.
+    AssignmentTypeMismatch(AssignmentType, AssignmentType),
+
+    /// refund invoice for the seal {0} requests non-fungible state.
+    NoAmount(XChain<SecretSeal>),
+
+    /// refund amount {0} exceeds the allocation amount {1}.
+    AmountExceeded(u64, u64),
+
+    /// partial refund of {0} out of {1} requires a change seal.
+    NoChangeSeal(u64, u64),
+
+    /// transition template belongs to the contract {0} instead of {1}.
+    TemplateContractMismatch(ContractId, ContractId),
+
+    /// transition template already spends inputs or assigns state of the
+    /// refunded type.
+    TemplateNotEmpty,
+
+    /// blinding factors of the refund can't be balanced; please retry.
+    InvalidBlinding,
+}
+
+/// Builder of a transition returning an incoming allocation to its sender.
+#[derive(Clone, Debug)]
+pub struct Refund {
+    contract_id: ContractId,
+    allocation: OutputAssignment<RevealedValue>,
+    refund_seal: XChain<SecretSeal>,
+    amount: u64,
+    change_seal: Option<XChain<GraphSeal>>,
+}
+
+impl Refund {
+    /// Prepares refund of an allocation to the seal of a refund invoice issued
+    /// by the sender. Invoices which don't specify the amount (requesting
+    /// [`InvoiceState::Void`]) refund the whole allocation.
+    pub fn new(
+        allocation: OutputAssignment<RevealedValue>,
+        invoice: &Invoice,
+    ) -> Result<Self, RefundError> {
+        let ty = allocation.opout.ty;
+        if invoice.assignment_type != ty {
+            return Err(RefundError::AssignmentTypeMismatch(invoice.assignment_type, ty));
+        }
+        let total = allocation.state.value.as_u64();
+        let amount = match &invoice.state {
+            InvoiceState::Void => total,
+            InvoiceState::Amount(amount) => amount.as_u64(),
+            InvoiceState::Data(_) => return Err(RefundError::NoAmount(invoice.seal)),
+        };
+        if amount > total {
+            return Err(RefundError::AmountExceeded(amount, total));
+        }
+        Ok(Refund {
+            contract_id: invoice.contract_id,
+            allocation,
+            refund_seal: invoice.seal,
+            amount,
+            change_seal: None,
+        })
+    }
+
+    /// Sets seal receiving the part of the allocation which is not refunded.
+    pub fn with_change(mut self, seal: XChain<GraphSeal>) -> Self {
+        self.change_seal = Some(seal);
+        self
+    }
+
+    /// Refunded amount.
+    pub fn amount(&self) -> u64 { self.amount }
+
+    /// Amount returned to the change seal.
+    pub fn change(&self) -> u64 { self.allocation.state.value.as_u64() - self.amount }
+
+    /// Spent allocation.
+    pub fn input(&self) -> Opout { self.allocation.opout }
+
+    /// Constructs the refund transition from a template provided by the
+    /// wallet, which defines transition type, metadata and global state.
+    ///
+    /// The template must not have inputs or assignments of the refunded type;
+    /// it may contain assignments of other types.
+    pub fn transition(&self, template: Transition) -> Result<Transition, RefundError> {
+        self.transition_with_rng(template, &mut thread_rng())
+    }
+
+    /// Constructs the refund transition like [`Refund::transition`], using
+    /// the provided random generator for creating blinding factors.
+    pub fn transition_with_rng<R: Rng + RngCore>(
+        &self,
+        mut template: Transition,
+        rng: &mut R,
+    ) -> Result<Transition, RefundError> {
+        if template.contract_id != self.contract_id {
+            return Err(RefundError::TemplateContractMismatch(
+                template.contract_id,
+                self.contract_id,
+            ));
+        }
+        let ty = self.allocation.opout.ty;
+        if !template.inputs.is_empty() || template.assignments.contains_key(&ty) {
+            return Err(RefundError::TemplateNotEmpty);
+        }
+
+        let change = self.change();
+        if change > 0 && self.change_seal.is_none() {
+            return Err(RefundError::NoChangeSeal(self.amount, self.amount + change));
+        }
+        let input = &self.allocation.state;
+        let tag = input.tag;
+        let mut assigns = SmallVec::new();
+        // Without change the single output must reuse the input blinding.
+        let refund_blinding = if change > 0 {
+            BlindingFactor::random_custom(rng)
+        } else {
+            input.blinding.clone()
+        };
+        assigns
+            .push(Assign::ConfidentialSeal {
+                seal: self.refund_seal,
+                state: RevealedValue::with_blinding(self.amount, refund_blinding.clone(), tag),
+                lock: default!(),
+            })
+            .expect("single item");
+        if let Some(seal) = self.change_seal.filter(|_| change > 0) {
+            let blinding =
+                BlindingFactor::zero_balanced([input.blinding.clone()], [refund_blinding])
+                    .map_err(|_| RefundError::InvalidBlinding)?;
+            assigns
+                .push(Assign::revealed(seal, RevealedValue::with_blinding(change, blinding, tag)))
+                .expect("two items");
+        }
+
+        template.inputs = Inputs::from(small_bset! { Input::with(self.allocation.opout) });
+        template
+            .assignments
+            .insert(ty, TypedAssigns::Fungible(assigns))
+            .map_err(|_| RefundError::TemplateNotEmpty)?;
+        template.invalidate_id();
+        Ok(template)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use bp::Txid;
+    use bp::dbc::Method;
+    use commit_verify::Conceal;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AssetTag, FungibleState, GenesisSeal, OpId, pedersen_engine};
+
+    #[test]
+    fn refund() {
+        let contract_id = ContractId::from_byte_array([0x6c; 32]);
+        let ty = AssignmentType::with(1);
+        let tag = AssetTag::from([0xAA; 32]);
+        let seal = GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), 0, 0);
+        let allocation = OutputAssignment::with_no_witness(
+            XChain::Bitcoin(seal),
+            RevealedValue::new_random_blinding(100u64, tag),
+            OpId::from_byte_array([0x01; 32]),
+            ty,
+            0,
+        );
+        let sender: XChain<GraphSeal> =
+            XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 1, 1));
+        let change: XChain<GraphSeal> =
+            XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 2, 2));
+        let invoice = |state| Invoice::new(contract_id, ty, state, sender.conceal());
+        let mut template = Transition::strict_dumb();
+        template.contract_id = contract_id;
+
+        let balanced = |transition: &Transition| {
+            let inputs = [Assign::revealed(sender, allocation.state.clone())];
+            let outputs = transition.assignments[&ty].as_fungible();
+            let commitment = |assign: &Assign<RevealedValue, GraphSeal>| {
+                assign.to_confidential_state().commitment
+            };
+            let inputs = inputs.iter().map(commitment).collect::<Vec<_>>();
+            let outputs = outputs.iter().map(commitment).collect::<Vec<_>>();
+            pedersen_engine().verify_sum(&inputs, &outputs) == Ok(true)
+        };
+
+        let full = Refund::new(allocation.clone(), &invoice(InvoiceState::Void)).unwrap();
+        assert_eq!((full.amount(), full.change()), (100, 0));
+        let transition = full.transition(template.clone()).unwrap();
+        assert_eq!(transition.inputs.len(), 1);
+        assert_eq!(transition.assignments[&ty].len_u16(), 1);
+        assert_eq!(transition.assignments[&ty].to_confidential_seal_at(0), Ok(sender.conceal()));
+        assert!(balanced(&transition));
+
+        let partial = invoice(InvoiceState::Amount(FungibleState::Bits64(30)));
+        let refund = Refund::new(allocation.clone(), &partial).unwrap();
+        assert_eq!(refund.transition(template.clone()), Err(RefundError::NoChangeSeal(30, 100)));
+        let transition = refund
+            .with_change(change)
+            .transition(template.clone())
+            .unwrap();
+        let assigns = &transition.assignments[&ty];
+        assert_eq!(assigns.len_u16(), 2);
+        assert_eq!(assigns.revealed_seal_at(1), Ok(Some(change)));
+        assert_eq!(
+            assigns
+                .as_fungible_state_at(1)
+                .unwrap()
+                .map(|state| state.value.as_u64()),
+            Some(70)
+        );
+        assert!(balanced(&transition));
+
+        let excessive = invoice(InvoiceState::Amount(FungibleState::Bits64(101)));
+        assert_eq!(
+            Refund::new(allocation.clone(), &excessive).unwrap_err(),
+            RefundError::AmountExceeded(101, 100)
+        );
+        let mut foreign = template;
+        foreign.contract_id = ContractId::from_byte_array([0x01; 32]);
+        assert!(matches!(full.transition(foreign), Err(RefundError::TemplateContractMismatch(..))));
+    }
+}
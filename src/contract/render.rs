@@ -0,0 +1,171 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering of the contract state into human-readable strings.
+//!
+//! The rendering format for each assignment type is selected by the contract
+//! interface (see [`StateFormat`]), such that explorers and wallets don't need
+//! to interpret amount precisions and token ids on their own. Applications
+//! may provide custom renderers by implementing [`StateRenderer`].
+
+use strict_encoding::FieldName;
+
+use crate::{
+    AssignmentType, ContractState, ExposedState, Iface, Opout, RevealedState, StateFormat,
+    XOutputSeal,
+};
+
+/// Renders revealed state into a human-readable string.
+pub trait StateRenderer {
+    fn render(&self, state: &RevealedState) -> String;
+}
+
+impl<R: StateRenderer + ?Sized> StateRenderer for &R {
+    fn render(&self, state: &RevealedState) -> String { (*self).render(state) }
+}
+
+impl StateRenderer for StateFormat {
+    fn render(&self, state: &RevealedState) -> String {
+        match (self, state) {
+            (StateFormat::Amount(precision), RevealedState::Fungible(value)) => {
+                render_amount(value.value.as_u64(), *precision)
+            }
+            (StateFormat::TokenId, RevealedState::Structured(data)) if data.value.len() <= 16 => {
+                let mut bytes = [0u8; 16];
+                bytes[..data.value.len()].copy_from_slice(&data.value);
+                format!("#{}", u128::from_le_bytes(bytes))
+            }
+            (StateFormat::Digest, RevealedState::Attachment(attach)) => {
+                format!("{} ({})", attach.id, attach.media_type)
+            }
+            (_, RevealedState::Void) => s!("void"),
+            (_, RevealedState::Fungible(value)) => value.value.to_string(),
+            (_, RevealedState::Structured(data)) => data.value.to_string(),
+            (_, RevealedState::Attachment(attach)) => attach.id.to_string(),
+        }
+    }
+}
+
+fn render_amount(value: u64, precision: u8) -> String {
+    let value = value.to_string();
+    let precision = precision as usize;
+    if precision == 0 {
+        return value;
+    }
+    let value = format!("{value:0>width$}", width = precision + 1);
+    let (int, fract) = value.split_at(value.len() - precision);
+    format!("{int}.{fract}")
+}
+
+/// Owned state allocation rendered into a human-readable form.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct RenderedAllocation {
+    /// Name of the assignment type, if known to the interface.
+    pub name: Option<FieldName>,
+    pub opout: Opout,
+    pub seal: XOutputSeal,
+    pub state: String,
+}
+
+impl ContractState {
+    /// Renders all owned state allocations using formats defined by the
+    /// interface.
+    pub fn render_allocations(&self, iface: &Iface) -> Vec<RenderedAllocation> {
+        self.render_allocations_with(iface, |ty| iface.state_format(ty))
+    }
+
+    /// Renders all owned state allocations with renderers selected for each
+    /// assignment type by a custom callback.
+    pub fn render_allocations_with<R: StateRenderer>(
+        &self,
+        iface: &Iface,
+        renderer: impl Fn(AssignmentType) -> R,
+    ) -> Vec<RenderedAllocation> {
+        let render = |opout: Opout, seal: XOutputSeal, state: RevealedState| RenderedAllocation {
+            name: iface.assignment_name(opout.ty).cloned(),
+            opout,
+            seal,
+            state: renderer(opout.ty).render(&state),
+        };
+        let rights = self
+            .rights()
+            .iter()
+            .map(|a| render(a.opout, a.seal, a.state.state_data()));
+        let fungibles = self
+            .fungibles()
+            .iter()
+            .map(|a| render(a.opout, a.seal, a.state.state_data()));
+        let data = self
+            .data()
+            .iter()
+            .map(|a| render(a.opout, a.seal, a.state.state_data()));
+        let attach = self
+            .attach()
+            .iter()
+            .map(|a| render(a.opout, a.seal, a.state.state_data()));
+        rights.chain(fungibles).chain(data).chain(attach).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        AssetTag, AttachId, BlindingFactor, DataState, MediaType, RevealedAttach, RevealedData,
+        RevealedValue,
+    };
+
+    #[test]
+    fn formats() {
+        let amount = RevealedState::Fungible(RevealedValue::with_blinding(
+            12345u64,
+            BlindingFactor::EMPTY,
+            AssetTag::from([0u8; 32]),
+        ));
+        assert_eq!(StateFormat::Plain.render(&amount), "12345");
+        assert_eq!(StateFormat::Amount(0).render(&amount), "12345");
+        assert_eq!(StateFormat::Amount(2).render(&amount), "123.45");
+        assert_eq!(StateFormat::Amount(8).render(&amount), "0.00012345");
+        assert_eq!(StateFormat::TokenId.render(&amount), "12345");
+
+        let token = RevealedState::Structured(RevealedData::with_salt(
+            DataState::from(small_vec![0x01, 0x02]),
+            0,
+        ));
+        assert_eq!(StateFormat::TokenId.render(&token), "#513");
+        assert_eq!(StateFormat::Plain.render(&token), "0102");
+
+        let attach = RevealedState::Attachment(RevealedAttach::with_salt(
+            AttachId::from([0u8; 32]),
+            MediaType::Any,
+            0,
+        ));
+        let id = AttachId::from([0u8; 32]);
+        assert_eq!(StateFormat::Digest.render(&attach), format!("{id} (*/*)"));
+        assert_eq!(StateFormat::Amount(2).render(&RevealedState::Void), "void");
+    }
+}
@@ -0,0 +1,116 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Binding of contracts to the text of a Ricardian contract.
+//!
+//! Contract commits to the SHA256 hash of the full legal text using a global
+//! state type of a Ricardian kind (see [`crate::Schema::ricardian`]). Since
+//! the hash is computed in the same way as [`AttachId`], the text itself may
+//! be distributed as an attachment under the same id.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::confinement::SmallBlob;
+use amplify::hex::{self, FromHex, ToHex};
+use amplify::{ByteArray, Bytes32};
+use commit_verify::{DigestExt, Sha256};
+
+use crate::{AttachId, DataState, LIB_NAME_RGB};
+
+/// SHA256 hash of the Ricardian contract text.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct RicardianHash(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl RicardianHash {
+    /// Computes hash of the contract text.
+    pub fn from_text(text: &str) -> Self {
+        let mut hasher = Sha256::default();
+        hasher.input_raw(text.as_bytes());
+        Self::from(hasher.finish())
+    }
+
+    /// Checks whether the text matches the hash.
+    pub fn verify_text(&self, text: &str) -> bool { *self == Self::from_text(text) }
+
+    /// Constructs global state value committing to the hash.
+    pub fn to_data_state(&self) -> DataState {
+        DataState::from(
+            SmallBlob::try_from(self.to_byte_array().to_vec()).expect("32 bytes fit small blob"),
+        )
+    }
+}
+
+impl TryFrom<&DataState> for RicardianHash {
+    type Error = usize;
+
+    fn try_from(state: &DataState) -> Result<Self, Self::Error> {
+        Bytes32::copy_from_slice(state.as_slice())
+            .map(Self)
+            .map_err(|_| state.len())
+    }
+}
+
+impl From<RicardianHash> for AttachId {
+    fn from(hash: RicardianHash) -> Self { AttachId::from(hash.to_byte_array()) }
+}
+
+impl From<AttachId> for RicardianHash {
+    fn from(id: AttachId) -> Self { RicardianHash::from(id.to_byte_array()) }
+}
+
+impl Display for RicardianHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&self.to_hex()) }
+}
+
+impl FromStr for RicardianHash {
+    type Err = hex::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn text_hash() {
+        let text = "The issuer undertakes to redeem each token for 1 USD.";
+        let hash = RicardianHash::from_text(text);
+        assert!(hash.verify_text(text));
+        assert!(!hash.verify_text("The issuer undertakes nothing."));
+        assert_eq!(RicardianHash::try_from(&hash.to_data_state()), Ok(hash));
+        assert_eq!(RicardianHash::from(AttachId::from(hash)), hash);
+        assert_eq!(hash.to_string().parse::<RicardianHash>().unwrap(), hash);
+    }
+}
@@ -26,23 +26,26 @@ use std::hash::Hash;
 use std::num::NonZeroU32;
 
 use bp::dbc::Method;
-pub use bp::seals::txout::blind::{ChainBlindSeal, ParseError, SingleBlindSeal};
+pub use bp::seals::SecretSeal;
 pub use bp::seals::txout::TxoSeal;
+pub use bp::seals::txout::blind::{ChainBlindSeal, ParseError, SingleBlindSeal};
 use bp::seals::txout::{BlindSeal, CloseMethod, ExplicitSeal, SealTxid, VerifyError, Witness};
-pub use bp::seals::SecretSeal;
-use bp::{dbc, Outpoint, Tx, Txid, Vout};
-use commit_verify::{mpc, Conceal};
+use bp::{Outpoint, Tx, Txid, Vout, dbc};
+use commit_verify::{Conceal, mpc};
 use single_use_seals::SealWitness;
 use strict_encoding::{StrictDecode, StrictDumb, StrictEncode, StrictType};
 
 use crate::contract::xchain::Impossible;
-use crate::{XChain, XOutpoint, LIB_NAME_RGB};
+use crate::{LIB_NAME_RGB, Layer1, XChain, XOutpoint};
 
 pub type GenesisSeal = SingleBlindSeal<Method>;
 pub type GraphSeal = ChainBlindSeal<Method>;
 
 pub type OutputSeal = ExplicitSeal<Txid, Method>;
 
+/// Id of a witness transaction on any of the supported layers 1. Public APIs
+/// refer to witnesses with this id rather than with a layer 1 specific
+/// transaction id, so adding a new layer 1 doesn't change them.
 pub type XWitnessId = XChain<Txid>;
 
 pub type XGenesisSeal = XChain<GenesisSeal>;
@@ -136,7 +139,42 @@ impl XChain<GenesisSeal> {
     pub fn to_outpoint(&self) -> XOutpoint { self.map_ref(GenesisSeal::to_outpoint).into() }
 }
 
+/// Errors resolving a seal definition into a fully specified transaction
+/// output.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SealResolutionError {
+    /// seal references the witness transaction of the operation defining it,
+    /// but the operation is not anchored to any witness transaction.
+    NoWitness,
+
+    /// seal is defined on {seal} layer 1, while the witness transaction of the
+    /// operation defining it belongs to {witness}.
+    ChainMismatch { seal: Layer1, witness: Layer1 },
+}
+
 impl<U: ExposedSeal> XChain<U> {
+    /// Resolves the seal into a fully specified transaction output. Seals
+    /// which do not define txid reference the witness transaction of the
+    /// operation defining them, which must be provided as `witness_id`.
+    pub fn resolve(
+        self,
+        witness_id: Option<XWitnessId>,
+    ) -> Result<XOutputSeal, SealResolutionError>
+    where
+        U: TxoSeal,
+    {
+        if let Some(seal) = self.to_output_seal() {
+            return Ok(seal);
+        }
+        let witness_id = witness_id.ok_or(SealResolutionError::NoWitness)?;
+        self.try_to_output_seal(witness_id)
+            .map_err(|_| SealResolutionError::ChainMismatch {
+                seal: self.layer1(),
+                witness: witness_id.layer1(),
+            })
+    }
+
     pub fn method(self) -> CloseMethod
     where U: TxoSeal {
         match self {
@@ -318,4 +356,26 @@ mod test {
         );
         assert_eq!(reveal.to_secret_seal(), reveal.conceal())
     }
+
+    #[test]
+    fn resolve_witness_seal() {
+        let seal = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::TapretFirst, 2, 0));
+        assert_eq!(seal.resolve(None), Err(SealResolutionError::NoWitness));
+
+        let txid = Txid::from([0x6c; 32]);
+        let resolved = seal.resolve(Some(XChain::Bitcoin(txid))).unwrap();
+        assert_eq!(resolved.to_outpoint(), XChain::Bitcoin(Outpoint::new(txid, 2)).into());
+        assert_eq!(
+            seal.resolve(Some(XChain::Liquid(txid))),
+            Err(SealResolutionError::ChainMismatch {
+                seal: Layer1::Bitcoin,
+                witness: Layer1::Liquid
+            })
+        );
+
+        let genesis_seal = XChain::Bitcoin(GraphSeal::new_random(Method::TapretFirst, txid, 1));
+        let resolved =
+            XChain::Bitcoin(ExplicitSeal::new(Method::TapretFirst, Outpoint::new(txid, 1)));
+        assert_eq!(genesis_seal.resolve(Some(XChain::Liquid(txid))), Ok(resolved));
+    }
 }
@@ -0,0 +1,349 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Snapshots of the computed contract state, allowing wallets to persist it
+//! and to restore it at startup without revalidating the contract history.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use amplify::confinement::{LargeOrdMap, LargeOrdSet, TinyOrdMap};
+use amplify::hex::{FromHex, ToHex};
+use amplify::{Bytes32, FromSliceError, Wrapper, hex};
+use commit_verify::{
+    CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, Sha256, StrictHash,
+};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{
+    ContractHistory, ContractState, DataState, GlobalOrd, GlobalStateType, LIB_NAME_RGB, OpId,
+    Schema, SchemaId,
+};
+
+/// Version of the contract state snapshot format.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(lowercase)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[repr(u8)]
+pub enum SnapshotVersion {
+    #[strict_type(dumb)]
+    V0 = 0,
+}
+
+/// Integrity hash of a contract state snapshot, committing to all of the
+/// snapshot data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(Self::to_hex)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct SnapshotHash(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for SnapshotHash {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for SnapshotHash {
+    const TAG: &'static str = "urn:lnp-bp:rgb:state-snapshot#2024-06-12";
+}
+
+impl FromStr for SnapshotHash {
+    type Err = hex::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+}
+
+impl SnapshotHash {
+    pub fn copy_from_slice(slice: impl AsRef<[u8]>) -> Result<Self, FromSliceError> {
+        Bytes32::copy_from_slice(slice).map(Self)
+    }
+}
+
+/// Errors restoring contract state from a snapshot.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SnapshotError {
+    /// snapshot is made for a contract under schema {actual}, while schema
+    /// {expected} was provided.
+    SchemaMismatch {
+        expected: SchemaId,
+        actual: SchemaId,
+    },
+
+    /// snapshot checksum {actual} doesn't match the snapshot data, which hash
+    /// to {expected}.
+    ChecksumMismatch {
+        expected: SnapshotHash,
+        actual: SnapshotHash,
+    },
+
+    /// tip operations listed in the snapshot don't match its contract history.
+    InvalidTips,
+
+    /// metadata digest in the snapshot doesn't match the global state of its
+    /// contract history.
+    InvalidMetadata,
+}
+
+/// Strict-encoded snapshot of the computed contract state.
+///
+/// Snapshot contains all owned state allocations and witness statuses
+/// (as a part of the contract history), together with the ids of the tip
+/// operations and a digest of the global state, and is protected with an
+/// integrity checksum.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ContractStateSnapshot {
+    pub version: SnapshotVersion,
+    pub history: ContractHistory,
+    /// Operations having at least one assignment which is not spent by any
+    /// of the operations from the history.
+    pub tips: LargeOrdSet<OpId>,
+    /// Digest of the contract global state.
+    pub metadata: StrictHash,
+    pub checksum: SnapshotHash,
+}
+
+impl StrictSerialize for ContractStateSnapshot {}
+impl StrictDeserialize for ContractStateSnapshot {}
+
+impl CommitEncode for ContractStateSnapshot {
+    type CommitmentId = SnapshotHash;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        e.commit_to_serialized(&self.version);
+        e.commit_to_serialized(&self.history);
+        e.commit_to_set(&self.tips);
+        // Metadata digest is derived from the history, which is already
+        // committed to, and is checked against it in `verify`.
+    }
+}
+
+struct GlobalDigest<'a>(&'a TinyOrdMap<GlobalStateType, LargeOrdMap<GlobalOrd, DataState>>);
+
+impl CommitEncode for GlobalDigest<'_> {
+    type CommitmentId = StrictHash;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        for (ty, state) in self.0 {
+            e.commit_to_serialized(ty);
+            e.commit_to_map(state);
+        }
+    }
+}
+
+impl ContractStateSnapshot {
+    pub fn with(history: ContractHistory) -> Self {
+        let mut snapshot = ContractStateSnapshot {
+            version: SnapshotVersion::V0,
+            tips: Self::compute_tips(&history),
+            metadata: GlobalDigest(history.global_state()).commit_id(),
+            history,
+            checksum: strict_dumb!(),
+        };
+        snapshot.checksum = snapshot.commit_id();
+        snapshot
+    }
+
+    pub fn schema_id(&self) -> SchemaId { self.history.schema_id() }
+
+    /// Checks snapshot integrity and consistency of the tips and metadata
+    /// digest with the contract history.
+    pub fn verify(&self) -> Result<(), SnapshotError> {
+        let expected = self.commit_id();
+        if expected != self.checksum {
+            return Err(SnapshotError::ChecksumMismatch {
+                expected,
+                actual: self.checksum,
+            });
+        }
+        if self.tips != Self::compute_tips(&self.history) {
+            return Err(SnapshotError::InvalidTips);
+        }
+        if self.metadata != GlobalDigest(self.history.global_state()).commit_id() {
+            return Err(SnapshotError::InvalidMetadata);
+        }
+        Ok(())
+    }
+
+    fn compute_tips(history: &ContractHistory) -> LargeOrdSet<OpId> {
+        let spent = history
+            .inputs()
+            .values()
+            .flat_map(|inputs| inputs.iter().map(|input| input.prev_out))
+            .collect::<BTreeSet<_>>();
+        let opouts = history
+            .rights()
+            .iter()
+            .map(|a| a.opout)
+            .chain(history.fungibles().iter().map(|a| a.opout))
+            .chain(history.data().iter().map(|a| a.opout))
+            .chain(history.attach().iter().map(|a| a.opout))
+            .chain(history.amounts().keys().copied())
+            .chain(history.concealed().iter().copied());
+        let tips = opouts
+            .filter(|opout| !spent.contains(opout))
+            .map(|opout| opout.op);
+        LargeOrdSet::try_from_iter(tips).expect("tips are a subset of the history operations")
+    }
+}
+
+impl ContractState {
+    /// Exports computed contract state as a snapshot which can be persisted
+    /// and later restored with [`ContractState::import_snapshot`].
+    pub fn export_snapshot(&self) -> ContractStateSnapshot {
+        ContractStateSnapshot::with(self.history.clone())
+    }
+
+    /// Restores contract state from a snapshot previously produced by
+    /// [`ContractState::export_snapshot`], checking the snapshot integrity.
+    pub fn import_snapshot(
+        schema: Schema,
+        snapshot: ContractStateSnapshot,
+    ) -> Result<Self, SnapshotError> {
+        let expected = schema.schema_id();
+        let actual = snapshot.schema_id();
+        if expected != actual {
+            return Err(SnapshotError::SchemaMismatch { expected, actual });
+        }
+        snapshot.verify()?;
+        Ok(ContractState {
+            schema,
+            history: snapshot.history,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement::{SmallOrdSet, SmallVec};
+    use bp::Txid;
+    use bp::dbc::Method;
+    use chrono::DateTime;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, Assign, AssignmentType, Assignments, BlindingFactor, Genesis, GenesisSeal,
+        GraphSeal, Input, Inputs, Operation, Opout, RevealedValue, Transition, TypedAssigns,
+        WitnessAnchor, XChain,
+    };
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+
+    fn amounts<Seal: crate::ExposedSeal>(
+        values: &[u64],
+        seal: impl Fn(u32) -> Seal,
+    ) -> Assignments<Seal> {
+        let tag = AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0);
+        let blinding = BlindingFactor::from_str(
+            "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53",
+        )
+        .unwrap();
+        let assigns = values
+            .iter()
+            .enumerate()
+            .map(|(no, value)| {
+                let state = RevealedValue::with_blinding(*value, blinding.clone(), tag);
+                Assign::revealed(XChain::Bitcoin(seal(no as u32)), state)
+            })
+            .collect::<Vec<_>>();
+        Assignments::from(tiny_bmap! {
+            ASSET => TypedAssigns::Fungible(SmallVec::try_from(assigns).unwrap()),
+        })
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let schema = Schema::strict_dumb();
+        let mut genesis = Genesis::strict_dumb();
+        genesis.assignments = amounts(&[60, 40], |vout| {
+            GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, 0)
+        });
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = ContractState { schema, history };
+
+        let mut transfer = Transition::strict_dumb();
+        let input = Input::with(Opout::new(genesis.id(), ASSET, 0));
+        transfer.inputs = Inputs::from(SmallOrdSet::try_from_iter([input]).unwrap());
+        transfer.assignments = amounts(&[60], |vout| {
+            GraphSeal::with_blinded_vout(Method::OpretFirst, vout, vout as u64)
+        });
+        let witness_id = XChain::Bitcoin(Txid::strict_dumb());
+        state.add_transition(&transfer, WitnessAnchor::from_mempool(witness_id));
+
+        let snapshot = state.export_snapshot();
+        assert_eq!(snapshot.tips.clone().into_inner(), bset! { genesis.id(), transfer.id() });
+        assert_eq!(snapshot.history.witnesses()[&transfer.id()].witness_id, witness_id);
+
+        let data = snapshot
+            .to_strict_serialized::<{ u32::MAX as usize }>()
+            .unwrap();
+        let restored =
+            ContractStateSnapshot::from_strict_serialized::<{ u32::MAX as usize }>(data).unwrap();
+        assert_eq!(restored, snapshot);
+        let imported = ContractState::import_snapshot(state.schema.clone(), restored).unwrap();
+        assert_eq!(imported, state);
+
+        let mut other = state.schema.clone();
+        other.timestamp += 1;
+        assert_eq!(
+            ContractState::import_snapshot(other.clone(), snapshot.clone()),
+            Err(SnapshotError::SchemaMismatch {
+                expected: other.schema_id(),
+                actual: state.schema.schema_id(),
+            })
+        );
+
+        let mut tampered = snapshot.clone();
+        tampered.tips.push(OpId::strict_dumb()).unwrap();
+        assert!(matches!(tampered.verify(), Err(SnapshotError::ChecksumMismatch { .. })));
+        tampered.checksum = tampered.commit_id();
+        assert_eq!(tampered.verify(), Err(SnapshotError::InvalidTips));
+
+        let mut tampered = snapshot;
+        tampered.metadata = strict_dumb!();
+        assert_eq!(tampered.verify(), Err(SnapshotError::InvalidMetadata));
+    }
+}
@@ -126,3 +126,54 @@ impl ConfidentialState for ConcealedState {
     }
     fn state_commitment(&self) -> ConcealedState { *self }
 }
+
+/// State of a single assignment with the specific type of the state being
+/// erased, which may be either revealed or known only by its commitment.
+#[derive(Clone, Eq, PartialEq, Debug, From)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[allow(clippy::large_enum_variant)]
+pub enum StateAtom {
+    #[from]
+    Revealed(RevealedState),
+    #[from]
+    Concealed(ConcealedState),
+}
+
+impl StateAtom {
+    pub fn state_type(&self) -> StateType {
+        match self {
+            StateAtom::Revealed(state) => state.state_type(),
+            StateAtom::Concealed(state) => state.state_type(),
+        }
+    }
+
+    pub fn is_revealed(&self) -> bool { matches!(self, StateAtom::Revealed(_)) }
+
+    pub fn as_revealed(&self) -> Option<&RevealedState> {
+        match self {
+            StateAtom::Revealed(state) => Some(state),
+            StateAtom::Concealed(_) => None,
+        }
+    }
+
+    /// Returns commitment to the state, concealing it if it is revealed.
+    pub fn commitment(&self) -> ConcealedState {
+        match self {
+            StateAtom::Revealed(RevealedState::Void) => ConcealedState::Void,
+            StateAtom::Revealed(RevealedState::Fungible(state)) => {
+                ConcealedState::Fungible(state.conceal())
+            }
+            StateAtom::Revealed(RevealedState::Structured(state)) => {
+                ConcealedState::Structured(state.conceal())
+            }
+            StateAtom::Revealed(RevealedState::Attachment(state)) => {
+                ConcealedState::Attachment(state.conceal())
+            }
+            StateAtom::Concealed(state) => *state,
+        }
+    }
+}
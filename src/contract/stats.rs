@@ -0,0 +1,293 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Statistics of the contract history for explorers and dashboards.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::U32 as U32MAX;
+use strict_encoding::StrictSerialize;
+
+use crate::{ContractId, ContractState, OpId, WitnessOrd};
+
+/// Statistics of a contract history, returned by [`ContractState::stats`].
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ContractStats {
+    pub contract_id: ContractId,
+    /// Number of state transitions in the history.
+    pub transitions: u32,
+    /// Number of state extensions in the history.
+    pub extensions: u32,
+    /// Number of global state items.
+    pub global_items: u32,
+    /// Number of assignments of rights with revealed seals.
+    pub rights: u32,
+    /// Number of fungible assignments with revealed seals and state.
+    pub fungibles: u32,
+    /// Number of structured data assignments with revealed seals.
+    pub data: u32,
+    /// Number of attachment assignments with revealed seals.
+    pub attachments: u32,
+    /// Total number of assignments with revealed seals.
+    pub total_assignments: u32,
+    /// Number of fungible assignments with revealed amounts, including the
+    /// ones with concealed seals.
+    pub revealed_amounts: u32,
+    /// Number of fungible assignments with concealed amounts.
+    pub concealed_amounts: u32,
+    /// Share of the fungible assignments with concealed amounts, from zero
+    /// to one.
+    pub confidential_ratio: f64,
+    /// Length of the longest chain of state transitions starting at the
+    /// genesis.
+    pub depth: u32,
+    /// Size of the strict-serialized schema, in bytes.
+    pub schema_size: u64,
+    /// Size of the strict-serialized contract history, in bytes.
+    pub history_size: u64,
+    /// Height of the oldest mined witness transaction.
+    pub oldest_height: Option<u32>,
+    /// Height of the most recent mined witness transaction.
+    pub newest_height: Option<u32>,
+}
+
+impl ContractState {
+    /// Computes statistics of the contract history.
+    pub fn stats(&self) -> ContractStats {
+        let transitions = self.inputs().len() as u32;
+        let extensions = self
+            .witnesses()
+            .keys()
+            .filter(|opid| !self.inputs().contains_key(*opid))
+            .count() as u32;
+        let global_items = self
+            .global_state()
+            .values()
+            .map(|items| items.len() as u32)
+            .sum();
+
+        let rights = self.rights().len() as u32;
+        let fungibles = self.fungibles().len() as u32;
+        let data = self.data().len() as u32;
+        let attachments = self.attach().len() as u32;
+        let revealed_amounts = self.amounts().len() as u32;
+        let concealed_amounts = self.concealed().len() as u32;
+        let confidential_ratio = match revealed_amounts + concealed_amounts {
+            0 => 0.0,
+            total => concealed_amounts as f64 / total as f64,
+        };
+
+        let heights = self
+            .witnesses()
+            .values()
+            .filter_map(|anchor| match anchor.witness_ord {
+                WitnessOrd::OnChain(pos) => Some(pos.height().get()),
+                WitnessOrd::OffChain => None,
+            })
+            .collect::<Vec<_>>();
+
+        ContractStats {
+            contract_id: self.contract_id(),
+            transitions,
+            extensions,
+            global_items,
+            rights,
+            fungibles,
+            data,
+            attachments,
+            total_assignments: rights + fungibles + data + attachments,
+            revealed_amounts,
+            concealed_amounts,
+            confidential_ratio,
+            depth: self.depth(),
+            schema_size: self
+                .schema
+                .strict_serialized_len::<U32MAX>()
+                .expect("in-memory counter") as u64,
+            history_size: self
+                .history
+                .strict_serialized_len::<U32MAX>()
+                .expect("in-memory counter") as u64,
+            oldest_height: heights.iter().min().copied(),
+            newest_height: heights.iter().max().copied(),
+        }
+    }
+
+    /// Length of the longest chain of the state transitions spending each
+    /// other.
+    fn depth(&self) -> u32 {
+        let mut depths = BTreeMap::<OpId, u32>::new();
+        for opid in self.inputs().keys() {
+            let mut stack = vec![*opid];
+            while let Some(opid) = stack.last().copied() {
+                if depths.contains_key(&opid) {
+                    stack.pop();
+                    continue;
+                }
+                let Some(inputs) = self.inputs().get(&opid) else {
+                    // Genesis, extensions and operations unknown to the history
+                    depths.insert(opid, 0);
+                    stack.pop();
+                    continue;
+                };
+                let pending = inputs
+                    .iter()
+                    .map(|input| input.prev_out.op)
+                    .filter(|prev| *prev != opid && !depths.contains_key(prev))
+                    .collect::<Vec<_>>();
+                if pending.is_empty() {
+                    let depth = inputs
+                        .iter()
+                        .filter_map(|input| depths.get(&input.prev_out.op))
+                        .max()
+                        .copied()
+                        .unwrap_or_default();
+                    depths.insert(opid, depth + 1);
+                    stack.pop();
+                } else {
+                    stack.extend(pending);
+                }
+            }
+        }
+        depths.values().max().copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::ByteArray;
+    use amplify::confinement::{SmallOrdSet, SmallVec};
+    use bp::Txid;
+    use bp::dbc::Method;
+    use chrono::DateTime;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, Assign, AssignmentType, Assignments, BlindingFactor, ContractHistory,
+        ExposedSeal, FungibleType, Genesis, GenesisSeal, GraphSeal, Input, Inputs, Operation,
+        Opout, OwnedStateSchema, RevealedValue, Schema, Transition, TypedAssigns, WitnessAnchor,
+        WitnessPos, XChain,
+    };
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+
+    fn amounts<Seal: ExposedSeal>(values: &[u64], seal: impl Fn(u32) -> Seal) -> Assignments<Seal> {
+        let tag = AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0);
+        let blinding = BlindingFactor::from_str(
+            "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53",
+        )
+        .unwrap();
+        let assigns = values
+            .iter()
+            .enumerate()
+            .map(|(no, value)| {
+                let state = RevealedValue::with_blinding(*value, blinding.clone(), tag);
+                Assign::revealed(XChain::Bitcoin(seal(no as u32)), state)
+            })
+            .collect::<Vec<_>>();
+        Assignments::from(tiny_bmap! {
+            ASSET => TypedAssigns::Fungible(SmallVec::try_from(assigns).unwrap()),
+        })
+    }
+
+    fn transition(inputs: &[Opout], outputs: &[u64]) -> Transition {
+        let mut transition = Transition::strict_dumb();
+        let inputs = inputs.iter().copied().map(Input::with);
+        transition.inputs = Inputs::from(SmallOrdSet::try_from_iter(inputs).unwrap());
+        transition.assignments = amounts(outputs, |vout| {
+            GraphSeal::with_blinded_vout(Method::OpretFirst, vout, vout as u64)
+        });
+        transition
+    }
+
+    fn anchor(no: u8, height: Option<u32>) -> WitnessAnchor {
+        let witness_id = XChain::Bitcoin(Txid::from_byte_array([no; 32]));
+        match height {
+            Some(height) => WitnessAnchor {
+                witness_ord: WitnessOrd::OnChain(
+                    WitnessPos::new(height, 1231006505 + height as i64 * 600).unwrap(),
+                ),
+                witness_id,
+            },
+            None => WitnessAnchor::from_mempool(witness_id),
+        }
+    }
+
+    #[test]
+    fn contract_stats() {
+        let mut schema = Schema::strict_dumb();
+        schema.owned_types =
+            tiny_bmap! { ASSET => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit) };
+
+        let mut genesis = Genesis::strict_dumb();
+        genesis.assignments = amounts(&[60, 40], |vout| {
+            GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, 0)
+        });
+        let genesis_id = genesis.id();
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = ContractState { schema, history };
+
+        let empty = state.stats();
+        assert_eq!(empty.transitions, 0);
+        assert_eq!(empty.depth, 0);
+        assert_eq!(empty.fungibles, 2);
+        assert_eq!(empty.confidential_ratio, 0.0);
+        assert_eq!(empty.oldest_height, None);
+
+        let transfer = transition(&[Opout::new(genesis_id, ASSET, 0)], &[50, 10]);
+        let next = transition(&[Opout::new(transfer.id(), ASSET, 0)], &[50]);
+        let mut hidden = transition(&[Opout::new(genesis_id, ASSET, 1)], &[40]);
+        let assign = &mut hidden
+            .assignments
+            .get_mut(&ASSET)
+            .unwrap()
+            .as_fungible_mut()
+            .unwrap()[0];
+        *assign = assign.conceal_state();
+        state.add_transition(&transfer, anchor(1, Some(100)));
+        state.add_transition(&next, anchor(2, Some(120)));
+        state.add_transition(&hidden, anchor(3, None));
+
+        let stats = state.stats();
+        assert_eq!(stats.contract_id, state.contract_id());
+        assert_eq!(stats.transitions, 3);
+        assert_eq!(stats.extensions, 0);
+        assert_eq!(stats.global_items, 0);
+        assert_eq!(stats.fungibles, 5);
+        assert_eq!(stats.total_assignments, 5);
+        assert_eq!(stats.revealed_amounts, 5);
+        assert_eq!(stats.concealed_amounts, 1);
+        assert_eq!(stats.confidential_ratio, 1.0 / 6.0);
+        assert_eq!(stats.depth, 2);
+        assert_eq!(stats.oldest_height, Some(100));
+        assert_eq!(stats.newest_height, Some(120));
+        assert!(stats.schema_size > 0);
+        assert!(stats.history_size > empty.history_size);
+    }
+}
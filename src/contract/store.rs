@@ -0,0 +1,127 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage of the contract operation graph nodes, allowing downstream stashes
+//! to plug in their own persistence while reusing the traversal logic of
+//! [`ContractState`] and the validator.
+
+use std::error::Error;
+
+use crate::{
+    BundleId, BundlePool, ContractId, ContractState, EAnchor, PoolError, TransitionBundle,
+    WitnessAnchor, WitnessOrd, XWitnessId,
+};
+
+/// Storage of transition bundles and their anchors, indexed by bundle ids.
+///
+/// [`BundlePool`] provides an in-memory implementation.
+pub trait NodeStore {
+    type Error: Error;
+
+    fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle>;
+
+    fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)>;
+
+    fn bundle_ids(&self) -> impl Iterator<Item = BundleId> + '_;
+
+    /// Puts anchored bundle into the store. If the bundle is already present,
+    /// the store must merge revealed data from both versions of the bundle.
+    fn put(
+        &mut self,
+        witness_id: XWitnessId,
+        anchor: EAnchor,
+        bundle: TransitionBundle,
+    ) -> Result<BundleId, Self::Error>;
+
+    /// Iterates over ids of the bundles containing state transitions of a
+    /// given contract.
+    fn contract_bundle_ids(&self, contract_id: ContractId) -> impl Iterator<Item = BundleId> + '_ {
+        self.bundle_ids().filter(move |bundle_id| {
+            self.bundle(*bundle_id).is_some_and(|bundle| {
+                bundle
+                    .known_transitions
+                    .values()
+                    .any(|transition| transition.contract_id == contract_id)
+            })
+        })
+    }
+}
+
+impl NodeStore for BundlePool {
+    type Error = PoolError;
+
+    fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+        BundlePool::bundle(self, bundle_id)
+    }
+
+    fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)> {
+        BundlePool::anchor(self, bundle_id)
+    }
+
+    fn bundle_ids(&self) -> impl Iterator<Item = BundleId> + '_ { BundlePool::bundle_ids(self) }
+
+    fn put(
+        &mut self,
+        witness_id: XWitnessId,
+        anchor: EAnchor,
+        bundle: TransitionBundle,
+    ) -> Result<BundleId, Self::Error> {
+        let bundle_id = bundle.bundle_id();
+        self.add(witness_id, anchor, bundle)?;
+        Ok(bundle_id)
+    }
+}
+
+impl ContractState {
+    /// Adds to the state all transitions of the contract from the bundles kept
+    /// in the store, using `witness_ord` to get the status of their witness
+    /// transactions. Returns number of the added transitions.
+    ///
+    /// The store must contain only bundles which were validated before.
+    pub fn add_stored(
+        &mut self,
+        store: &impl NodeStore,
+        witness_ord: impl Fn(XWitnessId) -> WitnessOrd,
+    ) -> usize {
+        let contract_id = self.contract_id();
+        let mut count = 0;
+        for bundle_id in store.contract_bundle_ids(contract_id) {
+            let (Some(bundle), Some((witness_id, _))) =
+                (store.bundle(bundle_id), store.anchor(bundle_id))
+            else {
+                continue;
+            };
+            let witness_anchor = WitnessAnchor {
+                witness_ord: witness_ord(witness_id),
+                witness_id,
+            };
+            for transition in bundle.known_transitions.values() {
+                if transition.contract_id != contract_id {
+                    continue;
+                }
+                self.add_transition(transition, witness_anchor);
+                count += 1;
+            }
+        }
+        count
+    }
+}
@@ -0,0 +1,286 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Supply audit reports for the fungible state of a contract.
+//!
+//! Reports are built from the contract history and account only for the
+//! state known to it. Operations which spend or assign fungible state with
+//! concealed amounts, or spend state unknown to the history, are listed as
+//! having unknown confidential portions and are not included into the totals.
+//!
+//! Burn-and-replace operations don't change the supply and are
+//! indistinguishable from transfers at the consensus level, so they don't
+//! appear in the reports.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use amplify::Wrapper;
+
+use crate::{AssignmentType, ContractId, ContractState, OpId, OwnedStateSchema, SchemaFlags};
+
+/// Supply report for all fungible state types of a contract.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SupplyReport {
+    pub contract_id: ContractId,
+    pub assets: BTreeMap<AssignmentType, AssetSupply>,
+}
+
+/// Supply of a single fungible state type.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AssetSupply {
+    /// Amount revealed by the genesis allocations.
+    pub genesis: u64,
+    /// Amount declared as issued by the genesis global state, if the schema
+    /// has a fixed supply of the type (see [`SchemaFlags::FIXED_SUPPLY`]).
+    pub declared: Option<u64>,
+    /// Secondary issues: amounts created by state extensions, and by state
+    /// transitions assigning more than they spend.
+    pub issues: BTreeMap<OpId, u64>,
+    /// Amounts destroyed by state transitions assigning less than they spend.
+    pub burns: BTreeMap<OpId, u64>,
+    /// Genesis issue plus all secondary issues less all burns.
+    pub circulating: u64,
+    /// Operations with unknown confidential portions of the state.
+    pub unknown: BTreeSet<OpId>,
+}
+
+impl ContractState {
+    /// Produces supply report for all fungible state types of the contract.
+    pub fn supply_report(&self) -> SupplyReport {
+        let genesis_id = OpId::from_inner(self.contract_id().into_inner());
+        let mut assets = BTreeMap::new();
+
+        for ty in self
+            .schema
+            .owned_types
+            .iter()
+            .filter(|(_, schema)| matches!(schema, OwnedStateSchema::Fungible(_)))
+            .map(|(ty, _)| *ty)
+        {
+            let mut supply = AssetSupply::default();
+
+            // Amounts assigned and spent by each of the operations; `None` if
+            // some of them are unknown.
+            let mut ops = BTreeMap::<OpId, (Option<u64>, Option<u64>)>::new();
+            let amounts = self
+                .amounts()
+                .iter()
+                .map(|(opout, amount)| (opout, Some(*amount)))
+                .chain(self.concealed().iter().map(|opout| (opout, None)));
+            for (opout, amount) in amounts.filter(|(opout, _)| opout.ty == ty) {
+                let (assigned, _) = ops.entry(opout.op).or_insert((Some(0), Some(0)));
+                *assigned = assigned
+                    .zip(amount)
+                    .map(|(sum, amount)| sum.saturating_add(amount));
+            }
+            for (opid, inputs) in self.inputs() {
+                for input in inputs.iter().filter(|input| input.prev_out.ty == ty) {
+                    let amount = self.amounts().get(&input.prev_out).copied();
+                    let (_, spent) = ops.entry(*opid).or_insert((Some(0), Some(0)));
+                    *spent = spent
+                        .zip(amount)
+                        .map(|(sum, amount)| sum.saturating_add(amount));
+                }
+            }
+
+            for (opid, (assigned, spent)) in ops {
+                let has_inputs = self
+                    .inputs()
+                    .get(&opid)
+                    .map(|inputs| inputs.iter().any(|input| input.prev_out.ty == ty))
+                    .unwrap_or_default();
+                match (assigned, spent) {
+                    (Some(assigned), _) if opid == genesis_id => supply.genesis = assigned,
+                    (Some(assigned), _) if !has_inputs && assigned > 0 => {
+                        supply.issues.insert(opid, assigned);
+                    }
+                    (Some(assigned), Some(spent)) if assigned > spent => {
+                        supply.issues.insert(opid, assigned - spent);
+                    }
+                    (Some(assigned), Some(spent)) if assigned < spent => {
+                        supply.burns.insert(opid, spent - assigned);
+                    }
+                    (Some(_), Some(_)) => {}
+                    _ => {
+                        supply.unknown.insert(opid);
+                    }
+                }
+            }
+
+            supply.circulating = supply
+                .issues
+                .values()
+                .fold(supply.genesis, |sum, amount| sum.saturating_add(*amount));
+            supply.circulating = supply
+                .burns
+                .values()
+                .fold(supply.circulating, |sum, amount| sum.saturating_sub(*amount));
+            supply.declared = self.declared_supply(ty);
+
+            assets.insert(ty, supply);
+        }
+
+        SupplyReport {
+            contract_id: self.contract_id(),
+            assets,
+        }
+    }
+
+    fn declared_supply(&self, ty: AssignmentType) -> Option<u64> {
+        let fixed_supply = self.schema.fixed_supply;
+        if !self.schema.flags.contains(SchemaFlags::FIXED_SUPPLY) ||
+            fixed_supply.assignment_type != ty ||
+            !self
+                .schema
+                .global_types
+                .contains_key(&fixed_supply.global_type)
+        {
+            return None;
+        }
+        // SAFETY: we have checked that the global type is part of the schema
+        let values = unsafe { self.global_unchecked(fixed_supply.global_type) };
+        let value = values.first()?;
+        <[u8; 8]>::try_from(value.as_ref())
+            .ok()
+            .map(u64::from_le_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement::{SmallOrdSet, SmallVec};
+    use bp::Txid;
+    use bp::dbc::Method;
+    use chrono::DateTime;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, Assign, Assignments, BlindingFactor, ContractHistory, DataState, ExposedSeal,
+        FungibleType, Genesis, GenesisSeal, GlobalStateSchema, GlobalStateType, GraphSeal, Input,
+        Inputs, Operation, Opout, RevealedValue, Schema, SupplySchema, Transition, TypedAssigns,
+        WitnessAnchor, XChain,
+    };
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+    const SUPPLY: GlobalStateType = GlobalStateType::with(1);
+
+    fn amounts<Seal: ExposedSeal>(values: &[u64], seal: impl Fn(u32) -> Seal) -> Assignments<Seal> {
+        let tag = AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0);
+        let blinding = BlindingFactor::from_str(
+            "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53",
+        )
+        .unwrap();
+        let assigns = values
+            .iter()
+            .enumerate()
+            .map(|(no, value)| {
+                let state = RevealedValue::with_blinding(*value, blinding.clone(), tag);
+                Assign::revealed(XChain::Bitcoin(seal(no as u32)), state)
+            })
+            .collect::<Vec<_>>();
+        Assignments::from(tiny_bmap! {
+            ASSET => TypedAssigns::Fungible(SmallVec::try_from(assigns).unwrap()),
+        })
+    }
+
+    fn transition(inputs: &[Opout], outputs: &[u64]) -> Transition {
+        let mut transition = Transition::strict_dumb();
+        let inputs = inputs.iter().copied().map(Input::with);
+        transition.inputs = Inputs::from(SmallOrdSet::try_from_iter(inputs).unwrap());
+        transition.assignments = amounts(outputs, |vout| {
+            GraphSeal::with_blinded_vout(Method::OpretFirst, vout, vout as u64)
+        });
+        transition
+    }
+
+    #[test]
+    fn supply_report() {
+        let mut schema = Schema::strict_dumb();
+        schema.owned_types =
+            tiny_bmap! { ASSET => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit) };
+        schema.global_types =
+            tiny_bmap! { SUPPLY => GlobalStateSchema::once(strict_types::SemId::strict_dumb()) };
+        schema.flags = SchemaFlags::FIXED_SUPPLY;
+        schema.fixed_supply = SupplySchema {
+            assignment_type: ASSET,
+            global_type: SUPPLY,
+        };
+
+        let mut genesis = Genesis::strict_dumb();
+        genesis.assignments = amounts(&[60, 40], |vout| {
+            GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, 0)
+        });
+        let supply = SmallVec::try_from(100u64.to_le_bytes().to_vec()).unwrap();
+        genesis
+            .globals
+            .add_state(SUPPLY, DataState::from(supply))
+            .unwrap();
+        let genesis_id = genesis.id();
+        let history = ContractHistory::with(schema.schema_id(), genesis.contract_id(), &genesis);
+        let mut state = ContractState { schema, history };
+        let anchor = WitnessAnchor::from_mempool(XChain::Bitcoin(Txid::strict_dumb()));
+
+        // Transfer, burn and secondary issue
+        let transfer = transition(&[Opout::new(genesis_id, ASSET, 0)], &[50, 10]);
+        let burn = transition(&[Opout::new(genesis_id, ASSET, 1)], &[30]);
+        let issue = transition(&[Opout::new(transfer.id(), ASSET, 0)], &[70]);
+        // Spends state unknown to the history
+        let unknown = transition(&[Opout::new(OpId::strict_dumb(), ASSET, 0)], &[5]);
+        // Assigns state with concealed amount
+        let mut hidden = transition(&[Opout::new(burn.id(), ASSET, 0)], &[30]);
+        let assign = &mut hidden
+            .assignments
+            .get_mut(&ASSET)
+            .unwrap()
+            .as_fungible_mut()
+            .unwrap()[0];
+        *assign = assign.conceal_state();
+        for op in [&transfer, &burn, &issue, &unknown, &hidden] {
+            state.add_transition(op, anchor);
+        }
+
+        let report = state.supply_report();
+        assert_eq!(report.contract_id, state.contract_id());
+        assert_eq!(report.assets.len(), 1);
+        assert_eq!(report.assets[&ASSET], AssetSupply {
+            genesis: 100,
+            declared: Some(100),
+            issues: bmap! { issue.id() => 20 },
+            burns: bmap! { burn.id() => 10 },
+            circulating: 110,
+            unknown: bset! { unknown.id(), hidden.id() },
+        });
+    }
+}
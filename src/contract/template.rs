@@ -0,0 +1,256 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reusable, partially filled state transitions.
+//!
+//! [`TransitionTemplate`] captures everything about a transition except its
+//! inputs and the seals and state of its outputs: the transition type,
+//! metadata, global state, valencies and the number of outputs of each owned
+//! state type. Templates can be strict-encoded, allowing merchants to publish
+//! standardized operations for their contracts, which wallets complete with
+//! [`TransitionBuilder`].
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::{Confined, TinyOrdMap};
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+
+use crate::{
+    AssignmentType, Assignments, ContractId, GlobalState, GraphSeal, Input, Inputs, LIB_NAME_RGB,
+    Metadata, Opout, Transition, TransitionType, TypedAssigns, Valencies,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TemplateError {
+    /// transition template doesn't define outputs of type {0}.
+    UnexpectedOutputs(AssignmentType),
+
+    /// transition template requires {1} outputs of type {0}, while {2} are
+    /// provided.
+    OutputCountMismatch(AssignmentType, u16, u16),
+
+    /// outputs of type {0} required by the transition template are not
+    /// provided.
+    OutputsAbsent(AssignmentType),
+
+    /// transition built from the template doesn't spend any inputs.
+    NoInputs,
+
+    /// transition built from the template spends too many inputs.
+    TooManyInputs,
+}
+
+/// State transition without inputs and the seals and state of its outputs.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct TransitionTemplate {
+    pub contract_id: ContractId,
+    pub transition_type: TransitionType,
+    pub metadata: Metadata,
+    pub globals: GlobalState,
+    pub valencies: Valencies,
+    /// Number of the outputs of each owned state type.
+    pub outputs: TinyOrdMap<AssignmentType, u16>,
+}
+
+impl StrictSerialize for TransitionTemplate {}
+impl StrictDeserialize for TransitionTemplate {}
+
+impl TransitionTemplate {
+    /// Constructs template of a transition without metadata, global state,
+    /// valencies and outputs.
+    pub fn new(contract_id: ContractId, transition_type: TransitionType) -> Self {
+        TransitionTemplate {
+            contract_id,
+            transition_type,
+            metadata: none!(),
+            globals: none!(),
+            valencies: none!(),
+            outputs: none!(),
+        }
+    }
+}
+
+impl From<&Transition> for TransitionTemplate {
+    /// Extracts template from an existing transition, dropping its inputs and
+    /// the seals and state of its outputs.
+    fn from(transition: &Transition) -> Self {
+        let outputs = transition
+            .assignments
+            .iter()
+            .map(|(ty, assigns)| (*ty, assigns.len_u16()));
+        TransitionTemplate {
+            contract_id: transition.contract_id,
+            transition_type: transition.transition_type,
+            metadata: transition.metadata.clone(),
+            globals: transition.globals.clone(),
+            valencies: transition.valencies.clone(),
+            outputs: Confined::try_from_iter(outputs).expect("same number as of assignment types"),
+        }
+    }
+}
+
+/// Builder completing a [`TransitionTemplate`] with inputs and outputs.
+#[derive(Clone, Debug)]
+pub struct TransitionBuilder {
+    template: TransitionTemplate,
+    inputs: Inputs,
+    assignments: BTreeMap<AssignmentType, TypedAssigns<GraphSeal>>,
+}
+
+impl TransitionBuilder {
+    /// Starts building a transition from the template.
+    pub fn from_template(template: TransitionTemplate) -> Self {
+        TransitionBuilder {
+            template,
+            inputs: none!(),
+            assignments: empty!(),
+        }
+    }
+
+    /// Template the transition is built from.
+    pub fn template(&self) -> &TransitionTemplate { &self.template }
+
+    /// Adds an input spent by the transition. Adding the same input twice
+    /// has no effect.
+    pub fn add_input(mut self, opout: Opout) -> Result<Self, TemplateError> {
+        self.inputs
+            .push(Input::with(opout))
+            .map_err(|_| TemplateError::TooManyInputs)?;
+        Ok(self)
+    }
+
+    /// Provides the outputs of the type, which must match the number of the
+    /// outputs defined by the template. Replaces the outputs of the type
+    /// provided before.
+    pub fn assign(
+        mut self,
+        ty: AssignmentType,
+        assigns: TypedAssigns<GraphSeal>,
+    ) -> Result<Self, TemplateError> {
+        let Some(count) = self.template.outputs.get(&ty) else {
+            return Err(TemplateError::UnexpectedOutputs(ty));
+        };
+        if assigns.len_u16() != *count {
+            return Err(TemplateError::OutputCountMismatch(ty, *count, assigns.len_u16()));
+        }
+        self.assignments.insert(ty, assigns);
+        Ok(self)
+    }
+
+    /// Completes the transition, checking that all the outputs defined by
+    /// the template are provided.
+    pub fn complete(self) -> Result<Transition, TemplateError> {
+        if self.inputs.is_empty() {
+            return Err(TemplateError::NoInputs);
+        }
+        if let Some(ty) = self
+            .template
+            .outputs
+            .keys()
+            .find(|ty| !self.assignments.contains_key(*ty))
+        {
+            return Err(TemplateError::OutputsAbsent(*ty));
+        }
+        let template = self.template;
+        let mut transition = Transition::strict_dumb();
+        transition.contract_id = template.contract_id;
+        transition.transition_type = template.transition_type;
+        transition.metadata = template.metadata;
+        transition.globals = template.globals;
+        transition.valencies = template.valencies;
+        transition.inputs = self.inputs;
+        transition.assignments = Assignments::from(
+            Confined::try_from(self.assignments).expect("types are limited by the template"),
+        );
+        transition.invalidate_id();
+        Ok(transition)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use bp::dbc::Method;
+
+    use super::*;
+    use crate::{Assign, DataState, GlobalStateType, OpId, Operation, VoidState, XChain};
+
+    #[test]
+    fn template() {
+        let ty = AssignmentType::with(1);
+        let rights = |vouts: &[u32]| {
+            let assigns = vouts
+                .iter()
+                .map(|vout| {
+                    let seal = GraphSeal::with_blinded_vout(Method::OpretFirst, *vout, 0);
+                    Assign::revealed(XChain::Bitcoin(seal), VoidState::default())
+                })
+                .collect::<Vec<_>>();
+            TypedAssigns::Declarative(Confined::try_from(assigns).unwrap())
+        };
+        let input = Opout::new(OpId::from_byte_array([0x01; 32]), ty, 0);
+
+        let mut transition = Transition::strict_dumb();
+        transition.contract_id = ContractId::from_byte_array([0x6c; 32]);
+        transition.transition_type = TransitionType::with(2);
+        transition
+            .globals
+            .add_state(GlobalStateType::with(1), DataState::from(small_vec![1u8]))
+            .unwrap();
+        transition.inputs = Inputs::from(small_bset! { Input::with(input) });
+        transition.assignments = Assignments::from(tiny_bmap! { ty => rights(&[1, 2]) });
+
+        let template = TransitionTemplate::from(&transition);
+        assert_eq!(template.outputs, tiny_bmap! { ty => 2 });
+        let data = template.to_strict_serialized::<0xFFFF>().unwrap();
+        assert_eq!(TransitionTemplate::from_strict_serialized::<0xFFFF>(data).unwrap(), template);
+
+        let builder = TransitionBuilder::from_template(template.clone());
+        assert_eq!(builder.template(), &template);
+        assert_eq!(builder.clone().complete(), Err(TemplateError::NoInputs));
+        let builder = builder.add_input(input).unwrap().add_input(input).unwrap();
+        assert_eq!(builder.clone().complete(), Err(TemplateError::OutputsAbsent(ty)));
+        let other = AssignmentType::with(2);
+        assert_eq!(
+            builder.clone().assign(other, rights(&[1])).unwrap_err(),
+            TemplateError::UnexpectedOutputs(other)
+        );
+        assert_eq!(
+            builder.clone().assign(ty, rights(&[1])).unwrap_err(),
+            TemplateError::OutputCountMismatch(ty, 2, 1)
+        );
+        let built = builder
+            .assign(ty, rights(&[1, 2]))
+            .unwrap()
+            .complete()
+            .unwrap();
+        assert_eq!(built.id(), transition.id());
+    }
+}
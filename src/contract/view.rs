@@ -0,0 +1,170 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Read-only views into the collections wrapped by the consensus data types.
+//!
+//! The views expose the lookup and iteration API of the collection without
+//! tying the users to the confinement wrappers used for the strict encoding,
+//! so the downstream code doesn't depend on `as_inner()` and the exact
+//! confinement bounds of a type.
+
+use std::collections::{BTreeMap, BTreeSet, btree_map, btree_set};
+
+/// Read-only view into an ordered map.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MapView<'a, K, V>(&'a BTreeMap<K, V>);
+
+impl<K, V> Copy for MapView<'_, K, V> {}
+impl<K, V> Clone for MapView<'_, K, V> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<'a, K: Ord, V> MapView<'a, K, V> {
+    pub(crate) fn new(map: &'a BTreeMap<K, V>) -> Self { MapView(map) }
+
+    /// Number of the items in the map.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    /// Detects whether the map has no items.
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Returns the value under the given key, if present.
+    pub fn get(&self, key: &K) -> Option<&'a V> { self.0.get(key) }
+
+    /// Detects whether the map has a value under the given key.
+    pub fn contains_key(&self, key: &K) -> bool { self.0.contains_key(key) }
+
+    /// Returns the item with the smallest key.
+    pub fn first(&self) -> Option<(&'a K, &'a V)> { self.0.first_key_value() }
+
+    /// Returns the item with the largest key.
+    pub fn last(&self) -> Option<(&'a K, &'a V)> { self.0.last_key_value() }
+
+    /// Iterates over the keys in ascending order.
+    pub fn keys(&self) -> btree_map::Keys<'a, K, V> { self.0.keys() }
+
+    /// Iterates over the values in the order of their keys.
+    pub fn values(&self) -> btree_map::Values<'a, K, V> { self.0.values() }
+
+    /// Iterates over the items in the ascending order of their keys.
+    pub fn iter(&self) -> btree_map::Iter<'a, K, V> { self.0.iter() }
+
+    /// Copies the items into a standard map.
+    pub fn to_map(&self) -> BTreeMap<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.0.clone()
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for MapView<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = btree_map::Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
+
+/// Read-only view into an ordered set.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SetView<'a, T>(&'a BTreeSet<T>);
+
+impl<T> Copy for SetView<'_, T> {}
+impl<T> Clone for SetView<'_, T> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<'a, T: Ord> SetView<'a, T> {
+    pub(crate) fn new(set: &'a BTreeSet<T>) -> Self { SetView(set) }
+
+    /// Number of the items in the set.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    /// Detects whether the set has no items.
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Detects whether the set contains the item.
+    pub fn contains(&self, item: &T) -> bool { self.0.contains(item) }
+
+    /// Returns the smallest item.
+    pub fn first(&self) -> Option<&'a T> { self.0.first() }
+
+    /// Returns the largest item.
+    pub fn last(&self) -> Option<&'a T> { self.0.last() }
+
+    /// Iterates over the items in ascending order.
+    pub fn iter(&self) -> btree_set::Iter<'a, T> { self.0.iter() }
+
+    /// Copies the items into a standard set.
+    pub fn to_set(&self) -> BTreeSet<T>
+    where T: Clone {
+        self.0.clone()
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for SetView<'a, T> {
+    type Item = &'a T;
+    type IntoIter = btree_set::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::SmallBlob;
+    use strict_encoding::StrictDumb;
+
+    use crate::schema::MetaType;
+    use crate::{Input, Inputs, MetaValue, Metadata, OpId, Opout};
+
+    #[test]
+    fn metadata_view() {
+        let mut metadata = Metadata::default();
+        assert!(metadata.view().is_empty());
+        for ty in [2u16, 1] {
+            let value = MetaValue::from(SmallBlob::try_from(vec![ty as u8]).unwrap());
+            metadata.add_value(MetaType::with(ty), value).unwrap();
+        }
+
+        let view = metadata.view();
+        assert_eq!(view.len(), 2);
+        assert!(view.contains_key(&MetaType::with(1)));
+        assert!(!view.contains_key(&MetaType::with(3)));
+        assert_eq!(view.get(&MetaType::with(2)).unwrap().as_slice(), &[2]);
+        assert_eq!(view.first().unwrap().0, &MetaType::with(1));
+        assert_eq!(view.keys().copied().collect::<Vec<_>>(), vec![
+            MetaType::with(1),
+            MetaType::with(2)
+        ]);
+    }
+
+    #[test]
+    fn inputs_view() {
+        let opout = Opout::new(OpId::strict_dumb(), 1.into(), 0);
+        let inputs = Inputs::from(small_bset![Input::with(opout)]);
+
+        let view = inputs.view();
+        assert_eq!(view.len(), 1);
+        assert!(view.contains(&Input::with(opout)));
+        assert_eq!(view.iter().map(|input| input.prev_out).collect::<Vec<_>>(), vec![opout]);
+    }
+}
@@ -0,0 +1,480 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Templates of witness transactions committing to state transitions.
+//!
+//! [`WitnessTemplate`] takes transition bundles which are going to be closed
+//! by a single witness transaction and computes everything a wallet needs to
+//! fund and construct that transaction: the set of inputs which must be spent
+//! at the exact positions committed by the bundle input maps, and the output
+//! carrying the multi-protocol commitment to the bundles, either as an
+//! `OP_RETURN` script (opret) or as a tweaked taproot key (tapret). Once the
+//! transaction is constructed, the template produces anchors for each of the
+//! contracts.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use amplify::confinement::Confined;
+use bp::dbc::opret::OpretProof;
+use bp::dbc::tapret::{
+    TapretCommitment, TapretFirst, TapretKeyError, TapretPathProof, TapretProof,
+};
+use bp::seals::txout::CloseMethod;
+use bp::{InternalPk, ScriptPubkey, TapScript};
+use commit_verify::mpc::{self, MerkleBlock, MerkleTree, MultiSource};
+use commit_verify::{CommitId, CommitVerify, ConvolveCommit, TryCommitVerify};
+
+use crate::{ContractId, DbcProof, EAnchor, OpId, Opout, TransitionBundle, Vin, XOutpoint};
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum WitnessTemplateError {
+    /// no transition bundles are provided for the witness transaction.
+    NoBundles,
+
+    /// bundle of the contract {0} uses seal closing method different from
+    /// the one of the witness transaction.
+    MethodMismatch(ContractId),
+
+    /// multiple bundles are provided for the contract {0}.
+    RepeatedContract(ContractId),
+
+    /// seal of the output {0} spent by a bundle transition is unknown.
+    UnresolvedInput(Opout),
+
+    /// transition {0} spends {2} distinct outputs, while the bundle input map
+    /// commits to {1} witness inputs for it.
+    InputCountMismatch(OpId, usize, usize),
+
+    /// witness input {0} is required to spend different outputs by different
+    /// bundles.
+    InputConflict(Vin),
+
+    /// output {0} must be spent by multiple witness inputs.
+    RepeatedOutpoint(XOutpoint),
+
+    /// too many contracts for a single multi-protocol commitment.
+    TooManyContracts,
+
+    /// witness transaction doesn't commit to the contract {0}.
+    UnknownContract(ContractId),
+
+    /// deterministic bitcoin commitment proof doesn't match the seal closing
+    /// method of the witness transaction.
+    ProofMismatch,
+
+    #[from]
+    #[display(inner)]
+    Mpc(mpc::Error),
+
+    #[from]
+    #[display(inner)]
+    Tapret(TapretKeyError),
+}
+
+/// Template of a witness transaction closing seals of one or more transition
+/// bundles, keyed by the contract ids.
+#[derive(Clone, Debug)]
+pub struct WitnessTemplate {
+    method: CloseMethod,
+    tree: MerkleTree,
+    contracts: BTreeSet<ContractId>,
+    inputs: BTreeMap<Vin, XOutpoint>,
+}
+
+impl WitnessTemplate {
+    /// Constructs the template from the bundles, resolving outputs spent by
+    /// the bundle transitions into transaction outpoints with `resolver`.
+    ///
+    /// The static entropy is used in the construction of the multi-protocol
+    /// commitment; it must be random and kept private unless the same
+    /// commitment has to be reproduced.
+    pub fn with<'bundle>(
+        method: CloseMethod,
+        static_entropy: u64,
+        bundles: impl IntoIterator<Item = &'bundle TransitionBundle>,
+        resolver: impl Fn(Opout) -> Option<XOutpoint>,
+    ) -> Result<Self, WitnessTemplateError> {
+        let mut messages = BTreeMap::new();
+        let mut inputs = BTreeMap::new();
+        let mut spent = BTreeSet::new();
+        let mut contracts = BTreeSet::new();
+        for bundle in bundles {
+            let contract_id = bundle
+                .known_transitions
+                .values()
+                .next()
+                .expect("bundle always has transitions")
+                .contract_id;
+            if bundle.close_method != method {
+                return Err(WitnessTemplateError::MethodMismatch(contract_id));
+            }
+            if !contracts.insert(contract_id) {
+                return Err(WitnessTemplateError::RepeatedContract(contract_id));
+            }
+            let message = mpc::Message::from(bundle.bundle_id());
+            messages.insert(mpc::ProtocolId::from(contract_id), message);
+            for (opid, transition) in &bundle.known_transitions {
+                let outpoints = transition
+                    .inputs
+                    .iter()
+                    .map(|input| {
+                        resolver(input.prev_out)
+                            .ok_or(WitnessTemplateError::UnresolvedInput(input.prev_out))
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()?;
+                let vins = bundle
+                    .input_map
+                    .iter()
+                    .filter(|(_, id)| *id == opid)
+                    .map(|(vin, _)| *vin)
+                    .collect::<Vec<_>>();
+                if vins.len() != outpoints.len() {
+                    return Err(WitnessTemplateError::InputCountMismatch(
+                        *opid,
+                        vins.len(),
+                        outpoints.len(),
+                    ));
+                }
+                for (vin, outpoint) in vins.into_iter().zip(outpoints) {
+                    match inputs.insert(vin, outpoint) {
+                        Some(prev) if prev != outpoint => {
+                            return Err(WitnessTemplateError::InputConflict(vin));
+                        }
+                        Some(_) => {}
+                        None if !spent.insert(outpoint) => {
+                            return Err(WitnessTemplateError::RepeatedOutpoint(outpoint));
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+        if messages.is_empty() {
+            return Err(WitnessTemplateError::NoBundles);
+        }
+        let source = MultiSource {
+            messages: Confined::try_from(messages)
+                .map_err(|_| WitnessTemplateError::TooManyContracts)?,
+            static_entropy: Some(static_entropy),
+            ..default!()
+        };
+        let tree = MerkleTree::try_commit(&source)?;
+        Ok(WitnessTemplate {
+            method,
+            tree,
+            contracts,
+            inputs,
+        })
+    }
+
+    /// Seal closing method used by the witness transaction.
+    pub fn method(&self) -> CloseMethod { self.method }
+
+    /// Multi-protocol commitment to the bundles.
+    pub fn commitment(&self) -> mpc::Commitment { self.tree.commit_id() }
+
+    /// Outpoints which must be spent by the witness transaction, indexed by
+    /// the positions of the transaction inputs.
+    pub fn inputs(&self) -> &BTreeMap<Vin, XOutpoint> { &self.inputs }
+
+    /// Ids of the contracts which bundles are committed to.
+    pub fn contract_ids(&self) -> &BTreeSet<ContractId> { &self.contracts }
+
+    /// Output script carrying the opret commitment. Returns `None` if the
+    /// template uses the tapret method.
+    pub fn opret_script(&self) -> Option<ScriptPubkey> {
+        if self.method != CloseMethod::OpretFirst {
+            return None;
+        }
+        Some(ScriptPubkey::op_return(self.commitment().as_slice()))
+    }
+
+    /// Commitment data put into the tapret leaf script under a given nonce.
+    /// Returns `None` if the template uses the opret method.
+    pub fn tapret_commitment(&self, nonce: u8) -> Option<TapretCommitment> {
+        if self.method != CloseMethod::TapretFirst {
+            return None;
+        }
+        Some(TapretCommitment::with(self.commitment(), nonce))
+    }
+
+    /// Tapret leaf script committing to the bundles under a given nonce.
+    /// Returns `None` if the template uses the opret method.
+    pub fn tapret_script(&self, nonce: u8) -> Option<TapScript> {
+        self.tapret_commitment(nonce)
+            .map(|commitment| <TapScript as CommitVerify<_, TapretFirst>>::commit(&commitment))
+    }
+
+    /// Tweaks the internal key of a taproot output with the tapret
+    /// commitment, placed into the script tree according to the path proof.
+    /// Returns the output script the wallet must use for the witness
+    /// transaction output and the proof for the anchors.
+    pub fn tapret_output(
+        &self,
+        internal_pk: InternalPk,
+        path_proof: &TapretPathProof,
+    ) -> Result<(ScriptPubkey, TapretProof), WitnessTemplateError> {
+        if self.method != CloseMethod::TapretFirst {
+            return Err(WitnessTemplateError::ProofMismatch);
+        }
+        let (output_pk, proof) = internal_pk.convolve_commit(path_proof, &self.commitment())?;
+        Ok((ScriptPubkey::p2tr_tweaked(output_pk), proof))
+    }
+
+    /// Opret proof for the anchors. Returns `None` if the template uses the
+    /// tapret method.
+    pub fn opret_proof(&self) -> Option<OpretProof> {
+        if self.method != CloseMethod::OpretFirst {
+            return None;
+        }
+        Some(OpretProof::default())
+    }
+
+    /// Constructs anchor of the contract bundle to the witness transaction.
+    pub fn anchor(
+        &self,
+        contract_id: ContractId,
+        dbc_proof: DbcProof,
+    ) -> Result<EAnchor, WitnessTemplateError> {
+        match (self.method, &dbc_proof) {
+            (CloseMethod::OpretFirst, DbcProof::Opret(_)) |
+            (CloseMethod::TapretFirst, DbcProof::Tapret(_)) => {}
+            _ => return Err(WitnessTemplateError::ProofMismatch),
+        }
+        let mpc_proof = MerkleBlock::from(&self.tree)
+            .to_merkle_proof(mpc::ProtocolId::from(contract_id))
+            .map_err(|_| WitnessTemplateError::UnknownContract(contract_id))?;
+        Ok(EAnchor {
+            mpc_proof,
+            dbc_proof,
+            method: self.method,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use bp::{LockTime, Outpoint, SeqNo, Tx, TxIn, TxOut, TxVer, Txid, Vout};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssignmentType, DbcError, Input, InputMap, Inputs, Operation, Transition,
+        UnsignedAnchorError, VerifyUnsigned, XChain,
+    };
+
+    const G: [u8; 32] = [
+        0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b,
+        0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8,
+        0x17, 0x98,
+    ];
+
+    fn bundle(
+        method: CloseMethod,
+        contract_id: ContractId,
+        prev: &[Opout],
+        first_vin: u32,
+    ) -> TransitionBundle {
+        let mut transition = Transition::strict_dumb();
+        transition.contract_id = contract_id;
+        transition.inputs = Inputs::from(
+            Confined::try_from_iter(prev.iter().copied().map(Input::with)).expect("few inputs"),
+        );
+        let opid = transition.id();
+        let vins = (first_vin..first_vin + prev.len() as u32).map(|no| (Vout::from_u32(no), opid));
+        TransitionBundle {
+            close_method: method,
+            input_map: InputMap::from(Confined::try_from_iter(vins).expect("few inputs")),
+            known_transitions: Confined::with((opid, transition)),
+        }
+    }
+
+    fn tx(template: &WitnessTemplate, script_pubkey: ScriptPubkey) -> Tx {
+        let inputs = template.inputs().values().map(|outpoint| TxIn {
+            prev_output: *outpoint.as_reduced_unsafe(),
+            sig_script: none!(),
+            sequence: SeqNo::from_consensus_u32(0xFFFF_FFFF),
+            witness: none!(),
+        });
+        Tx {
+            version: TxVer::V2,
+            inputs: Confined::try_from_iter(inputs).expect("few inputs"),
+            outputs: Confined::try_from(vec![TxOut::new(script_pubkey, 0u64)]).expect("one output"),
+            lock_time: LockTime::ZERO,
+        }
+    }
+
+    #[test]
+    fn witness_template() {
+        let contract_id = ContractId::from_byte_array([0x6c; 32]);
+        let other_id = ContractId::from_byte_array([0x6d; 32]);
+        let ty = AssignmentType::with(1);
+        let prev = [
+            Opout::new(OpId::from_byte_array([0x01; 32]), ty, 0),
+            Opout::new(OpId::from_byte_array([0x02; 32]), ty, 0),
+        ];
+        let outpoint = |no| {
+            XOutpoint::from(XChain::Bitcoin(Outpoint::new(Txid::from_byte_array([no; 32]), 0)))
+        };
+        let resolver = |opout: Opout| match opout {
+            opout if opout == prev[0] => Some(outpoint(0xB0)),
+            opout if opout == prev[1] => Some(outpoint(0xA0)),
+            _ => None,
+        };
+
+        let opret = bundle(CloseMethod::OpretFirst, contract_id, &prev, 0);
+        let template =
+            WitnessTemplate::with(CloseMethod::OpretFirst, 1, [&opret], resolver).unwrap();
+        assert_eq!(template.contract_ids(), &bset! { contract_id });
+        assert_eq!(
+            template.inputs(),
+            &bmap! { Vout::from_u32(0) => outpoint(0xA0), Vout::from_u32(1) => outpoint(0xB0) }
+        );
+        assert_eq!(template.tapret_script(0), None);
+        let script_pubkey = template.opret_script().unwrap();
+        let proof = DbcProof::Opret(template.opret_proof().unwrap());
+        let witness = tx(&template, script_pubkey);
+        let anchor = template.anchor(contract_id, proof.clone()).unwrap();
+        assert_eq!(
+            anchor.verify(contract_id, opret.bundle_id(), &witness),
+            Ok(template.commitment())
+        );
+        assert_eq!(
+            template.anchor(other_id, proof).unwrap_err(),
+            WitnessTemplateError::UnknownContract(other_id)
+        );
+
+        let tapret = bundle(CloseMethod::TapretFirst, contract_id, &prev[..1], 0);
+        let other = bundle(CloseMethod::TapretFirst, other_id, &prev[1..], 1);
+        let template =
+            WitnessTemplate::with(CloseMethod::TapretFirst, 1, [&tapret, &other], resolver)
+                .unwrap();
+        assert_eq!(template.opret_script(), None);
+        assert!(template.tapret_script(0).is_some());
+        let internal_pk = InternalPk::from_byte_array(G).unwrap();
+        let (script_pubkey, proof) = template
+            .tapret_output(internal_pk, &TapretPathProof::root(0))
+            .unwrap();
+        let witness = tx(&template, script_pubkey);
+        for (bundle, id) in [(&tapret, contract_id), (&other, other_id)] {
+            let anchor = template
+                .anchor(id, DbcProof::Tapret(proof.clone()))
+                .unwrap();
+            assert_eq!(anchor.verify(id, bundle.bundle_id(), &witness), Ok(template.commitment()));
+        }
+        assert_eq!(
+            template
+                .anchor(contract_id, DbcProof::Opret(OpretProof::default()))
+                .unwrap_err(),
+            WitnessTemplateError::ProofMismatch
+        );
+
+        let err = |bundles: &[&TransitionBundle]| {
+            WitnessTemplate::with(CloseMethod::TapretFirst, 1, bundles.iter().copied(), resolver)
+                .unwrap_err()
+        };
+        assert_eq!(err(&[]), WitnessTemplateError::NoBundles);
+        assert_eq!(err(&[&opret]), WitnessTemplateError::MethodMismatch(contract_id));
+        assert_eq!(err(&[&tapret, &tapret]), WitnessTemplateError::RepeatedContract(contract_id));
+        let unknown = Opout::new(OpId::from_byte_array([0x03; 32]), ty, 0);
+        let unresolved = bundle(CloseMethod::TapretFirst, contract_id, &[unknown], 0);
+        assert_eq!(err(&[&unresolved]), WitnessTemplateError::UnresolvedInput(unknown));
+        let mut uncommitted = bundle(CloseMethod::TapretFirst, contract_id, &prev, 0);
+        let opid = *uncommitted.known_transitions.keys().next().unwrap();
+        uncommitted.input_map = InputMap::with(Vout::from_u32(0), opid);
+        assert_eq!(err(&[&uncommitted]), WitnessTemplateError::InputCountMismatch(opid, 1, 2));
+        let conflicting = bundle(CloseMethod::TapretFirst, other_id, &prev[1..], 0);
+        assert_eq!(
+            err(&[&tapret, &conflicting]),
+            WitnessTemplateError::InputConflict(Vout::from_u32(0))
+        );
+    }
+
+    #[test]
+    fn verify_unsigned() {
+        let contract_id = ContractId::from_byte_array([0x6c; 32]);
+        let ty = AssignmentType::with(1);
+        let prev = [
+            Opout::new(OpId::from_byte_array([0x01; 32]), ty, 0),
+            Opout::new(OpId::from_byte_array([0x02; 32]), ty, 0),
+        ];
+        let resolver = |opout: Opout| {
+            let txid = Txid::from_byte_array([opout.op.to_byte_array()[0]; 32]);
+            Some(XOutpoint::from(XChain::Bitcoin(Outpoint::new(txid, 0))))
+        };
+
+        let opret = bundle(CloseMethod::OpretFirst, contract_id, &prev, 0);
+        let bundle_id = opret.bundle_id();
+        let template =
+            WitnessTemplate::with(CloseMethod::OpretFirst, 1, [&opret], resolver).unwrap();
+        let anchor = template
+            .anchor(contract_id, DbcProof::Opret(template.opret_proof().unwrap()))
+            .unwrap();
+        let mut witness = tx(&template, template.opret_script().unwrap());
+        assert_eq!(anchor.verify_unsigned(&opret, &witness), Ok(template.commitment()));
+
+        let tapret = bundle(CloseMethod::TapretFirst, contract_id, &prev, 0);
+        assert_eq!(
+            anchor.verify_unsigned(&tapret, &witness),
+            Err(UnsignedAnchorError::MethodMismatch(tapret.bundle_id()))
+        );
+        let other = bundle(CloseMethod::OpretFirst, contract_id, &prev[..1], 0);
+        assert_eq!(
+            anchor.verify_unsigned(&other, &witness),
+            Err(UnsignedAnchorError::Dbc(DbcError::CommitmentMismatch))
+        );
+        let mut unfunded = witness.clone();
+        unfunded.inputs = Confined::try_from(vec![unfunded.inputs[0].clone()]).unwrap();
+        assert_eq!(
+            anchor.verify_unsigned(&opret, &unfunded),
+            Err(UnsignedAnchorError::NoInput(bundle_id, Vout::from_u32(1)))
+        );
+        witness.outputs[0].script_pubkey = ScriptPubkey::op_return(&[]);
+        assert_eq!(
+            anchor.verify_unsigned(&opret, &witness),
+            Err(UnsignedAnchorError::Dbc(DbcError::InvalidOpretScript))
+        );
+
+        let template =
+            WitnessTemplate::with(CloseMethod::TapretFirst, 1, [&tapret], resolver).unwrap();
+        let internal_pk = InternalPk::from_byte_array(G).unwrap();
+        let (tweaked, proof) = template
+            .tapret_output(internal_pk, &TapretPathProof::root(0))
+            .unwrap();
+        let anchor = template
+            .anchor(contract_id, DbcProof::Tapret(proof))
+            .unwrap();
+        let untweaked = ScriptPubkey::p2tr_key_only(internal_pk);
+        for script_pubkey in [tweaked, untweaked] {
+            let witness = tx(&template, script_pubkey);
+            assert_eq!(anchor.verify_unsigned(&tapret, &witness), Ok(template.commitment()));
+        }
+        let mut key = G;
+        key[31] = 0x9a;
+        let foreign = ScriptPubkey::p2tr_key_only(InternalPk::from_byte_array(key).unwrap());
+        assert_eq!(
+            anchor.verify_unsigned(&tapret, &tx(&template, foreign)),
+            Err(UnsignedAnchorError::Dbc(DbcError::CommitmentMismatch))
+        );
+    }
+}
@@ -0,0 +1,183 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encrypted envelopes for delivering consignments through untrusted relays.
+//!
+//! Envelope is encrypted to the receiver X25519 key published in the invoice
+//! (see [`crate::Invoice::envelope_key`]). Sender generates an ephemeral key,
+//! performs Diffie-Hellman with the receiver key and uses SHA256 of the shared
+//! secret and both public keys as a ChaCha20-Poly1305 key. The envelope
+//! consists of [`ENVELOPE_MAGIC`] bytes, 32-byte ephemeral public key, 12-byte
+//! nonce and the ciphertext.
+//!
+//! Sealing and opening envelopes requires `envelope` feature.
+
+use std::str::FromStr;
+
+use amplify::Bytes32;
+use amplify::hex::{self, FromHex};
+
+use crate::LIB_NAME_RGB;
+
+/// Magic bytes starting an encrypted envelope.
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"RGBe";
+
+/// Public X25519 key of the envelope receiver.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Display)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(LowerHex)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct EnvelopeKey(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl FromStr for EnvelopeKey {
+    type Err = hex::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum EnvelopeError {
+    /// data are not an encrypted envelope.
+    NoMagic,
+
+    /// envelope data are truncated.
+    Truncated,
+
+    /// envelope can't be decrypted with the provided key or is corrupted.
+    Decryption,
+
+    /// unable to encrypt the envelope.
+    Encryption,
+}
+
+#[cfg(feature = "envelope")]
+mod crypto {
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+    use commit_verify::{DigestExt, Sha256};
+    use x25519_dalek::{EphemeralSecret, PublicKey, SharedSecret, StaticSecret};
+
+    use super::*;
+
+    const ENVELOPE_KDF_TAG: &str = "urn:lnp-bp:rgb:envelope#2024-05-20";
+    const NONCE_LEN: usize = 12;
+    const HEADER_LEN: usize = 4 + 32 + NONCE_LEN;
+
+    /// Secret X25519 key of the envelope receiver.
+    pub struct EnvelopeSecret(StaticSecret);
+
+    impl EnvelopeSecret {
+        pub fn random() -> Self { Self(StaticSecret::random_from_rng(OsRng)) }
+
+        pub fn from_bytes(bytes: [u8; 32]) -> Self { Self(StaticSecret::from(bytes)) }
+
+        pub fn to_bytes(&self) -> [u8; 32] { self.0.to_bytes() }
+
+        pub fn envelope_key(&self) -> EnvelopeKey {
+            EnvelopeKey::from(PublicKey::from(&self.0).to_bytes())
+        }
+    }
+
+    fn derive_key(shared: SharedSecret, ephemeral: &PublicKey, receiver: EnvelopeKey) -> Key {
+        let mut hasher = Sha256::from_tag(ENVELOPE_KDF_TAG);
+        hasher.input_raw(shared.as_bytes());
+        hasher.input_raw(ephemeral.as_bytes());
+        hasher.input_raw(receiver.as_slice());
+        Key::from(hasher.finish())
+    }
+
+    /// Encrypts data into an envelope which can be opened only by the owner of
+    /// the secret key corresponding to the `receiver` key.
+    pub fn seal_envelope(data: &[u8], receiver: EnvelopeKey) -> Result<Vec<u8>, EnvelopeError> {
+        let ephemeral_secret = EphemeralSecret::random_from_rng(OsRng);
+        let ephemeral = PublicKey::from(&ephemeral_secret);
+        let shared = ephemeral_secret.diffie_hellman(&PublicKey::from(receiver.to_byte_array()));
+        let cipher = ChaCha20Poly1305::new(&derive_key(shared, &ephemeral, receiver));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| EnvelopeError::Encryption)?;
+
+        let mut envelope = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        envelope.extend_from_slice(&ENVELOPE_MAGIC);
+        envelope.extend_from_slice(ephemeral.as_bytes());
+        envelope.extend_from_slice(&nonce);
+        envelope.extend(ciphertext);
+        Ok(envelope)
+    }
+
+    /// Decrypts data from an envelope using receiver secret key.
+    pub fn open_envelope(
+        envelope: &[u8],
+        secret: &EnvelopeSecret,
+    ) -> Result<Vec<u8>, EnvelopeError> {
+        if !envelope.starts_with(&ENVELOPE_MAGIC) {
+            return Err(EnvelopeError::NoMagic);
+        }
+        if envelope.len() < HEADER_LEN {
+            return Err(EnvelopeError::Truncated);
+        }
+        let mut ephemeral = [0u8; 32];
+        ephemeral.copy_from_slice(&envelope[4..36]);
+        let ephemeral = PublicKey::from(ephemeral);
+        let nonce = Nonce::from_slice(&envelope[36..HEADER_LEN]);
+        let shared = secret.0.diffie_hellman(&ephemeral);
+        let cipher = ChaCha20Poly1305::new(&derive_key(shared, &ephemeral, secret.envelope_key()));
+        cipher
+            .decrypt(nonce, &envelope[HEADER_LEN..])
+            .map_err(|_| EnvelopeError::Decryption)
+    }
+}
+#[cfg(feature = "envelope")]
+pub use crypto::{EnvelopeSecret, open_envelope, seal_envelope};
+
+#[cfg(test)]
+#[cfg(feature = "envelope")]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seal_open() {
+        let secret = EnvelopeSecret::random();
+        let key = secret.envelope_key();
+        let envelope = seal_envelope(b"consignment", key).unwrap();
+        assert_eq!(open_envelope(&envelope, &secret).unwrap(), b"consignment");
+
+        let other = EnvelopeSecret::random();
+        assert_eq!(open_envelope(&envelope, &other), Err(EnvelopeError::Decryption));
+
+        let mut corrupted = envelope.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        assert_eq!(open_envelope(&corrupted, &secret), Err(EnvelopeError::Decryption));
+        assert_eq!(open_envelope(b"RGBe", &secret), Err(EnvelopeError::Truncated));
+    }
+}
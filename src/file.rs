@@ -0,0 +1,308 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Binary container format for `.rgb` artifacts.
+//!
+//! The container consists of [`ARTIFACT_MAGIC`] bytes, 2-byte format version,
+//! 1-byte artifact type, 4-byte payload length, payload and a 4-byte checksum,
+//! which is the first 4 bytes of SHA256 hash of all preceding data. All
+//! integers are little-endian.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use amplify::confinement::{Confined, U32};
+use commit_verify::{DigestExt, Sha256};
+use strict_encoding::{DeserializeError, SerializeError, StrictDeserialize, StrictSerialize};
+
+use crate::budget::{BudgetError, DecodeBudget};
+
+/// Magic bytes starting any RGB artifact file.
+pub const ARTIFACT_MAGIC: [u8; 4] = *b"RGB\0";
+
+/// Current version of the artifact container format.
+pub const ARTIFACT_VERSION: u16 = 1;
+
+/// Length of the artifact container header.
+pub const ARTIFACT_HEADER_LEN: usize = 11;
+const HEADER_LEN: usize = ARTIFACT_HEADER_LEN;
+const CHECKSUM_LEN: usize = 4;
+
+/// Type of the data contained in an artifact file.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(lowercase)]
+#[repr(u8)]
+pub enum ArtifactType {
+    Schema = 1,
+    Consignment = 2,
+    Disclosure = 3,
+    #[display("validation-cache")]
+    ValidationCache = 4,
+}
+
+impl TryFrom<u8> for ArtifactType {
+    type Error = ArtifactError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(ArtifactType::Schema),
+            2 => Ok(ArtifactType::Consignment),
+            3 => Ok(ArtifactType::Disclosure),
+            4 => Ok(ArtifactType::ValidationCache),
+            unknown => Err(ArtifactError::UnknownType(unknown)),
+        }
+    }
+}
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ArtifactError {
+    /// data are not an RGB artifact.
+    NoMagic,
+
+    /// artifact format version {0} is not supported.
+    UnsupportedVersion(u16),
+
+    /// unknown artifact type {0}.
+    UnknownType(u8),
+
+    /// artifact has type {actual} while {expected} was expected.
+    TypeMismatch {
+        expected: ArtifactType,
+        actual: ArtifactType,
+    },
+
+    /// artifact data are truncated.
+    Truncated,
+
+    /// artifact contains {0} excessive bytes after the checksum.
+    DataNotConsumed(usize),
+
+    /// artifact checksum doesn't match its data, which means the file is
+    /// corrupted.
+    ChecksumMismatch,
+
+    /// artifact payload is too large.
+    TooLarge,
+
+    /// artifact payload doesn't fit decode budget. Details: {0}
+    #[from]
+    Budget(BudgetError),
+
+    /// I/O error. Details: {0}
+    #[from]
+    Io(std::io::Error),
+
+    /// unable to serialize artifact payload. Details: {0}
+    #[from]
+    Serialize(SerializeError),
+
+    /// unable to deserialize artifact payload. Details: {0}
+    #[from]
+    Deserialize(DeserializeError),
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut hasher = Sha256::default();
+    hasher.input_raw(data);
+    let hash = hasher.finish();
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&hash[..CHECKSUM_LEN]);
+    checksum
+}
+
+/// Header of an artifact container, which can be read before the payload.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ArtifactHeader {
+    pub version: u16,
+    pub ty: ArtifactType,
+    /// Payload length declared by the header.
+    pub len: u32,
+}
+
+impl ArtifactHeader {
+    /// Parses artifact header from the first [`ARTIFACT_HEADER_LEN`] bytes of
+    /// the data.
+    pub fn parse(data: &[u8]) -> Result<Self, ArtifactError> {
+        if !data.starts_with(&ARTIFACT_MAGIC) {
+            return Err(ArtifactError::NoMagic);
+        }
+        if data.len() < HEADER_LEN {
+            return Err(ArtifactError::Truncated);
+        }
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version != ARTIFACT_VERSION {
+            return Err(ArtifactError::UnsupportedVersion(version));
+        }
+        let ty = ArtifactType::try_from(data[6])?;
+        let len = u32::from_le_bytes([data[7], data[8], data[9], data[10]]);
+        Ok(ArtifactHeader { version, ty, len })
+    }
+
+    /// Checks that the declared payload fits the decode budget.
+    pub fn check_budget(&self, budget: DecodeBudget) -> Result<(), ArtifactError> {
+        budget.check(self.len as u64)?;
+        Ok(())
+    }
+}
+
+/// Wraps payload into an artifact container.
+pub fn encode_artifact(ty: ArtifactType, payload: &[u8]) -> Result<Vec<u8>, ArtifactError> {
+    let len = u32::try_from(payload.len()).map_err(|_| ArtifactError::TooLarge)?;
+    let mut data = Vec::with_capacity(HEADER_LEN + payload.len() + CHECKSUM_LEN);
+    data.extend_from_slice(&ARTIFACT_MAGIC);
+    data.extend_from_slice(&ARTIFACT_VERSION.to_le_bytes());
+    data.push(ty as u8);
+    data.extend_from_slice(&len.to_le_bytes());
+    data.extend_from_slice(payload);
+    let checksum = checksum(&data);
+    data.extend_from_slice(&checksum);
+    Ok(data)
+}
+
+/// Checks artifact container integrity and extracts its payload.
+///
+/// If `expected` type is provided, fails if the artifact has a different type.
+pub fn decode_artifact(
+    data: &[u8],
+    expected: Option<ArtifactType>,
+) -> Result<(ArtifactType, &[u8]), ArtifactError> {
+    let ArtifactHeader { ty, len, .. } = ArtifactHeader::parse(data)?;
+    if let Some(expected) = expected {
+        if expected != ty {
+            return Err(ArtifactError::TypeMismatch {
+                expected,
+                actual: ty,
+            });
+        }
+    }
+    // Lengths may overflow `usize` on 32-bit platforms
+    let end = usize::try_from(len)
+        .ok()
+        .and_then(|len| HEADER_LEN.checked_add(len))
+        .ok_or(ArtifactError::Truncated)?;
+    let total = end
+        .checked_add(CHECKSUM_LEN)
+        .ok_or(ArtifactError::Truncated)?;
+    if data.len() < total {
+        return Err(ArtifactError::Truncated);
+    }
+    if data.len() > total {
+        return Err(ArtifactError::DataNotConsumed(data.len() - total));
+    }
+    if checksum(&data[..end]) != data[end..] {
+        return Err(ArtifactError::ChecksumMismatch);
+    }
+    Ok((ty, &data[HEADER_LEN..end]))
+}
+
+/// Saves strict-serialized data as an artifact file.
+pub fn save_file(
+    path: impl AsRef<Path>,
+    ty: ArtifactType,
+    data: &impl StrictSerialize,
+) -> Result<(), ArtifactError> {
+    let payload = data.to_strict_serialized::<U32>()?;
+    fs::write(path, encode_artifact(ty, payload.as_slice())?)?;
+    Ok(())
+}
+
+/// Loads and deserializes data from an artifact file of a given type, using
+/// the default decode budget.
+pub fn load_file<T: StrictDeserialize>(
+    path: impl AsRef<Path>,
+    ty: ArtifactType,
+) -> Result<T, ArtifactError> {
+    load_file_with_budget(path, ty, default!())
+}
+
+/// Loads and deserializes data from an artifact file of a given type. The
+/// payload length declared in the file header is checked against the decode
+/// budget before the payload is read.
+pub fn load_file_with_budget<T: StrictDeserialize>(
+    path: impl AsRef<Path>,
+    ty: ArtifactType,
+    budget: DecodeBudget,
+) -> Result<T, ArtifactError> {
+    let mut file = File::open(path)?;
+    let mut data = vec![0u8; HEADER_LEN];
+    file.read_exact(&mut data)
+        .map_err(|_| ArtifactError::Truncated)?;
+    let header = ArtifactHeader::parse(&data)?;
+    header.check_budget(budget)?;
+    file.take(header.len as u64 + CHECKSUM_LEN as u64 + 1)
+        .read_to_end(&mut data)?;
+    let (_, payload) = decode_artifact(&data, Some(ty))?;
+    let payload = Confined::try_from(payload.to_vec()).map_err(|_| ArtifactError::TooLarge)?;
+    Ok(T::from_strict_serialized::<U32>(payload)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn budget() {
+        let data = encode_artifact(ArtifactType::Schema, &[0u8; 100]).unwrap();
+        let header = ArtifactHeader::parse(&data).unwrap();
+        assert_eq!(header.ty, ArtifactType::Schema);
+        assert_eq!(header.len, 100);
+        assert!(header.check_budget(DecodeBudget::with(100)).is_ok());
+        assert!(matches!(
+            header.check_budget(DecodeBudget::with(99)),
+            Err(ArtifactError::Budget(BudgetError::Exceeded {
+                required: 100,
+                budget: 99
+            }))
+        ));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data = encode_artifact(ArtifactType::Consignment, b"payload").unwrap();
+        let (ty, payload) = decode_artifact(&data, None).unwrap();
+        assert_eq!(ty, ArtifactType::Consignment);
+        assert_eq!(payload, b"payload");
+
+        assert!(matches!(
+            decode_artifact(&data, Some(ArtifactType::Schema)),
+            Err(ArtifactError::TypeMismatch {
+                expected: ArtifactType::Schema,
+                actual: ArtifactType::Consignment
+            })
+        ));
+        assert!(matches!(
+            decode_artifact(&data[..data.len() - 1], None),
+            Err(ArtifactError::Truncated)
+        ));
+        assert!(matches!(decode_artifact(b"not an artifact", None), Err(ArtifactError::NoMagic)));
+
+        let mut oversized = data.clone();
+        oversized[7..11].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(decode_artifact(&oversized, None), Err(ArtifactError::Truncated)));
+
+        let mut corrupted = data.clone();
+        corrupted[HEADER_LEN] ^= 0xFF;
+        assert!(matches!(decode_artifact(&corrupted, None), Err(ArtifactError::ChecksumMismatch)));
+    }
+}
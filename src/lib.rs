@@ -47,15 +47,23 @@ extern crate core;
 mod contract;
 pub mod schema;
 pub mod validation;
+pub mod budget;
+pub mod compression;
+pub mod file;
+pub mod envelope;
+mod summary;
 #[macro_use]
 pub mod vm;
 #[cfg(feature = "stl")]
 pub mod stl;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 pub mod prelude {
     pub use commit_verify::ReservedBytes;
     pub use contract::*;
     pub use schema::*;
+    pub use summary::{DISPLAY_HEX_LIMIT, DISPLAY_LIST_LIMIT};
 
     #[cfg(feature = "stl")]
     pub use super::stl;
@@ -79,6 +87,20 @@ pub const LIB_NAME_RGB: &str = "RGB";
 )]
 pub struct Ffv(u16);
 
+impl Ffv {
+    /// Initial version of the consensus data, produced by v0.11.0-beta.6 and
+    /// earlier releases.
+    pub const V0: Ffv = Ffv(0);
+
+    /// Operations commit to their metadata as a Merkle tree instead of its
+    /// strict hash, allowing proofs of individual metadata fields (see
+    /// [`Metadata::prove_field`]).
+    pub const V1: Ffv = Ffv(1);
+
+    /// The most recent version supported by this library.
+    pub const LATEST: Ffv = Ffv::V1;
+}
+
 mod _ffv {
     use strict_encoding::{DecodeError, ReadTuple, StrictDecode, TypedRead};
 
@@ -87,7 +109,7 @@ mod _ffv {
     impl StrictDecode for Ffv {
         fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
             let ffv = reader.read_tuple(|r| r.read_field().map(Self))?;
-            if ffv != Ffv::default() {
+            if ffv > Ffv::LATEST {
                 Err(DecodeError::DataIntegrityError(format!(
                     "unsupported fast-forward version code belonging to a future RGB version. \
                      Please update your software, or, if the problem persists, contact your \
@@ -140,3 +162,156 @@ macro_rules! impl_serde_baid64 {
 
 // TODO: Validate strict type data
 // TODO: Add parsed global and structured state to the ContractState
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    /// Source files of the consensus code, which validity verdicts must not
+    /// depend on the moment or the machine they are computed on.
+    const CONSENSUS_SOURCES: &[&str] = &[
+        "contract/anchor.rs",
+        "contract/assignments.rs",
+        "contract/attachment.rs",
+        "contract/bundle.rs",
+        "contract/chain.rs",
+        "contract/commit.rs",
+        "contract/data.rs",
+        "contract/expiry.rs",
+        "contract/fungible.rs",
+        "contract/global.rs",
+        "contract/issuance.rs",
+        "contract/meta.rs",
+        "contract/operations.rs",
+        "contract/pedersen.rs",
+        "contract/seal.rs",
+        "contract/state.rs",
+        "contract/xchain.rs",
+        "schema/",
+        "validation/cache.rs",
+        "validation/consignment.rs",
+        "validation/logic.rs",
+        "validation/payment.rs",
+        "validation/policy.rs",
+        "validation/rules.rs",
+        "validation/schema.rs",
+        "validation/spv.rs",
+        "validation/state.rs",
+        "validation/status.rs",
+        "validation/validator.rs",
+        "vm/",
+    ];
+
+    /// Wall-clock time and platform randomness sources. Timestamps must come
+    /// from the resolver-provided chain data, and randomness from the
+    /// generators provided by the callers.
+    const FORBIDDEN: &[&str] = &[
+        "SystemTime",
+        "Instant::now",
+        "Utc::now",
+        "Local::now",
+        "thread_rng",
+        "OsRng",
+        "random()",
+        "random_custom",
+        "new_random",
+    ];
+
+    /// Constructors of the state blindings and asset tags, which are never
+    /// called by the consensus code. Ones taking an explicit random generator
+    /// are the RNG injection points; the rest are convenience wrappers passing
+    /// them the platform time and randomness.
+    const ALLOWED: &[(&str, &str)] = &[
+        ("contract/attachment.rs", "new_random_salt"),
+        ("contract/data.rs", "new_random_salt"),
+        ("contract/fungible.rs", "new_random"),
+        ("contract/fungible.rs", "random"),
+        ("contract/fungible.rs", "random_custom"),
+        ("contract/fungible.rs", "new_random_blinding"),
+        ("contract/fungible.rs", "with_rng"),
+        // Dumb values are placeholders which never get into the consensus data
+        ("contract/fungible.rs", "default"),
+    ];
+
+    /// Items provided by the `std-helpers` feature, which consensus code must
+    /// never depend on.
+    const HELPERS: &[&str] = &[
+        "Airdrop",
+        "Refund",
+        "StateRenderer",
+        "RenderedAllocation",
+        "AssetSupply",
+        "SupplyReport",
+        "ContractStateSnapshot",
+        "Reachability",
+        "TransitionTemplate",
+        "TransitionBuilder",
+        "WitnessTemplate",
+        "ConstantShape",
+        "ContractStats",
+    ];
+
+    fn check(
+        src: &Path,
+        path: &Path,
+        forbidden: &[&str],
+        allowed: &[(&str, &str)],
+        violations: &mut Vec<String>,
+    ) {
+        if path.is_dir() {
+            for entry in fs::read_dir(path).unwrap() {
+                check(src, &entry.unwrap().path(), forbidden, allowed, violations);
+            }
+            return;
+        }
+        let file = path.strip_prefix(src).unwrap().to_string_lossy();
+        let source = fs::read_to_string(path).unwrap();
+        // Tests are not a part of consensus
+        let code = source.split("#[cfg(test)]").next().unwrap_or_default();
+        let mut func = None;
+        for (no, line) in code.lines().enumerate() {
+            let line = line.trim_start();
+            if line.starts_with("//") || line.starts_with("use ") {
+                continue;
+            }
+            if let Some((_, rest)) = line.split_once("fn ") {
+                func = rest.split(['(', '<']).next();
+            }
+            if func.is_some_and(|func| allowed.contains(&(file.as_ref(), func))) {
+                continue;
+            }
+            for pat in forbidden.iter().filter(|pat| line.contains(*pat)) {
+                violations.push(format!("{}:{}: {pat}", path.display(), no + 1));
+            }
+        }
+    }
+
+    #[test]
+    fn consensus_purity() {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut violations = vec![];
+        for source in CONSENSUS_SOURCES {
+            check(&src, &src.join(source), FORBIDDEN, ALLOWED, &mut violations);
+        }
+        assert!(
+            violations.is_empty(),
+            "non-deterministic consensus code:\n{}",
+            violations.join("\n")
+        );
+    }
+
+    #[test]
+    fn consensus_independence() {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut violations = vec![];
+        for source in CONSENSUS_SOURCES {
+            check(&src, &src.join(source), HELPERS, &[], &mut violations);
+        }
+        assert!(
+            violations.is_empty(),
+            "consensus code depends on helpers:\n{}",
+            violations.join("\n")
+        );
+    }
+}
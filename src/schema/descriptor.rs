@@ -0,0 +1,428 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! JSON descriptor of a schema for block explorers, registries and other
+//! external tooling.
+//!
+//! The descriptor is not a part of consensus: it lists the types declared by
+//! the schema, their bounds and the validation entry points in a form which
+//! doesn't require strict type or AluVM knowledge, and embeds the complete
+//! schema definition, from which the schema is recovered.
+
+use aluvm::library::{LibId, LibSite};
+use strict_encoding::TypeName;
+use strict_types::SemId;
+
+use super::{
+    AssignmentType, GlobalStateKind, GlobalStateType, MetaType, Occurrences, OpFullType, OpSchema,
+    OwnedStateSchema, RegistrySchema, RicardianSchema, Schema, SchemaFlags, SchemaId,
+    ScriptFingerprint, SupplySchema, ValencyType,
+};
+use crate::{Identity, StateType};
+
+/// Version of the schema descriptor format produced by
+/// [`Schema::to_descriptor_json`].
+pub const SCHEMA_DESCRIPTOR_VERSION: u8 = 1;
+
+/// Errors reading schema from a JSON descriptor.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum DescriptorError {
+    /// invalid schema descriptor JSON: {0}
+    Json(String),
+
+    /// unsupported schema descriptor version {0}.
+    Version(u8),
+
+    /// schema descriptor declares schema id {declared}, while its definition
+    /// has id {actual}.
+    IdMismatch {
+        declared: SchemaId,
+        actual: SchemaId,
+    },
+
+    /// schema descriptor doesn't match its schema definition.
+    Inconsistent,
+}
+
+/// Description of a schema for external tooling.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct SchemaDescriptor {
+    /// Version of the descriptor format, see [`SCHEMA_DESCRIPTOR_VERSION`].
+    pub version: u8,
+    pub schema_id: SchemaId,
+    pub name: TypeName,
+    pub developer: Identity,
+    pub timestamp: i64,
+    /// Raw schema flags.
+    pub flags: u8,
+    /// Names of the known schema flags which are set.
+    pub flag_names: Vec<String>,
+    pub meta_types: Vec<MetaDescriptor>,
+    pub global_types: Vec<GlobalDescriptor>,
+    pub owned_types: Vec<OwnedDescriptor>,
+    pub valency_types: Vec<ValencyType>,
+    /// Genesis, state extension and state transition types, in this order.
+    pub operations: Vec<OperationDescriptor>,
+    pub ricardian: Option<RicardianSchema>,
+    /// Fixed supply declaration, present only for the schemata with
+    /// [`SchemaFlags::FIXED_SUPPLY`] flag.
+    pub fixed_supply: Option<SupplySchema>,
+    /// Key registry declaration, present only for the schemata with
+    /// [`SchemaFlags::KEY_REGISTRY`] flag.
+    pub registry: Option<RegistrySchema>,
+    pub script: ScriptDescriptor,
+    /// Complete schema definition.
+    pub definition: Schema,
+}
+
+/// Metadata type declared by a schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct MetaDescriptor {
+    pub ty: MetaType,
+    pub sem_id: SemId,
+}
+
+/// Global state type declared by a schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct GlobalDescriptor {
+    pub ty: GlobalStateType,
+    pub kind: GlobalStateKind,
+    pub sem_id: SemId,
+    /// Maximal number of the values a single operation may assign.
+    pub max_items: u16,
+}
+
+/// Owned state type declared by a schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct OwnedDescriptor {
+    pub ty: AssignmentType,
+    pub state_type: StateType,
+    pub schema: OwnedStateSchema,
+}
+
+/// Number of the items of some type an operation may contain.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct TypeBounds<T> {
+    pub ty: T,
+    pub min: u16,
+    pub max: u16,
+}
+
+impl<T> TypeBounds<T> {
+    fn with(ty: T, occurrences: &Occurrences) -> Self {
+        TypeBounds {
+            ty,
+            min: occurrences.min_value(),
+            max: occurrences.max_value(),
+        }
+    }
+}
+
+/// Operation type declared by a schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct OperationDescriptor {
+    pub op_type: OpFullType,
+    pub metadata: Vec<MetaType>,
+    pub globals: Vec<TypeBounds<GlobalStateType>>,
+    /// Spent owned state; empty for genesis and state extensions.
+    pub inputs: Vec<TypeBounds<AssignmentType>>,
+    /// Redeemed valencies; empty for genesis and state transitions.
+    pub redeems: Vec<ValencyType>,
+    pub assignments: Vec<TypeBounds<AssignmentType>>,
+    pub valencies: Vec<ValencyType>,
+    /// Validation script entry point, if any.
+    pub entry_point: Option<LibSite>,
+}
+
+impl OperationDescriptor {
+    fn with(op_type: OpFullType, schema: &impl OpSchema, entry_point: Option<LibSite>) -> Self {
+        OperationDescriptor {
+            op_type,
+            metadata: schema.metadata().iter().copied().collect(),
+            globals: schema
+                .globals()
+                .iter()
+                .map(|(ty, occ)| TypeBounds::with(*ty, occ))
+                .collect(),
+            inputs: schema
+                .inputs()
+                .into_iter()
+                .flatten()
+                .map(|(ty, occ)| TypeBounds::with(*ty, occ))
+                .collect(),
+            redeems: schema.redeems().into_iter().flatten().copied().collect(),
+            assignments: schema
+                .assignments()
+                .iter()
+                .map(|(ty, occ)| TypeBounds::with(*ty, occ))
+                .collect(),
+            valencies: schema.valencies().iter().copied().collect(),
+            entry_point,
+        }
+    }
+}
+
+/// Kind of the validation scripts used by a schema.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub enum ScriptKind {
+    /// Schema has no validation scripts.
+    #[display("none")]
+    None,
+
+    /// AluVM scripts.
+    #[display("aluvm")]
+    AluVm,
+}
+
+/// Validation scripts used by a schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct ScriptDescriptor {
+    pub kind: ScriptKind,
+    /// See [`Schema::script_fingerprint`].
+    pub fingerprint: ScriptFingerprint,
+    /// Libraries containing the entry points, without repetitions.
+    pub libs: Vec<LibId>,
+}
+
+impl Schema {
+    /// Describes the schema for external tooling.
+    pub fn descriptor(&self) -> SchemaDescriptor {
+        let flag_names = [
+            (SchemaFlags::MERGE_DUPLICATE_SEALS, "mergeDuplicateSeals"),
+            (SchemaFlags::FIXED_SUPPLY, "fixedSupply"),
+            (SchemaFlags::REVEALED_AMOUNTS, "revealedAmounts"),
+            (SchemaFlags::KEY_REGISTRY, "keyRegistry"),
+            (SchemaFlags::ISSUER_AUTHORITY, "issuerAuthority"),
+            (SchemaFlags::EXPIRING_ALLOCATIONS, "expiringAllocations"),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| self.flags.contains(*flag))
+        .map(|(_, name)| name.to_owned())
+        .collect();
+
+        let mut operations = vec![OperationDescriptor::with(
+            OpFullType::Genesis,
+            &self.genesis,
+            self.genesis.validator,
+        )];
+        operations.extend(self.extensions.iter().map(|(ty, schema)| {
+            OperationDescriptor::with(OpFullType::StateExtension(*ty), schema, schema.validator)
+        }));
+        operations.extend(self.transitions.iter().map(|(ty, schema)| {
+            OperationDescriptor::with(OpFullType::StateTransition(*ty), schema, schema.validator)
+        }));
+
+        let mut libs = self.libs().collect::<Vec<_>>();
+        libs.sort_unstable();
+        libs.dedup();
+
+        SchemaDescriptor {
+            version: SCHEMA_DESCRIPTOR_VERSION,
+            schema_id: self.schema_id(),
+            name: self.name.clone(),
+            developer: self.developer.clone(),
+            timestamp: self.timestamp,
+            flags: self.flags.bits(),
+            flag_names,
+            meta_types: self
+                .meta_types
+                .iter()
+                .map(|(ty, sem_id)| MetaDescriptor {
+                    ty: *ty,
+                    sem_id: *sem_id,
+                })
+                .collect(),
+            global_types: self
+                .global_types
+                .iter()
+                .map(|(ty, schema)| GlobalDescriptor {
+                    ty: *ty,
+                    kind: schema.kind,
+                    sem_id: schema.sem_id,
+                    max_items: schema.max_items,
+                })
+                .collect(),
+            owned_types: self
+                .owned_types
+                .iter()
+                .map(|(ty, schema)| OwnedDescriptor {
+                    ty: *ty,
+                    state_type: schema.state_type(),
+                    schema: *schema,
+                })
+                .collect(),
+            valency_types: self.valency_types.iter().copied().collect(),
+            operations,
+            ricardian: self.ricardian(),
+            fixed_supply: self
+                .flags
+                .contains(SchemaFlags::FIXED_SUPPLY)
+                .then_some(self.fixed_supply),
+            registry: self
+                .flags
+                .contains(SchemaFlags::KEY_REGISTRY)
+                .then_some(self.registry),
+            script: ScriptDescriptor {
+                kind: if libs.is_empty() {
+                    ScriptKind::None
+                } else {
+                    ScriptKind::AluVm
+                },
+                fingerprint: self.script_fingerprint(),
+                libs,
+            },
+            definition: self.clone(),
+        }
+    }
+
+    /// Produces pretty-printed JSON descriptor of the schema (see
+    /// [`SchemaDescriptor`]).
+    pub fn to_descriptor_json(&self) -> String {
+        serde_json::to_string_pretty(&self.descriptor())
+            .expect("schema descriptor is always serializable")
+    }
+
+    /// Reads schema from its JSON descriptor, checking that the description
+    /// matches the embedded schema definition.
+    pub fn from_descriptor_json(json: &str) -> Result<Schema, DescriptorError> {
+        let descriptor = serde_json::from_str::<SchemaDescriptor>(json)
+            .map_err(|err| DescriptorError::Json(err.to_string()))?;
+        if descriptor.version != SCHEMA_DESCRIPTOR_VERSION {
+            return Err(DescriptorError::Version(descriptor.version));
+        }
+        let schema = descriptor.definition.clone();
+        let actual = schema.schema_id();
+        if descriptor.schema_id != actual {
+            return Err(DescriptorError::IdMismatch {
+                declared: descriptor.schema_id,
+                actual,
+            });
+        }
+        if schema.descriptor() != descriptor {
+            return Err(DescriptorError::Inconsistent);
+        }
+        Ok(schema)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aluvm::library::LibId;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::schema::{FungibleType, GlobalStateSchema, TransitionSchema, TransitionType};
+
+    fn schema() -> Schema {
+        let mut schema = Schema::strict_dumb();
+        schema.flags = SchemaFlags::REVEALED_AMOUNTS;
+        schema.global_types = tiny_bmap! {
+            GlobalStateType::with(1) => GlobalStateSchema::localized(SemId::strict_dumb(), 1),
+        };
+        schema.owned_types = tiny_bmap! {
+            AssignmentType::with(2) => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit),
+        };
+        let mut transition = TransitionSchema::default();
+        transition
+            .inputs
+            .insert(AssignmentType::with(2), Occurrences::OnceOrMore)
+            .unwrap();
+        transition
+            .assignments
+            .insert(AssignmentType::with(2), Occurrences::NoneOrUpTo(4))
+            .unwrap();
+        transition.validator = Some(LibSite::with(0x10, LibId::strict_dumb()));
+        schema.transitions = tiny_bmap! { TransitionType::with(3) => transition };
+        schema
+    }
+
+    #[test]
+    fn descriptor() {
+        let schema = schema();
+        let descriptor = schema.descriptor();
+        assert_eq!(descriptor.schema_id, schema.schema_id());
+        assert_eq!(descriptor.flag_names, vec!["revealedAmounts".to_owned()]);
+        assert_eq!(descriptor.global_types[0].kind, GlobalStateKind::Localized);
+        assert_eq!(descriptor.owned_types[0].state_type, StateType::Fungible);
+        assert_eq!(descriptor.fixed_supply, None);
+        assert_eq!(descriptor.operations.len(), 2);
+        let transition = &descriptor.operations[1];
+        assert_eq!(transition.op_type, OpFullType::StateTransition(TransitionType::with(3)));
+        assert_eq!(transition.inputs, vec![TypeBounds {
+            ty: AssignmentType::with(2),
+            min: 1,
+            max: u16::MAX
+        }]);
+        assert_eq!(transition.assignments[0].max, 4);
+        assert_eq!(transition.entry_point, Some(LibSite::with(0x10, LibId::strict_dumb())));
+        assert_eq!(descriptor.script.kind, ScriptKind::AluVm);
+        assert_eq!(descriptor.script.libs, vec![LibId::strict_dumb()]);
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let schema = schema();
+        let json = schema.to_descriptor_json();
+        let restored = Schema::from_descriptor_json(&json).unwrap();
+        assert_eq!(restored.schema_id(), schema.schema_id());
+        assert_eq!(restored.transitions, schema.transitions);
+
+        assert!(matches!(Schema::from_descriptor_json("{}"), Err(DescriptorError::Json(_))));
+
+        let mut descriptor = schema.descriptor();
+        descriptor.version = 2;
+        let json = serde_json::to_string(&descriptor).unwrap();
+        assert_eq!(Schema::from_descriptor_json(&json), Err(DescriptorError::Version(2)));
+
+        let mut descriptor = schema.descriptor();
+        descriptor.definition.timestamp += 1;
+        let json = serde_json::to_string(&descriptor).unwrap();
+        assert_eq!(
+            Schema::from_descriptor_json(&json),
+            Err(DescriptorError::IdMismatch {
+                declared: schema.schema_id(),
+                actual: descriptor.definition.schema_id(),
+            })
+        );
+
+        let mut descriptor = schema.descriptor();
+        descriptor.operations[1].assignments[0].max = 5;
+        let json = serde_json::to_string(&descriptor).unwrap();
+        assert_eq!(Schema::from_descriptor_json(&json), Err(DescriptorError::Inconsistent));
+    }
+}
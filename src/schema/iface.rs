@@ -0,0 +1,310 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contract interfaces: descriptors mapping human-readable names of the
+//! operations and state to the type ids of a schema.
+//!
+//! Interfaces are not part of the schema and are not required for the contract
+//! validation. They allow wallets to work with contracts under schemata they
+//! are not familiar with, after checking that the interface binds to the
+//! schema. Interfaces are committed with [`IfaceId`], which can be referenced
+//! by the contract data where the binding needs to be made consensus-critical.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::confinement::TinyOrdMap;
+use amplify::{ByteArray, Bytes32};
+use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+use commit_verify::{CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{FieldName, StrictDeserialize, StrictSerialize, TypeName};
+
+use super::{AssignmentType, ExtensionType, GlobalStateType, MetaType, Schema, TransitionType};
+use crate::{LIB_NAME_RGB, impl_serde_baid64};
+
+/// Interface identifier.
+///
+/// Interface identifier commits to all the interface data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct IfaceId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for IfaceId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for IfaceId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:iface#2024-06-14";
+}
+
+impl DisplayBaid64 for IfaceId {
+    const HRI: &'static str = "rgb:ifc";
+    const CHUNKING: bool = false;
+    const PREFIX: bool = true;
+    const EMBED_CHECKSUM: bool = false;
+    const MNEMONIC: bool = true;
+    fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
+}
+impl FromBaid64Str for IfaceId {}
+impl FromStr for IfaceId {
+    type Err = Baid64ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+}
+impl Display for IfaceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
+}
+
+impl_serde_baid64!(IfaceId);
+
+/// Errors binding interface to a schema.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum IfaceError {
+    /// interface metadata '{0}' references type {1} unknown to the schema.
+    MetaAbsent(FieldName, MetaType),
+
+    /// interface global state '{0}' references type {1} unknown to the schema.
+    GlobalAbsent(FieldName, GlobalStateType),
+
+    /// interface assignment '{0}' references type {1} unknown to the schema.
+    AssignmentAbsent(FieldName, AssignmentType),
+
+    /// interface state transition '{0}' references type {1} unknown to the
+    /// schema.
+    TransitionAbsent(FieldName, TransitionType),
+
+    /// interface state extension '{0}' references type {1} unknown to the
+    /// schema.
+    ExtensionAbsent(FieldName, ExtensionType),
+
+    /// interface defines format for '{0}', which is not an assignment name.
+    FormatUnbound(FieldName),
+}
+
+/// Format of the human-readable representation of owned state, used by
+/// [`crate::StateRenderer`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = order)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum StateFormat {
+    /// State is rendered without any interpretation.
+    #[default]
+    Plain,
+
+    /// Fungible amount with a given number of decimal digits after the point.
+    Amount(u8),
+
+    /// Structured state holding a token id as a little-endian integer, as in
+    /// non-fungible tokens.
+    TokenId,
+
+    /// Attachment rendered with its digest and media type.
+    Digest,
+}
+
+/// Contract interface, mapping names of the operations and state to the type
+/// ids of a schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Iface {
+    pub name: TypeName,
+    pub metadata: TinyOrdMap<FieldName, MetaType>,
+    pub globals: TinyOrdMap<FieldName, GlobalStateType>,
+    pub assignments: TinyOrdMap<FieldName, AssignmentType>,
+    pub transitions: TinyOrdMap<FieldName, TransitionType>,
+    pub extensions: TinyOrdMap<FieldName, ExtensionType>,
+    /// Formats of the assignments, listed by their names.
+    pub formats: TinyOrdMap<FieldName, StateFormat>,
+}
+
+impl CommitEncode for Iface {
+    type CommitmentId = IfaceId;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        e.commit_to_serialized(&self.name);
+        e.commit_to_map(&self.metadata);
+        e.commit_to_map(&self.globals);
+        e.commit_to_map(&self.assignments);
+        e.commit_to_map(&self.transitions);
+        e.commit_to_map(&self.extensions);
+        e.commit_to_map(&self.formats);
+    }
+}
+
+impl StrictSerialize for Iface {}
+impl StrictDeserialize for Iface {}
+
+impl Iface {
+    /// Constructs interface not mapping any names.
+    pub fn new(name: TypeName) -> Self {
+        Iface {
+            name,
+            metadata: empty!(),
+            globals: empty!(),
+            assignments: empty!(),
+            transitions: empty!(),
+            extensions: empty!(),
+            formats: empty!(),
+        }
+    }
+
+    #[inline]
+    pub fn iface_id(&self) -> IfaceId { self.commit_id() }
+
+    pub fn meta_type(&self, name: &FieldName) -> Option<MetaType> {
+        self.metadata.get(name).copied()
+    }
+
+    pub fn global_type(&self, name: &FieldName) -> Option<GlobalStateType> {
+        self.globals.get(name).copied()
+    }
+
+    pub fn assignment_type(&self, name: &FieldName) -> Option<AssignmentType> {
+        self.assignments.get(name).copied()
+    }
+
+    pub fn transition_type(&self, name: &FieldName) -> Option<TransitionType> {
+        self.transitions.get(name).copied()
+    }
+
+    pub fn extension_type(&self, name: &FieldName) -> Option<ExtensionType> {
+        self.extensions.get(name).copied()
+    }
+
+    /// Returns name of the assignment type, if it is covered by the
+    /// interface.
+    pub fn assignment_name(&self, ty: AssignmentType) -> Option<&FieldName> {
+        self.assignments
+            .iter()
+            .find(|(_, t)| **t == ty)
+            .map(|(name, _)| name)
+    }
+
+    /// Returns format of the state for a given assignment type, defaulting to
+    /// [`StateFormat::Plain`].
+    pub fn state_format(&self, ty: AssignmentType) -> StateFormat {
+        self.assignment_name(ty)
+            .and_then(|name| self.formats.get(name))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Checks that all the types referenced by the interface are defined by
+    /// the schema. The schema may define types not covered by the interface.
+    pub fn check(&self, schema: &Schema) -> Result<(), IfaceError> {
+        for (name, ty) in &self.metadata {
+            if !schema.meta_types.contains_key(ty) {
+                return Err(IfaceError::MetaAbsent(name.clone(), *ty));
+            }
+        }
+        for (name, ty) in &self.globals {
+            if !schema.global_types.contains_key(ty) {
+                return Err(IfaceError::GlobalAbsent(name.clone(), *ty));
+            }
+        }
+        for (name, ty) in &self.assignments {
+            if !schema.owned_types.contains_key(ty) {
+                return Err(IfaceError::AssignmentAbsent(name.clone(), *ty));
+            }
+        }
+        for (name, ty) in &self.transitions {
+            if !schema.transitions.contains_key(ty) {
+                return Err(IfaceError::TransitionAbsent(name.clone(), *ty));
+            }
+        }
+        for (name, ty) in &self.extensions {
+            if !schema.extensions.contains_key(ty) {
+                return Err(IfaceError::ExtensionAbsent(name.clone(), *ty));
+            }
+        }
+        if let Some(name) = self
+            .formats
+            .keys()
+            .find(|name| !self.assignments.contains_key(*name))
+        {
+            return Err(IfaceError::FormatUnbound(name.clone()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{GlobalStateSchema, OwnedStateSchema};
+
+    #[test]
+    fn binding() {
+        let mut schema = Schema::strict_dumb();
+        schema.global_types = tiny_bmap! {
+            GlobalStateType::with(1) => GlobalStateSchema::once(strict_dumb!())
+        };
+        schema.owned_types =
+            tiny_bmap! { AssignmentType::with(2) => OwnedStateSchema::Declarative };
+
+        let mut iface = Iface::new(tn!("Rights"));
+        iface.globals = tiny_bmap! { fname!("terms") => GlobalStateType::with(1) };
+        iface.assignments = tiny_bmap! { fname!("right") => AssignmentType::with(2) };
+        assert_eq!(iface.check(&schema), Ok(()));
+        assert_eq!(iface.assignment_type(&fname!("right")), Some(AssignmentType::with(2)));
+        assert_eq!(iface.global_type(&fname!("right")), None);
+        assert_eq!(iface.state_format(AssignmentType::with(2)), StateFormat::Plain);
+        iface.formats = tiny_bmap! { fname!("right") => StateFormat::TokenId };
+        assert_eq!(iface.state_format(AssignmentType::with(2)), StateFormat::TokenId);
+        iface.formats = tiny_bmap! { fname!("terms") => StateFormat::Digest };
+        assert_eq!(iface.check(&schema), Err(IfaceError::FormatUnbound(fname!("terms"))));
+        iface.formats = none!();
+
+        let id = iface.iface_id();
+        assert_eq!(IfaceId::from_str(&id.to_string()).unwrap(), id);
+        assert!(id.to_string().starts_with("rgb:ifc:"));
+
+        iface
+            .transitions
+            .insert(fname!("transfer"), TransitionType::with(1))
+            .unwrap();
+        assert_ne!(iface.iface_id(), id);
+        assert_eq!(
+            iface.check(&schema),
+            Err(IfaceError::TransitionAbsent(fname!("transfer"), TransitionType::with(1)))
+        );
+    }
+}
@@ -0,0 +1,253 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Registry of the type ids having embedded semantics in all schemata, which
+//! allows wallets to label such data generically and schema builders to
+//! reference the types without hardcoding their ids.
+
+use amplify::Wrapper;
+
+use super::{
+    AssignmentType, MIGRATION_TYPE, MetaType, SUCCESSOR_SCHEMA_TYPE, Schema, SchemaFlags,
+    TransitionType,
+};
+use crate::{ALLOCATION_EXPIRY_TYPE, GENESIS_SALT_TYPE, ISSUANCE_SPLIT_TYPE};
+
+/// Kind of the type id of a [`KnownType`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum KnownTypeKind {
+    #[display("metadata")]
+    Meta,
+
+    #[display("owned state")]
+    Owned,
+
+    #[display("state transition")]
+    Transition,
+}
+
+/// Type id with embedded semantics.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum KnownType {
+    /// See [`GENESIS_SALT_TYPE`].
+    #[display("genesis salt")]
+    GenesisSalt,
+
+    /// See [`SUCCESSOR_SCHEMA_TYPE`].
+    #[display("successor schema")]
+    SuccessorSchema,
+
+    /// See [`ISSUANCE_SPLIT_TYPE`].
+    #[display("issuance split")]
+    IssuanceSplit,
+
+    /// See [`ALLOCATION_EXPIRY_TYPE`].
+    #[display("allocation expiry")]
+    AllocationExpiry,
+
+    /// See [`MIGRATION_TYPE`].
+    #[display("contract migration")]
+    Migration,
+
+    /// See [`AssignmentType::ISSUER_AUTHORITY`].
+    #[display("issuer authority")]
+    IssuerAuthority,
+
+    /// See [`TransitionType::BLANK`].
+    #[display("blank transition")]
+    BlankTransition,
+}
+
+impl KnownType {
+    pub const ALL: [Self; 7] = [
+        KnownType::GenesisSalt,
+        KnownType::SuccessorSchema,
+        KnownType::IssuanceSplit,
+        KnownType::AllocationExpiry,
+        KnownType::Migration,
+        KnownType::IssuerAuthority,
+        KnownType::BlankTransition,
+    ];
+
+    pub fn kind(self) -> KnownTypeKind {
+        match self {
+            KnownType::GenesisSalt |
+            KnownType::SuccessorSchema |
+            KnownType::IssuanceSplit |
+            KnownType::AllocationExpiry |
+            KnownType::Migration => KnownTypeKind::Meta,
+            KnownType::IssuerAuthority => KnownTypeKind::Owned,
+            KnownType::BlankTransition => KnownTypeKind::Transition,
+        }
+    }
+
+    /// Numeric type id, which is interpreted according to [`Self::kind`].
+    pub fn id(self) -> u16 {
+        match self {
+            KnownType::GenesisSalt => GENESIS_SALT_TYPE.to_inner(),
+            KnownType::SuccessorSchema => SUCCESSOR_SCHEMA_TYPE.to_inner(),
+            KnownType::IssuanceSplit => ISSUANCE_SPLIT_TYPE.to_inner(),
+            KnownType::AllocationExpiry => ALLOCATION_EXPIRY_TYPE.to_inner(),
+            KnownType::Migration => MIGRATION_TYPE.to_inner(),
+            KnownType::IssuerAuthority => AssignmentType::ISSUER_AUTHORITY.to_inner(),
+            KnownType::BlankTransition => TransitionType::BLANK.to_inner(),
+        }
+    }
+
+    pub fn meta_type(self) -> Option<MetaType> {
+        (self.kind() == KnownTypeKind::Meta).then(|| MetaType::with(self.id()))
+    }
+
+    pub fn assignment_type(self) -> Option<AssignmentType> {
+        (self.kind() == KnownTypeKind::Owned).then(|| AssignmentType::with(self.id()))
+    }
+
+    pub fn transition_type(self) -> Option<TransitionType> {
+        (self.kind() == KnownTypeKind::Transition).then(|| TransitionType::with(self.id()))
+    }
+
+    /// Looks up known metadata type.
+    pub fn with_meta_type(ty: MetaType) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|known| known.meta_type() == Some(ty))
+    }
+
+    /// Looks up known owned state type.
+    pub fn with_assignment_type(ty: AssignmentType) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|known| known.assignment_type() == Some(ty))
+    }
+
+    /// Looks up known state transition type.
+    pub fn with_transition_type(ty: TransitionType) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|known| known.transition_type() == Some(ty))
+    }
+
+    /// Human-readable description of the type semantics.
+    pub fn description(self) -> &'static str {
+        match self {
+            KnownType::GenesisSalt => "uniqueness salt of the contract genesis",
+            KnownType::SuccessorSchema => "id of the schema the contract may migrate to",
+            KnownType::IssuanceSplit => "split of the genesis issuance among multiple issuers",
+            KnownType::AllocationExpiry => {
+                "heights after which the transition allocations return to the sender"
+            }
+            KnownType::Migration => "id of the contract migration the operation is created under",
+            KnownType::IssuerAuthority => "key or other data of the contract issuer authority",
+            KnownType::BlankTransition => "transition moving state without changing it",
+        }
+    }
+
+    /// Strict type of the data under the type, if it is fixed.
+    ///
+    /// Metadata values are strict-serialized values of this type; issuer
+    /// authority data format is defined by the schema.
+    pub fn data_type(self) -> Option<&'static str> {
+        match self {
+            KnownType::GenesisSalt => Some("U64"),
+            KnownType::SuccessorSchema => Some("RGB.SchemaId"),
+            KnownType::IssuanceSplit => Some("RGB.IssuanceSplit"),
+            KnownType::AllocationExpiry => Some("RGB.AllocationExpiries"),
+            KnownType::Migration => Some("RGB.MigrationId"),
+            KnownType::IssuerAuthority | KnownType::BlankTransition => None,
+        }
+    }
+
+    /// Schema flag enabling the embedded semantics of the type, if the
+    /// semantics is not enabled for all schemata.
+    pub fn required_flag(self) -> Option<SchemaFlags> {
+        match self {
+            KnownType::AllocationExpiry => Some(SchemaFlags::EXPIRING_ALLOCATIONS),
+            KnownType::IssuerAuthority => Some(SchemaFlags::ISSUER_AUTHORITY),
+            KnownType::GenesisSalt |
+            KnownType::SuccessorSchema |
+            KnownType::IssuanceSplit |
+            KnownType::Migration |
+            KnownType::BlankTransition => None,
+        }
+    }
+
+    /// Detects whether the embedded semantics of the type applies to the
+    /// contracts under the schema.
+    pub fn is_active(self, schema: &Schema) -> bool {
+        self.required_flag()
+            .map_or(true, |flag| schema.flags.contains(flag))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::{StrictDumb, StrictType};
+
+    use super::*;
+    use crate::{AllocationExpiries, IssuanceSplit, MigrationId, SchemaId};
+
+    #[test]
+    fn registry() {
+        for known in KnownType::ALL {
+            let found = match known.kind() {
+                KnownTypeKind::Meta => KnownType::with_meta_type(known.meta_type().unwrap()),
+                KnownTypeKind::Owned => {
+                    KnownType::with_assignment_type(known.assignment_type().unwrap())
+                }
+                KnownTypeKind::Transition => {
+                    KnownType::with_transition_type(known.transition_type().unwrap())
+                }
+            };
+            assert_eq!(found, Some(known));
+            assert!(!known.description().is_empty());
+        }
+        assert_eq!(KnownType::with_meta_type(MetaType::with(1)), None);
+        assert_eq!(KnownType::GenesisSalt.meta_type(), Some(GENESIS_SALT_TYPE));
+        assert_eq!(KnownType::GenesisSalt.assignment_type(), None);
+
+        fn name<T: StrictType>() -> String {
+            format!("{}.{}", T::STRICT_LIB_NAME, T::strict_name().unwrap())
+        }
+        assert_eq!(KnownType::SuccessorSchema.data_type(), Some(name::<SchemaId>().as_str()));
+        assert_eq!(KnownType::IssuanceSplit.data_type(), Some(name::<IssuanceSplit>().as_str()));
+        assert_eq!(
+            KnownType::AllocationExpiry.data_type(),
+            Some(name::<AllocationExpiries>().as_str())
+        );
+        assert_eq!(KnownType::Migration.data_type(), Some(name::<MigrationId>().as_str()));
+
+        let mut schema = Schema::strict_dumb();
+        assert!(KnownType::GenesisSalt.is_active(&schema));
+        assert!(!KnownType::AllocationExpiry.is_active(&schema));
+        schema.flags = SchemaFlags::EXPIRING_ALLOCATIONS;
+        assert!(KnownType::AllocationExpiry.is_active(&schema));
+    }
+}
@@ -0,0 +1,251 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Schema linter flagging patterns which are valid under the consensus rules,
+//! but are commonly a source of vulnerabilities in the contracts, such that
+//! schema registries may reject or mark such schemata.
+
+use std::collections::BTreeSet;
+
+use strict_types::{SemId, TypeSystem};
+
+use super::{
+    AssignmentType, MetaType, OpFullType, OpSchema, OwnedStateSchema, Schema, SchemaFlags, SchemaId,
+};
+
+/// Severity of a schema lint.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum LintSeverity {
+    /// Pattern which may be intended, but deserves a review.
+    #[display("warning")]
+    Warning,
+
+    /// Pattern which allows contract participants to break the contract
+    /// invariants.
+    #[display("critical")]
+    Critical,
+}
+
+/// Risky pattern found in a schema.
+#[derive(Clone, PartialEq, Eq, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+pub enum Lint {
+    /// {0} has no validation script, thus its state is checked only against
+    /// the schema-defined types and occurrences.
+    NoValidator(OpFullType),
+
+    /// {0} may assign an unbounded number of inflation rights of type {1},
+    /// which are spent to issue new fungible state.
+    InflationUnbounded(OpFullType, AssignmentType),
+
+    /// {1} assigns fungible state of type {0}, which may be confidential, but
+    /// has no validation script checking that the amounts are balanced and
+    /// do not overflow.
+    ConfidentialUnchecked(AssignmentType, OpFullType),
+
+    /// metadata type {0} has semantic type {1} absent from the type system.
+    MetaSemIdUnknown(MetaType, SemId),
+}
+
+impl Lint {
+    pub fn severity(&self) -> LintSeverity {
+        match self {
+            Lint::NoValidator(_) | Lint::InflationUnbounded(..) => LintSeverity::Warning,
+            Lint::ConfidentialUnchecked(..) | Lint::MetaSemIdUnknown(..) => LintSeverity::Critical,
+        }
+    }
+}
+
+/// Lints found in a schema by [`Schema::lint`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct LintReport {
+    pub schema_id: SchemaId,
+    pub lints: Vec<Lint>,
+}
+
+impl LintReport {
+    /// Detects whether the schema has no lints.
+    pub fn is_clean(&self) -> bool { self.lints.is_empty() }
+
+    /// Returns the highest severity of the lints, if there are any.
+    pub fn max_severity(&self) -> Option<LintSeverity> {
+        self.lints.iter().map(Lint::severity).max()
+    }
+
+    /// Iterates over the lints with severity at least the given one.
+    pub fn at_least(&self, severity: LintSeverity) -> impl Iterator<Item = &Lint> {
+        self.lints
+            .iter()
+            .filter(move |lint| lint.severity() >= severity)
+    }
+}
+
+impl Schema {
+    /// Checks the schema for risky patterns, using the type system to resolve
+    /// the semantic types used by the schema.
+    ///
+    /// Unlike [`Schema::verify`], the lints don't make the schema invalid.
+    pub fn lint(&self, types: &TypeSystem) -> LintReport {
+        let mut lints = vec![];
+
+        let mut ops = Vec::<(OpFullType, &dyn OpSchema, bool)>::new();
+        for (ty, schema) in &self.extensions {
+            ops.push((OpFullType::StateExtension(*ty), schema, schema.validator.is_some()));
+        }
+        for (ty, schema) in &self.transitions {
+            ops.push((OpFullType::StateTransition(*ty), schema, schema.validator.is_some()));
+        }
+
+        for (op_type, _, has_validator) in &ops {
+            if !has_validator {
+                lints.push(Lint::NoValidator(*op_type));
+            }
+        }
+
+        // Inflation rights are spent by the operations assigning fungible
+        // state which they don't spend, i.e. issuing new state.
+        let mut inflation_rights = BTreeSet::new();
+        for (_, schema, _) in &ops {
+            let inputs = schema.inputs().cloned().unwrap_or_default();
+            let issues = schema.assignments().keys().any(|ty| {
+                matches!(self.owned_types.get(ty), Some(OwnedStateSchema::Fungible(_))) &&
+                    !inputs.contains_key(ty)
+            });
+            if issues {
+                inflation_rights.extend(inputs.keys().copied());
+            }
+        }
+        let genesis = (OpFullType::Genesis, &self.genesis as &dyn OpSchema);
+        for (op_type, schema) in ops
+            .iter()
+            .map(|(op_type, schema, _)| (*op_type, *schema))
+            .chain([genesis])
+        {
+            for (ty, occurrences) in schema.assignments() {
+                if inflation_rights.contains(ty) && occurrences.max_value() == u16::MAX {
+                    lints.push(Lint::InflationUnbounded(op_type, *ty));
+                }
+            }
+        }
+
+        if !self.flags.contains(SchemaFlags::REVEALED_AMOUNTS) {
+            for (ty, _) in self
+                .owned_types
+                .iter()
+                .filter(|(_, schema)| matches!(schema, OwnedStateSchema::Fungible(_)))
+            {
+                for (op_type, schema, has_validator) in &ops {
+                    if !has_validator && schema.assignments().contains_key(ty) {
+                        lints.push(Lint::ConfidentialUnchecked(*ty, *op_type));
+                    }
+                }
+            }
+        }
+
+        for (ty, sem_id) in &self.meta_types {
+            if !types.contains_key(sem_id) {
+                lints.push(Lint::MetaSemIdUnknown(*ty, *sem_id));
+            }
+        }
+
+        LintReport {
+            schema_id: self.schema_id(),
+            lints,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aluvm::library::{LibId, LibSite};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::schema::{FungibleType, Occurrences, TransitionSchema, TransitionType};
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+    const INFLATION: AssignmentType = AssignmentType::with(2);
+    const TRANSFER: TransitionType = TransitionType::with(1);
+    const ISSUE: TransitionType = TransitionType::with(2);
+
+    #[test]
+    fn lint() {
+        let mut schema = Schema::strict_dumb();
+        schema.meta_types = tiny_bmap! { MetaType::with(1) => SemId::strict_dumb() };
+        schema.owned_types = tiny_bmap! {
+            ASSET => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit),
+            INFLATION => OwnedStateSchema::Declarative,
+        };
+        schema.genesis.assignments = tiny_bmap! {
+            ASSET => Occurrences::OnceOrMore,
+            INFLATION => Occurrences::NoneOrMore,
+        };
+        let transfer = TransitionSchema {
+            inputs: tiny_bmap! { ASSET => Occurrences::OnceOrMore },
+            assignments: tiny_bmap! { ASSET => Occurrences::OnceOrMore },
+            validator: Some(LibSite::with(0, LibId::strict_dumb())),
+            ..default!()
+        };
+        let issue = TransitionSchema {
+            inputs: tiny_bmap! { INFLATION => Occurrences::Once },
+            assignments: tiny_bmap! {
+                ASSET => Occurrences::OnceOrMore,
+                INFLATION => Occurrences::NoneOrOnce,
+            },
+            ..default!()
+        };
+        schema.transitions = tiny_bmap! { TRANSFER => transfer, ISSUE => issue };
+
+        let report = schema.lint(&TypeSystem::default());
+        assert_eq!(report.schema_id, schema.schema_id());
+        assert_eq!(report.lints, vec![
+            Lint::NoValidator(OpFullType::StateTransition(ISSUE)),
+            Lint::InflationUnbounded(OpFullType::Genesis, INFLATION),
+            Lint::ConfidentialUnchecked(ASSET, OpFullType::StateTransition(ISSUE)),
+            Lint::MetaSemIdUnknown(MetaType::with(1), SemId::strict_dumb()),
+        ]);
+        assert_eq!(report.max_severity(), Some(LintSeverity::Critical));
+        assert_eq!(report.at_least(LintSeverity::Critical).count(), 2);
+
+        schema.flags = SchemaFlags::REVEALED_AMOUNTS;
+        schema.meta_types = none!();
+        schema.genesis.assignments = tiny_bmap! { ASSET => Occurrences::OnceOrMore };
+        let report = schema.lint(&TypeSystem::default());
+        assert_eq!(report.lints, vec![Lint::NoValidator(OpFullType::StateTransition(ISSUE))]);
+        assert_eq!(report.max_severity(), Some(LintSeverity::Warning));
+        assert!(!report.is_clean());
+    }
+}
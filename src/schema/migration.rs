@@ -0,0 +1,495 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contract schema migration.
+//!
+//! A contract is validated against the schema claimed by its genesis. Issuer
+//! may pre-announce a successor schema by committing its id in the genesis
+//! metadata under [`SUCCESSOR_SCHEMA_TYPE`]; [`SchemaMigration`] then
+//! describes how the state types of the original schema map onto the types
+//! of the successor, and is accepted only if the mapping is complete, i.e.
+//! no existing allocation or global state is stranded by the migration.
+//!
+//! The mapping of the owned state types works as a set of aliases: outputs
+//! of the operations created under the original schema keep their types,
+//! and transitions under the successor schema spend them using the original
+//! type, which is resolved into its alias (see
+//! [`SchemaMigration::resolve_inputs`]). Thus, the contract history spanning
+//! both schemata remains valid without re-issuing the existing allocations.
+//!
+//! The migration itself is a [`Migration`] node supplied with the contract
+//! history, carrying the successor schema and the mapping. Operations created
+//! under the successor schema commit to the id of the migration in their
+//! metadata under [`MIGRATION_TYPE`].
+
+use amplify::confinement::{SmallBlob, TinyOrdMap};
+use amplify::hex::ToHex;
+use amplify::{ByteArray, Bytes32};
+use commit_verify::{CommitId, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use super::{AssignmentType, GlobalStateType, MetaType, Schema, SchemaId};
+use crate::{
+    Genesis, Inputs, LIB_NAME_RGB, MetaValue, Metadata, MetadataError, OpId, Opout, Transition,
+};
+
+/// Metadata type reserved for the id of the successor schema. Valid in
+/// genesis of any schema not declaring metadata of this type.
+pub const SUCCESSOR_SCHEMA_TYPE: MetaType = MetaType::with(0xFFFE);
+
+/// Metadata type reserved for the id of the contract migration (see
+/// [`Migration`]). Valid in state transitions and extensions of any schema
+/// not declaring metadata of this type.
+pub const MIGRATION_TYPE: MetaType = MetaType::with(0xFFFB);
+
+impl Genesis {
+    /// Returns id of the successor schema committed by the genesis, if
+    /// present and valid.
+    pub fn successor_schema(&self) -> Option<SchemaId> {
+        let value = self.metadata.get(&SUCCESSOR_SCHEMA_TYPE)?;
+        let bytes = <[u8; 32]>::try_from(value.as_slice()).ok()?;
+        Some(SchemaId::from(bytes))
+    }
+
+    /// Commits to the successor schema, changing the contract id.
+    pub fn add_successor_schema(&mut self, schema_id: SchemaId) -> Result<(), MetadataError> {
+        let value = SmallBlob::try_from(schema_id.to_byte_array().to_vec()).expect("32 bytes");
+        self.metadata
+            .add_value(SUCCESSOR_SCHEMA_TYPE, MetaValue::from(value))?;
+        self.invalidate_id();
+        Ok(())
+    }
+}
+
+impl Transition {
+    /// Commits the transition to the contract migration, such that it is
+    /// validated against the successor schema.
+    pub fn add_migration(&mut self, migration_id: MigrationId) -> Result<(), MetadataError> {
+        let value = SmallBlob::try_from(migration_id.to_byte_array().to_vec()).expect("32 bytes");
+        self.metadata
+            .add_value(MIGRATION_TYPE, MetaValue::from(value))?;
+        self.invalidate_id();
+        Ok(())
+    }
+}
+
+/// Errors verifying schema migration.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+pub enum MigrationError {
+    /// contract genesis uses schema {actual}, while the migration starts from
+    /// schema {expected}.
+    SourceMismatch {
+        expected: SchemaId,
+        actual: SchemaId,
+    },
+
+    /// contract genesis doesn't commit to a successor schema.
+    NoSuccessor,
+
+    /// contract genesis commits to the successor schema {expected}, while the
+    /// migration targets schema {actual}.
+    TargetMismatch {
+        expected: SchemaId,
+        actual: SchemaId,
+    },
+
+    /// owned state type {0} of the original schema is not mapped by the
+    /// migration.
+    OwnedUnmapped(AssignmentType),
+
+    /// migration maps owned state type {0}, which is unknown to the original
+    /// schema.
+    OwnedUnknown(AssignmentType),
+
+    /// migration maps owned state type {0} to type {1}, which is absent from
+    /// the successor schema or defines state in a different way.
+    OwnedIncompatible(AssignmentType, AssignmentType),
+
+    /// migration maps several owned state types to the same type {0}.
+    OwnedRepeated(AssignmentType),
+
+    /// global state type {0} of the original schema is not mapped by the
+    /// migration.
+    GlobalUnmapped(GlobalStateType),
+
+    /// migration maps global state type {0}, which is unknown to the original
+    /// schema.
+    GlobalUnknown(GlobalStateType),
+
+    /// migration maps global state type {0} to type {1}, which is absent from
+    /// the successor schema or defines state in a different way.
+    GlobalIncompatible(GlobalStateType, GlobalStateType),
+
+    /// migration maps several global state types to the same type {0}.
+    GlobalRepeated(GlobalStateType),
+
+    /// input {0} spends output of an operation under the original schema with
+    /// owned state type which is not mapped by the migration.
+    InputUnmapped(Opout),
+}
+
+/// Contract migration identifier.
+///
+/// Migration identifier commits to the mapping of the state types and to the
+/// ids of the original and successor schemata.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(Self::to_hex)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct MigrationId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for MigrationId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for MigrationId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:migration#2024-10-18";
+}
+
+/// Mapping of the contract state types from the original schema onto the
+/// types of its successor.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = MigrationId)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SchemaMigration {
+    pub from: SchemaId,
+    pub to: SchemaId,
+    pub owned_types: TinyOrdMap<AssignmentType, AssignmentType>,
+    pub global_types: TinyOrdMap<GlobalStateType, GlobalStateType>,
+}
+
+impl SchemaMigration {
+    pub fn migration_id(&self) -> MigrationId { self.commit_id() }
+
+    /// Returns type of the owned state in the successor schema.
+    pub fn owned_type(&self, ty: AssignmentType) -> Option<AssignmentType> {
+        self.owned_types.get(&ty).copied()
+    }
+
+    /// Resolves output of an operation created under the original schema into
+    /// the same output seen under the successor schema, i.e. having the alias
+    /// of its owned state type.
+    pub fn alias(&self, opout: Opout) -> Option<Opout> {
+        let ty = self.owned_type(opout.ty)?;
+        Some(Opout::new(opout.op, ty, opout.no))
+    }
+
+    /// Resolves inputs of a transition under the successor schema in a
+    /// history spanning both schemata. Inputs spending outputs of the
+    /// operations created under the original schema, as detected by
+    /// `is_original`, are replaced with their aliases (see
+    /// [`SchemaMigration::alias`]); other inputs are kept intact. The
+    /// resolved inputs must be checked against the successor schema instead
+    /// of the original ones.
+    ///
+    /// # Errors
+    ///
+    /// If an input spends an output of the original operation of a type not
+    /// mapped by the migration.
+    pub fn resolve_inputs(
+        &self,
+        inputs: &Inputs,
+        is_original: impl Fn(OpId) -> bool,
+    ) -> Result<Vec<Opout>, MigrationError> {
+        inputs
+            .iter()
+            .map(|input| input.prev_out)
+            .map(|opout| {
+                if !is_original(opout.op) {
+                    return Ok(opout);
+                }
+                self.alias(opout)
+                    .ok_or(MigrationError::InputUnmapped(opout))
+            })
+            .collect()
+    }
+
+    /// Returns type of the global state in the successor schema.
+    pub fn global_type(&self, ty: GlobalStateType) -> Option<GlobalStateType> {
+        self.global_types.get(&ty).copied()
+    }
+
+    /// Verifies that the contract with the given genesis may be migrated from
+    /// the `original` schema to the `successor` schema using this mapping.
+    ///
+    /// Each of the owned and global state types of the original schema must
+    /// be mapped onto a distinct type of the successor schema defining the
+    /// state in the same way.
+    pub fn verify(
+        &self,
+        genesis: &Genesis,
+        original: &Schema,
+        successor: &Schema,
+    ) -> Result<(), MigrationError> {
+        for actual in [genesis.schema_id, original.schema_id()] {
+            if actual != self.from {
+                return Err(MigrationError::SourceMismatch {
+                    expected: self.from,
+                    actual,
+                });
+            }
+        }
+        let expected = genesis
+            .successor_schema()
+            .ok_or(MigrationError::NoSuccessor)?;
+        for actual in [self.to, successor.schema_id()] {
+            if actual != expected {
+                return Err(MigrationError::TargetMismatch { expected, actual });
+            }
+        }
+
+        if let Some(ty) = original
+            .owned_types
+            .keys()
+            .find(|ty| !self.owned_types.contains_key(*ty))
+        {
+            return Err(MigrationError::OwnedUnmapped(*ty));
+        }
+        let mut targets = bset! {};
+        for (from, to) in &self.owned_types {
+            let Some(state) = original.owned_types.get(from) else {
+                return Err(MigrationError::OwnedUnknown(*from));
+            };
+            if successor.owned_types.get(to) != Some(state) {
+                return Err(MigrationError::OwnedIncompatible(*from, *to));
+            }
+            if !targets.insert(*to) {
+                return Err(MigrationError::OwnedRepeated(*to));
+            }
+        }
+
+        if let Some(ty) = original
+            .global_types
+            .keys()
+            .find(|ty| !self.global_types.contains_key(*ty))
+        {
+            return Err(MigrationError::GlobalUnmapped(*ty));
+        }
+        let mut targets = bset! {};
+        for (from, to) in &self.global_types {
+            let Some(state) = original.global_types.get(from) else {
+                return Err(MigrationError::GlobalUnknown(*from));
+            };
+            let compatible = successor
+                .global_types
+                .get(to)
+                .is_some_and(|new| new.kind == state.kind && new.sem_id == state.sem_id);
+            if !compatible {
+                return Err(MigrationError::GlobalIncompatible(*from, *to));
+            }
+            if !targets.insert(*to) {
+                return Err(MigrationError::GlobalRepeated(*to));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Contract migration node, upgrading the contract to the successor schema
+/// committed by its genesis.
+///
+/// Operations committing to the [`MigrationId`] of the node are validated
+/// against the successor schema, and so must be all their descendants.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Migration {
+    pub mapping: SchemaMigration,
+    pub schema: Schema,
+}
+
+impl StrictSerialize for Migration {}
+impl StrictDeserialize for Migration {}
+
+impl Migration {
+    pub fn new(mapping: SchemaMigration, schema: Schema) -> Self { Migration { mapping, schema } }
+
+    pub fn migration_id(&self) -> MigrationId { self.mapping.migration_id() }
+
+    /// Detects whether the operation with the given metadata commits to this
+    /// migration.
+    pub fn is_committed(&self, metadata: &Metadata) -> bool {
+        metadata
+            .get(&MIGRATION_TYPE)
+            .is_some_and(|value| value.as_slice() == self.migration_id().as_slice())
+    }
+
+    /// Verifies the migration of the contract with the given genesis from the
+    /// `original` schema to the schema carried by the node (see
+    /// [`SchemaMigration::verify`]).
+    pub fn verify(&self, genesis: &Genesis, original: &Schema) -> Result<(), MigrationError> {
+        self.mapping.verify(genesis, original, &self.schema)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use strict_encoding::StrictDumb;
+    use strict_types::SemId;
+
+    use super::*;
+    use crate::{FungibleType, GlobalStateSchema, Input, OwnedStateSchema};
+
+    #[test]
+    fn migration() {
+        let sem_id = SemId::strict_dumb();
+        let mut original = Schema::strict_dumb();
+        original.owned_types = tiny_bmap! {
+            1.into() => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit),
+            2.into() => OwnedStateSchema::Declarative,
+        };
+        original.global_types = tiny_bmap! { 1.into() => GlobalStateSchema::once(sem_id) };
+        let mut successor = original.clone();
+        successor.owned_types = tiny_bmap! {
+            10.into() => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit),
+            20.into() => OwnedStateSchema::Declarative,
+            30.into() => OwnedStateSchema::Declarative,
+        };
+        successor.global_types = tiny_bmap! { 1.into() => GlobalStateSchema::many(sem_id) };
+
+        let mut genesis = Genesis::strict_dumb();
+        genesis.schema_id = original.schema_id();
+        let mut migration = SchemaMigration {
+            from: original.schema_id(),
+            to: successor.schema_id(),
+            owned_types: tiny_bmap! { 1.into() => 10.into(), 2.into() => 20.into() },
+            global_types: tiny_bmap! { 1.into() => 1.into() },
+        };
+        assert_eq!(
+            migration.verify(&genesis, &original, &successor),
+            Err(MigrationError::NoSuccessor)
+        );
+
+        genesis.add_successor_schema(successor.schema_id()).unwrap();
+        assert_eq!(genesis.successor_schema(), Some(successor.schema_id()));
+        assert_eq!(migration.verify(&genesis, &original, &successor), Ok(()));
+        assert_eq!(migration.owned_type(2.into()), Some(20.into()));
+        assert_eq!(
+            migration.verify(&genesis, &successor, &successor),
+            Err(MigrationError::SourceMismatch {
+                expected: original.schema_id(),
+                actual: successor.schema_id()
+            })
+        );
+
+        migration.owned_types = tiny_bmap! { 1.into() => 10.into() };
+        assert_eq!(
+            migration.verify(&genesis, &original, &successor),
+            Err(MigrationError::OwnedUnmapped(2.into()))
+        );
+        migration.owned_types = tiny_bmap! { 1.into() => 20.into(), 2.into() => 10.into() };
+        assert_eq!(
+            migration.verify(&genesis, &original, &successor),
+            Err(MigrationError::OwnedIncompatible(1.into(), 20.into()))
+        );
+        migration.owned_types = tiny_bmap! {
+            1.into() => 10.into(),
+            2.into() => 20.into(),
+            3.into() => 30.into(),
+        };
+        assert_eq!(
+            migration.verify(&genesis, &original, &successor),
+            Err(MigrationError::OwnedUnknown(3.into()))
+        );
+        migration.owned_types = tiny_bmap! { 1.into() => 10.into(), 2.into() => 20.into() };
+        let node = Migration::new(migration.clone(), successor.clone());
+        let data = node.to_strict_serialized::<U32>().unwrap();
+        assert_eq!(Migration::from_strict_serialized::<U32>(data).unwrap(), node);
+        assert_eq!(node.migration_id(), migration.migration_id());
+        assert_eq!(node.verify(&genesis, &original), Ok(()));
+
+        migration.global_types = tiny_bmap! { 1.into() => 2.into() };
+        assert_ne!(migration.migration_id(), node.migration_id());
+        assert_eq!(
+            migration.verify(&genesis, &original, &successor),
+            Err(MigrationError::GlobalIncompatible(1.into(), 2.into()))
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let migration = SchemaMigration {
+            from: SchemaId::strict_dumb(),
+            to: SchemaId::strict_dumb(),
+            owned_types: tiny_bmap! { 1.into() => 10.into(), 2.into() => 2.into() },
+            global_types: none!(),
+        };
+        let original = OpId::from([1u8; 32]);
+        let migrated = OpId::from([2u8; 32]);
+        assert_eq!(
+            migration.alias(Opout::new(original, 1.into(), 3)),
+            Some(Opout::new(original, 10.into(), 3))
+        );
+        assert_eq!(migration.alias(Opout::new(original, 3.into(), 0)), None);
+
+        let inputs = Inputs::from(small_bset! {
+            Input::with(Opout::new(original, 1.into(), 0)),
+            Input::with(Opout::new(original, 2.into(), 1)),
+            Input::with(Opout::new(migrated, 10.into(), 0)),
+        });
+        assert_eq!(
+            migration.resolve_inputs(&inputs, |opid| opid == original),
+            Ok(vec![
+                Opout::new(original, 10.into(), 0),
+                Opout::new(original, 2.into(), 1),
+                Opout::new(migrated, 10.into(), 0),
+            ])
+        );
+
+        let unmapped = Opout::new(original, 3.into(), 0);
+        let inputs = Inputs::from(small_bset! { Input::with(unmapped) });
+        assert_eq!(
+            migration.resolve_inputs(&inputs, |opid| opid == original),
+            Err(MigrationError::InputUnmapped(unmapped))
+        );
+        assert_eq!(migration.resolve_inputs(&inputs, |_| false), Ok(vec![unmapped]));
+    }
+}
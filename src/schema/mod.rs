@@ -25,11 +25,34 @@ mod operations;
 mod schema;
 mod state;
 mod occurrences;
+mod iface;
+mod migration;
+mod known;
+mod lint;
+#[cfg(feature = "serde")]
+mod descriptor;
 
+#[cfg(feature = "serde")]
+pub use descriptor::{
+    DescriptorError, GlobalDescriptor, MetaDescriptor, OperationDescriptor, OwnedDescriptor,
+    SCHEMA_DESCRIPTOR_VERSION, SchemaDescriptor, ScriptDescriptor, ScriptKind, TypeBounds,
+};
+pub use iface::{Iface, IfaceError, IfaceId, StateFormat};
+pub use known::{KnownType, KnownTypeKind};
+pub use lint::{Lint, LintReport, LintSeverity};
+pub use migration::{
+    MIGRATION_TYPE, Migration, MigrationError, MigrationId, SUCCESSOR_SCHEMA_TYPE, SchemaMigration,
+};
 pub use occurrences::{Occurrences, OccurrencesMismatch};
 pub use operations::{
     AssignmentType, AssignmentsSchema, ExtensionSchema, GenesisSchema, GlobalSchema, MetaSchema,
     OpFullType, OpSchema, OpType, TransitionSchema, ValencySchema, ValencyType,
 };
-pub use schema::{ExtensionType, GlobalStateType, MetaType, Schema, SchemaId, TransitionType};
-pub use state::{FungibleType, GlobalStateSchema, MediaType, OwnedStateSchema};
+pub use schema::{
+    ExtensionType, GlobalStateType, MetaType, Schema, SchemaFlags, SchemaId, ScriptFingerprint,
+    TransitionType,
+};
+pub use state::{
+    FungibleType, GlobalStateKind, GlobalStateSchema, MediaType, OwnedStateSchema, RegistrySchema,
+    RicardianSchema, SupplySchema,
+};
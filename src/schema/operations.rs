@@ -40,9 +40,15 @@ use crate::LIB_NAME_RGB;
 )]
 pub struct AssignmentType(u16);
 impl AssignmentType {
+    /// Structured state holding the issuer authority of contracts with
+    /// [`crate::SchemaFlags::ISSUER_AUTHORITY`] flag set.
+    pub const ISSUER_AUTHORITY: Self = AssignmentType(u16::MAX);
+
     pub const fn with(ty: u16) -> Self { Self(ty) }
     #[inline]
     pub fn to_le_bytes(&self) -> [u8; 2] { self.0.to_le_bytes() }
+
+    pub fn is_issuer_authority(self) -> bool { self == Self::ISSUER_AUTHORITY }
 }
 
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Display)]
@@ -128,6 +134,17 @@ impl OpFullType {
     pub fn is_transition(self) -> bool { matches!(self, Self::StateTransition(_)) }
 
     pub fn is_extension(self) -> bool { matches!(self, Self::StateExtension(_)) }
+
+    /// Numeric code of the operation type used by AluVM scripts: zero for
+    /// genesis, `0x1_0000` plus the subtype for state transitions and
+    /// `0x2_0000` plus the subtype for state extensions.
+    pub fn vm_code(self) -> u32 {
+        match self {
+            OpFullType::Genesis => 0,
+            OpFullType::StateTransition(ty) => 0x1_0000 | ty.to_inner() as u32,
+            OpFullType::StateExtension(ty) => 0x2_0000 | ty.to_inner() as u32,
+        }
+    }
 }
 
 /// Trait defining common API for all operation type schemata
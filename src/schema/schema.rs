@@ -21,25 +21,30 @@
 // limitations under the License.
 
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
 use aluvm::library::LibId;
 use amplify::confinement::{TinyOrdMap, TinyOrdSet};
+use amplify::hex::ToHex;
 use amplify::{ByteArray, Bytes32};
 use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
-use commit_verify::{
-    CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, ReservedBytes, Sha256,
-};
+use commit_verify::{CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, Sha256};
 use strict_encoding::{
     StrictDecode, StrictDeserialize, StrictEncode, StrictSerialize, StrictType, TypeName,
 };
 use strict_types::SemId;
 
 use super::{
-    AssignmentType, ExtensionSchema, GenesisSchema, OwnedStateSchema, TransitionSchema, ValencyType,
+    AssignmentType, ExtensionSchema, GenesisSchema, KnownType, OwnedStateSchema, TransitionSchema,
+    ValencyType,
+};
+use crate::summary::fmt_list;
+use crate::{
+    Bulletproofs, Ffv, GlobalStateKind, GlobalStateSchema, Identity, LIB_NAME_RGB, Occurrences,
+    RangeProofScheme, RegistrySchema, RicardianSchema, SupplySchema, impl_serde_baid64,
 };
-use crate::{impl_serde_baid64, Ffv, GlobalStateSchema, Identity, Occurrences, LIB_NAME_RGB};
 
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Display)]
 #[wrapper(FromStr, LowerHex, UpperHex)]
@@ -107,6 +112,97 @@ impl TransitionType {
     pub fn is_blank(self) -> bool { self == Self::BLANK }
 }
 
+/// Schema flags selecting consensus rules which vary between schemata.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, From)]
+#[wrapper(LowerHex, UpperHex)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct SchemaFlags(u8);
+
+impl SchemaFlags {
+    /// Multiple fungible or declarative assignments of the same type to an
+    /// identical seal made by one operation, or by operations of the same
+    /// transition bundle, are valid and are treated as a single assignment:
+    /// fungible amounts are summed, and declarative rights are merged into a
+    /// single right. Thus, a right split which assigns its parts to the same
+    /// seal merges them back.
+    ///
+    /// Without this flag such assignments are rejected. Structured and
+    /// attachment state can't be merged and its duplicates are always
+    /// rejected.
+    ///
+    /// Seals are the same if they have the same concealed form, i.e. both the
+    /// same outpoint and the same blinding factor. Histories of the schemata
+    /// without this flag, which contain such duplicates, were accepted by
+    /// v0.11.0-beta.6 and are rejected now (see
+    /// [`crate::validation::ConsensusCheck::UniqueSeals`]).
+    pub const MERGE_DUPLICATE_SEALS: Self = SchemaFlags(0x01);
+
+    /// Contract has no inflation rights: the total outstanding supply of each
+    /// fungible state type is fixed at the amount issued in genesis and must
+    /// remain exactly equal to it after every state transition, i.e. neither
+    /// inflation nor burning are possible.
+    ///
+    /// The fungible state type and the global state type holding its issued
+    /// supply are declared in [`Schema::fixed_supply`]. Schema can't have
+    /// other fungible state types, and state extensions can't assign the
+    /// fixed-supply state. Zero issuance is supported by declaring zero supply
+    /// without assigning any state of the type in genesis.
+    pub const FIXED_SUPPLY: Self = SchemaFlags(0x02);
+
+    /// All fungible state must have revealed amounts: assignments with
+    /// confidential (pedersen-only) amounts are invalid, making the supply
+    /// and all the allocations deterministically public.
+    pub const REVEALED_AMOUNTS: Self = SchemaFlags(0x04);
+
+    /// Contract is a registry binding unique keys to values. The structured
+    /// state type holding the bindings is declared in [`Schema::registry`].
+    ///
+    /// Each key may be bound only once across the whole contract history,
+    /// i.e. only a single live binding may exist for a key. Operations may not
+    /// assign the same key twice, and a state transition spending a binding
+    /// must assign a new binding for the same key, updating its value: bindings
+    /// can't be removed. Binding state can't be concealed, since otherwise its
+    /// key can't be checked.
+    pub const KEY_REGISTRY: Self = SchemaFlags(0x08);
+
+    /// Contract has an issuer authority: structured state of the reserved
+    /// [`AssignmentType::ISSUER_AUTHORITY`] type, not declared by the schema,
+    /// holding the key (or other data) which validators of the contract
+    /// operations may reference, like an oracle or attestation key.
+    ///
+    /// Genesis may assign a single authority. The authority can be rotated
+    /// only by a state transition spending the current one, i.e. closing its
+    /// seal, and assigning exactly one new authority; no other operation may
+    /// assign it. The authority state can't be concealed.
+    pub const ISSUER_AUTHORITY: Self = SchemaFlags(0x10);
+
+    /// State transitions may assign expiring allocations, which must be
+    /// claimed by closing their seals within a witness mined up to some block
+    /// height; afterwards they are spendable only by closing a fallback seal,
+    /// returning the state to the sender. Expiries are committed with
+    /// [`crate::ALLOCATION_EXPIRY_TYPE`] metadata, not declared by the schema.
+    pub const EXPIRING_ALLOCATIONS: Self = SchemaFlags(0x20);
+
+    const KNOWN: u8 = 0x3F;
+
+    pub const fn empty() -> Self { SchemaFlags(0) }
+
+    pub const fn with(bits: u8) -> Self { SchemaFlags(bits) }
+
+    pub const fn bits(self) -> u8 { self.0 }
+
+    pub const fn contains(self, flags: Self) -> bool { self.0 & flags.0 == flags.0 }
+
+    /// Returns bits which don't correspond to any of the known flags.
+    pub const fn unknown_bits(self) -> u8 { self.0 & !Self::KNOWN }
+}
+
 /// Schema identifier.
 ///
 /// Schema identifier commits to all the schema data.
@@ -147,6 +243,53 @@ impl Display for SchemaId {
 
 impl_serde_baid64!(SchemaId);
 
+/// Fingerprint of the AluVM scripts used by a schema, committing to the
+/// validation entry points of all schema operations. Since an entry point
+/// includes the id of the library, which is a hash of the library bytecode,
+/// the fingerprint pins the exact code of all the schema validators.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(Self::to_hex)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ScriptFingerprint(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for ScriptFingerprint {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for ScriptFingerprint {
+    const TAG: &'static str = "urn:lnp-bp:rgb:schema-scripts#2024-07-16";
+}
+
+struct ScriptEntries<'schema>(&'schema Schema);
+
+impl CommitEncode for ScriptEntries<'_> {
+    type CommitmentId = ScriptFingerprint;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        let schema = self.0;
+        e.commit_to_option(&schema.genesis.validator);
+        for (ty, op) in &schema.extensions {
+            e.commit_to_serialized(ty);
+            e.commit_to_option(&op.validator);
+        }
+        for (ty, op) in &schema.transitions {
+            e.commit_to_serialized(ty);
+            e.commit_to_option(&op.validator);
+        }
+    }
+}
+
 #[derive(Clone, Eq, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
@@ -157,7 +300,7 @@ impl_serde_baid64!(SchemaId);
 )]
 pub struct Schema {
     pub ffv: Ffv,
-    pub flags: ReservedBytes<1, 0>,
+    pub flags: SchemaFlags,
 
     pub name: TypeName,
     pub timestamp: i64,
@@ -170,8 +313,13 @@ pub struct Schema {
     pub genesis: GenesisSchema,
     pub extensions: TinyOrdMap<ExtensionType, ExtensionSchema>,
     pub transitions: TinyOrdMap<TransitionType, TransitionSchema>,
-
-    pub reserved: ReservedBytes<8, 0>,
+    /// Fixed-supply state declaration, used only with
+    /// [`SchemaFlags::FIXED_SUPPLY`] flag set and zeroed otherwise. Occupies
+    /// previously reserved bytes.
+    pub fixed_supply: SupplySchema,
+    /// Key registry declaration, used only with [`SchemaFlags::KEY_REGISTRY`]
+    /// flag set and zeroed otherwise. Occupies previously reserved bytes.
+    pub registry: RegistrySchema,
 }
 
 impl CommitEncode for Schema {
@@ -192,8 +340,8 @@ impl CommitEncode for Schema {
         e.commit_to_serialized(&self.genesis);
         e.commit_to_map(&self.extensions);
         e.commit_to_map(&self.transitions);
-
-        e.commit_to_serialized(&self.reserved);
+        e.commit_to_serialized(&self.fixed_supply);
+        e.commit_to_serialized(&self.registry);
     }
 }
 
@@ -212,10 +360,69 @@ impl PartialOrd for Schema {
 impl StrictSerialize for Schema {}
 impl StrictDeserialize for Schema {}
 
+/// Displays a summary of the schema; the alternate format (`{:#}`) lists the
+/// declared types, limiting their number.
+impl Display for Schema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "schema {} {}: {} meta, {} global, {} owned, {} valency, {} extension and {} \
+             transition types",
+            self.schema_id(),
+            self.name,
+            self.meta_types.len(),
+            self.global_types.len(),
+            self.owned_types.len(),
+            self.valency_types.len(),
+            self.extensions.len(),
+            self.transitions.len()
+        )?;
+        if f.alternate() {
+            write!(f, "\n  flags {:#04x}", self.flags)?;
+            fmt_list(f, self.meta_types.keys().map(|ty| format!("meta {ty}")))?;
+            fmt_list(f, self.global_types.keys().map(|ty| format!("global {ty}")))?;
+            fmt_list(
+                f,
+                self.owned_types
+                    .iter()
+                    .map(|(ty, owned)| format!("owned {ty}: {}", owned.state_type())),
+            )?;
+            fmt_list(f, self.valency_types.iter().map(|ty| format!("valency {ty}")))?;
+            fmt_list(f, self.extensions.keys().map(|ty| format!("extension {ty}")))?;
+            fmt_list(f, self.transitions.keys().map(|ty| format!("transition {ty}")))?;
+        }
+        Ok(())
+    }
+}
+
 impl Schema {
     #[inline]
     pub fn schema_id(&self) -> SchemaId { self.commit_id() }
 
+    /// Returns global state type holding the Ricardian contract hash, if the
+    /// schema declares exactly one global state type of a Ricardian
+    /// [`GlobalStateKind`].
+    pub fn ricardian(&self) -> Option<RicardianSchema> {
+        let mut iter = self
+            .global_types
+            .iter()
+            .filter(|(_, schema)| schema.is_ricardian())
+            .map(|(ty, schema)| RicardianSchema {
+                global_type: *ty,
+                mutable: schema.kind == GlobalStateKind::MutableRicardian,
+            });
+        let ricardian = iter.next()?;
+        iter.next().is_none().then_some(ricardian)
+    }
+
+    /// Returns global state types holding [`crate::LocalizedText`] values.
+    pub fn localized(&self) -> impl Iterator<Item = GlobalStateType> + '_ {
+        self.global_types
+            .iter()
+            .filter(|(_, schema)| schema.is_localized())
+            .map(|(ty, _)| *ty)
+    }
+
     pub fn blank_transition(&self) -> TransitionSchema {
         let mut schema = TransitionSchema::default();
         for id in self.owned_types.keys() {
@@ -237,6 +444,12 @@ impl Schema {
             )
     }
 
+    /// Returns fingerprint of the validation entry points of the schema
+    /// operations, pinning the exact bytecode of the libraries used by the
+    /// schema. Schema id commits to the same entry points, thus schemata with
+    /// the same id always have the same script fingerprint.
+    pub fn script_fingerprint(&self) -> ScriptFingerprint { ScriptEntries(self).commit_id() }
+
     pub fn libs(&self) -> impl Iterator<Item = LibId> + '_ {
         self.genesis
             .validator
@@ -246,10 +459,43 @@ impl Schema {
             .chain(self.extensions.values().filter_map(|i| i.validator))
             .map(|site| site.lib)
     }
+
+    /// Range proof system used by the contracts of this schema to prove
+    /// ranges of concealed fungible amounts.
+    ///
+    /// All schemata currently use bulletproofs; future proof systems are to be
+    /// selected here basing on schema flags.
+    pub fn range_proof_scheme(&self) -> &'static dyn RangeProofScheme { &Bulletproofs }
+
+    /// Detects whether the schema uses the layout of v0.11.0-beta.6 release,
+    /// where the fast-forward version, flags, global state kinds and the
+    /// fixed-supply and key registry declarations are reserved and zeroed.
+    pub fn has_legacy_layout(&self) -> bool {
+        self.ffv == Ffv::V0 &&
+            self.flags == SchemaFlags::empty() &&
+            self.global_types
+                .values()
+                .all(|schema| schema.kind == GlobalStateKind::Plain) &&
+            self.fixed_supply == SupplySchema::default() &&
+            self.registry == RegistrySchema::default()
+    }
+
+    /// Metadata types having embedded semantics in the contracts under this
+    /// schema. Declaring such types by the schema would replace the embedded
+    /// validation of the metadata with the schema-defined one, thus the
+    /// schema must not declare them.
+    pub fn reserved_meta_types(&self) -> BTreeSet<MetaType> {
+        KnownType::ALL
+            .into_iter()
+            .filter(|known| known.is_active(self))
+            .filter_map(KnownType::meta_type)
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use aluvm::library::LibSite;
     use strict_encoding::StrictDumb;
 
     use super::*;
@@ -281,4 +527,49 @@ mod test {
         );
         assert_eq!(&format!("{less_dumb:-#}"), "RVY0MzUwLSc0dndqJzQ7di13OTR3J2UndkZWVkRocHE");
     }
+
+    #[test]
+    fn script_fingerprint() {
+        let mut schema = Schema::strict_dumb();
+        let fingerprint = schema.script_fingerprint();
+        let schema_id = schema.schema_id();
+
+        schema.name = tn!("Renamed");
+        assert_eq!(schema.script_fingerprint(), fingerprint);
+        assert_ne!(schema.schema_id(), schema_id);
+
+        let schema_id = schema.schema_id();
+        schema.genesis.validator = Some(LibSite::with(0, LibId::from_byte_array([1; 32])));
+        let pinned = schema.script_fingerprint();
+        assert_ne!(pinned, fingerprint);
+        assert_ne!(schema.schema_id(), schema_id);
+
+        schema.genesis.validator = Some(LibSite::with(1, LibId::from_byte_array([1; 32])));
+        assert_ne!(schema.script_fingerprint(), pinned);
+        schema.genesis.validator = Some(LibSite::with(0, LibId::from_byte_array([2; 32])));
+        assert_ne!(schema.script_fingerprint(), pinned);
+    }
+
+    #[test]
+    fn ricardian() {
+        let mut schema = Schema::strict_dumb();
+        assert_eq!(schema.ricardian(), None);
+
+        let ty = GlobalStateType::with(1);
+        let sem_id = SemId::strict_dumb();
+        schema.global_types = tiny_bmap! { ty => GlobalStateSchema::ricardian(sem_id, true) };
+        assert_eq!(
+            schema.ricardian(),
+            Some(RicardianSchema {
+                global_type: ty,
+                mutable: true
+            })
+        );
+
+        schema
+            .global_types
+            .insert(GlobalStateType::with(2), GlobalStateSchema::ricardian(sem_id, false))
+            .unwrap();
+        assert_eq!(schema.ricardian(), None);
+    }
 }
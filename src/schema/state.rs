@@ -20,11 +20,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use commit_verify::ReservedBytes;
 use strict_encoding::Primitive;
 use strict_types::SemId;
 
-use crate::{StateType, LIB_NAME_RGB};
+use crate::{AssignmentType, GlobalStateType, LIB_NAME_RGB, StateType};
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -118,9 +117,8 @@ pub enum FungibleType {
     serde(crate = "serde_crate", rename_all = "camelCase")
 )]
 pub struct GlobalStateSchema {
-    // TODO: Reserved for computed state (RCP240327A): will be used as an enum tag with computed
-    //       state having value 1.
-    pub reserved: ReservedBytes<1>,
+    // TODO: Computed state (RCP240327A) will be added as a new kind
+    pub kind: GlobalStateKind,
     pub sem_id: SemId,
     pub max_items: u16,
 }
@@ -128,7 +126,7 @@ pub struct GlobalStateSchema {
 impl GlobalStateSchema {
     pub fn once(sem_id: SemId) -> Self {
         GlobalStateSchema {
-            reserved: default!(),
+            kind: GlobalStateKind::Plain,
             sem_id,
             max_items: 1,
         }
@@ -136,9 +134,170 @@ impl GlobalStateSchema {
 
     pub fn many(sem_id: SemId) -> Self {
         GlobalStateSchema {
-            reserved: default!(),
+            kind: GlobalStateKind::Plain,
             sem_id,
             max_items: u16::MAX,
         }
     }
+
+    /// Declares numeric accumulator (see [`GlobalStateKind::Accumulator`]).
+    pub fn accumulator(sem_id: SemId) -> Self {
+        GlobalStateSchema {
+            kind: GlobalStateKind::Accumulator,
+            sem_id,
+            max_items: 1,
+        }
+    }
+
+    /// Declares global state holding the hash of the Ricardian contract text
+    /// (see [`GlobalStateKind::Ricardian`]).
+    pub fn ricardian(sem_id: SemId, mutable: bool) -> Self {
+        GlobalStateSchema {
+            kind: if mutable {
+                GlobalStateKind::MutableRicardian
+            } else {
+                GlobalStateKind::Ricardian
+            },
+            sem_id,
+            max_items: 1,
+        }
+    }
+
+    /// Declares global state holding localized texts (see
+    /// [`GlobalStateKind::Localized`]).
+    pub fn localized(sem_id: SemId, max_items: u16) -> Self {
+        GlobalStateSchema {
+            kind: GlobalStateKind::Localized,
+            sem_id,
+            max_items,
+        }
+    }
+
+    pub fn is_accumulator(&self) -> bool { self.kind == GlobalStateKind::Accumulator }
+
+    pub fn is_localized(&self) -> bool { self.kind == GlobalStateKind::Localized }
+
+    pub fn is_ricardian(&self) -> bool {
+        matches!(self.kind, GlobalStateKind::Ricardian | GlobalStateKind::MutableRicardian)
+    }
+}
+
+/// Kind of the global state, defining how the values assigned by different
+/// operations are combined into the contract state.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Display)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[repr(u8)]
+pub enum GlobalStateKind {
+    /// Each operation adds new values to the global state.
+    #[default]
+    #[display("plain")]
+    Plain = 0,
+
+    /// Contract-wide counter which is not owned by any party. Genesis may set
+    /// its initial value as a 64-bit unsigned little-endian integer, and each
+    /// of state extensions and transitions may change it by a 64-bit signed
+    /// little-endian delta. The accumulator value is the sum of the initial
+    /// value and all deltas, which must fit into a 64-bit unsigned integer.
+    ///
+    /// Each operation may assign at most one value of the accumulator type.
+    #[display("accumulator")]
+    Accumulator = 1,
+
+    /// Hash of the Ricardian contract text (see [`crate::RicardianHash`]),
+    /// which is set by genesis and can't be changed afterwards. A schema may
+    /// declare at most one global state type of a Ricardian kind.
+    #[display("ricardian")]
+    Ricardian = 2,
+
+    /// Hash of the Ricardian contract text, which state extensions and
+    /// transitions (for instance, renomination) are allowed to update after
+    /// the genesis.
+    #[display("mutableRicardian")]
+    MutableRicardian = 3,
+
+    /// Non-empty set of translations of a text (see
+    /// [`crate::LocalizedText`]).
+    #[display("localized")]
+    Localized = 4,
+}
+
+/// Global state type committing to the hash of the Ricardian contract text
+/// (see [`crate::RicardianHash`]), as declared by the schema with one of the
+/// Ricardian [`GlobalStateKind`]s (see [`crate::Schema::ricardian`]).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct RicardianSchema {
+    pub global_type: GlobalStateType,
+    /// Whether state extensions and transitions (for instance, renomination)
+    /// are allowed to update the contract text after the genesis.
+    pub mutable: bool,
+}
+
+/// Declares fungible state type with a fixed supply and global state type
+/// holding the supply issued in genesis as a 64-bit little-endian integer
+/// (see [`crate::SchemaFlags::FIXED_SUPPLY`]).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SupplySchema {
+    pub assignment_type: AssignmentType,
+    pub global_type: GlobalStateType,
+}
+
+impl Default for SupplySchema {
+    fn default() -> Self {
+        SupplySchema {
+            assignment_type: AssignmentType::with(0),
+            global_type: GlobalStateType::with(0),
+        }
+    }
+}
+
+/// Declares structured state type binding unique keys to values (see
+/// [`crate::SchemaFlags::KEY_REGISTRY`]). The key is a prefix of the
+/// serialized state data of `key_len` bytes; the rest of the data is the value
+/// bound to the key.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct RegistrySchema {
+    pub assignment_type: AssignmentType,
+    pub key_len: u16,
+}
+
+impl Default for RegistrySchema {
+    fn default() -> Self {
+        RegistrySchema {
+            assignment_type: AssignmentType::with(0),
+            key_len: 0,
+        }
+    }
+}
+
+impl RegistrySchema {
+    /// Extracts registry key from the serialized state data, returning `None`
+    /// if the data are shorter than the key.
+    pub fn key<'data>(&self, data: &'data [u8]) -> Option<&'data [u8]> {
+        data.get(..self.key_len as usize)
+    }
 }
@@ -20,22 +20,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
 pub use aluvm::stl::aluvm_stl;
 pub use bp::bc::stl::bp_tx_stl;
 pub use bp::stl::bp_core_stl;
 use commit_verify::stl::commit_verify_stl;
+use strict_encoding::{StrictType, TypeName};
 use strict_types::stl::{std_stl, strict_types_stl};
 use strict_types::typelib::LibBuilder;
-use strict_types::{CompileError, TypeLib};
+use strict_types::{CompileError, SemId, TypeLib};
 
+use crate::validation::{HeaderBundle, PaymentProof, SignedReceipt, SignedReport, WitnessProofs};
 use crate::{
-    ContractState, DbcProof, Extension, Genesis, OpCommitment, Schema, TransitionBundle,
-    XWitnessId, LIB_NAME_RGB,
+    AllocationExpiries, AssignmentType, BundleId, BundlePool, ContractId, ContractState, DbcProof,
+    Extension, ExtensionType, Genesis, GlobalState, GlobalStateType, Iface, Inputs, Invoice,
+    IssuanceSplit, LIB_NAME_RGB, LocalizedText, MetaProof, MetaType, Metadata, Migration,
+    MigrationId, OpCommitment, OpId, Opout, Redeemed, Schema, SchemaId, SchemaMigration,
+    Transition, TransitionBundle, TransitionType, Valencies, XWitnessId,
 };
 
 /// Strict types id for the library providing data types for RGB consensus.
 pub const LIB_ID_RGB: &str =
-    "stl:sqbS4Bea-l!IK7Dt-86Fkfgg-NOhi22w-S!kz5bC-l$99W!Y#bless-donald-poker";
+    "stl:cxTOwwge-rOWP8as-rRqUPKe-FoEid58-!cMWGGn-N8tn$6I#magnet-roman-split";
 
 fn _rgb_core_stl() -> Result<TypeLib, CompileError> {
     LibBuilder::new(libname!(LIB_NAME_RGB), tiny_bset! {
@@ -54,12 +62,101 @@ fn _rgb_core_stl() -> Result<TypeLib, CompileError> {
     .transpile::<Extension>()
     .transpile::<ContractState>()
     .transpile::<OpCommitment>()
+    .transpile::<Invoice>()
+    .transpile::<BundlePool>()
+    .transpile::<MetaProof>()
+    .transpile::<LocalizedText>()
+    .transpile::<Iface>()
+    .transpile::<SchemaMigration>()
+    .transpile::<Migration>()
+    .transpile::<MigrationId>()
+    .transpile::<IssuanceSplit>()
+    .transpile::<AllocationExpiries>()
+    .transpile::<WitnessProofs>()
+    .transpile::<HeaderBundle>()
+    .transpile::<SignedReport>()
+    .transpile::<PaymentProof>()
+    .transpile::<SignedReceipt>()
     .compile()
 }
 
 /// Generates strict type library providing data types for RGB consensus.
 pub fn rgb_core_stl() -> TypeLib { _rgb_core_stl().expect("invalid strict type RGB library") }
 
+fn cached_rgb_core_stl() -> &'static TypeLib {
+    static LIB: OnceLock<TypeLib> = OnceLock::new();
+    LIB.get_or_init(rgb_core_stl)
+}
+
+/// Returns semantic id of a type defined in the RGB strict type library, or
+/// `None` if the library doesn't define a type with the provided name.
+pub fn rgb_sem_id(name: &TypeName) -> Option<SemId> {
+    let ty = cached_rgb_core_stl().types.get(name)?;
+    Some(ty.sem_id_named(name))
+}
+
+/// Returns registry of all types defined in the RGB strict type library,
+/// mapping their names to semantic ids.
+///
+/// External verifiers may use the registry to check that the field-level
+/// encoding of the data they process matches the published library.
+pub fn rgb_sem_ids() -> BTreeMap<TypeName, SemId> {
+    cached_rgb_core_stl()
+        .types
+        .iter()
+        .map(|(name, ty)| (name.clone(), ty.sem_id_named(name)))
+        .collect()
+}
+
+/// Consensus data type which is a part of the RGB strict type library.
+pub trait ConsensusType: StrictType {
+    /// Returns name under which the type is defined in the RGB strict type
+    /// library.
+    fn type_name() -> TypeName { Self::strict_name().expect("consensus types must be named") }
+
+    /// Returns semantic id of the type from the RGB strict type library.
+    fn sem_id() -> SemId {
+        debug_assert_eq!(Self::STRICT_LIB_NAME, LIB_NAME_RGB);
+        rgb_sem_id(&Self::type_name()).expect("consensus type is absent in the RGB library")
+    }
+}
+
+macro_rules! impl_consensus_type {
+    ($($ty:ty),+ $(,)?) => {
+        $( impl ConsensusType for $ty {} )+
+    };
+}
+
+impl_consensus_type!(
+    Schema,
+    SchemaId,
+    Genesis,
+    Transition,
+    Extension,
+    TransitionBundle,
+    ContractId,
+    OpId,
+    BundleId,
+    Opout,
+    Inputs,
+    Redeemed,
+    Valencies,
+    Metadata,
+    GlobalState,
+    AssignmentType,
+    GlobalStateType,
+    MetaType,
+    TransitionType,
+    ExtensionType,
+    OpCommitment,
+    DbcProof,
+    XWitnessId,
+    SchemaMigration,
+    Migration,
+    MigrationId,
+    IssuanceSplit,
+);
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -69,4 +166,42 @@ mod test {
         let lib = rgb_core_stl();
         assert_eq!(lib.id().to_string(), LIB_ID_RGB);
     }
+
+    #[test]
+    fn sem_ids() {
+        let registry = rgb_sem_ids();
+        assert_eq!(registry.len(), rgb_core_stl().types.len());
+        assert_eq!(registry.get(&Schema::type_name()), Some(&Schema::sem_id()));
+        assert_eq!(registry.get(&Genesis::type_name()), Some(&Genesis::sem_id()));
+        assert_eq!(registry.get(&Transition::type_name()), Some(&Transition::sem_id()));
+        assert_ne!(Genesis::sem_id(), Extension::sem_id());
+        assert_eq!(rgb_sem_id(&tn!("Unknown")), None);
+
+        // every consensus type must be resolvable
+        let _ = (
+            SchemaId::sem_id(),
+            ContractId::sem_id(),
+            OpId::sem_id(),
+            BundleId::sem_id(),
+            Opout::sem_id(),
+            Inputs::sem_id(),
+            Redeemed::sem_id(),
+            Valencies::sem_id(),
+            Metadata::sem_id(),
+            GlobalState::sem_id(),
+            AssignmentType::sem_id(),
+            GlobalStateType::sem_id(),
+            MetaType::sem_id(),
+            TransitionType::sem_id(),
+            ExtensionType::sem_id(),
+            OpCommitment::sem_id(),
+            DbcProof::sem_id(),
+            XWitnessId::sem_id(),
+            SchemaMigration::sem_id(),
+            Migration::sem_id(),
+            MigrationId::sem_id(),
+            IssuanceSplit::sem_id(),
+            TransitionBundle::sem_id(),
+        );
+    }
 }
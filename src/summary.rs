@@ -0,0 +1,97 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Size-bounded formatting of large data structures.
+//!
+//! [`Display`] implementations of operations, bundles, schemata and
+//! consignments print a single-line summary by default. The alternate format
+//! (`{:#}`) adds details on separate lines, limiting both the number of listed
+//! items and the length of the binary data, such that the output stays usable
+//! in logs and error messages for structures of any size.
+
+use std::fmt::{self, Display, Formatter};
+
+use amplify::hex::ToHex;
+
+/// Maximal number of bytes of binary data printed in verbose format.
+pub const DISPLAY_HEX_LIMIT: usize = 32;
+
+/// Maximal number of items of a single list printed in verbose format.
+pub const DISPLAY_LIST_LIMIT: usize = 16;
+
+/// Hex representation of binary data, truncated to [`DISPLAY_HEX_LIMIT`]
+/// bytes.
+pub(crate) struct TruncatedHex<'data>(pub &'data [u8]);
+
+impl Display for TruncatedHex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.len() <= DISPLAY_HEX_LIMIT {
+            return f.write_str(&self.0.to_hex());
+        }
+        write!(f, "{}...({} bytes)", self.0[..DISPLAY_HEX_LIMIT].to_hex(), self.0.len())
+    }
+}
+
+/// Writes each item on a separate indented line, omitting items beyond
+/// [`DISPLAY_LIST_LIMIT`].
+pub(crate) fn fmt_list<T: Display>(
+    f: &mut Formatter<'_>,
+    items: impl IntoIterator<Item = T>,
+) -> fmt::Result {
+    let mut count = 0usize;
+    for item in items {
+        if count < DISPLAY_LIST_LIMIT {
+            write!(f, "\n  {item}")?;
+        }
+        count += 1;
+    }
+    if count > DISPLAY_LIST_LIMIT {
+        write!(f, "\n  ... and {} more", count - DISPLAY_LIST_LIMIT)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct List(usize);
+
+    impl Display for List {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            f.write_str("list")?;
+            fmt_list(f, 0..self.0)
+        }
+    }
+
+    #[test]
+    fn truncation() {
+        assert_eq!(TruncatedHex(&[0xAB; 2]).to_string(), "abab");
+        let long = TruncatedHex(&[0x00; 100]).to_string();
+        assert_eq!(long, format!("{}...(100 bytes)", "00".repeat(DISPLAY_HEX_LIMIT)));
+
+        assert_eq!(List(2).to_string(), "list\n  0\n  1");
+        let long = List(100).to_string();
+        assert_eq!(long.lines().count(), DISPLAY_LIST_LIMIT + 2);
+        assert!(long.ends_with("\n  15\n  ... and 84 more"));
+    }
+}
@@ -0,0 +1,635 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test framework for running contract scenarios end-to-end without any
+//! downstream stack: a mock blockchain resolving witness transactions and a
+//! scripted scenario runner issuing, transferring and validating a contract.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use aluvm::library::{Lib, LibId};
+use amplify::confinement::{Confined, SmallVec};
+use bp::dbc::Method;
+use bp::dbc::opret::OpretFirst;
+use bp::opcodes::OP_RETURN;
+use bp::seals::txout::CloseMethod;
+use bp::{LockTime, Outpoint, ScriptPubkey, SeqNo, Tx, TxIn, TxOut, TxVer, Txid, Vout};
+use commit_verify::mpc::{self, MerkleBlock, MerkleTree, MultiSource};
+use commit_verify::{CommitId, Conceal, EmbedCommitVerify, TryCommitVerify};
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+use strict_types::TypeSystem;
+
+use crate::validation::{
+    ConsignmentApi, ResolveWitness, Scripts, Status, Terminal, ValidationPolicy, Validator,
+    WitnessResolverError,
+};
+use crate::{
+    AssignRights, AssignmentType, Assignments, BundleId, BundlePool, ChainNet, ChainTrustAnchors,
+    DbcProof, EAnchor, ExposedSeal, Genesis, GenesisSeal, GraphSeal, Input, InputMap, Inputs,
+    LIB_NAME_RGB, Layer1, Migration, Occurrences, OpId, OpRef, Operation, Opout, OwnedStateSchema,
+    PoolError, Schema, SchemaFlags, SchemaId, SecretSeal, Transition, TransitionBundle,
+    TransitionSchema, TransitionType, TypedAssigns, VoidState, WitnessOrd, WitnessPos, XChain,
+    XWitnessId, XWitnessTx,
+};
+
+/// Timestamp of the first block produced by [`MockResolver`].
+pub const MOCK_CHAIN_START: i64 = 1_700_000_000;
+
+/// Assignment type of the right transferred in [`Scenario`].
+pub const RIGHTS: AssignmentType = AssignmentType::with(1);
+
+/// Transition type used by [`Scenario`] transfers.
+pub const TRANSFER: TransitionType = TransitionType::with(1);
+
+/// Mock blockchain, which keeps witness transactions either in mempool or in
+/// blocks and allows tests to control their mining status.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct MockResolver {
+    txs: BTreeMap<XWitnessId, XWitnessTx>,
+    mempool: BTreeSet<XWitnessId>,
+    blocks: Vec<BTreeSet<XWitnessId>>,
+}
+
+impl MockResolver {
+    pub fn new() -> Self { default!() }
+
+    /// Returns height of the chain tip; zero if no blocks were mined.
+    pub fn height(&self) -> u32 { self.blocks.len() as u32 }
+
+    /// Adds transaction to the mempool.
+    pub fn broadcast(&mut self, tx: XWitnessTx) -> XWitnessId {
+        let witness_id = tx.witness_id();
+        self.txs.insert(witness_id, tx);
+        self.mempool.insert(witness_id);
+        witness_id
+    }
+
+    /// Removes transaction from the mempool, making it unknown to the
+    /// resolver. Returns `false` if the transaction is not in the mempool.
+    pub fn evict(&mut self, witness_id: XWitnessId) -> bool {
+        if !self.mempool.remove(&witness_id) {
+            return false;
+        }
+        self.txs.remove(&witness_id);
+        true
+    }
+
+    /// Mines a new block confirming all transactions from the mempool and
+    /// returns its height.
+    pub fn mine(&mut self) -> u32 {
+        self.blocks.push(std::mem::take(&mut self.mempool));
+        self.height()
+    }
+
+    /// Disconnects `depth` blocks from the chain tip, returning their
+    /// transactions to the mempool.
+    pub fn reorg(&mut self, depth: u32) {
+        for _ in 0..depth {
+            let Some(block) = self.blocks.pop() else {
+                break;
+            };
+            self.mempool.extend(block);
+        }
+    }
+
+    /// Returns mining status of the transaction, if it is known.
+    pub fn witness_ord(&self, witness_id: XWitnessId) -> Option<WitnessOrd> {
+        if self.mempool.contains(&witness_id) {
+            return Some(WitnessOrd::OffChain);
+        }
+        let height = self
+            .blocks
+            .iter()
+            .position(|block| block.contains(&witness_id))? as u32 +
+            1;
+        let pos = WitnessPos::new(height, MOCK_CHAIN_START + height as i64 * 600)
+            .expect("valid mock block position");
+        Some(WitnessOrd::OnChain(pos))
+    }
+}
+
+impl ResolveWitness for MockResolver {
+    fn resolve_pub_witness(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<XWitnessTx, WitnessResolverError> {
+        self.txs
+            .get(&witness_id)
+            .cloned()
+            .ok_or(WitnessResolverError::Unknown(witness_id))
+    }
+
+    fn resolve_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        self.witness_ord(witness_id)
+            .ok_or(WitnessResolverError::Unknown(witness_id))
+    }
+
+    fn resolve_mempool(
+        &self,
+        layer1: Layer1,
+    ) -> Result<BTreeSet<XWitnessId>, WitnessResolverError> {
+        Ok(self
+            .mempool
+            .iter()
+            .filter(|witness_id| witness_id.layer1() == layer1)
+            .copied()
+            .collect())
+    }
+}
+
+/// In-memory consignment holding anchored transition bundles.
+#[derive(Clone, Debug)]
+pub struct MockConsignment {
+    schema: Schema,
+    types: TypeSystem,
+    scripts: Scripts,
+    genesis: Genesis,
+    genesis_id: OpId,
+    bundles: BundlePool,
+    op_bundles: BTreeMap<OpId, BundleId>,
+    terminals: BTreeSet<(BundleId, XChain<SecretSeal>)>,
+    migration: Option<Migration>,
+}
+
+impl MockConsignment {
+    pub fn new(schema: Schema, genesis: Genesis) -> Self {
+        MockConsignment {
+            schema,
+            types: none!(),
+            scripts: none!(),
+            genesis_id: genesis.id(),
+            genesis,
+            bundles: none!(),
+            op_bundles: empty!(),
+            terminals: empty!(),
+            migration: None,
+        }
+    }
+
+    /// Adds anchored bundle to the consignment.
+    pub fn add_bundle(
+        &mut self,
+        witness_id: XWitnessId,
+        anchor: EAnchor,
+        bundle: TransitionBundle,
+    ) -> Result<(), PoolError> {
+        let bundle_id = bundle.bundle_id();
+        for opid in bundle.known_transitions.keys() {
+            self.op_bundles.insert(*opid, bundle_id);
+        }
+        self.bundles.add(witness_id, anchor, bundle)
+    }
+
+    /// Returns pool of the anchored bundles of the consignment.
+    pub fn bundles(&self) -> &BundlePool { &self.bundles }
+
+    /// Removes transition from the index of the consignment operations while
+    /// keeping its bundle, making the transition absent from the consignment.
+    pub fn remove_operation(&mut self, opid: OpId) -> bool {
+        self.op_bundles.remove(&opid).is_some()
+    }
+
+    /// Adds script library to the consignment under a given id, which is not
+    /// checked to match the library, returning the library it replaces.
+    pub fn insert_lib(&mut self, lib_id: LibId, lib: Lib) -> Option<Lib> {
+        self.scripts
+            .insert(lib_id, lib)
+            .expect("too many script libraries")
+    }
+
+    /// Adds terminal seals to the consignment.
+    pub fn add_terminals(
+        &mut self,
+        terminals: impl IntoIterator<Item = (BundleId, XChain<SecretSeal>)>,
+    ) {
+        self.terminals.extend(terminals);
+    }
+
+    /// Replaces consignment terminals with a single terminal seal.
+    pub fn set_terminal(&mut self, bundle_id: BundleId, seal: XChain<SecretSeal>) {
+        self.terminals = bset! { (bundle_id, seal) };
+    }
+
+    /// Adds contract migration node to the consignment.
+    pub fn set_migration(&mut self, migration: Migration) { self.migration = Some(migration); }
+}
+
+impl ConsignmentApi for MockConsignment {
+    fn schema(&self) -> &Schema { &self.schema }
+
+    fn types(&self) -> &TypeSystem { &self.types }
+
+    fn scripts(&self) -> &Scripts { &self.scripts }
+
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>> {
+        if opid == self.genesis_id {
+            return Some(OpRef::Genesis(&self.genesis));
+        }
+        let bundle_id = self.op_bundles.get(&opid)?;
+        let bundle = self.bundles.bundle(*bundle_id)?;
+        bundle.known_transitions.get(&opid).map(OpRef::Transition)
+    }
+
+    fn genesis(&self) -> &Genesis { &self.genesis }
+
+    fn terminals<'iter>(&self) -> impl Iterator<Item = (BundleId, XChain<SecretSeal>)> + 'iter {
+        self.terminals.clone().into_iter()
+    }
+
+    fn bundle_ids<'iter>(&self) -> impl Iterator<Item = BundleId> + 'iter {
+        self.bundles.bundle_ids().collect::<Vec<_>>().into_iter()
+    }
+
+    fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+        self.bundles.bundle(bundle_id)
+    }
+
+    fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)> {
+        self.bundles.anchor(bundle_id)
+    }
+
+    fn op_witness_id(&self, opid: OpId) -> Option<XWitnessId> {
+        let bundle_id = self.op_bundles.get(&opid)?;
+        self.bundles
+            .anchor(*bundle_id)
+            .map(|(witness_id, _)| witness_id)
+    }
+
+    fn migration(&self) -> Option<&Migration> { self.migration.as_ref() }
+}
+
+/// Scripted scenario issuing a contract with a single non-fungible right and
+/// transferring it between owners, with each transfer anchored to a witness
+/// transaction broadcasted to a [`MockResolver`].
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    chain: MockResolver,
+    consignment: MockConsignment,
+    owner: (Opout, Outpoint),
+    blinding: u64,
+}
+
+impl Scenario {
+    /// Issues a contract on a given chain network, assigning the right to
+    /// an output of a funding transaction mined into the first block.
+    pub fn issue(chain_net: ChainNet) -> Self {
+        Self::issue_with_flags(chain_net, SchemaFlags::empty())
+    }
+
+    /// Issues a contract like [`Scenario::issue`] under a schema with given
+    /// flags.
+    pub fn issue_with_flags(chain_net: ChainNet, flags: SchemaFlags) -> Self {
+        Self::issue_with(chain_net, |schema| schema.flags = flags)
+    }
+
+    /// Issues a contract like [`Scenario::issue`], allowing to edit the
+    /// schema before the genesis gets committed to it.
+    pub fn issue_with(chain_net: ChainNet, edit: impl FnOnce(&mut Schema)) -> Self {
+        let mut chain = MockResolver::new();
+        let funding = witness_tx(Outpoint::new(Txid::strict_dumb(), 0), vec![]);
+        let funding_id = chain.broadcast(XChain::Bitcoin(funding.clone()));
+        chain.mine();
+
+        let mut schema = rights_schema();
+        edit(&mut schema);
+        let mut genesis = Genesis::strict_dumb();
+        genesis.schema_id = schema.schema_id();
+        genesis.chain_net = chain_net;
+        genesis.timestamp = MOCK_CHAIN_START;
+        genesis.assignments =
+            rights(GenesisSeal::with_blinding(Method::OpretFirst, funding.txid(), 0, 0));
+
+        let owner = Opout::new(genesis.id(), RIGHTS, 0);
+        let outpoint = Outpoint::new(*funding_id.as_reduced_unsafe(), 0);
+        Scenario {
+            chain,
+            consignment: MockConsignment::new(schema, genesis),
+            owner: (owner, outpoint),
+            blinding: 0,
+        }
+    }
+
+    pub fn chain(&self) -> &MockResolver { &self.chain }
+
+    pub fn chain_mut(&mut self) -> &mut MockResolver { &mut self.chain }
+
+    pub fn consignment(&self) -> &MockConsignment { &self.consignment }
+
+    pub fn consignment_mut(&mut self) -> &mut MockConsignment { &mut self.consignment }
+
+    /// Returns output of the operation owning the right, together with the
+    /// outpoint the right is assigned to.
+    pub fn owner(&self) -> (Opout, Outpoint) { self.owner }
+
+    /// Reassigns the right to a different outpoint without a state transition,
+    /// e.g. to the fallback seal of an expired allocation.
+    pub fn set_owner_outpoint(&mut self, outpoint: Outpoint) { self.owner.1 = outpoint; }
+
+    /// Commits the contract genesis to a successor schema, changing the
+    /// contract id. Must be called before any of the transfers.
+    pub fn add_successor_schema(&mut self, schema_id: SchemaId) {
+        let genesis = &mut self.consignment.genesis;
+        genesis
+            .add_successor_schema(schema_id)
+            .expect("genesis without successor schema");
+        self.consignment.genesis_id = genesis.id();
+        self.owner.0 = Opout::new(self.consignment.genesis_id, RIGHTS, 0);
+    }
+
+    /// Transfers the right to a new owner, broadcasting the witness
+    /// transaction to the mempool. Returns id of the state transition and
+    /// of its witness transaction.
+    pub fn transfer(&mut self) -> (OpId, XWitnessId) { self.transfer_with(|_| {}) }
+
+    /// Transfers the right like [`Scenario::transfer`], allowing to edit the
+    /// state transition before it gets committed to.
+    pub fn transfer_with(&mut self, edit: impl FnOnce(&mut Transition)) -> (OpId, XWitnessId) {
+        let (prev_out, outpoint) = self.owner;
+        self.blinding += 1;
+        let seal = GraphSeal::with_blinded_vout(Method::OpretFirst, 0, self.blinding);
+
+        let mut transition = Transition::strict_dumb();
+        transition.contract_id = self.consignment.genesis.contract_id();
+        transition.transition_type = TRANSFER;
+        transition.inputs = Inputs::from(small_bset! { Input::with(prev_out) });
+        transition.assignments = rights(seal);
+        edit(&mut transition);
+        let opid = transition.id();
+
+        let bundle = TransitionBundle {
+            close_method: CloseMethod::OpretFirst,
+            input_map: InputMap::with(Vout::from_u32(0), opid),
+            known_transitions: confined_bmap! { opid => transition },
+        };
+        let bundle_id = bundle.bundle_id();
+
+        let protocol_id = mpc::ProtocolId::from(self.consignment.genesis.contract_id());
+        let source = MultiSource {
+            messages: confined_bmap! { protocol_id => mpc::Message::from(bundle_id) },
+            static_entropy: Some(self.blinding),
+            ..default!()
+        };
+        let tree = MerkleTree::try_commit(&source).expect("single-message MPC tree");
+        let mpc_proof = MerkleBlock::from(&tree)
+            .to_merkle_proof(protocol_id)
+            .expect("protocol is in the tree");
+
+        let opret_out = TxOut::new(ScriptPubkey::from_unsafe(vec![OP_RETURN]), 0u64);
+        let mut tx = witness_tx(outpoint, vec![opret_out]);
+        let opret = <Tx as EmbedCommitVerify<mpc::Commitment, OpretFirst>>::embed_commit(
+            &mut tx,
+            &tree.commit_id(),
+        )
+        .expect("transaction has OP_RETURN output");
+        let anchor = EAnchor::new(mpc_proof, DbcProof::Opret(opret));
+
+        let witness_id = self.chain.broadcast(XChain::Bitcoin(tx.clone()));
+        self.consignment
+            .add_bundle(witness_id, anchor, bundle)
+            .expect("new bundle");
+        let seal = XChain::<GraphSeal>::Bitcoin(seal);
+        self.consignment.set_terminal(bundle_id, seal.conceal());
+        self.owner = (Opout::new(opid, RIGHTS, 0), Outpoint::new(tx.txid(), 0));
+        (opid, witness_id)
+    }
+
+    /// Validates consignment against the mock chain.
+    pub fn validate(&self, policy: ValidationPolicy) -> Status {
+        Validator::validate_with_policy(
+            &self.consignment,
+            &self.chain,
+            self.consignment.genesis.chain_net,
+            &ChainTrustAnchors::default(),
+            policy,
+        )
+    }
+
+    /// Freezes the scenario into a test vector.
+    pub fn to_vector(&self) -> TestVector {
+        let witnesses = self
+            .consignment
+            .bundles
+            .bundle_ids()
+            .filter_map(|bundle_id| self.consignment.bundles.anchor(bundle_id))
+            .filter_map(|(witness_id, _)| self.chain.txs.get(&witness_id).cloned());
+        let terminals = self
+            .consignment
+            .terminals
+            .iter()
+            .map(|(bundle_id, seal)| Terminal::new(*bundle_id, *seal));
+        TestVector {
+            schema: self.consignment.schema.clone(),
+            genesis: self.consignment.genesis.clone(),
+            bundles: self.consignment.bundles.clone(),
+            terminals: Confined::try_from_iter(terminals).expect("single terminal"),
+            witnesses: Confined::try_from_iter(witnesses).expect("small test scenario"),
+        }
+    }
+}
+
+/// Scenario frozen into a strict-encoded form. Test vectors stored in the
+/// repository detect changes breaking the decoding or validation of the
+/// contract data produced by earlier releases.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct TestVector {
+    pub schema: Schema,
+    pub genesis: Genesis,
+    pub bundles: BundlePool,
+    pub terminals: SmallVec<Terminal>,
+    pub witnesses: SmallVec<XWitnessTx>,
+}
+
+impl StrictSerialize for TestVector {}
+impl StrictDeserialize for TestVector {}
+
+impl TestVector {
+    /// Validates the vector against a mock chain, where each of the witness
+    /// transactions is mined into a separate block.
+    pub fn validate(&self, policy: ValidationPolicy) -> Status {
+        let mut chain = MockResolver::new();
+        for tx in &self.witnesses {
+            chain.broadcast(tx.clone());
+            chain.mine();
+        }
+        let mut consignment = MockConsignment::new(self.schema.clone(), self.genesis.clone());
+        for bundle_id in self.bundles.bundle_ids() {
+            let (Some(bundle), Some((witness_id, anchor))) =
+                (self.bundles.bundle(bundle_id), self.bundles.anchor(bundle_id))
+            else {
+                continue;
+            };
+            // Bundles which can't be added are reported by the validator as
+            // absent ones
+            let _ = consignment.add_bundle(witness_id, anchor.clone(), bundle.clone());
+        }
+        consignment.terminals = self
+            .terminals
+            .iter()
+            .map(|terminal| (terminal.bundle_id, terminal.seal))
+            .collect();
+        Validator::validate_with_policy(
+            &consignment,
+            &chain,
+            self.genesis.chain_net,
+            &ChainTrustAnchors::default(),
+            policy,
+        )
+    }
+}
+
+/// Scenarios frozen as test vectors by the `rgbcore-vectors` tool, named
+/// after the files they are stored in.
+pub fn vector_scenarios() -> Vec<(&'static str, Scenario)> {
+    let mut transfers = Scenario::issue(ChainNet::Regtest);
+    for _ in 0..3 {
+        transfers.transfer();
+        transfers.chain_mut().mine();
+    }
+    vec![("issue", Scenario::issue(ChainNet::Regtest)), ("transfers", transfers)]
+}
+
+fn rights_schema() -> Schema {
+    let mut schema = Schema::strict_dumb();
+    schema.owned_types = tiny_bmap! { RIGHTS => OwnedStateSchema::Declarative };
+    schema.genesis.assignments = tiny_bmap! { RIGHTS => Occurrences::Once };
+    schema.transitions = tiny_bmap! {
+        TRANSFER => TransitionSchema {
+            metadata: none!(),
+            globals: none!(),
+            inputs: tiny_bmap! { RIGHTS => Occurrences::Once },
+            assignments: tiny_bmap! { RIGHTS => Occurrences::Once },
+            valencies: none!(),
+            validator: None,
+        }
+    };
+    schema
+}
+
+fn rights<Seal: ExposedSeal>(seal: Seal) -> Assignments<Seal> {
+    Assignments::from(tiny_bmap! {
+        RIGHTS => TypedAssigns::Declarative(small_vec![AssignRights::revealed(
+            XChain::Bitcoin(seal),
+            VoidState::default()
+        )])
+    })
+}
+
+/// Constructs transaction spending a single outpoint into an output at
+/// index 0, followed by `extra` outputs.
+fn witness_tx(outpoint: Outpoint, extra: Vec<TxOut>) -> Tx {
+    let mut outputs = vec![TxOut::new(ScriptPubkey::p2wpkh([0x6c; 20]), 1000u64)];
+    outputs.extend(extra);
+    Tx {
+        version: TxVer::V2,
+        inputs: Confined::try_from(vec![TxIn {
+            prev_output: outpoint,
+            sig_script: none!(),
+            sequence: SeqNo::from_consensus_u32(0xFFFF_FFFF),
+            witness: none!(),
+        }])
+        .expect("single input"),
+        outputs: Confined::try_from(outputs).expect("few outputs"),
+        lock_time: LockTime::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+
+    use super::*;
+    use crate::file::{ArtifactType, decode_artifact};
+    use crate::validation::{Failure, Validity};
+
+    #[test]
+    fn mock_chain() {
+        let mut chain = MockResolver::new();
+        let tx = witness_tx(Outpoint::new(Txid::strict_dumb(), 0), vec![]);
+        let witness_id = chain.broadcast(XChain::Bitcoin(tx));
+        assert_eq!(chain.witness_ord(witness_id), Some(WitnessOrd::OffChain));
+
+        assert_eq!(chain.mine(), 1);
+        assert!(matches!(chain.witness_ord(witness_id), Some(WitnessOrd::OnChain(_))));
+        assert!(!chain.evict(witness_id));
+
+        chain.reorg(1);
+        assert_eq!(chain.height(), 0);
+        assert_eq!(chain.witness_ord(witness_id), Some(WitnessOrd::OffChain));
+        assert!(chain.evict(witness_id));
+        assert_eq!(chain.witness_ord(witness_id), None);
+        assert!(chain.resolve_pub_witness(witness_id).is_err());
+    }
+
+    #[test]
+    fn transfer_scenario() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        let (_, witness_id) = scenario.transfer();
+        let status = scenario.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+        assert_eq!(status.validity(), Validity::Valid);
+
+        let status = scenario.validate(ValidationPolicy::strict());
+        assert!(
+            status
+                .failures
+                .contains(&Failure::WitnessUnconfirmed(witness_id))
+        );
+
+        scenario.chain_mut().mine();
+        let status = scenario.validate(ValidationPolicy::strict());
+        assert_eq!(status.failures, vec![]);
+
+        scenario.chain_mut().reorg(1);
+        scenario.chain_mut().evict(witness_id);
+        let status = scenario.validate(ValidationPolicy::default());
+        assert!(
+            status
+                .failures
+                .contains(&Failure::SealNoWitnessTx(witness_id))
+        );
+    }
+
+    #[test]
+    fn frozen_vectors() {
+        const VECTORS: [(&str, &[u8]); 2] = [
+            ("issue", include_bytes!("../tests/data/issue.rgb")),
+            ("transfers", include_bytes!("../tests/data/transfers.rgb")),
+        ];
+        for (name, data) in VECTORS {
+            let (_, payload) = decode_artifact(data, Some(ArtifactType::Consignment)).unwrap();
+            let payload = Confined::try_from(payload.to_vec()).unwrap();
+            let vector = TestVector::from_strict_serialized::<U32>(payload.clone())
+                .unwrap_or_else(|err| panic!("vector {name} can't be decoded: {err}"));
+            assert_eq!(vector.to_strict_serialized::<U32>().unwrap(), payload, "vector {name}");
+
+            let status = vector.validate(ValidationPolicy::strict());
+            assert_eq!(status.failures, vec![], "vector {name}");
+            assert_eq!(status.validity(), Validity::Valid, "vector {name}");
+        }
+    }
+}
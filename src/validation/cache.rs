@@ -0,0 +1,203 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation cache shared between processes.
+//!
+//! The cache records ids of the operations which have passed validation
+//! against their schema and scripts as a part of a valid consignment. Since
+//! operation ids commit to the operation data and, through the inputs, to all
+//! of their ancestors, such operations don't need to be validated against the
+//! schema again. Seals, witnesses and anchors are always re-validated, since
+//! their validity depends on the state of the blockchain.
+//!
+//! The cache is bound to a schema and a validation policy, and is reset once
+//! used with other ones. It is stored as an RGB artifact file, which is
+//! replaced atomically and merged with the data stored by other processes.
+
+use std::path::Path;
+use std::{fs, io};
+
+use amplify::confinement::LargeOrdSet;
+use commit_verify::CommitId;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use super::{PolicyHash, ValidationPolicy};
+use crate::file::{ArtifactError, ArtifactType, load_file, save_file};
+use crate::{LIB_NAME_RGB, OpId, SchemaId};
+
+/// Set of operations known to be valid under a given schema and validation
+/// policy.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct ValidationCache {
+    schema_id: SchemaId,
+    policy_hash: PolicyHash,
+    validated: LargeOrdSet<OpId>,
+}
+
+impl StrictSerialize for ValidationCache {}
+impl StrictDeserialize for ValidationCache {}
+
+impl ValidationCache {
+    /// Constructs empty cache for the given schema and validation policy.
+    pub fn new(schema_id: SchemaId, policy: &ValidationPolicy) -> Self {
+        Self::with(schema_id, policy.commit_id())
+    }
+
+    fn with(schema_id: SchemaId, policy_hash: PolicyHash) -> Self {
+        ValidationCache {
+            schema_id,
+            policy_hash,
+            validated: none!(),
+        }
+    }
+
+    /// Loads the cache from a file. Returns an empty cache if the file
+    /// doesn't exist, or if it was stored for a different schema or policy.
+    pub fn load(
+        path: impl AsRef<Path>,
+        schema_id: SchemaId,
+        policy: &ValidationPolicy,
+    ) -> Result<Self, ArtifactError> {
+        Self::load_bound(path, schema_id, policy.commit_id())
+    }
+
+    fn load_bound(
+        path: impl AsRef<Path>,
+        schema_id: SchemaId,
+        policy_hash: PolicyHash,
+    ) -> Result<Self, ArtifactError> {
+        match load_file::<Self>(path, ArtifactType::ValidationCache) {
+            Ok(cache) if cache.schema_id == schema_id && cache.policy_hash == policy_hash => {
+                Ok(cache)
+            }
+            Ok(_) => Ok(Self::with(schema_id, policy_hash)),
+            Err(ArtifactError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(Self::with(schema_id, policy_hash))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Stores the cache to a file, merging it with the operations stored there
+    /// by other processes under the same schema and policy. The file is
+    /// replaced atomically, so concurrent readers never observe partially
+    /// written data.
+    pub fn store(&mut self, path: impl AsRef<Path>) -> Result<(), ArtifactError> {
+        let path = path.as_ref();
+        let stored = Self::load_bound(path, self.schema_id, self.policy_hash)?;
+        self.extend(stored.validated);
+        let tmp = path.with_extension("tmp");
+        save_file(&tmp, ArtifactType::ValidationCache, self)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Returns id of the schema the cache is bound to.
+    pub fn schema_id(&self) -> SchemaId { self.schema_id }
+
+    /// Returns hash of the validation policy the cache is bound to.
+    pub fn policy_hash(&self) -> PolicyHash { self.policy_hash }
+
+    /// Detects whether the cache can be used for the given schema and policy.
+    pub fn is_valid_for(&self, schema_id: SchemaId, policy: &ValidationPolicy) -> bool {
+        self.schema_id == schema_id && self.policy_hash == policy.commit_id()
+    }
+
+    /// Returns number of operations in the cache.
+    pub fn len(&self) -> usize { self.validated.len() }
+
+    /// Detects whether the cache is empty.
+    pub fn is_empty(&self) -> bool { self.validated.is_empty() }
+
+    /// Detects whether the operation is known to be valid.
+    pub fn contains(&self, opid: OpId) -> bool { self.validated.contains(&opid) }
+
+    /// Iterates over operations known to be valid.
+    pub fn iter(&self) -> impl Iterator<Item = OpId> + '_ { self.validated.iter().copied() }
+
+    /// Adds operations to the cache. Operations exceeding the cache capacity
+    /// are not added.
+    pub(super) fn extend(&mut self, validated: impl IntoIterator<Item = OpId>) {
+        for opid in validated {
+            if self.validated.push(opid).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::Scenario;
+    use crate::validation::{ConsignmentApi, Validator};
+    use crate::{ChainNet, ChainTrustAnchors, Operation};
+
+    #[test]
+    fn validation_cache() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let (opid, _) = scenario.transfer();
+        let schema_id = scenario.consignment().schema().schema_id();
+        let policy = ValidationPolicy::default();
+        let validate = |policy: ValidationPolicy, cache: &mut ValidationCache| {
+            Validator::validate_with_cache(
+                scenario.consignment(),
+                scenario.chain(),
+                ChainNet::Regtest,
+                &ChainTrustAnchors::default(),
+                policy,
+                cache,
+            )
+        };
+
+        let mut cache = ValidationCache::new(schema_id, &policy);
+        assert_eq!(validate(policy, &mut cache).failures, vec![]);
+        assert!(cache.contains(opid));
+        assert!(cache.contains(scenario.consignment().genesis().id()));
+        assert_eq!(validate(policy, &mut cache).failures, vec![]);
+        assert_eq!(cache.len(), 2);
+
+        let path =
+            std::env::temp_dir().join(format!("rgb-core-validation-{}.cache", std::process::id()));
+        cache.store(&path).unwrap();
+        let mut stored = ValidationCache::load(&path, schema_id, &policy).unwrap();
+        assert_eq!(stored, cache);
+        let strict = ValidationPolicy::strict();
+        assert!(
+            ValidationCache::load(&path, schema_id, &strict)
+                .unwrap()
+                .is_empty()
+        );
+
+        validate(strict, &mut stored);
+        assert!(stored.is_valid_for(schema_id, &strict));
+        assert!(!stored.is_valid_for(schema_id, &policy));
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            ValidationCache::load(&path, schema_id, &policy)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
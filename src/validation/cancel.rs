@@ -0,0 +1,115 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cooperative cancellation of the validation.
+//!
+//! Validator checks the cancellation token before validating each operation
+//! and each bundle, so long validations can be aborted from another thread
+//! without killing it. Cancelled validation returns status with the failures
+//! detected so far and [`super::Failure::ValidationCancelled`].
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Token checked by the validator to detect that the validation must be
+/// aborted.
+pub trait CancelToken: Send + Sync {
+    /// Returns whether the validation must be aborted.
+    fn is_cancelled(&self) -> bool;
+}
+
+/// Token which never cancels the validation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct NeverCancel;
+
+impl CancelToken for NeverCancel {
+    fn is_cancelled(&self) -> bool { false }
+}
+
+/// Flag which can be raised by another thread to cancel the validation.
+impl CancelToken for AtomicBool {
+    fn is_cancelled(&self) -> bool { self.load(Ordering::Relaxed) }
+}
+
+impl<T: CancelToken + ?Sized> CancelToken for Arc<T> {
+    fn is_cancelled(&self) -> bool { T::is_cancelled(self) }
+}
+
+impl<T: CancelToken + ?Sized> CancelToken for &T {
+    fn is_cancelled(&self) -> bool { T::is_cancelled(self) }
+}
+
+/// Deadline after which the validation is cancelled.
+///
+/// The deadline is checked only in between the operations, so the validation
+/// may run past it for the time needed to validate a single operation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// Constructs deadline at a given moment.
+    pub fn at(instant: Instant) -> Self { Deadline(instant) }
+
+    /// Constructs deadline after a given time from now.
+    pub fn after(timeout: Duration) -> Self { Deadline(Instant::now() + timeout) }
+}
+
+impl CancelToken for Deadline {
+    fn is_cancelled(&self) -> bool { Instant::now() >= self.0 }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::Scenario;
+    use crate::validation::{Failure, Validator, Validity};
+    use crate::{ChainNet, ChainTrustAnchors};
+
+    #[test]
+    fn cancellation() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        scenario.transfer();
+        let validate = |cancel: &dyn CancelToken| {
+            Validator::validate_with_cancel(
+                scenario.consignment(),
+                scenario.chain(),
+                ChainNet::Regtest,
+                &ChainTrustAnchors::default(),
+                default!(),
+                &cancel,
+            )
+        };
+
+        let status = validate(&Deadline::after(Duration::from_secs(3600)));
+        assert_eq!(status.failures, vec![]);
+
+        let cancelled = AtomicBool::new(true);
+        let status = validate(&cancelled);
+        assert_eq!(status.failures, vec![Failure::ValidationCancelled]);
+        assert_eq!(status.validity(), Validity::Invalid);
+
+        let status = validate(&Deadline::at(Instant::now()));
+        assert_eq!(status.failures, vec![Failure::ValidationCancelled]);
+    }
+}
@@ -24,39 +24,191 @@
 //! state transitions, extensions, genesis, outputs, assignments &
 //! single-use-seal data.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
 
 use aluvm::library::{Lib, LibId};
-use amplify::confinement::Confined;
+use amplify::confinement::{Confined, LargeOrdMap, SmallOrdMap, SmallOrdSet};
+use bp::secp256k1::rand::RngCore;
+use bp::secp256k1::rand::seq::IteratorRandom;
+use commit_verify::Conceal;
+use strict_encoding::{
+    DecodeError, ReadStruct, StrictDecode, StrictDeserialize, StrictDumb, StrictSerialize,
+    TypedRead,
+};
 use strict_types::TypeSystem;
 
+use super::{PaymentProof, PaymentProofError, WitnessProofs};
+use crate::summary::fmt_list;
 use crate::{
-    BundleId, EAnchor, Genesis, OpId, OpRef, Operation, Schema, SecretSeal, TransitionBundle,
-    XChain, XWitnessId,
+    BundleId, BundlePool, EAnchor, Extension, Genesis, GraphSeal, LIB_NAME_RGB, MergeReveal,
+    MergeRevealError, Migration, MigrationId, NodeStore, OpId, OpRef, Operation, PoolError, Schema,
+    SchemaId, SecretSeal, TransitionBundle, XChain, XOutputSeal, XWitnessId,
 };
 
 pub const CONSIGNMENT_MAX_LIBS: usize = 1024;
 
 pub type Scripts = Confined<BTreeMap<LibId, Lib>, 0, CONSIGNMENT_MAX_LIBS>;
 
+/// Consignment endpoint: a concealed seal receiving state from a bundle
+/// included into the consignment.
+///
+/// The terminal may contain a proposed reveal of the seal, which is filled in
+/// by the receiver once it has verified that the seal belongs to it (see
+/// [`ConsignmentApi::verify_terminals`]).
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Terminal {
+    pub bundle_id: BundleId,
+    pub seal: XChain<SecretSeal>,
+    pub reveal: Option<XChain<GraphSeal>>,
+}
+
+impl Terminal {
+    pub fn new(bundle_id: BundleId, seal: XChain<SecretSeal>) -> Self {
+        Terminal {
+            bundle_id,
+            seal,
+            reveal: None,
+        }
+    }
+
+    /// Adds proposed reveal to the terminal, checking that the revealed seal
+    /// matches the concealed one.
+    pub fn with_reveal(mut self, reveal: XChain<GraphSeal>) -> Result<Self, TerminalError> {
+        if reveal.conceal() != self.seal {
+            return Err(TerminalError::RevealMismatch(self.seal));
+        }
+        self.reveal = Some(reveal);
+        Ok(self)
+    }
+}
+
+/// Errors verifying consignment terminals against the receiver seals.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TerminalError {
+    /// consignment doesn't contain terminal for the seal {0} of the receiver.
+    NotPaid(XChain<SecretSeal>),
+
+    /// terminal bundle {0} is absent from the consignment.
+    BundleAbsent(BundleId),
+
+    /// terminal bundle {0} doesn't assign state to the seal {1}.
+    SealAbsent(BundleId, XChain<SecretSeal>),
+
+    /// revealed seal doesn't match terminal seal {0}.
+    RevealMismatch(XChain<SecretSeal>),
+}
+
+/// Seal which must be controlled by the receiver of a consignment for the
+/// transfer to be meaningful.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[display(inner)]
+pub enum RequiredSeal {
+    /// Blinded seal, which the receiver must be able to reveal.
+    #[from]
+    Blinded(XChain<SecretSeal>),
+
+    /// Seal revealed by the consignment, resolved into a transaction output.
+    #[from]
+    Output(XOutputSeal),
+}
+
+/// Summary of a consignment returned by [`ConsignmentApi::summary`]. The
+/// alternate format (`{:#}`) lists terminals and bundles with their witnesses,
+/// limiting their number.
+pub struct ConsignmentSummary<'consignment, C: ConsignmentApi>(&'consignment C);
+
+impl<C: ConsignmentApi> Display for ConsignmentSummary<'_, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let consignment = self.0;
+        write!(
+            f,
+            "consignment of contract {} under schema {}: {} bundles, {} terminals",
+            consignment.genesis().contract_id(),
+            consignment.schema().schema_id(),
+            consignment.bundle_ids().count(),
+            consignment.terminals().count()
+        )?;
+        if f.alternate() {
+            fmt_list(
+                f,
+                consignment
+                    .terminals()
+                    .map(|(bundle_id, seal)| format!("terminal {seal} in bundle {bundle_id}")),
+            )?;
+            fmt_list(
+                f,
+                consignment
+                    .bundle_ids()
+                    .map(|bundle_id| match consignment.anchor(bundle_id) {
+                        Some((witness_id, _)) => {
+                            format!("bundle {bundle_id} in witness {witness_id}")
+                        }
+                        None => format!("bundle {bundle_id} without anchor"),
+                    }),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Consignment wrapper checking that operations and bundles are returned
+/// under their real ids. Being a copyable reference, it returns data borrowed
+/// from the wrapped consignment rather than from the wrapper itself.
 pub struct CheckedConsignment<'consignment, C: ConsignmentApi>(&'consignment C);
 
+impl<C: ConsignmentApi> Clone for CheckedConsignment<'_, C> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<C: ConsignmentApi> Copy for CheckedConsignment<'_, C> {}
+
 impl<'consignment, C: ConsignmentApi> CheckedConsignment<'consignment, C> {
     pub fn new(consignment: &'consignment C) -> Self { Self(consignment) }
+
+    pub fn schema(&self) -> &'consignment Schema { self.0.schema() }
+
+    pub fn types(&self) -> &'consignment TypeSystem { self.0.types() }
+
+    pub fn operation(&self, opid: OpId) -> Option<OpRef<'consignment>> {
+        self.0.operation(opid).filter(|op| op.id() == opid)
+    }
+
+    pub fn genesis(&self) -> &'consignment Genesis { self.0.genesis() }
+
+    pub fn bundle(&self, bundle_id: BundleId) -> Option<&'consignment TransitionBundle> {
+        self.0
+            .bundle(bundle_id)
+            .filter(|b| b.bundle_id() == bundle_id)
+    }
+
+    pub fn migration(&self) -> Option<&'consignment Migration> { self.0.migration() }
+
+    pub fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &'consignment EAnchor)> {
+        self.0.anchor(bundle_id)
+    }
 }
 
 impl<'consignment, C: ConsignmentApi> ConsignmentApi for CheckedConsignment<'consignment, C> {
-    fn schema(&self) -> &Schema { self.0.schema() }
+    fn schema(&self) -> &Schema { CheckedConsignment::schema(self) }
 
-    fn types(&self) -> &TypeSystem { self.0.types() }
+    fn types(&self) -> &TypeSystem { CheckedConsignment::types(self) }
 
     fn scripts(&self) -> &Scripts { self.0.scripts() }
 
-    fn operation(&self, opid: OpId) -> Option<OpRef> {
-        self.0.operation(opid).filter(|op| op.id() == opid)
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>> {
+        CheckedConsignment::operation(self, opid)
     }
 
-    fn genesis(&self) -> &Genesis { self.0.genesis() }
+    fn genesis(&self) -> &Genesis { CheckedConsignment::genesis(self) }
 
     fn terminals<'iter>(&self) -> impl Iterator<Item = (BundleId, XChain<SecretSeal>)> + 'iter {
         self.0.terminals()
@@ -65,16 +217,377 @@ impl<'consignment, C: ConsignmentApi> ConsignmentApi for CheckedConsignment<'con
     fn bundle_ids<'iter>(&self) -> impl Iterator<Item = BundleId> + 'iter { self.0.bundle_ids() }
 
     fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
-        self.0
-            .bundle(bundle_id)
-            .filter(|b| b.bundle_id() == bundle_id)
+        CheckedConsignment::bundle(self, bundle_id)
     }
 
     fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)> {
-        self.0.anchor(bundle_id)
+        CheckedConsignment::anchor(self, bundle_id)
     }
 
     fn op_witness_id(&self, opid: OpId) -> Option<XWitnessId> { self.0.op_witness_id(opid) }
+
+    fn witness_proofs(&self) -> Option<&WitnessProofs> { self.0.witness_proofs() }
+
+    fn migration(&self) -> Option<&Migration> { CheckedConsignment::migration(self) }
+}
+
+/// Consignment backed by a [`NodeStore`], allowing to validate contract data
+/// kept by a stash without copying it into a dedicated container.
+pub struct StoredConsignment<'store, S: NodeStore> {
+    schema: Schema,
+    types: TypeSystem,
+    scripts: Scripts,
+    genesis: Genesis,
+    store: &'store S,
+    op_bundles: BTreeMap<OpId, BundleId>,
+    terminals: BTreeSet<(BundleId, XChain<SecretSeal>)>,
+}
+
+impl<'store, S: NodeStore> StoredConsignment<'store, S> {
+    /// Constructs consignment from the bundles of the genesis contract kept
+    /// in the store.
+    pub fn new(
+        schema: Schema,
+        types: TypeSystem,
+        scripts: Scripts,
+        genesis: Genesis,
+        store: &'store S,
+        terminals: impl IntoIterator<Item = (BundleId, XChain<SecretSeal>)>,
+    ) -> Self {
+        let contract_id = genesis.contract_id();
+        let mut op_bundles = BTreeMap::new();
+        for bundle_id in store.contract_bundle_ids(contract_id) {
+            let Some(bundle) = store.bundle(bundle_id) else {
+                continue;
+            };
+            for opid in bundle.known_transitions.keys() {
+                op_bundles.insert(*opid, bundle_id);
+            }
+        }
+        StoredConsignment {
+            schema,
+            types,
+            scripts,
+            genesis,
+            store,
+            op_bundles,
+            terminals: terminals.into_iter().collect(),
+        }
+    }
+
+    /// Adds up to `count` decoy terminals (see
+    /// [`ConsignmentApi::decoy_terminals`]).
+    pub fn with_decoys(mut self, count: usize, rng: &mut impl RngCore) -> Self {
+        let decoys = self.decoy_terminals(count, rng);
+        self.terminals.extend(decoys);
+        self
+    }
+}
+
+impl<'store, S: NodeStore> ConsignmentApi for StoredConsignment<'store, S> {
+    fn schema(&self) -> &Schema { &self.schema }
+
+    fn types(&self) -> &TypeSystem { &self.types }
+
+    fn scripts(&self) -> &Scripts { &self.scripts }
+
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>> {
+        if opid == self.genesis.cached_id() {
+            return Some(OpRef::Genesis(&self.genesis));
+        }
+        let bundle_id = self.op_bundles.get(&opid)?;
+        let bundle = self.store.bundle(*bundle_id)?;
+        bundle.known_transitions.get(&opid).map(OpRef::Transition)
+    }
+
+    fn genesis(&self) -> &Genesis { &self.genesis }
+
+    fn terminals<'iter>(&self) -> impl Iterator<Item = (BundleId, XChain<SecretSeal>)> + 'iter {
+        self.terminals.clone().into_iter()
+    }
+
+    fn bundle_ids<'iter>(&self) -> impl Iterator<Item = BundleId> + 'iter {
+        self.op_bundles
+            .values()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+        self.store.bundle(bundle_id)
+    }
+
+    fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)> {
+        self.store.anchor(bundle_id)
+    }
+
+    fn op_witness_id(&self, opid: OpId) -> Option<XWitnessId> {
+        let bundle_id = self.op_bundles.get(&opid)?;
+        self.store
+            .anchor(*bundle_id)
+            .map(|(witness_id, _)| witness_id)
+    }
+}
+
+/// Errors constructing or merging consignments.
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ConsignmentError {
+    /// consignment has exceeded the maximal number of {0}.
+    Overflow(&'static str),
+
+    /// consignments are made under different schemata {0} and {1}.
+    SchemaMismatch(SchemaId, SchemaId),
+
+    /// consignments migrate the contract with different migrations {0} and {1}.
+    MigrationMismatch(MigrationId, MigrationId),
+
+    /// invalid consignment bundles. Details: {0}
+    #[from]
+    Pool(PoolError),
+
+    /// conflicting consignment data. Details: {0}
+    #[from]
+    Merge(MergeRevealError),
+}
+
+/// Consignment holding the contract history in memory.
+///
+/// Each bundle is stored once in a [`BundlePool`] under its id together with
+/// its anchor, and is referenced by that id from the terminals and from the
+/// operations it contains, even if the history contains the bundle on multiple
+/// paths of the operation graph. Consignments using the legacy layout, where
+/// bundles are repeated for each path, are read with
+/// [`BundlePool::from_legacy_serialized`] and [`Consignment::with_bundles`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictEncode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct Consignment {
+    schema: Schema,
+    types: TypeSystem,
+    scripts: Scripts,
+    genesis: Genesis,
+    extensions: LargeOrdMap<OpId, Extension>,
+    bundles: BundlePool,
+    terminals: SmallOrdMap<BundleId, SmallOrdSet<XChain<SecretSeal>>>,
+    migration: Option<Migration>,
+    #[strict_type(skip)]
+    op_bundles: BTreeMap<OpId, BundleId>,
+}
+
+impl StrictSerialize for Consignment {}
+impl StrictDeserialize for Consignment {}
+
+impl StrictDumb for Consignment {
+    fn strict_dumb() -> Self { Consignment::new(strict_dumb!(), none!(), none!(), strict_dumb!()) }
+}
+
+impl StrictDecode for Consignment {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let mut consignment = Consignment {
+                schema: r.read_field(fname!("schema"))?,
+                types: r.read_field(fname!("types"))?,
+                scripts: r.read_field(fname!("scripts"))?,
+                genesis: r.read_field(fname!("genesis"))?,
+                extensions: r.read_field(fname!("extensions"))?,
+                bundles: r.read_field(fname!("bundles"))?,
+                terminals: r.read_field(fname!("terminals"))?,
+                migration: r.read_field(fname!("migration"))?,
+                op_bundles: empty!(),
+            };
+            consignment.index();
+            Ok(consignment)
+        })
+    }
+}
+
+impl Consignment {
+    /// Constructs consignment containing only the contract genesis.
+    pub fn new(schema: Schema, types: TypeSystem, scripts: Scripts, genesis: Genesis) -> Self {
+        Consignment {
+            schema,
+            types,
+            scripts,
+            genesis,
+            extensions: none!(),
+            bundles: none!(),
+            terminals: none!(),
+            migration: None,
+            op_bundles: empty!(),
+        }
+    }
+
+    /// Replaces bundles of the consignment with the ones from the pool.
+    pub fn with_bundles(mut self, bundles: BundlePool) -> Self {
+        self.bundles = bundles;
+        self.index();
+        self
+    }
+
+    /// Returns pool of the anchored bundles of the consignment.
+    pub fn bundles(&self) -> &BundlePool { &self.bundles }
+
+    /// Adds state extension to the consignment.
+    pub fn add_extension(&mut self, extension: Extension) -> Result<(), ConsignmentError> {
+        self.extensions
+            .insert(extension.id(), extension)
+            .map_err(|_| ConsignmentError::Overflow("extensions"))?;
+        Ok(())
+    }
+
+    /// Adds anchored bundle to the consignment. If the bundle is already
+    /// present, merges revealed data from both versions of the bundle.
+    pub fn add_bundle(
+        &mut self,
+        witness_id: XWitnessId,
+        anchor: EAnchor,
+        bundle: TransitionBundle,
+    ) -> Result<(), ConsignmentError> {
+        let bundle_id = bundle.bundle_id();
+        for opid in bundle.known_transitions.keys() {
+            self.op_bundles.insert(*opid, bundle_id);
+        }
+        self.bundles.add(witness_id, anchor, bundle)?;
+        Ok(())
+    }
+
+    /// Adds terminal seal receiving state from a bundle of the consignment.
+    pub fn add_terminal(
+        &mut self,
+        bundle_id: BundleId,
+        seal: XChain<SecretSeal>,
+    ) -> Result<(), ConsignmentError> {
+        match self.terminals.get_mut(&bundle_id) {
+            Some(seals) => seals.push(seal),
+            None => self
+                .terminals
+                .insert(bundle_id, small_bset![seal])
+                .map(|_| ()),
+        }
+        .map_err(|_| ConsignmentError::Overflow("terminals"))
+    }
+
+    /// Adds contract migration node, which operations of the consignment
+    /// created under the successor schema commit to.
+    pub fn set_migration(&mut self, migration: Migration) { self.migration = Some(migration); }
+
+    /// Merges another consignment of the same contract into this one,
+    /// revealing data known to either of them: genesis, state extensions,
+    /// bundles with their anchors, terminals, contract migration, script
+    /// libraries and types. Fails if the consignments are made under
+    /// different schemata or migrations, for different contracts or contain
+    /// conflicting data, leaving this consignment unchanged.
+    pub fn merge(&mut self, other: Consignment) -> Result<(), ConsignmentError> {
+        let (schema_id, other_id) = (self.schema.schema_id(), other.schema.schema_id());
+        if schema_id != other_id {
+            return Err(ConsignmentError::SchemaMismatch(schema_id, other_id));
+        }
+        if let (Some(migration), Some(other)) = (&self.migration, &other.migration) {
+            let (migration_id, other_id) = (migration.migration_id(), other.migration_id());
+            if migration_id != other_id {
+                return Err(ConsignmentError::MigrationMismatch(migration_id, other_id));
+            }
+        }
+        let mut merged = self.clone();
+        merged.genesis.merge_reveal(other.genesis)?;
+        merged.migration = merged.migration.or(other.migration);
+        for (opid, extension) in other.extensions {
+            match merged.extensions.get_mut(&opid) {
+                Some(existing) => existing.merge_reveal(extension)?,
+                None => merged.add_extension(extension)?,
+            }
+        }
+        merged.bundles.merge(other.bundles)?;
+        merged.index();
+        for (bundle_id, seals) in other.terminals {
+            for seal in seals {
+                merged.add_terminal(bundle_id, seal)?;
+            }
+        }
+        for (lib_id, lib) in other.scripts {
+            if !merged.scripts.contains_key(&lib_id) {
+                merged
+                    .scripts
+                    .insert(lib_id, lib)
+                    .map_err(|_| ConsignmentError::Overflow("script libraries"))?;
+            }
+        }
+        merged
+            .types
+            .extend(other.types)
+            .map_err(|_| ConsignmentError::Overflow("types"))?;
+        *self = merged;
+        Ok(())
+    }
+
+    /// Rebuilds index of the operations contained in the pool bundles.
+    fn index(&mut self) {
+        self.op_bundles = self
+            .bundles
+            .bundle_ids()
+            .filter_map(|bundle_id| self.bundles.bundle(bundle_id))
+            .flat_map(|bundle| {
+                let bundle_id = bundle.bundle_id();
+                bundle
+                    .known_transitions
+                    .keys()
+                    .map(move |opid| (*opid, bundle_id))
+            })
+            .collect();
+    }
+}
+
+impl ConsignmentApi for Consignment {
+    fn schema(&self) -> &Schema { &self.schema }
+
+    fn types(&self) -> &TypeSystem { &self.types }
+
+    fn scripts(&self) -> &Scripts { &self.scripts }
+
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>> {
+        if opid == self.genesis.cached_id() {
+            return Some(OpRef::Genesis(&self.genesis));
+        }
+        if let Some(extension) = self.extensions.get(&opid) {
+            return Some(OpRef::Extension(extension));
+        }
+        let bundle_id = self.op_bundles.get(&opid)?;
+        let bundle = self.bundles.bundle(*bundle_id)?;
+        bundle.known_transitions.get(&opid).map(OpRef::Transition)
+    }
+
+    fn genesis(&self) -> &Genesis { &self.genesis }
+
+    fn terminals<'iter>(&self) -> impl Iterator<Item = (BundleId, XChain<SecretSeal>)> + 'iter {
+        self.terminals
+            .iter()
+            .flat_map(|(bundle_id, seals)| seals.iter().map(|seal| (*bundle_id, *seal)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn bundle_ids<'iter>(&self) -> impl Iterator<Item = BundleId> + 'iter {
+        self.bundles.bundle_ids().collect::<Vec<_>>().into_iter()
+    }
+
+    fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+        self.bundles.bundle(bundle_id)
+    }
+
+    fn anchor(&self, bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)> {
+        self.bundles.anchor(bundle_id)
+    }
+
+    fn op_witness_id(&self, opid: OpId) -> Option<XWitnessId> {
+        let bundle_id = self.op_bundles.get(&opid)?;
+        self.bundles
+            .anchor(*bundle_id)
+            .map(|(witness_id, _)| witness_id)
+    }
+
+    fn migration(&self) -> Option<&Migration> { self.migration.as_ref() }
 }
 
 /// Trait defining common data access API for all storage-related RGB structures
@@ -97,7 +610,7 @@ pub trait ConsignmentApi {
 
     /// Retrieves reference to an operation (genesis, state transition or state
     /// extension) matching the provided id, or `None` otherwise
-    fn operation(&self, opid: OpId) -> Option<OpRef>;
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>>;
 
     /// Contract genesis.
     fn genesis(&self) -> &Genesis;
@@ -124,4 +637,511 @@ pub trait ConsignmentApi {
 
     /// Returns witness id for a given operation.
     fn op_witness_id(&self, opid: OpId) -> Option<XWitnessId>;
+
+    /// Returns witness transactions with SPV proofs of their mining embedded
+    /// into the consignment, which can be verified with
+    /// [`super::SpvResolver`] without access to a full resolver.
+    fn witness_proofs(&self) -> Option<&WitnessProofs> { None }
+
+    /// Returns contract migration node, which the operations created under
+    /// the successor schema commit to (see [`Migration`]).
+    fn migration(&self) -> Option<&Migration> { None }
+
+    /// Returns size-bounded summary of the consignment for displaying in logs
+    /// and error messages.
+    fn summary(&self) -> ConsignmentSummary<'_, Self>
+    where Self: Sized {
+        ConsignmentSummary(self)
+    }
+
+    /// Returns bundles with their anchors which are present in this
+    /// consignment but absent from the `base`, or which contain state
+    /// transitions unknown to the `base`. This is the part of the history
+    /// which should be sent to a peer holding the `base` data, which adds it
+    /// to its own data with [`BundlePool::merge`].
+    fn diff(&self, base: &impl ConsignmentApi) -> Result<BundlePool, PoolError> {
+        let mut diff = BundlePool::new();
+        for bundle_id in self.bundle_ids() {
+            let Some(bundle) = self.bundle(bundle_id) else {
+                continue;
+            };
+            let known = base.anchor(bundle_id).is_some() &&
+                base.bundle(bundle_id).is_some_and(|known| {
+                    bundle
+                        .known_transitions
+                        .keys()
+                        .all(|opid| known.known_transitions.contains_key(opid))
+                });
+            if known {
+                continue;
+            }
+            let (witness_id, anchor) = self
+                .anchor(bundle_id)
+                .ok_or(PoolError::AnchorAbsent(bundle_id))?;
+            diff.add(witness_id, anchor.clone(), bundle.clone())?;
+        }
+        Ok(diff)
+    }
+
+    /// Selects up to `count` decoy terminals, which the sender may add to the
+    /// consignment such that a relay observing it can't tell how many real
+    /// recipients the transfer has.
+    ///
+    /// Decoys are picked with `rng` from the seals assigned by the terminal
+    /// bundles which are not terminals themselves, like the change seals of
+    /// the sender. Thus, they are indistinguishable from the real terminals
+    /// both in the consignment and during its validation, while their
+    /// receivers are already known to the sender.
+    fn decoy_terminals(
+        &self,
+        count: usize,
+        rng: &mut impl RngCore,
+    ) -> BTreeSet<(BundleId, XChain<SecretSeal>)> {
+        let terminals = self.terminals().collect::<BTreeSet<_>>();
+        let bundle_ids = terminals
+            .iter()
+            .map(|(bundle_id, _)| *bundle_id)
+            .collect::<BTreeSet<_>>();
+        let candidates = bundle_ids
+            .into_iter()
+            .filter_map(|bundle_id| self.bundle(bundle_id).map(|bundle| (bundle_id, bundle)))
+            .flat_map(|(bundle_id, bundle)| {
+                bundle
+                    .known_transitions
+                    .values()
+                    .flat_map(|transition| transition.assignments.values())
+                    .flat_map(|assigns| assigns.to_confidential_seals())
+                    .map(move |seal| (bundle_id, seal))
+            })
+            .filter(|terminal| !terminals.contains(terminal))
+            .collect::<BTreeSet<_>>();
+        candidates
+            .into_iter()
+            .choose_multiple(rng, count)
+            .into_iter()
+            .collect()
+    }
+
+    /// Verifies that the consignment assigns state to each of the seals
+    /// controlled by the receiver, returning the list of matching terminals
+    /// with seal reveals. Receivers must call this method before accepting the
+    /// transfer. Terminals of the other receivers, including decoy terminals
+    /// (see [`ConsignmentApi::decoy_terminals`]), are ignored.
+    fn verify_terminals(
+        &self,
+        my_seals: impl IntoIterator<Item = XChain<GraphSeal>>,
+    ) -> Result<Vec<Terminal>, TerminalError> {
+        let terminals = self.terminals().collect::<Vec<_>>();
+        let mut verified = vec![];
+        for reveal in my_seals {
+            let seal = reveal.conceal();
+            let (bundle_id, _) = terminals
+                .iter()
+                .find(|(_, s)| *s == seal)
+                .ok_or(TerminalError::NotPaid(seal))?;
+            let bundle = self
+                .bundle(*bundle_id)
+                .ok_or(TerminalError::BundleAbsent(*bundle_id))?;
+            bundle
+                .known_transitions
+                .values()
+                .flat_map(|transition| transition.assignments.values())
+                .find(|assigns| assigns.to_confidential_seals().contains(&seal))
+                .ok_or(TerminalError::SealAbsent(*bundle_id, seal))?;
+            verified.push(Terminal::new(*bundle_id, seal).with_reveal(reveal)?);
+        }
+        Ok(verified)
+    }
+
+    /// Extracts proof that the consignment pays to the `terminal` seal, which
+    /// may be handed by the payer to the receiver or an arbiter (see
+    /// [`super::verify_payment_proof`]).
+    fn payment_proof(&self, terminal: &Terminal) -> Result<PaymentProof, PaymentProofError> {
+        let bundle_id = terminal.bundle_id;
+        let bundle = self
+            .bundle(bundle_id)
+            .ok_or(PaymentProofError::BundleAbsent(bundle_id))?;
+        let (witness_id, anchor) = self
+            .anchor(bundle_id)
+            .ok_or(PaymentProofError::AnchorAbsent(bundle_id))?;
+        PaymentProof::with(terminal.seal, bundle, witness_id, anchor.clone())
+    }
+
+    /// Lists seals which must belong to the receiver for the transfer to be
+    /// meaningful. Terminal seals revealed by the consignment are resolved
+    /// into transaction outputs; the rest are reported in the blinded form.
+    ///
+    /// Wallets may check the list before accepting the transfer, reporting
+    /// consignments which do not pay to any of their outputs.
+    fn required_seal_control(&self) -> BTreeSet<RequiredSeal> {
+        self.terminals()
+            .map(|(bundle_id, seal)| {
+                let witness_id = self.anchor(bundle_id).map(|(witness_id, _)| witness_id);
+                self.bundle(bundle_id)
+                    .into_iter()
+                    .flat_map(|bundle| bundle.known_transitions.values())
+                    .flat_map(|transition| transition.assignments.values())
+                    .flat_map(|assigns| {
+                        (0..assigns.len_u16())
+                            .filter_map(|index| assigns.revealed_seal_at(index).ok().flatten())
+                    })
+                    .find(|reveal| reveal.conceal() == seal)
+                    .and_then(|reveal| reveal.resolve(witness_id).ok())
+                    .map(RequiredSeal::Output)
+                    .unwrap_or(RequiredSeal::Blinded(seal))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use bp::dbc::Method;
+    use bp::secp256k1::rand::thread_rng;
+
+    use super::*;
+    use crate::testing::{RIGHTS, Scenario, TRANSFER};
+    use crate::validation::{ValidationPolicy, Validator, Validity};
+    use crate::{
+        AssignRights, Assignments, ChainNet, ChainTrustAnchors, ContractHistory, ContractState,
+        Occurrences, OutputSeal, SchemaFlags, TypedAssigns, VoidState, WitnessOrd,
+    };
+
+    fn receiver(blinding: u64) -> XChain<GraphSeal> {
+        XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, blinding))
+    }
+
+    fn pooled(scenario: &Scenario) -> Consignment {
+        let mock = scenario.consignment();
+        let mut consignment =
+            Consignment::new(mock.schema().clone(), none!(), none!(), mock.genesis().clone())
+                .with_bundles(mock.bundles().clone());
+        for (bundle_id, seal) in mock.terminals() {
+            consignment.add_terminal(bundle_id, seal).unwrap();
+        }
+        consignment
+    }
+
+    #[test]
+    fn pooled_consignment() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        scenario.transfer();
+        let consignment = pooled(&scenario);
+        assert_eq!(consignment.bundles().len(), 2);
+
+        let data = consignment.to_strict_serialized::<U32>().unwrap();
+        let decoded = Consignment::from_strict_serialized::<U32>(data).unwrap();
+        assert_eq!(decoded, consignment);
+        let status = Validator::validate(&decoded, scenario.chain(), ChainNet::Regtest);
+        assert_eq!(status.failures, vec![]);
+        assert_eq!(status.validity(), Validity::Valid);
+
+        let legacy = consignment.bundles().to_legacy().unwrap();
+        let legacy = legacy.to_strict_serialized::<U32>().unwrap();
+        let bundles = BundlePool::from_legacy_serialized::<U32>(legacy).unwrap();
+        let mut upgraded = consignment.clone().with_bundles(bundles);
+        assert_eq!(upgraded, consignment);
+
+        let (bundle_id, _) = consignment.terminals().next().unwrap();
+        let (witness_id, anchor) = consignment.anchor(bundle_id).unwrap();
+        let bundle = consignment.bundle(bundle_id).unwrap().clone();
+        upgraded
+            .add_bundle(witness_id, anchor.clone(), bundle)
+            .unwrap();
+        assert_eq!(upgraded, consignment);
+    }
+
+    #[test]
+    fn diff() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        let base = scenario.clone();
+        scenario.transfer();
+        scenario.transfer();
+        let consignment = scenario.consignment();
+
+        let diff = consignment.diff(base.consignment()).unwrap();
+        assert_eq!(diff.len(), 2);
+        assert!(
+            diff.bundle_ids()
+                .all(|bundle_id| base.consignment().bundle(bundle_id).is_none())
+        );
+        assert!(consignment.diff(consignment).unwrap().is_empty());
+        assert!(base.consignment().diff(consignment).unwrap().is_empty());
+
+        let mut synced = base.consignment().bundles().clone();
+        synced.merge(diff).unwrap();
+        assert_eq!(&synced, consignment.bundles());
+    }
+
+    #[test]
+    fn merge() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        scenario.transfer();
+        scenario.transfer();
+        let mut full = pooled(&scenario);
+        full.add_extension(Extension::strict_dumb()).unwrap();
+        let partial = |bundle_ids: &[BundleId]| {
+            let mut bundles = BundlePool::new();
+            for bundle_id in bundle_ids {
+                let (witness_id, anchor) = full.anchor(*bundle_id).unwrap();
+                let bundle = full.bundle(*bundle_id).unwrap().clone();
+                bundles.add(witness_id, anchor.clone(), bundle).unwrap();
+            }
+            let mut partial = full.clone().with_bundles(bundles);
+            partial.terminals = none!();
+            partial
+        };
+        let ids = full.bundle_ids().collect::<Vec<_>>();
+        let mut first = partial(&ids[..2]);
+        let mut second = partial(&ids[1..]);
+        second.genesis = second.genesis.conceal();
+        second.terminals = full.terminals.clone();
+        let genesis_seal = |consignment: &Consignment| {
+            consignment.genesis.assignments[&RIGHTS]
+                .revealed_seal_at(0)
+                .unwrap()
+        };
+        assert_eq!(genesis_seal(&second), None);
+
+        let mut merged = first.clone();
+        merged.merge(second.clone()).unwrap();
+        assert_eq!(merged, full);
+        second.merge(first.clone()).unwrap();
+        assert_eq!(second, full);
+        assert_eq!(genesis_seal(&second), genesis_seal(&full));
+        let status = Validator::validate(&merged, scenario.chain(), ChainNet::Regtest);
+        assert_eq!(status.validity(), Validity::Valid);
+
+        let other = pooled(&Scenario::issue(ChainNet::Testnet));
+        assert!(matches!(
+            first.merge(other),
+            Err(ConsignmentError::Merge(MergeRevealError::OperationMismatch(..)))
+        ));
+        let flags = SchemaFlags::EXPIRING_ALLOCATIONS;
+        let other = pooled(&Scenario::issue_with_flags(ChainNet::Regtest, flags));
+        assert!(matches!(first.merge(other), Err(ConsignmentError::SchemaMismatch(..))));
+        assert_eq!(first, partial(&ids[..2]));
+    }
+
+    #[test]
+    fn terminal_reveal() {
+        let seal = receiver(1);
+        let terminal = Terminal::new(BundleId::strict_dumb(), seal.conceal());
+        assert_eq!(terminal.clone().with_reveal(seal).unwrap().reveal, Some(seal));
+        assert_eq!(
+            terminal.with_reveal(receiver(2)),
+            Err(TerminalError::RevealMismatch(seal.conceal()))
+        );
+    }
+
+    #[test]
+    fn verify_terminals() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        let consignment = scenario.consignment();
+        let bundle_id = consignment.bundle_ids().next().unwrap();
+        let seal = receiver(1);
+        assert_eq!(
+            consignment.verify_terminals([seal]),
+            Ok(vec![Terminal {
+                bundle_id,
+                seal: seal.conceal(),
+                reveal: Some(seal),
+            }])
+        );
+        let other = receiver(2);
+        assert_eq!(
+            consignment.verify_terminals([seal, other]),
+            Err(TerminalError::NotPaid(other.conceal()))
+        );
+    }
+
+    #[test]
+    fn verify_terminals_absent() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        let bundle_id = scenario.consignment().bundle_ids().next().unwrap();
+
+        let seal = receiver(1);
+        let unknown = BundleId::strict_dumb();
+        scenario
+            .consignment_mut()
+            .set_terminal(unknown, seal.conceal());
+        assert_eq!(
+            scenario.consignment().verify_terminals([seal]),
+            Err(TerminalError::BundleAbsent(unknown))
+        );
+
+        let other = receiver(2);
+        scenario
+            .consignment_mut()
+            .set_terminal(bundle_id, other.conceal());
+        assert_eq!(
+            scenario.consignment().verify_terminals([other]),
+            Err(TerminalError::SealAbsent(bundle_id, other.conceal()))
+        );
+    }
+
+    #[test]
+    fn required_seals() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let (_, witness_id) = scenario.transfer();
+        let (_, outpoint) = scenario.owner();
+        assert_eq!(*witness_id.as_reduced_unsafe(), outpoint.txid);
+        let output = XChain::Bitcoin(OutputSeal::new(Method::OpretFirst, outpoint));
+        assert_eq!(scenario.consignment().required_seal_control(), bset! {
+            RequiredSeal::Output(output)
+        });
+
+        let bundle_id = scenario.consignment().bundle_ids().next().unwrap();
+        let seal = GraphSeal::with_blinded_vout(Method::OpretFirst, 1, 0);
+        let seal = XChain::<GraphSeal>::Bitcoin(seal);
+        scenario
+            .consignment_mut()
+            .set_terminal(bundle_id, seal.conceal());
+        assert_eq!(scenario.consignment().required_seal_control(), bset! {
+            RequiredSeal::Blinded(seal.conceal())
+        });
+    }
+
+    #[test]
+    fn stored_consignment() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        scenario.chain_mut().mine();
+        scenario.transfer();
+
+        let mock = scenario.consignment();
+        let store = mock.bundles();
+        let consignment = StoredConsignment::new(
+            mock.schema().clone(),
+            none!(),
+            none!(),
+            mock.genesis().clone(),
+            store,
+            mock.terminals(),
+        );
+        assert_eq!(consignment.bundle_ids().count(), 2);
+        let status = Validator::validate_with_policy(
+            &consignment,
+            scenario.chain(),
+            ChainNet::Regtest,
+            &ChainTrustAnchors::default(),
+            ValidationPolicy::default(),
+        );
+        assert_eq!(status.failures, vec![]);
+        assert_eq!(status.validity(), Validity::Valid);
+
+        let genesis = mock.genesis();
+        let history = ContractHistory::with(genesis.schema_id, genesis.contract_id(), genesis);
+        let mut state = ContractState {
+            schema: mock.schema().clone(),
+            history,
+        };
+        let added = state.add_stored(store, |witness_id| {
+            scenario
+                .chain()
+                .witness_ord(witness_id)
+                .unwrap_or(WitnessOrd::OffChain)
+        });
+        assert_eq!(added, 2);
+        assert_eq!(state.rights().len(), 3);
+        assert_eq!(state.witnesses().len(), 2);
+    }
+
+    #[test]
+    fn display_summary() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let (opid, _) = scenario.transfer();
+        let consignment = scenario.consignment();
+
+        let genesis = consignment.genesis();
+        assert_eq!(genesis.to_string().lines().count(), 1);
+        assert!(
+            genesis
+                .to_string()
+                .starts_with(&format!("genesis {}", genesis.id()))
+        );
+        assert!(format!("{genesis:#}").contains("\n  assignment "));
+
+        let transition = consignment.operation(opid).unwrap();
+        assert!(
+            transition
+                .to_string()
+                .starts_with(&format!("transition {opid}"))
+        );
+        assert!(format!("{transition:#}").contains(&format!("\n  input {}", genesis.id())));
+
+        let bundle_id = consignment.bundle_ids().next().unwrap();
+        let bundle = consignment.bundle(bundle_id).unwrap();
+        assert_eq!(bundle.to_string().lines().count(), 1);
+        assert!(format!("{bundle:#}").ends_with(&format!("\n  {transition}")));
+
+        let schema = consignment.schema();
+        assert!(
+            schema
+                .to_string()
+                .starts_with(&format!("schema {}", schema.schema_id()))
+        );
+        assert!(format!("{schema:#}").contains("\n  owned "));
+
+        let summary = consignment.summary();
+        assert_eq!(summary.to_string().lines().count(), 1);
+        assert!(format!("{summary:#}").contains(&format!("\n  bundle {bundle_id} in witness ")));
+    }
+
+    #[test]
+    fn decoy_terminals() {
+        let mut scenario = Scenario::issue_with(ChainNet::Regtest, |schema| {
+            let transfer = schema.transitions.get_mut(&TRANSFER).unwrap();
+            transfer.assignments = tiny_bmap! { RIGHTS => Occurrences::OnceOrMore };
+        });
+        let change = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 1, 0xC0));
+        scenario.transfer_with(|transition| {
+            let seal = transition.assignments[&RIGHTS]
+                .revealed_seal_at(0)
+                .unwrap()
+                .unwrap();
+            transition.assignments = Assignments::from(tiny_bmap! {
+                RIGHTS => TypedAssigns::Declarative(small_vec![
+                    AssignRights::revealed(seal, VoidState::default()),
+                    AssignRights::revealed(change, VoidState::default())
+                ])
+            });
+        });
+        let status = scenario.validate(ValidationPolicy::default());
+        assert_eq!(status.validity(), Validity::Valid);
+
+        let bundle_id = scenario.consignment().bundle_ids().next().unwrap();
+        let decoys = scenario.consignment().decoy_terminals(5, &mut thread_rng());
+        assert_eq!(decoys, bset! { (bundle_id, change.conceal()) });
+        let mock = scenario.consignment();
+        let stored = StoredConsignment::new(
+            mock.schema().clone(),
+            none!(),
+            none!(),
+            mock.genesis().clone(),
+            mock.bundles(),
+            mock.terminals(),
+        )
+        .with_decoys(5, &mut thread_rng());
+        assert_eq!(stored.terminals().count(), 2);
+
+        scenario.consignment_mut().add_terminals(decoys);
+        assert_eq!(scenario.consignment().decoy_terminals(5, &mut thread_rng()), bset! {});
+        let decoyed = scenario.validate(ValidationPolicy::default());
+        assert_eq!(decoyed.validity(), Validity::Valid);
+        assert_eq!(decoyed.warnings, status.warnings);
+        let receiver = XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 1));
+        let verified = scenario.consignment().verify_terminals([receiver]).unwrap();
+        assert_eq!(verified, vec![
+            Terminal::new(bundle_id, receiver.conceal())
+                .with_reveal(receiver)
+                .unwrap()
+        ]);
+    }
 }
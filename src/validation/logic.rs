@@ -20,35 +20,93 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
+use aluvm::Vm;
 use aluvm::data::Number;
 use aluvm::isa::Instr;
 use aluvm::reg::{Reg32, RegA};
-use aluvm::Vm;
-use amplify::confinement::Confined;
 use amplify::Wrapper;
+use amplify::confinement::{Confined, U16};
+use strict_encoding::StrictDeserialize;
 use strict_types::TypeSystem;
 
+use crate::assignments::RevealedStateError;
 use crate::schema::{AssignmentsSchema, GlobalSchema, ValencySchema};
-use crate::validation::{CheckedConsignment, ConsignmentApi, Failure};
-use crate::vm::RgbIsa;
+use crate::validation::{
+    CheckedConsignment, ConsensusCheck, ConsensusRules, ConsignmentApi, Failure, Phase, Timer,
+};
+use crate::vm::{EmbeddedVm, Preset, RgbIsa, VmApi};
 use crate::{
-    validation, AssetTags, Assignments, AssignmentsRef, ContractId, ExposedSeal, Extension,
-    GlobalState, GlobalStateSchema, GlobalValues, GraphSeal, Inputs, MetaSchema, Metadata,
-    OpFullType, OpId, OpRef, Operation, Opout, OwnedStateSchema, Schema, StateType, Transition,
-    TypedAssigns, Valencies,
+    ALLOCATION_EXPIRY_TYPE, AssetTags, Assign, AssignmentType, Assignments, AssignmentsRef,
+    AssignsVisitor, ContractId, ExposedSeal, ExposedState, Extension, Ffv, GENESIS_SALT_TYPE,
+    Genesis, GlobalState, GlobalStateKind, GlobalStateSchema, GlobalValues, GraphSeal,
+    ISSUANCE_SPLIT_TYPE, Inputs, LocalizedText, MIGRATION_TYPE, MetaSchema, Metadata, Migration,
+    OpFullType, OpId, OpRef, Operation, Opout, OwnedStateSchema, RangeProofScheme, RicardianHash,
+    RicardianSchema, SUCCESSOR_SCHEMA_TYPE, Schema, SchemaFlags, StateType, SupplySchema,
+    Transition, TypedAssigns, Valencies, validation,
 };
 
+/// Validates each of the assignments of a given type against its schema.
+struct AssignsValidator<'schema> {
+    schema: &'schema OwnedStateSchema,
+    opid: OpId,
+    state_type: AssignmentType,
+    range_proofs: &'schema dyn RangeProofScheme,
+    types: &'schema TypeSystem,
+}
+
+impl<Seal: ExposedSeal> AssignsVisitor<Seal> for AssignsValidator<'_> {
+    type Output = validation::Status;
+
+    fn visit<State: ExposedState>(self, assigns: &[Assign<State, Seal>]) -> Self::Output {
+        let mut status = validation::Status::new();
+        for data in assigns {
+            status += self.schema.validate(
+                self.opid,
+                self.state_type,
+                data,
+                self.range_proofs,
+                self.types,
+            );
+        }
+        status
+    }
+}
+
 impl Schema {
     pub fn validate_state<'validator, C: ConsignmentApi>(
         &'validator self,
         consignment: &'validator CheckedConsignment<'_, C>,
         op: OpRef,
+        rules: ConsensusRules,
     ) -> validation::Status {
-        let opid = op.id();
+        let opid = op.cached_id();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "operation",
+            contract_id = %op.contract_id(),
+            %opid,
+            ty = %op.full_type()
+        )
+        .entered();
         let mut status = validation::Status::new();
 
+        if rules.requires(ConsensusCheck::LegacyFfv) {
+            let ffv = match op {
+                OpRef::Genesis(genesis) => genesis.ffv,
+                OpRef::Transition(transition) => transition.ffv,
+                OpRef::Extension(extension) => extension.ffv,
+            };
+            if ffv != Ffv::V0 {
+                return validation::Status::with_failure(Failure::OperationFfvUnsupported(
+                    opid, ffv,
+                ));
+            }
+        }
+        // Metadata types with embedded semantics are unknown to the legacy rules
+        let embedded_meta = !rules.requires(ConsensusCheck::DeclaredMetaTypes);
+
         let empty_assign_schema = AssignmentsSchema::default();
         let empty_valency_schema = ValencySchema::default();
         let blank_transition = self.blank_transition();
@@ -155,20 +213,58 @@ impl Schema {
             }
         };
 
+        if !op.is_canonical() {
+            status.add_warning(validation::Warning::NonCanonicalOperation(opid));
+        }
+
         // Validate type system
         status += self.validate_type_system();
-        status += self.validate_metadata(opid, op.metadata(), metadata_schema, consignment.types());
+        status += self.validate_metadata(
+            opid,
+            op.metadata(),
+            metadata_schema,
+            ty.is_none(),
+            embedded_meta,
+            consignment.types(),
+        );
         status +=
             self.validate_global_state(opid, op.globals(), global_schema, consignment.types());
-        let prev_state = if let OpRef::Transition(transition) = op {
-            let prev_state = extract_prev_state(consignment, opid, &transition.inputs, &mut status);
+        status += self.validate_ricardian(opid, op.globals(), ty.is_none());
+        if let OpRef::Genesis(genesis) = op {
+            if self.flags.contains(SchemaFlags::FIXED_SUPPLY) {
+                status += self.validate_issued_supply(opid, genesis);
+            }
+            if embedded_meta &&
+                genesis.metadata.contains_key(&ISSUANCE_SPLIT_TYPE) &&
+                !self.meta_types.contains_key(&ISSUANCE_SPLIT_TYPE)
+            {
+                status += self.validate_issuance_split(opid, genesis);
+            }
+        }
+        if self.flags.contains(SchemaFlags::EXPIRING_ALLOCATIONS) &&
+            op.metadata().contains_key(&ALLOCATION_EXPIRY_TYPE) &&
+            !self.meta_types.contains_key(&ALLOCATION_EXPIRY_TYPE)
+        {
+            status += self.validate_allocation_expiry(opid, op.as_transition());
+        }
+        let (prev_state, prev_types) = if let Some(transition) = op.as_transition() {
+            // Transitions under the successor schema see the outputs of the original
+            // operations under the aliases of their state types
+            let migration = consignment
+                .migration()
+                .filter(|migration| embedded_meta && migration.is_committed(op.metadata()));
+            let (prev_state, prev_types) =
+                extract_prev_state(consignment, opid, &transition.inputs, migration, &mut status);
             status += self.validate_prev_state(opid, &prev_state, owned_schema);
-            prev_state
+            (prev_state, prev_types)
         } else {
-            Assignments::default()
+            (Assignments::default(), BTreeMap::new())
         };
+        if self.flags.contains(SchemaFlags::ISSUER_AUTHORITY) {
+            status += self.validate_authority(opid, &op, &prev_state);
+        }
         let mut redeemed = Valencies::default();
-        if let OpRef::Extension(extension) = op {
+        if let Some(extension) = op.as_extension() {
             for valency in extension.redeemed.keys() {
                 redeemed.push(*valency).expect("same size");
             }
@@ -191,10 +287,15 @@ impl Schema {
             opid,
             &op,
             &prev_state,
+            &prev_types,
             &redeemed,
             &genesis.asset_tags,
         );
 
+        if op.as_transition().is_some() && self.flags.contains(SchemaFlags::FIXED_SUPPLY) {
+            status += self.validate_fixed_supply(&op_info);
+        }
+
         // We need to run scripts as the very last step, since before that
         // we need to make sure that the operation data match the schema, so
         // scripts are not required to validate the structure of the state
@@ -204,7 +305,17 @@ impl Schema {
             if let Some(ty) = ty {
                 vm.registers.set_n(RegA::A16, Reg32::Reg0, ty);
             }
-            if !vm.exec(validator, |id| scripts.get(&id), &op_info) {
+            #[cfg(feature = "tracing")]
+            let span = tracing::debug_span!("vm", entry = %validator).entered();
+            let timer = Timer::start();
+            let success = vm.exec(validator, |id| scripts.get(&id), &op_info);
+            timer.stop(&mut status, Phase::Vm);
+            #[cfg(feature = "tracing")]
+            {
+                tracing::trace!(success, "script completed");
+                span.exit();
+            }
+            if !success {
                 let error_code: Option<Number> = vm.registers.get_n(RegA::A8, Reg32::Reg0).into();
                 status.add_failure(validation::Failure::ScriptFailure(
                     opid,
@@ -233,6 +344,8 @@ impl Schema {
         opid: OpId,
         metadata: &Metadata,
         metadata_schema: &MetaSchema,
+        is_genesis: bool,
+        embedded: bool,
         types: &TypeSystem,
     ) -> validation::Status {
         let mut status = validation::Status::new();
@@ -243,6 +356,38 @@ impl Schema {
             .collect::<BTreeSet<_>>()
             .difference(metadata_schema.as_inner())
             .for_each(|type_id| {
+                if !embedded {
+                    status.add_failure(validation::Failure::SchemaUnknownMetaType(opid, *type_id));
+                    return;
+                }
+                // Genesis may contain uniqueness salt and successor schema id unless the schema
+                // uses their metadata types
+                if is_genesis && *type_id == GENESIS_SALT_TYPE {
+                    if metadata[type_id].len() != 8 {
+                        status.add_failure(validation::Failure::GenesisSaltInvalid(opid));
+                    }
+                    return;
+                }
+                if is_genesis && *type_id == ISSUANCE_SPLIT_TYPE {
+                    // Checked by `validate_issuance_split`
+                    return;
+                }
+                if *type_id == ALLOCATION_EXPIRY_TYPE &&
+                    self.flags.contains(SchemaFlags::EXPIRING_ALLOCATIONS)
+                {
+                    // Checked by `validate_allocation_expiry`
+                    return;
+                }
+                if !is_genesis && *type_id == MIGRATION_TYPE {
+                    // Checked by the validator against the contract migration
+                    return;
+                }
+                if is_genesis && *type_id == SUCCESSOR_SCHEMA_TYPE {
+                    if metadata[type_id].len() != 32 {
+                        status.add_failure(validation::Failure::GenesisSuccessorInvalid(opid));
+                    }
+                    return;
+                }
                 status.add_failure(validation::Failure::SchemaUnknownMetaType(opid, *type_id));
             });
 
@@ -268,6 +413,141 @@ impl Schema {
         status
     }
 
+    fn validate_ricardian(
+        &self,
+        opid: OpId,
+        global: &GlobalState,
+        is_genesis: bool,
+    ) -> validation::Status {
+        let mut status = validation::Status::new();
+
+        let Some(RicardianSchema {
+            global_type,
+            mutable,
+        }) = self.ricardian()
+        else {
+            return status;
+        };
+        let Some(values) = global.get(&global_type) else {
+            return status;
+        };
+        if !is_genesis && !mutable {
+            status.add_failure(validation::Failure::SchemaRicardianChanged(opid, global_type));
+        }
+        for value in values.iter() {
+            if RicardianHash::try_from(value).is_err() {
+                status.add_failure(validation::Failure::SchemaInvalidRicardian(opid, global_type));
+            }
+        }
+
+        status
+    }
+
+    fn validate_issued_supply(&self, opid: OpId, genesis: &Genesis) -> validation::Status {
+        let mut status = validation::Status::new();
+
+        let SupplySchema {
+            assignment_type,
+            global_type,
+        } = self.fixed_supply;
+        let supply = match genesis
+            .globals
+            .get(&global_type)
+            .map(|values| values.as_slice())
+        {
+            Some([value]) => <[u8; 8]>::try_from(value.as_ref()).ok(),
+            _ => None,
+        };
+        let Some(supply) = supply.map(u64::from_le_bytes) else {
+            status.add_failure(validation::Failure::IssuedSupplyInvalid(opid, global_type));
+            return status;
+        };
+        let issued = match genesis.assignments.get(&assignment_type) {
+            None => Some(0u128),
+            Some(assigns) => assigns.as_fungible().iter().try_fold(0u128, |sum, assign| {
+                Some(sum + assign.as_revealed_state()?.value.as_u64() as u128)
+            }),
+        };
+        if issued != Some(supply as u128) {
+            status.add_failure(validation::Failure::IssuedSupplyMismatch(
+                opid,
+                assignment_type,
+                supply,
+            ));
+        }
+
+        status
+    }
+
+    fn validate_issuance_split(&self, opid: OpId, genesis: &Genesis) -> validation::Status {
+        let mut status = validation::Status::new();
+
+        let Some(split) = genesis
+            .issuance_split()
+            .filter(|split| self.owned_types.contains_key(&split.assignment_type))
+        else {
+            status.add_failure(validation::Failure::IssuanceSplitInvalid(opid));
+            return status;
+        };
+        let seals = genesis
+            .assignments
+            .get(&split.assignment_type)
+            .map(TypedAssigns::to_confidential_seals)
+            .unwrap_or_default();
+        if seals.len() != split.issuers.len() ||
+            seals.iter().collect::<BTreeSet<_>>().len() != seals.len()
+        {
+            status.add_failure(validation::Failure::IssuanceSplitMismatch(
+                opid,
+                split.assignment_type,
+                split.issuers.len() as u8,
+            ));
+        }
+
+        status
+    }
+
+    fn validate_allocation_expiry(
+        &self,
+        opid: OpId,
+        transition: Option<&Transition>,
+    ) -> validation::Status {
+        let mut status = validation::Status::new();
+
+        let valid = transition
+            .and_then(|transition| {
+                let expiries = transition.allocation_expiries()?;
+                let count = transition
+                    .assignments
+                    .get(&expiries.assignment_type)?
+                    .len_u16();
+                Some(
+                    !expiries.expiries.is_empty() && expiries.expiries.keys().all(|no| *no < count),
+                )
+            })
+            .unwrap_or_default();
+        if !valid {
+            status.add_failure(validation::Failure::AllocationExpiryInvalid(opid));
+        }
+
+        status
+    }
+
+    fn validate_fixed_supply(&self, op_info: &OpInfo) -> validation::Status {
+        let mut status = validation::Status::new();
+
+        let assignment_type = self.fixed_supply.assignment_type;
+        if (op_info.prev_state.contains_key(&assignment_type) ||
+            op_info.owned_state.get(assignment_type).is_some()) &&
+            !EmbeddedVm.verify(Preset::Conserve(assignment_type), op_info)
+        {
+            status
+                .add_failure(validation::Failure::FixedSupplyChanged(op_info.id, assignment_type));
+        }
+
+        status
+    }
+
     fn validate_global_state(
         &self,
         opid: OpId,
@@ -296,9 +576,9 @@ impl Schema {
                 .unwrap_or_default();
 
             let GlobalStateSchema {
+                kind,
                 sem_id,
                 max_items,
-                reserved: _,
             } = self.global_types.get(type_id).expect(
                 "if the field were absent, the schema would not be able to pass the internal \
                  validation and we would not reach this point",
@@ -319,6 +599,17 @@ impl Schema {
 
             // Validating data types
             for data in set {
+                if *kind == GlobalStateKind::Accumulator && data.len() != 8 {
+                    status.add_failure(validation::Failure::AccumulatorInvalid(opid, *type_id));
+                }
+                if *kind == GlobalStateKind::Localized &&
+                    !matches!(
+                        LocalizedText::from_strict_serialized::<U16>(data.to_inner()),
+                        Ok(text) if !text.is_empty()
+                    )
+                {
+                    status.add_failure(validation::Failure::SchemaInvalidLocalized(opid, *type_id));
+                }
                 if types
                     .strict_deserialize_type(*sem_id, data.as_ref())
                     .is_err()
@@ -343,6 +634,7 @@ impl Schema {
 
         owned_state
             .keys()
+            .filter(|ty| !self.is_authority(**ty))
             .collect::<BTreeSet<_>>()
             .difference(&assign_schema.keys().collect())
             .for_each(|owned_type_id| {
@@ -371,6 +663,44 @@ impl Schema {
         status
     }
 
+    /// Checks whether the owned state type is the issuer authority, which is
+    /// valid without being declared by the schema.
+    fn is_authority(&self, ty: AssignmentType) -> bool {
+        ty.is_issuer_authority() && self.flags.contains(SchemaFlags::ISSUER_AUTHORITY)
+    }
+
+    fn validate_authority(
+        &self,
+        opid: OpId,
+        op: &OpRef,
+        prev_state: &Assignments<GraphSeal>,
+    ) -> validation::Status {
+        let mut status = validation::Status::new();
+        let ty = AssignmentType::ISSUER_AUTHORITY;
+
+        let spent = prev_state.get(&ty).map(TypedAssigns::len_u16).unwrap_or(0);
+        let assigned = op.assignments().get(ty);
+        if let Some(assigns) = &assigned {
+            if assigns.len_u16() != 1 || !matches!(assigns.as_structured_state_at(0), Ok(Some(_))) {
+                status.add_failure(validation::Failure::AuthorityInvalid(opid));
+            }
+        }
+        if spent > 1 {
+            status.add_failure(validation::Failure::AuthorityInvalid(opid));
+        }
+        if matches!(op, OpRef::Genesis(_)) {
+            return status;
+        }
+        if spent == 0 && assigned.is_some() {
+            status.add_failure(validation::Failure::AuthorityUnauthorized(opid));
+        }
+        if spent > 0 && assigned.is_none() {
+            status.add_failure(validation::Failure::AuthorityRemoved(opid));
+        }
+
+        status
+    }
+
     fn validate_redeemed(
         &self,
         id: OpId,
@@ -399,9 +729,11 @@ impl Schema {
         types: &TypeSystem,
     ) -> validation::Status {
         let mut status = validation::Status::new();
+        let range_proofs = self.range_proof_scheme();
 
         owned_state
             .keys()
+            .filter(|ty| !self.is_authority(**ty))
             .collect::<BTreeSet<_>>()
             .difference(&assign_schema.keys().collect())
             .for_each(|assignment_type_id| {
@@ -429,21 +761,21 @@ impl Schema {
                  validation and we would not reach this point",
             );
 
-            match owned_state.get(state_id) {
-                None => {}
-                Some(TypedAssigns::Declarative(set)) => set
-                    .iter()
-                    .for_each(|data| status += assignment.validate(id, *state_id, data, types)),
-                Some(TypedAssigns::Fungible(set)) => set
-                    .iter()
-                    .for_each(|data| status += assignment.validate(id, *state_id, data, types)),
-                Some(TypedAssigns::Structured(set)) => set
-                    .iter()
-                    .for_each(|data| status += assignment.validate(id, *state_id, data, types)),
-                Some(TypedAssigns::Attachment(set)) => set
-                    .iter()
-                    .for_each(|data| status += assignment.validate(id, *state_id, data, types)),
+            let Some(assigns) = owned_state.get(state_id) else {
+                continue;
             };
+            status += assigns.visit(AssignsValidator {
+                schema: assignment,
+                opid: id,
+                state_type: *state_id,
+                range_proofs,
+                types,
+            });
+            if let Err(RevealedStateError::ConfidentialForbidden(no)) =
+                assigns.as_revealed_state_values(self.flags)
+            {
+                status.add_failure(validation::Failure::ConfidentialAmount(id, *state_id, no));
+            }
         }
 
         status
@@ -477,6 +809,9 @@ pub struct OpInfo<'op> {
     pub asset_tags: &'op AssetTags,
     pub metadata: &'op Metadata,
     pub prev_state: &'op Assignments<GraphSeal>,
+    /// Types of the operations which have created each of the inputs, in the
+    /// same order as the inputs in `prev_state`.
+    pub prev_types: &'op BTreeMap<AssignmentType, Vec<OpFullType>>,
     pub owned_state: AssignmentsRef<'op>,
     pub redeemed: &'op Valencies,
     pub valencies: &'op Valencies,
@@ -489,6 +824,7 @@ impl<'op> OpInfo<'op> {
         id: OpId,
         op: &'op OpRef<'op>,
         prev_state: &'op Assignments<GraphSeal>,
+        prev_types: &'op BTreeMap<AssignmentType, Vec<OpFullType>>,
         redeemed: &'op Valencies,
         asset_tags: &'op AssetTags,
     ) -> Self {
@@ -499,6 +835,7 @@ impl<'op> OpInfo<'op> {
             asset_tags,
             metadata: op.metadata(),
             prev_state,
+            prev_types,
             owned_state: op.assignments(),
             redeemed,
             valencies: op.valencies(),
@@ -507,15 +844,39 @@ impl<'op> OpInfo<'op> {
     }
 }
 
+/// Extracts state spent by the transition inputs. If the transition is created
+/// under the successor schema of the contract `migration`, the state spent
+/// from the operations under the original schema is returned under the
+/// aliases of its types.
 fn extract_prev_state<C: ConsignmentApi>(
     consignment: &C,
     opid: OpId,
     inputs: &Inputs,
+    migration: Option<&Migration>,
     status: &mut validation::Status,
-) -> Assignments<GraphSeal> {
+) -> (Assignments<GraphSeal>, BTreeMap<AssignmentType, Vec<OpFullType>>) {
     let mut assignments = bmap! {};
-    for input in inputs {
+    let mut prev_types = BTreeMap::<_, Vec<_>>::new();
+    let aliases = match migration {
+        None => inputs.iter().map(|input| input.prev_out).collect(),
+        Some(migration) => {
+            let is_original = |prev_id| {
+                consignment
+                    .operation(prev_id)
+                    .is_some_and(|prev_op| !migration.is_committed(prev_op.metadata()))
+            };
+            match migration.mapping.resolve_inputs(inputs, is_original) {
+                Ok(aliases) => aliases,
+                Err(err) => {
+                    status.add_failure(Failure::MigrationInvalid(migration.migration_id(), err));
+                    return (Assignments::default(), prev_types);
+                }
+            }
+        }
+    };
+    for (input, alias) in inputs.iter().zip(aliases) {
         let Opout { op, ty, no } = input.prev_out;
+        let alias = alias.ty;
 
         let prev_op = match consignment.operation(op) {
             None => {
@@ -530,11 +891,15 @@ fn extract_prev_state<C: ConsignmentApi>(
             Some(TypedAssigns::Declarative(prev_assignments)) => {
                 if let Some(prev_assign) = prev_assignments.get(no) {
                     if let Some(typed_assigns) = assignments
-                        .entry(ty)
+                        .entry(alias)
                         .or_insert_with(|| TypedAssigns::Declarative(Default::default()))
                         .as_declarative_mut()
                     {
                         typed_assigns.push(prev_assign.clone()).expect("same size");
+                        prev_types
+                            .entry(alias)
+                            .or_default()
+                            .push(prev_op.full_type());
                     }
                 } else {
                     status.add_failure(validation::Failure::NoPrevOut(opid, input.prev_out));
@@ -543,11 +908,15 @@ fn extract_prev_state<C: ConsignmentApi>(
             Some(TypedAssigns::Fungible(prev_assignments)) => {
                 if let Some(prev_assign) = prev_assignments.get(no) {
                     if let Some(typed_assigns) = assignments
-                        .entry(ty)
+                        .entry(alias)
                         .or_insert_with(|| TypedAssigns::Fungible(Default::default()))
                         .as_fungible_mut()
                     {
                         typed_assigns.push(prev_assign.clone()).expect("same size");
+                        prev_types
+                            .entry(alias)
+                            .or_default()
+                            .push(prev_op.full_type());
                     }
                 } else {
                     status.add_failure(validation::Failure::NoPrevOut(opid, input.prev_out));
@@ -556,11 +925,15 @@ fn extract_prev_state<C: ConsignmentApi>(
             Some(TypedAssigns::Structured(prev_assignments)) => {
                 if let Some(prev_assign) = prev_assignments.get(no) {
                     if let Some(typed_assigns) = assignments
-                        .entry(ty)
+                        .entry(alias)
                         .or_insert_with(|| TypedAssigns::Structured(Default::default()))
                         .as_structured_mut()
                     {
                         typed_assigns.push(prev_assign.clone()).expect("same size");
+                        prev_types
+                            .entry(alias)
+                            .or_default()
+                            .push(prev_op.full_type());
                     }
                 } else {
                     status.add_failure(validation::Failure::NoPrevOut(opid, input.prev_out));
@@ -569,11 +942,15 @@ fn extract_prev_state<C: ConsignmentApi>(
             Some(TypedAssigns::Attachment(prev_assignments)) => {
                 if let Some(prev_assign) = prev_assignments.get(no) {
                     if let Some(typed_assigns) = assignments
-                        .entry(ty)
+                        .entry(alias)
                         .or_insert_with(|| TypedAssigns::Attachment(Default::default()))
                         .as_attachment_mut()
                     {
                         typed_assigns.push(prev_assign.clone()).expect("same size");
+                        prev_types
+                            .entry(alias)
+                            .or_default()
+                            .push(prev_op.full_type());
                     }
                 } else {
                     status.add_failure(validation::Failure::NoPrevOut(opid, input.prev_out));
@@ -586,7 +963,411 @@ fn extract_prev_state<C: ConsignmentApi>(
             }
         }
     }
-    Confined::try_from(assignments)
+    let assignments = Confined::try_from(assignments)
         .expect("collections is assembled from another collection with the same size requirements")
-        .into()
+        .into();
+    (assignments, prev_types)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::Bytes32;
+    use amplify::confinement::SmallVec;
+    use bp::Txid;
+    use bp::dbc::Method;
+    use chrono::DateTime;
+    use strict_encoding::StrictDumb;
+    use strict_types::SemId;
+
+    use super::*;
+    use crate::validation::ConsensusRules;
+    use crate::{
+        AssetTag, Assign, AssignmentType, BlindingFactor, DataState, ExtensionSchema,
+        ExtensionType, FungibleType, GenesisSeal, GlobalStateType, IssuanceSplit, Occurrences,
+        RevealedData, RevealedValue, VoidState, XChain,
+    };
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+    const SUPPLY: GlobalStateType = GlobalStateType::with(1);
+
+    fn fixed_supply_schema() -> Schema {
+        let mut schema = Schema::strict_dumb();
+        schema.flags = SchemaFlags::FIXED_SUPPLY;
+        schema.owned_types =
+            tiny_bmap! { ASSET => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit) };
+        schema.global_types =
+            tiny_bmap! { SUPPLY => GlobalStateSchema::once(SemId::strict_dumb()) };
+        schema.fixed_supply = SupplySchema {
+            assignment_type: ASSET,
+            global_type: SUPPLY,
+        };
+        schema
+    }
+
+    fn tag() -> AssetTag { AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0) }
+
+    fn amounts<Seal: ExposedSeal>(
+        values: &[(u64, &BlindingFactor)],
+        seal: impl Fn(u32) -> Seal,
+    ) -> TypedAssigns<Seal> {
+        let assigns = values
+            .iter()
+            .enumerate()
+            .map(|(no, (value, blinding))| {
+                let state = RevealedValue::with_blinding(*value, (*blinding).clone(), tag());
+                Assign::revealed(XChain::Bitcoin(seal(no as u32)), state)
+            })
+            .collect::<Vec<_>>();
+        TypedAssigns::Fungible(SmallVec::try_from(assigns).unwrap())
+    }
+
+    fn genesis_seal(vout: u32) -> GenesisSeal {
+        GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, vout as u64)
+    }
+
+    fn graph_seal(vout: u32) -> GraphSeal {
+        GraphSeal::with_blinded_vout(Method::OpretFirst, vout, vout as u64)
+    }
+
+    fn blinding(hex: &str) -> BlindingFactor { BlindingFactor::from_str(hex).unwrap() }
+
+    #[test]
+    fn fixed_supply_schema_verify() {
+        let failures = |schema: &Schema| {
+            schema
+                .verify(&TypeSystem::default())
+                .failures
+                .into_iter()
+                .filter(|failure| {
+                    matches!(
+                        failure,
+                        Failure::SchemaInflatable(_) | Failure::SchemaFixedSupplyInvalid(..)
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut schema = fixed_supply_schema();
+        assert_eq!(failures(&schema), vec![]);
+
+        let other = AssignmentType::with(2);
+        schema
+            .owned_types
+            .insert(other, OwnedStateSchema::Declarative)
+            .unwrap();
+        assert_eq!(failures(&schema), vec![]);
+        schema
+            .owned_types
+            .insert(other, OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit))
+            .unwrap();
+        assert_eq!(failures(&schema), vec![Failure::SchemaInflatable(other)]);
+        schema.owned_types.remove(&other).unwrap();
+
+        let mut extension = ExtensionSchema::strict_dumb();
+        extension.assignments = tiny_bmap! { ASSET => Occurrences::Once };
+        schema.extensions = tiny_bmap! { ExtensionType::with(1) => extension };
+        assert_eq!(failures(&schema), vec![Failure::SchemaInflatable(ASSET)]);
+        schema.extensions = none!();
+
+        schema.fixed_supply.global_type = GlobalStateType::with(2);
+        assert_eq!(failures(&schema), vec![Failure::SchemaFixedSupplyInvalid(
+            ASSET,
+            GlobalStateType::with(2)
+        )]);
+
+        schema.flags = SchemaFlags::empty();
+        assert_eq!(failures(&schema), vec![Failure::SchemaFixedSupplyInvalid(
+            ASSET,
+            GlobalStateType::with(2)
+        )]);
+        schema.fixed_supply = default!();
+        assert_eq!(failures(&schema), vec![]);
+    }
+
+    #[test]
+    fn reserved_meta_types_verify() {
+        let failures = |schema: &Schema, rules: ConsensusRules| {
+            schema
+                .verify_with_rules(&TypeSystem::default(), rules)
+                .failures
+                .into_iter()
+                .filter(|failure| matches!(failure, Failure::SchemaReservedMetaType(_)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut schema = Schema::strict_dumb();
+        schema.meta_types = tiny_bmap! {
+            GENESIS_SALT_TYPE => SemId::strict_dumb(),
+            ALLOCATION_EXPIRY_TYPE => SemId::strict_dumb(),
+        };
+        assert_eq!(failures(&schema, ConsensusRules::LATEST), vec![
+            Failure::SchemaReservedMetaType(GENESIS_SALT_TYPE)
+        ]);
+        assert_eq!(failures(&schema, ConsensusRules::V0_11Beta6), vec![]);
+
+        schema.flags = SchemaFlags::EXPIRING_ALLOCATIONS;
+        assert_eq!(failures(&schema, ConsensusRules::LATEST), vec![
+            Failure::SchemaReservedMetaType(ALLOCATION_EXPIRY_TYPE),
+            Failure::SchemaReservedMetaType(GENESIS_SALT_TYPE),
+        ]);
+    }
+
+    #[test]
+    fn issued_supply() {
+        let schema = fixed_supply_schema();
+        let zero = blinding("a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53");
+        let mut genesis = Genesis::strict_dumb();
+        let opid = genesis.id();
+        let set_supply = |genesis: &mut Genesis, supply: &[u8]| {
+            genesis.globals = none!();
+            genesis
+                .globals
+                .add_state(SUPPLY, DataState::from(SmallVec::try_from(supply.to_vec()).unwrap()))
+                .unwrap();
+        };
+        let failures = |genesis: &Genesis| schema.validate_issued_supply(opid, genesis).failures;
+
+        assert_eq!(failures(&genesis), vec![Failure::IssuedSupplyInvalid(opid, SUPPLY)]);
+        set_supply(&mut genesis, &[0; 4]);
+        assert_eq!(failures(&genesis), vec![Failure::IssuedSupplyInvalid(opid, SUPPLY)]);
+
+        // Zero issuance
+        set_supply(&mut genesis, &0u64.to_le_bytes());
+        assert_eq!(failures(&genesis), vec![]);
+
+        set_supply(&mut genesis, &100u64.to_le_bytes());
+        assert_eq!(failures(&genesis), vec![Failure::IssuedSupplyMismatch(opid, ASSET, 100)]);
+        genesis.assignments = Assignments::from(tiny_bmap! {
+            ASSET => amounts(&[(60, &zero), (40, &zero)], genesis_seal),
+        });
+        assert_eq!(failures(&genesis), vec![]);
+        genesis.assignments = Assignments::from(tiny_bmap! {
+            ASSET => amounts(&[(60, &zero), (41, &zero)], genesis_seal),
+        });
+        assert_eq!(failures(&genesis), vec![Failure::IssuedSupplyMismatch(opid, ASSET, 100)]);
+
+        let mut assigns = amounts(&[(60, &zero), (40, &zero)], genesis_seal);
+        let assign = &mut assigns.as_fungible_mut().unwrap()[1];
+        *assign = assign.conceal_state();
+        genesis.assignments = Assignments::from(tiny_bmap! { ASSET => assigns });
+        assert_eq!(failures(&genesis), vec![Failure::IssuedSupplyMismatch(opid, ASSET, 100)]);
+    }
+
+    #[test]
+    fn fixed_supply() {
+        let mut schema = fixed_supply_schema();
+        let input = blinding("a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53");
+        let change = blinding("0f13a3b3a1adf1bd4ba0d0da1d6a7f8fb0b3a3e6b03a0c5ce7e9af61a7cdd2a1");
+        let balance = BlindingFactor::zero_balanced([input.clone()], [change.clone()]).unwrap();
+        let prev_state = Assignments::from(tiny_bmap! {
+            ASSET => amounts(&[(10, &input)], graph_seal),
+        });
+        let asset_tags = AssetTags::from(tiny_bmap! { ASSET => tag() });
+        let redeemed = Valencies::default();
+        let prev_types = BTreeMap::new();
+
+        let check = |schema: &Schema, transition: &Transition, prev_state: &Assignments<_>| {
+            let op = OpRef::Transition(transition);
+            let op_info = OpInfo::with(
+                ContractId::strict_dumb(),
+                transition.id(),
+                &op,
+                prev_state,
+                &prev_types,
+                &redeemed,
+                &asset_tags,
+            );
+            schema.validate_fixed_supply(&op_info).failures
+        };
+
+        let no_state = Assignments::default();
+        let mut transition = Transition::strict_dumb();
+        assert_eq!(check(&schema, &transition, &no_state), vec![]);
+
+        transition.assignments = Assignments::from(tiny_bmap! {
+            ASSET => amounts(&[(4, &change), (6, &balance)], graph_seal),
+        });
+        assert_eq!(check(&schema, &transition, &prev_state), vec![]);
+        assert_eq!(check(&schema, &transition, &no_state), vec![Failure::FixedSupplyChanged(
+            transition.id(),
+            ASSET
+        )]);
+
+        transition.assignments = Assignments::from(tiny_bmap! {
+            ASSET => amounts(&[(4, &change), (7, &balance)], graph_seal),
+        });
+        assert_eq!(check(&schema, &transition, &prev_state), vec![Failure::FixedSupplyChanged(
+            transition.id(),
+            ASSET
+        )]);
+
+        // Burning is not possible either
+        transition.assignments = none!();
+        assert_eq!(check(&schema, &transition, &prev_state), vec![Failure::FixedSupplyChanged(
+            transition.id(),
+            ASSET
+        )]);
+
+        schema.fixed_supply.assignment_type = AssignmentType::with(2);
+        assert_eq!(check(&schema, &transition, &prev_state), vec![]);
+    }
+
+    #[test]
+    fn revealed_amounts() {
+        let mut schema = fixed_supply_schema();
+        let blinding = blinding("a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53");
+        let assign_schema = tiny_bmap! { ASSET => Occurrences::OnceOrMore };
+        let mut assigns = amounts(&[(60, &blinding), (40, &blinding)], graph_seal);
+        let failures = |schema: &Schema, assigns: &TypedAssigns<GraphSeal>| {
+            let owned_state = Assignments::from(tiny_bmap! { ASSET => assigns.clone() });
+            let status = schema.validate_owned_state(
+                OpId::strict_dumb(),
+                &owned_state,
+                &assign_schema,
+                &TypeSystem::default(),
+            );
+            status
+                .failures
+                .into_iter()
+                .filter(|failure| matches!(failure, Failure::ConfidentialAmount(..)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            assigns
+                .as_revealed_state_values(schema.flags)
+                .unwrap()
+                .len(),
+            2
+        );
+        let assign = &mut assigns.as_fungible_mut().unwrap()[1];
+        *assign = assign.conceal_state();
+        assert_eq!(
+            assigns.as_revealed_state_values(schema.flags),
+            Err(RevealedStateError::Withheld(1))
+        );
+        assert_eq!(failures(&schema, &assigns), vec![]);
+
+        schema.flags = SchemaFlags::REVEALED_AMOUNTS;
+        assert_eq!(
+            assigns.as_revealed_state_values(schema.flags),
+            Err(RevealedStateError::ConfidentialForbidden(1))
+        );
+        assert_eq!(failures(&schema, &assigns), vec![Failure::ConfidentialAmount(
+            OpId::strict_dumb(),
+            ASSET,
+            1
+        )]);
+    }
+
+    #[test]
+    fn authority() {
+        fn key<Seal: ExposedSeal>(keys: &[u8], seal: impl Fn(u32) -> Seal) -> Assignments<Seal> {
+            let assigns = keys
+                .iter()
+                .map(|key| {
+                    let state = RevealedData::with_salt(small_vec![*key], *key as u128);
+                    Assign::revealed(XChain::Bitcoin(seal(*key as u32)), state)
+                })
+                .collect::<Vec<_>>();
+            Assignments::from(tiny_bmap! {
+                AssignmentType::ISSUER_AUTHORITY =>
+                    TypedAssigns::Structured(SmallVec::try_from(assigns).unwrap()),
+            })
+        }
+        let mut schema = Schema::strict_dumb();
+        schema.flags = SchemaFlags::ISSUER_AUTHORITY;
+        let check = |op: OpRef, prev_state: &Assignments<GraphSeal>| {
+            schema.validate_authority(op.id(), &op, prev_state).failures
+        };
+        let none = Assignments::default();
+        let current = key(&[1], graph_seal);
+
+        let mut genesis = Genesis::strict_dumb();
+        assert_eq!(check(OpRef::Genesis(&genesis), &none), vec![]);
+        genesis.assignments = key(&[1], genesis_seal);
+        assert_eq!(check(OpRef::Genesis(&genesis), &none), vec![]);
+        genesis.assignments = key(&[1, 2], genesis_seal);
+        let opid = genesis.id();
+        assert_eq!(check(OpRef::Genesis(&genesis), &none), vec![Failure::AuthorityInvalid(opid)]);
+
+        let mut transition = Transition::strict_dumb();
+        let opid = transition.id();
+        assert_eq!(check(OpRef::Transition(&transition), &none), vec![]);
+        assert_eq!(check(OpRef::Transition(&transition), &current), vec![
+            Failure::AuthorityRemoved(opid)
+        ]);
+        transition.assignments = key(&[2], graph_seal);
+        let opid = transition.id();
+        assert_eq!(check(OpRef::Transition(&transition), &current), vec![]);
+        assert_eq!(check(OpRef::Transition(&transition), &none), vec![
+            Failure::AuthorityUnauthorized(opid)
+        ]);
+        let mut assigns = transition.assignments.clone();
+        let assign = &mut assigns
+            .get_mut(&AssignmentType::ISSUER_AUTHORITY)
+            .unwrap()
+            .as_structured_mut()
+            .unwrap()[0];
+        *assign = assign.conceal_state();
+        transition.assignments = assigns;
+        let opid = transition.id();
+        assert_eq!(check(OpRef::Transition(&transition), &current), vec![
+            Failure::AuthorityInvalid(opid)
+        ]);
+
+        let mut extension = Extension::strict_dumb();
+        extension.assignments = key(&[2], genesis_seal);
+        let opid = extension.id();
+        assert_eq!(check(OpRef::Extension(&extension), &none), vec![
+            Failure::AuthorityUnauthorized(opid)
+        ]);
+    }
+
+    #[test]
+    fn issuance_split() {
+        const INFLATION: AssignmentType = AssignmentType::with(2);
+        fn rights(vouts: &[u32]) -> Assignments<GenesisSeal> {
+            let assigns = vouts
+                .iter()
+                .map(|vout| {
+                    Assign::revealed(XChain::Bitcoin(genesis_seal(*vout)), VoidState::default())
+                })
+                .collect::<Vec<_>>();
+            Assignments::from(tiny_bmap! {
+                INFLATION => TypedAssigns::Declarative(SmallVec::try_from(assigns).unwrap()),
+            })
+        }
+        let mut schema = Schema::strict_dumb();
+        schema.owned_types = tiny_bmap! { INFLATION => OwnedStateSchema::Declarative };
+        let split = IssuanceSplit {
+            assignment_type: INFLATION,
+            issuers: tiny_bset![Bytes32::from([1u8; 32]), Bytes32::from([2u8; 32])],
+        };
+
+        let mut genesis = Genesis::strict_dumb();
+        genesis.add_issuance_split(&split).unwrap();
+        assert_eq!(genesis.issuance_split(), Some(split.clone()));
+        let check = |genesis: &Genesis| schema.validate_issuance_split(genesis.id(), genesis);
+
+        genesis.assignments = rights(&[0, 1]);
+        assert_eq!(check(&genesis).failures, vec![]);
+        for vouts in [&[0][..], &[0, 1, 2], &[0, 0]] {
+            genesis.assignments = rights(vouts);
+            assert_eq!(check(&genesis).failures, vec![Failure::IssuanceSplitMismatch(
+                genesis.id(),
+                INFLATION,
+                2
+            )]);
+        }
+
+        let mut single = split.clone();
+        single.issuers = tiny_bset![Bytes32::from([1u8; 32])];
+        let mut genesis = Genesis::strict_dumb();
+        genesis.add_issuance_split(&single).unwrap();
+        assert_eq!(genesis.issuance_split(), None);
+        assert_eq!(check(&genesis).failures, vec![Failure::IssuanceSplitInvalid(genesis.id())]);
+    }
 }
@@ -0,0 +1,213 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation profiling. With `metrics` feature enabled, the validator records
+//! time spent in each of the validation phases into [`ValidationMetrics`]
+//! stored in the validation [`Status`]; otherwise the instrumentation compiles
+//! into no-op.
+
+#[cfg(feature = "metrics")]
+use std::ops::AddAssign;
+#[cfg(feature = "metrics")]
+use std::time::{Duration, Instant};
+
+use super::Status;
+
+/// Phase of contract data processing profiled by [`ValidationMetrics`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum Phase {
+    /// Decoding of the consignment data. Since the validator receives already
+    /// decoded data, this phase is recorded by the caller.
+    Decode,
+    /// Verification of the schema and of the operations against the schema,
+    /// excluding nested VM and pedersen phases.
+    Schema,
+    /// Execution of AluVM validation scripts.
+    Vm,
+    /// Verification of pedersen commitment range proofs.
+    Pedersen,
+    /// Verification of anchor MPC proofs and closing of single-use seals.
+    Anchor,
+}
+
+/// Number of times a phase was run and the total time spent in it.
+#[cfg(feature = "metrics")]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct PhaseMetrics {
+    pub count: u32,
+    pub time: Duration,
+}
+
+#[cfg(feature = "metrics")]
+impl PhaseMetrics {
+    pub fn record(&mut self, time: Duration) {
+        self.count = self.count.saturating_add(1);
+        self.time += time;
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl AddAssign for PhaseMetrics {
+    fn add_assign(&mut self, rhs: Self) {
+        self.count = self.count.saturating_add(rhs.count);
+        self.time += rhs.time;
+    }
+}
+
+/// Per-phase validation timings and counters.
+#[cfg(feature = "metrics")]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ValidationMetrics {
+    pub decode: PhaseMetrics,
+    pub schema: PhaseMetrics,
+    pub vm: PhaseMetrics,
+    pub pedersen: PhaseMetrics,
+    pub anchor: PhaseMetrics,
+}
+
+#[cfg(feature = "metrics")]
+impl ValidationMetrics {
+    pub fn phase(&self, phase: Phase) -> &PhaseMetrics {
+        match phase {
+            Phase::Decode => &self.decode,
+            Phase::Schema => &self.schema,
+            Phase::Vm => &self.vm,
+            Phase::Pedersen => &self.pedersen,
+            Phase::Anchor => &self.anchor,
+        }
+    }
+
+    pub fn phase_mut(&mut self, phase: Phase) -> &mut PhaseMetrics {
+        match phase {
+            Phase::Decode => &mut self.decode,
+            Phase::Schema => &mut self.schema,
+            Phase::Vm => &mut self.vm,
+            Phase::Pedersen => &mut self.pedersen,
+            Phase::Anchor => &mut self.anchor,
+        }
+    }
+
+    /// Total time spent in all phases.
+    pub fn total(&self) -> Duration {
+        self.decode.time + self.schema.time + self.vm.time + self.pedersen.time + self.anchor.time
+    }
+
+    /// Runs the closure recording its execution time under the given phase.
+    pub fn measure<T>(&mut self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let res = f();
+        self.phase_mut(phase).record(start.elapsed());
+        res
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl AddAssign for ValidationMetrics {
+    fn add_assign(&mut self, rhs: Self) {
+        self.decode += rhs.decode;
+        self.schema += rhs.schema;
+        self.vm += rhs.vm;
+        self.pedersen += rhs.pedersen;
+        self.anchor += rhs.anchor;
+    }
+}
+
+/// Stopwatch recording duration of a validation phase into the status.
+pub(crate) struct Timer {
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+impl Timer {
+    #[inline]
+    pub fn start() -> Self {
+        Timer {
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+
+    /// Records time elapsed since the timer start under the given phase.
+    #[inline]
+    pub fn stop(self, status: &mut Status, phase: Phase) {
+        #[cfg(feature = "metrics")]
+        status.metrics.phase_mut(phase).record(self.start.elapsed());
+        #[cfg(not(feature = "metrics"))]
+        let _ = (status, phase);
+    }
+
+    /// Records time elapsed since the timer start under the given phase,
+    /// excluding time of the phases nested into it. The nested phases must be
+    /// already recorded in the provided status, which must not contain any
+    /// other timings.
+    #[inline]
+    pub fn stop_nesting(self, status: &mut Status, phase: Phase) {
+        #[cfg(feature = "metrics")]
+        {
+            let nested = status.metrics.total();
+            let time = self.start.elapsed().saturating_sub(nested);
+            status.metrics.phase_mut(phase).record(time);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = (status, phase);
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod test {
+    use super::*;
+    use crate::ChainNet;
+    use crate::testing::Scenario;
+    use crate::validation::ValidationPolicy;
+
+    #[test]
+    fn phases() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        scenario.transfer();
+        let status = scenario.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+
+        let metrics = &status.metrics;
+        assert_eq!(metrics.decode, PhaseMetrics::default());
+        // Schema verification, genesis and two transitions
+        assert_eq!(metrics.schema.count, 4);
+        assert_eq!(metrics.anchor.count, 2);
+        assert_eq!(metrics.vm.count, 0);
+        assert_eq!(metrics.pedersen.count, 0);
+
+        let mut metrics = metrics.clone();
+        metrics.measure(Phase::Decode, || ());
+        assert_eq!(metrics.phase(Phase::Decode).count, 1);
+    }
+}
@@ -25,9 +25,38 @@ mod logic;
 mod state;
 mod validator;
 mod consignment;
+mod policy;
+mod rules;
 mod status;
+mod metrics;
+mod spv;
+mod report;
+mod payment;
+mod receipt;
+mod cancel;
+mod cache;
 
-pub use consignment::{CheckedConsignment, ConsignmentApi, Scripts, CONSIGNMENT_MAX_LIBS};
-pub(crate) use logic::OpInfo;
+pub use cache::ValidationCache;
+pub use cancel::{CancelToken, Deadline, NeverCancel};
+pub use consignment::{
+    CONSIGNMENT_MAX_LIBS, CheckedConsignment, Consignment, ConsignmentApi, ConsignmentError,
+    ConsignmentSummary, RequiredSeal, Scripts, StoredConsignment, Terminal, TerminalError,
+};
+pub use logic::OpInfo;
+pub use metrics::Phase;
+pub(crate) use metrics::Timer;
+#[cfg(feature = "metrics")]
+pub use metrics::{PhaseMetrics, ValidationMetrics};
+pub use payment::{PaymentProof, PaymentProofError, verify_payment_proof};
+pub use policy::{PolicyHash, ResourceLimit, ResourceLimits, SchemaAllowlist, ValidationPolicy};
+pub use receipt::{Receipt, ReceiptError, ReceiptId, SignReceipt, SignedReceipt};
+#[cfg(feature = "signing")]
+pub use report::ReportSecret;
+pub use report::{ReportError, ReportId, ReportSigner, SignedReport, ValidationReport};
+pub use rules::{ConsensusCheck, ConsensusRules};
+pub use spv::{
+    HeaderBundle, HeaderError, HeaderSource, Headers, MerkleProof, SpvResolver, SpvWitness,
+    WitnessProofs, block_merkle_root, block_work,
+};
 pub use status::{Failure, Info, Status, Validity, Warning};
 pub use validator::{ResolveWitness, Validator, WitnessResolverError};
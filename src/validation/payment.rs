@@ -0,0 +1,314 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proofs of payment.
+//!
+//! A [`PaymentProof`] is extracted by a payer from the consignment it has sent
+//! and proves that a specific seal was assigned state by a state transition
+//! committed into a witness transaction. The proof reveals only the paid
+//! assignment: the rest of the transition assignments are concealed, and the
+//! multi-protocol commitment is represented by a merkle path. A merchant or an
+//! arbiter verifies the proof with [`verify_payment_proof`] without having
+//! access to the contract history.
+
+use amplify::Wrapper;
+use amplify::confinement::{Confined, SmallVec};
+use bp::dbc::Proof;
+use bp::seals::txout::CloseMethod;
+use commit_verify::{Conceal, mpc};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use super::{ResolveWitness, WitnessResolverError};
+use crate::{
+    Assign, Assignments, BundleId, DbcError, DbcProof, EAnchor, ExposedState, GraphSeal,
+    LIB_NAME_RGB, OpId, Operation, SecretSeal, Transition, TransitionBundle, TypedAssigns, XChain,
+    XWitnessId,
+};
+
+/// Errors extracting and verifying proofs of payment.
+#[derive(Clone, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PaymentProofError {
+    /// bundle {0} is absent from the consignment.
+    BundleAbsent(BundleId),
+
+    /// anchor for the bundle {0} is absent from the consignment.
+    AnchorAbsent(BundleId),
+
+    /// bundle {0} doesn't assign state to the seal {1}.
+    SealAbsent(BundleId, XChain<SecretSeal>),
+
+    /// payment proof must contain a single state transition.
+    NoTransition,
+
+    /// state transition {0} is not a part of the bundle input map.
+    TransitionUnmapped(OpId),
+
+    /// state transition {0} doesn't assign state to the seal {1}.
+    SealNotAssigned(OpId, XChain<SecretSeal>),
+
+    /// close method of the bundle {0} doesn't match the anchor proof.
+    MethodMismatch(BundleId),
+
+    /// witness transaction {0} doesn't match the anchor layer 1.
+    WitnessMismatch(XWitnessId),
+
+    /// bundle {0} is not committed under the contract: {1}
+    Mpc(BundleId, mpc::InvalidProof),
+
+    /// witness transaction {0} doesn't commit to the bundle: {1}
+    Dbc(XWitnessId, DbcError),
+
+    #[display(inner)]
+    #[from]
+    Resolver(WitnessResolverError),
+}
+
+/// Proof that a state transition assigned state to a specific seal, committed
+/// into a witness transaction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct PaymentProof {
+    /// The paid seal.
+    pub seal: XChain<SecretSeal>,
+    /// Bundle containing only the paying transition, with all assignments
+    /// concealed except the ones to the paid seal.
+    pub bundle: TransitionBundle,
+    /// Id of the witness transaction.
+    pub witness_id: XWitnessId,
+    /// Anchor containing the multi-protocol commitment merkle path and the
+    /// deterministic bitcoin commitment proof.
+    pub anchor: EAnchor,
+}
+
+impl StrictSerialize for PaymentProof {}
+impl StrictDeserialize for PaymentProof {}
+
+impl PaymentProof {
+    /// Constructs proof of payment to a `seal` from an anchored bundle.
+    pub fn with(
+        seal: XChain<SecretSeal>,
+        bundle: &TransitionBundle,
+        witness_id: XWitnessId,
+        anchor: EAnchor,
+    ) -> Result<Self, PaymentProofError> {
+        let bundle_id = bundle.bundle_id();
+        let (opid, transition) = bundle
+            .known_transitions
+            .iter()
+            .find(|(_, transition)| {
+                transition
+                    .assignments
+                    .values()
+                    .any(|assigns| assigns.to_confidential_seals().contains(&seal))
+            })
+            .ok_or(PaymentProofError::SealAbsent(bundle_id, seal))?;
+        let transition = conceal_siblings(transition, seal);
+        Ok(PaymentProof {
+            seal,
+            bundle: TransitionBundle {
+                close_method: bundle.close_method,
+                input_map: bundle.input_map.clone(),
+                known_transitions: confined_bmap! { *opid => transition },
+            },
+            witness_id,
+            anchor,
+        })
+    }
+
+    /// Returns the paying state transition.
+    pub fn transition(&self) -> &Transition {
+        self.bundle
+            .known_transitions
+            .values()
+            .next()
+            .expect("bundle always contains at least one transition")
+    }
+}
+
+/// Conceals all transition assignments except the ones to the `seal`.
+fn conceal_siblings(transition: &Transition, seal: XChain<SecretSeal>) -> Transition {
+    fn conceal<State: ExposedState>(
+        assigns: &[Assign<State, GraphSeal>],
+        seal: XChain<SecretSeal>,
+    ) -> impl Iterator<Item = Assign<State, GraphSeal>> + '_ {
+        assigns.iter().map(move |assign| {
+            if assign.to_confidential_seal() == seal {
+                assign.clone()
+            } else {
+                assign.conceal()
+            }
+        })
+    }
+
+    let assignments = transition.assignments.iter().map(|(ty, assigns)| {
+        let assigns = match assigns {
+            TypedAssigns::Declarative(a) => TypedAssigns::Declarative(
+                SmallVec::try_from_iter(conceal(a, seal)).expect("same size"),
+            ),
+            TypedAssigns::Fungible(a) => TypedAssigns::Fungible(
+                SmallVec::try_from_iter(conceal(a, seal)).expect("same size"),
+            ),
+            TypedAssigns::Structured(a) => TypedAssigns::Structured(
+                SmallVec::try_from_iter(conceal(a, seal)).expect("same size"),
+            ),
+            TypedAssigns::Attachment(a) => TypedAssigns::Attachment(
+                SmallVec::try_from_iter(conceal(a, seal)).expect("same size"),
+            ),
+        };
+        (*ty, assigns)
+    });
+    let mut transition = transition.clone();
+    transition.assignments =
+        Assignments::from_inner(Confined::try_from_iter(assignments).expect("same size"));
+    transition
+}
+
+/// Verifies proof of payment against the witness transaction retrieved from
+/// the `resolver`, returning id of the paying state transition.
+///
+/// The verification checks that the state transition assigns state to the
+/// paid seal and is a part of the bundle which is committed under the
+/// contract of the transition into the witness transaction. It doesn't
+/// validate the transition itself or its history, nor does it check the
+/// mining status of the witness, which is left to the caller.
+pub fn verify_payment_proof(
+    proof: &PaymentProof,
+    resolver: &impl ResolveWitness,
+) -> Result<OpId, PaymentProofError> {
+    let bundle = &proof.bundle;
+    let bundle_id = bundle.bundle_id();
+    if bundle.known_transitions.len() != 1 {
+        return Err(PaymentProofError::NoTransition);
+    }
+    let transition = proof.transition();
+    let opid = transition.id();
+    if !bundle.input_map.values().any(|id| *id == opid) ||
+        !bundle.known_transitions.contains_key(&opid)
+    {
+        return Err(PaymentProofError::TransitionUnmapped(opid));
+    }
+    if !transition
+        .assignments
+        .values()
+        .any(|assigns| assigns.to_confidential_seals().contains(&proof.seal))
+    {
+        return Err(PaymentProofError::SealNotAssigned(opid, proof.seal));
+    }
+
+    match (bundle.close_method, &proof.anchor.dbc_proof) {
+        (CloseMethod::TapretFirst, DbcProof::Tapret(_)) |
+        (CloseMethod::OpretFirst, DbcProof::Opret(_)) => {}
+        _ => return Err(PaymentProofError::MethodMismatch(bundle_id)),
+    }
+    let commitment = proof
+        .anchor
+        .convolve(transition.contract_id, mpc::Message::from(bundle_id))
+        .map_err(|err| PaymentProofError::Mpc(bundle_id, err))?;
+
+    let witness_id = proof.witness_id;
+    let witness = resolver.resolve_pub_witness(witness_id)?;
+    if witness.witness_id() != witness_id {
+        return Err(PaymentProofError::WitnessMismatch(witness_id));
+    }
+    proof
+        .anchor
+        .dbc_proof
+        .verify(&commitment, witness.as_reduced_unsafe())
+        .map_err(|err| PaymentProofError::Dbc(witness_id, err))?;
+    Ok(opid)
+}
+
+#[cfg(test)]
+mod test {
+    use bp::Vout;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::testing::{RIGHTS, Scenario};
+    use crate::validation::{ConsignmentApi, Terminal};
+    use crate::{ChainNet, InputMap};
+
+    #[test]
+    fn payment_proof() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let (_, first_witness) = scenario.transfer();
+        let (opid, _) = scenario.transfer();
+        let consignment = scenario.consignment();
+        let (bundle_id, seal) = consignment.terminals().next().unwrap();
+        let proof = consignment
+            .payment_proof(&Terminal::new(bundle_id, seal))
+            .unwrap();
+        assert_eq!(verify_payment_proof(&proof, scenario.chain()).unwrap(), opid);
+        assert!(
+            proof.transition().assignments[&RIGHTS]
+                .revealed_seal_at(0)
+                .unwrap()
+                .is_some()
+        );
+
+        let data = proof
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .unwrap();
+        assert_eq!(PaymentProof::from_strict_serialized(data).unwrap(), proof);
+
+        let absent = Terminal::new(BundleId::strict_dumb(), seal);
+        assert!(matches!(
+            consignment.payment_proof(&absent),
+            Err(PaymentProofError::BundleAbsent(_))
+        ));
+
+        let mut unpaid = proof.clone();
+        unpaid.seal = XChain::Bitcoin(SecretSeal::strict_dumb());
+        assert!(matches!(
+            verify_payment_proof(&unpaid, scenario.chain()),
+            Err(PaymentProofError::SealNotAssigned(id, _)) if id == opid
+        ));
+
+        let mut remapped = proof.clone();
+        remapped.bundle.input_map = InputMap::with(Vout::from_u32(1), opid);
+        assert!(matches!(
+            verify_payment_proof(&remapped, scenario.chain()),
+            Err(PaymentProofError::Dbc(..))
+        ));
+
+        let mut other_witness = proof.clone();
+        other_witness.witness_id = first_witness;
+        assert!(matches!(
+            verify_payment_proof(&other_witness, scenario.chain()),
+            Err(PaymentProofError::Dbc(..))
+        ));
+
+        let mut chain = scenario.chain().clone();
+        chain.evict(proof.witness_id);
+        assert!(matches!(
+            verify_payment_proof(&proof, &chain),
+            Err(PaymentProofError::Resolver(WitnessResolverError::Unknown(_)))
+        ));
+    }
+}
@@ -0,0 +1,305 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use amplify::hex::ToHex;
+use amplify::{Bytes32, Wrapper};
+use commit_verify::{CommitmentId, DigestExt, Sha256};
+
+use super::ConsensusRules;
+use crate::{LIB_NAME_RGB, SchemaId};
+
+/// Limits on the resources used by the validator while traversing the
+/// operation graph, protecting it from maliciously crafted consignments.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ResourceLimits {
+    /// Maximal number of operations between a terminal and genesis.
+    pub max_depth: u32,
+
+    /// Maximal number of operations visited during the graph traversal.
+    pub max_nodes: u32,
+
+    /// Maximal number of ancestors of a single operation. Default value
+    /// matches the consensus limit on the number of transition inputs.
+    pub max_fan_in: u16,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            max_depth: 1_000_000,
+            max_nodes: 10_000_000,
+            max_fan_in: u16::MAX,
+        }
+    }
+}
+
+/// Type of resource limit exceeded during validation.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum ResourceLimit {
+    #[display("history depth")]
+    Depth,
+
+    #[display("number of operations")]
+    Nodes,
+
+    #[display("number of operation ancestors")]
+    FanIn,
+}
+
+/// Hash committing to all parameters of a [`ValidationPolicy`].
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(Self::to_hex)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct PolicyHash(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for PolicyHash {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for PolicyHash {
+    const TAG: &'static str = "urn:lnp-bp:rgb:validation-policy#2024-07-15";
+}
+
+/// Policy controlling optional checks performed by the [`super::Validator`].
+///
+/// Default policy corresponds to the full validation of the consignment, which
+/// still accepts witness transactions not yet mined. Verifiers accepting
+/// payments should use [`ValidationPolicy::strict`], while wallets showing a
+/// preview of an incoming consignment may use [`ValidationPolicy::preview`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = PolicyHash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ValidationPolicy {
+    /// Report operation data unknown to the schema as warnings instead of
+    /// failures. Since flag bytes of operations are reserved and rejected
+    /// during deserialization, and unknown schema flags may change consensus
+    /// rules and are always rejected, this currently applies to metadata types
+    /// not declared by the schema.
+    pub allow_unknown_flags: bool,
+
+    /// Accept witness transactions which are not mined yet. If set to `false`,
+    /// the validator requires [`super::ResolveWitness::resolve_witness_ord`]
+    /// to report each witness as mined.
+    pub allow_unconfirmed: bool,
+
+    /// Limits on the operation graph traversal.
+    pub limits: ResourceLimits,
+
+    /// Accept histories with ancestor operations absent from the consignment,
+    /// reporting them as warnings. Operations spending outputs of the absent
+    /// operations are not validated against the schema.
+    pub allow_pruned: bool,
+
+    /// Stop the validation at the first detected failure, which is the
+    /// cheapest way of rejecting invalid consignments. Otherwise the validator
+    /// proceeds with the rest of the consignment data, collecting all the
+    /// failures for diagnostics.
+    pub fail_fast: bool,
+
+    /// Version of the consensus rules to validate against. Rules other than
+    /// [`ConsensusRules::LATEST`] skip the checks added after them (see
+    /// [`ConsensusRules::diff`]) and apply the ones removed since them,
+    /// reproducing the verdicts of the respective release. Allows operators
+    /// to run several versions side by side while upgrading and detect
+    /// contracts whose validity differs between them. Must not be used for
+    /// accepting payments.
+    pub rules: ConsensusRules,
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        ValidationPolicy {
+            allow_unknown_flags: false,
+            allow_unconfirmed: true,
+            limits: default!(),
+            allow_pruned: false,
+            fail_fast: false,
+            rules: ConsensusRules::LATEST,
+        }
+    }
+}
+
+impl ValidationPolicy {
+    /// Policy requiring all witnesses to be mined.
+    pub fn strict() -> Self {
+        ValidationPolicy {
+            allow_unconfirmed: false,
+            ..default!()
+        }
+    }
+
+    /// Permissive policy for a fast preview of the consignment data.
+    pub fn preview() -> Self {
+        ValidationPolicy {
+            allow_unknown_flags: true,
+            allow_unconfirmed: true,
+            limits: default!(),
+            allow_pruned: true,
+            fail_fast: false,
+            rules: ConsensusRules::LATEST,
+        }
+    }
+
+    /// Policy reproducing the verdicts of the v0.11.0-beta.6 release; see
+    /// [`ValidationPolicy::rules`] field for the details.
+    pub fn compat() -> Self {
+        ValidationPolicy {
+            rules: ConsensusRules::V0_11Beta6,
+            ..default!()
+        }
+    }
+}
+
+/// Schemata of the contracts accepted by a verifier, together with the
+/// validation policies applied to the contracts under each of them.
+///
+/// Used with [`super::Validator::validate_allowlisted`], which fails the
+/// validation of contracts under schemata absent from the list.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SchemaAllowlist {
+    /// Policy applied to the schemata which don't override it.
+    pub default_policy: ValidationPolicy,
+    schemata: BTreeMap<SchemaId, Option<ValidationPolicy>>,
+}
+
+impl SchemaAllowlist {
+    /// Constructs allowlist of the schemata validated with the same policy.
+    pub fn with(
+        default_policy: ValidationPolicy,
+        schemata: impl IntoIterator<Item = SchemaId>,
+    ) -> Self {
+        SchemaAllowlist {
+            default_policy,
+            schemata: schemata
+                .into_iter()
+                .map(|schema_id| (schema_id, None))
+                .collect(),
+        }
+    }
+
+    /// Adds schema to the allowlist, optionally overriding the default policy
+    /// for the contracts under it. Returns whether the schema was already
+    /// allowed.
+    pub fn allow(&mut self, schema_id: SchemaId, policy: Option<ValidationPolicy>) -> bool {
+        self.schemata.insert(schema_id, policy).is_some()
+    }
+
+    /// Removes schema from the allowlist, returning whether it was allowed.
+    pub fn disallow(&mut self, schema_id: SchemaId) -> bool {
+        self.schemata.remove(&schema_id).is_some()
+    }
+
+    /// Detects whether contracts under the schema are accepted.
+    pub fn is_allowed(&self, schema_id: SchemaId) -> bool { self.schemata.contains_key(&schema_id) }
+
+    /// Returns policy for validating contracts under the schema, or `None` if
+    /// the schema is not allowed.
+    pub fn policy(&self, schema_id: SchemaId) -> Option<ValidationPolicy> {
+        self.schemata
+            .get(&schema_id)
+            .map(|policy| policy.unwrap_or(self.default_policy))
+    }
+
+    /// Iterates over ids of the allowed schemata.
+    pub fn schema_ids(&self) -> impl Iterator<Item = SchemaId> + '_ {
+        self.schemata.keys().copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::Scenario;
+    use crate::validation::{ConsignmentApi, Failure, Validator, Validity};
+    use crate::{ChainNet, ChainTrustAnchors};
+
+    #[test]
+    fn schema_allowlist() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        let schema_id = scenario.consignment().schema().schema_id();
+        let validate = |allowlist: &SchemaAllowlist| {
+            Validator::validate_allowlisted(
+                scenario.consignment(),
+                scenario.chain(),
+                ChainNet::Regtest,
+                &ChainTrustAnchors::default(),
+                allowlist,
+            )
+        };
+
+        let mut allowlist = SchemaAllowlist::default();
+        let status = validate(&allowlist);
+        assert_eq!(status.failures, vec![Failure::SchemaNotAllowed(schema_id)]);
+        assert_eq!(status.validity(), Validity::Invalid);
+
+        assert!(!allowlist.allow(schema_id, None));
+        assert_eq!(validate(&allowlist).failures, vec![]);
+        assert_eq!(allowlist.policy(schema_id), Some(ValidationPolicy::default()));
+
+        let strict = ValidationPolicy::strict();
+        assert!(allowlist.allow(schema_id, Some(strict)));
+        assert_eq!(allowlist.policy(schema_id), Some(strict));
+        assert_eq!(allowlist.schema_ids().collect::<Vec<_>>(), vec![schema_id]);
+
+        assert!(allowlist.disallow(schema_id));
+        assert!(!allowlist.is_allowed(schema_id));
+        assert_eq!(allowlist.policy(schema_id), None);
+    }
+}
@@ -0,0 +1,327 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts issued by a receiver for the accepted payments.
+//!
+//! Complementing [`super::PaymentProof`], which is produced by a payer, a
+//! [`Receipt`] binds an invoice to the ids of the consignment terminal state
+//! transitions paying it. The receiver signs the receipt with a BIP-340 key,
+//! either with [`super::ReportSecret`] or with an external signer implementing
+//! [`SignReceipt`] hook, and hands it back to the payer.
+//!
+//! Verifying receipt signatures requires `signing` feature.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::confinement::TinyOrdSet;
+use amplify::{ByteArray, Bytes32, Bytes64};
+use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+use commit_verify::{CommitId, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use super::{ConsignmentApi, ReportSigner};
+use crate::{ContractId, Invoice, InvoiceId, LIB_NAME_RGB, OpId, Operation, impl_serde_baid64};
+
+/// Receipt identifier.
+///
+/// Receipt identifier commits to all the receipt data and is the message
+/// signed by the receiver.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct ReceiptId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for ReceiptId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for ReceiptId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:receipt#2024-07-01";
+}
+
+impl DisplayBaid64 for ReceiptId {
+    const HRI: &'static str = "rgb:rcp";
+    const CHUNKING: bool = false;
+    const PREFIX: bool = true;
+    const EMBED_CHECKSUM: bool = false;
+    const MNEMONIC: bool = false;
+    fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
+}
+impl FromBaid64Str for ReceiptId {}
+impl FromStr for ReceiptId {
+    type Err = Baid64ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+}
+impl Display for ReceiptId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
+}
+
+impl_serde_baid64!(ReceiptId);
+
+/// Confirmation of an invoice payment by the receiver.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = ReceiptId)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Receipt {
+    pub invoice_id: InvoiceId,
+    pub contract_id: ContractId,
+    /// Ids of the terminal state transitions assigning state to the invoice
+    /// seal.
+    pub terminals: TinyOrdSet<OpId>,
+}
+
+impl StrictSerialize for Receipt {}
+impl StrictDeserialize for Receipt {}
+
+impl Receipt {
+    /// Constructs receipt for the invoice paid by an accepted consignment.
+    pub fn new(invoice: &Invoice, consignment: &impl ConsignmentApi) -> Result<Self, ReceiptError> {
+        let invoice_id = invoice.invoice_id();
+        let contract_id = consignment.genesis().contract_id();
+        if contract_id != invoice.contract_id {
+            return Err(ReceiptError::ContractMismatch(invoice_id, contract_id));
+        }
+        let terminals = consignment
+            .terminals()
+            .filter(|(_, seal)| invoice.is_paid_by(contract_id, *seal))
+            .filter_map(|(bundle_id, _)| consignment.bundle(bundle_id))
+            .flat_map(|bundle| bundle.known_transitions.iter())
+            .filter(|(_, transition)| {
+                transition
+                    .assignments
+                    .values()
+                    .any(|assigns| assigns.to_confidential_seals().contains(&invoice.seal))
+            })
+            .map(|(opid, _)| *opid);
+        let terminals =
+            TinyOrdSet::try_from_iter(terminals).map_err(|_| ReceiptError::TooManyTerminals)?;
+        if terminals.is_empty() {
+            return Err(ReceiptError::NotPaid(invoice_id));
+        }
+        Ok(Receipt {
+            invoice_id,
+            contract_id,
+            terminals,
+        })
+    }
+
+    pub fn receipt_id(&self) -> ReceiptId { self.commit_id() }
+}
+
+/// Receipt signed by the receiver.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SignedReceipt {
+    pub receipt: Receipt,
+    pub signer: ReportSigner,
+    /// BIP-340 signature of the receipt id.
+    pub signature: Bytes64,
+}
+
+impl StrictSerialize for SignedReceipt {}
+impl StrictDeserialize for SignedReceipt {}
+
+/// Hook for signing receipts with keys which may be kept outside of the
+/// process, like in a hardware signer.
+pub trait SignReceipt {
+    /// Returns x-only public key of the receiver.
+    fn receipt_signer(&self) -> ReportSigner;
+
+    /// Produces BIP-340 signature of the receipt id.
+    fn sign_receipt_id(&self, receipt_id: ReceiptId) -> Bytes64;
+
+    /// Signs the receipt.
+    fn sign_receipt(&self, receipt: Receipt) -> SignedReceipt {
+        let signature = self.sign_receipt_id(receipt.receipt_id());
+        SignedReceipt {
+            receipt,
+            signer: self.receipt_signer(),
+            signature,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ReceiptError {
+    /// consignment doesn't pay invoice {0}.
+    NotPaid(InvoiceId),
+
+    /// consignment of the contract {1} can't pay invoice {0}.
+    ContractMismatch(InvoiceId, ContractId),
+
+    /// receipt contains too many terminals.
+    TooManyTerminals,
+
+    /// receipt {0} is signed by {1}, which is not the expected receiver.
+    UntrustedSigner(ReceiptId, ReportSigner),
+
+    /// receipt {0} signature is invalid.
+    InvalidSignature(ReceiptId),
+
+    /// receipt {0} is issued for the invoice {1} instead of {2}.
+    InvoiceMismatch(ReceiptId, InvoiceId, InvoiceId),
+}
+
+#[cfg(feature = "signing")]
+mod crypto {
+    use secp256k1_zkp::schnorr::Signature;
+    use secp256k1_zkp::{Message, SECP256K1, XOnlyPublicKey};
+
+    use super::*;
+    use crate::validation::ReportSecret;
+
+    impl SignReceipt for ReportSecret {
+        fn receipt_signer(&self) -> ReportSigner { self.signer() }
+
+        fn sign_receipt_id(&self, receipt_id: ReceiptId) -> Bytes64 {
+            self.sign_digest(receipt_id.to_byte_array())
+        }
+    }
+
+    impl SignedReceipt {
+        /// Verifies the receipt signature, returning the receipt id.
+        pub fn verify_signature(&self) -> Result<ReceiptId, ReceiptError> {
+            let receipt_id = self.receipt.receipt_id();
+            let msg = Message::from_digest(receipt_id.to_byte_array());
+            let signer = XOnlyPublicKey::from_slice(self.signer.as_slice())
+                .map_err(|_| ReceiptError::InvalidSignature(receipt_id))?;
+            let signature = Signature::from_slice(self.signature.as_slice())
+                .map_err(|_| ReceiptError::InvalidSignature(receipt_id))?;
+            SECP256K1
+                .verify_schnorr(&signature, &msg, &signer)
+                .map_err(|_| ReceiptError::InvalidSignature(receipt_id))?;
+            Ok(receipt_id)
+        }
+
+        /// Verifies that the receipt is signed by the expected receiver and
+        /// confirms payment of the invoice.
+        pub fn verify(
+            &self,
+            receiver: ReportSigner,
+            invoice: &Invoice,
+        ) -> Result<ReceiptId, ReceiptError> {
+            let receipt_id = self.verify_signature()?;
+            if self.signer != receiver {
+                return Err(ReceiptError::UntrustedSigner(receipt_id, self.signer));
+            }
+            let invoice_id = invoice.invoice_id();
+            if self.receipt.invoice_id != invoice_id {
+                return Err(ReceiptError::InvoiceMismatch(
+                    receipt_id,
+                    self.receipt.invoice_id,
+                    invoice_id,
+                ));
+            }
+            Ok(receipt_id)
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "signing")]
+mod test {
+    use super::*;
+    use crate::testing::{RIGHTS, Scenario};
+    use crate::validation::ReportSecret;
+    use crate::{ChainNet, InvoiceState, SecretSeal, XChain};
+
+    #[test]
+    fn signed_receipt() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let (opid, _) = scenario.transfer();
+        let consignment = scenario.consignment();
+        let contract_id = consignment.genesis().contract_id();
+        let (_, seal) = consignment.terminals().next().unwrap();
+        let invoice = Invoice::new(contract_id, RIGHTS, InvoiceState::Void, seal);
+
+        let receipt = Receipt::new(&invoice, consignment).unwrap();
+        assert_eq!(receipt.terminals, tiny_bset! { opid });
+        let receipt_id = receipt.receipt_id();
+        assert_eq!(receipt_id.to_string().parse::<ReceiptId>().unwrap(), receipt_id);
+
+        let secret = ReportSecret::random();
+        let signed = secret.sign_receipt(receipt.clone());
+        let data = signed
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .unwrap();
+        let signed = SignedReceipt::from_strict_serialized::<{ u16::MAX as usize }>(data).unwrap();
+        assert_eq!(signed.verify(secret.signer(), &invoice), Ok(receipt.receipt_id()));
+
+        let other = ReportSecret::random();
+        assert_eq!(
+            signed.verify(other.signer(), &invoice),
+            Err(ReceiptError::UntrustedSigner(receipt.receipt_id(), secret.signer()))
+        );
+        let mut other_invoice = invoice.clone();
+        other_invoice.expiry = Some(100);
+        assert_eq!(
+            signed.verify(secret.signer(), &other_invoice),
+            Err(ReceiptError::InvoiceMismatch(
+                receipt.receipt_id(),
+                invoice.invoice_id(),
+                other_invoice.invoice_id()
+            ))
+        );
+        let mut forged = signed.clone();
+        forged.receipt.terminals = none!();
+        assert_eq!(
+            forged.verify(secret.signer(), &invoice),
+            Err(ReceiptError::InvalidSignature(forged.receipt.receipt_id()))
+        );
+
+        let unpaid = Invoice::new(
+            contract_id,
+            RIGHTS,
+            InvoiceState::Void,
+            XChain::Bitcoin(SecretSeal::from([0x11; 32])),
+        );
+        assert_eq!(
+            Receipt::new(&unpaid, consignment),
+            Err(ReceiptError::NotPaid(unpaid.invoice_id()))
+        );
+        let foreign = Invoice::new(ContractId::from([0u8; 32]), RIGHTS, InvoiceState::Void, seal);
+        assert_eq!(
+            Receipt::new(&foreign, consignment),
+            Err(ReceiptError::ContractMismatch(foreign.invoice_id(), contract_id))
+        );
+    }
+}
@@ -0,0 +1,339 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detached validation reports for split trust setups.
+//!
+//! An air-gapped verifier validates a consignment and produces a
+//! [`ValidationReport`], which commits to the validation outcome, the contract,
+//! the terminals and the policy used. The report is signed with a BIP-340
+//! Schnorr key of the verifier into a [`SignedReport`], which is passed back to
+//! an online watcher. The watcher checks the signature against the verifier
+//! key it trusts before accepting the consignment.
+//!
+//! Signing and verifying reports requires `signing` feature.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::confinement::LargeVec;
+use amplify::hex::{self, FromHex};
+use amplify::{ByteArray, Bytes32, Bytes64};
+use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+use commit_verify::{CommitId, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use super::{ConsignmentApi, Status, Terminal, ValidationPolicy, Validity};
+use crate::{ChainNet, ContractId, LIB_NAME_RGB, Operation, impl_serde_baid64};
+
+/// Validation report identifier.
+///
+/// Report identifier commits to all the report data and is the message signed
+/// by the verifier.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct ReportId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl From<Sha256> for ReportId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl CommitmentId for ReportId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:report#2024-06-20";
+}
+
+impl DisplayBaid64 for ReportId {
+    const HRI: &'static str = "rgb:rpt";
+    const CHUNKING: bool = false;
+    const PREFIX: bool = true;
+    const EMBED_CHECKSUM: bool = false;
+    const MNEMONIC: bool = false;
+    fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
+}
+impl FromBaid64Str for ReportId {}
+impl FromStr for ReportId {
+    type Err = Baid64ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+}
+impl Display for ReportId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
+}
+
+impl_serde_baid64!(ReportId);
+
+/// Outcome of a consignment validation, which can be passed to another party.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = ReportId)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ValidationReport {
+    pub contract_id: ContractId,
+    pub chain_net: ChainNet,
+    pub validity: Validity,
+    /// Number of validation failures.
+    pub failures: u32,
+    /// Number of validation warnings.
+    pub warnings: u32,
+    /// Terminals of the validated consignment.
+    pub terminals: LargeVec<Terminal>,
+    pub policy: ValidationPolicy,
+}
+
+impl StrictSerialize for ValidationReport {}
+impl StrictDeserialize for ValidationReport {}
+
+impl ValidationReport {
+    /// Constructs report from the status returned by the validation of a
+    /// consignment.
+    pub fn new(
+        consignment: &impl ConsignmentApi,
+        status: &Status,
+        chain_net: ChainNet,
+        policy: ValidationPolicy,
+    ) -> Self {
+        let terminals = consignment
+            .terminals()
+            .map(|(bundle_id, seal)| Terminal::new(bundle_id, seal))
+            .collect::<Vec<_>>();
+        ValidationReport {
+            contract_id: consignment.genesis().contract_id(),
+            chain_net,
+            validity: status.validity(),
+            failures: status.failures.len() as u32,
+            warnings: status.warnings.len() as u32,
+            terminals: LargeVec::try_from(terminals).expect("number of terminals exceeds 2^32"),
+            policy,
+        }
+    }
+
+    pub fn report_id(&self) -> ReportId { self.commit_id() }
+}
+
+/// Public x-only key of the verifier signing validation reports.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Display)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(LowerHex)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ReportSigner(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl FromStr for ReportSigner {
+    type Err = hex::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+}
+
+/// Validation report signed by a verifier.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SignedReport {
+    pub report: ValidationReport,
+    pub signer: ReportSigner,
+    /// BIP-340 signature of the report id.
+    pub signature: Bytes64,
+}
+
+impl StrictSerialize for SignedReport {}
+impl StrictDeserialize for SignedReport {}
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ReportError {
+    /// report is signed by {0}, which is not a trusted verifier.
+    UntrustedSigner(ReportSigner),
+
+    /// report {0} signature is invalid.
+    InvalidSignature(ReportId),
+
+    /// report {0} is issued for the contract {1} instead of {2}.
+    ContractMismatch(ReportId, ContractId, ContractId),
+
+    /// report {0} doesn't confirm validity of the consignment, which {1}.
+    NotValid(ReportId, Validity),
+}
+
+#[cfg(feature = "signing")]
+mod crypto {
+    use secp256k1_zkp::schnorr::Signature;
+    use secp256k1_zkp::{Keypair, Message, SECP256K1, SecretKey, XOnlyPublicKey, rand};
+
+    use super::*;
+
+    /// Secret key of the verifier signing validation reports.
+    pub struct ReportSecret(Keypair);
+
+    impl ReportSecret {
+        pub fn random() -> Self { Self(Keypair::new(SECP256K1, &mut rand::thread_rng())) }
+
+        pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+            SecretKey::from_slice(&bytes)
+                .ok()
+                .map(|sk| Self(Keypair::from_secret_key(SECP256K1, &sk)))
+        }
+
+        pub fn to_bytes(&self) -> [u8; 32] { self.0.secret_bytes() }
+
+        pub fn signer(&self) -> ReportSigner {
+            ReportSigner::from(self.0.x_only_public_key().0.serialize())
+        }
+
+        /// Produces BIP-340 signature of a 32-byte digest.
+        pub fn sign_digest(&self, digest: [u8; 32]) -> Bytes64 {
+            let msg = Message::from_digest(digest);
+            let signature = SECP256K1.sign_schnorr(&msg, &self.0);
+            Bytes64::from(*signature.as_ref())
+        }
+
+        /// Signs the report.
+        pub fn sign(&self, report: ValidationReport) -> SignedReport {
+            SignedReport {
+                signature: self.sign_digest(report.report_id().to_byte_array()),
+                report,
+                signer: self.signer(),
+            }
+        }
+    }
+
+    impl SignedReport {
+        /// Verifies the report signature, returning the report id.
+        pub fn verify_signature(&self) -> Result<ReportId, ReportError> {
+            let report_id = self.report.report_id();
+            let msg = Message::from_digest(report_id.to_byte_array());
+            let signer = XOnlyPublicKey::from_slice(self.signer.as_slice())
+                .map_err(|_| ReportError::InvalidSignature(report_id))?;
+            let signature = Signature::from_slice(self.signature.as_slice())
+                .map_err(|_| ReportError::InvalidSignature(report_id))?;
+            SECP256K1
+                .verify_schnorr(&signature, &msg, &signer)
+                .map_err(|_| ReportError::InvalidSignature(report_id))?;
+            Ok(report_id)
+        }
+
+        /// Verifies that the report is signed by a trusted verifier and
+        /// confirms validity of the consignment for the expected contract.
+        /// Reports with non-mined terminals are accepted, since they are
+        /// checked by the online side.
+        pub fn verify(
+            &self,
+            trusted: ReportSigner,
+            contract_id: ContractId,
+        ) -> Result<ReportId, ReportError> {
+            if self.signer != trusted {
+                return Err(ReportError::UntrustedSigner(self.signer));
+            }
+            let report_id = self.verify_signature()?;
+            if self.report.contract_id != contract_id {
+                return Err(ReportError::ContractMismatch(
+                    report_id,
+                    self.report.contract_id,
+                    contract_id,
+                ));
+            }
+            match self.report.validity {
+                Validity::Valid | Validity::UnminedTerminals => Ok(report_id),
+                validity => Err(ReportError::NotValid(report_id, validity)),
+            }
+        }
+    }
+}
+#[cfg(feature = "signing")]
+pub use crypto::ReportSecret;
+
+#[cfg(test)]
+#[cfg(feature = "signing")]
+mod test {
+    use super::*;
+    use crate::testing::Scenario;
+
+    #[test]
+    fn signed_report() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        let policy = ValidationPolicy::default();
+        let status = scenario.validate(policy);
+        let report =
+            ValidationReport::new(scenario.consignment(), &status, ChainNet::Regtest, policy);
+        assert_eq!(report.terminals.len(), 1);
+        let contract_id = report.contract_id;
+
+        let secret = ReportSecret::random();
+        let signed = secret.sign(report.clone());
+        let data = signed
+            .to_strict_serialized::<{ u16::MAX as usize }>()
+            .unwrap();
+        let signed = SignedReport::from_strict_serialized::<{ u16::MAX as usize }>(data).unwrap();
+        assert_eq!(signed.verify(secret.signer(), contract_id), Ok(report.report_id()));
+
+        let other = ReportSecret::random();
+        assert_eq!(
+            signed.verify(other.signer(), contract_id),
+            Err(ReportError::UntrustedSigner(secret.signer()))
+        );
+        assert_eq!(
+            signed.verify(secret.signer(), ContractId::from([0u8; 32])),
+            Err(ReportError::ContractMismatch(
+                report.report_id(),
+                contract_id,
+                ContractId::from([0u8; 32])
+            ))
+        );
+
+        let mut forged = signed.clone();
+        forged.report.validity = Validity::Invalid;
+        assert_eq!(
+            forged.verify(secret.signer(), contract_id),
+            Err(ReportError::InvalidSignature(forged.report.report_id()))
+        );
+        let mut invalid = report;
+        invalid.validity = Validity::Invalid;
+        let signed = secret.sign(invalid.clone());
+        assert_eq!(
+            signed.verify(secret.signer(), contract_id),
+            Err(ReportError::NotValid(invalid.report_id(), Validity::Invalid))
+        );
+    }
+}
@@ -0,0 +1,232 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versions of the consensus rules applied by the validator.
+//!
+//! Checks listed in [`ConsensusCheck`] are performed by the validator only
+//! when [`ConsensusRules::requires`] them, so each version of the rules
+//! reproduces the verdicts of its release, and [`ConsensusRules::diff`]
+//! reports the differences at runtime.
+
+use std::collections::BTreeSet;
+
+use crate::LIB_NAME_RGB;
+
+/// Version of the consensus rules used to validate contract histories.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Display)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[repr(u8)]
+pub enum ConsensusRules {
+    /// Rules of the v0.11.0-beta.6 release.
+    #[display("v0.11.0-beta.6")]
+    V0_11Beta6 = 0,
+
+    /// Current rules.
+    #[default]
+    #[display("v0.11.0")]
+    V0_11 = 1,
+}
+
+impl ConsensusRules {
+    /// Most recent version of the rules.
+    pub const LATEST: Self = ConsensusRules::V0_11;
+
+    /// All known versions of the rules, from the oldest to the most recent.
+    pub const ALL: [Self; 2] = [ConsensusRules::V0_11Beta6, ConsensusRules::V0_11];
+
+    /// Detects whether the version of the rules requires the check.
+    pub fn requires(self, check: ConsensusCheck) -> bool {
+        match (self, check) {
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::ChainGenesis) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::ScriptLibIds) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::UniqueSeals) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::BundleInputsCommitted) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::BundleDoubleSpend) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::ReservedTypeIds) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::ChainNetwork) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::ResourceLimits) => false,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::LegacySchemaLayout) => true,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::LegacyFfv) => true,
+            (ConsensusRules::V0_11Beta6, ConsensusCheck::DeclaredMetaTypes) => true,
+
+            (ConsensusRules::V0_11, ConsensusCheck::ChainGenesis) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::ScriptLibIds) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::UniqueSeals) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::BundleInputsCommitted) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::BundleDoubleSpend) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::ReservedTypeIds) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::ChainNetwork) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::ResourceLimits) => true,
+            (ConsensusRules::V0_11, ConsensusCheck::LegacySchemaLayout) => false,
+            (ConsensusRules::V0_11, ConsensusCheck::LegacyFfv) => false,
+            (ConsensusRules::V0_11, ConsensusCheck::DeclaredMetaTypes) => false,
+        }
+    }
+
+    /// Lists checks required by this version of the rules.
+    pub fn checks(self) -> BTreeSet<ConsensusCheck> {
+        ConsensusCheck::ALL
+            .into_iter()
+            .filter(|check| self.requires(*check))
+            .collect()
+    }
+
+    /// Lists checks which are required by one of the versions of the rules
+    /// and not by the other.
+    pub fn diff(self, other: Self) -> BTreeSet<ConsensusCheck> {
+        ConsensusCheck::ALL
+            .into_iter()
+            .filter(|check| self.requires(*check) != other.requires(*check))
+            .collect()
+    }
+}
+
+/// Validation check which is not required by all versions of the consensus
+/// rules and can be skipped by the validator.
+///
+/// Checks which are added by a release are required by its rules and the later
+/// ones; checks rejecting data introduced by a release are required only by
+/// the rules of the earlier releases.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum ConsensusCheck {
+    /// Genesis block hash of each chain the contract uses, as reported by the
+    /// resolver, must match one of the trust anchors registered for the
+    /// contract chain network (see [`crate::ChainTrustAnchors`]).
+    #[display("chain genesis")]
+    ChainGenesis,
+
+    /// Libraries supplied with a consignment must have the ids they are
+    /// referenced by.
+    #[display("script library ids")]
+    ScriptLibIds,
+
+    /// An operation, or the transitions of a bundle, must not assign state of
+    /// the same type to the same seal twice, unless the schema allows merging
+    /// (see [`crate::SchemaFlags::MERGE_DUPLICATE_SEALS`]). Seals are compared
+    /// in their concealed form, i.e. must have the same blinding factor.
+    ///
+    /// Since none of the schemata created before the check existed has the
+    /// flag set, their histories containing such assignments, which were
+    /// valid under v0.11.0-beta.6, are now rejected.
+    #[display("seal uniqueness")]
+    UniqueSeals,
+
+    /// All inputs of the bundle transitions must be committed by the bundle
+    /// input map.
+    #[display("bundle input commitments")]
+    BundleInputsCommitted,
+
+    /// Transitions of a bundle must spend distinct outputs.
+    #[display("bundle double spends")]
+    BundleDoubleSpend,
+
+    /// Schema must not declare metadata types reserved for the embedded
+    /// consensus rules (see [`crate::Schema::reserved_meta_types`]).
+    #[display("reserved type ids")]
+    ReservedTypeIds,
+
+    /// Contract must be issued on the chain network the validator runs on.
+    /// Without the check, only the networks being a testnet or not are
+    /// compared, since v0.11.0-beta.6 genesis commits just to a testnet flag,
+    /// and a mismatch is reported as [`super::Failure::TestnetMismatch`].
+    #[display("chain network")]
+    ChainNetwork,
+
+    /// Traversal of the operation graph must not exceed the limits on the
+    /// number of operations, depth and fan-in (see [`super::ResourceLimits`]).
+    #[display("resource limits")]
+    ResourceLimits,
+
+    /// Schema must use the v0.11.0-beta.6 layout, where the fast-forward
+    /// version, flags, global state kinds and fixed-supply and key registry
+    /// declarations are reserved and must be zero.
+    #[display("legacy schema layout")]
+    LegacySchemaLayout,
+
+    /// Operations must have v0.11.0-beta.6 fast-forward version
+    /// [`crate::Ffv::V0`], which commits to the operation metadata by its
+    /// strict hash.
+    #[display("legacy fast-forward version")]
+    LegacyFfv,
+
+    /// Operations may contain only metadata types declared by the schema,
+    /// including the ones with embedded semantics (see [`crate::KnownType`]),
+    /// which later rules accept without declaration. This also excludes
+    /// operations under the successor schema of a contract migration (see
+    /// [`crate::Migration`]).
+    #[display("declared metadata types")]
+    DeclaredMetaTypes,
+}
+
+impl ConsensusCheck {
+    /// All checks which can be skipped depending on the version of the rules.
+    pub const ALL: [Self; 11] = [
+        ConsensusCheck::ChainGenesis,
+        ConsensusCheck::ScriptLibIds,
+        ConsensusCheck::UniqueSeals,
+        ConsensusCheck::BundleInputsCommitted,
+        ConsensusCheck::BundleDoubleSpend,
+        ConsensusCheck::ReservedTypeIds,
+        ConsensusCheck::ChainNetwork,
+        ConsensusCheck::ResourceLimits,
+        ConsensusCheck::LegacySchemaLayout,
+        ConsensusCheck::LegacyFfv,
+        ConsensusCheck::DeclaredMetaTypes,
+    ];
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rules_diff() {
+        assert_eq!(ConsensusRules::default(), ConsensusRules::LATEST);
+        assert_eq!(ConsensusRules::LATEST, *ConsensusRules::ALL.last().unwrap());
+        let legacy = bset! {
+            ConsensusCheck::LegacySchemaLayout,
+            ConsensusCheck::LegacyFfv,
+            ConsensusCheck::DeclaredMetaTypes,
+        };
+        assert_eq!(ConsensusRules::V0_11Beta6.checks(), legacy);
+        assert_eq!(ConsensusRules::LATEST.checks(), &BTreeSet::from(ConsensusCheck::ALL) - &legacy);
+        assert_eq!(
+            ConsensusRules::V0_11Beta6.diff(ConsensusRules::V0_11),
+            BTreeSet::from(ConsensusCheck::ALL)
+        );
+        for rules in ConsensusRules::ALL {
+            assert!(rules.diff(rules).is_empty());
+        }
+        assert_eq!(ConsensusRules::V0_11Beta6.to_string(), "v0.11.0-beta.6");
+    }
+}
@@ -22,12 +22,36 @@
 
 use strict_types::TypeSystem;
 
-use crate::{validation, OpFullType, OpSchema, OwnedStateSchema, Schema, TransitionType};
+use crate::validation::{ConsensusCheck, ConsensusRules};
+use crate::{
+    AssignmentType, OpFullType, OpSchema, OwnedStateSchema, RegistrySchema, Schema, SchemaFlags,
+    SupplySchema, TransitionType, validation,
+};
 
 impl Schema {
+    /// Verifies the schema under the latest consensus rules.
     pub fn verify(&self, types: &TypeSystem) -> validation::Status {
+        self.verify_with_rules(types, ConsensusRules::LATEST)
+    }
+
+    /// Verifies the schema under the given version of the consensus rules.
+    pub fn verify_with_rules(
+        &self,
+        types: &TypeSystem,
+        rules: ConsensusRules,
+    ) -> validation::Status {
+        // Schemata using the data reserved by the v0.11.0-beta.6 layout can't be read
+        // by that release, so none of their other properties are checked
+        if rules.requires(ConsensusCheck::LegacySchemaLayout) && !self.has_legacy_layout() {
+            return validation::Status::with_failure(validation::Failure::SchemaLayoutUnsupported);
+        }
+
         let mut status = validation::Status::new();
 
+        if self.flags.unknown_bits() != 0 {
+            status.add_failure(validation::Failure::SchemaUnknownFlags(self.flags.unknown_bits()));
+        }
+
         status += self.verify_operation(OpFullType::Genesis, &self.genesis);
         for (type_id, schema) in &self.transitions {
             status += self.verify_operation(OpFullType::StateTransition(*type_id), schema);
@@ -39,6 +63,81 @@ impl Schema {
         if self.transitions.contains_key(&TransitionType::BLANK) {
             status.add_failure(validation::Failure::SchemaBlankTransitionRedefined);
         }
+        if self.flags.contains(SchemaFlags::ISSUER_AUTHORITY) &&
+            self.owned_types
+                .contains_key(&AssignmentType::ISSUER_AUTHORITY)
+        {
+            status.add_failure(validation::Failure::SchemaAuthorityRedefined);
+        }
+
+        let ricardian = self
+            .global_types
+            .iter()
+            .filter(|(_, schema)| schema.is_ricardian())
+            .map(|(ty, _)| *ty)
+            .collect::<Vec<_>>();
+        if ricardian.len() > 1 {
+            status.add_failure(validation::Failure::SchemaRicardianMultiple(ricardian[1]));
+        }
+
+        let fixed_supply = self.flags.contains(SchemaFlags::FIXED_SUPPLY);
+        let SupplySchema {
+            assignment_type,
+            global_type,
+        } = self.fixed_supply;
+        if (fixed_supply || self.fixed_supply != SupplySchema::default()) &&
+            (!fixed_supply ||
+                !matches!(
+                    self.owned_types.get(&assignment_type),
+                    Some(OwnedStateSchema::Fungible(_))
+                ) ||
+                !self.global_types.contains_key(&global_type))
+        {
+            status.add_failure(validation::Failure::SchemaFixedSupplyInvalid(
+                assignment_type,
+                global_type,
+            ));
+        }
+        if fixed_supply {
+            for (type_id, schema) in &self.owned_types {
+                if !matches!(schema, OwnedStateSchema::Fungible(_)) {
+                    continue;
+                }
+                if *type_id != assignment_type ||
+                    self.extensions
+                        .values()
+                        .any(|ext| ext.assignments.contains_key(type_id))
+                {
+                    status.add_failure(validation::Failure::SchemaInflatable(*type_id));
+                }
+            }
+        }
+
+        let registry = self.flags.contains(SchemaFlags::KEY_REGISTRY);
+        let RegistrySchema {
+            assignment_type,
+            key_len,
+        } = self.registry;
+        if (registry || self.registry != RegistrySchema::default()) &&
+            (!registry ||
+                key_len == 0 ||
+                !matches!(
+                    self.owned_types.get(&assignment_type),
+                    Some(OwnedStateSchema::Structured(_))
+                ))
+        {
+            status
+                .add_failure(validation::Failure::SchemaRegistryInvalid(assignment_type, key_len));
+        }
+
+        if rules.requires(ConsensusCheck::ReservedTypeIds) {
+            let reserved = self.reserved_meta_types();
+            for type_id in self.meta_types.keys() {
+                if reserved.contains(type_id) {
+                    status.add_failure(validation::Failure::SchemaReservedMetaType(*type_id));
+                }
+            }
+        }
 
         for (type_id, sem_id) in &self.meta_types {
             if !types.contains_key(sem_id) {
@@ -47,6 +146,9 @@ impl Schema {
         }
 
         for (type_id, schema) in &self.global_types {
+            if schema.is_accumulator() && schema.max_items > 1 {
+                status.add_failure(validation::Failure::SchemaAccumulatorInvalid(*type_id));
+            }
             if !types.contains_key(&schema.sem_id) {
                 status.add_failure(validation::Failure::SchemaGlobalSemIdUnknown(
                     *type_id,
@@ -0,0 +1,598 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SPV proofs of the witness transaction mining.
+//!
+//! Consignments may embed witness transactions together with merkle proofs of
+//! their inclusion into blocks ([`WitnessProofs`]). This allows validation
+//! without a full resolver: [`SpvResolver`] checks the proofs against a chain
+//! of block headers provided by the caller, requiring a given number of
+//! confirmations to protect against chain reorganizations.
+//!
+//! Headers are trusted: [`Headers`] checks that they form a chain with valid
+//! proof of work and accounts for the accumulated chainwork, but it is up to
+//! the caller to ensure that the chain is the most-work chain, including
+//! difficulty adjustments. Air-gapped signers may receive the headers together
+//! with the consignment as a [`HeaderBundle`]. Proofs are supported
+//! only for the bitcoin layer 1.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::{LargeOrdMap, LargeVec, TinyVec};
+use amplify::num::u256;
+use amplify::{ByteArray, Bytes32};
+use bp::{BlockHash, BlockHeader, BlockMerkleRoot, Txid};
+use commit_verify::{DigestExt, Sha256};
+
+use crate::validation::{ResolveWitness, WitnessResolverError};
+use crate::{
+    LIB_NAME_RGB, Layer1, MapView, WitnessOrd, WitnessPos, XChain, XWitnessId, XWitnessTx,
+};
+
+fn sha256d(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut engine = Sha256::default();
+    engine.input_raw(left);
+    engine.input_raw(right);
+    let mut double = Sha256::default();
+    double.input_raw(&engine.finish());
+    double.finish()
+}
+
+/// Computes merkle root of the transactions of a block.
+pub fn block_merkle_root(txids: &[Txid]) -> BlockMerkleRoot {
+    let mut level = txids.iter().map(Txid::to_byte_array).collect::<Vec<_>>();
+    if level.is_empty() {
+        return BlockMerkleRoot::from([0u8; 32]);
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| sha256d(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+    }
+    BlockMerkleRoot::from(level[0])
+}
+
+/// Merkle proof of inclusion of a transaction into a block.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct MerkleProof {
+    pub block_hash: BlockHash,
+    /// Position of the transaction in the block.
+    pub position: u32,
+    /// Hashes of the sibling nodes, starting from the leaf level.
+    pub path: TinyVec<Bytes32>,
+}
+
+impl MerkleProof {
+    /// Constructs proof for a transaction at a given position from the list of
+    /// all block transactions.
+    pub fn build(block_hash: BlockHash, txids: &[Txid], position: u32) -> Option<Self> {
+        let mut index = position as usize;
+        if index >= txids.len() {
+            return None;
+        }
+        let mut level = txids.iter().map(Txid::to_byte_array).collect::<Vec<_>>();
+        let mut path = vec![];
+        while level.len() > 1 {
+            let sibling = level.get(index ^ 1).unwrap_or(&level[index]);
+            path.push(Bytes32::from(*sibling));
+            level = level
+                .chunks(2)
+                .map(|pair| sha256d(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            index /= 2;
+        }
+        Some(MerkleProof {
+            block_hash,
+            position,
+            path: TinyVec::try_from(path).expect("block can't have more than 2^32 transactions"),
+        })
+    }
+
+    /// Computes merkle root of the block from the transaction id, returning
+    /// `None` if the position doesn't match the proof depth.
+    pub fn merkle_root(&self, txid: Txid) -> Option<BlockMerkleRoot> {
+        if self.path.len() < 32 && self.position >> self.path.len() != 0 {
+            return None;
+        }
+        let mut node = txid.to_byte_array();
+        let mut index = self.position;
+        for sibling in &self.path {
+            node = if index & 1 == 0 {
+                sha256d(&node, &sibling.to_byte_array())
+            } else {
+                sha256d(&sibling.to_byte_array(), &node)
+            };
+            index >>= 1;
+        }
+        Some(BlockMerkleRoot::from(node))
+    }
+}
+
+/// Witness transaction with the proof of its mining.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SpvWitness {
+    pub tx: XWitnessTx,
+    pub proof: MerkleProof,
+}
+
+/// Witness transactions with SPV proofs, which may be embedded into
+/// consignments.
+#[derive(Wrapper, WrapperMut, Clone, PartialEq, Eq, Debug, Default, From)]
+#[wrapper(Deref)]
+#[wrapper_mut(DerefMut)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct WitnessProofs(LargeOrdMap<XWitnessId, SpvWitness>);
+
+impl WitnessProofs {
+    pub fn new() -> Self { default!() }
+
+    /// Read-only view of the witness transactions with their proofs.
+    pub fn view(&self) -> MapView<'_, XWitnessId, SpvWitness> { MapView::new(&self.0) }
+
+    /// Adds witness transaction with its proof, returning the witness id.
+    pub fn add(&mut self, tx: XWitnessTx, proof: MerkleProof) -> XWitnessId {
+        let witness_id = tx.witness_id();
+        self.0
+            .insert(witness_id, SpvWitness { tx, proof })
+            .expect("number of witnesses exceeds 2^32");
+        witness_id
+    }
+}
+
+/// Errors verifying chain of block headers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum HeaderError {
+    /// header chain doesn't contain any blocks.
+    Empty,
+
+    /// block {0} doesn't extend the tip of the header chain.
+    Disconnected(BlockHash),
+
+    /// block {0} doesn't satisfy the proof of work target.
+    InvalidPow(BlockHash),
+
+    /// header chain has less accumulated work than required.
+    InsufficientWork,
+}
+
+/// Source of block headers trusted by the caller.
+pub trait HeaderSource {
+    /// Returns height and header of a block with a given hash.
+    fn header_by_hash(&self, block_hash: BlockHash) -> Option<(u32, BlockHeader)>;
+
+    /// Returns header of a block at a given height.
+    fn header_at(&self, height: u32) -> Option<BlockHeader>;
+
+    /// Returns height of the most recent block in the chain.
+    fn tip_height(&self) -> u32;
+
+    /// Returns work accumulated by the blocks known to the source.
+    fn chainwork(&self) -> u256;
+
+    /// Checks that the blocks known to the source accumulate at least
+    /// `min_work` of proof of work.
+    fn verify_chainwork(&self, min_work: u256) -> Result<(), HeaderError> {
+        if self.chainwork() < min_work {
+            return Err(HeaderError::InsufficientWork);
+        }
+        Ok(())
+    }
+
+    /// Returns hash of the genesis block, if the chain starts from it.
+    fn genesis_hash(&self) -> Option<BlockHash> { None }
+}
+
+/// Block headers which may be bundled with a consignment for the validation
+/// by offline signers.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct HeaderBundle {
+    /// Height of the first block.
+    pub start_height: u32,
+    pub headers: LargeVec<BlockHeader>,
+}
+
+impl HeaderBundle {
+    /// Verifies that the headers form a chain with valid proof of work,
+    /// returning the chain.
+    pub fn verify(&self) -> Result<Headers, HeaderError> {
+        let mut iter = self.headers.iter();
+        let first = iter.next().ok_or(HeaderError::Empty)?;
+        let mut headers = Headers::with(self.start_height, *first)?;
+        for header in iter {
+            headers.push(*header)?;
+        }
+        Ok(headers)
+    }
+}
+
+impl From<&Headers> for HeaderBundle {
+    fn from(headers: &Headers) -> Self {
+        HeaderBundle {
+            start_height: headers.start_height,
+            headers: LargeVec::try_from(headers.headers.clone())
+                .expect("header chain is limited by u32 height"),
+        }
+    }
+}
+
+/// Chain of block headers starting at some height, checked for the proof of
+/// work and the links between the blocks.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Headers {
+    start_height: u32,
+    headers: Vec<BlockHeader>,
+    index: BTreeMap<BlockHash, u32>,
+    chainwork: u256,
+}
+
+impl Headers {
+    /// Starts chain with a checkpoint block at a given height.
+    pub fn with(start_height: u32, checkpoint: BlockHeader) -> Result<Self, HeaderError> {
+        let block_hash = check_pow(&checkpoint)?;
+        Ok(Headers {
+            start_height,
+            headers: vec![checkpoint],
+            index: bmap! { block_hash => start_height },
+            chainwork: block_work(checkpoint.bits),
+        })
+    }
+
+    /// Extends the chain with a new block.
+    pub fn push(&mut self, header: BlockHeader) -> Result<(), HeaderError> {
+        let tip = self.headers.last().expect("header chain is never empty");
+        let block_hash = header.block_hash();
+        if header.prev_block_hash != tip.block_hash() {
+            return Err(HeaderError::Disconnected(block_hash));
+        }
+        check_pow(&header)?;
+        self.headers.push(header);
+        self.index.insert(block_hash, self.tip_height());
+        self.chainwork = self.chainwork.saturating_add(block_work(header.bits));
+        Ok(())
+    }
+}
+
+impl HeaderSource for Headers {
+    fn header_by_hash(&self, block_hash: BlockHash) -> Option<(u32, BlockHeader)> {
+        let height = *self.index.get(&block_hash)?;
+        Some((height, self.headers[(height - self.start_height) as usize]))
+    }
+
+    fn header_at(&self, height: u32) -> Option<BlockHeader> {
+        let index = height.checked_sub(self.start_height)?;
+        self.headers.get(index as usize).copied()
+    }
+
+    fn tip_height(&self) -> u32 { self.start_height + self.headers.len() as u32 - 1 }
+
+    fn chainwork(&self) -> u256 { self.chainwork }
+
+    fn genesis_hash(&self) -> Option<BlockHash> {
+        Some(self.headers[0].block_hash()).filter(|_| self.start_height == 0)
+    }
+}
+
+/// Decodes proof of work target from its compact representation, returning
+/// `None` for negative, zero or overflowing targets.
+fn target(bits: u32) -> Option<u256> {
+    let exponent = bits >> 24;
+    let mantissa = bits & 0x007F_FFFF;
+    if bits & 0x0080_0000 != 0 || mantissa == 0 {
+        return None;
+    }
+    match exponent {
+        0..=3 => Some(u256::from(mantissa >> (8 * (3 - exponent)))).filter(|t| *t != u256::ZERO),
+        4..=32 => Some(u256::from(mantissa) << (8 * (exponent - 3)) as usize)
+            .filter(|target| *target >> (8 * (exponent - 3)) as usize == u256::from(mantissa)),
+        _ => None,
+    }
+}
+
+/// Computes expected number of hashes required to mine a block with a given
+/// compact proof of work target.
+pub fn block_work(bits: u32) -> u256 {
+    match target(bits) {
+        // 2^256 / (target + 1), computed without overflowing 256 bits
+        Some(target) if target != u256::MAX => (!target / (target + u256::ONE)) + u256::ONE,
+        Some(_) => u256::ONE,
+        None => u256::ZERO,
+    }
+}
+
+fn check_pow(header: &BlockHeader) -> Result<BlockHash, HeaderError> {
+    let block_hash = header.block_hash();
+    match target(header.bits) {
+        Some(target) if u256::from_le_bytes(block_hash.to_byte_array()) <= target => Ok(block_hash),
+        _ => Err(HeaderError::InvalidPow(block_hash)),
+    }
+}
+
+/// Resolver of witness transactions using SPV proofs embedded into a
+/// consignment.
+pub struct SpvResolver<'proofs, 'headers, H: HeaderSource> {
+    proofs: &'proofs WitnessProofs,
+    headers: &'headers H,
+    min_confirmations: u32,
+}
+
+impl<'proofs, 'headers, H: HeaderSource> SpvResolver<'proofs, 'headers, H> {
+    pub fn new(proofs: &'proofs WitnessProofs, headers: &'headers H) -> Self {
+        SpvResolver {
+            proofs,
+            headers,
+            min_confirmations: 1,
+        }
+    }
+
+    /// Sets number of confirmations required for a witness to be reported as
+    /// mined. Witnesses with fewer confirmations are reported as off-chain and
+    /// are rejected by the validation policies not allowing unconfirmed
+    /// witnesses.
+    pub fn with_min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    fn verify(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<(&'proofs SpvWitness, u32, BlockHeader), WitnessResolverError> {
+        let witness = self
+            .proofs
+            .get(&witness_id)
+            .ok_or(WitnessResolverError::Unknown(witness_id))?;
+        let XChain::Bitcoin(tx) = &witness.tx else {
+            return Err(WitnessResolverError::Other(
+                witness_id,
+                s!("SPV proofs are supported only for bitcoin"),
+            ));
+        };
+        if XChain::Bitcoin(tx.txid()) != witness_id {
+            return Err(WitnessResolverError::Other(
+                witness_id,
+                s!("SPV witness transaction doesn't match its id"),
+            ));
+        }
+        let Some((height, header)) = self.headers.header_by_hash(witness.proof.block_hash) else {
+            return Err(WitnessResolverError::Other(
+                witness_id,
+                format!("block {} is not a part of the header chain", witness.proof.block_hash),
+            ));
+        };
+        if witness.proof.merkle_root(tx.txid()) != Some(header.merkle_root) {
+            return Err(WitnessResolverError::Other(witness_id, s!("invalid SPV merkle proof")));
+        }
+        Ok((witness, height, header))
+    }
+}
+
+impl<H: HeaderSource> ResolveWitness for SpvResolver<'_, '_, H> {
+    fn resolve_pub_witness(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<XWitnessTx, WitnessResolverError> {
+        self.verify(witness_id)
+            .map(|(witness, ..)| witness.tx.clone())
+    }
+
+    fn resolve_genesis_hash(
+        &self,
+        layer1: Layer1,
+    ) -> Result<Option<BlockHash>, WitnessResolverError> {
+        Ok(self
+            .headers
+            .genesis_hash()
+            .filter(|_| layer1 == Layer1::Bitcoin))
+    }
+
+    fn resolve_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        let (_, height, header) = self.verify(witness_id)?;
+        let confirmations = self.headers.tip_height().saturating_sub(height) + 1;
+        if confirmations < self.min_confirmations {
+            return Ok(WitnessOrd::OffChain);
+        }
+        WitnessPos::new(height, header.time as i64)
+            .map(WitnessOrd::OnChain)
+            .ok_or_else(|| {
+                WitnessResolverError::Other(witness_id, s!("invalid block height or timestamp"))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement::Confined;
+    use bp::{LockTime, Outpoint, ScriptPubkey, SeqNo, Tx, TxIn, TxOut, TxVer};
+
+    use super::*;
+
+    const REGTEST_BITS: u32 = 0x207f_ffff;
+
+    fn mine(prev_block_hash: BlockHash, merkle_root: BlockMerkleRoot, time: u32) -> BlockHeader {
+        let mut header = BlockHeader {
+            version: 4,
+            prev_block_hash,
+            merkle_root,
+            time,
+            bits: REGTEST_BITS,
+            nonce: 0,
+        };
+        while check_pow(&header).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    fn tx(vout: u32) -> Tx {
+        Tx {
+            version: TxVer::V2,
+            inputs: Confined::try_from(vec![TxIn {
+                prev_output: Outpoint::new(Txid::from([0x11; 32]), vout),
+                sig_script: none!(),
+                sequence: SeqNo::from_consensus_u32(0xFFFF_FFFF),
+                witness: none!(),
+            }])
+            .unwrap(),
+            outputs: Confined::try_from(vec![TxOut::new(
+                ScriptPubkey::p2wpkh([0x6c; 20]),
+                1000u64,
+            )])
+            .unwrap(),
+            lock_time: LockTime::ZERO,
+        }
+    }
+
+    #[test]
+    fn merkle_root() {
+        // Block 100000 of the bitcoin mainnet
+        let txids = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+        ]
+        .map(|s| Txid::from_str(s).unwrap());
+        let root = BlockMerkleRoot::from_str(
+            "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766",
+        )
+        .unwrap();
+        assert_eq!(block_merkle_root(&txids), root);
+        for (pos, txid) in txids.iter().enumerate() {
+            let proof = MerkleProof::build(BlockHash::from([0u8; 32]), &txids, pos as u32).unwrap();
+            assert_eq!(proof.merkle_root(*txid), Some(root));
+        }
+        // Odd number of transactions
+        let proof = MerkleProof::build(BlockHash::from([0u8; 32]), &txids[..3], 2).unwrap();
+        assert_eq!(proof.merkle_root(txids[2]), Some(block_merkle_root(&txids[..3])));
+        assert_eq!(MerkleProof::build(BlockHash::from([0u8; 32]), &txids, 4), None);
+    }
+
+    #[test]
+    fn spv_resolver() {
+        let witness = tx(0);
+        let txids = [tx(1).txid(), witness.txid(), tx(2).txid()];
+        let genesis = mine(BlockHash::from([0u8; 32]), block_merkle_root(&txids[..1]), 1296688602);
+        let block = mine(genesis.block_hash(), block_merkle_root(&txids), 1296688700);
+        let mut headers = Headers::with(0, genesis).unwrap();
+        headers.push(block).unwrap();
+        assert_eq!(headers.push(genesis), Err(HeaderError::Disconnected(genesis.block_hash())));
+        let mut invalid = mine(block.block_hash(), block_merkle_root(&txids), 1296688800);
+        invalid.bits = 0x1d00_ffff;
+        assert_eq!(headers.push(invalid), Err(HeaderError::InvalidPow(invalid.block_hash())));
+        headers
+            .push(mine(block.block_hash(), block_merkle_root(&txids), 1296688800))
+            .unwrap();
+
+        let mut proofs = WitnessProofs::new();
+        let proof = MerkleProof::build(block.block_hash(), &txids, 1).unwrap();
+        let witness_id = proofs.add(XChain::Bitcoin(witness.clone()), proof.clone());
+        let unmined = proofs.add(
+            XChain::Bitcoin(tx(3)),
+            MerkleProof::build(block.block_hash(), &txids, 2).unwrap(),
+        );
+
+        let resolver = SpvResolver::new(&proofs, &headers);
+        assert_eq!(resolver.resolve_pub_witness(witness_id).unwrap(), XChain::Bitcoin(witness));
+        assert_eq!(
+            resolver.resolve_witness_ord(witness_id).unwrap(),
+            WitnessOrd::OnChain(WitnessPos::new(1, 1296688700).unwrap())
+        );
+        assert_eq!(
+            resolver.resolve_genesis_hash(Layer1::Bitcoin).unwrap(),
+            Some(genesis.block_hash())
+        );
+        assert!(matches!(
+            resolver.resolve_pub_witness(unmined),
+            Err(WitnessResolverError::Other(id, _)) if id == unmined
+        ));
+        let unknown = XChain::Bitcoin(tx(4).txid());
+        assert!(matches!(
+            resolver.resolve_pub_witness(unknown),
+            Err(WitnessResolverError::Unknown(id)) if id == unknown
+        ));
+
+        let resolver = SpvResolver::new(&proofs, &headers).with_min_confirmations(3);
+        assert_eq!(resolver.resolve_witness_ord(witness_id).unwrap(), WitnessOrd::OffChain);
+    }
+
+    #[test]
+    fn header_bundle() {
+        assert_eq!(block_work(REGTEST_BITS), u256::from(2u64));
+        assert_eq!(block_work(0x1d00_ffff), u256::from(0x0001_0001_0001u64));
+        assert_eq!(block_work(0x0180_0001), u256::ZERO);
+
+        let genesis =
+            mine(BlockHash::from([0u8; 32]), BlockMerkleRoot::from([1u8; 32]), 1296688602);
+        let block = mine(genesis.block_hash(), BlockMerkleRoot::from([2u8; 32]), 1296688700);
+        let mut bundle = HeaderBundle {
+            start_height: 0,
+            headers: LargeVec::try_from(vec![genesis, block]).unwrap(),
+        };
+        let headers = bundle.verify().unwrap();
+        assert_eq!(HeaderBundle::from(&headers), bundle);
+        assert_eq!(headers.header_at(1), Some(block));
+        assert_eq!(headers.header_at(2), None);
+        assert_eq!(headers.header_by_hash(block.block_hash()), Some((1, block)));
+        assert_eq!(headers.chainwork(), u256::from(4u64));
+        assert_eq!(headers.verify_chainwork(u256::from(4u64)), Ok(()));
+        assert_eq!(headers.verify_chainwork(u256::from(5u64)), Err(HeaderError::InsufficientWork));
+
+        bundle.headers = LargeVec::try_from(vec![block, genesis]).unwrap();
+        assert_eq!(bundle.verify(), Err(HeaderError::Disconnected(genesis.block_hash())));
+        bundle.headers = none!();
+        assert_eq!(bundle.verify(), Err(HeaderError::Empty));
+    }
+}
@@ -23,9 +23,10 @@
 use strict_types::TypeSystem;
 
 use crate::schema::AssignmentType;
+use crate::validation::{Phase, Timer};
 use crate::{
-    validation, Assign, ConcealedState, ConfidentialState, ExposedSeal, ExposedState, OpId,
-    OwnedStateSchema, RevealedState,
+    Assign, ConcealedState, ConfidentialState, ExposedSeal, ExposedState, OpId, OwnedStateSchema,
+    RangeProofScheme, RevealedState, validation,
 };
 
 impl OwnedStateSchema {
@@ -34,6 +35,7 @@ impl OwnedStateSchema {
         opid: OpId,
         state_type: AssignmentType,
         data: &Assign<State, Seal>,
+        range_proofs: &dyn RangeProofScheme,
         type_system: &TypeSystem,
     ) -> validation::Status {
         let mut status = validation::Status::new();
@@ -43,7 +45,10 @@ impl OwnedStateSchema {
                     (OwnedStateSchema::Declarative, ConcealedState::Void) => {}
                     (OwnedStateSchema::Fungible(_), ConcealedState::Fungible(value)) => {
                         // [SECURITY-CRITICAL]: Bulletproofs validation
-                        if let Err(err) = value.verify_range_proof() {
+                        let timer = Timer::start();
+                        let res = value.verify_range_proof_with(range_proofs);
+                        timer.stop(&mut status, Phase::Pedersen);
+                        if let Err(err) = res {
                             status.add_failure(validation::Failure::BulletproofsInvalid(
                                 opid,
                                 state_type,
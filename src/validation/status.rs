@@ -23,31 +23,43 @@
 use core::ops::AddAssign;
 use std::fmt::{self, Display, Formatter};
 
-use bp::Txid;
+use aluvm::library::LibId;
 use commit_verify::mpc::InvalidProof;
 use strict_types::SemId;
 
+use super::ResourceLimit;
+#[cfg(feature = "metrics")]
+use super::ValidationMetrics;
 use crate::contract::Opout;
-use crate::schema::{self, SchemaId};
+use crate::schema::{self, MigrationError, MigrationId, SchemaId};
 use crate::{
-    AssignmentType, BundleId, ContractId, Layer1, OccurrencesMismatch, OpFullType, OpId,
-    SecretSeal, StateType, Vin, XChain, XGraphSeal, XOutputSeal, XWitnessId,
+    AssignmentType, BundleId, ChainError, ChainNet, ContractId, Ffv, LIB_NAME_RGB, Layer1,
+    OccurrencesMismatch, OpFullType, OpId, SecretSeal, StateType, Vin, XChain, XGraphSeal,
+    XOutpoint, XOutputSeal, XWitnessId,
 };
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
 #[repr(u8)]
 pub enum Validity {
     #[display("is valid")]
-    Valid,
+    Valid = 0,
 
     #[display("has non-mined terminal(s)")]
-    UnminedTerminals,
+    UnminedTerminals = 1,
 
     #[display("contains unknown witness transactions")]
-    UnresolvedTransactions,
+    UnresolvedTransactions = 2,
 
     #[display("is NOT valid")]
-    Invalid,
+    #[strict_type(dumb)]
+    Invalid = 3,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
@@ -58,10 +70,12 @@ pub enum Validity {
 )]
 pub struct Status {
     pub absent_pub_witnesses: Vec<XWitnessId>,
-    pub unmined_terminals: Vec<Txid>,
+    pub unmined_terminals: Vec<XWitnessId>,
     pub failures: Vec<Failure>,
     pub warnings: Vec<Warning>,
     pub info: Vec<Info>,
+    #[cfg(feature = "metrics")]
+    pub metrics: ValidationMetrics,
 }
 
 impl Display for Status {
@@ -79,8 +93,8 @@ impl Display for Status {
 
         if !self.unmined_terminals.is_empty() {
             f.write_str("Non-mined terminals:\n")?;
-            for txid in &self.unmined_terminals {
-                writeln!(f, "- {txid}")?;
+            for witness_id in &self.unmined_terminals {
+                writeln!(f, "- {witness_id}")?;
             }
         }
 
@@ -116,27 +130,24 @@ impl AddAssign for Status {
         self.failures.extend(rhs.failures);
         self.warnings.extend(rhs.warnings);
         self.info.extend(rhs.info);
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics += rhs.metrics;
+        }
     }
 }
 
 impl Status {
-    pub fn from_error(v: Failure) -> Self {
-        Status {
-            absent_pub_witnesses: vec![],
-            unmined_terminals: vec![],
-            failures: vec![v],
-            warnings: vec![],
-            info: vec![],
-        }
-    }
+    pub fn from_error(v: Failure) -> Self { Self::with_failure(v) }
 }
 
 impl FromIterator<Failure> for Status {
     fn from_iter<T: IntoIterator<Item = Failure>>(iter: T) -> Self {
-        Self {
-            failures: iter.into_iter().collect(),
-            ..Self::default()
+        let mut status = Self::default();
+        for failure in iter {
+            status.add_failure(failure);
         }
+        status
     }
 }
 
@@ -144,24 +155,32 @@ impl Status {
     pub fn new() -> Self { Self::default() }
 
     pub fn with_failure(failure: impl Into<Failure>) -> Self {
-        Self {
-            failures: vec![failure.into()],
-            ..Self::default()
-        }
+        let mut status = Self::default();
+        status.add_failure(failure);
+        status
     }
 
     pub fn add_failure(&mut self, failure: impl Into<Failure>) -> &Self {
-        self.failures.push(failure.into());
+        let failure = failure.into();
+        #[cfg(feature = "tracing")]
+        tracing::warn!(%failure, "validation failure");
+        self.failures.push(failure);
         self
     }
 
     pub fn add_warning(&mut self, warning: impl Into<Warning>) -> &Self {
-        self.warnings.push(warning.into());
+        let warning = warning.into();
+        #[cfg(feature = "tracing")]
+        tracing::info!(%warning, "validation warning");
+        self.warnings.push(warning);
         self
     }
 
     pub fn add_info(&mut self, info: impl Into<Info>) -> &Self {
-        self.info.push(info.into());
+        let info = info.into();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%info, "validation info");
+        self.info.push(info);
         self
     }
 
@@ -188,9 +207,20 @@ impl Status {
 )]
 #[display(doc_comments)]
 pub enum Failure {
+    /// the contract network doesn't match (validator runs in {0} configuration
+    /// while the contract is issued on {1}).
+    NetworkMismatch(ChainNet, ChainNet),
+
     /// the contract network doesn't match (validator runs in testnet={0}
     /// configuration).
-    NetworkMismatch(bool),
+    TestnetMismatch(bool),
+
+    /// unable to check genesis block of the {0} chain used by the resolver.
+    /// Details: {1}
+    ChainUnresolved(Layer1, String),
+
+    /// resolver operates on a chain not matching the contract. Details: {0}
+    ChainMismatch(ChainError),
 
     /// schema {actual} provided for the consignment validation doesn't match
     /// schema {expected} used by the contract. This means that the consignment
@@ -201,8 +231,21 @@ pub enum Failure {
         /// Actual schema id provided by the consignment.
         actual: SchemaId,
     },
+    /// contract schema {0} is not among the schemata accepted by the verifier.
+    SchemaNotAllowed(SchemaId),
     /// schema uses reserved type for the blank state transition.
     SchemaBlankTransitionRedefined,
+    /// schema with issuer authority declares the owned state type reserved for
+    /// the authority.
+    SchemaAuthorityRedefined,
+    /// schema declares metadata type {0} reserved for the embedded consensus
+    /// rules.
+    SchemaReservedMetaType(schema::MetaType),
+    /// schema sets unknown flags {0:#04x}.
+    SchemaUnknownFlags(u8),
+    /// schema uses fast-forward version, flags, global state kinds or
+    /// declarations which are reserved under the consensus rules.
+    SchemaLayoutUnsupported,
 
     /// schema global state #{0} uses semantic data type absent in type library
     /// ({1}).
@@ -214,6 +257,24 @@ pub enum Failure {
     /// ({1}).
     SchemaMetaSemIdUnknown(schema::MetaType, SemId),
 
+    /// schema declares global state type {0} as a Ricardian contract hash,
+    /// while another global state type is already declared as such.
+    SchemaRicardianMultiple(schema::GlobalStateType),
+    /// schema declares fixed supply of owned state type {0} issued under global
+    /// state type {1}, while {0} is not a fungible state type, {1} is not
+    /// declared, or the fixed supply flag is not set.
+    SchemaFixedSupplyInvalid(schema::AssignmentType, schema::GlobalStateType),
+    /// schema requires fixed supply, but fungible state type {0} is not the
+    /// declared fixed-supply type or can be assigned by a state extension.
+    SchemaInflatable(schema::AssignmentType),
+    /// schema declares key registry under owned state type {0} with {1}-byte
+    /// keys, while {0} is not a structured state type, the key length is zero,
+    /// or the key registry flag is not set.
+    SchemaRegistryInvalid(schema::AssignmentType, u16),
+    /// schema declares global state type {0} as an accumulator, which allows
+    /// more than a single value per operation.
+    SchemaAccumulatorInvalid(schema::GlobalStateType),
+
     /// schema for {0} has zero inputs.
     SchemaOpEmptyInputs(OpFullType),
     /// schema for {0} references undeclared metadata type {1}.
@@ -248,16 +309,89 @@ pub enum Failure {
     SchemaNoMetadata(OpId, schema::MetaType),
     /// invalid metadata in operation {0} not matching semantic type id {1}.
     SchemaInvalidMetadata(OpId, SemId),
+    /// genesis {0} contains uniqueness salt which is not a 64-bit integer.
+    GenesisSaltInvalid(OpId),
+    /// genesis {0} commits to a successor schema with an invalid id.
+    GenesisSuccessorInvalid(OpId),
+    /// contract migration {0} is invalid. Details: {1}
+    MigrationInvalid(MigrationId, MigrationError),
+    /// operation {0} commits to a contract migration which is absent from the
+    /// consignment.
+    MigrationUnknown(OpId),
+    /// operation {0} is created under the original contract schema, while it
+    /// spends state of operation {1} migrated to the successor schema.
+    MigrationReverted(OpId, OpId),
+    /// genesis {0} contains issuance split which can't be decoded, has less
+    /// than two issuers or splits owned state type unknown to the schema.
+    IssuanceSplitInvalid(OpId),
+    /// genesis {0} must assign {2} rights of type {1} to distinct seals, one
+    /// per each of the issuers.
+    IssuanceSplitMismatch(OpId, schema::AssignmentType, u8),
+    /// transition {0} contains allocation expiries which can't be decoded,
+    /// are empty or refer to allocations which it doesn't assign.
+    AllocationExpiryInvalid(OpId),
+    /// transition {0} spends expiring allocation {1} with a witness which
+    /// mining status can't be resolved, so it is not known whether the
+    /// allocation has expired.
+    AllocationExpiryUnresolved(OpId, Opout),
+    /// operation {0} assigns value of accumulator global state type {1} which
+    /// is not a 64-bit integer.
+    AccumulatorInvalid(OpId, schema::GlobalStateType),
     /// invalid global state value in operation {0}, state type #{1} which does
     /// not match semantic type id {2}.
     SchemaInvalidGlobalValue(OpId, schema::GlobalStateType, SemId),
     /// invalid owned state value in operation {0}, state type #{1} which does
     /// not match semantic type id {2}.
     SchemaInvalidOwnedValue(OpId, schema::AssignmentType, SemId),
+    /// operation {0} changes Ricardian contract text committed with global
+    /// state type {1}, while the schema doesn't allow the text to be
+    /// changed after the genesis.
+    SchemaRicardianChanged(OpId, schema::GlobalStateType),
+    /// operation {0} contains Ricardian contract hash under global state type
+    /// {1} which is not a 32-byte value.
+    SchemaInvalidRicardian(OpId, schema::GlobalStateType),
+    /// global state of type {1} in operation {0} is not a valid non-empty set
+    /// of localized texts.
+    SchemaInvalidLocalized(OpId, schema::GlobalStateType),
+    /// genesis {0} must contain a single 64-bit issued supply value under
+    /// global state type {1}.
+    IssuedSupplyInvalid(OpId, schema::GlobalStateType),
+    /// genesis {0} assigns state of type {1} not matching the issued supply of
+    /// {2}, or assigns it in concealed form.
+    IssuedSupplyMismatch(OpId, schema::AssignmentType, u64),
+    /// state transition {0} changes the fixed total supply of state type {1}.
+    FixedSupplyChanged(OpId, schema::AssignmentType),
+    /// operation {0} assigns state of type {1} with concealed amount at index
+    /// {2}, while the schema requires all amounts to be revealed.
+    ConfidentialAmount(OpId, schema::AssignmentType, u16),
     /// invalid number of input entries of type {1} in operation {0} - {2}  
     SchemaInputOccurrences(OpId, schema::AssignmentType, OccurrencesMismatch),
     /// invalid number of assignment entries of type {1} in operation {0} - {2}
     SchemaAssignmentOccurrences(OpId, schema::AssignmentType, OccurrencesMismatch),
+    /// operation {0} assigns state of type {1} to seal {2}, which is already
+    /// assigned the state of the same type by this operation or by other
+    /// operation from the same transition bundle.
+    ///
+    /// Seals are the same if their concealed forms match, i.e. they have both
+    /// the same outpoint and the same blinding factor.
+    DuplicateSeal(OpId, schema::AssignmentType, XChain<SecretSeal>),
+    /// registry binding {0} is concealed or its data are shorter than the
+    /// registry key.
+    RegistryKeyInvalid(Opout),
+    /// registry binding {1} assigns a key which is already bound by {0}.
+    RegistryKeyDuplicate(Opout, Opout),
+    /// state transition {0} spends registry binding {1} without assigning a
+    /// new binding for its key.
+    RegistryBindingRemoved(OpId, Opout),
+    /// operation {0} assigns issuer authority which is not a single revealed
+    /// structured state, or spends multiple authorities.
+    AuthorityInvalid(OpId),
+    /// operation {0} assigns issuer authority without spending the current
+    /// one.
+    AuthorityUnauthorized(OpId),
+    /// state transition {0} spends issuer authority without assigning a new
+    /// one.
+    AuthorityRemoved(OpId),
 
     // Consignment consistency errors
     /// operation {0} is referenced within the history multiple times. RGB
@@ -265,6 +399,17 @@ pub enum Failure {
     CyclicGraph(OpId),
     /// operation {0} is absent from the consignment.
     OperationAbsent(OpId),
+    /// validation of operation {opid} exceeds the limit of {max} on the
+    /// {limit}.
+    ResourceLimitExceeded {
+        opid: OpId,
+        limit: ResourceLimit,
+        max: u32,
+    },
+    /// witness transaction {0} is not mined.
+    WitnessUnconfirmed(XWitnessId),
+    /// validation was cancelled before it was complete.
+    ValidationCancelled,
     /// transition bundle {0} referenced in consignment terminals is absent from
     /// the consignment.
     TerminalBundleAbsent(BundleId),
@@ -276,6 +421,9 @@ pub enum Failure {
     WitnessIdAbsent(BundleId),
     /// operation {0} is under a different contract {1}.
     ContractMismatch(OpId, ContractId),
+    /// operation {0} uses fast-forward version {1} unknown to the consensus
+    /// rules.
+    OperationFfvUnsupported(OpId, Ffv),
 
     // Errors checking bundle commitments
     /// transition bundle {0} references state transition {1} which is not
@@ -287,6 +435,12 @@ pub enum Failure {
     /// transition bundle {0} doesn't commit to the input {1} in the witness {2}
     /// which is an input of the state transition {3}.
     BundleInvalidCommitment(BundleId, Vin, XWitnessId, OpId),
+    /// transition bundle {0} contains several state transitions spending the
+    /// same output {1}.
+    BundleDoubleSpend(BundleId, Opout),
+    /// state transition {1} from the bundle {0} closes seal at {2}, which is
+    /// not committed to the transition by the bundle input map.
+    BundleUncommittedInput(BundleId, OpId, XOutpoint),
 
     // Errors checking asset tags
     /// asset type provided in genesis references unknown fungible state of type
@@ -376,6 +530,8 @@ pub enum Failure {
     /// evaluation of AluVM script for operation {0} has failed with the code
     /// {1:?} and message {2:?}.
     ScriptFailure(OpId, Option<u8>, Option<String>),
+    /// consignment supplies library with id {1} in place of library {0}.
+    ScriptLibMismatch(LibId, LibId),
 
     /// Custom error by external services on top of RGB Core.
     #[display(inner)]
@@ -395,7 +551,17 @@ pub enum Warning {
     /// operation assignments.
     TerminalSealAbsent(OpId, XChain<SecretSeal>),
     /// terminal witness transaction {0} is not yet mined.
-    TerminalWitnessNotMined(Txid),
+    TerminalWitnessNotMined(XWitnessId),
+    /// operation {0} is absent from the pruned consignment history.
+    OperationPruned(OpId),
+    /// operation {0} uses metadata type {1} unknown to the schema.
+    UnknownMetaType(OpId, schema::MetaType),
+    /// operation {0} is not in the canonical form: it contains an empty list
+    /// of assignments, changing its encoding and disclosure hash.
+    NonCanonicalOperation(OpId),
+    /// operation {0} spends expiring allocation {1} with a witness which is
+    /// not yet mined, so it is not known whether the allocation has expired.
+    AllocationExpiryUnconfirmed(OpId, Opout),
 
     /// Custom warning by external services on top of RGB Core.
     #[display(inner)]
@@ -414,6 +580,10 @@ pub enum Info {
     /// thus was not validated.
     UncheckableConfidentialState(OpId, schema::AssignmentType),
 
+    /// operation {0} assigns state of type {1} to seal {2} more than once;
+    /// the assignments are merged as allowed by the schema.
+    DuplicateSealMerged(OpId, schema::AssignmentType, XChain<SecretSeal>),
+
     /// Custom info by external services on top of RGB Core.
     #[display(inner)]
     Custom(String),
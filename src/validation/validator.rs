@@ -20,21 +20,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::mem;
 
 use bp::dbc::Anchor;
 use bp::seals::txout::{CloseMethod, TxoSeal, Witness};
-use bp::{dbc, Outpoint};
+use bp::{BlockHash, Outpoint, dbc};
 use commit_verify::mpc;
 use single_use_seals::SealWitness;
 
-use super::status::{Failure, Warning};
-use super::{CheckedConsignment, ConsignmentApi, Status, Validity};
+use super::status::{Failure, Info, Warning};
+use super::{
+    CancelToken, CheckedConsignment, ConsensusCheck, ConsignmentApi, NeverCancel, Phase,
+    ResourceLimit, SchemaAllowlist, Status, Timer, ValidationCache, ValidationPolicy, Validity,
+};
 use crate::{
-    AltLayer1, BundleId, ContractId, DbcProof, EAnchor, Layer1, OpId, OpRef, OpType, Operation,
-    Opout, Schema, SchemaId, TransitionBundle, TypedAssigns, XChain, XOutpoint, XOutputSeal,
-    XWitnessId, XWitnessTx,
+    AllocationExpiry, AltLayer1, Assign, AssignmentType, AssignmentsRef, BundleId, ChainNet,
+    ChainTrustAnchors, ContractId, DbcProof, EAnchor, Input, Layer1, MIGRATION_TYPE, Migration,
+    OpId, OpRef, OpType, Operation, Opout, RevealedData, Schema, SchemaFlags, SchemaId, StateType,
+    TransitionBundle, WitnessOrd, XChain, XOutpoint, XOutputSeal, XWitnessId, XWitnessTx,
 };
 
 #[derive(Clone, Debug, Display, Error, From)]
@@ -44,6 +48,10 @@ pub enum WitnessResolverError {
     Unknown(XWitnessId),
     /// unable to retrieve witness {0}, {1}
     Other(XWitnessId, String),
+    /// unable to retrieve genesis block hash for {0}, {1}
+    Genesis(Layer1, String),
+    /// unable to retrieve mempool transactions for {0}, {1}
+    Mempool(Layer1, String),
 }
 
 pub trait ResolveWitness {
@@ -52,28 +60,99 @@ pub trait ResolveWitness {
         &self,
         witness_id: XWitnessId,
     ) -> Result<XWitnessTx, WitnessResolverError>;
+
+    /// Returns hash of the genesis block of the chain the resolver is connected
+    /// to for a given layer 1. Resolvers which are unable to provide this
+    /// information should return `Ok(None)`, which skips the check against
+    /// chain trust anchors.
+    fn resolve_genesis_hash(
+        &self,
+        layer1: Layer1,
+    ) -> Result<Option<BlockHash>, WitnessResolverError> {
+        let _ = layer1;
+        Ok(None)
+    }
+
+    /// Returns position of the witness transaction in the chain. Used only
+    /// when the validation policy doesn't allow unconfirmed witnesses;
+    /// resolvers which are unable to provide this information fail the
+    /// validation under such policy.
+    fn resolve_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        Err(WitnessResolverError::Other(
+            witness_id,
+            s!("resolver doesn't support witness mining status"),
+        ))
+    }
+
+    /// Returns ids of the transactions which are currently present in the
+    /// mempool of a given layer 1. Used by the double-spend early warnings
+    /// (see [`crate::ContractState::watch_conflicts`]); resolvers without
+    /// mempool access return an error.
+    fn resolve_mempool(
+        &self,
+        layer1: Layer1,
+    ) -> Result<BTreeSet<XWitnessId>, WitnessResolverError> {
+        Err(WitnessResolverError::Mempool(layer1, s!("resolver doesn't support mempool access")))
+    }
 }
 
 pub struct Validator<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness> {
     consignment: CheckedConsignment<'consignment, C>,
 
-    status: RefCell<Status>,
+    status: Status,
 
     schema_id: SchemaId,
     genesis_id: OpId,
     contract_id: ContractId,
     layers1: BTreeSet<Layer1>,
 
-    validated_op_seals: RefCell<BTreeSet<OpId>>,
-    validated_op_state: RefCell<BTreeSet<OpId>>,
+    validated_op_seals: BTreeSet<OpId>,
+    validated_op_state: BTreeSet<OpId>,
 
     resolver: &'resolver R,
+    policy: ValidationPolicy,
+    cancel: &'resolver dyn CancelToken,
+    cache: Option<ValidationCache>,
+
+    registry_keys: BTreeMap<Vec<u8>, Opout>,
+}
+
+/// Returns revealed state of the structured assignments of a given type, if
+/// any.
+fn registry_data(
+    assignments: AssignmentsRef<'_>,
+    ty: AssignmentType,
+) -> Vec<Option<&RevealedData>> {
+    match assignments {
+        AssignmentsRef::Genesis(a) => a.get(&ty).map(|assigns| {
+            assigns
+                .as_structured()
+                .iter()
+                .map(Assign::as_revealed_state)
+                .collect()
+        }),
+        AssignmentsRef::Graph(a) => a.get(&ty).map(|assigns| {
+            assigns
+                .as_structured()
+                .iter()
+                .map(Assign::as_revealed_state)
+                .collect()
+        }),
+    }
+    .unwrap_or_default()
 }
 
 impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
     Validator<'consignment, 'resolver, C, R>
 {
-    fn init(consignment: &'consignment C, resolver: &'resolver R) -> Self {
+    fn init(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        policy: ValidationPolicy,
+    ) -> Self {
         // We use validation status object to store all detected failures and
         // warnings
         let mut status = Status::default();
@@ -98,9 +177,8 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
                 // Checking for endpoint definition duplicates
                 if !transition
                     .assignments
-                    .values()
-                    .flat_map(TypedAssigns::to_confidential_seals)
-                    .any(|seal| seal == seal_endpoint)
+                    .iter_flat()
+                    .any(|(_, _, assign)| assign.to_confidential_seal() == seal_endpoint)
                 {
                     // We generate just a warning here because it's up to a user to decide whether
                     // to accept consignment with wrong endpoint list
@@ -112,15 +190,15 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
         // Validation index is used to check that all transitions presented in the
         // consignment were validated. Also, we use it to avoid double schema
         // validations for transitions.
-        let validated_op_state = RefCell::new(BTreeSet::<OpId>::new());
-        let validated_op_seals = RefCell::new(BTreeSet::<OpId>::new());
+        let validated_op_state = BTreeSet::<OpId>::new();
+        let validated_op_seals = BTreeSet::<OpId>::new();
 
         let mut layers1 = bset! { Layer1::Bitcoin };
         layers1.extend(genesis.alt_layers1.iter().map(AltLayer1::layer1));
 
         Self {
             consignment,
-            status: RefCell::new(status),
+            status,
             schema_id,
             genesis_id,
             contract_id,
@@ -128,6 +206,10 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
             validated_op_state,
             validated_op_seals,
             resolver,
+            policy,
+            cancel: &NeverCancel,
+            cache: None,
+            registry_keys: none!(),
         }
     }
 
@@ -140,56 +222,229 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
     /// logged into the status object, but the validation continues for the
     /// rest of the consignment data. This can help it debugging and
     /// detecting all problems with the consignment.
-    pub fn validate(consignment: &'consignment C, resolver: &'resolver R, testnet: bool) -> Status {
-        let mut validator = Validator::init(consignment, resolver);
+    pub fn validate(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+    ) -> Status {
+        Self::validate_with_anchors(consignment, resolver, chain_net, &ChainTrustAnchors::default())
+    }
+
+    /// Validation procedure which, in addition to [`Validator::validate`],
+    /// checks that the resolver is connected to a chain which genesis block
+    /// is registered in the provided set of trust anchors. This allows
+    /// validation of contracts issued on private regtest or custom chains.
+    pub fn validate_with_anchors(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+        anchors: &ChainTrustAnchors,
+    ) -> Status {
+        Self::validate_with_policy(consignment, resolver, chain_net, anchors, default!())
+    }
+
+    /// Validation procedure performing optional checks according to the
+    /// provided [`ValidationPolicy`].
+    pub fn validate_with_policy(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+        anchors: &ChainTrustAnchors,
+        policy: ValidationPolicy,
+    ) -> Status {
+        Self::validate_with_cancel(consignment, resolver, chain_net, anchors, policy, &NeverCancel)
+    }
+
+    /// Validation procedure like [`Validator::validate_with_policy`], which
+    /// can be aborted with the cancellation token (see [`CancelToken`]). The
+    /// token is checked before validating each operation and bundle; once it
+    /// is cancelled, the validation returns status with the failures detected
+    /// so far and [`Failure::ValidationCancelled`].
+    pub fn validate_with_cancel(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+        anchors: &ChainTrustAnchors,
+        policy: ValidationPolicy,
+        cancel: &'resolver impl CancelToken,
+    ) -> Status {
+        let mut validator = Validator::init(consignment, resolver, policy);
+        validator.cancel = cancel;
+        validator.run(chain_net, anchors);
+        validator.status
+    }
+
+    /// Validates the consignment if its contract schema is in the
+    /// `allowlist`, using the policy the allowlist defines for the schema.
+    /// Contracts under other schemata fail the validation with
+    /// [`Failure::SchemaNotAllowed`].
+    pub fn validate_allowlisted(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+        anchors: &ChainTrustAnchors,
+        allowlist: &SchemaAllowlist,
+    ) -> Status {
+        let schema_id = consignment.genesis().schema_id;
+        let Some(policy) = allowlist.policy(schema_id) else {
+            return Status::with_failure(Failure::SchemaNotAllowed(schema_id));
+        };
+        Self::validate_with_policy(consignment, resolver, chain_net, anchors, policy)
+    }
+
+    /// Validates the consignment in the same way as
+    /// [`Validator::validate_with_policy`], skipping validation against the
+    /// schema of the operations present in the `cache`. If the consignment is
+    /// valid, all of its validated operations are added to the cache.
+    ///
+    /// A cache bound to another schema or validation policy is reset.
+    pub fn validate_with_cache(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+        anchors: &ChainTrustAnchors,
+        policy: ValidationPolicy,
+        cache: &mut ValidationCache,
+    ) -> Status {
+        let mut validator = Validator::init(consignment, resolver, policy);
+        let empty = ValidationCache::new(validator.schema_id, &policy);
+        let used = mem::replace(cache, empty);
+        if used.is_valid_for(validator.schema_id, &policy) {
+            validator.cache = Some(used);
+        }
+        validator.run(chain_net, anchors);
+        if let Some(used) = validator.cache.take() {
+            *cache = used;
+        }
+        if validator.status.validity() == Validity::Valid {
+            cache.extend(validator.validated_op_state.iter().copied());
+        }
+        validator.status
+    }
+
+    fn run(&mut self, chain_net: ChainNet, anchors: &ChainTrustAnchors) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "validate",
+            contract_id = %self.contract_id,
+            %chain_net
+        )
+        .entered();
         // If the network mismatches there is no point in validating the contract since
         // all witness transactions will be missed.
-        if testnet != validator.consignment.genesis().testnet {
-            validator
-                .status
-                .borrow_mut()
-                .add_failure(Failure::NetworkMismatch(testnet));
-            return validator.status.into_inner();
+        let contract_net = self.consignment.genesis().chain_net;
+        if self.policy.rules.requires(ConsensusCheck::ChainNetwork) {
+            if chain_net != contract_net {
+                self.status
+                    .add_failure(Failure::NetworkMismatch(chain_net, contract_net));
+                return;
+            }
+        } else if chain_net.is_testnet() != contract_net.is_testnet() {
+            self.status
+                .add_failure(Failure::TestnetMismatch(chain_net.is_testnet()));
+            return;
+        }
+        if self.policy.rules.requires(ConsensusCheck::ChainGenesis) {
+            self.validate_chain(anchors);
+        }
+        if self.status.validity() == Validity::Invalid {
+            return;
         }
 
-        validator.validate_schema(consignment.schema());
+        self.validate_schema(self.consignment.schema());
+        if let Some(migration) = self.migration() {
+            self.validate_migration(migration);
+        }
         // We must return here, since if the schema is not valid there is no reason to
         // validate contract nodes against it: it will produce a plenty of errors.
-        if validator.status.borrow().validity() == Validity::Invalid {
-            return validator.status.into_inner();
+        if self.status.validity() == Validity::Invalid {
+            return;
         }
 
-        validator.validate_commitments();
+        self.validate_commitments();
         // We must return here, since if there were no proper commitments, it is
         // pointless to validate the contract state.
-        if validator.status.borrow().validity() == Validity::Invalid {
-            return validator.status.into_inner();
+        if self.status.validity() == Validity::Invalid {
+            return;
         }
 
-        validator.validate_logic();
-        // Done. Returning status report with all possible failures, issues, warnings
+        // Done. The status report contains all possible failures, issues, warnings
         // and notifications about transactions we were unable to obtain.
-        validator.status.into_inner()
+        self.validate_logic();
+    }
+
+    // *** PART 0: Chain validation
+    fn validate_chain(&mut self, anchors: &ChainTrustAnchors) {
+        let chain_net = self.consignment.genesis().chain_net;
+        for layer1 in &self.layers1 {
+            let genesis = match self.resolver.resolve_genesis_hash(*layer1) {
+                Ok(Some(genesis)) => genesis,
+                Ok(None) => continue,
+                Err(err) => {
+                    self.status
+                        .add_failure(Failure::ChainUnresolved(*layer1, err.to_string()));
+                    continue;
+                }
+            };
+            if let Err(err) = anchors.verify(*layer1, chain_net, genesis) {
+                self.status.add_failure(Failure::ChainMismatch(err));
+            }
+        }
     }
 
     // *** PART I: Schema validation
     fn validate_schema(&mut self, schema: &Schema) {
-        *self.status.borrow_mut() += schema.verify(self.consignment.types());
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("schema", schema_id = %schema.schema_id()).entered();
+        let timer = Timer::start();
+        let mut status = schema.verify_with_rules(self.consignment.types(), self.policy.rules);
+        timer.stop(&mut status, Phase::Schema);
+        self.status += status;
+
+        // [VALIDATION]: Making sure that the libraries supplied with the consignment
+        //               are the ones pinned by the schema validators, which
+        //               reference them by the hash of their code.
+        if !self.policy.rules.requires(ConsensusCheck::ScriptLibIds) {
+            return;
+        }
+        for (id, lib) in self.consignment.scripts() {
+            let actual = lib.id();
+            if *id != actual {
+                self.status
+                    .add_failure(Failure::ScriptLibMismatch(*id, actual));
+            }
+        }
+    }
+
+    /// Validates the successor schema of the contract migration and the
+    /// mapping of the contract state types onto it.
+    fn validate_migration(&mut self, migration: &Migration) {
+        let timer = Timer::start();
+        let mut status = migration
+            .schema
+            .verify_with_rules(self.consignment.types(), self.policy.rules);
+        timer.stop(&mut status, Phase::Schema);
+        self.status += status;
+
+        // [VALIDATION]: Making sure that the contract genesis commits to the successor
+        //               schema and that no contract state is stranded by the migration
+        if let Err(err) = migration.verify(self.consignment.genesis(), self.consignment.schema()) {
+            self.status
+                .add_failure(Failure::MigrationInvalid(migration.migration_id(), err));
+        }
     }
 
     // *** PART II: Validating business logic
-    fn validate_logic(&self) {
+    fn validate_logic(&mut self) {
         let schema = self.consignment.schema();
 
         // [VALIDATION]: Making sure that we were supplied with the schema
         //               that corresponds to the schema of the contract genesis
         if schema.schema_id() != self.schema_id {
-            self.status
-                .borrow_mut()
-                .add_failure(Failure::SchemaMismatch {
-                    expected: self.schema_id,
-                    actual: schema.schema_id(),
-                });
+            self.status.add_failure(Failure::SchemaMismatch {
+                expected: self.schema_id,
+                actual: schema.schema_id(),
+            });
             // Unlike other failures, here we return immediately, since there is no point
             // to validate all consignment data against an invalid schema: it will result in
             // a plenty of meaningless errors
@@ -197,34 +452,121 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
         }
 
         // [VALIDATION]: Validate genesis
-        *self.status.borrow_mut() +=
-            schema.validate_state(&self.consignment, OpRef::Genesis(self.consignment.genesis()));
-        self.validated_op_state.borrow_mut().insert(self.genesis_id);
+        let timer = Timer::start();
+        let mut status = schema.validate_state(
+            &self.consignment,
+            OpRef::Genesis(self.consignment.genesis()),
+            self.policy.rules,
+        );
+        timer.stop_nesting(&mut status, Phase::Schema);
+        self.status += status;
+        self.validated_op_state.insert(self.genesis_id);
+        // [VALIDATION]: Making sure that genesis seals are defined on the layers 1
+        //               which genesis commits to
+        self.validate_genesis_seals();
+        // [VALIDATION]: Making sure that genesis doesn't assign the same state type to a seal
+        //               multiple times
+        self.validate_unique_seals([OpRef::Genesis(self.consignment.genesis())]);
+        // [VALIDATION]: Making sure that genesis registers each key only once
+        self.validate_registry(OpRef::Genesis(self.consignment.genesis()));
+        if self.should_stop() {
+            return;
+        }
 
         // [VALIDATION]: Iterating over each endpoint, reconstructing operation
-        //               graph up to genesis for each one of them.
+        //               graph up to genesis for all of them.
         // NB: We are not aiming to validate the consignment as a whole, but instead
-        // treat it as a superposition of subgraphs, one for each endpoint; and validate
-        // them independently.
+        // treat it as a superposition of subgraphs, one for each endpoint. Since the
+        // subgraphs share their ancestors, each operation is visited only once.
+        let mut queue: VecDeque<(OpId, OpRef, u32)> = VecDeque::new();
         for (bundle_id, _) in self.consignment.terminals() {
             let Some(bundle) = self.consignment.bundle(bundle_id) else {
                 // We already checked and errored here during the terminal validation, so just
                 // skipping.
                 continue;
             };
+            bundle.precompute_ids();
             for opid in bundle.known_transitions.keys() {
-                self.validate_logic_on_route(*opid);
+                let Some(op) = self.consignment.operation(*opid) else {
+                    // Either the operation is absent, or the bundle contains transition under
+                    // a wrong id
+                    self.status.add_failure(Failure::OperationAbsent(*opid));
+                    continue;
+                };
+                queue.push_back((*opid, op, 1));
             }
         }
+        self.validate_history(queue);
     }
 
-    fn validate_logic_on_route(&self, opid: OpId) {
-        let schema = self.consignment.schema();
-        let Some(OpRef::Transition(transition)) = self.consignment.operation(opid) else {
-            panic!("provided {opid} is absent");
-        };
+    /// Computes depth of each operation reachable from the queue, which is the
+    /// length of the longest path to it from a queued operation. Distances at
+    /// which the breadth-first traversal visits the operations are the shortest
+    /// ones, thus can't be used to enforce the depth limit on histories where
+    /// the operations share ancestors. Operations which are a part of a cycle,
+    /// or are beyond the limit on the number of operations, get no depth.
+    fn history_depths(
+        &self,
+        queue: &VecDeque<(OpId, OpRef<'consignment>, u32)>,
+    ) -> BTreeMap<OpId, u32> {
+        let max_nodes = self.policy.limits.max_nodes as usize;
+        let mut parents = BTreeMap::<OpId, BTreeSet<OpId>>::new();
+        let mut children = BTreeMap::<OpId, usize>::new();
+        let mut stack = queue
+            .iter()
+            .map(|(opid, op, _)| (*opid, *op))
+            .collect::<Vec<_>>();
+        while let Some((opid, operation)) = stack.pop() {
+            if parents.contains_key(&opid) {
+                continue;
+            }
+            if parents.len() > max_nodes {
+                break;
+            }
+            let parent_ids = operation.parent_ids();
+            for prev_id in &parent_ids {
+                *children.entry(*prev_id).or_default() += 1;
+                if let Some(prev_op) = self.consignment.operation(*prev_id) {
+                    stack.push((*prev_id, prev_op));
+                }
+            }
+            parents.insert(opid, parent_ids);
+        }
 
-        let mut queue: VecDeque<OpRef> = VecDeque::new();
+        // Operations are assigned their depth once all their descendants have one
+        let mut depths = BTreeMap::<OpId, u32>::new();
+        let mut ready = parents
+            .keys()
+            .filter(|opid| !children.contains_key(*opid))
+            .map(|opid| (*opid, 1u32))
+            .collect::<Vec<_>>();
+        while let Some((opid, depth)) = ready.pop() {
+            depths.insert(opid, depth);
+            for prev_id in &parents[&opid] {
+                let prev_depth = depths.entry(*prev_id).or_default();
+                *prev_depth = (*prev_depth).max(depth + 1);
+                let count = children.get_mut(prev_id).expect("counted above");
+                *count -= 1;
+                if *count == 0 && parents.contains_key(prev_id) {
+                    ready.push((*prev_id, *prev_depth));
+                }
+            }
+        }
+        depths.retain(|opid, _| children.get(opid).map_or(true, |count| *count == 0));
+        depths
+    }
+
+    /// Validates operations in the queue and all their ancestors. Each queue
+    /// item contains operation id, which must be already checked to match the
+    /// operation, and a distance from the terminal.
+    fn validate_history(&mut self, mut queue: VecDeque<(OpId, OpRef<'consignment>, u32)>) {
+        let mut visited = BTreeSet::<OpId>::new();
+        let check_limits = self.policy.rules.requires(ConsensusCheck::ResourceLimits);
+        let depths = if check_limits {
+            self.history_depths(&queue)
+        } else {
+            none!()
+        };
 
         // Instead of constructing complex graph structures or using a recursions we
         // utilize queue to keep the track of the upstream (ancestor) nodes and make
@@ -235,95 +577,259 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
         // change to a given operation is valid against the schema + committed
         // into bitcoin transaction graph with proper anchor. That is what we are
         // checking in the code below:
-        queue.push_back(OpRef::Transition(transition));
-        while let Some(operation) = queue.pop_front() {
-            let opid = operation.id();
+        while let Some((opid, operation, depth)) = queue.pop_front() {
+            if self.should_stop() {
+                return;
+            }
+            if !visited.insert(opid) {
+                continue;
+            }
+
+            let limits = self.policy.limits;
+            if check_limits && visited.len() > limits.max_nodes as usize {
+                self.status.add_failure(Failure::ResourceLimitExceeded {
+                    opid,
+                    limit: ResourceLimit::Nodes,
+                    max: limits.max_nodes,
+                });
+                return;
+            }
+            // Longest path to the operation, unless it is a part of a cycle
+            let depth = depths.get(&opid).copied().unwrap_or(depth);
+            if check_limits && depth > limits.max_depth {
+                self.status.add_failure(Failure::ResourceLimitExceeded {
+                    opid,
+                    limit: ResourceLimit::Depth,
+                    max: limits.max_depth,
+                });
+                continue;
+            }
+            let fan_in = operation.parent_ids().len();
+            if check_limits && fan_in > limits.max_fan_in as usize {
+                self.status.add_failure(Failure::ResourceLimitExceeded {
+                    opid,
+                    limit: ResourceLimit::FanIn,
+                    max: limits.max_fan_in as u32,
+                });
+                continue;
+            }
 
             if operation.contract_id() != self.contract_id {
                 self.status
-                    .borrow_mut()
                     .add_failure(Failure::ContractMismatch(opid, operation.contract_id()));
                 continue;
             }
 
-            if !self.validated_op_seals.borrow().contains(&opid) &&
+            if !self.validated_op_seals.contains(&opid) &&
                 operation.op_type() == OpType::StateTransition
             {
-                self.status
-                    .borrow_mut()
-                    .add_failure(Failure::SealsUnvalidated(opid));
+                self.status.add_failure(Failure::SealsUnvalidated(opid));
             }
             // [VALIDATION]: Verify operation against the schema and scripts
-            if self.validated_op_state.borrow_mut().insert(opid) {
-                *self.status.borrow_mut() += schema.validate_state(&self.consignment, operation);
+            if !self.is_pruned(operation) && self.validated_op_state.insert(opid) {
+                let cached = self
+                    .cache
+                    .as_ref()
+                    .is_some_and(|cache| cache.contains(opid));
+                if !cached {
+                    debug_assert_eq!(
+                        operation.verify_conceal_consistency(),
+                        Ok(()),
+                        "operation {opid} is constructed incorrectly"
+                    );
+                    if let Some(schema) = self.operation_schema(opid, operation) {
+                        let timer = Timer::start();
+                        let mut status =
+                            schema.validate_state(&self.consignment, operation, self.policy.rules);
+                        timer.stop_nesting(&mut status, Phase::Schema);
+                        self.status += self.apply_policy(status);
+                    }
+                }
+                // Transitions are checked for duplicated seals together with the rest of
+                // their bundle
+                if operation.op_type() == OpType::StateExtension {
+                    self.validate_unique_seals([operation]);
+                }
+                self.validate_registry(operation);
             }
 
-            match operation {
-                OpRef::Genesis(_) => {
-                    // nothing to add to the queue here
+            if let Some(extension) = operation.as_extension() {
+                for (valency, prev_id) in &extension.redeemed {
+                    let Some(prev_op) = self.consignment.operation(*prev_id) else {
+                        self.status.add_failure(Failure::ValencyNoParent {
+                            opid,
+                            prev_id: *prev_id,
+                            valency: *valency,
+                        });
+                        continue;
+                    };
+
+                    if !prev_op.valencies().contains(valency) {
+                        self.status.add_failure(Failure::NoPrevValency {
+                            opid,
+                            prev_id: *prev_id,
+                            valency: *valency,
+                        });
+                        continue;
+                    }
+
+                    queue.push_back((*prev_id, prev_op, depth + 1));
                 }
-                OpRef::Transition(transition) => {
-                    // Now, we must collect all parent nodes and add them to the verification queue
-                    let parent_nodes = transition.inputs.iter().filter_map(|input| {
-                        self.consignment.operation(input.prev_out.op).or_else(|| {
-                            self.status
-                                .borrow_mut()
-                                .add_failure(Failure::OperationAbsent(input.prev_out.op));
-                            None
-                        })
-                    });
+            } else {
+                // Now, we must collect all parent nodes and add them to the verification queue
+                let parent_nodes = operation.parent_ids().into_iter().filter_map(|prev_id| {
+                    if visited.contains(&prev_id) {
+                        return None;
+                    }
+                    let Some(prev_op) = self.consignment.operation(prev_id) else {
+                        self.report_absent(prev_id);
+                        return None;
+                    };
+                    Some((prev_id, prev_op, depth + 1))
+                });
+
+                queue.extend(parent_nodes);
+            }
+        }
+    }
 
-                    queue.extend(parent_nodes);
+    /// Returns the contract migration, unless the consensus rules predate
+    /// contract migrations.
+    fn migration(&self) -> Option<&'consignment Migration> {
+        if self
+            .policy
+            .rules
+            .requires(ConsensusCheck::DeclaredMetaTypes)
+        {
+            return None;
+        }
+        self.consignment.migration()
+    }
+
+    /// Returns schema the operation is created under: the successor schema if
+    /// the operation commits to the contract migration, or the contract schema
+    /// otherwise. Reports operations committing to a migration absent from the
+    /// consignment, and the ones created under the contract schema spending
+    /// state of migrated operations.
+    fn operation_schema(&mut self, opid: OpId, operation: OpRef) -> Option<&'consignment Schema> {
+        if self
+            .policy
+            .rules
+            .requires(ConsensusCheck::DeclaredMetaTypes)
+        {
+            return Some(self.consignment.schema());
+        }
+        let is_migrated = |op: OpRef| op.metadata().contains_key(&MIGRATION_TYPE);
+        if is_migrated(operation) {
+            return match self.migration() {
+                Some(migration) if migration.is_committed(operation.metadata()) => {
+                    Some(&migration.schema)
                 }
-                OpRef::Extension(extension) => {
-                    for (valency, prev_id) in &extension.redeemed {
-                        let Some(prev_op) = self.consignment.operation(*prev_id) else {
-                            self.status
-                                .borrow_mut()
-                                .add_failure(Failure::ValencyNoParent {
-                                    opid,
-                                    prev_id: *prev_id,
-                                    valency: *valency,
-                                });
-                            continue;
-                        };
-
-                        if !prev_op.valencies().contains(valency) {
-                            self.status
-                                .borrow_mut()
-                                .add_failure(Failure::NoPrevValency {
-                                    opid,
-                                    prev_id: *prev_id,
-                                    valency: *valency,
-                                });
-                            continue;
-                        }
+                _ => {
+                    self.status.add_failure(Failure::MigrationUnknown(opid));
+                    None
+                }
+            };
+        }
+        for prev_id in operation.parent_ids() {
+            if self.consignment.operation(prev_id).is_some_and(is_migrated) {
+                self.status
+                    .add_failure(Failure::MigrationReverted(opid, prev_id));
+                return None;
+            }
+        }
+        Some(self.consignment.schema())
+    }
 
-                        queue.push_back(prev_op);
-                    }
+    fn validate_genesis_seals(&mut self) {
+        let genesis = self.consignment.genesis();
+        for assigns in genesis.assignments.values() {
+            for no in 0..assigns.len_u16() {
+                let Some(seal) = assigns.revealed_seal_at(no).expect("index within range") else {
+                    continue;
+                };
+                if !self.layers1.contains(&seal.layer1()) {
+                    self.status
+                        .add_failure(Failure::SealLayerMismatch(seal.layer1(), seal.transmutate()));
                 }
             }
         }
     }
 
+    /// Checks whether the validation must be stopped since it was cancelled,
+    /// or a failure was already detected and the policy requires failing fast.
+    fn should_stop(&mut self) -> bool {
+        if self.status.failures.contains(&Failure::ValidationCancelled) {
+            return true;
+        }
+        if self.cancel.is_cancelled() {
+            self.status.add_failure(Failure::ValidationCancelled);
+            return true;
+        }
+        self.policy.fail_fast && !self.status.failures.is_empty()
+    }
+
+    /// Detects operations spending outputs of the operations absent from the
+    /// history, when such histories are allowed by the policy.
+    fn is_pruned(&self, operation: OpRef) -> bool {
+        if operation.as_transition().is_none() {
+            return false;
+        }
+        self.policy.allow_pruned &&
+            operation
+                .parent_ids()
+                .into_iter()
+                .any(|prev_id| self.consignment.operation(prev_id).is_none())
+    }
+
+    fn report_absent(&mut self, opid: OpId) {
+        let status = &mut self.status;
+        if self.policy.allow_pruned {
+            status.add_warning(Warning::OperationPruned(opid));
+        } else {
+            status.add_failure(Failure::OperationAbsent(opid));
+        }
+    }
+
+    /// Downgrades failures allowed by the validation policy to warnings.
+    fn apply_policy(&self, mut status: Status) -> Status {
+        if self.policy.allow_unknown_flags {
+            let mut warnings = vec![];
+            status.failures.retain(|failure| match failure {
+                Failure::SchemaUnknownMetaType(opid, ty) => {
+                    warnings.push(Warning::UnknownMetaType(*opid, *ty));
+                    false
+                }
+                _ => true,
+            });
+            status.warnings.extend(warnings);
+        }
+        status
+    }
+
     // *** PART III: Validating single-use-seals
     fn validate_commitments(&mut self) {
         for bundle_id in self.consignment.bundle_ids() {
+            if self.should_stop() {
+                return;
+            }
             let Some(bundle) = self.consignment.bundle(bundle_id) else {
-                self.status
-                    .borrow_mut()
-                    .add_failure(Failure::BundleAbsent(bundle_id));
+                self.status.add_failure(Failure::BundleAbsent(bundle_id));
                 continue;
             };
             let Some((witness_id, anchor)) = self.consignment.anchor(bundle_id) else {
-                self.status
-                    .borrow_mut()
-                    .add_failure(Failure::AnchorAbsent(bundle_id));
+                self.status.add_failure(Failure::AnchorAbsent(bundle_id));
                 continue;
             };
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("bundle", %bundle_id, %witness_id).entered();
+
+            // [VALIDATION]: We validate that transitions of the bundle do not assign state of
+            //               the same type to a seal multiple times
+            self.validate_unique_seals(bundle.known_transitions.values().map(OpRef::Transition));
 
             // [VALIDATION]: We validate that the seals were properly defined on BP-type layers
-            let (seals, input_map) = self.validate_seal_definitions(witness_id.layer1(), bundle);
+            let (seals, input_map) = self.validate_seal_definitions(witness_id, bundle_id, bundle);
 
             // [VALIDATION]: We validate that the seals were properly closed on BP-type layers
             let Some(witness_tx) = self.validate_seal_commitments(
@@ -341,22 +847,134 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
         }
     }
 
+    /// Checks that the operations assign state of each type to a seal only
+    /// once, reporting duplicates as merged if the schema allows that. The
+    /// operations must be either a single operation or transitions of the
+    /// same bundle.
+    ///
+    /// Seals are compared in their concealed form, thus two seals are the same
+    /// only if they have the same blinding factor, and not just the same
+    /// outpoint: assignments to the same output under different blinding
+    /// factors are never treated as duplicates.
+    fn validate_unique_seals<'op>(&mut self, ops: impl IntoIterator<Item = OpRef<'op>>) {
+        if !self.policy.rules.requires(ConsensusCheck::UniqueSeals) {
+            return;
+        }
+        let merge = self
+            .consignment
+            .schema()
+            .flags
+            .contains(SchemaFlags::MERGE_DUPLICATE_SEALS);
+        let mut seen = BTreeSet::new();
+        for op in ops {
+            let opid = op.cached_id();
+            for (ty, state_type, seal) in op.assignments().confidential_seals() {
+                if seen.insert((ty, seal)) {
+                    continue;
+                }
+                let mergeable = matches!(state_type, StateType::Fungible | StateType::Void);
+                let status = &mut self.status;
+                if merge && mergeable {
+                    status.add_info(Info::DuplicateSealMerged(opid, ty, seal));
+                } else {
+                    status.add_failure(Failure::DuplicateSeal(opid, ty, seal));
+                }
+            }
+        }
+    }
+
+    /// Checks key registry bindings assigned and spent by the operation, if the
+    /// schema is a key registry (see [`SchemaFlags::KEY_REGISTRY`]). Keys which
+    /// are not bound by the spent bindings are new registrations, which must
+    /// be unique across all the validated operations.
+    fn validate_registry(&mut self, op: OpRef) {
+        // Operations under the successor schema spend bindings of the original
+        // operations under the aliases of their state types
+        let migration = self
+            .migration()
+            .filter(|migration| migration.is_committed(op.metadata()));
+        let schema = migration.map_or(self.consignment.schema(), |migration| &migration.schema);
+        if !schema.flags.contains(SchemaFlags::KEY_REGISTRY) {
+            return;
+        }
+        let registry = schema.registry;
+        let ty = registry.assignment_type;
+        let opid = op.cached_id();
+
+        let mut spent = BTreeMap::<Vec<u8>, Opout>::new();
+        let inputs = op.as_transition().map(|transition| &transition.inputs);
+        for Input { prev_out, .. } in inputs.into_iter().flatten() {
+            // Absent operations and invalid bindings are reported during their validation
+            let Some(prev_op) = self.consignment.operation(prev_out.op) else {
+                continue;
+            };
+            let alias = match migration {
+                Some(migration) if !migration.is_committed(prev_op.metadata()) => {
+                    migration.mapping.alias(prev_out)
+                }
+                _ => Some(prev_out),
+            };
+            if alias.map(|alias| alias.ty) != Some(ty) {
+                continue;
+            }
+            let data = registry_data(prev_op.assignments(), prev_out.ty);
+            if let Some(key) = data
+                .get(prev_out.no as usize)
+                .copied()
+                .flatten()
+                .and_then(|data| registry.key(data.value.as_slice()))
+            {
+                spent.insert(key.to_vec(), prev_out);
+            }
+        }
+
+        let status = &mut self.status;
+        let registry_keys = &mut self.registry_keys;
+        let mut assigned = BTreeMap::<&[u8], Opout>::new();
+        for (no, data) in registry_data(op.assignments(), ty).into_iter().enumerate() {
+            let opout = Opout::new(opid, ty, no as u16);
+            let Some(key) = data.and_then(|data| registry.key(data.value.as_slice())) else {
+                status.add_failure(Failure::RegistryKeyInvalid(opout));
+                continue;
+            };
+            if let Some(prev) = assigned.insert(key, opout) {
+                status.add_failure(Failure::RegistryKeyDuplicate(prev, opout));
+                continue;
+            }
+            if spent.remove(key).is_some() {
+                continue;
+            }
+            match registry_keys.get(key) {
+                Some(prev) if *prev != opout => {
+                    status.add_failure(Failure::RegistryKeyDuplicate(*prev, opout));
+                }
+                Some(_) => {}
+                None => {
+                    registry_keys.insert(key.to_vec(), opout);
+                }
+            }
+        }
+        for opout in spent.into_values() {
+            status.add_failure(Failure::RegistryBindingRemoved(opid, opout));
+        }
+    }
+
     /// Validates that the transition bundle is internally consistent: inputs of
     /// its state transitions correspond to the way how they are committed
     /// in the input map of the bundle; and these inputs are real inputs of
     /// the transaction.
     fn validate_bundle_commitments(
-        &self,
+        &mut self,
         bundle_id: BundleId,
         bundle: &TransitionBundle,
         pub_witness: XWitnessTx,
         input_map: BTreeMap<OpId, BTreeSet<XOutpoint>>,
     ) {
         let witness_id = pub_witness.witness_id();
+        let mut committed: BTreeMap<OpId, BTreeSet<XOutpoint>> = bmap!();
         for (vin, opid) in &bundle.input_map {
             let Some(outpoints) = input_map.get(opid) else {
                 self.status
-                    .borrow_mut()
                     .add_failure(Failure::BundleExtraTransition(bundle_id, *opid));
                 continue;
             };
@@ -364,16 +982,37 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
             let pub_witness = pub_witness.as_reduced_unsafe();
             let Some(input) = pub_witness.inputs.get(vin.to_usize()) else {
                 self.status
-                    .borrow_mut()
                     .add_failure(Failure::BundleInvalidInput(bundle_id, *opid, witness_id));
                 continue;
             };
-            if !outpoints.contains(&XChain::with(layer1, input.prev_output)) {
-                self.status
-                    .borrow_mut()
-                    .add_failure(Failure::BundleInvalidCommitment(
-                        bundle_id, *vin, witness_id, *opid,
-                    ));
+            let outpoint = XChain::with(layer1, input.prev_output);
+            if !outpoints.contains(&outpoint) {
+                self.status.add_failure(Failure::BundleInvalidCommitment(
+                    bundle_id, *vin, witness_id, *opid,
+                ));
+            }
+            committed.entry(*opid).or_default().insert(outpoint.into());
+        }
+
+        // Each of the bundle transitions must have all its inputs committed by the
+        // input map, such that the transitions sharing the witness can't claim
+        // seals of each other.
+        if !self
+            .policy
+            .rules
+            .requires(ConsensusCheck::BundleInputsCommitted)
+        {
+            return;
+        }
+        for (opid, outpoints) in &input_map {
+            for outpoint in outpoints {
+                if !committed
+                    .get(opid)
+                    .is_some_and(|set| set.contains(outpoint))
+                {
+                    self.status
+                        .add_failure(Failure::BundleUncommittedInput(bundle_id, *opid, *outpoint));
+                }
             }
         }
     }
@@ -381,7 +1020,7 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
     /// Bitcoin- and liquid-specific commitment validation using deterministic
     /// bitcoin commitments with opret and tapret schema.
     fn validate_seal_commitments(
-        &self,
+        &mut self,
         seals: impl AsRef<[XOutputSeal]>,
         bundle_id: BundleId,
         witness_id: XWitnessId,
@@ -400,24 +1039,28 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
                 // failure in a strict sense, however we can't be sure that the consignment is
                 // valid. That's why we keep the track of such information in a separate place
                 // (`unresolved_txids` field of the validation status object).
-                self.status
-                    .borrow_mut()
-                    .absent_pub_witnesses
-                    .push(witness_id);
+                self.status.absent_pub_witnesses.push(witness_id);
                 // This also can mean that there is no known transaction with the id provided by
                 // the anchor, i.e. consignment is invalid. We are proceeding with further
                 // validation in order to detect the rest of problems (and reporting the
                 // failure!)
                 self.status
-                    .borrow_mut()
                     .add_failure(Failure::SealNoWitnessTx(witness_id));
                 None
             }
             Ok(pub_witness) => {
+                if !self.policy.allow_unconfirmed &&
+                    !matches!(
+                        self.resolver.resolve_witness_ord(witness_id),
+                        Ok(WitnessOrd::OnChain(_))
+                    )
+                {
+                    self.status
+                        .add_failure(Failure::WitnessUnconfirmed(witness_id));
+                }
                 let seals = seals.as_ref();
                 for seal in seals.iter().filter(|seal| seal.method() != close_method) {
                     self.status
-                        .borrow_mut()
                         .add_failure(Failure::SealInvalidMethod(bundle_id, *seal));
                 }
                 match (close_method, anchor.clone()) {
@@ -445,7 +1088,6 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
                     }
                     (_, _) => {
                         self.status
-                            .borrow_mut()
                             .add_failure(Failure::AnchorMethodMismatch(bundle_id));
                     }
                 }
@@ -460,19 +1102,20 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
     /// Takes state transition, extracts all seals from its inputs and makes
     /// sure they are defined or a correct layer1.
     fn validate_seal_definitions(
-        &self,
-        layer1: Layer1,
+        &mut self,
+        witness_id: XWitnessId,
+        bundle_id: BundleId,
         bundle: &TransitionBundle,
     ) -> (Vec<XOutputSeal>, BTreeMap<OpId, BTreeSet<XOutpoint>>) {
+        let layer1 = witness_id.layer1();
         let mut input_map: BTreeMap<OpId, BTreeSet<XOutpoint>> = bmap!();
+        let mut spent = BTreeSet::<Opout>::new();
         let mut seals = vec![];
         for (opid, transition) in &bundle.known_transitions {
             let opid = *opid;
 
-            if !self.validated_op_seals.borrow_mut().insert(opid) {
-                self.status
-                    .borrow_mut()
-                    .add_failure(Failure::CyclicGraph(opid));
+            if !self.validated_op_seals.insert(opid) {
+                self.status.add_failure(Failure::CyclicGraph(opid));
             }
 
             // Checking that witness transaction closes seals defined by transition previous
@@ -480,17 +1123,27 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
             for input in &transition.inputs {
                 let Opout { op, ty, no } = input.prev_out;
 
+                // Several transitions of a bundle may close seals in the same witness, but
+                // they must spend distinct outputs.
+                if !spent.insert(input.prev_out) &&
+                    self.policy
+                        .rules
+                        .requires(ConsensusCheck::BundleDoubleSpend)
+                {
+                    self.status
+                        .add_failure(Failure::BundleDoubleSpend(bundle_id, input.prev_out));
+                }
+
                 let Some(prev_op) = self.consignment.operation(op) else {
                     // Node, referenced as the ancestor, was not found in the consignment.
-                    // Usually this means that the consignment data are broken
-                    self.status
-                        .borrow_mut()
-                        .add_failure(Failure::OperationAbsent(op));
+                    // Usually this means that the consignment data are broken, unless the
+                    // policy allows pruned histories
+                    self.report_absent(op);
                     continue;
                 };
 
                 let Some(variant) = prev_op.assignments_by_type(ty) else {
-                    self.status.borrow_mut().add_failure(Failure::NoPrevState {
+                    self.status.add_failure(Failure::NoPrevState {
                         opid,
                         prev_id: op,
                         state_type: ty,
@@ -500,7 +1153,6 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
 
                 let Ok(seal) = variant.revealed_seal_at(no) else {
                     self.status
-                        .borrow_mut()
                         .add_failure(Failure::NoPrevOut(opid, input.prev_out));
                     continue;
                 };
@@ -508,44 +1160,42 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
                     // Everything is ok, but we have incomplete data (confidential), thus can't do a
                     // full verification and have to report the failure
                     self.status
-                        .borrow_mut()
                         .add_failure(Failure::ConfidentialSeal(input.prev_out));
                     continue;
                 };
 
                 if seal.layer1() != layer1 {
-                    self.status
-                        .borrow_mut()
-                        .add_failure(Failure::SealWitnessLayer1Mismatch {
-                            seal: seal.layer1(),
-                            anchor: layer1,
-                        });
+                    self.status.add_failure(Failure::SealWitnessLayer1Mismatch {
+                        seal: seal.layer1(),
+                        anchor: layer1,
+                    });
                     continue;
                 }
                 if !self.layers1.contains(&seal.layer1()) {
                     self.status
-                        .borrow_mut()
                         .add_failure(Failure::SealLayerMismatch(seal.layer1(), seal));
                     continue;
                 }
 
                 let seal = if prev_op.op_type() == OpType::StateTransition {
-                    let Some(witness_id) = self.consignment.op_witness_id(op) else {
-                        self.status
-                            .borrow_mut()
-                            .add_failure(Failure::OperationAbsent(op));
+                    let Some(prev_witness_id) = self.consignment.op_witness_id(op) else {
+                        self.status.add_failure(Failure::OperationAbsent(op));
                         continue;
                     };
 
-                    match seal.try_to_output_seal(witness_id) {
+                    // [VALIDATION]: Expired allocations are controlled by their fallback seals
+                    let seal = self
+                        .expired_allocation(opid, input.prev_out, prev_op, witness_id)
+                        .map(|expiry| expiry.fallback)
+                        .unwrap_or(seal);
+
+                    match seal.try_to_output_seal(prev_witness_id) {
                         Ok(seal) => seal,
                         Err(_) => {
-                            self.status.borrow_mut().add_failure(
-                                Failure::SealWitnessLayer1Mismatch {
-                                    seal: seal.layer1(),
-                                    anchor: witness_id.layer1(),
-                                },
-                            );
+                            self.status.add_failure(Failure::SealWitnessLayer1Mismatch {
+                                seal: seal.layer1(),
+                                anchor: prev_witness_id.layer1(),
+                            });
                             continue;
                         }
                     }
@@ -564,6 +1214,44 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
         (seals, input_map)
     }
 
+    /// Returns expiry of an allocation spent by the operation if the schema
+    /// supports expiring allocations and the witness spending it is mined after
+    /// the expiry height. If the spending witness is not yet mined the expiry
+    /// can't be checked, which is a warning when the validation policy allows
+    /// unconfirmed witnesses and a failure otherwise; failure to resolve the
+    /// witness mining status is always a failure.
+    fn expired_allocation(
+        &mut self,
+        opid: OpId,
+        prev_out: Opout,
+        prev_op: OpRef,
+        witness_id: XWitnessId,
+    ) -> Option<AllocationExpiry> {
+        if !self
+            .consignment
+            .schema()
+            .flags
+            .contains(SchemaFlags::EXPIRING_ALLOCATIONS)
+        {
+            return None;
+        }
+        let expiries = prev_op.as_transition()?.allocation_expiries()?;
+        let expiry = expiries.get(prev_out)?;
+        match self.resolver.resolve_witness_ord(witness_id) {
+            Ok(ord @ WitnessOrd::OnChain(_)) => expiry.is_expired_at(ord).then(|| expiry.clone()),
+            Ok(WitnessOrd::OffChain) if self.policy.allow_unconfirmed => {
+                self.status
+                    .add_warning(Warning::AllocationExpiryUnconfirmed(opid, prev_out));
+                None
+            }
+            Ok(WitnessOrd::OffChain) | Err(_) => {
+                self.status
+                    .add_failure(Failure::AllocationExpiryUnresolved(opid, prev_out));
+                None
+            }
+        }
+    }
+
     /// Single-use-seal closing validation.
     ///
     /// Checks that the set of seals is closed over the message, which is
@@ -575,7 +1263,7 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
     /// Additionally, checks that the provided message contains commitment to
     /// the bundle under the current contract.
     fn validate_seal_closing<'seal, Seal: 'seal, Dbc: dbc::Proof>(
-        &self,
+        &mut self,
         seals: impl IntoIterator<Item = &'seal Seal>,
         bundle_id: BundleId,
         witness: XChain<Witness<Dbc>>,
@@ -583,6 +1271,7 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
     ) where
         XChain<Witness<Dbc>>: SealWitness<Seal, Message = mpc::Commitment>,
     {
+        let timer = Timer::start();
         let message = mpc::Message::from(bundle_id);
         let witness_id = witness.witness_id();
         let anchor = Anchor::new(mpc_proof, witness.as_reduced_unsafe().proof.clone());
@@ -592,7 +1281,6 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
                 // The operation is not committed to bitcoin transaction graph!
                 // Ultimate failure. But continuing to detect the rest (after reporting it).
                 self.status
-                    .borrow_mut()
                     .add_failure(Failure::MpcInvalid(bundle_id, witness_id, err));
             }
             Ok(commitment) => {
@@ -600,7 +1288,7 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
                 witness
                     .verify_many_seals(seals, &commitment)
                     .map_err(|err| {
-                        self.status.borrow_mut().add_failure(Failure::SealsInvalid(
+                        self.status.add_failure(Failure::SealsInvalid(
                             bundle_id,
                             witness_id,
                             err.to_string(),
@@ -609,5 +1297,857 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveWitness>
                     .ok();
             }
         }
+        timer.stop(&mut self.status, Phase::Anchor);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{SmallBlob, SmallOrdSet, SmallVec};
+    use bp::Txid;
+    use bp::dbc::Method;
+    use strict_encoding::StrictDumb;
+    use strict_types::TypeSystem;
+
+    use super::*;
+    use crate::schema::MigrationError;
+    use crate::testing::Scenario;
+    use crate::validation::{Scripts, Warning};
+    use crate::vm::Preset;
+    use crate::{
+        AllocationExpiries, AssignData, AssignRights, Assignments, DataState, ExposedSeal, Ffv,
+        GENESIS_SALT_TYPE, Genesis, GenesisSeal, GraphSeal, Inputs, MetaValue, Metadata,
+        Occurrences, OwnedStateSchema, RegistrySchema, SchemaMigration, SecretSeal, Transition,
+        TransitionSchema, TransitionType, TypedAssigns, VoidState, XWitnessTx,
+    };
+
+    const RIGHTS: AssignmentType = AssignmentType::with(1);
+    const TRANSFER: TransitionType = TransitionType::with(1);
+
+    struct History {
+        schema: Schema,
+        types: TypeSystem,
+        scripts: Scripts,
+        genesis: Genesis,
+        genesis_id: OpId,
+        transitions: BTreeMap<OpId, Transition>,
+        terminal: TransitionBundle,
+    }
+
+    impl ConsignmentApi for History {
+        fn schema(&self) -> &Schema { &self.schema }
+
+        fn types(&self) -> &TypeSystem { &self.types }
+
+        fn scripts(&self) -> &Scripts { &self.scripts }
+
+        fn operation(&self, opid: OpId) -> Option<OpRef<'_>> {
+            if opid == self.genesis_id {
+                return Some(OpRef::Genesis(&self.genesis));
+            }
+            self.transitions.get(&opid).map(OpRef::Transition)
+        }
+
+        fn genesis(&self) -> &Genesis { &self.genesis }
+
+        fn terminals<'iter>(&self) -> impl Iterator<Item = (BundleId, XChain<SecretSeal>)> + 'iter {
+            vec![(self.terminal.bundle_id(), strict_dumb!())].into_iter()
+        }
+
+        fn bundle_ids<'iter>(&self) -> impl Iterator<Item = BundleId> + 'iter {
+            vec![self.terminal.bundle_id()].into_iter()
+        }
+
+        fn bundle(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+            Some(&self.terminal).filter(|bundle| bundle.bundle_id() == bundle_id)
+        }
+
+        fn anchor(&self, _bundle_id: BundleId) -> Option<(XWitnessId, &EAnchor)> { None }
+
+        fn op_witness_id(&self, _opid: OpId) -> Option<XWitnessId> { None }
+    }
+
+    struct NoResolver;
+
+    impl ResolveWitness for NoResolver {
+        fn resolve_pub_witness(
+            &self,
+            witness_id: XWitnessId,
+        ) -> Result<XWitnessTx, WitnessResolverError> {
+            Err(WitnessResolverError::Unknown(witness_id))
+        }
+    }
+
+    fn rights<Seal: ExposedSeal>(seal: Seal) -> Assignments<Seal> {
+        Assignments::from(tiny_bmap! {
+            RIGHTS => TypedAssigns::Declarative(small_vec![AssignRights::revealed(
+                XChain::Bitcoin(seal),
+                VoidState::default()
+            )])
+        })
+    }
+
+    fn deep_history(depth: usize) -> History {
+        let mut schema = Schema::strict_dumb();
+        schema.owned_types = tiny_bmap! { RIGHTS => OwnedStateSchema::Declarative };
+        schema.genesis.assignments = tiny_bmap! { RIGHTS => Occurrences::Once };
+        schema.transitions = tiny_bmap! {
+            TRANSFER => TransitionSchema {
+                metadata: none!(),
+                globals: none!(),
+                inputs: tiny_bmap! { RIGHTS => Occurrences::Once },
+                assignments: tiny_bmap! { RIGHTS => Occurrences::Once },
+                valencies: none!(),
+                validator: None,
+            }
+        };
+
+        let mut genesis = Genesis::strict_dumb();
+        genesis.schema_id = schema.schema_id();
+        genesis.assignments =
+            rights(GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), 0, 0));
+        let contract_id = genesis.contract_id();
+
+        let mut transitions = BTreeMap::new();
+        let mut prev = genesis.id();
+        let mut last = None;
+        for no in 0..depth {
+            let mut transition = Transition::strict_dumb();
+            transition.contract_id = contract_id;
+            transition.transition_type = TRANSFER;
+            transition.inputs =
+                Inputs::from(small_bset! { Input::with(Opout::new(prev, RIGHTS, 0)) });
+            transition.assignments =
+                rights(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, no as u64));
+            prev = transition.id();
+            last = Some(transition.clone());
+            transitions.insert(prev, transition);
+        }
+        let last = last.expect("non-zero depth");
+        let mut terminal = TransitionBundle::strict_dumb();
+        terminal.known_transitions = confined_bmap! { last.id() => last };
+
+        History {
+            schema,
+            types: none!(),
+            scripts: none!(),
+            genesis_id: genesis.id(),
+            genesis,
+            transitions,
+            terminal,
+        }
+    }
+
+    fn validate_logic(history: &History, policy: ValidationPolicy, check_state: bool) -> Status {
+        let mut validator = Validator::init(history, &NoResolver, policy);
+        validator
+            .validated_op_seals
+            .extend(history.transitions.keys());
+        if !check_state {
+            validator
+                .validated_op_state
+                .extend(history.transitions.keys());
+        }
+        validator.validate_logic();
+        validator.status
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Validator<History, NoResolver>>();
+        assert_send_sync::<crate::vm::EmbeddedVm>();
+        assert_send_sync::<crate::vm::AluRuntime>();
+    }
+
+    #[test]
+    fn shallow_history() {
+        let history = deep_history(100);
+        let status = validate_logic(&history, ValidationPolicy::default(), true);
+        assert_eq!(status.failures, vec![]);
+    }
+
+    #[test]
+    fn diamond_history() {
+        // The terminal spends both the tip of a transfer chain and the
+        // operation the chain starts from, so the breadth-first traversal
+        // reaches the ancestors of that operation by the short path first
+        let mut history = deep_history(50);
+        let root = *history.terminal.known_transitions.keys().next().unwrap();
+        let mut prev = root;
+        for no in 0..50 {
+            let mut transition = Transition::strict_dumb();
+            transition.contract_id = history.genesis.contract_id();
+            transition.transition_type = TRANSFER;
+            transition.inputs =
+                Inputs::from(small_bset! { Input::with(Opout::new(prev, RIGHTS, 0)) });
+            transition.assignments =
+                rights(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 1000 + no));
+            prev = transition.id();
+            history.transitions.insert(prev, transition);
+        }
+        let mut terminal = Transition::strict_dumb();
+        terminal.contract_id = history.genesis.contract_id();
+        terminal.transition_type = TRANSFER;
+        terminal.inputs = Inputs::from(small_bset! {
+            Input::with(Opout::new(prev, RIGHTS, 0)),
+            Input::with(Opout::new(root, RIGHTS, 1))
+        });
+        terminal.assignments = rights(GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 2000));
+        history.terminal.known_transitions = confined_bmap! { terminal.id() => terminal.clone() };
+        history.transitions.insert(terminal.id(), terminal);
+
+        // Genesis has depth of 102 by the longest path from the terminal and
+        // of 52 by the shortest one
+        let mut policy = ValidationPolicy::default();
+        policy.limits.max_depth = 102;
+        assert_eq!(validate_logic(&history, policy, false).failures, vec![]);
+        policy.limits.max_depth = 101;
+        let status = validate_logic(&history, policy, false);
+        assert!(matches!(status.failures.as_slice(), [Failure::ResourceLimitExceeded {
+            limit: ResourceLimit::Depth,
+            max: 101,
+            ..
+        }]));
+    }
+
+    #[test]
+    fn deep_history_no_overflow() {
+        // Schema validation is skipped for the deep history to keep the test fast
+        // in debug builds, since only the traversal depth matters here
+        let history = deep_history(10_000);
+        let status = validate_logic(&history, ValidationPolicy::default(), false);
+        assert_eq!(status.failures, vec![]);
+
+        let mut policy = ValidationPolicy::default();
+        policy.limits.max_depth = 100;
+        let status = validate_logic(&history, policy, false);
+        assert!(matches!(status.failures.as_slice(), [Failure::ResourceLimitExceeded {
+            limit: ResourceLimit::Depth,
+            max: 100,
+            ..
+        }]));
+
+        let mut pruned = deep_history(100);
+        let genesis_id = pruned.genesis_id;
+        let first = pruned
+            .transitions
+            .iter()
+            .find(|(_, t)| t.inputs.iter().any(|input| input.prev_out.op == genesis_id))
+            .map(|(opid, _)| *opid)
+            .unwrap();
+        pruned.transitions.remove(&first);
+        let status = validate_logic(&pruned, ValidationPolicy::preview(), true);
+        assert_eq!(status.failures, vec![]);
+        let pruned = status
+            .warnings
+            .iter()
+            .filter(|warning| matches!(warning, Warning::OperationPruned(_)))
+            .count();
+        assert_eq!(pruned, 1);
+    }
+
+    #[test]
+    fn genesis_seal_layer() {
+        let mut history = deep_history(1);
+        let seal = GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), 0, 0);
+        history.genesis.assignments = Assignments::from(tiny_bmap! {
+            RIGHTS => TypedAssigns::Declarative(small_vec![AssignRights::revealed(
+                XChain::Liquid(seal),
+                VoidState::default()
+            )])
+        });
+        history.genesis_id = history.genesis.id();
+        let status = validate_logic(&history, ValidationPolicy::default(), true);
+        let failure =
+            Failure::SealLayerMismatch(Layer1::Liquid, XChain::Liquid(seal.transmutate()));
+        assert!(status.failures.contains(&failure));
+    }
+
+    #[test]
+    fn genesis_salt() {
+        let mut history = deep_history(1);
+        history.genesis.add_salt(1).unwrap();
+        history.genesis_id = history.genesis.id();
+        let status = validate_logic(&history, ValidationPolicy::default(), true);
+        assert!(!status.failures.iter().any(|failure| matches!(
+            failure,
+            Failure::SchemaUnknownMetaType(..) | Failure::GenesisSaltInvalid(_)
+        )));
+
+        history.genesis.metadata = Metadata::default();
+        history
+            .genesis
+            .metadata
+            .add_value(GENESIS_SALT_TYPE, MetaValue::from(small_vec![1]))
+            .unwrap();
+        history.genesis.invalidate_id();
+        history.genesis_id = history.genesis.id();
+        let status = validate_logic(&history, ValidationPolicy::default(), true);
+        assert!(
+            status
+                .failures
+                .contains(&Failure::GenesisSaltInvalid(history.genesis_id))
+        );
+    }
+
+    #[test]
+    fn duplicate_seals() {
+        let mut history = deep_history(1);
+        let seal = GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), 0, 0);
+        let right = AssignRights::revealed(XChain::Bitcoin(seal), VoidState::default());
+        history.genesis.assignments = Assignments::from(tiny_bmap! {
+            RIGHTS => TypedAssigns::Declarative(small_vec![right.clone(), right])
+        });
+        let genesis_id = history.genesis.id();
+
+        let mut validator = Validator::init(&history, &NoResolver, default!());
+        validator.validate_unique_seals([OpRef::Genesis(&history.genesis)]);
+        let status = validator.status;
+        assert!(matches!(status.failures.as_slice(), [Failure::DuplicateSeal(opid, RIGHTS, _)]
+            if *opid == genesis_id));
+
+        // Transitions of a bundle assigning the same seal
+        let transition = history
+            .terminal
+            .known_transitions
+            .values()
+            .next()
+            .unwrap()
+            .clone();
+        let mut other = transition.clone();
+        other.inputs = Inputs::from(small_bset! { Input::with(Opout::new(genesis_id, RIGHTS, 1)) });
+        let transitions = [OpRef::Transition(&transition), OpRef::Transition(&other)];
+        let mut validator = Validator::init(&history, &NoResolver, default!());
+        validator.validate_unique_seals(transitions);
+        let status = validator.status;
+        assert!(matches!(status.failures.as_slice(), [Failure::DuplicateSeal(opid, RIGHTS, _)]
+            if *opid == other.id()));
+
+        history.schema.flags = SchemaFlags::MERGE_DUPLICATE_SEALS;
+        let mut validator = Validator::init(&history, &NoResolver, default!());
+        validator.validate_unique_seals(transitions);
+        let status = validator.status;
+        assert_eq!(status.failures, vec![]);
+        assert!(matches!(status.info.as_slice(), [Info::DuplicateSealMerged(opid, RIGHTS, _)]
+            if *opid == other.id()));
+
+        history.schema.flags = SchemaFlags::empty();
+        let mut validator = Validator::init(&history, &NoResolver, ValidationPolicy::compat());
+        validator.validate_unique_seals(transitions);
+        assert_eq!(validator.status.failures, vec![]);
+        assert_eq!(validator.status.info, vec![]);
+    }
+
+    #[test]
+    fn bundle_double_spend() {
+        let history = deep_history(1);
+        let spend = |ty| {
+            let mut transition = Transition::strict_dumb();
+            transition.transition_type = ty;
+            let prev_out = Opout::new(history.genesis_id, RIGHTS, 0);
+            transition.inputs = Inputs::from(small_bset! { Input::with(prev_out) });
+            (transition.id(), transition)
+        };
+        let (first, second) = (spend(TRANSFER), spend(TransitionType::with(2)));
+        let mut bundle = TransitionBundle::strict_dumb();
+        bundle.known_transitions = confined_bmap! { first.0 => first.1.clone() };
+        let witness_id = XChain::Bitcoin(strict_dumb!());
+        let mut validator = Validator::init(&history, &NoResolver, default!());
+        validator.validate_seal_definitions(witness_id, bundle.bundle_id(), &bundle);
+        assert_eq!(validator.status.failures, vec![]);
+
+        bundle.known_transitions = confined_bmap! { first.0 => first.1, second.0 => second.1 };
+        let bundle_id = bundle.bundle_id();
+        let mut validator = Validator::init(&history, &NoResolver, default!());
+        let (seals, input_map) =
+            validator.validate_seal_definitions(witness_id, bundle_id, &bundle);
+        assert_eq!(seals.len(), 2);
+        assert_eq!(input_map.len(), 2);
+        assert_eq!(validator.status.failures, vec![Failure::BundleDoubleSpend(
+            bundle_id,
+            Opout::new(history.genesis_id, RIGHTS, 0)
+        )]);
+    }
+
+    #[test]
+    fn key_registry() {
+        const NAMES: AssignmentType = AssignmentType::with(2);
+        fn names<Seal: ExposedSeal>(bindings: &[(&[u8], Seal)]) -> Assignments<Seal> {
+            let bindings = bindings.iter().map(|(data, seal)| {
+                let data = DataState::from(SmallBlob::try_from(data.to_vec()).unwrap());
+                AssignData::revealed(XChain::Bitcoin(*seal), RevealedData::with_salt(data, 0))
+            });
+            Assignments::from(tiny_bmap! {
+                NAMES => TypedAssigns::Structured(SmallVec::try_from_iter(bindings).unwrap())
+            })
+        }
+        let genesis_seal =
+            |vout| GenesisSeal::with_blinding(Method::OpretFirst, Txid::strict_dumb(), vout, 0);
+        let seal = |vout| GraphSeal::with_blinded_vout(Method::OpretFirst, vout, 0);
+
+        let mut history = deep_history(1);
+        history.schema.flags = SchemaFlags::KEY_REGISTRY;
+        history
+            .schema
+            .owned_types
+            .insert(NAMES, OwnedStateSchema::Structured(strict_dumb!()))
+            .unwrap();
+        history.schema.registry = RegistrySchema {
+            assignment_type: NAMES,
+            key_len: 2,
+        };
+        let registry_invalid = |schema: &Schema| {
+            schema
+                .verify(&TypeSystem::default())
+                .failures
+                .contains(&Failure::SchemaRegistryInvalid(NAMES, schema.registry.key_len))
+        };
+        assert!(!registry_invalid(&history.schema));
+        history.schema.flags = SchemaFlags::empty();
+        assert!(registry_invalid(&history.schema));
+        history.schema.flags = SchemaFlags::KEY_REGISTRY;
+
+        history.genesis.assignments =
+            names(&[(b"ab1", genesis_seal(0)), (b"cd1", genesis_seal(1))]);
+        history.genesis.invalidate_id();
+        history.genesis_id = history.genesis.id();
+        let genesis = OpRef::Genesis(&history.genesis);
+        let ab = Opout::new(history.genesis_id, NAMES, 0);
+        let cd = Opout::new(history.genesis_id, NAMES, 1);
+
+        let mut validator = Validator::init(&history, &NoResolver, default!());
+        validator.validate_registry(genesis);
+        assert_eq!(validator.status.failures, vec![]);
+
+        let transition = |inputs: &[Opout], bindings: &[(&[u8], GraphSeal)]| {
+            let mut transition = Transition::strict_dumb();
+            transition.inputs = Inputs::from(
+                SmallOrdSet::try_from_iter(inputs.iter().copied().map(Input::with)).unwrap(),
+            );
+            transition.assignments = names(bindings);
+            transition
+        };
+        // Update of a binding
+        let update = transition(&[ab], &[(b"ab2", seal(0))]);
+        validator.validate_registry(OpRef::Transition(&update));
+        assert_eq!(validator.status.failures, vec![]);
+
+        // Registration of an already bound key
+        let dup = transition(&[ab], &[(b"ab2", seal(0)), (b"cd2", seal(1))]);
+        validator.validate_registry(OpRef::Transition(&dup));
+        assert_eq!(validator.status.failures, vec![Failure::RegistryKeyDuplicate(
+            cd,
+            Opout::new(dup.id(), NAMES, 1)
+        )]);
+
+        let mut validator = Validator::init(&history, &NoResolver, default!());
+        validator.validate_registry(genesis);
+        // Removal of a binding, duplicated and invalid keys
+        let invalid =
+            transition(&[ab, cd], &[(b"cd2", seal(0)), (b"cd3", seal(1)), (b"x", seal(2))]);
+        let opid = invalid.id();
+        validator.validate_registry(OpRef::Transition(&invalid));
+        assert_eq!(validator.status.failures, vec![
+            Failure::RegistryKeyDuplicate(Opout::new(opid, NAMES, 0), Opout::new(opid, NAMES, 1)),
+            Failure::RegistryKeyInvalid(Opout::new(opid, NAMES, 2)),
+            Failure::RegistryBindingRemoved(opid, ab),
+        ]);
+    }
+
+    // Contract data produced by v0.11.0-beta.6 must keep decoding into the same
+    // ids and pass the validation
+    #[test]
+    fn beta6_vectors() {
+        use amplify::confinement::U24;
+        use commit_verify::Conceal;
+        use strict_encoding::{StrictDecode, StrictReader};
+
+        use crate::testing::{MockConsignment, MockResolver};
+        use crate::{ChainTrustAnchors, EAnchor, Operation, TransitionBundle};
+
+        fn decode<T: StrictDecode>(data: &[u8]) -> T {
+            T::strict_decode(&mut StrictReader::in_memory::<U24>(data)).unwrap()
+        }
+        macro_rules! vector {
+            ($name:literal) => {
+                include_bytes!(concat!("../../tests/data/v0.11.0-beta.6/", $name)).as_slice()
+            };
+        }
+        let transfers = [
+            (vector!("bundle-1.bin"), vector!("anchor-1.bin"), vector!("witness-1.bin")),
+            (vector!("bundle-2.bin"), vector!("anchor-2.bin"), vector!("witness-2.bin")),
+            (vector!("bundle-3.bin"), vector!("anchor-3.bin"), vector!("witness-3.bin")),
+        ];
+        // Transition, bundle and witness ids reported by v0.11.0-beta.6
+        let ids = [
+            [
+                "7b3cd7e1b90c385ebd123ca5f70ddfebf2527108aef966bf0ab8f4ec91f18e7c",
+                "4bca53b73f40bbcbaffb279c2453e951a146fd0cd706e33680960cd2441ba611",
+                "3e35676956e2821d4892e3f47f4b88d130d843ebeb139a76842d40d177fdefea",
+            ],
+            [
+                "be0ba50a8c2070031c2fe82f2db4edda2fdba5c7aa4ba7cf5e0d18682fbc340b",
+                "6ef840f1efb426eea9dbd568dd83d2b72fab4e397146b8cf0a4c7ff3ea9db52a",
+                "5c075aa4225ffd724d4a4fcaf232b1e6a90d0c2896b4c39fb78bb38d8b4d4940",
+            ],
+            [
+                "e21577b61de517d7005c301684535b7d4ca0a5b5fa14bdbf807f8342b6cab79b",
+                "2d3533ad40f9e8a2cc58203207ae5084857c198dfa12abb258cfe11f636bf6b9",
+                "1ff6e3e70fe5b3727e4404514a7aea54e6a18a65221926459f4d7cf9ba015380",
+            ],
+        ];
+
+        let schema = decode::<Schema>(vector!("schema.bin"));
+        assert_eq!(
+            schema.schema_id().to_string(),
+            "rgb:sch:RW0bN7wpI30jR5HfiPLQjZtNWezP9c7P9dLvOYygv$c#imagine-bandit-pogo"
+        );
+        let genesis = decode::<Genesis>(vector!("genesis.bin"));
+        assert_eq!(genesis.chain_net, ChainNet::Testnet);
+        assert_eq!(
+            genesis.id().to_string(),
+            "27b78662da364ccd82bbc560d8a31b6bb241df55433608a789a179609dee572b"
+        );
+        assert_eq!(
+            format!("{:#}", genesis.contract_id()),
+            "rgb:J7eGYto2-TM2Cu8V-g2KMba7-JB31VDN-giniaF5-YJ3uVys"
+        );
+
+        let mut chain = MockResolver::new();
+        let mut consignment = MockConsignment::new(schema, genesis);
+        let mut terminal = None;
+        for ((bundle, anchor, witness), [opid, bundle_id, txid]) in transfers.into_iter().zip(ids) {
+            let bundle = decode::<TransitionBundle>(bundle);
+            let anchor = decode::<EAnchor>(anchor);
+            let witness = decode::<XWitnessTx>(witness);
+            let transition = bundle.known_transitions.values().next().unwrap();
+            assert_eq!(transition.id().to_string(), opid);
+            assert_eq!(bundle.bundle_id().to_string(), bundle_id);
+            assert_eq!(witness.as_reduced_unsafe().txid().to_string(), txid);
+
+            terminal = Some(bundle.bundle_id());
+            let witness_id = chain.broadcast(witness);
+            chain.mine();
+            consignment.add_bundle(witness_id, anchor, bundle).unwrap();
+        }
+        let seal = GraphSeal::with_blinded_vout(Method::OpretFirst, 0, 3);
+        let seal = XChain::<GraphSeal>::Bitcoin(seal);
+        consignment.set_terminal(terminal.unwrap(), seal.conceal());
+
+        let validate = |chain_net, policy| {
+            Validator::validate_with_policy(
+                &consignment,
+                &chain,
+                chain_net,
+                &ChainTrustAnchors::default(),
+                policy,
+            )
+        };
+        for policy in [ValidationPolicy::default(), ValidationPolicy::compat()] {
+            let status = validate(ChainNet::Testnet, policy);
+            assert_eq!(status.failures, vec![]);
+            assert_eq!(status.validity(), Validity::Valid);
+        }
+
+        // v0.11.0-beta.6 compares only whether the networks are testnets
+        let status = validate(ChainNet::Regtest, ValidationPolicy::default());
+        assert_eq!(status.failures, vec![Failure::NetworkMismatch(
+            ChainNet::Regtest,
+            ChainNet::Testnet
+        )]);
+        let status = validate(ChainNet::Regtest, ValidationPolicy::compat());
+        assert_eq!(status.failures, vec![]);
+        let status = validate(ChainNet::Mainnet, ValidationPolicy::compat());
+        assert_eq!(status.failures, vec![Failure::TestnetMismatch(false)]);
+    }
+
+    #[test]
+    fn compat_rules() {
+        let compat = ValidationPolicy::compat();
+
+        let mut policy = compat;
+        policy.limits.max_depth = 100;
+        policy.limits.max_nodes = 100;
+        let history = deep_history(200);
+        let status = validate_logic(&history, policy, false);
+        assert_eq!(status.failures, vec![]);
+
+        let mut schema = history.schema.clone();
+        assert_eq!(
+            schema
+                .verify_with_rules(&history.types, compat.rules)
+                .failures,
+            vec![]
+        );
+        schema.flags = SchemaFlags::EXPIRING_ALLOCATIONS;
+        assert_eq!(
+            schema
+                .verify_with_rules(&history.types, compat.rules)
+                .failures,
+            vec![Failure::SchemaLayoutUnsupported]
+        );
+
+        let mut history = deep_history(1);
+        let (_, mut transition) = history.transitions.pop_first().unwrap();
+        transition.ffv = Ffv::V1;
+        history.terminal.known_transitions =
+            confined_bmap! { transition.id() => transition.clone() };
+        history
+            .transitions
+            .insert(transition.id(), transition.clone());
+        let status = validate_logic(&history, ValidationPolicy::default(), true);
+        assert_eq!(status.failures, vec![]);
+        let status = validate_logic(&history, compat, true);
+        assert_eq!(status.failures, vec![Failure::OperationFfvUnsupported(
+            transition.id(),
+            Ffv::V1
+        )]);
+
+        let mut history = deep_history(1);
+        history.genesis.add_salt(1).unwrap();
+        history.genesis_id = history.genesis.id();
+        let status = validate_logic(&history, compat, true);
+        assert!(
+            status
+                .failures
+                .contains(&Failure::SchemaUnknownMetaType(history.genesis_id, GENESIS_SALT_TYPE))
+        );
+    }
+
+    #[test]
+    fn absent_transition() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let (opid, _) = scenario.transfer();
+        assert!(scenario.consignment_mut().remove_operation(opid));
+        let status = scenario.validate(ValidationPolicy::default());
+        assert!(status.failures.contains(&Failure::OperationAbsent(opid)));
+        assert_eq!(status.validity(), Validity::Invalid);
+    }
+
+    #[test]
+    fn expiring_allocation() {
+        let mut scenario =
+            Scenario::issue_with_flags(ChainNet::Regtest, SchemaFlags::EXPIRING_ALLOCATIONS);
+        let fallback = GraphSeal::with_blinded_vout(Method::OpretFirst, 1, 99);
+        let expiries = |no| AllocationExpiries {
+            assignment_type: RIGHTS,
+            expiries: tiny_bmap! { no => AllocationExpiry {
+                height: 3,
+                fallback: XChain::Bitcoin(fallback),
+            }},
+        };
+        let mut invalid = scenario.clone();
+        let (opid, _) = invalid
+            .transfer_with(|transition| transition.add_allocation_expiries(&expiries(1)).unwrap());
+        let status = invalid.validate(ValidationPolicy::default());
+        assert!(
+            status
+                .failures
+                .contains(&Failure::AllocationExpiryInvalid(opid))
+        );
+
+        let (_, witness_id) = scenario
+            .transfer_with(|transition| transition.add_allocation_expiries(&expiries(0)).unwrap());
+        scenario.chain_mut().mine();
+        let fallback = Outpoint::new(*witness_id.as_reduced_unsafe(), 1);
+
+        // Claimed in time
+        let mut claimed = scenario.clone();
+        claimed.transfer();
+        claimed.chain_mut().mine();
+        let status = claimed.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+
+        // Not yet mined, so the expiry can't be checked
+        let mut pending = scenario.clone();
+        let (opout, _) = pending.owner();
+        let (opid, witness_id) = pending.transfer();
+        let status = pending.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+        assert!(
+            status
+                .warnings
+                .contains(&Warning::AllocationExpiryUnconfirmed(opid, opout))
+        );
+        let status = pending.validate(ValidationPolicy::strict());
+        assert!(
+            status
+                .failures
+                .contains(&Failure::AllocationExpiryUnresolved(opid, opout))
+        );
+        assert!(
+            !status
+                .warnings
+                .contains(&Warning::AllocationExpiryUnconfirmed(opid, opout))
+        );
+
+        // Mining status of the witness can't be resolved
+        assert!(pending.chain_mut().evict(witness_id));
+        let status = pending.validate(ValidationPolicy::default());
+        assert!(
+            status
+                .failures
+                .contains(&Failure::AllocationExpiryUnresolved(opid, opout))
+        );
+        assert_ne!(status.validity(), Validity::Valid);
+
+        // Claimed after the expiry
+        scenario.chain_mut().mine();
+        scenario.chain_mut().mine();
+        let mut late = scenario.clone();
+        late.transfer();
+        late.chain_mut().mine();
+        assert_eq!(late.validate(ValidationPolicy::default()).validity(), Validity::Invalid);
+
+        // Returned after the expiry
+        scenario.set_owner_outpoint(fallback);
+        scenario.transfer();
+        scenario.chain_mut().mine();
+        let status = scenario.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+    }
+
+    #[test]
+    fn fail_fast() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let (_, first) = scenario.transfer();
+        let (_, second) = scenario.transfer();
+        scenario.chain_mut().evict(second);
+        scenario.chain_mut().evict(first);
+
+        let status = scenario.validate(ValidationPolicy::default());
+        assert!(status.failures.contains(&Failure::SealNoWitnessTx(first)));
+        assert!(status.failures.contains(&Failure::SealNoWitnessTx(second)));
+
+        let policy = ValidationPolicy {
+            fail_fast: true,
+            ..default!()
+        };
+        let status = scenario.validate(policy);
+        assert_eq!(status.failures.len(), 1);
+    }
+
+    #[test]
+    fn substituted_lib() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        scenario.transfer();
+        let lib = Preset::SameCount(RIGHTS).to_lib();
+        let other = Preset::Conserve(RIGHTS).to_lib();
+        let (lib_id, other_id) = (lib.id(), other.id());
+        let validate = |scenario: &Scenario| {
+            Validator::validate(scenario.consignment(), scenario.chain(), ChainNet::Regtest)
+        };
+
+        scenario.consignment_mut().insert_lib(lib_id, lib);
+        assert_eq!(validate(&scenario).failures, vec![]);
+
+        scenario.consignment_mut().insert_lib(lib_id, other);
+        let status = validate(&scenario);
+        assert_eq!(status.failures, vec![Failure::ScriptLibMismatch(lib_id, other_id)]);
+        assert_eq!(status.validity(), Validity::Invalid);
+
+        let status = Validator::validate_with_policy(
+            scenario.consignment(),
+            scenario.chain(),
+            ChainNet::Regtest,
+            &ChainTrustAnchors::default(),
+            ValidationPolicy::compat(),
+        );
+        assert_eq!(status.failures, vec![]);
+    }
+
+    #[test]
+    fn migration() {
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let mut successor = scenario.consignment().schema().clone();
+        successor.name = tn!("Successor");
+        scenario.add_successor_schema(successor.schema_id());
+        scenario.transfer();
+        scenario.chain_mut().mine();
+
+        let mapping = SchemaMigration {
+            from: scenario.consignment().schema().schema_id(),
+            to: successor.schema_id(),
+            owned_types: tiny_bmap! { RIGHTS => RIGHTS },
+            global_types: none!(),
+        };
+        let migration = Migration::new(mapping, successor);
+        let migration_id = migration.migration_id();
+        let (migrated, _) = scenario.transfer_with(|transition| {
+            transition.add_migration(migration_id).unwrap();
+        });
+        scenario.chain_mut().mine();
+        assert_eq!(scenario.validate(ValidationPolicy::default()).failures, vec![
+            Failure::MigrationUnknown(migrated)
+        ]);
+
+        scenario.consignment_mut().set_migration(migration.clone());
+        let status = scenario.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+        assert_eq!(status.validity(), Validity::Valid);
+
+        let mut invalid = scenario.clone();
+        let mut unmapped = migration.clone();
+        unmapped.mapping.owned_types = none!();
+        invalid.consignment_mut().set_migration(unmapped.clone());
+        let err = MigrationError::OwnedUnmapped(RIGHTS);
+        assert_eq!(invalid.validate(ValidationPolicy::default()).failures, vec![
+            Failure::MigrationInvalid(unmapped.migration_id(), err)
+        ]);
+
+        let (reverted, _) = scenario.transfer();
+        scenario.chain_mut().mine();
+        assert_eq!(scenario.validate(ValidationPolicy::default()).failures, vec![
+            Failure::MigrationReverted(reverted, migrated)
+        ]);
+    }
+
+    #[test]
+    fn migration_aliases() {
+        const SHARES: AssignmentType = AssignmentType::with(2);
+
+        let mut scenario = Scenario::issue(ChainNet::Regtest);
+        let mut successor = scenario.consignment().schema().clone();
+        successor.owned_types = tiny_bmap! { SHARES => OwnedStateSchema::Declarative };
+        successor.genesis.assignments = tiny_bmap! { SHARES => Occurrences::Once };
+        let transfer = successor.transitions.get_mut(&TRANSFER).unwrap();
+        transfer.inputs = tiny_bmap! { SHARES => Occurrences::Once };
+        transfer.assignments = tiny_bmap! { SHARES => Occurrences::Once };
+        scenario.add_successor_schema(successor.schema_id());
+        scenario.transfer();
+        scenario.chain_mut().mine();
+
+        let mapping = SchemaMigration {
+            from: scenario.consignment().schema().schema_id(),
+            to: successor.schema_id(),
+            owned_types: tiny_bmap! { RIGHTS => SHARES },
+            global_types: none!(),
+        };
+        let migration = Migration::new(mapping, successor);
+        let migration_id = migration.migration_id();
+        scenario.consignment_mut().set_migration(migration);
+        let migrate = |transition: &mut Transition| {
+            let assigns = transition.assignments.get(&RIGHTS).unwrap().clone();
+            transition.assignments = Assignments::from(tiny_bmap! { SHARES => assigns });
+            transition.add_migration(migration_id).unwrap();
+        };
+
+        // The first migrated transition spends the right under its original type
+        let (migrated, _) = scenario.transfer_with(migrate);
+        scenario.chain_mut().mine();
+        let status = scenario.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+        assert_eq!(status.validity(), Validity::Valid);
+
+        scenario.transfer_with(|transition| {
+            let prev_out = Opout::new(migrated, SHARES, 0);
+            transition.inputs = Inputs::from(small_bset! { Input::with(prev_out) });
+            migrate(transition);
+        });
+        scenario.chain_mut().mine();
+        let status = scenario.validate(ValidationPolicy::default());
+        assert_eq!(status.failures, vec![]);
+        assert_eq!(status.validity(), Validity::Valid);
     }
 }
@@ -0,0 +1,169 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Disassembler of the AluVM validation scripts, allowing schema auditors to
+//! review the validation logic committed to by schemata.
+
+use std::fmt::{self, Display, Formatter};
+
+use aluvm::isa::{Bytecode, Instr};
+use aluvm::library::{CodeEofError, Cursor, Lib, LibId, LibSite, Read};
+
+use super::RgbIsa;
+
+/// Instruction of a validation script together with its offset in the
+/// library code segment.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScriptInstr {
+    pub offset: u16,
+    pub instr: Instr<RgbIsa>,
+}
+
+impl ScriptInstr {
+    /// Detects whether the instruction belongs to the RGB ISA extension.
+    pub fn is_rgb(&self) -> bool { matches!(self.instr, Instr::ExtensionCodes(_)) }
+}
+
+impl Display for ScriptInstr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:04X}: {}", self.offset, self.instr)
+    }
+}
+
+/// Validation script disassembled into the instructions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Disassembly {
+    pub lib_id: LibId,
+    /// Names of the ISA extensions used by the script.
+    pub isae: String,
+    pub instructions: Vec<ScriptInstr>,
+    /// Libraries which may be called by the script.
+    pub libs: Vec<LibId>,
+    /// Size of the script data segment, in bytes.
+    pub data_len: usize,
+}
+
+impl Disassembly {
+    /// Returns instructions starting at the entry point, or `None` if the entry
+    /// point belongs to another library or doesn't match an instruction
+    /// boundary.
+    pub fn entry(&self, site: LibSite) -> Option<&[ScriptInstr]> {
+        if site.lib != self.lib_id {
+            return None;
+        }
+        let pos = self
+            .instructions
+            .iter()
+            .position(|instr| instr.offset == site.pos)?;
+        Some(&self.instructions[pos..])
+    }
+}
+
+impl Display for Disassembly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "lib {}", self.lib_id)?;
+        writeln!(f, "  isae {}", self.isae)?;
+        for lib in &self.libs {
+            writeln!(f, "  uses {lib}")?;
+        }
+        writeln!(f, "  data {} bytes", self.data_len)?;
+        for instr in &self.instructions {
+            writeln!(f, "  {instr}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Validation script library, which can be disassembled into the instructions
+/// of the RGB instruction set.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, From)]
+pub struct ValidationScript<'lib>(&'lib Lib);
+
+impl<'lib> ValidationScript<'lib> {
+    pub fn new(lib: &'lib Lib) -> Self { ValidationScript(lib) }
+
+    pub fn lib(&self) -> &'lib Lib { self.0 }
+
+    /// Disassembles the code segment of the script. Unknown instructions are
+    /// decoded as failing ones, thus only a truncated code segment results in
+    /// an error.
+    pub fn disassemble(&self) -> Result<Disassembly, CodeEofError> {
+        let lib = self.0;
+        let mut reader = Cursor::with(&lib.code, &lib.data, &lib.libs);
+        let mut instructions = vec![];
+        while !reader.is_eof() {
+            let offset = reader.pos();
+            let instr = Instr::<RgbIsa>::decode(&mut reader)?;
+            instructions.push(ScriptInstr { offset, instr });
+        }
+        Ok(Disassembly {
+            lib_id: lib.id(),
+            isae: lib.isae.to_string(),
+            instructions,
+            libs: lib.libs.iter().copied().collect(),
+            data_len: lib.data.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aluvm::isa::ControlFlowOp;
+    use amplify::confinement::SmallBlob;
+
+    use super::*;
+    use crate::AssignmentType;
+    use crate::vm::{ContractOp, Preset};
+
+    #[test]
+    fn disassemble() {
+        let preset = Preset::Conserve(AssignmentType::with(1));
+        let lib = preset.to_lib();
+        let disasm = ValidationScript::new(&lib).disassemble().unwrap();
+        assert_eq!(disasm.lib_id, lib.id());
+        assert_eq!(
+            disasm
+                .instructions
+                .iter()
+                .map(|instr| instr.instr.clone())
+                .collect::<Vec<_>>(),
+            preset.compile()
+        );
+        assert_eq!(disasm.instructions[0].offset, 0);
+        assert!(disasm.instructions.iter().any(|instr| instr.is_rgb() &&
+            instr.instr ==
+                Instr::ExtensionCodes(RgbIsa::Contract(ContractOp::Pcvs(
+                    AssignmentType::with(1)
+                )))));
+        assert!(disasm.to_string().contains("pcvs"));
+
+        let entry = disasm.entry(LibSite::with(0, lib.id())).unwrap();
+        assert_eq!(entry.len(), disasm.instructions.len());
+        assert_eq!(disasm.entry(LibSite::with(0, LibId::from([0u8; 32]))), None);
+
+        let mut truncated = lib.clone();
+        let code = Lib::assemble(&[Instr::<RgbIsa>::ControlFlow(ControlFlowOp::Jmp(0))])
+            .unwrap()
+            .code;
+        truncated.code = SmallBlob::try_from(code[..code.len() - 1].to_vec()).unwrap();
+        assert!(ValidationScript::from(&truncated).disassemble().is_err());
+    }
+}
@@ -0,0 +1,338 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dispatch of the validation presets to the operations they apply to,
+//! allowing the presets to be checked natively instead of running their
+//! compiled scripts.
+
+use std::collections::BTreeMap;
+
+use super::{EmbeddedVm, Preset, VmApi};
+use crate::validation::OpInfo;
+use crate::{AssignmentType, GlobalStateType, OpFullType, Schema, StateType};
+
+/// Errors assigning validation presets to the entry points of a schema.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum InitError {
+    /// preset {1} is assigned to {0}, which is not defined by the schema.
+    OpAbsent(OpFullType, Preset),
+
+    /// preset {0} references assignment type {1} unknown to the schema.
+    AssignmentAbsent(Preset, AssignmentType),
+
+    /// preset {0} references global state type {1} unknown to the schema.
+    GlobalAbsent(Preset, GlobalStateType),
+
+    /// preset {0} checks {1} state, while the schema defines {2} state for
+    /// its assignment type.
+    StateMismatch(Preset, StateType, StateType),
+
+    /// preset {1} is assigned to the owned rights of type {0}, which it
+    /// doesn't check.
+    RightMismatch(AssignmentType, Preset),
+}
+
+impl Preset {
+    /// Returns the type of the owned state checked by the preset, if any.
+    pub fn assignment_type(self) -> Option<AssignmentType> {
+        match self {
+            Preset::Conserve(ty) |
+            Preset::SameCount(ty) |
+            Preset::RightsSplit(ty, _) |
+            Preset::IdentityTransfer(ty) |
+            Preset::Predecessors(ty, _) => Some(ty),
+            Preset::HasGlobal(_) => None,
+        }
+    }
+
+    /// Returns the type of the owned state the preset is able to check, or
+    /// `None` if the preset doesn't depend on it.
+    pub fn state_type(self) -> Option<StateType> {
+        match self {
+            Preset::Conserve(_) => Some(StateType::Fungible),
+            Preset::RightsSplit(_, state_type) => Some(state_type),
+            Preset::IdentityTransfer(_) => Some(StateType::Structured),
+            Preset::SameCount(_) | Preset::HasGlobal(_) | Preset::Predecessors(..) => None,
+        }
+    }
+}
+
+/// Validation presets assigned to the entry points of a schema: to the
+/// operation subtypes and to the types of the owned rights. Presets assigned
+/// to an owned right type apply to each operation which spends or creates
+/// rights of the type.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct EntryPoints<Vm: VmApi = EmbeddedVm> {
+    vm: Vm,
+    nodes: BTreeMap<OpFullType, Vec<Preset>>,
+    rights: BTreeMap<AssignmentType, Vec<Preset>>,
+}
+
+impl<Vm: VmApi> EntryPoints<Vm> {
+    /// Constructs the entry points, checking that the presets reference
+    /// operations and state types defined by the schema.
+    pub fn with(
+        vm: Vm,
+        schema: &Schema,
+        nodes: impl IntoIterator<Item = (OpFullType, Preset)>,
+        rights: impl IntoIterator<Item = (AssignmentType, Preset)>,
+    ) -> Result<Self, InitError> {
+        let mut entry_points = EntryPoints {
+            vm,
+            nodes: empty!(),
+            rights: empty!(),
+        };
+        for (op_type, preset) in nodes {
+            let defined = match op_type {
+                OpFullType::Genesis => true,
+                OpFullType::StateTransition(ty) => schema.transitions.contains_key(&ty),
+                OpFullType::StateExtension(ty) => schema.extensions.contains_key(&ty),
+            };
+            if !defined {
+                return Err(InitError::OpAbsent(op_type, preset));
+            }
+            check(schema, preset)?;
+            entry_points.nodes.entry(op_type).or_default().push(preset);
+        }
+        for (ty, preset) in rights {
+            if preset.assignment_type() != Some(ty) {
+                return Err(InitError::RightMismatch(ty, preset));
+            }
+            check(schema, preset)?;
+            entry_points.rights.entry(ty).or_default().push(preset);
+        }
+        Ok(entry_points)
+    }
+
+    /// Iterates over the presets applying to the operation, starting with
+    /// the ones assigned to its subtype.
+    pub fn presets<'me>(&'me self, op_info: &'me OpInfo) -> impl Iterator<Item = Preset> + 'me {
+        let nodes = self.nodes.get(&op_info.ty).into_iter().flatten();
+        let rights = self
+            .rights
+            .iter()
+            .filter(|(ty, _)| {
+                op_info.prev_state.contains_key(*ty) || op_info.owned_state.get(**ty).is_some()
+            })
+            .flat_map(|(_, presets)| presets);
+        nodes.chain(rights).copied()
+    }
+
+    /// Checks the operation against all presets applying to it, returning
+    /// the first preset it fails.
+    pub fn verify(&self, op_info: &OpInfo) -> Result<(), Preset> {
+        match self
+            .presets(op_info)
+            .find(|preset| !self.vm.verify(*preset, op_info))
+        {
+            Some(preset) => Err(preset),
+            None => Ok(()),
+        }
+    }
+}
+
+fn check(schema: &Schema, preset: Preset) -> Result<(), InitError> {
+    if let Preset::HasGlobal(ty) = preset {
+        if !schema.global_types.contains_key(&ty) {
+            return Err(InitError::GlobalAbsent(preset, ty));
+        }
+    }
+    let Some(ty) = preset.assignment_type() else {
+        return Ok(());
+    };
+    let Some(state_schema) = schema.owned_types.get(&ty) else {
+        return Err(InitError::AssignmentAbsent(preset, ty));
+    };
+    match preset.state_type() {
+        Some(state_type) if state_type != state_schema.state_type() => {
+            Err(InitError::StateMismatch(preset, state_type, state_schema.state_type()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bp::dbc::Method;
+    use strict_encoding::StrictDumb;
+    use strict_types::SemId;
+
+    use super::*;
+    use crate::{
+        AssetTag, AssetTags, Assign, Assignments, ContractId, FungibleType, GlobalStateSchema,
+        GraphSeal, Occurrences, OpRef, Operation, OwnedStateSchema, RevealedValue, Transition,
+        TransitionSchema, TransitionType, TypedAssigns, Valencies, VoidState, XChain,
+    };
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+    const RIGHTS: AssignmentType = AssignmentType::with(2);
+    const NAME: GlobalStateType = GlobalStateType::with(1);
+    const TRANSFER: OpFullType = OpFullType::StateTransition(TransitionType::with(1));
+    const BURN: OpFullType = OpFullType::StateTransition(TransitionType::with(2));
+
+    fn schema() -> Schema {
+        let mut schema = Schema::strict_dumb();
+        schema.global_types = tiny_bmap! {
+            NAME => GlobalStateSchema::once(SemId::strict_dumb())
+        };
+        schema.owned_types = tiny_bmap! {
+            ASSET => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit),
+            RIGHTS => OwnedStateSchema::Declarative,
+        };
+        let transfer = TransitionSchema {
+            metadata: none!(),
+            globals: none!(),
+            inputs: tiny_bmap! { RIGHTS => Occurrences::Once },
+            assignments: tiny_bmap! { RIGHTS => Occurrences::OnceOrMore },
+            valencies: none!(),
+            validator: None,
+        };
+        schema.transitions = tiny_bmap! {
+            TransitionType::with(1) => transfer.clone(),
+            TransitionType::with(2) => transfer,
+        };
+        schema
+    }
+
+    fn rights(vouts: &[u32]) -> TypedAssigns<GraphSeal> {
+        let assigns = vouts
+            .iter()
+            .map(|vout| {
+                let seal = GraphSeal::with_blinded_vout(Method::OpretFirst, *vout, *vout as u64);
+                Assign::revealed(XChain::Bitcoin(seal), VoidState::default())
+            })
+            .collect::<Vec<_>>();
+        TypedAssigns::Declarative(Confined::try_from(assigns).unwrap())
+    }
+
+    fn verify(
+        entry_points: &EntryPoints,
+        op_type: OpFullType,
+        prev_state: Assignments<GraphSeal>,
+        assignments: Assignments<GraphSeal>,
+    ) -> (Vec<Preset>, Result<(), Preset>) {
+        let mut transition = Transition::strict_dumb();
+        if let OpFullType::StateTransition(ty) = op_type {
+            transition.transition_type = ty;
+        }
+        transition.assignments = assignments;
+        let op = OpRef::Transition(&transition);
+        let prev_types = prev_state
+            .iter()
+            .map(|(ty, assigns)| (*ty, vec![OpFullType::Genesis; assigns.len_u16() as usize]))
+            .collect();
+        let (redeemed, asset_tags) = (Valencies::default(), AssetTags::default());
+        let op_info = OpInfo::with(
+            ContractId::strict_dumb(),
+            transition.id(),
+            &op,
+            &prev_state,
+            &prev_types,
+            &redeemed,
+            &asset_tags,
+        );
+        (entry_points.presets(&op_info).collect(), entry_points.verify(&op_info))
+    }
+
+    #[test]
+    fn init_errors() {
+        let schema = schema();
+        let init = |nodes: &[(OpFullType, Preset)], rights: &[(AssignmentType, Preset)]| {
+            EntryPoints::with(EmbeddedVm, &schema, nodes.to_vec(), rights.to_vec())
+        };
+
+        let unknown = OpFullType::StateExtension(1.into());
+        let preset = Preset::SameCount(RIGHTS);
+        assert_eq!(init(&[(unknown, preset)], &[]), Err(InitError::OpAbsent(unknown, preset)));
+        let preset = Preset::SameCount(3.into());
+        assert_eq!(
+            init(&[(TRANSFER, preset)], &[]),
+            Err(InitError::AssignmentAbsent(preset, 3.into()))
+        );
+        let preset = Preset::HasGlobal(2.into());
+        assert_eq!(
+            init(&[(OpFullType::Genesis, preset)], &[]),
+            Err(InitError::GlobalAbsent(preset, 2.into()))
+        );
+        let preset = Preset::Conserve(RIGHTS);
+        assert_eq!(
+            init(&[], &[(RIGHTS, preset)]),
+            Err(InitError::StateMismatch(preset, StateType::Fungible, StateType::Void))
+        );
+        let preset = Preset::RightsSplit(ASSET, StateType::Structured);
+        assert_eq!(
+            init(&[(TRANSFER, preset)], &[]),
+            Err(InitError::StateMismatch(preset, StateType::Structured, StateType::Fungible))
+        );
+        for preset in [Preset::SameCount(ASSET), Preset::HasGlobal(NAME)] {
+            let err = InitError::RightMismatch(RIGHTS, preset);
+            assert_eq!(init(&[], &[(RIGHTS, preset)]), Err(err));
+        }
+        assert!(
+            init(
+                &[
+                    (OpFullType::Genesis, Preset::HasGlobal(NAME)),
+                    (TRANSFER, Preset::SameCount(ASSET))
+                ],
+                &[(ASSET, Preset::Conserve(ASSET))]
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn dispatch() {
+        let same_count = Preset::SameCount(RIGHTS);
+        let split = Preset::RightsSplit(RIGHTS, StateType::Void);
+        let conserve = Preset::Conserve(ASSET);
+        let entry_points =
+            EntryPoints::with(EmbeddedVm, &schema(), [(TRANSFER, same_count), (BURN, split)], [(
+                ASSET, conserve,
+            )])
+            .unwrap();
+
+        let state = |vouts: &[u32]| Assignments::from(tiny_bmap! { RIGHTS => rights(vouts) });
+        let (presets, verdict) = verify(&entry_points, TRANSFER, state(&[0]), state(&[1]));
+        assert_eq!(presets, vec![same_count]);
+        assert_eq!(verdict, Ok(()));
+        let (_, verdict) = verify(&entry_points, TRANSFER, state(&[0]), state(&[1, 2]));
+        assert_eq!(verdict, Err(same_count));
+        let (presets, verdict) = verify(&entry_points, BURN, state(&[0]), state(&[1, 2]));
+        assert_eq!(presets, vec![split]);
+        assert_eq!(verdict, Ok(()));
+
+        let tag = AssetTag::new_random("test", ASSET);
+        let coins = TypedAssigns::Fungible(small_vec![Assign::revealed(
+            XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, 1, 1)),
+            RevealedValue::new_random_blinding(10u64, tag),
+        )]);
+        let mut issued = state(&[1]);
+        issued.insert(ASSET, coins).unwrap();
+        let (presets, verdict) = verify(&entry_points, TRANSFER, state(&[0]), issued);
+        assert_eq!(presets, vec![same_count, conserve]);
+        assert_eq!(verdict, Err(conserve));
+        let (_, verdict) = verify(&entry_points, OpFullType::Genesis, none!(), none!());
+        assert_eq!(verdict, Ok(()));
+    }
+}
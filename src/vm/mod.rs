@@ -28,10 +28,18 @@ pub mod opcodes;
 mod isa;
 mod op_contract;
 mod op_timechain;
+mod presets;
+mod dispatch;
+mod disasm;
 #[macro_use]
 mod macroasm;
 
 pub use aluvm::aluasm_isa;
+pub use disasm::{Disassembly, ScriptInstr, ValidationScript};
+pub use dispatch::{EntryPoints, InitError};
 pub use isa::RgbIsa;
 pub use op_contract::ContractOp;
 pub use op_timechain::TimechainOp;
+pub use presets::{
+    AluRuntime, Differential, EmbeddedVm, IDENTITY_TRANSFER_MAX, PREDECESSORS_MAX, Preset, VmApi,
+};
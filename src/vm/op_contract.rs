@@ -36,7 +36,7 @@ use super::opcodes::*;
 use crate::validation::OpInfo;
 use crate::{
     Assign, AssignmentType, BlindingFactor, GlobalStateType, MetaType, PedersenCommitment,
-    RevealedValue, TypedAssigns,
+    RevealedValue, TypedAssigns, pedersen_engine,
 };
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
@@ -94,6 +94,18 @@ pub enum ContractOp {
     #[display("ldf     {0},a16{1},a64{2}")]
     LdF(AssignmentType, Reg16, Reg16),
 
+    /// Loads type of the operation which has created the input (previous
+    /// state) with type id from the first argument and index from the second
+    /// argument `a16` register into `a32` register provided in the third
+    /// argument. The type is encoded as returned by [`OpFullType::vm_code`].
+    ///
+    /// If the input is absent sets `st0` to `false` and terminates the
+    /// program.
+    ///
+    /// [`OpFullType::vm_code`]: crate::OpFullType::vm_code
+    #[display("ldt     {0},a16{1},a32{2}")]
+    LdT(AssignmentType, Reg16, Reg16),
+
     /// Loads global state from the current operation with type id from the
     /// first argument and index from the second argument `a8` register into a
     /// register provided in the third argument.
@@ -173,6 +185,7 @@ impl InstructionSet for ContractOp {
         match self {
             ContractOp::LdP(_, reg, _) |
             ContractOp::LdF(_, reg, _) |
+            ContractOp::LdT(_, reg, _) |
             ContractOp::LdS(_, reg, _) => bset![Reg::A(RegA::A16, (*reg).into())],
             ContractOp::LdG(_, reg, _) => bset![Reg::A(RegA::A8, (*reg).into())],
             ContractOp::LdC(_, reg, _) => bset![Reg::A(RegA::A32, (*reg).into())],
@@ -199,6 +212,9 @@ impl InstructionSet for ContractOp {
             ContractOp::LdF(_, _, reg) => {
                 bset![Reg::A(RegA::A64, (*reg).into())]
             }
+            ContractOp::LdT(_, _, reg) => {
+                bset![Reg::A(RegA::A32, (*reg).into())]
+            }
             ContractOp::LdG(_, _, reg) |
             ContractOp::LdS(_, _, reg) |
             ContractOp::LdP(_, _, reg) |
@@ -222,6 +238,7 @@ impl InstructionSet for ContractOp {
             ContractOp::LdP(_, _, _) |
             ContractOp::LdS(_, _, _) |
             ContractOp::LdF(_, _, _) |
+            ContractOp::LdT(_, _, _) |
             ContractOp::LdG(_, _, _) |
             ContractOp::LdC(_, _, _) => 8,
             ContractOp::LdM(_, _) => 6,
@@ -247,7 +264,7 @@ impl InstructionSet for ContractOp {
                     TypedAssigns::Fungible(state) => state
                         .iter()
                         .map(Assign::to_confidential_state)
-                        .map(|s| s.commitment.into_inner())
+                        .map(|s| s.commitment)
                         .collect::<Vec<_>>(),
                     _ => fail!(),
                 }
@@ -262,7 +279,7 @@ impl InstructionSet for ContractOp {
                     TypedAssigns::Fungible(state) => state
                         .iter()
                         .map(Assign::to_confidential_state)
-                        .map(|s| s.commitment.into_inner())
+                        .map(|s| s.commitment)
                         .collect::<Vec<_>>(),
                     _ => fail!(),
                 }
@@ -338,6 +355,21 @@ impl InstructionSet for ContractOp {
                 };
                 regs.set_n(RegA::A64, *reg, state.map(|s| s.value.as_u64()));
             }
+            ContractOp::LdT(state_type, reg_32, reg) => {
+                let Some(reg_32) = *regs.get_n(RegA::A16, *reg_32) else {
+                    fail!()
+                };
+                let index: u16 = reg_32.into();
+
+                let Some(ty) = context
+                    .prev_types
+                    .get(state_type)
+                    .and_then(|types| types.get(index as usize))
+                else {
+                    fail!()
+                };
+                regs.set_n(RegA::A32, *reg, Some(ty.vm_code()));
+            }
             ContractOp::LdG(state_type, reg_32, reg_s) => {
                 let Some(reg_32) = *regs.get_n(RegA::A8, *reg_32) else {
                     fail!()
@@ -368,11 +400,7 @@ impl InstructionSet for ContractOp {
             ContractOp::Pcvs(state_type) => {
                 let inputs = load_inputs!(state_type);
                 let outputs = load_outputs!(state_type);
-                if !secp256k1_zkp::verify_commitments_sum_to_equal(
-                    secp256k1_zkp::SECP256K1,
-                    &inputs,
-                    &outputs,
-                ) {
+                if pedersen_engine().verify_sum(&inputs, &outputs) != Ok(true) {
                     fail!()
                 }
             }
@@ -388,14 +416,10 @@ impl InstructionSet for ContractOp {
                 };
                 let sum = RevealedValue::with_blinding(sum, BlindingFactor::EMPTY, *tag);
 
-                let inputs = [PedersenCommitment::commit(&sum).into_inner()];
+                let inputs = [PedersenCommitment::commit(&sum)];
                 let outputs = load_outputs!(owned_state);
 
-                if !secp256k1_zkp::verify_commitments_sum_to_equal(
-                    secp256k1_zkp::SECP256K1,
-                    &inputs,
-                    &outputs,
-                ) {
+                if pedersen_engine().verify_sum(&inputs, &outputs) != Ok(true) {
                     fail!()
                 }
             }
@@ -411,14 +435,10 @@ impl InstructionSet for ContractOp {
                 };
                 let sum = RevealedValue::with_blinding(sum, BlindingFactor::EMPTY, *tag);
 
-                let inputs = [PedersenCommitment::commit(&sum).into_inner()];
+                let inputs = [PedersenCommitment::commit(&sum)];
                 let outputs = load_inputs!(owned_state);
 
-                if !secp256k1_zkp::verify_commitments_sum_to_equal(
-                    secp256k1_zkp::SECP256K1,
-                    &inputs,
-                    &outputs,
-                ) {
+                if pedersen_engine().verify_sum(&inputs, &outputs) != Ok(true) {
                     fail!()
                 }
             }
@@ -443,6 +463,7 @@ impl Bytecode for ContractOp {
             ContractOp::LdS(_, _, _) => INSTR_LDS,
             ContractOp::LdP(_, _, _) => INSTR_LDP,
             ContractOp::LdF(_, _, _) => INSTR_LDF,
+            ContractOp::LdT(_, _, _) => INSTR_LDT,
             ContractOp::LdC(_, _, _) => INSTR_LDC,
             ContractOp::LdM(_, _) => INSTR_LDM,
 
@@ -492,6 +513,11 @@ impl Bytecode for ContractOp {
                 writer.write_u4(reg_a)?;
                 writer.write_u4(reg_dst)?;
             }
+            ContractOp::LdT(state_type, reg_a, reg_dst) => {
+                writer.write_u16(*state_type)?;
+                writer.write_u4(reg_a)?;
+                writer.write_u4(reg_dst)?;
+            }
             ContractOp::LdG(state_type, reg_a, reg_s) => {
                 writer.write_u16(*state_type)?;
                 writer.write_u4(reg_a)?;
@@ -554,6 +580,11 @@ impl Bytecode for ContractOp {
                 reader.read_u4()?.into(),
                 reader.read_u4()?.into(),
             ),
+            INSTR_LDT => Self::LdT(
+                reader.read_u16()?.into(),
+                reader.read_u4()?.into(),
+                reader.read_u4()?.into(),
+            ),
             INSTR_LDG => Self::LdG(
                 reader.read_u16()?.into(),
                 reader.read_u4()?.into(),
@@ -0,0 +1,911 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation presets: validation rules which have both an embedded (native)
+//! implementation and an equivalent AluVM script. [`Differential`] VM runs
+//! both implementations against the same operation and asserts their
+//! verdicts are identical, guarding against semantic drift between the two
+//! execution paths.
+
+use std::collections::BTreeSet;
+
+use aluvm::Vm;
+use aluvm::data::{MaybeNumber, Number, Step};
+use aluvm::isa::{ArithmeticOp, BytesOp, CmpOp, ControlFlowOp, Instr, NoneEqFlag, PutOp, SignFlag};
+use aluvm::library::{Lib, LibSite};
+use aluvm::reg::{Reg16, Reg32, RegA, RegS};
+use amplify::num::u4;
+
+use super::{ContractOp, RgbIsa};
+use crate::validation::OpInfo;
+use crate::{
+    AssignFungible, AssignmentType, DataState, ExposedSeal, GlobalStateType, OpFullType,
+    PedersenCommitment, StateType, TypedAssigns, pedersen_engine,
+};
+
+/// Validation rule which can be checked either natively or by an AluVM
+/// script compiled from it.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum Preset {
+    /// Sum of the fungible inputs of the type must match the sum of the
+    /// fungible outputs of the same type.
+    #[display("conserve({0})")]
+    Conserve(AssignmentType),
+
+    /// Number of the inputs of the type must match the number of the outputs
+    /// of the same type. Absence of both inputs and outputs is valid.
+    #[display("same_count({0})")]
+    SameCount(AssignmentType),
+
+    /// Operation must define global state of the type.
+    #[display("has_global({0})")]
+    HasGlobal(GlobalStateType),
+
+    /// Rights of the given type are split between outputs assigned to
+    /// distinct seals. The state type defines what must be preserved by the
+    /// split:
+    /// - fungible amounts must re-commit to the same total, checked with
+    ///   pedersen commitment sums;
+    /// - declarative and attachment rights must come from a single input and be
+    ///   split into at least one output;
+    /// - structured data must come from a single input and be copied into each
+    ///   of the (at least one) outputs unchanged; salts may differ.
+    #[display("rights_split({0}, {1})")]
+    RightsSplit(AssignmentType, StateType),
+
+    /// Structured state of the given type is transferred as-is: the inputs
+    /// and the outputs of the type must hold the same multiset of data values,
+    /// matched one-to-one regardless of their order (salts may differ).
+    /// Concealed state is matched only against other concealed state. The
+    /// number of the transferred assignments must not exceed
+    /// [`IDENTITY_TRANSFER_MAX`].
+    #[display("identity_transfer({0})")]
+    IdentityTransfer(AssignmentType),
+
+    /// Inputs of the given type may only spend outputs created by the
+    /// operations of the listed types, allowing schemas to restrict which
+    /// transitions may follow which (for instance, only a release may spend
+    /// an escrow lock output). Absence of inputs is valid. The number of the
+    /// inputs must not exceed [`PREDECESSORS_MAX`].
+    #[display("predecessors({0}, {1:?})")]
+    Predecessors(AssignmentType, &'static [OpFullType]),
+}
+
+/// Maximum number of assignments transferred by [`Preset::IdentityTransfer`],
+/// keeping the number of jumps made by its script within the AluVM limit.
+pub const IDENTITY_TRANSFER_MAX: u16 = 128;
+
+/// Maximum number of inputs checked by [`Preset::Predecessors`], keeping the
+/// number of jumps made by its script within the AluVM limit.
+pub const PREDECESSORS_MAX: u16 = 0x7FFF;
+
+impl Preset {
+    /// Compiles the preset into AluVM code.
+    pub fn compile(self) -> Vec<Instr<RgbIsa>> {
+        match self {
+            Preset::Conserve(ty) | Preset::RightsSplit(ty, StateType::Fungible) => {
+                vec![contract(ContractOp::Pcvs(ty))]
+            }
+            Preset::SameCount(ty) => {
+                // Undefined counts of absent inputs or outputs may only match
+                // each other
+                let mut code = vec![
+                    contract(ContractOp::CnP(ty, Reg32::Reg0)),
+                    contract(ContractOp::CnS(ty, Reg32::Reg1)),
+                    eq_a16(Reg32::Reg0, Reg32::Reg1),
+                    Instr::ControlFlow(ControlFlowOp::Jif(0)),
+                    Instr::Cmp(CmpOp::IfNA(RegA::A16, Reg32::Reg0)),
+                    Instr::ControlFlow(ControlFlowOp::Test),
+                    Instr::Cmp(CmpOp::IfNA(RegA::A16, Reg32::Reg1)),
+                ];
+                code[3] = Instr::ControlFlow(ControlFlowOp::Jif(offset(&code)));
+                code
+            }
+            Preset::HasGlobal(ty) => vec![
+                contract(ContractOp::CnG(ty, Reg32::Reg0)),
+                Instr::Cmp(CmpOp::EqA(NoneEqFlag::NonEqual, RegA::A8, Reg32::Reg0, Reg32::Reg0)),
+            ],
+            Preset::RightsSplit(ty, StateType::Void | StateType::Attachment) => {
+                let mut code = single_input(ty);
+                code.extend(some_outputs(ty, Reg32::Reg2));
+                code
+            }
+            Preset::RightsSplit(ty, StateType::Structured) => {
+                let mut code = single_input(ty);
+                code.extend(some_outputs(ty, Reg32::Reg2));
+                code.extend([
+                    Instr::ControlFlow(ControlFlowOp::Test),
+                    put_a16(Reg32::Reg3, 0),
+                    contract(ContractOp::LdP(ty, Reg16::Reg0, s16(0))),
+                ]);
+                let outer = offset(&code);
+                code.extend([
+                    contract(ContractOp::LdS(ty, Reg16::Reg3, s16(1))),
+                    Instr::Bytes(BytesOp::Eq(s16(0), s16(1))),
+                    Instr::ControlFlow(ControlFlowOp::Test),
+                    step_a16(Reg32::Reg3),
+                    lt_a16(Reg32::Reg3, Reg32::Reg2),
+                    Instr::ControlFlow(ControlFlowOp::Jif(outer)),
+                    eq_a16(Reg32::Reg3, Reg32::Reg2),
+                ]);
+                code
+            }
+            Preset::IdentityTransfer(ty) => {
+                // a16[0] - number of inputs, a16[2] - current input,
+                // a16[3] - current input or output compared against it,
+                // a16[4] and a16[5] - number of its occurrences in inputs and outputs
+                let mut code = vec![
+                    contract(ContractOp::CnP(ty, Reg32::Reg0)),
+                    contract(ContractOp::CnS(ty, Reg32::Reg1)),
+                    eq_a16(Reg32::Reg0, Reg32::Reg1),
+                    Instr::ControlFlow(ControlFlowOp::Test),
+                    put_a16(Reg32::Reg6, IDENTITY_TRANSFER_MAX),
+                    Instr::Cmp(CmpOp::GtA(SignFlag::Unsigned, RegA::A16, Reg32::Reg0, Reg32::Reg6)),
+                    Instr::Cmp(CmpOp::StInv),
+                    Instr::ControlFlow(ControlFlowOp::Test),
+                    put_a16(Reg32::Reg2, 0),
+                ];
+                let outer = offset(&code);
+                code.extend([
+                    contract(ContractOp::LdP(ty, Reg16::Reg2, s16(0))),
+                    put_a16(Reg32::Reg3, 0),
+                    put_a16(Reg32::Reg4, 0),
+                    put_a16(Reg32::Reg5, 0),
+                ]);
+                let inner = offset(&code);
+                code.push(contract(ContractOp::LdP(ty, Reg16::Reg3, s16(1))));
+                count_if_eq(&mut code, Reg32::Reg4);
+                code.push(contract(ContractOp::LdS(ty, Reg16::Reg3, s16(1))));
+                count_if_eq(&mut code, Reg32::Reg5);
+                code.extend([
+                    step_a16(Reg32::Reg3),
+                    lt_a16(Reg32::Reg3, Reg32::Reg0),
+                    Instr::ControlFlow(ControlFlowOp::Jif(inner)),
+                    eq_a16(Reg32::Reg4, Reg32::Reg5),
+                    Instr::ControlFlow(ControlFlowOp::Test),
+                    step_a16(Reg32::Reg2),
+                    lt_a16(Reg32::Reg2, Reg32::Reg0),
+                    Instr::ControlFlow(ControlFlowOp::Jif(outer)),
+                    eq_a16(Reg32::Reg2, Reg32::Reg0),
+                ]);
+                code
+            }
+            Preset::Predecessors(ty, allowed) => {
+                // a16[0] - number of inputs, a16[1] - current input,
+                // a32[3] - type of the operation which has created it
+                let mut code = vec![
+                    contract(ContractOp::CnP(ty, Reg32::Reg0)),
+                    put_a16(Reg32::Reg2, PREDECESSORS_MAX),
+                    Instr::Cmp(CmpOp::GtA(SignFlag::Unsigned, RegA::A16, Reg32::Reg0, Reg32::Reg2)),
+                    Instr::Cmp(CmpOp::StInv),
+                    Instr::ControlFlow(ControlFlowOp::Test),
+                    put_a16(Reg32::Reg1, 0),
+                    lt_a16(Reg32::Reg1, Reg32::Reg0),
+                    Instr::Cmp(CmpOp::StInv),
+                    Instr::ControlFlow(ControlFlowOp::Jif(0)),
+                ];
+                let skip = code.len() - 1;
+                let outer = offset(&code);
+                code.push(contract(ContractOp::LdT(ty, Reg16::Reg1, Reg16::Reg3)));
+                let mut matches = Vec::with_capacity(allowed.len());
+                for op_type in allowed {
+                    code.extend([
+                        Instr::Put(PutOp::PutA(
+                            RegA::A32,
+                            Reg32::Reg4,
+                            Box::new(MaybeNumber::from(Number::from(op_type.vm_code()))),
+                        )),
+                        Instr::Cmp(CmpOp::EqA(
+                            NoneEqFlag::NonEqual,
+                            RegA::A32,
+                            Reg32::Reg3,
+                            Reg32::Reg4,
+                        )),
+                        Instr::ControlFlow(ControlFlowOp::Jif(0)),
+                    ]);
+                    matches.push(code.len() - 1);
+                }
+                code.push(Instr::ControlFlow(ControlFlowOp::Fail));
+                let matched = offset(&code);
+                for pos in matches {
+                    code[pos] = Instr::ControlFlow(ControlFlowOp::Jif(matched));
+                }
+                code.extend([
+                    step_a16(Reg32::Reg1),
+                    lt_a16(Reg32::Reg1, Reg32::Reg0),
+                    Instr::ControlFlow(ControlFlowOp::Jif(outer)),
+                    eq_a16(Reg32::Reg1, Reg32::Reg0),
+                ]);
+                code[skip] = Instr::ControlFlow(ControlFlowOp::Jif(offset(&code)));
+                code
+            }
+        }
+    }
+
+    /// Returns the assignment type and the types of the operations allowed to
+    /// create its inputs, if the preset restricts them.
+    pub fn predecessors(self) -> Option<(AssignmentType, &'static [OpFullType])> {
+        match self {
+            Preset::Predecessors(ty, allowed) => Some((ty, allowed)),
+            _ => None,
+        }
+    }
+
+    /// Checks that the outputs of a [`Preset::RightsSplit`] are assigned to
+    /// distinct seals. Scripts have no access to the seal definitions, so
+    /// this part of the preset is always checked by the host. Returns `true`
+    /// for all other presets.
+    pub fn check_seals(self, op_info: &OpInfo) -> bool {
+        let Preset::RightsSplit(ty, _) = self else {
+            return true;
+        };
+        let Some(outputs) = op_info.owned_state.get(ty) else {
+            return true;
+        };
+        let seals = outputs.to_confidential_seals();
+        seals.iter().collect::<BTreeSet<_>>().len() == seals.len()
+    }
+
+    /// Assembles the preset into an AluVM library.
+    pub fn to_lib(self) -> Lib { Lib::assemble(&self.compile()).expect("valid preset code") }
+}
+
+fn contract(op: ContractOp) -> Instr<RgbIsa> { Instr::ExtensionCodes(RgbIsa::Contract(op)) }
+
+fn s16(index: u8) -> RegS { RegS::from(u4::with(index)) }
+
+fn put_a16(reg: Reg32, value: u16) -> Instr<RgbIsa> {
+    Instr::Put(PutOp::PutA(RegA::A16, reg, Box::new(MaybeNumber::from(Number::from(value)))))
+}
+
+fn eq_a16(reg1: Reg32, reg2: Reg32) -> Instr<RgbIsa> {
+    Instr::Cmp(CmpOp::EqA(NoneEqFlag::NonEqual, RegA::A16, reg1, reg2))
+}
+
+fn lt_a16(reg1: Reg32, reg2: Reg32) -> Instr<RgbIsa> {
+    Instr::Cmp(CmpOp::LtA(SignFlag::Unsigned, RegA::A16, reg1, reg2))
+}
+
+fn step_a16(reg: Reg32) -> Instr<RgbIsa> {
+    Instr::Arithmetic(ArithmeticOp::Stp(RegA::A16, reg, Step::with(1)))
+}
+
+/// Returns byte offset of the code end, used as a jump target.
+fn offset(code: &[Instr<RgbIsa>]) -> u16 {
+    Lib::assemble(code).expect("valid preset code").code.len() as u16
+}
+
+/// Appends code incrementing the `a16` register `reg` if `s16[0]` and
+/// `s16[1]` are equal.
+fn count_if_eq(code: &mut Vec<Instr<RgbIsa>>, reg: Reg32) {
+    code.extend([
+        Instr::Bytes(BytesOp::Eq(s16(0), s16(1))),
+        Instr::Cmp(CmpOp::StInv),
+        Instr::ControlFlow(ControlFlowOp::Jif(0)),
+        step_a16(reg),
+    ]);
+    let skip = code.len() - 2;
+    code[skip] = Instr::ControlFlow(ControlFlowOp::Jif(offset(code)));
+}
+
+/// Code checking that there is exactly one input of the type; leaves the
+/// index of the input in `a16[0]`.
+fn single_input(ty: AssignmentType) -> Vec<Instr<RgbIsa>> {
+    vec![
+        contract(ContractOp::CnP(ty, Reg32::Reg1)),
+        put_a16(Reg32::Reg0, 1),
+        eq_a16(Reg32::Reg0, Reg32::Reg1),
+        Instr::ControlFlow(ControlFlowOp::Test),
+        put_a16(Reg32::Reg0, 0),
+    ]
+}
+
+/// Code checking that there is at least one output of the type; leaves the
+/// number of outputs in the `a16` register `reg`.
+fn some_outputs(ty: AssignmentType, reg: Reg32) -> Vec<Instr<RgbIsa>> {
+    vec![
+        contract(ContractOp::CnS(ty, reg)),
+        put_a16(Reg32::Reg31, 0),
+        Instr::Cmp(CmpOp::GtA(SignFlag::Unsigned, RegA::A16, reg, Reg32::Reg31)),
+    ]
+}
+
+/// Virtual machine able to check validation presets against an operation.
+/// Virtual machines must be shareable across threads, so the validation can
+/// run in multithreaded environments.
+pub trait VmApi: Send + Sync {
+    /// Returns whether the operation passes the preset rule.
+    fn verify(&self, preset: Preset, op_info: &OpInfo) -> bool;
+}
+
+/// Native implementation of the validation presets.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct EmbeddedVm;
+
+impl VmApi for EmbeddedVm {
+    fn verify(&self, preset: Preset, op_info: &OpInfo) -> bool {
+        if !preset.check_seals(op_info) {
+            return false;
+        }
+        match preset {
+            Preset::Conserve(ty) | Preset::RightsSplit(ty, StateType::Fungible) => {
+                let (Some(TypedAssigns::Fungible(inputs)), Some(TypedAssigns::Fungible(outputs))) =
+                    (op_info.prev_state.get(&ty), op_info.owned_state.get(ty))
+                else {
+                    return false;
+                };
+                pedersen_engine().verify_sum(&commitments(inputs), &commitments(&outputs)) ==
+                    Ok(true)
+            }
+            Preset::SameCount(ty) => {
+                op_info.prev_state.get(&ty).map(TypedAssigns::len_u16) ==
+                    op_info
+                        .owned_state
+                        .get(ty)
+                        .as_ref()
+                        .map(TypedAssigns::len_u16)
+            }
+            Preset::HasGlobal(ty) => op_info.global.contains_key(&ty),
+            Preset::RightsSplit(ty, state_type) => {
+                let (Some(inputs), Some(outputs)) =
+                    (op_info.prev_state.get(&ty), op_info.owned_state.get(ty))
+                else {
+                    return false;
+                };
+                if inputs.len_u16() != 1 || outputs.is_empty() {
+                    return false;
+                }
+                if state_type != StateType::Structured {
+                    return true;
+                }
+                let Ok(input) = inputs.as_structured_state_at(0) else {
+                    return false;
+                };
+                let value = input.map(|data| &data.value);
+                (0..outputs.len_u16()).all(|index| {
+                    matches!(
+                        outputs.clone().into_structured_state_at(index),
+                        Ok(output) if output.as_ref().map(|data| &data.value) == value
+                    )
+                })
+            }
+            Preset::IdentityTransfer(ty) => {
+                let (Some(inputs), Some(outputs)) =
+                    (op_info.prev_state.get(&ty), op_info.owned_state.get(ty))
+                else {
+                    return false;
+                };
+                if inputs.is_empty() ||
+                    inputs.len_u16() != outputs.len_u16() ||
+                    inputs.len_u16() > IDENTITY_TRANSFER_MAX
+                {
+                    return false;
+                }
+                let (Some(mut inputs), Some(mut outputs)) =
+                    (data_values(inputs), data_values(&outputs))
+                else {
+                    return false;
+                };
+                inputs.sort();
+                outputs.sort();
+                inputs == outputs
+            }
+            Preset::Predecessors(ty, allowed) => {
+                let types = op_info
+                    .prev_types
+                    .get(&ty)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                types.len() <= PREDECESSORS_MAX as usize &&
+                    types.iter().all(|op_type| allowed.contains(op_type))
+            }
+        }
+    }
+}
+
+fn data_values<Seal: ExposedSeal>(assigns: &TypedAssigns<Seal>) -> Option<Vec<Option<&DataState>>> {
+    (0..assigns.len_u16())
+        .map(|index| {
+            let data = assigns.as_structured_state_at(index).ok()?;
+            Some(data.map(|data| &data.value))
+        })
+        .collect()
+}
+
+fn commitments<Seal: ExposedSeal>(assigns: &[AssignFungible<Seal>]) -> Vec<PedersenCommitment> {
+    assigns
+        .iter()
+        .map(|assign| assign.to_confidential_state().commitment)
+        .collect()
+}
+
+/// AluVM running scripts compiled from the validation presets.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct AluRuntime;
+
+impl VmApi for AluRuntime {
+    fn verify(&self, preset: Preset, op_info: &OpInfo) -> bool {
+        if !preset.check_seals(op_info) {
+            return false;
+        }
+        let lib = preset.to_lib();
+        let lib_id = lib.id();
+        let mut vm = Vm::<Instr<RgbIsa>>::new();
+        vm.exec(LibSite::with(0, lib_id), |id| (id == lib_id).then_some(&lib), op_info)
+    }
+}
+
+/// Differential VM running two VM implementations on the same inputs.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Differential<A: VmApi = EmbeddedVm, B: VmApi = AluRuntime>(pub A, pub B);
+
+impl<A: VmApi, B: VmApi> VmApi for Differential<A, B> {
+    /// # Panics
+    ///
+    /// If the verdicts of the two VMs differ.
+    fn verify(&self, preset: Preset, op_info: &OpInfo) -> bool {
+        let verdict = self.0.verify(preset, op_info);
+        assert_eq!(
+            verdict,
+            self.1.verify(preset, op_info),
+            "VM verdicts for {preset} on operation {} differ",
+            op_info.id
+        );
+        verdict
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use amplify::confinement::Confined;
+    use bp::dbc::Method;
+    use chrono::DateTime;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::testing::{self, RIGHTS, Scenario};
+    use crate::validation::{Failure, Validator, Validity};
+    use crate::{
+        AssetTag, AssetTags, Assign, Assignments, BlindingFactor, ChainNet, ContractId, DataState,
+        ExtensionType, GraphSeal, OpRef, Operation, RevealedData, RevealedValue, Transition,
+        TransitionType, Valencies, VoidState, XChain,
+    };
+
+    const ASSET: AssignmentType = AssignmentType::with(1);
+    const OTHER: AssignmentType = AssignmentType::with(2);
+    const NAME: GlobalStateType = GlobalStateType::with(1);
+    const TRANSFER: OpFullType = OpFullType::StateTransition(TransitionType::with(1));
+    const LOCK: OpFullType = OpFullType::StateTransition(TransitionType::with(2));
+    const ISSUE: OpFullType = OpFullType::StateExtension(ExtensionType::with(1));
+
+    fn fungible(tag: AssetTag, values: &[(u64, &BlindingFactor)]) -> TypedAssigns<GraphSeal> {
+        let assigns = values
+            .iter()
+            .enumerate()
+            .map(|(no, (value, blinding))| {
+                let seal = GraphSeal::with_blinded_vout(Method::OpretFirst, 0, no as u64);
+                let state = RevealedValue::with_blinding(*value, (*blinding).clone(), tag);
+                Assign::revealed(XChain::Bitcoin(seal), state)
+            })
+            .collect::<Vec<_>>();
+        TypedAssigns::Fungible(Confined::try_from(assigns).unwrap())
+    }
+
+    fn seal(vout: u32) -> XChain<GraphSeal> {
+        XChain::Bitcoin(GraphSeal::with_blinded_vout(Method::OpretFirst, vout, vout as u64))
+    }
+
+    fn declarative(vouts: &[u32]) -> TypedAssigns<GraphSeal> {
+        let assigns = vouts
+            .iter()
+            .map(|vout| Assign::revealed(seal(*vout), VoidState::default()))
+            .collect::<Vec<_>>();
+        TypedAssigns::Declarative(Confined::try_from(assigns).unwrap())
+    }
+
+    fn structured(data: &[(u32, u8)]) -> TypedAssigns<GraphSeal> {
+        let assigns = data
+            .iter()
+            .map(|(vout, byte)| {
+                let state = RevealedData::with_salt(small_vec![*byte], *vout as u128);
+                Assign::revealed(seal(*vout), state)
+            })
+            .collect::<Vec<_>>();
+        TypedAssigns::Structured(Confined::try_from(assigns).unwrap())
+    }
+
+    fn conceal(mut assigns: TypedAssigns<GraphSeal>) -> TypedAssigns<GraphSeal> {
+        if let Some(assigns) = assigns.as_fungible_mut() {
+            for no in 0..assigns.len() {
+                assigns[no] = assigns[no].conceal_state();
+            }
+        }
+        if let Some(assigns) = assigns.as_structured_mut() {
+            for no in 0..assigns.len() {
+                assigns[no] = assigns[no].conceal_state();
+            }
+        }
+        assigns
+    }
+
+    fn asset(assigns: Option<TypedAssigns<GraphSeal>>) -> Assignments<GraphSeal> {
+        Assignments::from(Confined::try_from_iter(assigns.map(|assigns| (ASSET, assigns))).unwrap())
+    }
+
+    /// Name of the preset variant. The match is exhaustive, so adding a
+    /// preset without covering it in the [`coverage`] test fails to compile.
+    fn variant(preset: Preset) -> &'static str {
+        match preset {
+            Preset::Conserve(_) => "conserve",
+            Preset::SameCount(_) => "same_count",
+            Preset::HasGlobal(_) => "has_global",
+            Preset::RightsSplit(..) => "rights_split",
+            Preset::IdentityTransfer(_) => "identity_transfer",
+            Preset::Predecessors(..) => "predecessors",
+        }
+    }
+
+    fn check(
+        transition: &Transition,
+        prev_state: &Assignments<GraphSeal>,
+        asset_tags: &AssetTags,
+        preset: Preset,
+    ) -> bool {
+        let prev_types = prev_state
+            .iter()
+            .map(|(ty, assigns)| (*ty, vec![OpFullType::Genesis; assigns.len_u16() as usize]))
+            .collect();
+        check_with_types(transition, prev_state, &prev_types, asset_tags, preset)
+    }
+
+    fn check_with_types(
+        transition: &Transition,
+        prev_state: &Assignments<GraphSeal>,
+        prev_types: &BTreeMap<AssignmentType, Vec<OpFullType>>,
+        asset_tags: &AssetTags,
+        preset: Preset,
+    ) -> bool {
+        let op = OpRef::Transition(transition);
+        let redeemed = Valencies::default();
+        let op_info = OpInfo::with(
+            ContractId::strict_dumb(),
+            transition.id(),
+            &op,
+            prev_state,
+            prev_types,
+            &redeemed,
+            asset_tags,
+        );
+        <Differential>::default().verify(preset, &op_info)
+    }
+
+    #[test]
+    fn differential() {
+        let tag = AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0);
+        let asset_tags = AssetTags::from(tiny_bmap! { ASSET => tag });
+        let input = BlindingFactor::from_str(
+            "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53",
+        )
+        .unwrap();
+        let change = BlindingFactor::from_str(
+            "0f13a3b3a1adf1bd4ba0d0da1d6a7f8fb0b3a3e6b03a0c5ce7e9af61a7cdd2a1",
+        )
+        .unwrap();
+        let balance = BlindingFactor::zero_balanced([input.clone()], [change.clone()]).unwrap();
+
+        let prev_state = Assignments::from(tiny_bmap! {
+            ASSET => fungible(tag, &[(10, &input)]),
+        });
+        let mut transition = Transition::strict_dumb();
+        transition.assignments = Assignments::from(tiny_bmap! {
+            ASSET => fungible(tag, &[(4, &change), (6, &balance)]),
+        });
+        transition
+            .globals
+            .add_state(NAME, DataState::from(small_vec![1u8]))
+            .unwrap();
+
+        assert!(check(&transition, &prev_state, &asset_tags, Preset::Conserve(ASSET)));
+        assert!(check(&transition, &prev_state, &asset_tags, Preset::SameCount(OTHER)));
+        assert!(check(&transition, &prev_state, &asset_tags, Preset::HasGlobal(NAME)));
+        assert!(!check(&transition, &prev_state, &asset_tags, Preset::Conserve(OTHER)));
+        assert!(!check(&transition, &prev_state, &asset_tags, Preset::SameCount(ASSET)));
+        assert!(!check(&transition, &prev_state, &asset_tags, Preset::HasGlobal(2.into())));
+
+        transition.assignments = Assignments::from(tiny_bmap! {
+            ASSET => fungible(tag, &[(5, &change), (6, &balance)]),
+        });
+        assert!(!check(&transition, &prev_state, &asset_tags, Preset::Conserve(ASSET)));
+    }
+
+    #[test]
+    fn rights_split() {
+        let asset_tags = AssetTags::default();
+        let split = |prev: TypedAssigns<GraphSeal>, next: TypedAssigns<GraphSeal>, st| {
+            let prev_state = Assignments::from(tiny_bmap! { ASSET => prev });
+            let mut transition = Transition::strict_dumb();
+            transition.assignments = Assignments::from(tiny_bmap! { ASSET => next });
+            check(&transition, &prev_state, &asset_tags, Preset::RightsSplit(ASSET, st))
+        };
+
+        let void = StateType::Void;
+        assert!(split(declarative(&[0]), declarative(&[1, 2, 3]), void));
+        assert!(split(declarative(&[0]), declarative(&[1]), void));
+        assert!(!split(declarative(&[0]), declarative(&[]), void));
+        assert!(!split(declarative(&[0, 1]), declarative(&[2, 3]), void));
+        assert!(!split(declarative(&[0]), declarative(&[1, 1]), void));
+
+        let data = StateType::Structured;
+        assert!(split(structured(&[(0, 7)]), structured(&[(1, 7), (2, 7)]), data));
+        assert!(!split(structured(&[(0, 7)]), structured(&[(1, 7), (2, 8)]), data));
+        assert!(!split(structured(&[(0, 7)]), structured(&[(1, 8), (2, 7)]), data));
+        assert!(!split(structured(&[(0, 7)]), structured(&[(1, 7), (1, 7)]), data));
+        assert!(!split(structured(&[(0, 7), (1, 7)]), structured(&[(2, 7)]), data));
+        assert!(!split(declarative(&[0]), structured(&[(1, 7)]), data));
+        assert!(!split(structured(&[(0, 7)]), declarative(&[1]), data));
+
+        let tag = AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0);
+        let blinding = BlindingFactor::from_str(
+            "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53",
+        )
+        .unwrap();
+        let change = BlindingFactor::from_str(
+            "0f13a3b3a1adf1bd4ba0d0da1d6a7f8fb0b3a3e6b03a0c5ce7e9af61a7cdd2a1",
+        )
+        .unwrap();
+        let balance = BlindingFactor::zero_balanced([blinding.clone()], [change.clone()]).unwrap();
+        let value = StateType::Fungible;
+        let prev = fungible(tag, &[(10, &blinding)]);
+        assert!(split(prev.clone(), fungible(tag, &[(4, &change), (6, &balance)]), value));
+        assert!(!split(prev.clone(), fungible(tag, &[(4, &change), (7, &balance)]), value));
+        let mut same_seal = fungible(tag, &[(4, &change), (6, &balance)]);
+        let assigns = same_seal.as_fungible_mut().unwrap();
+        assigns[1] = Assign::with_seal_replaced(&assigns[1], assigns[0].revealed_seal().unwrap());
+        assert!(!split(prev, same_seal, value));
+    }
+
+    #[test]
+    fn identity_transfer() {
+        let asset_tags = AssetTags::default();
+        let transfer = |prev: TypedAssigns<GraphSeal>, next: TypedAssigns<GraphSeal>| {
+            let prev_state = Assignments::from(tiny_bmap! { ASSET => prev });
+            let mut transition = Transition::strict_dumb();
+            transition.assignments = Assignments::from(tiny_bmap! { ASSET => next });
+            check(&transition, &prev_state, &asset_tags, Preset::IdentityTransfer(ASSET))
+        };
+
+        assert!(transfer(structured(&[(0, 7)]), structured(&[(1, 7)])));
+        assert!(transfer(structured(&[(0, 7), (1, 8)]), structured(&[(2, 8), (3, 7)])));
+        assert!(transfer(
+            structured(&[(0, 7), (1, 7), (2, 8)]),
+            structured(&[(3, 8), (4, 7), (5, 7)])
+        ));
+        assert!(!transfer(structured(&[(0, 7)]), structured(&[(1, 8)])));
+        assert!(!transfer(
+            structured(&[(0, 7), (1, 7), (2, 8)]),
+            structured(&[(3, 7), (4, 8), (5, 8)])
+        ));
+        assert!(!transfer(structured(&[(0, 7), (1, 8)]), structured(&[(2, 7)])));
+        assert!(!transfer(structured(&[(0, 7)]), structured(&[(1, 7), (2, 7)])));
+        assert!(!transfer(structured(&[]), structured(&[])));
+        assert!(!transfer(declarative(&[0]), declarative(&[1])));
+
+        let mut concealed = structured(&[(0, 7), (1, 8)]);
+        let assigns = concealed.as_structured_mut().unwrap();
+        assigns[0] = assigns[0].conceal_state();
+        assert!(transfer(concealed.clone(), concealed.clone()));
+        assert!(!transfer(concealed.clone(), structured(&[(2, 7), (3, 8)])));
+
+        let max = IDENTITY_TRANSFER_MAX as u32;
+        let data = (0..max).map(|no| (no, no as u8)).collect::<Vec<_>>();
+        let rotated = (0..max)
+            .map(|no| (max + no, ((no + 1) % max) as u8))
+            .collect::<Vec<_>>();
+        assert!(transfer(structured(&data), structured(&rotated)));
+        let data = (0..=max).map(|no| (no, 0)).collect::<Vec<_>>();
+        assert!(!transfer(structured(&data), structured(&data)));
+    }
+
+    #[test]
+    fn predecessors() {
+        let asset_tags = AssetTags::default();
+        let spend = |types: &[OpFullType], preset| {
+            let vouts = (0..types.len() as u32).collect::<Vec<_>>();
+            let prev_state = Assignments::from(tiny_bmap! { ASSET => declarative(&vouts) });
+            let prev_types = bmap! { ASSET => types.to_vec() };
+            let mut transition = Transition::strict_dumb();
+            transition.assignments = Assignments::from(tiny_bmap! { ASSET => declarative(&[9]) });
+            check_with_types(&transition, &prev_state, &prev_types, &asset_tags, preset)
+        };
+
+        let transfer = Preset::Predecessors(ASSET, &[OpFullType::Genesis, TRANSFER, ISSUE]);
+        let release = Preset::Predecessors(ASSET, &[LOCK]);
+        let nothing = Preset::Predecessors(ASSET, &[]);
+        assert_eq!(release.predecessors(), Some((ASSET, &[LOCK][..])));
+        assert_eq!(Preset::SameCount(ASSET).predecessors(), None);
+
+        assert!(spend(&[OpFullType::Genesis], transfer));
+        assert!(spend(&[TRANSFER, ISSUE, OpFullType::Genesis], transfer));
+        assert!(!spend(&[LOCK], transfer));
+        assert!(!spend(&[TRANSFER, LOCK], transfer));
+        assert!(spend(&[LOCK, LOCK], release));
+        assert!(!spend(&[LOCK, TRANSFER], release));
+        assert!(!spend(&[OpFullType::Genesis], release));
+        assert!(!spend(&[TRANSFER], nothing));
+        assert!(spend(&[], nothing));
+
+        let transition = Transition::strict_dumb();
+        assert!(check(&transition, &Assignments::default(), &asset_tags, release));
+    }
+
+    #[test]
+    fn coverage() {
+        let tag = AssetTag::new_deterministic("test", ASSET, DateTime::UNIX_EPOCH, 0);
+        let asset_tags = AssetTags::from(tiny_bmap! { ASSET => tag });
+        let input = BlindingFactor::from_str(
+            "a3401bcceb26201b55978ff705fecf7d8a0a03598ebeccf2a947030b91a0ff53",
+        )
+        .unwrap();
+        let change = BlindingFactor::from_str(
+            "0f13a3b3a1adf1bd4ba0d0da1d6a7f8fb0b3a3e6b03a0c5ce7e9af61a7cdd2a1",
+        )
+        .unwrap();
+        let balance = BlindingFactor::zero_balanced([input.clone()], [change.clone()]).unwrap();
+        let coins = |values: &[(u64, &BlindingFactor)]| Some(fungible(tag, values));
+        let rights = |vouts: &[u32]| Some(declarative(vouts));
+        let data = |data: &[(u32, u8)]| Some(structured(data));
+        let hidden = |assigns: Option<TypedAssigns<GraphSeal>>| assigns.map(conceal);
+
+        let conserve = Preset::Conserve(ASSET);
+        let same_count = Preset::SameCount(ASSET);
+        let split = Preset::RightsSplit(ASSET, StateType::Fungible);
+        let split_rights = Preset::RightsSplit(ASSET, StateType::Attachment);
+        let split_data = Preset::RightsSplit(ASSET, StateType::Structured);
+        let identity = Preset::IdentityTransfer(ASSET);
+        let after_lock = Preset::Predecessors(ASSET, &[LOCK]);
+        let cases = [
+            // Value conservation, including inflation and burn attempts
+            (conserve, coins(&[(10, &input)]), coins(&[(4, &change), (6, &balance)]), true),
+            (conserve, coins(&[(10, &input)]), coins(&[(4, &change), (7, &balance)]), false),
+            (conserve, coins(&[(10, &input)]), coins(&[(4, &change), (5, &balance)]), false),
+            (conserve, None, coins(&[(4, &change), (6, &balance)]), false),
+            (conserve, coins(&[(10, &input)]), None, false),
+            (conserve, None, None, false),
+            (conserve, rights(&[0]), rights(&[1]), false),
+            (
+                conserve,
+                hidden(coins(&[(10, &input)])),
+                hidden(coins(&[(4, &change), (6, &balance)])),
+                true,
+            ),
+            (
+                conserve,
+                hidden(coins(&[(10, &input)])),
+                hidden(coins(&[(5, &change), (6, &balance)])),
+                false,
+            ),
+            // Mismatched counts
+            (same_count, rights(&[0]), rights(&[1]), true),
+            (same_count, rights(&[0]), rights(&[1, 2]), false),
+            (same_count, rights(&[0, 1]), rights(&[2]), false),
+            (same_count, None, rights(&[1]), false),
+            (same_count, rights(&[0]), None, false),
+            (same_count, None, None, true),
+            (same_count, hidden(data(&[(0, 7)])), hidden(data(&[(1, 8)])), true),
+            // Splits of value and rights
+            (split, coins(&[(10, &input)]), coins(&[(4, &change), (6, &balance)]), true),
+            (split, coins(&[(10, &input)]), coins(&[(4, &change), (7, &balance)]), false),
+            (split, None, coins(&[(4, &change), (6, &balance)]), false),
+            (
+                split,
+                hidden(coins(&[(10, &input)])),
+                hidden(coins(&[(4, &change), (6, &balance)])),
+                true,
+            ),
+            (split_rights, rights(&[0]), rights(&[1, 2]), true),
+            (split_rights, rights(&[0, 1]), rights(&[2, 3]), false),
+            (split_rights, rights(&[0]), None, false),
+            (split_rights, None, rights(&[1]), false),
+            (split_data, hidden(data(&[(0, 7)])), data(&[(1, 7), (2, 7)]), false),
+            (split_data, data(&[(0, 7)]), hidden(data(&[(1, 7), (2, 7)])), false),
+            (split_data, None, data(&[(1, 7)]), false),
+            // Identity transfers of confidential-only state
+            (identity, hidden(data(&[(0, 7)])), hidden(data(&[(1, 7)])), true),
+            (identity, data(&[(0, 7)]), hidden(data(&[(1, 7)])), false),
+            (identity, hidden(data(&[(0, 7)])), data(&[(1, 7)]), false),
+            (identity, hidden(data(&[(0, 7)])), hidden(data(&[(1, 7), (2, 7)])), false),
+            (identity, None, None, false),
+            (identity, None, data(&[(1, 7)]), false),
+            // Spending outputs of the genesis
+            (after_lock, rights(&[0]), rights(&[1]), false),
+            (after_lock, None, rights(&[1]), true),
+        ];
+
+        let mut covered = BTreeSet::new();
+        for (preset, prev, next, valid) in cases {
+            let mut transition = Transition::strict_dumb();
+            transition.assignments = asset(next);
+            let verdict = check(&transition, &asset(prev), &asset_tags, preset);
+            assert_eq!(verdict, valid, "{preset} on {transition:#}");
+            covered.insert(variant(preset));
+        }
+
+        let has_global = Preset::HasGlobal(NAME);
+        let mut transition = Transition::strict_dumb();
+        assert!(!check(&transition, &none!(), &asset_tags, has_global));
+        transition
+            .globals
+            .add_state(2.into(), DataState::from(small_vec![1u8]))
+            .unwrap();
+        assert!(!check(&transition, &none!(), &asset_tags, has_global));
+        transition
+            .globals
+            .add_state(NAME, DataState::from(small_vec![1u8]))
+            .unwrap();
+        assert!(check(&transition, &none!(), &asset_tags, has_global));
+        covered.insert(variant(has_global));
+
+        assert_eq!(covered, bset! {
+            "conserve", "same_count", "has_global", "rights_split", "identity_transfer",
+            "predecessors"
+        });
+    }
+
+    #[test]
+    fn preset_failure() {
+        let validate = |preset: Preset| {
+            let lib = preset.to_lib();
+            let lib_id = lib.id();
+            let mut scenario = Scenario::issue_with(ChainNet::Regtest, |schema| {
+                let transfer = schema.transitions.get_mut(&testing::TRANSFER).unwrap();
+                transfer.validator = Some(LibSite::with(0, lib_id));
+            });
+            scenario.consignment_mut().insert_lib(lib_id, lib);
+            let (opid, _) = scenario.transfer();
+            let status =
+                Validator::validate(scenario.consignment(), scenario.chain(), ChainNet::Regtest);
+            (opid, status)
+        };
+
+        for preset in [
+            Preset::SameCount(RIGHTS),
+            Preset::RightsSplit(RIGHTS, StateType::Void),
+            Preset::SameCount(AssignmentType::with(2)),
+            Preset::Predecessors(RIGHTS, &[OpFullType::Genesis]),
+        ] {
+            assert_eq!(validate(preset).1.failures, vec![], "{preset}");
+        }
+        for preset in [
+            Preset::Conserve(RIGHTS),
+            Preset::HasGlobal(GlobalStateType::with(1)),
+            Preset::IdentityTransfer(RIGHTS),
+            Preset::Predecessors(RIGHTS, &[]),
+        ] {
+            let (opid, status) = validate(preset);
+            assert_eq!(status.failures, vec![Failure::ScriptFailure(opid, None, None)], "{preset}");
+            assert_eq!(status.validity(), Validity::Invalid);
+        }
+    }
+}